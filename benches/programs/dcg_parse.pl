@@ -0,0 +1,38 @@
+% parsing and evaluating arithmetic expressions with a DCG.
+:- module(dcg_parse, []).
+
+:- use_module(library(bench)).
+:- use_module(library(dcgs)).
+:- use_module(library(lists)).
+
+expr(V) --> term(T), expr_rest(T, V).
+
+expr_rest(Acc, V) --> "+", !, term(T), { Acc1 is Acc + T }, expr_rest(Acc1, V).
+expr_rest(Acc, V) --> "-", !, term(T), { Acc1 is Acc - T }, expr_rest(Acc1, V).
+expr_rest(V, V) --> [].
+
+term(V) --> factor(F), term_rest(F, V).
+
+term_rest(Acc, V) --> "*", !, factor(F), { Acc1 is Acc * F }, term_rest(Acc1, V).
+term_rest(V, V) --> [].
+
+factor(V) --> "(", !, expr(V), ")".
+factor(V) --> digit(D), digits(Ds), { number_chars(V, [D | Ds]) }.
+
+digits([D | Ds]) --> digit(D), !, digits(Ds).
+digits([]) --> [].
+
+digit(D) --> [D], { '0' @=< D, D @=< '9' }.
+
+input(Cs) :-
+    length(Parts, 50),
+    maplist(=("(12+34*5-6)*(7+8)+"), Parts),
+    append(Parts, Cs0),
+    append(Cs0, "1", Cs).
+
+run :-
+    input(Cs),
+    phrase(expr(V), Cs),
+    V =:= 50 * (12 + 34 * 5 - 6) * (7 + 8) + 1.
+
+:- initialization((bench(dcg_parse:run, 500), halt)).
@@ -0,0 +1,5 @@
+% no benchmark at all, measuring the start up and halting of the
+% process against which the other programs are run.
+:- module(empty, []).
+
+:- initialization(halt).
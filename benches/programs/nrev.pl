@@ -0,0 +1,20 @@
+% naive reverse of a 30 element list.
+:- module(nrev, []).
+
+:- use_module(library(bench)).
+:- use_module(library(between)).
+
+app([], Ys, Ys).
+app([X | Xs], Ys, [X | Zs]) :-
+    app(Xs, Ys, Zs).
+
+nrev([], []).
+nrev([X | Xs], Ys) :-
+    nrev(Xs, Zs),
+    app(Zs, [X], Ys).
+
+run :-
+    numlist(1, 30, Xs),
+    nrev(Xs, _).
+
+:- initialization((bench(nrev:run, 20000), halt)).
@@ -0,0 +1,29 @@
+% all solutions to the eight queens problem.
+:- module(queens, []).
+
+:- use_module(library(bench)).
+:- use_module(library(between)).
+:- use_module(library(lists)).
+
+queens(N, Qs) :-
+    numlist(1, N, Ns),
+    queens(Ns, [], Qs).
+
+queens([], Qs, Qs).
+queens(Ns, Placed, Qs) :-
+    select(Q, Ns, Ns1),
+    safe(Placed, Q, 1),
+    queens(Ns1, [Q | Placed], Qs).
+
+safe([], _, _).
+safe([Q | Qs], Q0, D) :-
+    Q0 =\= Q + D,
+    Q0 =\= Q - D,
+    D1 is D + 1,
+    safe(Qs, Q0, D1).
+
+run :-
+    findall(Qs, queens(8, Qs), Solutions),
+    length(Solutions, 92).
+
+:- initialization((bench(queens:run, 20), halt)).
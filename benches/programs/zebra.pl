@@ -0,0 +1,43 @@
+% the zebra puzzle. houses are house(Color, Nationality, Pet, Drink,
+% Smoke).
+:- module(zebra, []).
+
+:- use_module(library(bench)).
+
+right_of(A, B, [B, A | _]).
+right_of(A, B, [_ | Hs]) :- right_of(A, B, Hs).
+
+next_to(A, B, [A, B | _]).
+next_to(A, B, [B, A | _]).
+next_to(A, B, [_ | Hs]) :- next_to(A, B, Hs).
+
+member_(H, [H | _]).
+member_(H, [_ | Hs]) :- member_(H, Hs).
+
+houses([house(_, _, _, _, _), house(_, _, _, _, _), house(_, _, _, _, _),
+        house(_, _, _, _, _), house(_, _, _, _, _)]).
+
+zebra(Hs) :-
+    houses(Hs),
+    member_(house(red, english, _, _, _), Hs),
+    member_(house(_, spanish, dog, _, _), Hs),
+    member_(house(green, _, _, coffee, _), Hs),
+    member_(house(_, ukrainian, _, tea, _), Hs),
+    right_of(house(green, _, _, _, _), house(ivory, _, _, _, _), Hs),
+    member_(house(_, _, snails, _, winstons), Hs),
+    member_(house(yellow, _, _, _, kools), Hs),
+    Hs = [_, _, house(_, _, _, milk, _), _, _],
+    Hs = [house(_, norwegian, _, _, _) | _],
+    next_to(house(_, _, _, _, chesterfields), house(_, _, fox, _, _), Hs),
+    next_to(house(_, _, _, _, kools), house(_, _, horse, _, _), Hs),
+    member_(house(_, _, _, orange_juice, lucky_strikes), Hs),
+    member_(house(_, japanese, _, _, parliaments), Hs),
+    next_to(house(_, norwegian, _, _, _), house(blue, _, _, _, _), Hs),
+    member_(house(_, _, zebra, _, _), Hs),
+    member_(house(_, _, _, water, _), Hs).
+
+run :-
+    zebra(Hs),
+    member_(house(_, japanese, zebra, _, _), Hs).
+
+:- initialization((bench(zebra:run, 200), halt)).
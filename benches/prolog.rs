@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// the programs of benches/programs, each of which times its benchmark
+// with bench/2 and halts.
+const PROGRAMS: &[&str] = &["nrev", "queens", "deriv", "zebra", "boyer", "dcg_parse"];
+
+fn program_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    path.push("benches");
+    path.push("programs");
+    path.push(format!("{}.pl", name));
+
+    path
+}
+
+// runs the program in a fresh process, which halts once the benchmark
+// is done. the process's start up is measured along with it, and can
+// be told apart by comparing against the empty program.
+fn run_program(path: &PathBuf) {
+    let status = Command::new(env!("CARGO_BIN_EXE_scryer-prolog"))
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .expect("failed to run scryer-prolog");
+
+    assert!(status.success(), "{} failed", path.display());
+}
+
+fn prolog_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prolog");
+    group.sample_size(10);
+
+    for name in std::iter::once(&"empty").chain(PROGRAMS) {
+        let path = program_path(name);
+        group.bench_function(*name, |b| b.iter(|| run_program(&path)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, prolog_benchmarks);
+criterion_main!(benches);
@@ -3,10 +3,103 @@ extern crate indexmap;
 use indexmap::IndexSet;
 
 use std::env;
-use std::fs::{File, copy, read_dir};
+use std::fs::{File, copy, read_dir, read_to_string};
 use std::io::Write;
 use std::path::Path;
 
+// the predicate indicators in the export list of a library's module
+// declaration, skipping op/3 declarations and '$'-prefixed names.
+fn module_exports(src: &str) -> Vec<(String, usize)>
+{
+    let mut exports = vec![];
+
+    let start = match src.find(":- module(") {
+        Some(start) => start,
+        None => return exports,
+    };
+
+    let src = &src[start ..];
+
+    let src = match src.find('[') {
+        Some(idx) => &src[idx + 1 ..],
+        None => return exports,
+    };
+
+    let mut items = vec![];
+    let mut item = String::new();
+    let mut depth = 0;
+    let mut in_quotes = false;
+    let mut in_comment = false;
+
+    for c in src.chars() {
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+
+        match c {
+            '\'' => in_quotes = !in_quotes,
+            '%' if !in_quotes => {
+                in_comment = true;
+                continue;
+            }
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth -= 1,
+            ',' if !in_quotes && depth == 0 => {
+                items.push(item.trim().to_string());
+                item.clear();
+                continue;
+            }
+            ']' if !in_quotes && depth == 0 => {
+                items.push(item.trim().to_string());
+                break;
+            }
+            _ => {}
+        }
+
+        item.push(c);
+    }
+
+    for item in items {
+        if item.starts_with("op(") {
+            continue;
+        }
+
+        let slash = match item.rfind('/') {
+            Some(slash) => slash,
+            None => continue,
+        };
+
+        let mut arity = match item[slash + 1 ..].trim().parse::<usize>() {
+            Ok(arity) => arity,
+            Err(_) => continue,
+        };
+
+        let mut name = item[.. slash].trim();
+
+        if name.ends_with('/') {
+            // a non-terminal indicator, Name//Arity. the name itself
+            // may be followed by whitespace, as in (Op) // Arity.
+            name = name[.. name.len() - 1].trim_end();
+            arity += 2;
+        }
+
+        if name.starts_with('(') && name.ends_with(')') {
+            name = &name[1 .. name.len() - 1];
+        }
+
+        if name.starts_with('\'') && name.ends_with('\'') && name.len() > 1 {
+            name = &name[1 .. name.len() - 1];
+        }
+
+        if !name.is_empty() && !name.starts_with('$') {
+            exports.push((name.to_string(), arity));
+        }
+    }
+
+    exports
+}
+
 fn main()
 {
     let out_dir = env::var("OUT_DIR").unwrap();
@@ -14,6 +107,7 @@ fn main()
 
     let mut libraries = File::create(&dest_path).unwrap();
     let mut library_index = IndexSet::new();
+    let mut autoload_index = vec![];
 
     let paths = read_dir("./src/prolog/lib").unwrap();
 
@@ -37,6 +131,14 @@ fn main()
 
                     libraries.write_all(include_line.as_bytes()).unwrap();
                     library_index.insert(file_stem.to_string_lossy().to_string());
+
+                    if file_stem != "builtins" {
+                        let src = read_to_string(&item).unwrap();
+
+                        for (name, arity) in module_exports(&src) {
+                            autoload_index.push((name, arity, file_stem.to_string_lossy().to_string()));
+                        }
+                    }
                 }
             }
         }
@@ -54,6 +156,15 @@ fn main()
     libraries.write_all(b"\n\n        m\n    };
 }\n").unwrap();
 
+    libraries.write_all(b"\npub static AUTOLOAD_INDEX: &[(&str, usize, &str)] = &[").unwrap();
+
+    for (name, arity, library) in autoload_index {
+        let line = format!("\n    ({:?}, {}, {:?}),", name, arity, library);
+        libraries.write_all(line.as_bytes()).unwrap();
+    }
+
+    libraries.write_all(b"\n];\n").unwrap();
+
     libraries.write_all(b"\npub static PROJECT_DIR: &'static str = \"").unwrap();
     libraries.write_all(env::var("CARGO_MANIFEST_DIR").unwrap().as_bytes()).unwrap();
     libraries.write_all(b"\";\n").unwrap();
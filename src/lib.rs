@@ -0,0 +1,19 @@
+#[cfg(feature = "repl")]
+extern crate crossterm;
+#[macro_use]
+extern crate downcast;
+extern crate indexmap;
+#[macro_use]
+extern crate lazy_static;
+extern crate libc;
+#[cfg(feature = "logging")]
+extern crate log;
+#[macro_use]
+extern crate prolog_parser;
+#[macro_use]
+extern crate ref_thread_local;
+
+pub mod prolog;
+
+pub use crate::prolog::machine::Machine;
+pub use crate::prolog::machine::embedded::{PrepareError, PreparedQuery, ResourceLimits, Solution, Solutions};
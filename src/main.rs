@@ -1,23 +1,12 @@
-extern crate crossterm;
-#[macro_use]
-extern crate downcast;
-extern crate indexmap;
-#[macro_use]
-extern crate lazy_static;
 extern crate libc;
 extern crate nix;
-#[macro_use]
-extern crate prolog_parser;
-#[macro_use]
-extern crate ref_thread_local;
+extern crate scryer_prolog;
 
 use nix::sys::signal;
 
-mod prolog;
-
-use crate::prolog::machine::*;
-use crate::prolog::machine::streams::*;
-use crate::prolog::read::*;
+use scryer_prolog::prolog::machine::*;
+use scryer_prolog::prolog::machine::streams::*;
+use scryer_prolog::prolog::read::*;
 
 use std::sync::atomic::Ordering;
 
@@ -62,7 +62,7 @@ pub trait Allocator<'a> {
                     }
                 }
                 VarStatus::Perm(_) => {
-                    self.bindings_mut().insert(var.clone(), VarData::Perm(0));
+                    self.bindings_mut().insert(var.clone(), VarData::Perm(None));
                     perm_vs.insert(var, (var_status, cells));
                 }
             };
@@ -84,7 +84,7 @@ pub trait Allocator<'a> {
     fn record_register(&mut self, var: Rc<Var>, r: RegType) {
         match self.bindings_mut().get_mut(&var).unwrap() {
             &mut VarData::Temp(_, ref mut s, _) => *s = r.reg_num(),
-            &mut VarData::Perm(ref mut s) => *s = r.reg_num(),
+            &mut VarData::Perm(ref mut s) => *s = Some(r.reg_num()),
         }
     }
 }
@@ -10,8 +10,8 @@ use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 
 use crate::prolog::ordered_float::*;
-use crate::prolog::rug::ops::PowAssign;
-use crate::prolog::rug::{Assign, Integer, Rational};
+use crate::prolog::bignum::ops::PowAssign;
+use crate::prolog::bignum::{Assign, Integer, Rational};
 
 use std::cell::Cell;
 use std::cmp::{max, min, Ordering};
@@ -293,10 +293,16 @@ impl<'a> ArithmeticEvaluator<'a> {
             match term_ref? {
                 ArithTermRef::Constant(c) => self.push_constant(c)?,
                 ArithTermRef::Var(cell, name) => {
+                    // permanent variables are entered in the bindings ahead
+                    // of time, so an unassigned one hasn't occurred yet.
+                    if let Some(&VarData::Perm(None)) = self.bindings.get(&name) {
+                        return Err(ArithmeticError::UninstantiatedVar);
+                    }
+
                     let r = if cell.get().norm().reg_num() == 0 {
                         match self.bindings.get(&name) {
                             Some(&VarData::Temp(_, t, _)) if t != 0 => RegType::Temp(t),
-                            Some(&VarData::Perm(p)) if p != 0 => RegType::Perm(p),
+                            Some(&VarData::Perm(Some(p))) => RegType::Perm(p),
                             _ => return Err(ArithmeticError::UninstantiatedVar),
                         }
                     } else {
@@ -0,0 +1,64 @@
+/* the arbitrary precision arithmetic the machine is built on. by
+ * default it is GMP's, through rug. with the num feature in place of
+ * rug, it is num-bigint's, through num-rug-adapter, which mirrors the
+ * part of rug's interface used here, and builds wherever Rust does.
+ * the rest of the crate names these types from here only. */
+
+#[cfg(all(feature = "rug", feature = "num"))]
+compile_error!("the rug and num features are exclusive; build num with --no-default-features");
+
+#[cfg(not(any(feature = "rug", feature = "num")))]
+compile_error!("either the rug or the num feature is needed for bignum arithmetic");
+
+pub use crate::prolog::rug::ops;
+pub use crate::prolog::rug::{Assign, Integer, Rational};
+
+// the state of random/1 and friends. GMP's generator, as seeded by
+// set_random/1, is kept under rug.
+#[cfg(feature = "rug")]
+pub type RandState = crate::prolog::rug::rand::RandState<'static>;
+
+// under num, a xorshift64* generator. the numbers it draws for a seed
+// differ from those GMP's generator draws for it.
+#[cfg(not(feature = "rug"))]
+pub struct RandState {
+    state: u64,
+}
+
+#[cfg(not(feature = "rug"))]
+impl RandState {
+    const INITIAL_STATE: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    pub fn new() -> Self {
+        RandState { state: Self::INITIAL_STATE }
+    }
+
+    // the seed is hashed by the FNV-1a hash of its decimal digits, as
+    // it may exceed the generator's state.
+    pub fn seed(&mut self, seed: &Integer) {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+        for byte in seed.to_string().bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        // the state of xorshift must never be 0.
+        self.state = if hash == 0 { Self::INITIAL_STATE } else { hash };
+    }
+
+    // the low bits of the next draw, of which there are at most 32.
+    pub fn bits(&mut self, bits: u32) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        let n = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32;
+
+        if bits >= 32 {
+            n as u32
+        } else {
+            (n as u32) & ((1 << bits) - 1)
+        }
+    }
+}
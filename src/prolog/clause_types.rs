@@ -2,7 +2,7 @@ use prolog_parser::ast::*;
 
 use crate::prolog::forms::Number;
 use crate::prolog::machine::machine_indices::*;
-use crate::prolog::rug::rand::RandState;
+use crate::prolog::bignum::RandState;
 
 use ref_thread_local::RefThreadLocal;
 
@@ -82,7 +82,12 @@ pub enum InlinedClauseType {
 }
 
 ref_thread_local! {
-    pub static managed RANDOM_STATE: RandState<'static> = RandState::new();
+    pub static managed RANDOM_STATE: RandState = RandState::new();
+}
+
+// the number of draws from RANDOM_STATE since it was last seeded.
+ref_thread_local! {
+    pub static managed RANDOM_DRAWS: usize = 0;
 }
 
 ref_thread_local! {
@@ -152,38 +157,69 @@ impl InlinedClauseType {
 pub enum SystemClauseType {
     AbolishClause,
     AbolishModuleClause,
+    Append,
     AssertDynamicPredicateToBack,
     AssertDynamicPredicateToFront,
     AtEndOfExpansion,
+    AtEndOfStream,
     AtomChars,
     AtomCodes,
     AtomLength,
+    AutoloadImport,
     BindFromRegister,
+    BulkAssertDynamicPredicate,
     CallAttributeGoals,
     CallContinuation,
+    CaptureOutput,
+    CapturedOutput,
     CharCode,
     CharsToNumber,
+    CharsUtf8Bytes,
     ClearAttributeGoals,
     CloneAttributeGoals,
+    CloseStream,
+    CodesBytes,
     CodesToNumber,
     CopyTermWithoutAttrVars,
     CheckCutPoint,
+    ChoicePoints,
+    CodeLocation,
+    CodeLocationPointer,
+    CopyCodeIndex,
     CopyToLiftedHeap,
     CreatePartialString,
     CurrentInput,
     CurrentOutput,
+    Decidable,
     DeleteAttribute,
+    DeleteFile,
     DeleteHeadAttribute,
     DynamicModuleResolution(usize),
     EnqueueAttributeGoal,
     EnqueueAttributedVar,
     ExpandGoal,
     ExpandTerm,
+    FastRead,
+    FastWrite,
     FetchAttributeGoals,
     FetchGlobalVar,
     FetchGlobalVarWithOffset,
+    GetByte,
     GetChar,
+    PeekByte,
+    PeekChar,
+    PeekCode,
+    PutByte,
+    ReclaimedCode,
+    ResetAfterAbort,
     ResetAttrVarState,
+    ResetQueryStats,
+    QueryStats,
+    FdLinearPropagate,
+    StackStatistics,
+    SocketClientOpen,
+    SocketServerAccept,
+    SocketServerOpen,
     TruncateIfNoLiftedHeapGrowthDiff,
     TruncateIfNoLiftedHeapGrowth,
     GetAttributedVariableList,
@@ -195,7 +231,11 @@ pub enum SystemClauseType {
     GetModuleClause,
     GetNextDBRef,
     GetNextOpDBRef,
+    IsDeterministicAnswer,
+    IsCallable,
+    IsList,
     IsPartialString,
+    IsString,
     LookupDBRef,
     LookupOpDBRef,
     Halt,
@@ -206,7 +246,12 @@ pub enum SystemClauseType {
     HeadIsDynamic,
     InstallSCCCleaner,
     InstallInferenceCounter,
+    InstallMemoryLimit,
+    InstallSandbox,
+    InstallTimeLimit,
     LiftedHeapLength,
+    LoadAtomTable,
+    LoadContext,
     ModuleAssertDynamicPredicateToFront,
     ModuleAssertDynamicPredicateToBack,
     ModuleExists,
@@ -214,27 +259,52 @@ pub enum SystemClauseType {
     ModuleRetractClause,
     NextEP,
     NoSuchPredicate,
+    NullStream,
     NumberToChars,
     NumberToCodes,
     OpDeclaration,
+    OpenMemoryStream,
+    OpenStream,
     PartialStringTail,
+    PipeClose,
+    PipeCreate,
+    PipeReceive,
+    PipeSend,
+    PortrayClause,
+    PrintTerm,
+    HostCall,
     PointsToContinuationResetMarker,
+    ProcedureSuggestions,
     REPL(REPLCodePtr),
     ReadQueryTerm,
+    ReadToplevelCommand,
     ReadTerm,
+    ReadTermFromChars,
+    RebuildSubterms,
     RedoAttrVarBinding,
     RemoveCallPolicyCheck,
     RemoveInferenceCounter,
+    RemoveMemoryLimit,
+    RemoveSandbox,
+    RemoveTimeLimit,
     ResetContinuationMarker,
     ResetGlobalVarAtKey,
     ResetGlobalVarAtOffset,
     RetractClause,
     RestoreCutPolicy,
+    SaveAtomTable,
     SetCutPoint(RegType),
     SetInput,
     SetOutput,
+    SetStream,
+    StreamProperties,
+    Streams,
+    SetToplevelPrompt,
+    Statistics,
     StoreGlobalVar,
     StoreGlobalVarWithOffset,
+    Subterms,
+    ToplevelPrompt,
     InferenceLevel,
     CleanUpBlock,
     EraseBall,
@@ -242,27 +312,51 @@ pub enum SystemClauseType {
     GetBall,
     GetCurrentBlock,
     GetCutPoint,
+    GetAutoload,
+    GetBlockStrings,
     GetDoubleQuotes,
+    GetQuasiQuotations,
+    GetReproducible,
+    GetUnicodeIdentifiers,
     InstallNewBlock,
     Maybe,
+    Merge,
     QuotedToken,
+    RandomFloat,
+    RandomInteger,
     RawInputReadChar,
     ResetBlock,
     ReturnFromVerifyAttr,
     SetBall,
     SetCutPointByDefault(RegType),
+    SetAutoload,
+    SetBlockStrings,
     SetDoubleQuotes,
+    SetQuasiQuotations,
+    SetReproducible,
+    SetUnicodeIdentifiers,
     SetSeed,
     SkipMaxList,
+    SoftCut,
     Succeed,
+    SubsumesTerm,
     TermVariables,
+    TmpFile,
     TruncateLiftedHeapTo,
+    Unifiable,
     UnifyWithOccursCheck,
     UnwindEnvironments,
     UnwindStack,
     Variant,
+    VariantHash,
     WAMInstructions,
+    MachineSnapshot,
+    PolicyRefs,
+    PolicyStates,
+    SetPolicyStates,
+    WriteCheckpoint,
     WriteTerm,
+    WriteTermToChars,
 }
 
 impl SystemClauseType {
@@ -270,26 +364,40 @@ impl SystemClauseType {
         match self {
             &SystemClauseType::AbolishClause => clause_name!("$abolish_clause"),
             &SystemClauseType::AbolishModuleClause => clause_name!("$abolish_module_clause"),
+            &SystemClauseType::Append => clause_name!("$append"),
             &SystemClauseType::AssertDynamicPredicateToBack => clause_name!("$assertz"),
             &SystemClauseType::AssertDynamicPredicateToFront => clause_name!("$asserta"),
             &SystemClauseType::AtEndOfExpansion => clause_name!("$at_end_of_expansion"),
             &SystemClauseType::AtomChars => clause_name!("$atom_chars"),
             &SystemClauseType::AtomCodes => clause_name!("$atom_codes"),
             &SystemClauseType::AtomLength => clause_name!("$atom_length"),
+            &SystemClauseType::AutoloadImport => clause_name!("$autoload_import"),
             &SystemClauseType::BindFromRegister => clause_name!("$bind_from_register"),
+            &SystemClauseType::BulkAssertDynamicPredicate => clause_name!("$assertz_all"),
             &SystemClauseType::CallAttributeGoals => clause_name!("$call_attribute_goals"),
             &SystemClauseType::CallContinuation => clause_name!("$call_continuation"),
             &SystemClauseType::CharCode => clause_name!("$char_code"),
             &SystemClauseType::CharsToNumber => clause_name!("$chars_to_number"),
+            &SystemClauseType::CharsUtf8Bytes => clause_name!("$chars_utf8bytes"),
             &SystemClauseType::CheckCutPoint => clause_name!("$check_cp"),
+            &SystemClauseType::ChoicePoints => clause_name!("$choice_points"),
+            &SystemClauseType::CodeLocation => clause_name!("$code_location"),
+            &SystemClauseType::CodeLocationPointer => clause_name!("$code_location_pointer"),
             &SystemClauseType::ClearAttributeGoals => clause_name!("$clear_attribute_goals"),
             &SystemClauseType::CloneAttributeGoals => clause_name!("$clone_attribute_goals"),
+            &SystemClauseType::CodesBytes => clause_name!("$codes_bytes"),
             &SystemClauseType::CodesToNumber => clause_name!("$codes_to_number"),
             &SystemClauseType::CopyTermWithoutAttrVars => clause_name!("$copy_term_without_attr_vars"),
             &SystemClauseType::CreatePartialString => clause_name!("$create_partial_string"),
+            &SystemClauseType::CloseStream => clause_name!("$close"),
             &SystemClauseType::CurrentInput => clause_name!("$current_input"),
             &SystemClauseType::CurrentOutput => clause_name!("$current_output"),
+            &SystemClauseType::Decidable => clause_name!("$decidable"),
             &SystemClauseType::REPL(REPLCodePtr::CompileBatch) => clause_name!("$compile_batch"),
+            &SystemClauseType::REPL(REPLCodePtr::CreateModule) => clause_name!("$create_module"),
+            &SystemClauseType::REPL(REPLCodePtr::GarbageCollectClauses) => {
+                clause_name!("$garbage_collect_clauses")
+            }
 	    &SystemClauseType::REPL(REPLCodePtr::UseModule) => clause_name!("$use_module"),
 	    &SystemClauseType::REPL(REPLCodePtr::UseQualifiedModule) => {
 		clause_name!("$use_qualified_module")
@@ -300,21 +408,39 @@ impl SystemClauseType {
 	    &SystemClauseType::REPL(REPLCodePtr::UseQualifiedModuleFromFile) => {
 		clause_name!("$use_qualified_module_from_file")
 	    }
+            &SystemClauseType::CopyCodeIndex => clause_name!("$copy_code_index"),
             &SystemClauseType::CopyToLiftedHeap => clause_name!("$copy_to_lh"),
             &SystemClauseType::DeleteAttribute => clause_name!("$del_attr_non_head"),
+            &SystemClauseType::DeleteFile => clause_name!("$delete_file"),
             &SystemClauseType::DeleteHeadAttribute => clause_name!("$del_attr_head"),
             &SystemClauseType::DynamicModuleResolution(_) => clause_name!("$module_call"),
             &SystemClauseType::EnqueueAttributeGoal => clause_name!("$enqueue_attribute_goal"),
             &SystemClauseType::EnqueueAttributedVar => clause_name!("$enqueue_attr_var"),
             &SystemClauseType::ExpandTerm => clause_name!("$expand_term"),
             &SystemClauseType::ExpandGoal => clause_name!("$expand_goal"),
+            &SystemClauseType::FastRead => clause_name!("$fast_read"),
+            &SystemClauseType::FastWrite => clause_name!("$fast_write"),
             &SystemClauseType::FetchAttributeGoals => clause_name!("$fetch_attribute_goals"),
             &SystemClauseType::FetchGlobalVar => clause_name!("$fetch_global_var"),
             &SystemClauseType::FetchGlobalVarWithOffset => {
                 clause_name!("$fetch_global_var_with_offset")
             }
+            &SystemClauseType::GetByte => clause_name!("$get_byte"),
             &SystemClauseType::GetChar => clause_name!("$get_char"),
+            &SystemClauseType::PeekByte => clause_name!("$peek_byte"),
+            &SystemClauseType::PeekChar => clause_name!("$peek_char"),
+            &SystemClauseType::PeekCode => clause_name!("$peek_code"),
+            &SystemClauseType::SocketClientOpen => clause_name!("$socket_client_open"),
+            &SystemClauseType::SocketServerAccept => clause_name!("$socket_server_accept"),
+            &SystemClauseType::SocketServerOpen => clause_name!("$socket_server_open"),
+            &SystemClauseType::PutByte => clause_name!("$put_byte"),
+            &SystemClauseType::ReclaimedCode => clause_name!("$reclaimed_code"),
+            &SystemClauseType::ResetAfterAbort => clause_name!("$reset_after_abort"),
             &SystemClauseType::ResetAttrVarState => clause_name!("$reset_attr_var_state"),
+            &SystemClauseType::ResetQueryStats => clause_name!("$reset_query_stats"),
+            &SystemClauseType::QueryStats => clause_name!("$query_stats"),
+            &SystemClauseType::FdLinearPropagate => clause_name!("$fd_linear_propagate"),
+            &SystemClauseType::StackStatistics => clause_name!("$stack_statistics"),
             &SystemClauseType::TruncateIfNoLiftedHeapGrowth => {
                 clause_name!("$truncate_if_no_lh_growth")
             }
@@ -332,11 +458,18 @@ impl SystemClauseType {
                 clause_name!("$get_lh_from_offset_diff")
             }
             &SystemClauseType::GetBValue => clause_name!("$get_b_value"),
+            &SystemClauseType::SoftCut => clause_name!("$soft_cut"),
+            &SystemClauseType::IsDeterministicAnswer => clause_name!("$is_deterministic_answer"),
             &SystemClauseType::GetClause => clause_name!("$get_clause"),
             &SystemClauseType::GetNextDBRef => clause_name!("$get_next_db_ref"),
             &SystemClauseType::GetNextOpDBRef => clause_name!("$get_next_op_db_ref"),
             &SystemClauseType::LookupDBRef => clause_name!("$lookup_db_ref"),
             &SystemClauseType::LookupOpDBRef => clause_name!("$lookup_op_db_ref"),
+            &SystemClauseType::GetAutoload => clause_name!("$get_autoload"),
+            &SystemClauseType::GetBlockStrings => clause_name!("$get_block_strings"),
+            &SystemClauseType::GetQuasiQuotations => clause_name!("$get_quasi_quotations"),
+            &SystemClauseType::GetReproducible => clause_name!("$get_reproducible"),
+            &SystemClauseType::GetUnicodeIdentifiers => clause_name!("$get_unicode_identifiers"),
             &SystemClauseType::GetDoubleQuotes => clause_name!("$get_double_quotes"),
             &SystemClauseType::GetModuleClause => clause_name!("$get_module_clause"),
             &SystemClauseType::GetSCCCleaner => clause_name!("$get_scc_cleaner"),
@@ -344,13 +477,31 @@ impl SystemClauseType {
             &SystemClauseType::HeadIsDynamic => clause_name!("$head_is_dynamic"),
             &SystemClauseType::OpDeclaration => clause_name!("$op$"),
             &SystemClauseType::InstallSCCCleaner => clause_name!("$install_scc_cleaner"),
+            &SystemClauseType::InstallSandbox => clause_name!("$install_sandbox"),
+            &SystemClauseType::InstallTimeLimit => clause_name!("$install_time_limit"),
             &SystemClauseType::InstallInferenceCounter => {
                 clause_name!("$install_inference_counter")
             }
+            &SystemClauseType::InstallMemoryLimit => clause_name!("$install_memory_limit"),
+            &SystemClauseType::IsCallable => clause_name!("$is_callable"),
+            &SystemClauseType::IsList => clause_name!("$is_list"),
             &SystemClauseType::IsPartialString => clause_name!("$is_partial_string"),
+            &SystemClauseType::IsString => clause_name!("$is_string"),
             &SystemClauseType::PartialStringTail => clause_name!("$partial_string_tail"),
+            &SystemClauseType::PipeClose => clause_name!("$pipe_close"),
+            &SystemClauseType::PipeCreate => clause_name!("$pipe_create"),
+            &SystemClauseType::PipeReceive => clause_name!("$pipe_receive"),
+            &SystemClauseType::PipeSend => clause_name!("$pipe_send"),
+            &SystemClauseType::PortrayClause => clause_name!("$portray_clause"),
+            &SystemClauseType::PrintTerm => clause_name!("$print_term"),
+            &SystemClauseType::HostCall => clause_name!("$host_call"),
             &SystemClauseType::LiftedHeapLength => clause_name!("$lh_length"),
+            &SystemClauseType::LoadAtomTable => clause_name!("$load_atom_table"),
+            &SystemClauseType::LoadContext => clause_name!("$load_context"),
             &SystemClauseType::Maybe => clause_name!("maybe"),
+            &SystemClauseType::Merge => clause_name!("$merge"),
+            &SystemClauseType::RandomFloat => clause_name!("$random_float"),
+            &SystemClauseType::RandomInteger => clause_name!("$random_integer"),
             &SystemClauseType::ModuleAssertDynamicPredicateToFront => {
                 clause_name!("$module_asserta")
             }
@@ -361,11 +512,15 @@ impl SystemClauseType {
             &SystemClauseType::ModuleExists => clause_name!("$module_exists"),
             &SystemClauseType::ModuleOf => clause_name!("$module_of"),
             &SystemClauseType::NoSuchPredicate => clause_name!("$no_such_predicate"),
+            &SystemClauseType::NullStream => clause_name!("$null_stream"),
+            &SystemClauseType::CaptureOutput => clause_name!("$capture_output"),
+            &SystemClauseType::CapturedOutput => clause_name!("$captured_output"),
             &SystemClauseType::NumberToChars => clause_name!("$number_to_chars"),
             &SystemClauseType::NumberToCodes => clause_name!("$number_to_codes"),
             &SystemClauseType::PointsToContinuationResetMarker => {
                 clause_name!("$points_to_cont_reset_marker")
             }
+            &SystemClauseType::ProcedureSuggestions => clause_name!("$procedure_suggestions"),
             &SystemClauseType::QuotedToken => {
                 clause_name!("$quoted_token")
             }
@@ -373,15 +528,29 @@ impl SystemClauseType {
             &SystemClauseType::RedoAttrVarBinding => clause_name!("$redo_attr_var_binding"),
             &SystemClauseType::RemoveCallPolicyCheck => clause_name!("$remove_call_policy_check"),
             &SystemClauseType::RemoveInferenceCounter => clause_name!("$remove_inference_counter"),
+            &SystemClauseType::RemoveMemoryLimit => clause_name!("$remove_memory_limit"),
+            &SystemClauseType::RemoveSandbox => clause_name!("$remove_sandbox"),
+            &SystemClauseType::RemoveTimeLimit => clause_name!("$remove_time_limit"),
             &SystemClauseType::RestoreCutPolicy => clause_name!("$restore_cut_policy"),
             &SystemClauseType::SetCutPoint(_) => clause_name!("$set_cp"),
+            &SystemClauseType::OpenMemoryStream => clause_name!("$open_memory_stream"),
+            &SystemClauseType::OpenStream => clause_name!("$open"),
             &SystemClauseType::SetInput => clause_name!("$set_input"),
             &SystemClauseType::SetOutput => clause_name!("$set_output"),
+            &SystemClauseType::SaveAtomTable => clause_name!("$save_atom_table"),
+            &SystemClauseType::SetStream => clause_name!("$set_stream"),
+            &SystemClauseType::StreamProperties => clause_name!("$stream_properties"),
+            &SystemClauseType::Streams => clause_name!("$streams"),
+            &SystemClauseType::AtEndOfStream => clause_name!("$at_end_of_stream"),
+            &SystemClauseType::SetToplevelPrompt => clause_name!("$set_toplevel_prompt"),
             &SystemClauseType::SetSeed => clause_name!("$set_seed"),
+            &SystemClauseType::Statistics => clause_name!("$statistics"),
             &SystemClauseType::StoreGlobalVar => clause_name!("$store_global_var"),
             &SystemClauseType::StoreGlobalVarWithOffset => {
                 clause_name!("$store_global_var_with_offset")
             }
+            &SystemClauseType::Subterms => clause_name!("$subterms"),
+            &SystemClauseType::ToplevelPrompt => clause_name!("$toplevel_prompt"),
             &SystemClauseType::InferenceLevel => clause_name!("$inference_level"),
             &SystemClauseType::CleanUpBlock => clause_name!("$clean_up_block"),
             &SystemClauseType::EraseBall => clause_name!("$erase_ball"),
@@ -393,7 +562,10 @@ impl SystemClauseType {
             &SystemClauseType::ModuleRetractClause => clause_name!("$module_retract_clause"),
             &SystemClauseType::NextEP => clause_name!("$nextEP"),
             &SystemClauseType::ReadQueryTerm => clause_name!("$read_query_term"),
+            &SystemClauseType::ReadToplevelCommand => clause_name!("$read_toplevel_command"),
             &SystemClauseType::ReadTerm => clause_name!("$read_term"),
+            &SystemClauseType::ReadTermFromChars => clause_name!("$read_term_from_chars"),
+            &SystemClauseType::RebuildSubterms => clause_name!("$rebuild_subterms"),
             &SystemClauseType::ResetGlobalVarAtKey => clause_name!("$reset_global_var_at_key"),
             &SystemClauseType::ResetGlobalVarAtOffset => clause_name!("$reset_global_var_at_offset"),
             &SystemClauseType::RetractClause => clause_name!("$retract_clause"),
@@ -402,29 +574,47 @@ impl SystemClauseType {
             &SystemClauseType::ReturnFromVerifyAttr => clause_name!("$return_from_verify_attr"),
             &SystemClauseType::SetBall => clause_name!("$set_ball"),
             &SystemClauseType::SetCutPointByDefault(_) => clause_name!("$set_cp_by_default"),
+            &SystemClauseType::SetAutoload => clause_name!("$set_autoload"),
+            &SystemClauseType::SetBlockStrings => clause_name!("$set_block_strings"),
+            &SystemClauseType::SetQuasiQuotations => clause_name!("$set_quasi_quotations"),
+            &SystemClauseType::SetReproducible => clause_name!("$set_reproducible"),
+            &SystemClauseType::SetUnicodeIdentifiers => clause_name!("$set_unicode_identifiers"),
             &SystemClauseType::SetDoubleQuotes => clause_name!("$set_double_quotes"),
             &SystemClauseType::SkipMaxList => clause_name!("$skip_max_list"),
             &SystemClauseType::Succeed => clause_name!("$succeed"),
+            &SystemClauseType::SubsumesTerm => clause_name!("$subsumes_term"),
             &SystemClauseType::TermVariables => clause_name!("$term_variables"),
+            &SystemClauseType::TmpFile => clause_name!("$tmp_file"),
             &SystemClauseType::TruncateLiftedHeapTo => clause_name!("$truncate_lh_to"),
+            &SystemClauseType::Unifiable => clause_name!("$unifiable"),
             &SystemClauseType::UnifyWithOccursCheck => clause_name!("$unify_with_occurs_check"),
             &SystemClauseType::UnwindEnvironments => clause_name!("$unwind_environments"),
             &SystemClauseType::UnwindStack => clause_name!("$unwind_stack"),
             &SystemClauseType::Variant => clause_name!("$variant"),
+            &SystemClauseType::VariantHash => clause_name!("$variant_hash"),
             &SystemClauseType::WAMInstructions => clause_name!("$wam_instructions"),
+            &SystemClauseType::MachineSnapshot => clause_name!("$machine_snapshot"),
+            &SystemClauseType::PolicyRefs => clause_name!("$policy_refs"),
+            &SystemClauseType::PolicyStates => clause_name!("$policy_states"),
+            &SystemClauseType::SetPolicyStates => clause_name!("$set_policy_states"),
+            &SystemClauseType::WriteCheckpoint => clause_name!("$write_checkpoint"),
             &SystemClauseType::WriteTerm => clause_name!("$write_term"),
+            &SystemClauseType::WriteTermToChars => clause_name!("$write_term_to_chars"),
         }
     }
 
     pub fn from(name: &str, arity: usize) -> Option<SystemClauseType> {
         match (name, arity) {
             ("$abolish_clause", 2) => Some(SystemClauseType::AbolishClause),
+            ("$append", 3) => Some(SystemClauseType::Append),
             ("$at_end_of_expansion", 0) => Some(SystemClauseType::AtEndOfExpansion),
             ("$atom_chars", 2) => Some(SystemClauseType::AtomChars),
             ("$atom_codes", 2) => Some(SystemClauseType::AtomCodes),
             ("$atom_length", 2) => Some(SystemClauseType::AtomLength),
+            ("$autoload_import", 4) => Some(SystemClauseType::AutoloadImport),
             ("$abolish_module_clause", 3) => Some(SystemClauseType::AbolishModuleClause),
             ("$bind_from_register", 2) => Some(SystemClauseType::BindFromRegister),
+            ("$assertz_all", 4) => Some(SystemClauseType::BulkAssertDynamicPredicate),
             ("$module_asserta", 5) => Some(SystemClauseType::ModuleAssertDynamicPredicateToFront),
             ("$module_assertz", 5) => Some(SystemClauseType::ModuleAssertDynamicPredicateToBack),
             ("$asserta", 4) => Some(SystemClauseType::AssertDynamicPredicateToFront),
@@ -433,18 +623,28 @@ impl SystemClauseType {
             ("$call_continuation", 1) => Some(SystemClauseType::CallContinuation),
             ("$char_code", 2) => Some(SystemClauseType::CharCode),
             ("$chars_to_number", 2) => Some(SystemClauseType::CharsToNumber),
+            ("$chars_utf8bytes", 2) => Some(SystemClauseType::CharsUtf8Bytes),
             ("$clear_attribute_goals", 0) => Some(SystemClauseType::ClearAttributeGoals),
             ("$clone_attribute_goals", 1) => Some(SystemClauseType::CloneAttributeGoals),
+            ("$codes_bytes", 2) => Some(SystemClauseType::CodesBytes),
             ("$codes_to_number", 2) => Some(SystemClauseType::CodesToNumber),
             ("$copy_term_without_attr_vars", 2) => Some(SystemClauseType::CopyTermWithoutAttrVars),
             ("$create_partial_string", 3) => Some(SystemClauseType::CreatePartialString),
             ("$check_cp", 1) => Some(SystemClauseType::CheckCutPoint),
+            ("$choice_points", 1) => Some(SystemClauseType::ChoicePoints),
+            ("$code_location", 2) => Some(SystemClauseType::CodeLocation),
+            ("$code_location_pointer", 2) => Some(SystemClauseType::CodeLocationPointer),
             ("$compile_batch", 0) => Some(SystemClauseType::REPL(REPLCodePtr::CompileBatch)),
+            ("$create_module", 3) => Some(SystemClauseType::REPL(REPLCodePtr::CreateModule)),
+            ("$copy_code_index", 5) => Some(SystemClauseType::CopyCodeIndex),
             ("$copy_to_lh", 2) => Some(SystemClauseType::CopyToLiftedHeap),
+            ("$close", 1) => Some(SystemClauseType::CloseStream),
             ("$current_input", 1) => Some(SystemClauseType::CurrentInput),
             ("$current_output", 1) => Some(SystemClauseType::CurrentOutput),
+            ("$decidable", 2) => Some(SystemClauseType::Decidable),
             ("$del_attr_non_head", 1) => Some(SystemClauseType::DeleteAttribute),
             ("$del_attr_head", 1) => Some(SystemClauseType::DeleteHeadAttribute),
+            ("$delete_file", 1) => Some(SystemClauseType::DeleteFile),
             ("$get_next_db_ref", 2) => Some(SystemClauseType::GetNextDBRef),
             ("$get_next_op_db_ref", 2) => Some(SystemClauseType::GetNextOpDBRef),
             ("$lookup_db_ref", 3) => Some(SystemClauseType::LookupDBRef),
@@ -453,17 +653,48 @@ impl SystemClauseType {
             ("$enqueue_attribute_goal", 1) => Some(SystemClauseType::EnqueueAttributeGoal),
             ("$enqueue_attr_var", 1) => Some(SystemClauseType::EnqueueAttributedVar),
             ("$partial_string_tail", 2) => Some(SystemClauseType::PartialStringTail),
+            ("$pipe_close", 1) => Some(SystemClauseType::PipeClose),
+            ("$pipe_create", 4) => Some(SystemClauseType::PipeCreate),
+            ("$pipe_receive", 3) => Some(SystemClauseType::PipeReceive),
+            ("$pipe_send", 3) => Some(SystemClauseType::PipeSend),
+            ("$portray_clause", 1) => Some(SystemClauseType::PortrayClause),
+            ("$print_term", 8) => Some(SystemClauseType::PrintTerm),
+            ("$host_call", 2) => Some(SystemClauseType::HostCall),
+            ("$is_deterministic_answer", 2) => Some(SystemClauseType::IsDeterministicAnswer),
+            ("$is_callable", 2) => Some(SystemClauseType::IsCallable),
+            ("$is_list", 1) => Some(SystemClauseType::IsList),
             ("$is_partial_string", 1) => Some(SystemClauseType::IsPartialString),
+            ("$is_string", 1) => Some(SystemClauseType::IsString),
             ("$expand_term", 2) => Some(SystemClauseType::ExpandTerm),
             ("$expand_goal", 2) => Some(SystemClauseType::ExpandGoal),
+            ("$fast_read", 2) => Some(SystemClauseType::FastRead),
+            ("$fast_write", 3) => Some(SystemClauseType::FastWrite),
             ("$fetch_attribute_goals", 1) => Some(SystemClauseType::FetchAttributeGoals),
             ("$fetch_global_var", 2) => Some(SystemClauseType::FetchGlobalVar),
             ("$fetch_global_var_with_offset", 3) => Some(SystemClauseType::FetchGlobalVarWithOffset),
+            ("$garbage_collect_clauses", 0) => {
+                Some(SystemClauseType::REPL(REPLCodePtr::GarbageCollectClauses))
+            }
+            ("$get_byte", 2) => Some(SystemClauseType::GetByte),
             ("$get_char", 1) => Some(SystemClauseType::GetChar),
+            ("$peek_byte", 2) => Some(SystemClauseType::PeekByte),
+            ("$peek_char", 2) => Some(SystemClauseType::PeekChar),
+            ("$peek_code", 2) => Some(SystemClauseType::PeekCode),
+            ("$socket_client_open", 5) => Some(SystemClauseType::SocketClientOpen),
+            ("$socket_server_accept", 4) => Some(SystemClauseType::SocketServerAccept),
+            ("$socket_server_open", 3) => Some(SystemClauseType::SocketServerOpen),
+            ("$put_byte", 2) => Some(SystemClauseType::PutByte),
             ("$points_to_cont_reset_marker", 1) => {
                 Some(SystemClauseType::PointsToContinuationResetMarker)
             }
+            ("$procedure_suggestions", 4) => Some(SystemClauseType::ProcedureSuggestions),
+            ("$reclaimed_code", 0) => Some(SystemClauseType::ReclaimedCode),
+            ("$reset_after_abort", 0) => Some(SystemClauseType::ResetAfterAbort),
             ("$reset_attr_var_state", 0) => Some(SystemClauseType::ResetAttrVarState),
+            ("$reset_query_stats", 0) => Some(SystemClauseType::ResetQueryStats),
+            ("$query_stats", 4) => Some(SystemClauseType::QueryStats),
+            ("$fd_linear_propagate", 7) => Some(SystemClauseType::FdLinearPropagate),
+            ("$stack_statistics", 4) => Some(SystemClauseType::StackStatistics),
             ("$truncate_if_no_lh_growth", 1) => {
                 Some(SystemClauseType::TruncateIfNoLiftedHeapGrowth)
             }
@@ -472,33 +703,61 @@ impl SystemClauseType {
             }
             ("$get_attr_list", 2) => Some(SystemClauseType::GetAttributedVariableList),
             ("$get_b_value", 1) => Some(SystemClauseType::GetBValue),
+            ("$soft_cut", 1) => Some(SystemClauseType::SoftCut),
             ("$get_clause", 2) => Some(SystemClauseType::GetClause),
             ("$get_module_clause", 3) => Some(SystemClauseType::GetModuleClause),
             ("$get_lh_from_offset", 2) => Some(SystemClauseType::GetLiftedHeapFromOffset),
             ("$get_lh_from_offset_diff", 3) => Some(SystemClauseType::GetLiftedHeapFromOffsetDiff),
+            ("$get_autoload", 1) => Some(SystemClauseType::GetAutoload),
+            ("$get_block_strings", 1) => Some(SystemClauseType::GetBlockStrings),
+            ("$get_quasi_quotations", 1) => Some(SystemClauseType::GetQuasiQuotations),
+            ("$get_reproducible", 1) => Some(SystemClauseType::GetReproducible),
+            ("$get_unicode_identifiers", 1) => Some(SystemClauseType::GetUnicodeIdentifiers),
             ("$get_double_quotes", 1) => Some(SystemClauseType::GetDoubleQuotes),
             ("$get_scc_cleaner", 1) => Some(SystemClauseType::GetSCCCleaner),
             ("$halt", 0) => Some(SystemClauseType::Halt),
             ("$head_is_dynamic", 1) => Some(SystemClauseType::HeadIsDynamic),
             ("$install_scc_cleaner", 2) => Some(SystemClauseType::InstallSCCCleaner),
             ("$install_inference_counter", 3) => Some(SystemClauseType::InstallInferenceCounter),
+            ("$install_memory_limit", 2) => Some(SystemClauseType::InstallMemoryLimit),
+            ("$install_sandbox", 2) => Some(SystemClauseType::InstallSandbox),
+            ("$install_time_limit", 2) => Some(SystemClauseType::InstallTimeLimit),
             ("$lh_length", 1) => Some(SystemClauseType::LiftedHeapLength),
+            ("$load_atom_table", 1) => Some(SystemClauseType::LoadAtomTable),
+            ("$load_context", 2) => Some(SystemClauseType::LoadContext),
             ("$maybe", 0) => Some(SystemClauseType::Maybe),
+            ("$merge", 4) => Some(SystemClauseType::Merge),
+            ("$random_float", 1) => Some(SystemClauseType::RandomFloat),
+            ("$random_integer", 2) => Some(SystemClauseType::RandomInteger),
             ("$module_exists", 1) => Some(SystemClauseType::ModuleExists),
             ("$module_of", 2) => Some(SystemClauseType::ModuleOf),
             ("$module_retract_clause", 5) => Some(SystemClauseType::ModuleRetractClause),
             ("$module_head_is_dynamic", 2) => Some(SystemClauseType::ModuleHeadIsDynamic),
             ("$no_such_predicate", 1) => Some(SystemClauseType::NoSuchPredicate),
-            ("$number_to_chars", 2) => Some(SystemClauseType::NumberToChars),
-            ("$number_to_codes", 2) => Some(SystemClauseType::NumberToCodes),
+            ("$null_stream", 1) => Some(SystemClauseType::NullStream),
+            ("$capture_output", 1) => Some(SystemClauseType::CaptureOutput),
+            ("$captured_output", 2) => Some(SystemClauseType::CapturedOutput),
+            ("$number_to_chars", 3) => Some(SystemClauseType::NumberToChars),
+            ("$number_to_codes", 3) => Some(SystemClauseType::NumberToCodes),
             ("$op", 3) => Some(SystemClauseType::OpDeclaration),
             ("$redo_attr_var_binding", 2) => Some(SystemClauseType::RedoAttrVarBinding),
             ("$remove_call_policy_check", 1) => Some(SystemClauseType::RemoveCallPolicyCheck),
             ("$remove_inference_counter", 2) => Some(SystemClauseType::RemoveInferenceCounter),
+            ("$remove_memory_limit", 1) => Some(SystemClauseType::RemoveMemoryLimit),
+            ("$remove_sandbox", 1) => Some(SystemClauseType::RemoveSandbox),
+            ("$remove_time_limit", 1) => Some(SystemClauseType::RemoveTimeLimit),
             ("$restore_cut_policy", 0) => Some(SystemClauseType::RestoreCutPolicy),
             ("$set_cp", 1) => Some(SystemClauseType::SetCutPoint(temp_v!(1))),
+            ("$open", 4) => Some(SystemClauseType::OpenStream),
+            ("$open_memory_stream", 3) => Some(SystemClauseType::OpenMemoryStream),
             ("$set_input", 1) => Some(SystemClauseType::SetInput),
             ("$set_output", 1) => Some(SystemClauseType::SetOutput),
+            ("$save_atom_table", 1) => Some(SystemClauseType::SaveAtomTable),
+            ("$set_stream", 2) => Some(SystemClauseType::SetStream),
+            ("$stream_properties", 2) => Some(SystemClauseType::StreamProperties),
+            ("$streams", 1) => Some(SystemClauseType::Streams),
+            ("$at_end_of_stream", 1) => Some(SystemClauseType::AtEndOfStream),
+            ("$set_toplevel_prompt", 2) => Some(SystemClauseType::SetToplevelPrompt),
             ("$inference_level", 2) => Some(SystemClauseType::InferenceLevel),
             ("$clean_up_block", 1) => Some(SystemClauseType::CleanUpBlock),
             ("$erase_ball", 0) => Some(SystemClauseType::EraseBall),
@@ -514,7 +773,10 @@ impl SystemClauseType {
             ("$raw_input_read_char", 1) => Some(SystemClauseType::RawInputReadChar),
             ("$nextEP", 3) => Some(SystemClauseType::NextEP),
             ("$read_query_term", 2) => Some(SystemClauseType::ReadQueryTerm),
-            ("$read_term", 2) => Some(SystemClauseType::ReadTerm),
+            ("$read_toplevel_command", 2) => Some(SystemClauseType::ReadToplevelCommand),
+            ("$read_term", 5) => Some(SystemClauseType::ReadTerm),
+            ("$read_term_from_chars", 3) => Some(SystemClauseType::ReadTermFromChars),
+            ("$rebuild_subterms", 3) => Some(SystemClauseType::RebuildSubterms),
             ("$reset_block", 1) => Some(SystemClauseType::ResetBlock),
             ("$reset_cont_marker", 0) => Some(SystemClauseType::ResetContinuationMarker),
             ("$reset_global_var_at_key", 1) => Some(SystemClauseType::ResetGlobalVarAtKey),
@@ -523,15 +785,26 @@ impl SystemClauseType {
             ("$return_from_verify_attr", 0) => Some(SystemClauseType::ReturnFromVerifyAttr),
             ("$set_ball", 1) => Some(SystemClauseType::SetBall),
             ("$set_cp_by_default", 1) => Some(SystemClauseType::SetCutPointByDefault(temp_v!(1))),
+            ("$set_autoload", 1) => Some(SystemClauseType::SetAutoload),
+            ("$set_block_strings", 1) => Some(SystemClauseType::SetBlockStrings),
+            ("$set_quasi_quotations", 1) => Some(SystemClauseType::SetQuasiQuotations),
+            ("$set_reproducible", 1) => Some(SystemClauseType::SetReproducible),
+            ("$set_unicode_identifiers", 1) => Some(SystemClauseType::SetUnicodeIdentifiers),
             ("$set_double_quotes", 1) => Some(SystemClauseType::SetDoubleQuotes),
             ("$set_seed", 1) => Some(SystemClauseType::SetSeed),
             ("$skip_max_list", 4) => Some(SystemClauseType::SkipMaxList),
+            ("$statistics", 2) => Some(SystemClauseType::Statistics),
             ("$store_global_var", 2) => Some(SystemClauseType::StoreGlobalVar),
             ("$store_global_var_with_offset", 2) => Some(SystemClauseType::StoreGlobalVarWithOffset),
+            ("$subterms", 2) => Some(SystemClauseType::Subterms),
+            ("$toplevel_prompt", 2) => Some(SystemClauseType::ToplevelPrompt),
+            ("$subsumes_term", 2) => Some(SystemClauseType::SubsumesTerm),
             ("$term_variables", 2) => Some(SystemClauseType::TermVariables),
+            ("$tmp_file", 2) => Some(SystemClauseType::TmpFile),
             ("$truncate_lh_to", 1) => Some(SystemClauseType::TruncateLiftedHeapTo),
             ("$unwind_environments", 0) => Some(SystemClauseType::UnwindEnvironments),
             ("$unwind_stack", 0) => Some(SystemClauseType::UnwindStack),
+            ("$unifiable", 3) => Some(SystemClauseType::Unifiable),
             ("$unify_with_occurs_check", 2) => Some(SystemClauseType::UnifyWithOccursCheck),
 	    ("$use_module", 1) => Some(SystemClauseType::REPL(REPLCodePtr::UseModule)),
 	    ("$use_module_from_file", 1) =>
@@ -541,8 +814,15 @@ impl SystemClauseType {
 	    ("$use_qualified_module_from_file", 2) =>
 		Some(SystemClauseType::REPL(REPLCodePtr::UseQualifiedModuleFromFile)),
             ("$variant", 2) => Some(SystemClauseType::Variant),
-            ("$write_term", 6) => Some(SystemClauseType::WriteTerm),
-            ("$wam_instructions", 3) => Some(SystemClauseType::WAMInstructions),
+            ("$variant_hash", 2) => Some(SystemClauseType::VariantHash),
+            ("$write_checkpoint", 2) => Some(SystemClauseType::WriteCheckpoint),
+            ("$write_term", 7) => Some(SystemClauseType::WriteTerm),
+            ("$write_term_to_chars", 7) => Some(SystemClauseType::WriteTermToChars),
+            ("$wam_instructions", 4) => Some(SystemClauseType::WAMInstructions),
+            ("$machine_snapshot", 2) => Some(SystemClauseType::MachineSnapshot),
+            ("$policy_refs", 2) => Some(SystemClauseType::PolicyRefs),
+            ("$policy_states", 2) => Some(SystemClauseType::PolicyStates),
+            ("$set_policy_states", 2) => Some(SystemClauseType::SetPolicyStates),
             _ => None,
         }
     }
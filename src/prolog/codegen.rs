@@ -130,7 +130,7 @@ impl<'a, TermMarker: Allocator<'a>> CodeGenerator<TermMarker> {
     ) -> RegType {
         match self.marker.bindings().get(&name) {
             Some(&VarData::Temp(_, t, _)) if t != 0 => RegType::Temp(t),
-            Some(&VarData::Perm(p)) if p != 0 => RegType::Perm(p),
+            Some(&VarData::Perm(Some(p))) => RegType::Perm(p),
             _ => {
                 let mut target = Vec::new();
 
@@ -486,14 +486,14 @@ impl<'a, TermMarker: Allocator<'a>> CodeGenerator<TermMarker> {
     fn compile_is_call(
         &mut self,
         terms: &'a Vec<Box<Term>>,
+        (mut acode, at): ArithCont,
         code: &mut Code,
         term_loc: GenContext,
         use_default_call_policy: bool,
-    ) -> Result<(), ParserError> {
-        let (mut acode, at) = self.call_arith_eval(terms[1].as_ref(), 1)?;
+    ) {
         code.append(&mut acode);
 
-        Ok(match terms[0].as_ref() {
+        match terms[0].as_ref() {
             &Term::Var(ref vr, ref name) => {
                 let mut target = vec![];
 
@@ -551,7 +551,7 @@ impl<'a, TermMarker: Allocator<'a>> CodeGenerator<TermMarker> {
                 }
             }
             _ => code.push(fail!()),
-        })
+        }
     }
 
     #[inline]
@@ -615,17 +615,36 @@ impl<'a, TermMarker: Allocator<'a>> CodeGenerator<TermMarker> {
                         ClauseType::BuiltIn(BuiltInClauseType::Is(..)),
                         ref terms,
                         use_default_call_policy,
-                    ) => self.compile_is_call(terms, code, term_loc, use_default_call_policy)?,
+                    ) => match self.call_arith_eval(terms[1].as_ref(), 1) {
+                        Ok(arith_cont) => self.compile_is_call(
+                            terms,
+                            arith_cont,
+                            code,
+                            term_loc,
+                            use_default_call_policy,
+                        ),
+                        Err(_) => {
+                            // the expression can't be compiled ahead of time (it
+                            // contains a fresh variable or a non-evaluable
+                            // functor), so it's passed to is/2 as a term, which
+                            // evaluates it and raises any errors at runtime.
+                            let num_perm_vars =
+                                Self::num_perm_vars_left(conjunct_info, chunk_num, i);
+
+                            self.compile_query_line(
+                                term,
+                                term_loc,
+                                code,
+                                num_perm_vars,
+                                is_exposed,
+                            );
+                        }
+                    },
                     &QueryTerm::Clause(_, ClauseType::Inlined(ref ct), ref terms, _) => {
                         self.compile_inlined(ct, terms, term_loc, code)?
                     }
                     _ => {
-                        let num_perm_vars = if chunk_num == 0 {
-                            conjunct_info.perm_vars()
-                        } else {
-                            conjunct_info.perm_vs.vars_above_threshold(i + 1)
-                        };
-
+                        let num_perm_vars = Self::num_perm_vars_left(conjunct_info, chunk_num, i);
                         self.compile_query_line(term, term_loc, code, num_perm_vars, is_exposed);
                     }
                 }
@@ -637,6 +656,15 @@ impl<'a, TermMarker: Allocator<'a>> CodeGenerator<TermMarker> {
         Ok(())
     }
 
+    #[inline]
+    fn num_perm_vars_left(conjunct_info: &ConjunctInfo, chunk_num: usize, i: usize) -> usize {
+        if chunk_num == 0 {
+            conjunct_info.perm_vars()
+        } else {
+            conjunct_info.perm_vs.vars_above_threshold(i + 1) + conjunct_info.perm_var_offset()
+        }
+    }
+
     fn compile_seq_prelude(&mut self, conjunct_info: &ConjunctInfo, body: &mut Code) {
         if conjunct_info.allocates() {
             let perm_vars = conjunct_info.perm_vars();
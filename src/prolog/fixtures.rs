@@ -20,10 +20,10 @@ pub enum VarStatus {
 
 pub type OccurrenceSet = BTreeSet<(GenContext, usize)>;
 
-// Perm: 0 initially, a stack register once processed.
+// Perm: None initially, a stack register once processed.
 // Temp: labeled with chunk_num and temp offset (unassigned if 0).
 pub enum VarData {
-    Perm(usize),
+    Perm(Option<usize>),
     Temp(usize, usize, TempVarData),
 }
 
@@ -31,7 +31,7 @@ impl VarData {
     pub fn as_reg_type(&self) -> RegType {
         match self {
             &VarData::Temp(_, r, _) => RegType::Temp(r),
-            &VarData::Perm(r) => RegType::Perm(r),
+            &VarData::Perm(r) => RegType::Perm(r.unwrap_or(0)),
         }
     }
 }
@@ -6,7 +6,7 @@ use crate::prolog::clause_types::*;
 use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::ordered_float::OrderedFloat;
-use crate::prolog::rug::{Integer, Rational};
+use crate::prolog::bignum::{Integer, Rational};
 
 use indexmap::IndexMap;
 
@@ -384,6 +384,13 @@ pub enum MultiFileIndicator {
     ModuleScoped(ScopedPredicateKey),
 }
 
+#[derive(Clone, Copy)]
+pub enum PrologFlag {
+    BlockStrings(bool),
+    DoubleQuotes(DoubleQuotes),
+    QuasiQuotations(bool),
+}
+
 #[derive(Clone)]
 pub enum Declaration {
     Dynamic(ClauseName, usize), // name, arity
@@ -394,7 +401,7 @@ pub enum Declaration {
     MultiFile(MultiFileIndicator),
     NonCountedBacktracking(ClauseName, usize), // name, arity
     Op(OpDecl),
-    SetPrologFlag(DoubleQuotes),
+    SetPrologFlag(PrologFlag),
     UseModule(ModuleSource),
     UseQualifiedModule(ModuleSource, Vec<ModuleExport>),
 }
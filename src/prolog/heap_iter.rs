@@ -9,6 +9,8 @@ use std::cmp::Ordering;
 use std::ops::Deref;
 use std::vec::Vec;
 
+// the subterms yet to be visited are kept on state_stack, so that the
+// comparisons and walks built on the iterator don't recurse.
 pub struct HCPreOrderIterator<'a> {
     pub machine_st: &'a MachineState,
     pub state_stack: Vec<Addr>,
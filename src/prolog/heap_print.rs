@@ -6,7 +6,7 @@ use crate::prolog::heap_iter::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::machine_state::*;
 use crate::prolog::ordered_float::OrderedFloat;
-use crate::prolog::rug::Integer;
+use crate::prolog::bignum::Integer;
 
 use indexmap::{IndexMap, IndexSet};
 
@@ -175,6 +175,7 @@ enum TokenOrRedirect {
     OpenList(Rc<Cell<(bool, usize)>>),
     CloseList(Rc<Cell<(bool, usize)>>),
     HeadTailSeparator,
+    ElidedListTail(Option<usize>),
 }
 
 pub trait HCValueOutputter {
@@ -191,6 +192,14 @@ pub trait HCValueOutputter {
     fn truncate(&mut self, _: usize);
     fn range(&self, _: Range<usize>) -> &str;
     fn range_from(&self, _: RangeFrom<usize>) -> &str;
+
+    // the arguments of a compound term, the elements of a list and
+    // the contents of brackets are written between begin_group and
+    // end_group, with a break after each comma separating them. only
+    // outputters that lay out their output over lines heed them.
+    fn begin_group(&mut self) {}
+    fn end_group(&mut self) {}
+    fn break_point(&mut self) {}
 }
 
 pub struct PrinterOutputter {
@@ -253,6 +262,238 @@ impl HCValueOutputter for PrinterOutputter {
     }
 }
 
+#[derive(Clone, Copy)]
+enum LayoutMark {
+    BeginGroup,
+    EndGroup,
+    Break,
+}
+
+enum Layout {
+    Text(String),
+    Group(Vec<Layout>),
+    Break,
+}
+
+impl Layout {
+    fn width(&self) -> usize {
+        match self {
+            &Layout::Text(ref text) => text.chars().count(),
+            &Layout::Group(ref items) => items.iter().map(Layout::width).sum(),
+            &Layout::Break => 0,
+        }
+    }
+}
+
+// the width of items up to their first break, or, if there is none,
+// that of items and then rest.
+fn width_to_break(items: &[Layout], rest: usize) -> usize {
+    let mut width = 0;
+
+    for item in items {
+        match item {
+            &Layout::Break => return width,
+            item => width += item.width(),
+        }
+    }
+
+    width + rest
+}
+
+const DEFAULT_RIGHT_MARGIN: usize = 72;
+
+/* An outputter that breaks its output over lines so that it keeps
+ * within right_margin where it can. A group that fits on the rest of
+ * its line, with whatever follows it up to the next break, is written
+ * as it is. One that doesn't has a line break at each of its breaks,
+ * its items then aligned with the first of them or, if indent is
+ * positive, begun on a line of their own, indent columns in from
+ * the line the group begins on. column is the column the output
+ * begins at. */
+pub struct LayoutOutputter {
+    contents: String,
+    marks: Vec<(usize, LayoutMark)>,
+    pub(crate) column: usize,
+    pub(crate) right_margin: usize,
+    pub(crate) indent: usize,
+}
+
+impl LayoutOutputter {
+    fn layout(&self) -> Vec<Layout> {
+        let mut groups = vec![vec![]];
+        let mut start = 0;
+
+        for &(pos, mark) in &self.marks {
+            if pos > start {
+                groups.last_mut().unwrap().push(Layout::Text(self.contents[start .. pos].to_string()));
+                start = pos;
+            }
+
+            match mark {
+                LayoutMark::BeginGroup => groups.push(vec![]),
+                LayoutMark::EndGroup if groups.len() > 1 => {
+                    let group = groups.pop().unwrap();
+                    groups.last_mut().unwrap().push(Layout::Group(group));
+                }
+                LayoutMark::EndGroup => {}
+                LayoutMark::Break => groups.last_mut().unwrap().push(Layout::Break),
+            }
+        }
+
+        if start < self.contents.len() {
+            groups.last_mut().unwrap().push(Layout::Text(self.contents[start ..].to_string()));
+        }
+
+        while groups.len() > 1 {
+            let group = groups.pop().unwrap();
+            groups.last_mut().unwrap().push(Layout::Group(group));
+        }
+
+        groups.pop().unwrap()
+    }
+}
+
+struct LayoutWriter {
+    output: String,
+    column: usize,
+    line_indent: usize,
+    right_margin: usize,
+    indent: usize,
+}
+
+impl LayoutWriter {
+    fn newline_at(&mut self, col: usize) {
+        self.output.push('\n');
+        self.output.extend(std::iter::repeat(' ').take(col));
+
+        self.column = col;
+        self.line_indent = col;
+    }
+
+    // writes items, breaking lines at their breaks if broken. rest is
+    // the width of what follows items up to the next break.
+    fn write(&mut self, items: &[Layout], broken: bool, col: usize, rest: usize) {
+        for (idx, item) in items.iter().enumerate() {
+            match item {
+                &Layout::Text(ref text) => {
+                    self.output += text;
+                    self.column += text.chars().count();
+                }
+                &Layout::Break => {
+                    if broken {
+                        self.newline_at(col);
+                    }
+                }
+                &Layout::Group(ref group) => {
+                    let following = width_to_break(&items[idx + 1 ..], rest);
+
+                    if self.column + item.width() + following <= self.right_margin {
+                        self.write(group, false, self.column, following);
+                    } else if self.indent > 0 && self.line_indent + self.indent < self.column {
+                        let group_col = self.line_indent + self.indent;
+
+                        self.newline_at(group_col);
+                        self.write(group, true, group_col, following);
+                    } else {
+                        let group_col = self.column;
+                        self.write(group, true, group_col, following);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl HCValueOutputter for LayoutOutputter {
+    type Output = String;
+
+    fn new() -> Self {
+        LayoutOutputter {
+            contents: String::new(),
+            marks: vec![],
+            column: 0,
+            right_margin: DEFAULT_RIGHT_MARGIN,
+            indent: 0,
+        }
+    }
+
+    fn append(&mut self, contents: &str) {
+        if requires_space(&self.contents, contents) {
+            self.push_char(' ');
+        }
+
+        self.contents += contents;
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.contents.push(c);
+    }
+
+    fn begin_new_var(&mut self) {
+        if self.contents.len() != 0 {
+            self.contents += ", ";
+        }
+    }
+
+    fn insert(&mut self, idx: usize, c: char) {
+        self.contents.insert(idx, c);
+
+        for &mut (ref mut pos, _) in &mut self.marks {
+            if *pos > idx {
+                *pos += c.len_utf8();
+            }
+        }
+    }
+
+    fn result(self) -> Self::Output {
+        let layout = self.layout();
+
+        let mut writer = LayoutWriter {
+            output: String::new(),
+            column: self.column,
+            line_indent: self.column,
+            right_margin: self.right_margin,
+            indent: self.indent,
+        };
+
+        writer.write(&layout, false, self.column, 0);
+        writer.output
+    }
+
+    fn ends_with(&self, s: &str) -> bool {
+        self.contents.ends_with(s)
+    }
+
+    fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.contents.truncate(len);
+        self.marks.retain(|&(pos, _)| pos <= len);
+    }
+
+    fn range(&self, index: Range<usize>) -> &str {
+        &self.contents.as_str()[index]
+    }
+
+    fn range_from(&self, index: RangeFrom<usize>) -> &str {
+        &self.contents.as_str()[index]
+    }
+
+    fn begin_group(&mut self) {
+        self.marks.push((self.contents.len(), LayoutMark::BeginGroup));
+    }
+
+    fn end_group(&mut self) {
+        self.marks.push((self.contents.len(), LayoutMark::EndGroup));
+    }
+
+    fn break_point(&mut self) {
+        self.marks.push((self.contents.len(), LayoutMark::Break));
+    }
+}
+
 #[inline]
 fn is_numbered_var(ct: &ClauseType, arity: usize) -> bool {
     arity == 1 && ct.name().as_str() == "$VAR"
@@ -317,6 +558,11 @@ pub struct HCPrinter<'a, Outputter> {
     cyclic_terms: IndexMap<Addr, usize>,
     non_cyclic_terms: IndexSet<usize>,
     pub(crate) var_names: IndexMap<Addr, Var>,
+    // if set, variables missing from var_names are written as _G0,
+    // _G1, ... in the order they're first written, rather than by
+    // their locations.
+    pub(crate) number_unnamed_vars: bool,
+    unnamed_vars: IndexMap<Addr, usize>,
     pub(crate) numbervars_offset: Integer,
     pub(crate) numbervars: bool,
     pub(crate) quoted: bool,
@@ -407,6 +653,12 @@ fn non_quoted_graphic_token<Iter: Iterator<Item = char>>(mut iter: Iter, c: char
     }
 }
 
+/* Decides whether the atom with the characters of iter can be
+ * written without quotes. Letters are only recognized in ASCII, as
+ * they are by the lexer, so that writeq/1 quotes any atom that the
+ * reader couldn't otherwise read back, whatever the
+ * unicode_identifiers flag says.
+ */
 pub(super)
 fn non_quoted_token<Iter: Iterator<Item = char>>(mut iter: Iter) -> bool {
     if let Some(c) = iter.next() {
@@ -462,6 +714,8 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
             cyclic_terms: IndexMap::new(),
             non_cyclic_terms: IndexSet::new(),
             var_names: IndexMap::new(),
+            number_unnamed_vars: false,
+            unnamed_vars: IndexMap::new(),
             print_strings_as_strs: false,
             max_depth: 0,
         }
@@ -757,6 +1011,12 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
             Addr::Lis(h) | Addr::Str(h) => {
                 Some(format!("{}", h))
             }
+            _ if self.number_unnamed_vars && addr.as_var().is_some() => {
+                let n = self.unnamed_vars.len();
+                let n = *self.unnamed_vars.entry(addr).or_insert(n);
+
+                Some(format!("_G{}", n))
+            }
             _ => {
                 if let Some(r) = addr.as_var() {
                     match r {
@@ -1138,19 +1398,23 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
                         Box::new(s[offset ..].chars().take(max_depth))
                     };
 
+                let mut char_count = 0;
                 let mut byte_len = 0;
 
                 for c in iter {
                     self.print_char(false, c);
                     self.push_char(',');
 
+                    char_count += 1;
                     byte_len += c.len_utf8();
                 }
 
+                self.outputter.truncate(self.outputter.len() - ','.len_utf8());
+
                 if self.max_depth > 0 && byte_len < s[offset ..].len() {
-                    self.append_str("...|...]");
+                    let elided = s[offset ..].chars().count() - char_count;
+                    self.append_str(&format!("|...({} more)...]", elided));
                 }  else {
-                    self.outputter.truncate(self.outputter.len() - ','.len_utf8());
                     self.push_char(']');
                 }
             }
@@ -1180,8 +1444,32 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
         false
     }
 
+    // the number of elements in the list whose head and tail are atop
+    // the iterator stack, if it's a proper list.
+    fn elided_list_len(&self, iter: &mut HCPreOrderIterator) -> Option<usize> {
+        let stack = iter.stack();
+
+        if stack.len() < 2 {
+            return None;
+        }
+
+        let tail = stack[stack.len() - 2].clone();
+        self.machine_st.proper_list_len(tail).map(|n| n + 1)
+    }
+
     fn push_list(&mut self, iter: &mut HCPreOrderIterator, mut max_depth: usize) {
         if self.check_max_depth(&mut max_depth) {
+            if self.outputter.ends_with("|") {
+                // summarize the tail of the list being printed.
+                let elided = self.elided_list_len(iter);
+
+                iter.stack().pop();
+                iter.stack().pop();
+
+                self.state_stack.push(TokenOrRedirect::ElidedListTail(elided));
+                return;
+            }
+
             iter.stack().pop();
             iter.stack().pop();
 
@@ -1313,7 +1601,8 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
                     if stream.is_stdout() || stream.is_stdin() {
                         self.print_atom(&clause_name!("user"));
                     } else {
-                        self.format_struct(iter, max_depth, 1, clause_name!("$stream"));
+                        self.print_atom(&clause_name!("$stream"));
+                        self.append_str(&format!("({:#x})", stream.as_ptr() as usize));
                     }
                 }
             }
@@ -1361,26 +1650,49 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
                     TokenOrRedirect::FunctorRedirect(max_depth) => {
                         self.handle_heap_term(&mut iter, None, true, max_depth)
                     }
-                    TokenOrRedirect::Close => self.push_char(')'),
-                    TokenOrRedirect::Open => self.push_char('('),
+                    TokenOrRedirect::Close => {
+                        self.outputter.end_group();
+                        self.push_char(')');
+                    }
+                    TokenOrRedirect::Open => {
+                        self.push_char('(');
+                        self.outputter.begin_group();
+                    }
                     TokenOrRedirect::OpenList(delimit) => {
                         if !self.at_cdr(",") {
                             self.push_char('[');
+                            self.outputter.begin_group();
                         } else {
                             let (_, max_depth) = delimit.get();
                             delimit.set((false, max_depth));
+
+                            self.outputter.break_point();
                         }
                     }
                     TokenOrRedirect::CloseList(delimit) => {
                         if delimit.get().0 {
+                            self.outputter.end_group();
                             self.push_char(']');
                         }
                     }
                     TokenOrRedirect::HeadTailSeparator => self.append_str("|"),
-                    TokenOrRedirect::Comma => self.append_str(","),
+                    TokenOrRedirect::ElidedListTail(Some(n)) => {
+                        self.append_str(&format!("...({} more)...", n))
+                    }
+                    TokenOrRedirect::ElidedListTail(None) => self.append_str("..."),
+                    TokenOrRedirect::Comma => {
+                        self.append_str(",");
+                        self.outputter.break_point();
+                    }
                     TokenOrRedirect::Space => self.push_char(' '),
-                    TokenOrRedirect::LeftCurly => self.push_char('{'),
-                    TokenOrRedirect::RightCurly => self.push_char('}'),
+                    TokenOrRedirect::LeftCurly => {
+                        self.push_char('{');
+                        self.outputter.begin_group();
+                    }
+                    TokenOrRedirect::RightCurly => {
+                        self.outputter.end_group();
+                        self.push_char('}');
+                    }
                 }
             } else if !iter.stack().is_empty() {
                 let spec = self.toplevel_spec.take();
@@ -1393,3 +1705,228 @@ impl<'a, Outputter: HCValueOutputter> HCPrinter<'a, Outputter> {
         self.outputter
     }
 }
+
+/* Lays out a clause the way portray_clause/1 prints it: the body
+ * starts on a new line after the neck, each conjunct is given a line
+ * of its own, and disjunctions and if-then-elses are laid out in
+ * columns, with their branches indented one level further in.
+ * Variables are named A, B, ... in order of first occurrence, except
+ * for singletons, which are printed as _. Everything below the level
+ * of a goal is printed by an HCPrinter, over several lines if it
+ * would run past the right margin. */
+pub struct ClausePortrayer<'a> {
+    machine_st: &'a MachineState,
+    op_dir: &'a OpDir,
+    var_names: IndexMap<Addr, Var>,
+    output: String,
+}
+
+const PORTRAY_INDENT: usize = 4;
+const PORTRAY_RIGHT_MARGIN: usize = 78;
+
+impl<'a> ClausePortrayer<'a> {
+    pub fn new(machine_st: &'a MachineState, op_dir: &'a OpDir) -> Self {
+        ClausePortrayer {
+            machine_st,
+            op_dir,
+            var_names: IndexMap::new(),
+            output: String::new(),
+        }
+    }
+
+    fn name_vars(&mut self, addr: Addr) {
+        let mut occurrences: IndexMap<Addr, usize> = IndexMap::new();
+
+        for item in self.machine_st.acyclic_pre_order_iter(addr) {
+            if let HeapCellValue::Addr(addr) = item {
+                if addr.is_ref() {
+                    *occurrences.entry(addr).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut n = 0;
+
+        for (addr, count) in occurrences {
+            let name = if count == 1 {
+                String::from("_")
+            } else {
+                n += 1;
+                numbervar(Integer::from(n - 1))
+            };
+
+            self.var_names.insert(addr, name);
+        }
+    }
+
+    fn binary_op(&self, addr: &Addr, op: &str) -> Option<(Addr, Addr)> {
+        match self.machine_st.store(self.machine_st.deref(addr.clone())) {
+            Addr::Str(s) => match &self.machine_st.heap[s] {
+                HeapCellValue::NamedStr(2, ref name, _) if name.as_str() == op => {
+                    Some((
+                        self.machine_st.heap[s + 1].as_addr(s + 1),
+                        self.machine_st.heap[s + 2].as_addr(s + 2),
+                    ))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn unary_op(&self, addr: &Addr, op: &str) -> Option<Addr> {
+        match self.machine_st.store(self.machine_st.deref(addr.clone())) {
+            Addr::Str(s) => match &self.machine_st.heap[s] {
+                HeapCellValue::NamedStr(1, ref name, _) if name.as_str() == op => {
+                    Some(self.machine_st.heap[s + 1].as_addr(s + 1))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn is_atom(&self, addr: &Addr, atom: &str) -> bool {
+        match self.machine_st.store(self.machine_st.deref(addr.clone())) {
+            Addr::Con(Constant::Atom(ref name, _)) => name.as_str() == atom,
+            _ => false,
+        }
+    }
+
+    // operators binding more loosely than the arguments of ','/2 are
+    // bracketed, so that every goal reads back as a single conjunct.
+    fn needs_bracketing(&self, addr: &Addr) -> bool {
+        match self.machine_st.store(self.machine_st.deref(addr.clone())) {
+            Addr::Str(s) => match &self.machine_st.heap[s] {
+                HeapCellValue::NamedStr(arity, ref name, ref spec) => {
+                    match fetch_op_spec(name.clone(), *arity, spec.clone(), self.op_dir) {
+                        Some(spec) => spec.prec() > 999,
+                        None => false,
+                    }
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    // the column the next character of the output is written at.
+    fn column(&self) -> usize {
+        match self.output.rfind('\n') {
+            Some(idx) => self.output[idx + 1 ..].chars().count(),
+            None => self.output.chars().count(),
+        }
+    }
+
+    fn print_term(&mut self, addr: Addr) {
+        let bracket = self.needs_bracketing(&addr);
+
+        if bracket {
+            self.output.push('(');
+        }
+
+        let mut outputter = LayoutOutputter::new();
+
+        outputter.column = self.column();
+        outputter.right_margin = PORTRAY_RIGHT_MARGIN;
+
+        let mut printer = HCPrinter::new(self.machine_st, self.op_dir, outputter);
+
+        printer.quoted = true;
+        printer.numbervars = true;
+        printer.var_names = self.var_names.clone();
+
+        self.output += &printer.print(addr).result();
+
+        if bracket {
+            self.output.push(')');
+        }
+    }
+
+    fn newline_at(&mut self, col: usize) {
+        self.output.push('\n');
+        self.output.extend(std::iter::repeat(' ').take(col));
+    }
+
+    // prints a body whose first line has already been indented to
+    // col, indenting the lines after it to col as well.
+    fn print_body(&mut self, addr: Addr, col: usize) {
+        if let Some((left, right)) = self.binary_op(&addr, ",") {
+            self.print_body(left, col);
+            self.output.push(',');
+            self.newline_at(col);
+            self.print_body(right, col);
+        } else if self.binary_op(&addr, ";").is_some() || self.binary_op(&addr, "->").is_some()
+               || self.binary_op(&addr, "*->").is_some()
+        {
+            self.output += "(   ";
+            self.print_disjunction(addr, col);
+            self.newline_at(col);
+            self.output.push(')');
+        } else {
+            self.print_term(addr);
+        }
+    }
+
+    // prints the branches of a disjunction, each led by its
+    // connective in the column of the opening bracket.
+    fn print_disjunction(&mut self, addr: Addr, col: usize) {
+        let inner_col = col + PORTRAY_INDENT;
+
+        if let Some((left, right)) = self.binary_op(&addr, ";") {
+            if let Some((cond, then)) = self.binary_op(&left, "->") {
+                self.print_if_then(cond, "->  ", then, col);
+            } else if let Some((cond, then)) = self.binary_op(&left, "*->") {
+                self.print_if_then(cond, "*-> ", then, col);
+            } else {
+                self.print_body(left, inner_col);
+            }
+
+            self.newline_at(col);
+            self.output += ";   ";
+
+            if self.binary_op(&right, ";").is_some() || self.binary_op(&right, "->").is_some() {
+                self.print_disjunction(right, col);
+            } else {
+                self.print_body(right, inner_col);
+            }
+        } else if let Some((cond, then)) = self.binary_op(&addr, "->") {
+            self.print_if_then(cond, "->  ", then, col);
+        } else if let Some((cond, then)) = self.binary_op(&addr, "*->") {
+            self.print_if_then(cond, "*-> ", then, col);
+        } else {
+            self.print_body(addr, inner_col);
+        }
+    }
+
+    fn print_if_then(&mut self, cond: Addr, arrow: &str, then: Addr, col: usize) {
+        let inner_col = col + PORTRAY_INDENT;
+
+        self.print_body(cond, inner_col);
+        self.newline_at(col);
+        self.output += arrow;
+        self.print_body(then, inner_col);
+    }
+
+    pub fn portray(mut self, addr: Addr) -> String {
+        self.name_vars(addr.clone());
+
+        if let Some((head, body)) = self.binary_op(&addr, ":-") {
+            self.print_term(head);
+
+            if !self.is_atom(&body, "true") {
+                self.output += " :-";
+                self.newline_at(PORTRAY_INDENT);
+                self.print_body(body, PORTRAY_INDENT);
+            }
+        } else if let Some(directive) = self.unary_op(&addr, ":-") {
+            self.output += ":- ";
+            self.print_body(directive, 3);
+        } else {
+            self.print_term(addr);
+        }
+
+        self.output += ".\n";
+        self.output
+    }
+}
@@ -14,6 +14,19 @@ enum IntIndex {
     Internal(usize),
 }
 
+impl IntIndex {
+    // the location of the index in the code of the predicate, whose
+    // choice sequences begin at prelude_start and clauses at
+    // clauses_start.
+    fn location(self, prelude_start: usize, clauses_start: usize) -> Option<usize> {
+        match self {
+            IntIndex::External(o) => Some(clauses_start + o),
+            IntIndex::Internal(o) => Some(prelude_start + o),
+            IntIndex::Fail => None,
+        }
+    }
+}
+
 pub struct CodeOffsets {
     pub constants: IndexMap<Constant, ThirdLevelIndex>,
     pub lists: ThirdLevelIndex,
@@ -116,92 +129,40 @@ impl CodeOffsets {
         index_locs
     }
 
-    fn no_indices(&self) -> bool {
-        let no_constants = self.constants.is_empty();
-        let no_structures = self.structures.is_empty();
-        let no_lists = self.lists.is_empty();
-
-        no_constants && no_structures && no_lists
-    }
-
-    fn flatten_index<Index>(index: IndexMap<Index, IntIndex>, len: usize) -> IndexMap<Index, usize>
+    // the jumps of a switch instruction at from to the locations of
+    // its index.
+    fn jump_table<Index>(
+        index: IndexMap<Index, IntIndex>,
+        from: usize,
+        prelude_start: usize,
+        clauses_start: usize,
+    ) -> IndexMap<Index, usize>
     where
         Index: Eq + Hash,
     {
-        let mut flattened_index = IndexMap::new();
-
-        for (key, int_index) in index.into_iter() {
-            match int_index {
-                IntIndex::External(offset) => {
-                    flattened_index.insert(key, offset + len + 1);
-                }
-                IntIndex::Internal(offset) => {
-                    flattened_index.insert(key, offset + 1);
-                }
-                _ => {}
-            };
-        }
-
-        flattened_index
-    }
-
-    fn adjust_internal_index(index: IntIndex) -> IntIndex {
-        match index {
-            IntIndex::Internal(o) => IntIndex::Internal(o + 1),
-            IntIndex::External(o) => IntIndex::External(o),
-            _ => IntIndex::Fail,
-        }
-    }
-
-    fn switch_on_constant(
-        con_ind: IndexMap<Constant, ThirdLevelIndex>,
-        prelude: &mut CodeDeque,
-    ) -> IntIndex {
-        let con_ind = Self::second_level_index(con_ind, prelude);
-
-        if con_ind.len() > 1 {
-            let index = Self::flatten_index(con_ind, prelude.len());
-            let instr = IndexingInstruction::SwitchOnConstant(index.len(), index);
-
-            prelude.push_front(Line::from(instr));
-
-            IntIndex::Internal(1)
-        } else {
-            con_ind
-                .values()
-                .next()
-                .map(|index| Self::adjust_internal_index(*index))
-                .unwrap_or(IntIndex::Fail)
-        }
+        index.into_iter()
+            .filter_map(|(key, index)| {
+                index.location(prelude_start, clauses_start).map(|loc| (key, loc - from))
+            })
+            .collect()
     }
 
-    fn switch_on_structure(
-        str_ind: IndexMap<(ClauseName, usize), ThirdLevelIndex>,
-        prelude: &mut CodeDeque,
-    ) -> IntIndex {
-        let str_ind = Self::second_level_index(str_ind, prelude);
-
-        if str_ind.len() > 1 {
-            let index = Self::flatten_index(str_ind, prelude.len());
-            let instr = IndexingInstruction::SwitchOnStructure(index.len(), index);
-
-            prelude.push_front(Line::from(instr));
+    fn no_indices(&self) -> bool {
+        let no_constants = self.constants.is_empty();
+        let no_structures = self.structures.is_empty();
+        let no_lists = self.lists.is_empty();
 
-            IntIndex::Internal(1)
-        } else {
-            str_ind
-                .values()
-                .next()
-                .map(|index| Self::adjust_internal_index(*index))
-                .unwrap_or(IntIndex::Fail)
-        }
+        no_constants && no_structures && no_lists
     }
 
     fn switch_on_list(mut lists: ThirdLevelIndex, prelude: &mut CodeDeque) -> IntIndex {
         if lists.len() > 1 {
+            let index = IntIndex::Internal(prelude.len());
+
             Self::cap_choice_seq_with_trust(&mut lists);
             prelude.extend(lists.into_iter().map(|i| Line::from(i)));
-            IntIndex::Internal(0)
+
+            index
         } else {
             lists
                 .first()
@@ -210,41 +171,17 @@ impl CodeOffsets {
         }
     }
 
-    fn switch_on_str_offset_from(
-        str_loc: IntIndex,
-        prelude_len: usize,
-        con_loc: IntIndex,
-    ) -> usize {
-        match str_loc {
-            IntIndex::External(o) => o + prelude_len + 1,
-            IntIndex::Fail => 0,
-            IntIndex::Internal(_) => match con_loc {
-                IntIndex::Internal(_) => 2,
-                _ => 1,
-            },
-        }
-    }
-
-    fn switch_on_con_offset_from(con_loc: IntIndex, prelude_len: usize) -> usize {
-        match con_loc {
-            IntIndex::External(offset) => offset + prelude_len + 1,
-            IntIndex::Fail => 0,
-            IntIndex::Internal(offset) => offset,
-        }
-    }
-
-    fn switch_on_lst_offset_from(
-        lst_loc: IntIndex,
-        prelude_len: usize,
-        lst_offset: usize,
-    ) -> usize {
-        match lst_loc {
-            IntIndex::External(o) => o + prelude_len + 1,
-            IntIndex::Fail => 0,
-            IntIndex::Internal(_) => prelude_len - lst_offset + 1,
-        }
-    }
-
+    /* The indexing code of a predicate is laid out as
+     *
+     *   switch_on_term, [switch_on_constant], [switch_on_structure],
+     *   choice sequences of lists, structures and constants,
+     *   clauses
+     *
+     * and its jumps are offsets from the instruction making them.
+     * Internal indices are locations in the choice sequences and
+     * External indices offsets into the clauses, so both are turned
+     * into jumps only once the layout is known.
+     */
     pub fn add_indices(self, code: &mut Code, mut code_body: Code) {
         if self.no_indices() {
             *code = code_body;
@@ -254,30 +191,57 @@ impl CodeOffsets {
         let mut prelude = VecDeque::new();
 
         let lst_loc = Self::switch_on_list(self.lists, &mut prelude);
-        let lst_offset = prelude.len();
+        let str_ind = Self::second_level_index(self.structures, &mut prelude);
+        let con_ind = Self::second_level_index(self.constants, &mut prelude);
 
-        let str_loc = Self::switch_on_structure(self.structures, &mut prelude);
-        let con_loc = Self::switch_on_constant(self.constants, &mut prelude);
+        let has_con_switch = con_ind.len() > 1;
+        let has_str_switch = str_ind.len() > 1;
 
-        let prelude_length = prelude.len();
+        let con_switch_loc = 1;
+        let str_switch_loc = 1 + has_con_switch as usize;
+
+        let prelude_start = 1 + has_con_switch as usize + has_str_switch as usize;
+        let clauses_start = prelude_start + prelude.len();
+
+        let jump_from_switch = |index: Option<&IntIndex>| {
+            index.and_then(|index| index.location(prelude_start, clauses_start)).unwrap_or(0)
+        };
 
         for (index, line) in prelude.iter_mut().enumerate() {
             match line {
                 &mut Line::IndexedChoice(IndexedChoiceInstruction::Try(ref mut i))
               | &mut Line::IndexedChoice(IndexedChoiceInstruction::Retry(ref mut i))
               | &mut Line::IndexedChoice(IndexedChoiceInstruction::Trust(ref mut i)) => {
-                    *i += prelude_length - index
+                    *i += clauses_start - (prelude_start + index)
                 }
                 _ => {}
             }
         }
 
-        let str_loc = Self::switch_on_str_offset_from(str_loc, prelude.len(), con_loc);
-        let con_loc = Self::switch_on_con_offset_from(con_loc, prelude.len());
-        let lst_loc = Self::switch_on_lst_offset_from(lst_loc, prelude.len(), lst_offset);
+        let str_loc = if has_str_switch {
+            let index = Self::jump_table(str_ind, str_switch_loc, prelude_start, clauses_start);
+            let instr = IndexingInstruction::SwitchOnStructure(index.len(), index);
+
+            prelude.push_front(Line::from(instr));
+            str_switch_loc
+        } else {
+            jump_from_switch(str_ind.values().next())
+        };
+
+        let con_loc = if has_con_switch {
+            let index = Self::jump_table(con_ind, con_switch_loc, prelude_start, clauses_start);
+            let instr = IndexingInstruction::SwitchOnConstant(index.len(), index);
+
+            prelude.push_front(Line::from(instr));
+            con_switch_loc
+        } else {
+            jump_from_switch(con_ind.values().next())
+        };
+
+        let lst_loc = jump_from_switch(Some(&lst_loc));
 
         let switch_instr =
-            IndexingInstruction::SwitchOnTerm(prelude.len() + 1, con_loc, lst_loc, str_loc);
+            IndexingInstruction::SwitchOnTerm(clauses_start, con_loc, lst_loc, str_loc);
 
         prelude.push_front(Line::from(switch_instr));
 
@@ -5,7 +5,7 @@ use crate::prolog::forms::*;
 use crate::prolog::machine::machine_errors::MachineStub;
 use crate::prolog::machine::machine_indices::*;
 
-use crate::prolog::rug::Integer;
+use crate::prolog::bignum::Integer;
 
 use indexmap::IndexMap;
 
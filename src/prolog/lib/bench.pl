@@ -0,0 +1,42 @@
+%% timing goals. load at the REPL with
+
+%% ?- use_module(library(bench)).
+
+%% bench(Goal, N) calls Goal once, N times over, discarding its
+%% bindings each time, and prints the processor time taken in all and
+%% per call. it fails if any of the calls fails. bench(Goal) is
+%% bench(Goal, 1).
+
+%% goals defined in modules other than user should be qualified.
+
+:- module(bench, [bench/1, bench/2]).
+
+:- use_module(library(between)).
+:- use_module(library(error)).
+:- use_module(library(format)).
+
+bench(Goal) :-
+    bench(Goal, 1).
+
+bench(Goal, N) :-
+    must_be(callable, Goal),
+    must_be(integer, N),
+    (  N < 1 ->
+       throw(error(domain_error(positive_integer, N), bench/2))
+    ;  true
+    ),
+    statistics(cputime, T0),
+    calls(N, Goal),
+    statistics(cputime, T),
+    Time is T - T0,
+    PerCall is Time / N,
+    write('% '),
+    writeq(Goal),
+    format(": ~d calls in ~3f s, ~6f s per call~n", [N, Time, PerCall]).
+
+calls(N, Goal) :-
+    (  between(1, N, _),
+       \+ once(Goal) ->
+       false
+    ;  true
+    ).
@@ -31,30 +31,51 @@ user:term_expansion((:- op(Pred, Spec, [Op | OtherOps])), OpResults) :-
 :- op(700, xfx, [==, \==, @=<, @>=, @<, @>]).
 
 % conditional operators.
-:- op(1050, xfy, ->).
+:- op(1050, xfy, [->, *->]).
 :- op(1100, xfy, ;).
 
 % control.
-:- op(700, xfx, [=, =.., \=]).
+:- op(700, xfx, [=, =.., \=, ?=]).
 :- op(900, fy, \+).
 
-:- module(builtins, [(=)/2, (\=)/2, (\+)/1, (',')/2, (->)/2, (;)/2,
-                     (=..)/2, (:)/2, (:)/3, (:)/4, (:)/5, (:)/6,
+% grammar rules.
+:- op(1200, xfx, -->).
+:- op(1105, xfy, '|').
+
+:- module(builtins, [(=)/2, (\=)/2, (\+)/1, (',')/2, (->)/2, (*->)/2, (;)/2,
+                     (=..)/2, (?=)/2, (:)/2, (:)/3, (:)/4, (:)/5, (:)/6,
                      (:)/7, (:)/8, (:)/9, (:)/10, (:)/11, (:)/12,
-                     abolish/1, asserta/1, assertz/1, atom_chars/2,
+                     abolish/1, abort/0, asserta/1, assertz/1,
+                     at_end_of_stream/0, at_end_of_stream/1, atom_chars/2,
                      atom_codes/2, atom_concat/3, atom_length/2,
-                     bagof/3, catch/3, char_code/2, clause/2,
+                     bagof/3, callable/1, catch/3, char_code/2, clause/2, close/1,
                      current_input/1, current_output/1, current_op/3,
                      current_predicate/1, current_prolog_flag/2,
-                     expand_goal/2, expand_term/2, fail/0, false/0,
-                     findall/3, findall/4, get_char/1, halt/0,
-                     max_arity/1, number_chars/2, number_codes/2,
-                     once/1, op/3, read_term/2, repeat/0, retract/1,
+                     deterministic/1, expand_goal/2, expand_term/2, fail/0, false/0,
+                     fast_read/2, fast_write/2, fast_write/3, findall/3,
+                     findall/4, garbage_collect_clauses/0, get_byte/1,
+                     get_byte/2, get_char/1, halt/0, if/3,
+                     is_list/1, load_atom_table/1,
+                     max_arity/1, message_to_codes/3, nl/1, not/1,
+                     number_chars/2, number_chars/3, number_codes/2,
+                     number_codes/3, number_string/2, atom_number/2,
+                     once/1, op/3, open/3, open/4, peek_byte/1, peek_byte/2,
+                     phrase/2, phrase/3,
+                     peek_char/1, peek_char/2, peek_code/1, peek_code/2,
+                     print/1, print/2, print_message/2, print_term/2,
+                     propagation_priority/2,
+                     put_byte/1, put_byte/2,
+                     prolog_load_context/2, read/2, read_term/2, read_term/3,
+                     repeat/0, retract/1,
+                     save_atom_table/1,
                      set_prolog_flag/2, set_input/1, set_output/1,
-                     setof/3, sub_atom/5, subsumes_term/2,
-                     term_variables/2, throw/1, true/0,
-                     unify_with_occurs_check/2, write/1,
-                     write_canonical/1, write_term/2, writeq/1]).
+                     set_stream/2, setof/3, statistics/2, stream_property/2,
+                     sub_atom/5,
+                     subsumes_term/2, tab/1, tab/2, term_variables/2,
+                     throw/1, true/0,
+                     unifiable/3, unify_with_occurs_check/2, write/1,
+                     write/2, write_canonical/1, write_canonical/2,
+                     write_term/2, write_term/3, writeq/1, writeq/2]).
 
 
 % the maximum arity flag. needs to be replaced with
@@ -132,8 +153,44 @@ current_prolog_flag(Flag, false) :- Flag == bounded, !.
 current_prolog_flag(bounded, false).
 current_prolog_flag(Flag, toward_zero) :- Flag == integer_rounding_function, !.
 current_prolog_flag(integer_rounding_function, toward_zero).
+current_prolog_flag(Flag, Value) :- Flag == autoload, !, '$get_autoload'(Value).
+current_prolog_flag(autoload, Value) :- '$get_autoload'(Value).
+current_prolog_flag(Flag, Value) :- Flag == block_strings, !, '$get_block_strings'(Value).
+current_prolog_flag(block_strings, Value) :- '$get_block_strings'(Value).
 current_prolog_flag(Flag, Value) :- Flag == double_quotes, !, '$get_double_quotes'(Value).
 current_prolog_flag(double_quotes, Value) :- '$get_double_quotes'(Value).
+current_prolog_flag(Flag, Value) :- Flag == quasi_quotations, !, '$get_quasi_quotations'(Value).
+current_prolog_flag(quasi_quotations, Value) :- '$get_quasi_quotations'(Value).
+current_prolog_flag(Flag, Value) :- Flag == reproducible, !, '$get_reproducible'(Value).
+current_prolog_flag(reproducible, Value) :- '$get_reproducible'(Value).
+current_prolog_flag(Flag, Value) :- Flag == unicode_identifiers, !, '$get_unicode_identifiers'(Value).
+current_prolog_flag(unicode_identifiers, Value) :- '$get_unicode_identifiers'(Value).
+current_prolog_flag(Flag, Value) :- Flag == toplevel_command_prefix, !, toplevel_command_prefix(Value).
+current_prolog_flag(toplevel_command_prefix, Value) :- toplevel_command_prefix(Value).
+current_prolog_flag(Flag, Value) :- Flag == toplevel_prompt, !, '$toplevel_prompt'(query, Value).
+current_prolog_flag(toplevel_prompt, Value) :- '$toplevel_prompt'(query, Value).
+current_prolog_flag(Flag, Value) :-
+    Flag == toplevel_continuation_prompt, !, '$toplevel_prompt'(continuation, Value).
+current_prolog_flag(toplevel_continuation_prompt, Value) :-
+    '$toplevel_prompt'(continuation, Value).
+current_prolog_flag(Flag, Value) :- Flag == toplevel_banner, !, toplevel_banner(Value).
+current_prolog_flag(toplevel_banner, Value) :- toplevel_banner(Value).
+current_prolog_flag(Flag, Value) :-
+    Flag == toplevel_max_bindings_shown, !, '$toplevel_answer_flag'(max_bindings_shown, Value).
+current_prolog_flag(toplevel_max_bindings_shown, Value) :-
+    '$toplevel_answer_flag'(max_bindings_shown, Value).
+current_prolog_flag(Flag, Value) :-
+    Flag == toplevel_elide_self_bindings, !, '$toplevel_answer_flag'(elide_self_bindings, Value).
+current_prolog_flag(toplevel_elide_self_bindings, Value) :-
+    '$toplevel_answer_flag'(elide_self_bindings, Value).
+current_prolog_flag(Flag, Value) :-
+    Flag == toplevel_max_atom_length, !, '$toplevel_answer_flag'(max_atom_length, Value).
+current_prolog_flag(toplevel_max_atom_length, Value) :-
+    '$toplevel_answer_flag'(max_atom_length, Value).
+current_prolog_flag(Flag, Value) :-
+    Flag == toplevel_print_query_stats, !, '$toplevel_answer_flag'(print_query_stats, Value).
+current_prolog_flag(toplevel_print_query_stats, Value) :-
+    '$toplevel_answer_flag'(print_query_stats, Value).
 current_prolog_flag(Flag, _) :- Flag == max_integer, !, '$fail'.
 current_prolog_flag(Flag, _) :- Flag == min_integer, !, '$fail'.
 current_prolog_flag(Flag, _) :-
@@ -160,6 +217,20 @@ set_prolog_flag(integer_rounding_function, down) :- !. % 7.11.1.4
 set_prolog_flag(integer_rounding_function, Value) :-
     throw(error(domain_error(flag_value, integer_rounding_function + Value),
 		set_prolog_flag/2)). % 8.17.1.3 e
+set_prolog_flag(autoload, true) :-
+    !, '$set_autoload'(true).
+set_prolog_flag(autoload, false) :-
+    !, '$set_autoload'(false).
+set_prolog_flag(autoload, Value) :-
+    throw(error(domain_error(flag_value, autoload + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(block_strings, true) :-
+    !, '$set_block_strings'(true).
+set_prolog_flag(block_strings, false) :-
+    !, '$set_block_strings'(false).
+set_prolog_flag(block_strings, Value) :-
+    throw(error(domain_error(flag_value, block_strings + Value),
+		set_prolog_flag/2)).
 set_prolog_flag(double_quotes, chars) :-
     !, '$set_double_quotes'(chars). % 7.11.2.5, list of one-char atoms.
 set_prolog_flag(double_quotes, atom) :-
@@ -169,18 +240,164 @@ set_prolog_flag(double_quotes, codes) :-
 set_prolog_flag(double_quotes, Value) :-
     throw(error(domain_error(flag_value, double_quotes + Value),
 		set_prolog_flag/2)). % 8.17.1.3 e
+set_prolog_flag(quasi_quotations, true) :-
+    !, '$set_quasi_quotations'(true).
+set_prolog_flag(quasi_quotations, false) :-
+    !, '$set_quasi_quotations'(false).
+set_prolog_flag(quasi_quotations, Value) :-
+    throw(error(domain_error(flag_value, quasi_quotations + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(reproducible, true) :-
+    !, '$set_reproducible'(true). % reseeds the random number generator.
+set_prolog_flag(reproducible, false) :-
+    !, '$set_reproducible'(false).
+set_prolog_flag(reproducible, Value) :-
+    throw(error(domain_error(flag_value, reproducible + Value),
+		set_prolog_flag/2)).
+% the reader doesn't accept Unicode identifiers yet, so writeq/1 goes
+% on quoting atoms with non-ASCII letters whatever the flag says.
+set_prolog_flag(unicode_identifiers, true) :-
+    !, '$set_unicode_identifiers'(true).
+set_prolog_flag(unicode_identifiers, false) :-
+    !, '$set_unicode_identifiers'(false).
+set_prolog_flag(unicode_identifiers, Value) :-
+    throw(error(domain_error(flag_value, unicode_identifiers + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_command_prefix, Value) :-
+    atom(Value), !, '$store_global_var'('$toplevel_command_prefix', Value).
+set_prolog_flag(toplevel_command_prefix, Value) :-
+    throw(error(domain_error(flag_value, toplevel_command_prefix + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_prompt, Value) :-
+    atom(Value), !, '$set_toplevel_prompt'(query, Value).
+set_prolog_flag(toplevel_prompt, Value) :-
+    throw(error(domain_error(flag_value, toplevel_prompt + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_continuation_prompt, Value) :-
+    atom(Value), !, '$set_toplevel_prompt'(continuation, Value).
+set_prolog_flag(toplevel_continuation_prompt, Value) :-
+    throw(error(domain_error(flag_value, toplevel_continuation_prompt + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_banner, Value) :-
+    atom(Value), !, '$store_global_var'('$toplevel_banner', Value).
+set_prolog_flag(toplevel_banner, Value) :-
+    throw(error(domain_error(flag_value, toplevel_banner + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_max_bindings_shown, Value) :-
+    (  Value == infinite ; integer(Value), Value >= 0 ), !,
+    '$store_global_var'('$toplevel_max_bindings_shown', Value).
+set_prolog_flag(toplevel_max_bindings_shown, Value) :-
+    throw(error(domain_error(flag_value, toplevel_max_bindings_shown + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_elide_self_bindings, Value) :-
+    (  Value == true ; Value == false ), !,
+    '$store_global_var'('$toplevel_elide_self_bindings', Value).
+set_prolog_flag(toplevel_elide_self_bindings, Value) :-
+    throw(error(domain_error(flag_value, toplevel_elide_self_bindings + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_max_atom_length, Value) :-
+    (  Value == infinite ; integer(Value), Value > 0 ), !,
+    '$store_global_var'('$toplevel_max_atom_length', Value).
+set_prolog_flag(toplevel_max_atom_length, Value) :-
+    throw(error(domain_error(flag_value, toplevel_max_atom_length + Value),
+		set_prolog_flag/2)).
+set_prolog_flag(toplevel_print_query_stats, Value) :-
+    (  Value == true ; Value == false ), !,
+    '$store_global_var'('$toplevel_print_query_stats', Value).
+set_prolog_flag(toplevel_print_query_stats, Value) :-
+    throw(error(domain_error(flag_value, toplevel_print_query_stats + Value),
+		set_prolog_flag/2)).
 set_prolog_flag(Flag, _) :-
     atom(Flag),
     throw(error(domain_error(prolog_flag, Flag), set_prolog_flag/2)). % 8.17.1.3 d
 set_prolog_flag(Flag, _) :-
     throw(error(type_error(atom, Flag), set_prolog_flag/2)). % 8.17.1.3 c
 
+% lines of toplevel input starting with the prefix are read as
+% commands, such as :help, rather than as queries. the empty atom
+% turns commands off.
+toplevel_command_prefix(Prefix) :-
+    (  '$fetch_global_var'('$toplevel_command_prefix', Prefix0) ->
+       Prefix = Prefix0
+    ;  Prefix = (:)
+    ).
+
+% the goals posted by verify_attributes/3 hooks are called in order
+% of increasing priority, so that cheap propagators can run before
+% expensive ones. Key is Module:Name/Arity, giving the priority of
+% goals calling the predicate, or a Module, giving that of the goals
+% posted by its hook. the default priority is 0.
+propagation_priority(Key, Priority) :-
+    (  var(Key) ->
+       throw(error(instantiation_error, propagation_priority/2))
+    ;  var(Priority) ->
+       throw(error(instantiation_error, propagation_priority/2))
+    ;  \+ integer(Priority) ->
+       throw(error(type_error(integer, Priority), propagation_priority/2))
+    ;  \+ propagation_priority_key(Key) ->
+       throw(error(domain_error(propagation_priority_key, Key), propagation_priority/2))
+    ;  (  '$fetch_global_var'('$propagation_priorities', Priorities0) -> true
+       ;  Priorities0 = []
+       ),
+       remove_propagation_priority(Priorities0, Key, Priorities),
+       '$store_global_var'('$propagation_priorities', [Key-Priority | Priorities])
+    ).
+
+propagation_priority_key(Key) :-
+    (  atom(Key) -> true
+    ;  Key = Module:Name/Arity,
+       atom(Module),
+       atom(Name),
+       integer(Arity)
+    ).
+
+remove_propagation_priority([], _, []).
+remove_propagation_priority([Key0-P | Priorities0], Key, Priorities) :-
+    (  Key0 == Key -> Priorities = Priorities0
+    ;  Priorities = [Key0-P | Priorities1],
+       remove_propagation_priority(Priorities0, Key, Priorities1)
+    ).
+
+% the banner printed by the toplevel once the files it was started
+% with are loaded. the empty atom, the default, prints none.
+toplevel_banner(Banner) :-
+    (  '$fetch_global_var'('$toplevel_banner', Banner0) ->
+       Banner = Banner0
+    ;  Banner = ''
+    ).
+
+% the flags read by the toplevel as it writes an answer:
+% max_bindings_shown bindings are written before the rest are elided
+% by an ellipsis, bindings of a variable to itself, ie. of a variable
+% left unbound by the query, are omitted if elide_self_bindings is
+% true, and atoms longer than max_atom_length are cut short, ending
+% in an ellipsis. w at the prompt writes the answer in full. if
+% print_query_stats is true, the memory used by each query is
+% reported once its answers are done with.
+'$toplevel_answer_flag'(Flag, Value) :-
+    atom_concat('$toplevel_', Flag, Key),
+    (  '$fetch_global_var'(Key, Value0) ->
+       Value = Value0
+    ;  '$toplevel_answer_flag_default'(Flag, Value)
+    ).
+
+'$toplevel_answer_flag_default'(max_bindings_shown, infinite).
+'$toplevel_answer_flag_default'(elide_self_bindings, true).
+'$toplevel_answer_flag_default'(max_atom_length, infinite).
+'$toplevel_answer_flag_default'(print_query_stats, false).
+
 % control operators.
 
 fail :- '$fail'.
 
-\+ G :- call(G), !, false.
-\+ _.
+\+ G :- '$get_b_value'(B), '$call_with_default_policy'(not_worker(G, B, (\+)/1)).
+
+not(G) :- '$get_b_value'(B), '$call_with_default_policy'(not_worker(G, B, not/1)).
+
+:- non_counted_backtracking not_worker/3.
+not_worker(G, _, PI) :- var(G), throw(error(instantiation_error, PI)).
+not_worker(G, B, _) :- nonvar(G), call(G), '$set_cp'(B), false.
+not_worker(_, _, _).
 
 X \= X :- !, false.
 _ \= _.
@@ -221,6 +438,10 @@ comma_errors(G1, G2, B) :- '$call_with_default_policy'(','(G1, G2, B)).
 		;  '$set_cp'(B),
 		   call(G4)
 		).
+;(G1, G4, _) :- compound(G1),
+		'$call_with_default_policy'(G1 = *->(G2, G3)),
+		!,
+		if(G2, G3, G4).
 ;(G1, G2, B) :- G1 == !, '$set_cp'(B), call(G2).
 ;(G1, G2, B) :- G2 == !, call(G1), '$set_cp'(B).
 ;(G, _, _) :- call(G).
@@ -232,6 +453,29 @@ G1 -> G2 :- '$get_b_value'(B), '$call_with_default_policy'(->(G1, G2, B)).
 ->(G1, G2, B) :- G2 == !, call(G1), '$set_cp'(B).
 ->(G1, G2, B) :- call(G1), '$set_cp'(B), call(G2).
 
+G1 *-> G2 :- call(G1), call(G2).
+
+% if/3 keeps the choice points of If for Then, and only runs Else if
+% If has no solutions. '$soft_cut'/1 disables the disjunction's
+% alternative once If succeeds, by pointing it at the trust_me of
+% '$soft_cut_alternative'/0.
+:- non_counted_backtracking if/3.
+if(If, Then, Else) :-
+    '$get_b_value'(B0),
+    (  '$get_b_value'(B),
+       call(If),
+       '$soft_cut'(B),
+       (  Then == ! -> '$set_cp'(B0)
+       ;  call(Then)
+       )
+    ;  Else == ! -> '$set_cp'(B0)
+    ;  call(Else)
+    ).
+
+:- non_counted_backtracking '$soft_cut_alternative'/0.
+'$soft_cut_alternative' :- '$fail'.
+'$soft_cut_alternative' :- '$fail'.
+
 % univ.
 
 :- non_counted_backtracking univ_errors/3.
@@ -285,83 +529,466 @@ get_args([Arg|Args], Func, I0, N) :-
     '$call_with_default_policy'(get_args(Args, Func, I1, N)).
 
 % write, write_canonical, writeq, write_term.
-is_write_option(Functor) :-
-    Functor =.. [Name, Arg],
-    ( Arg == true -> true
-    ; Arg == false -> true
-    ; Name == variable_names -> must_be_var_names_list(Arg)
-    ; Name == max_depth -> integer(Arg), Arg >= 0
-    ; var(Arg) -> throw(error(instantiation_error, write_term/2))
-    ; throw(error(domain_error(write_option, Functor), write_term/2))
+
+% the options of 8.14.2.1, ignore_ops/1, quoted/1, numbervars/1 and
+% variable_names/1, and max_depth(N), which writes the terms nested
+% deeper than N as ..., and the elements of lists past the Nth as
+% ...(K more)..., or everything if N is 0. all of them are checked
+% before anything is written, and the first of several occurrences of
+% an option is the one that counts.
+is_write_option(Option, PI) :-
+    Option =.. [Name, Arg],
+    (  Name == variable_names -> must_be_var_names_list(Arg, Option, PI)
+    ;  var(Arg) -> throw(error(instantiation_error, PI))
+    ;  Name == max_depth -> integer(Arg), Arg >= 0
+    ;  ( Name == ignore_ops ; Name == quoted ; Name == numbervars ) ->
+       ( Arg == true ; Arg == false )
     ), % 8.14.2.3 e)
-    ( Name == ignore_ops -> true
-    ; Name == quoted -> true
-    ; Name == numbervars -> true
-    ; Name == variable_names -> true
-    ; Name == max_depth -> true
-    ; throw(error(domain_error(write_option, Functor), write_term/2))
-    ). % 8.14.2.3 e)
-
-inst_member_or([X|Xs], Y, Z) :-
-    (  var(X) -> throw(error(instantiation_error, write_term/2))
-    ;  is_write_option(X) -> ( Y = X, ! ; inst_member_or(Xs, Y, Z) )
-    ;  throw(error(domain_error(write_option, X), write_term/2))
-    ).
-inst_member_or([], Y, Y).
-
-must_be_var_names_list(VarNames) :-
+    !.
+is_write_option(Option, PI) :-
+    throw(error(domain_error(write_option, Option), PI)). % 8.14.2.3 e)
+
+must_be_write_options([], _).
+must_be_write_options([Option | Options], PI) :-
+    (  var(Option) -> throw(error(instantiation_error, PI))
+    ;  is_write_option(Option, PI)
+    ),
+    must_be_write_options(Options, PI).
+
+write_option_or([X|Xs], Y, Z) :-
+    (  Y = X -> true
+    ;  write_option_or(Xs, Y, Z)
+    ).
+write_option_or([], Y, Y).
+
+% the pairs Name = Var of variable_names/1. Names are atoms, and a
+% variable named twice is written by the first of its names.
+must_be_var_names_list(VarNames, Option, PI) :-
     '$skip_max_list'(_, -1, VarNames, Tail),
-    (  Tail == [] -> must_be_var_names_list_(VarNames, VarNames)
-    ;  var(Tail)  -> throw(error(instantiation_error, write_term/2))
-    ;  throw(error(domain_error(write_options, variable_names(VarNames)), write_term/2))
-    ).
-
-must_be_var_names_list_([], List).
-must_be_var_names_list_([VarName | VarNames], List) :-
-    (  nonvar(VarName) ->
-       (  VarName = (Atom = _) ->
-	      (  atom(Atom) -> must_be_var_names_list_(VarNames, List)
-	      ;  var(Atom)  -> throw(error(instantiation_error, write_term/2))
-	      ;  throw(error(domain_error(write_options, variable_names(List)), write_term/2))
-	      )
-       ;  throw(error(domain_error(write_options, variable_names(List)), write_term/2))
+    (  Tail == [] -> must_be_var_names_list_(VarNames, Option, PI)
+    ;  var(Tail)  -> throw(error(instantiation_error, PI))
+    ;  throw(error(domain_error(write_option, Option), PI))
+    ).
+
+must_be_var_names_list_([], _, _).
+must_be_var_names_list_([VarName | VarNames], Option, PI) :-
+    (  var(VarName) ->
+       throw(error(instantiation_error, PI))
+    ;  VarName = (Atom = _) ->
+       (  atom(Atom) -> must_be_var_names_list_(VarNames, Option, PI)
+       ;  var(Atom)  -> throw(error(instantiation_error, PI))
+       ;  throw(error(domain_error(write_option, Option), PI))
        )
-    ;  throw(error(instantiation_error, write_term/2)) % throw(error(domain_error(write_options, variable_names(List)), write_term/2))
+    ;  throw(error(domain_error(write_option, Option), PI))
     ).
 
-write_term(_, Options) :-
-    var(Options), throw(error(instantiation_error, write_term/2)).
 write_term(Term, Options) :-
+    current_output(Stream),
+    write_term_(Stream, Term, Options, write_term/2).
+
+write_term(Stream, Term, Options) :-
+    write_term_(Stream, Term, Options, write_term/3).
+
+write_term_(Stream, Term, Options, PI) :-
+    must_be_option_list(Options, PI),
+    must_be_write_options(Options, PI),
+    write_option_values(Options, IgnoreOps, NumberVars, Quoted, VarNames, MaxDepth),
+    '$write_term'(Term, IgnoreOps, NumberVars, Quoted, VarNames, MaxDepth, Stream).
+
+must_be_option_list(Options, PI) :-
     '$skip_max_list'(_, -1, Options, Options0),
-    (  var(Options0)  -> throw(error(instantiation_error, write_term/2))
+    (  var(Options0)  -> throw(error(instantiation_error, PI))
     ;  Options0 == [] -> true
-    ;  throw(error(type_error(list, Options), write_term/2))
-    ), % 8.14.2.3 c)
-    inst_member_or(Options, ignore_ops(IgnoreOps), ignore_ops(false)),
-    inst_member_or(Options, numbervars(NumberVars), numbervars(false)),
-    inst_member_or(Options, quoted(Quoted), quoted(false)),
-    inst_member_or(Options, variable_names(VarNames), variable_names([])),
-    inst_member_or(Options, max_depth(MaxDepth), max_depth(0)),
-    '$write_term'(Term, IgnoreOps, NumberVars, Quoted, VarNames, MaxDepth).
+    ;  throw(error(type_error(list, Options), PI))
+    ). % 8.14.2.3 c)
+
+write_option_values(Options, IgnoreOps, NumberVars, Quoted, VarNames, MaxDepth) :-
+    write_option_or(Options, ignore_ops(IgnoreOps), ignore_ops(false)),
+    write_option_or(Options, numbervars(NumberVars), numbervars(false)),
+    write_option_or(Options, quoted(Quoted), quoted(false)),
+    % variables not named by variable_names/1, if it's given, are
+    % written as _G0, _G1, ... in the order they're first written.
+    write_option_or(Options, variable_names(VarNames), variable_names(_)),
+    write_option_or(Options, max_depth(MaxDepth), max_depth(0)).
+
+% print_term(+Term, +Options) writes Term to the current output as
+% write_term/2 does, but over several lines where it would otherwise
+% run past the right margin. a compound term, list or bracketed term
+% that doesn't fit on the rest of its line has its arguments or
+% elements written one to a line, aligned with the first of them.
+% besides the options of write_term/2, of which quoted(true) and
+% numbervars(true) are the defaults, Options may hold
+% right_margin(Column), 72 by default, and indent_arguments(N),
+% which, if N is positive, begins the arguments on a line of their
+% own, N columns in from the line the term begins on.
+print_term(Term, Options) :-
+    must_be_option_list(Options, print_term/2),
+    print_term_options(Options, WriteOptions, [quoted(true), numbervars(true)]),
+    must_be_write_options(WriteOptions, print_term/2),
+    write_option_or(Options, right_margin(RightMargin), right_margin(72)),
+    write_option_or(Options, indent_arguments(Indent), indent_arguments(0)),
+    write_option_values(WriteOptions, IgnoreOps, NumberVars, Quoted, VarNames, MaxDepth),
+    '$print_term'(Term, IgnoreOps, NumberVars, Quoted, VarNames, MaxDepth,
+                  RightMargin, Indent).
+
+% the options of write_term/2 among Options, followed by Defaults.
+print_term_options([], Defaults, Defaults).
+print_term_options([Option | Options], WriteOptions, Defaults) :-
+    (  var(Option) ->
+       throw(error(instantiation_error, print_term/2))
+    ;  ( Option = right_margin(N) ; Option = indent_arguments(N) ) ->
+       (  var(N) -> throw(error(instantiation_error, print_term/2))
+       ;  integer(N), N >= 0 -> WriteOptions = WriteOptions0
+       ;  throw(error(domain_error(print_option, Option), print_term/2))
+       )
+    ;  WriteOptions = [Option | WriteOptions0]
+    ),
+    print_term_options(Options, WriteOptions0, Defaults).
 
 write(Term) :- write_term(Term, [numbervars(true)]).
 
+write(Stream, Term) :- write_term(Stream, Term, [numbervars(true)]).
+
 write_canonical(Term) :- write_term(Term, [ignore_ops(true), quoted(true)]).
 
+write_canonical(Stream, Term) :-
+    write_term(Stream, Term, [ignore_ops(true), quoted(true)]).
+
 writeq(Term) :- write_term(Term, [quoted(true), numbervars(true)]).
 
-%% TODO: complete the predicate! Most read options are missing.
+writeq(Stream, Term) :-
+    write_term(Stream, Term, [quoted(true), numbervars(true)]).
+
+% as writeq, there being no portray/1 hook for print to call.
+print(Term) :- write_term(Term, [quoted(true), numbervars(true)]).
+
+print(Stream, Term) :-
+    write_term(Stream, Term, [quoted(true), numbervars(true)]).
+
+nl(Stream) :- put_text(Stream, '\n', nl/1).
+
+tab(N) :-
+    current_output(Stream),
+    tab_(Stream, N, tab/1).
+
+tab(Stream, N) :- tab_(Stream, N, tab/2).
+
+% writes the value of the expression N in spaces.
+tab_(Stream, N, PI) :-
+    catch(N0 is N, error(E, _), throw(error(E, PI))),
+    (  integer(N0) -> spaces(N0, Spaces)
+    ;  throw(error(type_error(integer, N0), PI))
+    ),
+    atom_chars(Atom, Spaces),
+    put_text(Stream, Atom, PI).
+
+spaces(N, Spaces) :-
+    (  N > 0 ->
+       Spaces = [' ' | Spaces0],
+       N1 is N - 1,
+       spaces(N1, Spaces0)
+    ;  Spaces = []
+    ).
+
+% writes Atom to Stream as it is, reporting errors against PI.
+put_text(Stream, Atom, PI) :-
+    catch('$write_term'(Atom, false, false, false, _, 0, Stream),
+          error(E, _),
+          throw(error(E, PI))).
+
+% messages.
+
+% print_message(+Kind, +Message) writes the text of Message, as
+% rendered by message_to_codes/3, to the current output, unless
+% user:message_hook(Message, Kind, Codes) is defined and succeeds.
+% silent messages are only offered to the hook.
+print_message(Kind, Message) :-
+    must_be_message_kind(Kind, print_message/2),
+    message_to_codes(Message, Kind, Codes),
+    (  once(current_predicate(message_hook/3)),
+       user:message_hook(Message, Kind, Codes) ->
+       true
+    ;  Kind == silent ->
+       true
+    ;  message_prefix(Kind, Prefix),
+       atom_codes(Text, Codes),
+       write(Prefix),
+       write(Text),
+       nl
+    ).
+
+message_prefix(error, '').
+message_prefix(warning, 'Warning: ').
+message_prefix(informational, '% ').
+
+message_kind(error).
+message_kind(warning).
+message_kind(informational).
+message_kind(silent).
+
+must_be_message_kind(Kind, PI) :-
+    (  var(Kind) -> throw(error(instantiation_error, PI))
+    ;  message_kind(Kind) -> true
+    ;  atom(Kind) -> throw(error(domain_error(message_kind, Kind), PI))
+    ;  throw(error(type_error(atom, Kind), PI))
+    ).
+
+% message_to_codes(+Message, +Kind, -Codes) renders Message as the
+% codes printed for it by print_message/2. messages without a rule of
+% their own are written quoted, errors being taken to be uncaught
+% exceptions.
+message_to_codes(Message, Kind, Codes) :-
+    must_be_message_kind(Kind, message_to_codes/3),
+    (  message_format(Message, Kind, Format, Args) -> true
+    ;  Kind == error -> Format = 'caught: ~q', Args = [Message]
+    ;  Format = 'Unknown message: ~q', Args = [Message]
+    ),
+    atom_chars(Format, Fs),
+    format_message_chars(Fs, Args, Chars, []),
+    chars_to_codes(Chars, Codes).
+
+message_format(singletons(File, Line, Names), _,
+               '~w:~w: Singleton variables: [~s]', [File, Line, Cs]) :-
+    comma_separated_chars(Names, Cs, []).
+message_format(initialization_failed(File), _,
+               'initialization goal for ~w failed', [File]).
+message_format(syntax_error(File, Line, Description), _,
+               '~w:~w: ~w', [File, Line, Description]).
+message_format(unknown_toplevel_command(Prefix, Name), _,
+               'unknown command ~w~w, ~whelp lists the commands', [Prefix, Name, Prefix]).
+message_format(query_unbound_singletons(Names), _,
+               'singleton variables of the query left unbound: [~s]', [Cs]) :-
+    comma_separated_chars(Names, Cs, []).
+message_format(query_calls_empty_dynamic(PI), _,
+               'the query calls ~q, which is dynamic and has no clauses', [PI]).
+message_format(query_stats(Heap, Trail, Stack, ChoicePoints), _,
+               'query used ~w heap cells, ~w trail entries, ~w bytes of stack and ~w choice points',
+               [Heap, Trail, Stack, ChoicePoints]).
+message_format(plunit(passed(Unit, Test, Ms)), _,
+               'test ~w:~q passed in ~w ms', [Unit, Test, Ms]).
+message_format(plunit(failed(File, Line, Unit, Test, Reason)), _, Format, Args) :-
+    test_failure_format(Reason, Format0, Args0),
+    atom_concat('~w:~w: test ~w:~q failed: ', Format0, Format),
+    Args = [File, Line, Unit, Test | Args0].
+message_format(plunit(assertion_failed(File, Line, Unit, Test, Goal)), _,
+               '~w:~w: test ~w:~q: assertion ~q failed', [File, Line, Unit, Test, Goal]).
+message_format(plunit(blocked(Unit, Test, Why)), _,
+               'test ~w:~q blocked: ~w', [Unit, Test, Why]).
+message_format(plunit(summary(Passed, Failed, Assertions, Ms)), _,
+               '~w tests passed, ~w failed, ~w assertions checked in ~w ms',
+               [Passed, Failed, Assertions, Ms]).
+message_format(error(Formal, Context), error, Format, Args) :-
+    nonvar(Formal),
+    error_format(Formal, Format0, Args0),
+    error_context_format(Context, Formal, Format1, Args1, Args0),
+    atom_concat('caught: ', Format1, Format2),
+    atom_concat(Format2, Format0, Format),
+    Args = Args1.
+
+% the goal that raised an error, and the line at which it was raised,
+% if known, are written before the error's description.
+error_context_format(Context, _, '', Args, Args) :-
+    var(Context),
+    !.
+error_context_format(PI:Line, _, '~q, line ~w: ', [PI, Line | Args], Args) :-
+    integer(Line),
+    !.
+error_context_format(_, existence_error(procedure, _), '', Args, Args) :-
+    !.
+error_context_format(Context, _, '~q: ', [Context | Args], Args).
+
+% the reasons for which library(plunit) fails a test.
+test_failure_format(failed, 'goal failed', []).
+test_failure_format(succeeded, 'succeeded, expected failure', []).
+test_failure_format(raised(Ball), 'raised ~q', [Ball]).
+test_failure_format(no_exception(Ball), 'succeeded, expected ~q to be thrown', [Ball]).
+test_failure_format(wrong_exception(Expected, Ball), 'threw ~q, expected ~q', [Ball, Expected]).
+test_failure_format(condition(Cond), 'condition ~q does not hold', [Cond]).
+test_failure_format(all(Found, Expected), 'found ~q, expected ~q', [Found, Expected]).
+test_failure_format(setup(Setup), 'setup ~q failed', [Setup]).
+test_failure_format(assertions(N), '~w assertions failed', [N]).
+
+error_format(instantiation_error, 'arguments are not sufficiently instantiated', []).
+error_format(type_error(Type, Culprit), 'type error: ~q expected, found ~q', [Type, Culprit]).
+error_format(domain_error(Domain, Culprit), 'domain error: ~q expected, found ~q', [Domain, Culprit]).
+error_format(existence_error(procedure, PI), Format, [PI | Args]) :-
+    !,
+    procedure_suggestions(PI, Arities, Libraries),
+    arity_suggestion_format(Arities, PI, Format0, Args, Args0),
+    library_suggestion_format(Libraries, Format1, Args0),
+    atom_concat('unknown procedure: ~q', Format0, Format2),
+    atom_concat(Format2, Format1, Format).
+error_format(existence_error(Kind, Culprit), 'existence error: ~q ~q does not exist', [Kind, Culprit]).
+error_format(permission_error(Action, Type, Culprit),
+             'permission error: cannot ~q ~q ~q', [Action, Type, Culprit]).
+error_format(representation_error(Limit), 'representation error: cannot represent ~q', [Limit]).
+error_format(evaluation_error(Error), 'evaluation error: ~q', [Error]).
+error_format(resource_error(Resource), 'resource error: insufficient ~q', [Resource]).
+error_format(syntax_error(Error), 'syntax error: ~q', [Error]).
+
+% the near misses of an unknown procedure Name/Arity: the other
+% arities at which Name is defined, and the libraries exporting
+% Name/Arity, none of which has been imported.
+procedure_suggestions(PI, Arities, Libraries) :-
+    (  nonvar(PI),
+       PI = Name/Arity,
+       atom(Name),
+       integer(Arity),
+       Arity >= 0 ->
+       '$procedure_suggestions'(Name, Arity, Arities, Libraries)
+    ;  Arities = [],
+       Libraries = []
+    ).
+
+arity_suggestion_format([], _, '', Args, Args).
+arity_suggestion_format([Arity | Arities], Name/_, ' (did you mean ~s?)', [Cs | Args], Args) :-
+    indicator_alternatives([Arity | Arities], Name, Cs, []).
+
+indicator_alternatives([Arity | Arities], Name, Cs0, Cs) :-
+    format_directive_chars(q, [Name/Arity], [], Cs0, Cs1),
+    (  Arities == [] -> Cs1 = Cs
+    ;  Arities = [_] -> append_chars([' ', o, r, ' '], Cs1, Cs2),
+       indicator_alternatives(Arities, Name, Cs2, Cs)
+    ;  Cs1 = [',', ' ' | Cs2],
+       indicator_alternatives(Arities, Name, Cs2, Cs)
+    ).
+
+library_suggestion_format([], '', []).
+library_suggestion_format([Library | Libraries], ' (exported by ~s)', [Cs]) :-
+    library_alternatives([Library | Libraries], Cs, []).
+
+library_alternatives([Library | Libraries], Cs0, Cs) :-
+    format_directive_chars(q, [library(Library)], [], Cs0, Cs1),
+    (  Libraries == [] -> Cs1 = Cs
+    ;  Cs1 = [',', ' ' | Cs2],
+       library_alternatives(Libraries, Cs2, Cs)
+    ).
+
+% the directives of message formats are ~w, ~q, ~s (a list of chars)
+% and ~~. terms written by ~q are cut off below a depth of 20, so that
+% large culprits do not flood the output.
+format_message_chars([], _, Cs, Cs).
+format_message_chars([~, D | Fs], Args0, Cs0, Cs) :-
+    !,
+    format_directive_chars(D, Args0, Args, Cs0, Cs1),
+    format_message_chars(Fs, Args, Cs1, Cs).
+format_message_chars([F | Fs], Args, [F | Cs0], Cs) :-
+    format_message_chars(Fs, Args, Cs0, Cs).
+
+format_directive_chars(w, [Arg | Args], Args, Cs0, Cs) :-
+    '$write_term_to_chars'(Arg, false, true, false, _, 0, Chars),
+    append_chars(Chars, Cs0, Cs).
+format_directive_chars(q, [Arg | Args], Args, Cs0, Cs) :-
+    '$write_term_to_chars'(Arg, false, true, true, _, 20, Chars),
+    append_chars(Chars, Cs0, Cs).
+format_directive_chars(s, [Chars | Args], Args, Cs0, Cs) :-
+    append_chars(Chars, Cs0, Cs).
+format_directive_chars(~, Args, Args, [~ | Cs], Cs).
+
+append_chars([], Cs, Cs).
+append_chars([C | Cs], [C | Cs0], Cs1) :-
+    append_chars(Cs, Cs0, Cs1).
+
+comma_separated_chars([], Cs, Cs).
+comma_separated_chars([Name | Names], Cs0, Cs) :-
+    format_directive_chars(w, [Name], [], Cs0, Cs1),
+    (  Names == [] -> Cs1 = Cs
+    ;  Cs1 = [',', ' ' | Cs2],
+       comma_separated_chars(Names, Cs2, Cs)
+    ).
+
+chars_to_codes([], []).
+chars_to_codes([C | Cs], [D | Ds]) :-
+    char_code(C, D),
+    chars_to_codes(Cs, Ds).
+
+read(Stream, Term) :-
+    read_term(Stream, Term, []).
+
 read_term(Term, Options) :-
+    (  Options == [] -> read(Term)
+    ;  current_input(Stream),
+       read_term_(Stream, Term, Options, read_term/2)
+    ).
+
+read_term(Stream, Term, Options) :-
+    read_term_(Stream, Term, Options, read_term/3).
+
+read_term_(Stream, Term, Options, PI) :-
     '$skip_max_list'(_, -1, Options, Options0),
     (  Options0 == [] -> true
-    ;  var(Options0)  -> throw(error(instantiation_error, read_term/2)) % 8.14.1.3 b)
-    ;  throw(error(type_error(list, Options), read_term/2)) % 8.14.1.3 d)
+    ;  var(Options0)  -> throw(error(instantiation_error, PI)) % 8.14.1.3 b)
+    ;  throw(error(type_error(list, Options), PI)) % 8.14.1.3 d)
     ),
-    (  Options = [variable_names(VarList)] -> '$read_term'(Term, VarList)
-    ;  Options = [] -> read(Term)
-    ;  false
+    read_term_options(Options, Vars, VarList, Singletons, DoubleQuotes, Lines, PI),
+    '$read_term'(Stream, Term, VarList, DoubleQuotes, Lines),
+    term_variables(Term, Vars),
+    singleton_names(VarList, Term, Singletons).
+
+% the options are those of 8.14.1.1, variables(Vars),
+% variable_names(VarList) and singletons(Singletons),
+% double_quotes(Value), which reads the term as if the double_quotes
+% flag were set to Value, and lines(Start, End), the lines of the
+% stream, counted from 1, on which the term begins and ends. all of
+% them are checked before the term is read.
+read_term_options([], _, _, _, _, _, _).
+read_term_options([Option | Options], Vars, VarList, Singletons, DoubleQuotes, Lines, PI) :-
+    (  var(Option) -> throw(error(instantiation_error, PI)) % 8.14.1.3 c)
+    ;  Option = variables(Vars0) -> Vars0 = Vars
+    ;  Option = variable_names(VarList0) -> VarList0 = VarList
+    ;  Option = singletons(Singletons0) -> Singletons0 = Singletons
+    ;  Option = lines(Start, End) -> Lines = Start-End
+    ;  Option = double_quotes(DoubleQuotes0) ->
+       (  var(DoubleQuotes0) ->
+          throw(error(instantiation_error, PI))
+       ;  double_quotes_value(DoubleQuotes0) ->
+          DoubleQuotes = DoubleQuotes0
+       ;  throw(error(domain_error(read_option, Option), PI))
+       )
+    ;  throw(error(domain_error(read_option, Option), PI)) % 8.14.1.3 e)
+    ),
+    read_term_options(Options, Vars, VarList, Singletons, DoubleQuotes, Lines, PI).
+
+% the pairs of VarList whose variables occur once in Term.
+singleton_names([], _, []).
+singleton_names([Name = Var | VarList], Term, Singletons) :-
+    (  var_occurrences(Term, Var, 0, 1) ->
+       Singletons = [Name = Var | Singletons0]
+    ;  Singletons = Singletons0
+    ),
+    singleton_names(VarList, Term, Singletons0).
+
+var_occurrences(Term, Var, N0, N) :-
+    (  var(Term) ->
+       (  Term == Var -> N is N0 + 1
+       ;  N = N0
+       )
+    ;  compound(Term) ->
+       Term =.. [_ | Args],
+       args_var_occurrences(Args, Var, N0, N)
+    ;  N = N0
     ).
 
+args_var_occurrences([], _, N, N).
+args_var_occurrences([Arg | Args], Var, N0, N) :-
+    var_occurrences(Arg, Var, N0, N1),
+    args_var_occurrences(Args, Var, N1, N).
+
+double_quotes_value(atom).
+double_quotes_value(chars).
+double_quotes_value(codes).
+
+% deterministic.
+
+% Det is true if no choice point was created since entering the
+% clause in which deterministic/1 appears, and false otherwise. goals
+% deterministic(Det) in clause bodies are compiled against the cut
+% barrier of their clause, so this clause only serves calls through
+% call/N, which enter it afresh and always find Det = true.
+deterministic(Det) :-
+    '$get_level'(B),
+    '$is_deterministic_answer'(B, Det).
+
 % expand_goal.
 
 expand_goal(Term0, Term) :- '$expand_goal'(Term0, Term).
@@ -370,6 +997,105 @@ expand_goal(Term0, Term) :- '$expand_goal'(Term0, Term).
 
 expand_term(Term0, Term) :- '$expand_term'(Term0, Term).
 
+% phrase. grammar rules are translated to clauses as they are loaded;
+% phrase/3 interprets the control constructs of a body given to it.
+
+phrase(GRBody, S0) :-
+    phrase(GRBody, S0, []).
+
+phrase(GRBody, S0, S) :-
+    (  var(GRBody) -> throw(error(instantiation_error, phrase/3))
+    ;  '$dcg_constr'(GRBody) -> '$phrase'(GRBody, S0, S)
+    ;  callable(GRBody) -> call(GRBody, S0, S)
+    ;  throw(error(type_error(callable, GRBody), phrase/3))
+    ).
+
+'$dcg_constr'([]).
+'$dcg_constr'([_|_]).
+'$dcg_constr'((_, _)).
+'$dcg_constr'((_ ; _)).
+'$dcg_constr'((_ '|' _)).
+'$dcg_constr'((_ -> _)).
+'$dcg_constr'(\+ _).
+'$dcg_constr'({_}).
+'$dcg_constr'(!).
+
+'$phrase'([], S, S).
+'$phrase'(!, S, S).
+'$phrase'((A, B), S0, S) :-
+    phrase(A, S0, S1), phrase(B, S1, S).
+'$phrase'((A -> B ; C), S0, S) :-
+    !,
+    (  phrase(A, S0, S1) ->
+       phrase(B, S1, S)
+    ;  phrase(C, S0, S)
+    ).
+'$phrase'((A ; B), S0, S) :-
+    (  phrase(A, S0, S) ; phrase(B, S0, S)  ).
+'$phrase'((A '|' B), S0, S) :-
+    (  phrase(A, S0, S) ; phrase(B, S0, S)  ).
+'$phrase'((A -> B), S0, S) :-
+    (  phrase(A, S0, S1) -> phrase(B, S1, S)  ).
+'$phrase'(\+ A, S0, S) :-
+    \+ phrase(A, S0, _),
+    S0 = S.
+'$phrase'({G}, S0, S) :-
+    call(G),
+    S0 = S.
+'$phrase'([T|Ts], S0, S) :-
+    '$phrase_terminals'([T|Ts], S0, S).
+
+'$phrase_terminals'([], S, S).
+'$phrase_terminals'([T|Ts], [T|S0], S) :-
+    '$phrase_terminals'(Ts, S0, S).
+
+% prolog_load_context.
+
+% the context of the file being loaded, available to term and goal
+% expansion hooks and to initialization goals. term_position is the
+% line on which the term being loaded begins, and variable_names the
+% Name = Var equations of the term being expanded.
+prolog_load_context(Key, Value) :-
+    load_context_key(Key),
+    '$load_context'(Key, Value).
+
+load_context_key(module).
+load_context_key(source).
+load_context_key(file).
+load_context_key(directory).
+load_context_key(term_position).
+load_context_key(variable_names).
+
+% statistics.
+
+% the sizes of the machine's tables: atoms is the number of atoms in
+% the atom tables, strings the number of distinct string literals
+% read, heap the number of cells in use on the heap, and
+% retired_code the number of superseded predicate definitions whose
+% code is still retained. cputime is the processor time used by the
+% process so far, in seconds, and random_draws the number of draws
+% from the random number generator since it was last seeded. runtime
+% and walltime are lists [Total, SinceLast] of the processor time and
+% the time elapsed since the machine started, in milliseconds,
+% SinceLast counting from when the key was last asked for.
+statistics(Key, Value) :-
+    statistics_key(Key),
+    '$statistics'(Key, Value).
+
+statistics_key(atoms).
+statistics_key(strings).
+statistics_key(heap).
+statistics_key(retired_code).
+statistics_key(cputime).
+statistics_key(runtime).
+statistics_key(walltime).
+statistics_key(random_draws).
+
+% redefined predicates keep their old code for as long as running
+% goals may still return to it. garbage_collect_clauses/0 releases
+% the old code that is no longer reachable.
+garbage_collect_clauses :- '$garbage_collect_clauses'.
+
 % term_variables.
 
 % ensures List is either a variable or a list.
@@ -400,9 +1126,31 @@ end_block(Bb, NBb) :- '$clean_up_block'(NBb), '$reset_block'(Bb).
 end_block(Bb, NBb) :- '$reset_block'(NBb), '$fail'.
 
 :- non_counted_backtracking handle_ball/3.
+handle_ball(Ball, C, R) :-
+    % abort/0 is only caught by catchers that ask for it. a variable
+    % catcher still runs its recovery goal, so that whatever it
+    % restores is restored, before the ball is passed on.
+    Ball == '$aborted',
+    var(C),
+    !,
+    '$erase_ball',
+    C = Ball,
+    (  call(R) -> true
+    ;  true
+    ),
+    throw(Ball).
+handle_ball(Ball, _, _) :-
+    % limit tokens are only caught by the call that installed the limit.
+    limit_token(Ball),
+    !,
+    '$unwind_stack'.
 handle_ball(C, C, R) :- !, '$erase_ball', call(R).
 handle_ball(_, _, _) :- '$unwind_stack'.
 
+limit_token('$inference_limit_exceeded'(_)).
+limit_token('$time_limit_exceeded'(_)).
+limit_token('$memory_limit_exceeded'(_)).
+
 throw(Ball) :- '$set_ball'(Ball), '$unwind_stack'.
 
 :- non_counted_backtracking '$iterate_find_all'/4.
@@ -707,7 +1455,7 @@ retract(Clause) :-
     ).
 
 module_abolish(Pred, Module) :-
-    (  var(Pred) -> throw(error(instantiation_error), abolish/1)
+    (  var(Pred) -> throw(error(instantiation_error, abolish/1))
     ;  Pred = Name/Arity ->
        (  var(Name)  -> throw(error(instantiation_error, abolish/1))
        ;  integer(Arity) ->
@@ -726,7 +1474,7 @@ module_abolish(Pred, Module) :-
     ).
 
 abolish(Pred) :-
-    (  var(Pred) -> throw(error(instantiation_error), abolish/1)
+    (  var(Pred) -> throw(error(instantiation_error, abolish/1))
     ;  Pred = Module:InnerPred -> module_abolish(InnerPred, Module)
     ;  Pred = Name/Arity ->
        (  var(Name)  -> throw(error(instantiation_error, abolish/1))
@@ -789,7 +1537,7 @@ list_of_op_atoms([]).
 op_priority(Priority) :-
     integer(Priority), !,
     (  ( Priority < 0 ; Priority > 1200 ) ->
-       throw(error(domain_error(operator_priority, Priority))) % 8.14.3.3 h)
+       throw(error(domain_error(operator_priority, Priority), op/3)) % 8.14.3.3 h)
     ;  true
     ).
 op_priority(Priority) :-
@@ -827,14 +1575,17 @@ op(Priority, OpSpec, Op) :-
 
 halt :- '$halt'.
 
+abort :- '$set_ball'('$aborted'), '$unwind_stack'.
+
 atom_length(Atom, Length) :-
     (  var(Atom)  -> throw(error(instantiation_error, atom_length/2)) % 8.16.1.3 a)
-    ;  atom(Atom) -> (  var(Length) -> '$atom_length'(Atom, Length)
-		     ;  integer(Length), Length >= 0 -> '$atom_length'(Atom, Length)
-		     ;  integer(Length) -> throw(error(domain_error(not_less_than_zero, Length), atom_length/2))
-		     % 8.16.1.3 d)
-		     ;  throw(error(type_error(integer, Length), atom_length/2)) % 8.16.1.3 c)
-		     )
+    ;  ( atom(Atom) ; string_text(Atom, atom_length/2) ) ->
+       (  var(Length) -> '$atom_length'(Atom, Length)
+       ;  integer(Length), Length >= 0 -> '$atom_length'(Atom, Length)
+       ;  integer(Length) -> throw(error(domain_error(not_less_than_zero, Length), atom_length/2))
+       % 8.16.1.3 d)
+       ;  throw(error(type_error(integer, Length), atom_length/2)) % 8.16.1.3 c)
+       )
     ;  throw(error(type_error(atom, Atom), atom_length/2)) % 8.16.1.3 b)
     ).
 
@@ -890,22 +1641,44 @@ atom_concat(Atom_1, Atom_2, Atom_12) :-
        atom_chars(Atom_12, Atom_12_Chars)
     ).
 
+% strings are read as the lists of characters they denote, so that
+% neither they nor their substrings are added to the atom table.
 sub_atom(Atom, Before, Length, After, Sub_atom) :-
-    error:must_be(atom, Atom),
-    error:can_be(atom, Sub_atom),
+    (  string_text(Atom, sub_atom/5) ->
+       (  ( var(Sub_atom) ; atom(Sub_atom) ; string_text(Sub_atom, sub_atom/5) ) -> true
+       ;  throw(error(type_error(atom, Sub_atom), sub_atom/5))
+       )
+    ;  error:must_be(atom, Atom),
+       error:can_be(atom, Sub_atom)
+    ),
     error:can_be(integer, Before),
     error:can_be(integer, Length),
     error:can_be(integer, After),
     (  integer(Before), Before < 0 -> throw(error(domain_error(not_less_than_zero, Before), sub_atom/5))
     ;  integer(Length), Length < 0 -> throw(error(domain_error(not_less_than_zero, Length), sub_atom/5))
     ;  integer(After), After < 0 -> throw(error(domain_error(not_less_than_zero, After), sub_atom/5))
-    ;  atom_chars(Atom, AtomChars),
+    ;  (  atom(Atom) -> atom_chars(Atom, AtomChars)
+       ;  AtomChars = Atom
+       ),
        lists:append(BeforeChars, LengthAndAfterChars, AtomChars),
        lists:append(LengthChars, AfterChars, LengthAndAfterChars),
        '$skip_max_list'(Before, -1, BeforeChars, []),
        '$skip_max_list'(Length, -1, LengthChars, []),
        '$skip_max_list'(After, -1, AfterChars, []),
-       atom_chars(Sub_atom, LengthChars)
+       (  atom(Atom) -> atom_chars(Sub_atom, LengthChars)
+       ;  atom(Sub_atom) -> atom_chars(Sub_atom, LengthChars)
+       ;  Sub_atom = LengthChars
+       )
+    ).
+
+% String is a string, or a partial string or list of characters
+% ending in one. a partial string with an unbound tail is
+% insufficiently instantiated.
+string_text(String, PI) :-
+    '$is_string'(String),
+    '$skip_max_list'(_, -1, String, Tail),
+    (  var(Tail) -> throw(error(instantiation_error, PI))
+    ;  Tail == []
     ).
 
 char_code(Char, Code) :-
@@ -925,6 +1698,33 @@ get_char(C) :-
     ;  throw(error(type_error(in_character, C), get_char/1))
     ).
 
+peek_char(C) :-
+    current_input(S),
+    peek_char(S, C).
+
+peek_char(S, C) :-
+    (  var(S) ->
+       throw(error(instantiation_error, peek_char/2)) % 8.12.2.3 a
+    ;  var(C) -> '$peek_char'(S, C)
+    ;  C == end_of_file -> '$peek_char'(S, C)
+    ;  atom(C), atom_length(C, 1) -> '$peek_char'(S, C)
+    ;  throw(error(type_error(in_character, C), peek_char/2)) % 8.12.2.3 c
+    ).
+
+peek_code(C) :-
+    current_input(S),
+    peek_code(S, C).
+
+peek_code(S, C) :-
+    (  var(S) ->
+       throw(error(instantiation_error, peek_code/2)) % 8.12.2.3 a
+    ;  var(C) -> '$peek_code'(S, C)
+    ;  \+ integer(C) ->
+       throw(error(type_error(integer, C), peek_code/2)) % 8.12.2.3 c
+    ;  C >= -1, C =< 0x10FFFF -> '$peek_code'(S, C)
+    ;  throw(error(representation_error(in_character_code), peek_code/2)) % 8.12.2.3 f
+    ).
+
 can_be_number(N, PI) :-
     (  var(N) -> true
     ;  must_be_number(N, PI)
@@ -990,7 +1790,7 @@ number_chars(N, Chs) :-
       ;  can_be_list(Chs, number_chars/2)
       ,  chars_or_vars(Chs, number_chars/2)
       ),
-      '$number_to_chars'(N, Chsx),
+      '$number_to_chars'(N, 10, Chsx),
       Chsx = Chs
     ).
 
@@ -1005,20 +1805,174 @@ number_codes(N, Chs) :-
       ;  can_be_list(Chs, number_codes/2)
       ,  codes_or_vars(Chs, number_codes/2)
       ),
-      '$number_to_codes'(N, Chsx),
+      '$number_to_codes'(N, 10, Chsx),
       Chsx = Chs
     ).
 
-subsumes_term(General, Specific) :-
-   \+ \+ (
-      term_variables(Specific, SVs1),
-      General = Specific,
-      term_variables(SVs1, SVs2),
-      SVs1 == SVs2
+% atom_number(Atom, N) relates an atom to the number it reads as,
+% failing rather than raising a syntax error if Atom isn't one, so
+% that it can test text for being numeric. number_string(N, String)
+% does the same for strings, written as the double_quotes flag has
+% them: lists of characters or codes, or atoms. layout around the
+% number is ignored.
+atom_number(Atom, N) :-
+    (  var(Atom) ->
+       must_be_number(N, atom_number/2),
+       number_chars(N, Cs),
+       atom_chars(Atom, Cs)
+    ;  atom(Atom) ->
+       can_be_number(N, atom_number/2),
+       atom_chars(Atom, Cs),
+       chars_number(Cs, N)
+    ;  throw(error(type_error(atom, Atom), atom_number/2))
+    ).
+
+number_string(N, String) :-
+    (  var(String) ->
+       must_be_number(N, number_string/2),
+       current_prolog_flag(double_quotes, DoubleQuotes),
+       number_chars(N, Cs),
+       string_of_chars(DoubleQuotes, Cs, String)
+    ;  can_be_number(N, number_string/2),
+       string_chars(String, Cs) ->
+       chars_number(Cs, N)
+    ;  throw(error(type_error(string, String), number_string/2))
+    ).
+
+string_of_chars(chars, Cs, Cs).
+string_of_chars(codes, Cs, Codes) :- chars_to_codes(Cs, Codes).
+string_of_chars(atom, Cs, Atom) :- atom_chars(Atom, Cs).
+
+string_chars(String, Cs) :-
+    (  atom(String) -> atom_chars(String, Cs)
+    ;  is_list(String), String = [C | _], integer(C) ->
+       catch(atom_codes(Atom, String), error(representation_error(_), _), false),
+       atom_chars(Atom, Cs)
+    ;  is_list(String),
+       catch(atom_chars(_, String), error(type_error(_, _), _), false),
+       Cs = String
+    ).
+
+chars_number(Cs0, N) :-
+    trim_layout(Cs0, Cs1),
+    trim_trailing_layout(Cs1, Cs),
+    Cs \== [],
+    catch('$chars_to_number'(Cs, N0), error(syntax_error(_), _), false),
+    N0 = N.
+
+trim_layout([C | Cs0], Cs) :-
+    layout_char(C),
+    !,
+    trim_layout(Cs0, Cs).
+trim_layout(Cs, Cs).
+
+trim_trailing_layout([], []).
+trim_trailing_layout([C | Cs0], Cs) :-
+    trim_trailing_layout(Cs0, Cs1),
+    (  Cs1 == [], layout_char(C) -> Cs = []
+    ;  Cs = [C | Cs1]
+    ).
+
+layout_char(' ').
+layout_char('\t').
+layout_char('\n').
+layout_char('\r').
+
+% number_chars/3 and number_codes/3 relate the integer N to its digits
+% in Radix, from 2 to 36, preceded by a minus sign if N is negative.
+% number_chars/2 and number_codes/2 read integers written as
+% Radix'Digits, as well as the 0x, 0o and 0b prefixed forms.
+
+number_chars(N, Radix, Chs) :-
+   must_be_radix(Radix, number_chars/3),
+   (  ground(Chs)
+   -> can_be_integer(N, number_chars/3),
+      can_be_list(Chs, number_chars/3),
+      '$number_to_chars'(Radix, 10, RChs),
+      radix_prefixed(Chs, RChs, '\'', '-', Chs1),
+      radix_number(Chs1, Nx, number_chars/3),
+      Nx = N
+   ;  must_be_integer(N, number_chars/3),
+      (  var(Chs) -> true
+      ;  can_be_list(Chs, number_chars/3)
+      ,  chars_or_vars(Chs, number_chars/3)
+      ),
+      '$number_to_chars'(N, Radix, Chsx),
+      Chsx = Chs
+   ).
+
+number_codes(N, Radix, Chs) :-
+   must_be_radix(Radix, number_codes/3),
+   (  ground(Chs)
+   -> can_be_integer(N, number_codes/3),
+      can_be_list(Chs, number_codes/3),
+      '$number_to_codes'(Radix, 10, RChs),
+      radix_prefixed(Chs, RChs, 0'\', 0'-, Chs1),
+      radix_number(Chs1, Nx, number_codes/3),
+      Nx = N
+   ;  must_be_integer(N, number_codes/3),
+      (  var(Chs) -> true
+      ;  can_be_list(Chs, number_codes/3)
+      ,  codes_or_vars(Chs, number_codes/3)
+      ),
+      '$number_to_codes'(N, Radix, Chsx),
+      Chsx = Chs
+   ).
+
+% Chs1 is the digits Chs written as Radix'Digits, with the minus sign
+% of a negative number moved in front of the radix.
+radix_prefixed([Minus | Chs], RChs, Quote, Minus, [Minus | Chs1]) :-
+   !,
+   append_chars(RChs, Chs1, [Quote | Chs]).
+radix_prefixed(Chs, RChs, Quote, _, Chs1) :-
+   append_chars(RChs, Chs1, [Quote | Chs]).
+
+radix_number(Chs, N, PI) :-
+   catch(text_to_number(PI, Chs, N),
+         error(syntax_error(E), _),
+         throw(error(syntax_error(E), PI))).
+
+text_to_number(number_chars/3, Chs, N) :- '$chars_to_number'(Chs, N).
+text_to_number(number_codes/3, Cs, N) :- '$codes_to_number'(Cs, N).
+
+must_be_radix(Radix, PI) :-
+   (  var(Radix) -> throw(error(instantiation_error, PI))
+   ;  \+ integer(Radix) -> throw(error(type_error(integer, Radix), PI))
+   ;  Radix >= 2, Radix =< 36 -> true
+   ;  throw(error(domain_error(radix, Radix), PI))
    ).
 
+can_be_integer(N, PI) :-
+   (  var(N) -> true
+   ;  must_be_integer(N, PI)
+   ).
+
+must_be_integer(N, PI) :-
+   (  integer(N) -> true
+   ;  var(N) -> throw(error(instantiation_error, PI))
+   ;  throw(error(type_error(integer, N), PI))
+   ).
+
+% type checking.
+
+% callable(Term) holds for atoms and compound terms (8.3.9).
+callable(Term) :- '$is_callable'(Term, shallow).
+
+% is_list(Term) holds for proper lists, and not for partial or cyclic
+% lists.
+is_list(Term) :- '$is_list'(Term).
+
+subsumes_term(General, Specific) :- '$subsumes_term'(General, Specific).
+
 unify_with_occurs_check(X, Y) :- '$unify_with_occurs_check'(X, Y).
 
+% Unifier is the list of Var = Value equations whose execution unifies
+% X and Y. fails if X and Y do not unify. never binds.
+unifiable(X, Y, Unifier) :- '$unifiable'(X, Y, Unifier).
+
+% succeeds iff X and Y are identical or cannot be unified. never binds.
+X ?= Y :- '$decidable'(X, Y).
+
 current_input(S) :- '$current_input'(S).    
 
 current_output(S) :- '$current_output'(S).
@@ -1034,3 +1988,209 @@ set_output(S) :-
        throw(error(instantiation_error, set_output/1))
     ;  '$set_output'(S)
     ).
+
+open(SourceSink, Mode, Stream) :-
+    open(SourceSink, Mode, Stream, []).
+
+% the only option is type(T), where T is text, the default, or binary.
+% binary streams are read and written by get_byte/2, peek_byte/2 and
+% put_byte/2 rather than by the predicates of characters and terms.
+open(SourceSink, Mode, Stream, Options) :-
+    (  var(SourceSink) ->
+       throw(error(instantiation_error, open/4)) % 8.11.5.3 a
+    ;  var(Mode) ->
+       throw(error(instantiation_error, open/4)) % 8.11.5.3 b
+    ;  \+ atom(Mode) ->
+       throw(error(type_error(atom, Mode), open/4)) % 8.11.5.3 d
+    ;  nonvar(Stream) ->
+       throw(error(uninstantiation_error(Stream), open/4)) % 8.11.5.3 f
+    ;  Mode \== read, Mode \== write, Mode \== append ->
+       throw(error(domain_error(io_mode, Mode), open/4)) % 8.11.5.3 h
+    ;  open_options(Options, text, Type),
+       '$open'(SourceSink, Mode, Stream, Type)
+    ).
+
+open_options(Options, _, _) :-
+    var(Options),
+    throw(error(instantiation_error, open/4)). % 8.11.5.3 c
+open_options([], Type, Type) :- !.
+open_options([Option | Options], Type0, Type) :-
+    !,
+    (  var(Option) ->
+       throw(error(instantiation_error, open/4)) % 8.11.5.3 g
+    ;  Option = type(Type1), ( Type1 == text ; Type1 == binary ) ->
+       open_options(Options, Type1, Type)
+    ;  throw(error(domain_error(stream_option, Option), open/4)) % 8.11.5.3 i
+    ).
+open_options(Options, _, _) :-
+    throw(error(type_error(list, Options), open/4)). % 8.11.5.3 e
+
+close(S) :-
+    (  var(S) ->
+       throw(error(instantiation_error, close/1)) % 8.11.6.3 a
+    ;  '$close'(S)
+    ).
+
+set_stream(S, Property) :-
+    (  var(S) ->
+       throw(error(instantiation_error, set_stream/2))
+    ;  var(Property) ->
+       throw(error(instantiation_error, set_stream/2))
+    ;  Property = timeout(T) ->
+       (  var(T) ->
+          throw(error(instantiation_error, set_stream/2))
+       ;  T == infinite ->
+          '$set_stream'(S, Property)
+       ;  integer(T), T > 0 ->
+          '$set_stream'(S, Property)
+       ;  float(T), T > 0.0 ->
+          '$set_stream'(S, Property)
+       ;  throw(error(domain_error(stream_property, Property), set_stream/2))
+       )
+    ;  Property = double_quotes(DoubleQuotes) ->
+       (  var(DoubleQuotes) ->
+          throw(error(instantiation_error, set_stream/2))
+       ;  double_quotes_value(DoubleQuotes) ->
+          '$set_stream'(S, Property)
+       ;  throw(error(domain_error(stream_property, Property), set_stream/2))
+       )
+    ;  throw(error(domain_error(stream_property, Property), set_stream/2))
+    ).
+
+% the properties of an open stream are
+%
+%     file_name(F), mode(M), input, output, alias(A), position(P),
+%     end_of_stream(E), eof_action(A), reposition(B) and type(T)
+%
+% where the position P is the number of bytes read from or written
+% to the stream.
+stream_property(S, P) :-
+    (  nonvar(P), \+ stream_property_term(P) ->
+       throw(error(domain_error(stream_property, P), stream_property/2))
+    ;  nonvar(S) ->
+       '$stream_properties'(S, Ps),
+       lists:member(P, Ps)
+    ;  '$streams'(Ss),
+       lists:member(S, Ss),
+       '$stream_properties'(S, Ps),
+       lists:member(P, Ps)
+    ).
+
+stream_property_term(file_name(_)).
+stream_property_term(mode(_)).
+stream_property_term(input).
+stream_property_term(output).
+stream_property_term(alias(_)).
+stream_property_term(position(_)).
+stream_property_term(end_of_stream(_)).
+stream_property_term(eof_action(_)).
+stream_property_term(reposition(_)).
+stream_property_term(type(_)).
+
+get_byte(B) :-
+    current_input(S),
+    get_byte(S, B).
+
+get_byte(S, B) :-
+    (  var(S) ->
+       throw(error(instantiation_error, get_byte/2)) % 8.13.1.3 a
+    ;  var(B) -> '$get_byte'(S, B)
+    ;  in_byte(B) -> '$get_byte'(S, B)
+    ;  throw(error(type_error(in_byte, B), get_byte/2)) % 8.13.1.3 c
+    ).
+
+peek_byte(B) :-
+    current_input(S),
+    peek_byte(S, B).
+
+peek_byte(S, B) :-
+    (  var(S) ->
+       throw(error(instantiation_error, peek_byte/2)) % 8.13.2.3 a
+    ;  var(B) -> '$peek_byte'(S, B)
+    ;  in_byte(B) -> '$peek_byte'(S, B)
+    ;  throw(error(type_error(in_byte, B), peek_byte/2)) % 8.13.2.3 c
+    ).
+
+put_byte(B) :-
+    current_output(S),
+    put_byte(S, B).
+
+put_byte(S, B) :-
+    (  var(S) ->
+       throw(error(instantiation_error, put_byte/2)) % 8.13.3.3 a
+    ;  var(B) ->
+       throw(error(instantiation_error, put_byte/2)) % 8.13.3.3 b
+    ;  integer(B), B >= 0, B =< 255 -> '$put_byte'(S, B)
+    ;  throw(error(type_error(byte, B), put_byte/2)) % 8.13.3.3 c
+    ).
+
+% a byte, or -1 for the end of a stream.
+in_byte(B) :-
+    integer(B),
+    B >= -1,
+    B =< 255.
+
+at_end_of_stream :-
+    current_input(S),
+    '$at_end_of_stream'(S).
+
+at_end_of_stream(S) :-
+    (  var(S) ->
+       throw(error(instantiation_error, at_end_of_stream/1))
+    ;  '$at_end_of_stream'(S)
+    ).
+
+fast_read(S, Term) :-
+    (  var(S) ->
+       throw(error(instantiation_error, fast_read/2))
+    ;  '$fast_read'(S, Term)
+    ).
+
+fast_write(S, Term) :-
+    (  var(S) ->
+       throw(error(instantiation_error, fast_write/2))
+    ;  '$fast_write'(S, Term, false)
+    ).
+
+% with the option atom_ids(true), atoms are written by their ids in
+% the atom table of the machine, which is then needed to read the
+% term back (see save_atom_table/1).
+fast_write(S, Term, Options) :-
+    (  var(S) ->
+       throw(error(instantiation_error, fast_write/3))
+    ;  fast_write_options(Options, false, AtomIds),
+       '$fast_write'(S, Term, AtomIds)
+    ).
+
+fast_write_options(Options, _, _) :-
+    var(Options),
+    !,
+    throw(error(instantiation_error, fast_write/3)).
+fast_write_options([], AtomIds, AtomIds) :-
+    !.
+fast_write_options([Option|Options], _, AtomIds) :-
+    !,
+    (  var(Option) ->
+       throw(error(instantiation_error, fast_write/3))
+    ;  Option = atom_ids(AtomIds0), ( AtomIds0 == true ; AtomIds0 == false ) ->
+       fast_write_options(Options, AtomIds0, AtomIds)
+    ;  throw(error(domain_error(fast_write_option, Option), fast_write/3))
+    ).
+fast_write_options(Options, _, _) :-
+    throw(error(type_error(list, Options), fast_write/3)).
+
+% the atom table holds the atoms written by fast_write/3 under their
+% ids. save_atom_table/1 writes it to a stream, and load_atom_table/1
+% registers the atoms it reads from a stream under their saved ids,
+% throwing a syntax error if an atom already has a different id.
+save_atom_table(S) :-
+    (  var(S) ->
+       throw(error(instantiation_error, save_atom_table/1))
+    ;  '$save_atom_table'(S)
+    ).
+
+load_atom_table(S) :-
+    (  var(S) ->
+       throw(error(instantiation_error, load_atom_table/1))
+    ;  '$load_atom_table'(S)
+    ).
@@ -0,0 +1,43 @@
+%% conversions between text and the bytes of its UTF-8 encoding,
+%% performed natively, and reading terms from lists of characters.
+%% load at the REPL with
+
+%% ?- use_module(library(charsio)).
+
+%% if Bytes is a list of integers in 0..255, it is decoded and the
+%% result is unified with the text argument. otherwise, the text
+%% argument is encoded and Bytes is unified with the resulting list.
+
+:- module(charsio, [chars_utf8bytes/2, codes_bytes/2,
+                    read_term_from_chars/3]).
+
+chars_utf8bytes(Chars, Bytes) :- '$chars_utf8bytes'(Chars, Bytes).
+
+codes_bytes(Codes, Bytes) :- '$codes_bytes'(Codes, Bytes).
+
+%% read_term_from_chars(+Chars, -Term, +Options)
+%%
+%% reads Term from the characters of Chars, which are parsed in place
+%% rather than copied. the closing full stop of Term may be omitted.
+%% the supported options are variable_names(VNs) and variables(Vs).
+
+read_term_from_chars(Chars, Term, Options) :-
+    '$skip_max_list'(_, -1, Options, Options0),
+    (  Options0 == [] -> true
+    ;  var(Options0) -> throw(error(instantiation_error, read_term_from_chars/3))
+    ;  throw(error(type_error(list, Options), read_term_from_chars/3))
+    ),
+    '$read_term_from_chars'(Chars, Term, VNs),
+    read_term_options(Options, Term, VNs).
+
+read_term_options([], _, _).
+read_term_options([Option | Options], Term, VNs) :-
+    (  var(Option) ->
+       throw(error(instantiation_error, read_term_from_chars/3))
+    ;  Option = variable_names(VNs0) ->
+       VNs0 = VNs
+    ;  Option = variables(Vs) ->
+       term_variables(Term, Vs)
+    ;  throw(error(domain_error(read_option, Option), read_term_from_chars/3))
+    ),
+    read_term_options(Options, Term, VNs).
@@ -0,0 +1,531 @@
+%% constraint logic programming over the integers. load with
+
+%% ?- use_module(library(clpz)).
+
+%% each constrained variable has a domain, a union of disjoint
+%% intervals L..H where L may be inf and H sup, and the propagators of
+%% the constraints it occurs in. posting a constraint, or binding or
+%% aliasing a constrained variable, runs the propagators of the
+%% variables whose domains change, removing the values that can't be
+%% part of a solution, until none of them changes a domain. a
+%% variable whose domain is a single integer is bound to it.
+
+%%     X in Dom     X lies in Dom, an integer, L..H, or Dom1 \/ Dom2
+%%     Xs ins Dom   the elements of Xs lie in Dom
+%%     A #= B       the expressions A and B are equal
+%%     A #\= B      A and B differ
+%%     A #< B, A #> B, A #=< B, A #>= B
+%%                  A and B are ordered so
+%%     all_different(Xs)
+%%                  the elements of Xs are pairwise distinct
+%%     label(Xs)    binds the elements of Xs, with finite domains, to
+%%                  their values in ascending order, on backtracking
+%%     fd_dom(X, Dom)
+%%                  Dom is the domain of X
+
+%% expressions are integers, variables, and their sums, differences,
+%% negations and products. linear constraints are propagated by
+%% bounds, tightened by '$fd_linear_propagate'/7. all_different/1
+%% removes the value of each bound element from the domains of the
+%% others.
+
+%%     ?- X in 0..5, Y #= 2*X + 1, Y #> 6.
+
+%% leaves X in 3..5 and Y in 7..11.
+
+:- module(clpz, [op(700, xfx, #=), op(700, xfx, #\=), op(700, xfx, #<),
+                 op(700, xfx, #>), op(700, xfx, #=<), op(700, xfx, #>=),
+                 op(700, xfx, in), op(700, xfx, ins), op(450, xfx, ..),
+                 (#=)/2, (#\=)/2, (#<)/2, (#>)/2, (#=<)/2, (#>=)/2,
+                 all_different/1, fd_dom/2, (in)/2, (ins)/2, label/1]).
+
+:- use_module(library(atts)).
+:- use_module(library(between)).
+:- use_module(library(dcgs)).
+:- use_module(library(error)).
+:- use_module(library(lists)).
+:- use_module(library(pairs)).
+
+:- attribute clpz/2.
+
+X in Dom0 :-
+    parse_dom(Dom0, Dom),
+    fd_in(Dom, X).
+
+Xs ins Dom0 :-
+    must_be(list, Xs),
+    parse_dom(Dom0, Dom),
+    maplist(fd_in(Dom), Xs).
+
+A #= B :- post_linear(A, B, =).
+A #\= B :- post_linear(A, B, \=).
+A #< B :- post_linear(A+1, B, =<).
+A #> B :- post_linear(B+1, A, =<).
+A #=< B :- post_linear(A, B, =<).
+A #>= B :- post_linear(B, A, =<).
+
+all_different(Xs) :-
+    must_be(list, Xs),
+    maplist(fd_variable, Xs),
+    post_prop(alldiff(Xs), Xs).
+
+label(Xs) :-
+    must_be(list, Xs),
+    maplist(fd_variable, Xs),
+    maplist(finite_domain, Xs),
+    label_(Xs).
+
+label_([]).
+label_([X | Xs]) :-
+    (  integer(X) -> true
+    ;  fd_get(X, Dom, _),
+       dom_member(Dom, X)
+    ),
+    label_(Xs).
+
+fd_dom(X, Dom) :-
+    fd_variable(X),
+    (  integer(X) -> Dom = X..X
+    ;  fd_get(X, Dom0, _),
+       dom_expr(Dom0, Dom)
+    ).
+
+fd_variable(X) :-
+    (  var(X) -> true
+    ;  integer(X) -> true
+    ;  throw(error(type_error(integer, X), _))
+    ).
+
+finite_domain(X) :-
+    fd_bounds(X, L, H),
+    (  integer(L), integer(H) -> true
+    ;  throw(error(instantiation_error, label/1))
+    ).
+
+/* domains are sorted lists of disjoint intervals L-H, L =< H, whose
+   bounds are integers or inf and sup. */
+
+parse_dom(Dom0, Dom) :-
+    (  var(Dom0) ->
+       throw(error(instantiation_error, in/2))
+    ;  integer(Dom0) ->
+       Dom = [Dom0-Dom0]
+    ;  Dom0 = L..H, bound(L), bound(H) ->
+       (  bound_le(L, H), L \== sup, H \== inf -> Dom = [L-H]
+       ;  Dom = []
+       )
+    ;  Dom0 = (Dom1 \/ Dom2) ->
+       parse_dom(Dom1, Is),
+       parse_dom(Dom2, Js),
+       foldl(dom_insert, Js, Is, Dom)
+    ;  throw(error(type_error(clpz_domain, Dom0), in/2))
+    ).
+
+bound(B) :-
+    (  integer(B) -> true
+    ;  B == inf -> true
+    ;  B == sup
+    ).
+
+bound_le(inf, _) :- !.
+bound_le(_, sup) :- !.
+bound_le(sup, _) :- !, false.
+bound_le(_, inf) :- !, false.
+bound_le(A, B) :- A =< B.
+
+bound_min(A, B, Min) :-
+    (  bound_le(A, B) -> Min = A
+    ;  Min = B
+    ).
+
+bound_max(A, B, Max) :-
+    (  bound_le(A, B) -> Max = B
+    ;  Max = A
+    ).
+
+% adds the interval L-H to a domain, joining it to the intervals it
+% overlaps or adjoins.
+dom_insert(L-H, [], [L-H]).
+dom_insert(L-H, [A-B | Is], Dom) :-
+    (  integer(H), integer(A), H + 1 < A ->
+       Dom = [L-H, A-B | Is]
+    ;  integer(B), integer(L), B + 1 < L ->
+       Dom = [A-B | Dom1],
+       dom_insert(L-H, Is, Dom1)
+    ;  bound_min(L, A, L1),
+       bound_max(H, B, H1),
+       dom_insert(L1-H1, Is, Dom)
+    ).
+
+dom_intersection([], _, []) :- !.
+dom_intersection(_, [], []) :- !.
+dom_intersection([A-B | Is], [C-D | Js], Dom) :-
+    bound_max(A, C, L),
+    bound_min(B, D, H),
+    (  bound_le(L, H) -> Dom = [L-H | Dom1]
+    ;  Dom = Dom1
+    ),
+    (  bound_le(B, D) -> dom_intersection(Is, [C-D | Js], Dom1)
+    ;  dom_intersection([A-B | Is], Js, Dom1)
+    ).
+
+dom_remove([], _, []).
+dom_remove([L-H | Is], N, Dom) :-
+    (  \+ bound_le(L, N) -> Dom = [L-H | Is]
+    ;  \+ bound_le(N, H) ->
+       Dom = [L-H | Dom1],
+       dom_remove(Is, N, Dom1)
+    ;  N0 is N - 1,
+       N1 is N + 1,
+       (  L == N -> Dom = Dom1
+       ;  Dom = [L-N0 | Dom1]
+       ),
+       (  H == N -> Dom1 = Is
+       ;  Dom1 = [N1-H | Is]
+       )
+    ).
+
+dom_contains(Dom, N) :-
+    member(L-H, Dom),
+    bound_le(L, N),
+    bound_le(N, H),
+    !.
+
+dom_member(Dom, N) :-
+    member(L-H, Dom),
+    between(L, H, N0),
+    N = N0.
+
+dom_bounds(Dom, L, H) :-
+    Dom = [L-_ | _],
+    last(Dom, _-H).
+
+last([I], I) :- !.
+last([_ | Is], I) :- last(Is, I).
+
+dom_expr([I | Is], Expr) :-
+    interval_expr(I, Expr0),
+    foldl(union_expr, Is, Expr0, Expr).
+
+union_expr(I, Expr0, Expr0 \/ Expr1) :-
+    interval_expr(I, Expr1).
+
+interval_expr(L-H, Expr) :-
+    (  L == H -> Expr = L
+    ;  Expr = L..H
+    ).
+
+/* the constrained variables. */
+
+fd_get(X, Dom, Props) :-
+    (  get_atts(X, +clpz(Dom, Props)) -> true
+    ;  Dom = [inf-sup],
+       Props = []
+    ).
+
+fd_bounds(X, L, H) :-
+    (  integer(X) -> L = X, H = X
+    ;  fd_get(X, Dom, _),
+       dom_bounds(Dom, L, H)
+    ).
+
+fd_in(Dom, X) :-
+    (  integer(X) -> dom_contains(Dom, X)
+    ;  var(X) ->
+       fd_get(X, Dom0, Props),
+       dom_intersection(Dom0, Dom, Dom1),
+       fd_update(X, Dom0, Dom1, Props)
+    ;  throw(error(type_error(integer, X), in/2))
+    ).
+
+fd_restrict(X, L, H) :-
+    fd_in([L-H], X).
+
+fd_remove(X, N) :-
+    (  integer(X) -> X =\= N
+    ;  fd_get(X, Dom0, Props),
+       dom_remove(Dom0, N, Dom),
+       fd_update(X, Dom0, Dom, Props)
+    ).
+
+% narrows the domain of X from Dom0 to Dom, running its propagators
+% if it changed. binding X runs them through verify_attributes/3.
+fd_update(X, Dom0, Dom, Props) :-
+    (  Dom == Dom0 -> true
+    ;  Dom = [N-N], integer(N) -> X = N
+    ;  Dom \== [],
+       put_atts(X, +clpz(Dom, Props)),
+       run_props(Props)
+    ).
+
+verify_attributes(X, Value, Goals) :-
+    (  get_atts(X, +clpz(Dom, Props)) ->
+       (  integer(Value) ->
+          dom_contains(Dom, Value),
+          Goals = [clpz:run_props(Props)]
+       ;  var(Value) ->
+          fd_get(Value, Dom0, Props0),
+          dom_intersection(Dom, Dom0, Dom1),
+          Dom1 \== [],
+          append(Props, Props0, Props1),
+          put_atts(Value, +clpz(Dom1, Props1)),
+          (  Dom1 = [N-N], integer(N) -> Goals = [Value = N]
+          ;  Goals = [clpz:run_props(Props1)]
+          )
+       ;  throw(error(type_error(integer, Value), _))
+       )
+    ;  Goals = []
+    ).
+
+attribute_goals(X) -->
+    { get_atts(X, +clpz(Dom, Props)),
+      put_atts(X, -clpz(_, _)) },
+    (  { Dom == [inf-sup] } -> []
+    ;  { dom_expr(Dom, Expr) },
+       [X in Expr]
+    ),
+    props_goals(Props).
+
+% the goals of the live propagators, each of which is marked dead
+% once given so that it's given once among the variables it shares.
+props_goals([]) --> [].
+props_goals([propagator(Prop, Dead) | Props]) -->
+    (  { var(Dead) } ->
+       { Dead = dead,
+         prop_goal(Prop, Goal) },
+       [Goal]
+    ;  []
+    ),
+    props_goals(Props).
+
+prop_goal(lin(Cs, Xs, C0, Rel), Goal) :-
+    lin_normalize(Cs, Xs, C0, Ks, Vs, C),
+    lin_sides(Ks, Vs, Ls0, Rs0),
+    (  C > 0 -> append(Ls0, [C], Ls), Rs = Rs0
+    ;  C < 0 -> N is -C, append(Rs0, [N], Rs), Ls = Ls0
+    ;  Ls = Ls0, Rs = Rs0
+    ),
+    sum_expr(Ls, L),
+    sum_expr(Rs, R),
+    rel_goal(Rel, L, R, Goal).
+prop_goal(prod(X, Y, Z), X*Y #= Z).
+prop_goal(alldiff(Xs), all_different(Xs)).
+
+lin_sides([], [], [], []).
+lin_sides([K | Ks], [V | Vs], Ls, Rs) :-
+    (  K > 0 ->
+       term_expr(K, V, T),
+       Ls = [T | Ls1],
+       Rs = Rs1
+    ;  N is -K,
+       term_expr(N, V, T),
+       Ls = Ls1,
+       Rs = [T | Rs1]
+    ),
+    lin_sides(Ks, Vs, Ls1, Rs1).
+
+term_expr(K, V, T) :-
+    (  K =:= 1 -> T = V
+    ;  T = K*V
+    ).
+
+sum_expr([], 0).
+sum_expr([T | Ts], Expr) :-
+    foldl(plus_expr, Ts, T, Expr).
+
+plus_expr(T, Expr, Expr + T).
+
+rel_goal(=, L, R, L #= R).
+rel_goal(=<, L, R, L #=< R).
+rel_goal(\=, L, R, L #\= R).
+
+/* posting constraints. a linear constraint is given by coefficients
+   Cs, variables Xs and a constant C, meaning sum(Ci*Xi) + C Rel 0. */
+
+post_linear(A, B, Rel) :-
+    linear(A, Ps0, C0),
+    linear(B, Ps1, C1),
+    scale(Ps1, -1, Ps2),
+    append(Ps0, Ps2, Ps),
+    C is C0 - C1,
+    pairs_keys_values(Ps, Xs, Cs),
+    post_lin(Cs, Xs, C, Rel).
+
+post_lin(Cs, Xs, C0, Rel) :-
+    lin_normalize(Cs, Xs, C0, Ks, Vs, C),
+    (  Vs == [] -> rel_holds(Rel, C)
+    ;  post_prop(lin(Ks, Vs, C, Rel), Vs)
+    ).
+
+post_prop(Prop, Xs) :-
+    P = propagator(Prop, _),
+    term_variables(Xs, Vs),
+    maplist(attach(P), Vs),
+    run_prop(P).
+
+attach(P, V) :-
+    fd_get(V, Dom, Props),
+    put_atts(V, +clpz(Dom, [P | Props])).
+
+% the linear form of an expression, as Var-Coefficient pairs and a
+% constant. a product of two non-constant expressions is replaced by
+% a new variable constrained to be their product.
+linear(E, Ps, C) :-
+    (  var(E) -> Ps = [E-1], C = 0
+    ;  integer(E) -> Ps = [], C = E
+    ;  E = A + B ->
+       linear(A, PsA, CA),
+       linear(B, PsB, CB),
+       append(PsA, PsB, Ps),
+       C is CA + CB
+    ;  E = A - B ->
+       linear(A, PsA, CA),
+       linear(B, PsB0, CB),
+       scale(PsB0, -1, PsB),
+       append(PsA, PsB, Ps),
+       C is CA - CB
+    ;  E = -A ->
+       linear(A, PsA, CA),
+       scale(PsA, -1, Ps),
+       C is -CA
+    ;  E = A * B ->
+       linear(A, PsA, CA),
+       linear(B, PsB, CB),
+       (  PsA == [] ->
+          scale(PsB, CA, Ps),
+          C is CA * CB
+       ;  PsB == [] ->
+          scale(PsA, CB, Ps),
+          C is CA * CB
+       ;  product_var(PsA, CA, X),
+          product_var(PsB, CB, Y),
+          post_prop(prod(X, Y, Z), [X, Y, Z]),
+          Ps = [Z-1],
+          C = 0
+       )
+    ;  throw(error(type_error(clpz_expression, E), _))
+    ).
+
+scale([], _, []).
+scale([X-K0 | Ps0], M, [X-K | Ps]) :-
+    K is K0 * M,
+    scale(Ps0, M, Ps).
+
+product_var(Ps, C, X) :-
+    (  Ps = [Y-1], C =:= 0 -> X = Y
+    ;  pairs_keys_values(Ps, Ys, Ks),
+       post_lin([-1 | Ks], [X | Ys], C, =)
+    ).
+
+% the coefficients Ks of the distinct variables Vs of a linear form,
+% with those of its bound variables added to its constant.
+lin_normalize(Cs, Xs, C0, Ks, Vs, C) :-
+    lin_pairs(Xs, Cs, C0, C, Pairs0),
+    keysort(Pairs0, Pairs),
+    merge_pairs(Pairs, Vs, Ks).
+
+lin_pairs([], [], C, C, []).
+lin_pairs([X | Xs], [K | Ks], C0, C, Pairs) :-
+    (  integer(X) ->
+       C1 is C0 + K * X,
+       Pairs = Pairs1
+    ;  C1 = C0,
+       Pairs = [X-K | Pairs1]
+    ),
+    lin_pairs(Xs, Ks, C1, C, Pairs1).
+
+merge_pairs([], [], []).
+merge_pairs([X-K0 | Pairs0], Vs, Ks) :-
+    same_var(Pairs0, X, K0, K, Pairs),
+    (  K =:= 0 -> Vs = Vs1, Ks = Ks1
+    ;  Vs = [X | Vs1], Ks = [K | Ks1]
+    ),
+    merge_pairs(Pairs, Vs1, Ks1).
+
+same_var([Y-K1 | Pairs0], X, K0, K, Pairs) :-
+    Y == X,
+    !,
+    K2 is K0 + K1,
+    same_var(Pairs0, X, K2, K, Pairs).
+same_var(Pairs, _, K, K, Pairs).
+
+rel_holds(=, C) :- C =:= 0.
+rel_holds(=<, C) :- C =< 0.
+rel_holds(\=, C) :- C =\= 0.
+
+/* propagators. each is shared by the variables it constrains, as
+   propagator(Prop, Dead), Dead being bound once it's entailed. */
+
+run_props([]).
+run_props([P | Ps]) :-
+    run_prop(P),
+    run_props(Ps).
+
+run_prop(propagator(Prop, Dead)) :-
+    (  nonvar(Dead) -> true
+    ;  propagate(Prop, Dead)
+    ).
+
+propagate(lin(Cs, Xs, C0, Rel), Dead) :-
+    lin_normalize(Cs, Xs, C0, Ks, Vs, C),
+    (  Vs == [] ->
+       rel_holds(Rel, C),
+       Dead = dead
+    ;  Rel == (\=) ->
+       (  Vs = [V], Ks = [K] ->
+          Dead = dead,
+          (  C mod K =:= 0 ->
+             N is -C // K,
+             fd_remove(V, N)
+          ;  true
+          )
+       ;  true
+       )
+    ;  maplist(fd_bounds, Vs, Los, His),
+       '$fd_linear_propagate'(Rel, Ks, C, Los, His, Los1, His1),
+       % the bounds of a single variable are all there is to it.
+       (  Vs = [_] -> Dead = dead
+       ;  true
+       ),
+       maplist(fd_restrict, Vs, Los1, His1)
+    ).
+propagate(prod(X, Y, Z), Dead) :-
+    (  integer(X), integer(Y) ->
+       Dead = dead,
+       P is X * Y,
+       fd_restrict(Z, P, P)
+    ;  integer(X) ->
+       propagate(lin([X, -1], [Y, Z], 0, =), Dead)
+    ;  integer(Y) ->
+       propagate(lin([Y, -1], [X, Z], 0, =), Dead)
+    ;  fd_bounds(X, XL, XH),
+       fd_bounds(Y, YL, YH),
+       (  integer(XL), integer(XH), integer(YL), integer(YH) ->
+          P1 is XL * YL,
+          P2 is XL * YH,
+          P3 is XH * YL,
+          P4 is XH * YH,
+          L is min(min(P1, P2), min(P3, P4)),
+          H is max(max(P1, P2), max(P3, P4)),
+          fd_restrict(Z, L, H)
+       ;  true
+       )
+    ).
+propagate(alldiff(Xs), Dead) :-
+    integers_vars(Xs, Ns, Vs),
+    sort(Ns, Ns1),
+    length(Ns, N),
+    length(Ns1, N),
+    (  Vs == [] -> Dead = dead
+    ;  maplist(remove_values(Ns), Vs)
+    ).
+
+integers_vars([], [], []).
+integers_vars([X | Xs], Ns, Vs) :-
+    (  integer(X) -> Ns = [X | Ns1], Vs = Vs1
+    ;  Ns = Ns1, Vs = [X | Vs1]
+    ),
+    integers_vars(Xs, Ns1, Vs1).
+
+remove_values(Ns, V) :-
+    maplist(fd_remove(V), Ns).
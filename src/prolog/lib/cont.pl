@@ -1,5 +1,8 @@
+:- module(cont, [reset/3, shift/1,
+                 call_checkpointed/2, checkpoint/0, resume_checkpoint/2]).
 
-:- module(cont, [reset/3, shift/1]).
+:- use_module(library(error)).
+:- use_module(library(iso_ext)).
 
 reset(Goal, Ball, Cont) :-
     call(Goal),
@@ -29,3 +32,84 @@ get_chunks(E, P, L) :-
 call_continuation(L) :- '$call_continuation'(L).
 
 '$write_cont_and_term'(_, _, _, _).
+
+% checkpoints.
+
+% call_checkpointed(Goal, File) calls Goal once. Whenever Goal calls
+% checkpoint/0, the rest of its computation is saved to File as a
+% continuation, together with Goal itself, and resume_checkpoint/2
+% completes it from there in a later process that has loaded the
+% same program. Choicepoints and the database are not saved.
+call_checkpointed(Goal, File) :-
+    must_be(atom, File),
+    run_checkpointed(Goal, Goal, File).
+
+checkpoint :-
+    shift('$checkpoint').
+
+% resume_checkpoint(File, Goal) continues the computation saved in
+% File, unifying Goal with the goal of call_checkpointed/2 once it
+% completes. The code of the continuation is located by predicate,
+% so every predicate it returns to must be loaded, unchanged.
+resume_checkpoint(File, Goal) :-
+    must_be(atom, File),
+    setup_call_cleanup(open(File, read, S),
+                       fast_read(S, Checkpoint),
+                       close(S)),
+    (  Checkpoint = checkpoint(Goal0, Chunks0) ->
+       true
+    ;  throw(error(domain_error(checkpoint, File), resume_checkpoint/2))
+    ),
+    (  located_chunks(Chunks, Chunks0) ->
+       true
+    ;  throw(error(permission_error(resume, checkpoint, File), resume_checkpoint/2))
+    ),
+    continue_checkpointed(Chunks, Goal0, File),
+    Goal = Goal0.
+
+run_checkpointed(Goal, Goal0, File) :-
+    reset(Goal, Ball, Cont),
+    (  Cont == none ->
+       Chunks = []
+    ;  Cont = cont(call_continuation(Chunks))
+    ),
+    (  var(Ball) ->
+       true
+    ;  Ball == '$checkpoint' ->
+       save_checkpoint(File, Goal0, Chunks),
+       continue_checkpointed(Chunks, Goal0, File)
+    ;  shift(Ball),
+       continue_checkpointed(Chunks, Goal0, File)
+    ).
+
+continue_checkpointed([], _, _) :-
+    !.
+continue_checkpointed(Chunks, Goal0, File) :-
+    run_checkpointed(call_continuation(Chunks), Goal0, File).
+
+save_checkpoint(File, Goal0, Chunks) :-
+    (  located_chunks(Chunks, Chunks0) ->
+       true
+    ;  throw(error(representation_error(checkpoint), call_checkpointed/2))
+    ),
+    setup_call_cleanup(open(File, write, S),
+                       write_checkpoint(S, checkpoint(Goal0, Chunks0)),
+                       close(S)).
+
+write_checkpoint(S, Checkpoint) :-
+    '$write_checkpoint'(S, Checkpoint).
+
+% the code pointers of continuation chunks are offsets into the code
+% area, which differ between processes, so checkpoints replace them
+% with their location relative to the predicate containing them.
+located_chunks([], []).
+located_chunks([Chunk|Chunks], [Chunk0|Chunks0]) :-
+    (  nonvar(Chunk) ->
+       Chunk =.. [cont_chunk, CodePtr | Vars],
+       '$code_location'(CodePtr, Location),
+       Chunk0 =.. [cont_chunk, Location | Vars]
+    ;  Chunk0 =.. [cont_chunk, Location | Vars],
+       '$code_location_pointer'(Location, CodePtr),
+       Chunk =.. [cont_chunk, CodePtr | Vars]
+    ),
+    located_chunks(Chunks, Chunks0).
@@ -1,14 +1,39 @@
-:- module(diag, [wam_instructions/2]).
+:- module(diag, [wam_instructions/2, machine_snapshot/1, machine_snapshot/2,
+                 choice_points/1]).
 
 :- use_module(library(error)).
 
+% the instructions of the predicate Name/Arity, or Module:Name/Arity
+% if it is local to Module. jumps of indexing instructions are listed
+% as label(N), N numbering the instructions they jump to, each of
+% which is preceded in Listing by label(N).
 wam_instructions(Clause, Listing) :-
     (  nonvar(Clause) ->
-       Clause = Name / Arity,
-       must_be(atom, Name),
-       must_be(integer, Arity),
-       (  Arity >= 0 -> '$wam_instructions'(Name, Arity, Listing)
-       ;  throw(error(domain_error(not_less_than_zero, Arity), wam_instructions/2))
+       (  Clause = Module:PI ->
+          must_be(atom, Module)
+       ;  Module = user,
+          PI = Clause
+       ),
+       (  nonvar(PI) ->
+          PI = Name / Arity,
+          must_be(atom, Name),
+          must_be(integer, Arity),
+          (  Arity >= 0 -> '$wam_instructions'(Module, Name, Arity, Listing)
+          ;  throw(error(domain_error(not_less_than_zero, Arity), wam_instructions/2))
+          )
+       ;  throw(error(instantiation_error, wam_instructions/2))
        )
     ;  throw(error(instantiation_error, wam_instructions/2))
     ).
+
+machine_snapshot(Snapshot) :-
+    '$machine_snapshot'(0, Snapshot).
+
+machine_snapshot(From, Snapshot) :-
+    must_be(integer, From),
+    (  From >= 0 -> '$machine_snapshot'(From, Snapshot)
+    ;  throw(error(domain_error(not_less_than_zero, From), machine_snapshot/2))
+    ).
+
+choice_points(ChoicePoints) :-
+    '$choice_points'(ChoicePoints).
@@ -39,7 +39,8 @@ verify_attributes(Var, Value, Goals) :-
 % suggestions for improvement.
 
 dif(X, Y) :- X \== Y,
-             (   term_variables(X, XVars), term_variables(Y, YVars),
+             (   X ?= Y -> true % X and Y can never be unified.
+             ;   term_variables(X, XVars), term_variables(Y, YVars),
 	         dif_set_variables(XVars, X, Y),
 		 dif_set_variables(YVars, X, Y)
 	     ).
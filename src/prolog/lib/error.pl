@@ -21,9 +21,27 @@
 
    Currently, the following types are supported:
 
-       - integer
-       - atom
-       - list.
+       - acyclic: a term without cycles
+       - atom, atomic, compound, float, integer, number
+       - boolean: true or false
+       - callable: an atom or compound term
+       - character: an atom of length 1
+       - chars, codes: a list of characters or character codes
+       - goal: a callable term whose control constructs (,)/2, (;)/2
+         and (->)/2 have callable or variable arguments
+       - ground: a term without variables
+       - list: a proper list
+       - list_or_partial_list
+       - nonneg: an integer >= 0
+       - positive_integer: an integer >= 1
+       - type: one of these types
+       - var: an unbound variable.
+
+   Variables are instantiation errors, except for the types var,
+   acyclic and list_or_partial_list. So are partial lists, and lists
+   with unbound elements, for the types list, chars and codes.
+   nonneg and positive_integer raise domain errors for integers out
+   of range, and all other failed checks raise type errors.
 - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
 
 must_be(Type, Term) :-
@@ -33,29 +51,115 @@ must_be(Type, Term) :-
 must_be_(Type, _) :-
         var(Type),
         instantiation_error(Type).
-must_be_(var, Term) :-
-        (   var(Term) -> true
-        ;   throw(error(uninstantiation_error, _))
-        ).
-must_be_(integer, Term) :- check_(integer, integer, Term).
-must_be_(atom, Term)    :- check_(atom, atom, Term).
-must_be_(list, Term)    :- check_(ilist, list, Term).
-must_be_(type, Term)    :- check_(type, type, Term).
-
-check_(Pred, Type, Term) :-
-        (   var(Term) -> instantiation_error(Term)
-        ;   call(Pred, Term) -> true
-        ;   type_error(Type, Term)
+must_be_(Type, Term) :-
+        nonvar(Type),
+        (   has_type(Type, Term) -> true
+        ;   is_not(Type, Term)
         ).
 
-ilist(V) :- var(V), instantiation_error(V).
-ilist([]).
-ilist([_|Ls]) :- ilist(Ls).
-
-type(type).
-type(integer).
+% has_type/2 is indexed on Type, so that each check is a single
+% clause lookup followed by built-in type tests.
+has_type(acyclic, Term)    :- acyclic_term(Term).
+has_type(atom, Term)       :- atom(Term).
+has_type(atomic, Term)     :- atomic(Term).
+has_type(boolean, Term)    :- ( Term == true ; Term == false ), !.
+has_type(callable, Term)   :- callable(Term).
+has_type(character, Term)  :- atom(Term), atom_length(Term, 1).
+has_type(chars, Term)      :- is_list(Term), all_chars(Term).
+has_type(codes, Term)      :- is_list(Term), all_codes(Term).
+has_type(compound, Term)   :- compound(Term).
+has_type(float, Term)      :- float(Term).
+has_type(goal, Term)       :- '$is_callable'(Term, deep).
+has_type(ground, Term)     :- ground(Term).
+has_type(integer, Term)    :- integer(Term).
+has_type(list, Term)       :- is_list(Term).
+has_type(list_or_partial_list, Term) :-
+        '$skip_max_list'(_, -1, Term, Tail),
+        (   var(Tail) -> true
+        ;   Tail == []
+        ).
+has_type(nonneg, Term)     :- integer(Term), Term >= 0.
+has_type(number, Term)     :- number(Term).
+has_type(positive_integer, Term) :- integer(Term), Term >= 1.
+has_type(type, Term)       :- atom(Term), type(Term).
+has_type(var, Term)        :- var(Term).
+
+all_chars([]).
+all_chars([C|Cs]) :-
+        atom(C),
+        atom_length(C, 1),
+        all_chars(Cs).
+
+all_codes([]).
+all_codes([C|Cs]) :-
+        integer(C),
+        C >= 0,
+        C =< 0x10ffff,
+        all_codes(Cs).
+
+is_not(var, Term) :-
+        !,
+        throw(error(uninstantiation_error(Term), _)).
+is_not(Type, Term) :-
+        var(Term),
+        !,
+        instantiation_error(Term).
+is_not(ground, Term) :-
+        !,
+        instantiation_error(Term).
+is_not(Type, Term) :-
+        list_type(Type),
+        '$skip_max_list'(_, -1, Term, Tail),
+        (   var(Tail)
+        ;   Tail == [], Type \== list, \+ ground(Term)
+        ),
+        !,
+        instantiation_error(Term).
+is_not(Type, Term) :-
+        not_type(Type, Term).
+
+list_type(list).
+list_type(chars).
+list_type(codes).
+
+% the error for a nonvar Term that cannot be of Type.
+not_type(nonneg, Term) :-
+        integer(Term),
+        !,
+        domain_error(not_less_than_zero, Term).
+not_type(positive_integer, Term) :-
+        integer(Term),
+        !,
+        domain_error(positive_integer, Term).
+not_type(Type, Term) :-
+        error_type(Type, ErrorType),
+        type_error(ErrorType, Term).
+
+error_type(acyclic, acyclic_term) :- !.
+error_type(goal, callable) :- !.
+error_type(nonneg, integer) :- !.
+error_type(positive_integer, integer) :- !.
+error_type(Type, Type).
+
+type(acyclic).
 type(atom).
+type(atomic).
+type(boolean).
+type(callable).
+type(character).
+type(chars).
+type(codes).
+type(compound).
+type(float).
+type(goal).
+type(ground).
+type(integer).
 type(list).
+type(list_or_partial_list).
+type(nonneg).
+type(number).
+type(positive_integer).
+type(type).
 type(var).
 
 /* - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
@@ -76,17 +180,34 @@ can_be(Type, Term) :-
         must_be(type, Type),
         (   var(Term) -> true
         ;   can_(Type, Term) -> true
-        ;   type_error(Type, Term)
+        ;   Type == var -> is_not(var, Term)
+        ;   not_type(Type, Term)
         ).
 
-can_(integer, Term) :- integer(Term).
-can_(atom, Term)    :- atom(Term).
-can_(list, Term)    :- list_or_partial_list(Term).
-
-list_or_partial_list(Var) :- var(Var).
-list_or_partial_list([]).
-list_or_partial_list([_|Ls]) :-
-        list_or_partial_list(Ls).
+can_(list, Term)  :- !, list_or_partial_list(Term).
+can_(chars, Term) :- !, list_or_partial_list(Term), can_be_chars(Term).
+can_(codes, Term) :- !, list_or_partial_list(Term), can_be_codes(Term).
+can_(ground, _)   :- !.
+can_(Type, Term)  :- has_type(Type, Term).
+
+list_or_partial_list(Term) :-
+        has_type(list_or_partial_list, Term).
+
+can_be_chars(Cs) :- var(Cs), !.
+can_be_chars([]).
+can_be_chars([C|Cs]) :-
+        (   var(C) -> true
+        ;   has_type(character, C)
+        ),
+        can_be_chars(Cs).
+
+can_be_codes(Cs) :- var(Cs), !.
+can_be_codes([]).
+can_be_codes([C|Cs]) :-
+        (   var(C) -> true
+        ;   all_codes([C])
+        ),
+        can_be_codes(Cs).
 
 /* - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
    Shorthands for throwing ISO errors.
@@ -0,0 +1,97 @@
+%% bulk loading of fact bases. load with
+
+%% ?- use_module(library(facts)).
+
+%% load_facts(+File, -Count) reads the facts of File and adds them to
+%% the end of their predicates in the user module, as assertz/1 would,
+%% unifying Count with their number. assertz/1 recompiles a predicate
+%% for every clause it adds, whereas load_facts/2 recompiles each
+%% predicate of File once, after all of its facts are read, so large
+%% data files load in time linear in their size.
+
+%% File may contain only facts, and their predicates must be dynamic
+%% or undefined. these are checked before any fact is added.
+
+:- module(facts, [load_facts/2]).
+
+:- use_module(library(error)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+load_facts(File, Count) :-
+    must_be(atom, File),
+    setup_call_cleanup(open(File, read, S),
+                       read_facts(S, Pairs0),
+                       close(S)),
+    keysort(Pairs0, Pairs),
+    fact_groups(Pairs, Groups),
+    check_modifiable(Groups),
+    add_fact_groups(Groups),
+    length(Pairs, Count).
+
+read_facts(S, Pairs) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S),
+                       read_facts_(Pairs),
+                       set_input(In)).
+
+read_facts_(Pairs) :-
+    read(Term),
+    (  Term == end_of_file ->
+       Pairs = []
+    ;  fact_key(Term, Key),
+       Pairs = [Key-Term | Pairs0],
+       read_facts_(Pairs0)
+    ).
+
+fact_key(Term, Name/Arity) :-
+    (  var(Term) ->
+       throw(error(instantiation_error, load_facts/2))
+    ;  callable(Term) ->
+       functor(Term, Name, Arity),
+       (  rule_or_directive(Name, Arity) ->
+          throw(error(domain_error(fact, Term), load_facts/2))
+       ;  true
+       )
+    ;  throw(error(type_error(callable, Term), load_facts/2))
+    ).
+
+rule_or_directive((:-), 1).
+rule_or_directive((:-), 2).
+rule_or_directive((?-), 1).
+rule_or_directive((:), 2).
+
+% keysort/2 is stable, so the facts of each group keep their order
+% in File.
+fact_groups([], []).
+fact_groups([Key-Fact | Pairs0], [Key-[(Fact :- true) | Clauses] | Groups]) :-
+    same_key_clauses(Pairs0, Key, Clauses, Pairs),
+    fact_groups(Pairs, Groups).
+
+same_key_clauses([], _, [], []).
+same_key_clauses([Key0-Fact | Pairs0], Key, Clauses, Pairs) :-
+    (  Key0 == Key ->
+       Clauses = [(Fact :- true) | Clauses0],
+       same_key_clauses(Pairs0, Key, Clauses0, Pairs)
+    ;  Clauses = [],
+       Pairs = [Key0-Fact | Pairs0]
+    ).
+
+check_modifiable([]).
+check_modifiable([Name/Arity-_ | Groups]) :-
+    functor(Head, Name, Arity),
+    (  '$no_such_predicate'(Head) ->
+       true
+    ;  '$head_is_dynamic'(Head) ->
+       true
+    ;  throw(error(permission_error(modify, static_procedure, Name/Arity),
+                   load_facts/2))
+    ),
+    check_modifiable(Groups).
+
+add_fact_groups([]).
+add_fact_groups([Name/Arity-Clauses | Groups]) :-
+    functor(VarHead, Name, Arity),
+    findall((VarHead :- VarBody), clause(VarHead, VarBody), Clauses0),
+    '$assertz_all'(Clauses, Clauses0, Name, Arity),
+    add_fact_groups(Groups).
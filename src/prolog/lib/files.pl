@@ -0,0 +1,21 @@
+%% deleting files and naming temporary ones. load at the REPL with
+
+%% ?- use_module(library(files)).
+
+%% delete_file(File) removes the file named by the atom File.
+
+%% tmp_file(Base, File) unifies File with the name of a file in the
+%% system's temporary directory that doesn't exist yet. the name ends
+%% in Base, and isn't handed out again by the same process.
+
+:- module(files, [delete_file/1, tmp_file/2]).
+
+:- use_module(library(error)).
+
+delete_file(File) :-
+    must_be(atom, File),
+    '$delete_file'(File).
+
+tmp_file(Base, File) :-
+    must_be(atom, Base),
+    '$tmp_file'(Base, File).
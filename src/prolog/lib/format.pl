@@ -44,6 +44,16 @@
    The predicate format/2 is like format_//2, except that it outputs
    the text on the terminal instead of describing it declaratively.
 
+   format/3 is like format/2, except that its first argument is a
+   sink that receives the formatted text instead of the terminal:
+
+     atom(A)    A is unified with an atom of the formatted text
+     chars(Cs)  Cs is unified with the list of characters
+     codes(Cs)  Cs is unified with the list of character codes
+
+   Numeric directives do not depend on any locale setting: the
+   decimal point is always "." and ~D always groups digits with ",".
+
    If at all possible, format_//2 should be used, to stress pure parts
    that enable easy testing etc. If necessary, you can emit the list Ls
    with maplist(write, Ls).
@@ -58,11 +68,15 @@
    %@    Cs = [h,e,l,l,o,'\n','.','.','.','.','.','.',t,h,e,r,e,!]
    %@ ;  false.
 
+   ?- format(atom(A), "~w-~2f", [x,3]).
+   %@    A = 'x-3.00'.
+
    I place this code in the public domain. Use it in any way you want.
 - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
 
 :- module(format, [format_//2,
-                   format/2
+                   format/2,
+                   format/3
                   ]).
 
 :- use_module(library(dcgs)).
@@ -322,6 +336,22 @@ format(Fs, Args) :-
         phrase(format_(Fs, Args), Cs),
         maplist(write, Cs).
 
+format(Sink, Fs, Args) :-
+        must_be_sink(Sink),
+        phrase(format_(Fs, Args), Cs),
+        sink_chars(Sink, Cs).
+
+must_be_sink(Sink) :-
+        (   var(Sink) -> throw(error(instantiation_error, format/3))
+        ;   functor(Sink, Name, 1),
+            member(Name, [atom,chars,codes]) -> true
+        ;   domain_error(format_sink, Sink)
+        ).
+
+sink_chars(atom(A), Cs)   :- atom_chars(A, Cs).
+sink_chars(chars(Cs), Cs).
+sink_chars(codes(Ls), Cs) :- maplist(char_code, Cs, Ls).
+
 /* - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 ?- phrase(cells("hello", [], 0, []), Cs).
 
@@ -0,0 +1,17 @@
+%% host callbacks: functions of the program embedding the machine,
+%% registered under a name. load at the REPL with
+
+%% ?- use_module(library(host)).
+
+%% host_call(Request, Reply) invokes the callback named by the
+%% principal functor of Request, passing it Request. Reply is
+%% unified with the callback's answer. host_call/2 fails if the
+%% callback declines to answer, and throws any ball it raises.
+
+:- module(host, [host_call/2]).
+
+:- use_module(library(error)).
+
+host_call(Request, Reply) :-
+    must_be(callable, Request),
+    '$host_call'(Request, Reply).
@@ -0,0 +1,227 @@
+%% ISO conformance tests of the built-in predicates, numbered by the
+%% sections of ISO/IEC 13211-1 they are drawn from. run them at the
+%% REPL with
+
+%% ?- use_module(library(iso_conformance)).
+%% ?- run_iso_tests.
+
+%% iso_test(Section, Goal, Expected) expects Goal to succeed, to fail,
+%% or to throw error(Formal, _) for a Formal subsumed by that of
+%% Expected, as Expected is one of succeeds, fails or error(Formal).
+%% run_iso_tests/0 prints every test not meeting its expectation and
+%% fails if there are any.
+
+:- module(iso_conformance, [run_iso_tests/0]).
+
+:- use_module(library(lists)).
+
+run_iso_tests :-
+    findall(Section, iso_test(Section, _, _), Sections),
+    length(Sections, N),
+    findall(failure(Section, Goal, Expected, Result),
+            failed_iso_test(Section, Goal, Expected, Result),
+            Failures),
+    maplist(print_failure, Failures),
+    length(Failures, F),
+    (  F =:= 0 ->
+       write('% all '), write(N), write(' ISO conformance tests passed'), nl
+    ;  write('% '), write(F), write(' of '), write(N),
+       write(' ISO conformance tests failed'), nl,
+       false
+    ).
+
+failed_iso_test(Section, Goal0, Expected, Result) :-
+    iso_test(Section, Goal, Expected),
+    copy_term(Goal, Goal0),
+    run_iso_test(Goal, Result),
+    \+ meets_expectation(Expected, Result).
+
+run_iso_test(Goal, Result) :-
+    catch((  call(Goal) -> Result = succeeds
+          ;  Result = fails
+          ),
+          Ball,
+          Result = throws(Ball)).
+
+meets_expectation(succeeds, succeeds).
+meets_expectation(fails, fails).
+meets_expectation(error(Formal), throws(error(Formal0, _))) :-
+    subsumes_term(Formal, Formal0).
+
+print_failure(failure(Section, Goal, Expected, Result)) :-
+    write('% '), write(Section), write(': '), writeq(Goal),
+    (  Result = throws(Ball) ->
+       write(' threw '), writeq(Ball)
+    ;  write(' '), write(Result)
+    ),
+    write(', expected '), writeq(Expected), nl.
+
+% 7.8 control constructs.
+
+iso_test('7.8.3', call(true), succeeds).
+iso_test('7.8.3', call(fail), fails).
+iso_test('7.8.3', call(_), error(instantiation_error)).
+iso_test('7.8.3', call(1), error(type_error(callable, 1))).
+iso_test('7.8.3', call(iso_undefined_procedure), error(existence_error(procedure, _))).
+iso_test('7.8.4', ((X = 1 ; X = 2), X == 2), succeeds).
+iso_test('7.8.7', (fail -> true ; true), succeeds).
+iso_test('7.8.9', catch(throw(ball), ball, true), succeeds).
+iso_test('7.8.9', catch(catch(throw(ball), other, fail), ball, true), succeeds).
+iso_test('7.8.9', catch(true, _, fail), succeeds).
+
+% 8.2 term unification.
+
+iso_test('8.2.1', (f(X, a) = f(b, Y), X == b, Y == a), succeeds).
+iso_test('8.2.1', f(a) = g(a), fails).
+iso_test('8.2.3', 1 \= 1, fails).
+iso_test('8.2.3', f(_) \= g(_), succeeds).
+
+% 8.3 type testing.
+
+iso_test('8.3.1', var(_), succeeds).
+iso_test('8.3.2', atom([]), succeeds).
+iso_test('8.3.2', atom("ab"), fails).
+iso_test('8.3.5', atomic(1.5), succeeds).
+iso_test('8.3.6', compound(-(1)), succeeds).
+iso_test('8.3.6', compound(-1), fails).
+iso_test('8.3.10', number(a), fails).
+
+% 8.4 term comparison.
+
+iso_test('8.4.1', (compare(O, a, b), O == (<)), succeeds).
+iso_test('8.4.1', 1.0 @< 1, succeeds).
+iso_test('8.4.1', f(b) @< g(a), succeeds).
+
+% 8.5 term creation and decomposition.
+
+iso_test('8.5.1', (functor(foo(a, b), N, A), N == foo, A == 2), succeeds).
+iso_test('8.5.1', (functor(F, foo, 0), F == foo), succeeds).
+iso_test('8.5.1', functor(_, _, 3), error(instantiation_error)).
+iso_test('8.5.1', functor(_, foo, _), error(instantiation_error)).
+iso_test('8.5.1', functor(_, foo, a), error(type_error(integer, a))).
+iso_test('8.5.1', functor(_, foo(a), 1), error(type_error(atomic, foo(a)))).
+iso_test('8.5.1', functor(_, foo, -1), error(domain_error(not_less_than_zero, -1))).
+iso_test('8.5.2', (arg(1, foo(a, b), A), A == a), succeeds).
+iso_test('8.5.2', arg(0, foo(a), _), fails).
+iso_test('8.5.2', arg(_, foo(a), _), error(instantiation_error)).
+iso_test('8.5.2', arg(1, _, _), error(instantiation_error)).
+iso_test('8.5.2', arg(-1, foo(a), _), error(domain_error(not_less_than_zero, -1))).
+iso_test('8.5.3', (foo(a, b) =.. L, L == [foo, a, b]), succeeds).
+iso_test('8.5.3', (T =.. [foo, a], T == foo(a)), succeeds).
+iso_test('8.5.3', _ =.. _, error(instantiation_error)).
+iso_test('8.5.3', _ =.. [foo|bar], error(type_error(list, [foo|bar]))).
+iso_test('8.5.3', _ =.. [_, a], error(instantiation_error)).
+iso_test('8.5.3', _ =.. [f(a), b], error(type_error(atom, f(a)))).
+iso_test('8.5.3', _ =.. [f(a)], error(type_error(atomic, f(a)))).
+iso_test('8.5.3', _ =.. [], error(domain_error(non_empty_list, []))).
+iso_test('8.5.4', (copy_term(f(X, Y, X), f(A, B, C)), A == C, A \== B), succeeds).
+iso_test('8.5.4', (copy_term(X, Y), X \== Y), succeeds).
+
+% 8.6 arithmetic evaluation.
+
+iso_test('8.6.1', (X is 1 + 2, X == 3), succeeds).
+iso_test('8.6.1', _ is _ + 1, error(instantiation_error)).
+iso_test('8.6.1', _ is foo + 1, error(type_error(evaluable, foo/0))).
+iso_test('8.6.1', _ is 1 / 0, error(evaluation_error(zero_divisor))).
+iso_test('8.6.1', _ is 1 // 0, error(evaluation_error(zero_divisor))).
+iso_test('8.6.1', _ is 1 mod 0, error(evaluation_error(zero_divisor))).
+iso_test('8.6.1', _ is 1 rem 0, error(evaluation_error(zero_divisor))).
+
+% 8.7 arithmetic comparison.
+
+iso_test('8.7.1', 1 =:= 1.0, succeeds).
+iso_test('8.7.1', 1 < 0, fails).
+iso_test('8.7.1', _ < 1, error(instantiation_error)).
+iso_test('8.7.1', a < 1, error(type_error(evaluable, a/0))).
+
+% 8.9 clause creation and destruction.
+
+iso_test('8.9.1', asserta(_), error(instantiation_error)).
+iso_test('8.9.1', asserta(4), error(type_error(callable, 4))).
+iso_test('8.9.1', asserta((foo :- 4)), error(type_error(callable, 4))).
+iso_test('8.9.2', assertz(_), error(instantiation_error)).
+iso_test('8.9.2', assertz(4), error(type_error(callable, 4))).
+iso_test('8.9.2', assertz((foo :- 4)), error(type_error(callable, 4))).
+iso_test('8.9.3', retract(_), error(instantiation_error)).
+iso_test('8.9.3', retract(4), error(type_error(callable, 4))).
+iso_test('8.9.4', abolish(_), error(instantiation_error)).
+iso_test('8.9.4', abolish(foo/_), error(instantiation_error)).
+iso_test('8.9.4', abolish(foo), error(type_error(predicate_indicator, foo))).
+iso_test('8.9.4', abolish(5/2), error(type_error(atom, 5))).
+iso_test('8.9.4', abolish(foo/a), error(type_error(integer, a))).
+iso_test('8.9.4', abolish(foo/(-1)), error(domain_error(not_less_than_zero, -1))).
+
+% 8.10 all solutions.
+
+iso_test('8.10.1', findall(X, (X = 1 ; X = 2), [1, 2]), succeeds).
+iso_test('8.10.1', findall(X, fail, [X]), fails).
+iso_test('8.10.1', findall(_, _, _), error(instantiation_error)).
+iso_test('8.10.1', findall(_, 4, _), error(type_error(callable, 4))).
+iso_test('8.10.1', findall(X, true, [a|b]), error(type_error(list, [a|b]))).
+iso_test('8.10.2', (bagof(X, (X = 2 ; X = 1), L), L == [2, 1]), succeeds).
+iso_test('8.10.2', bagof(_, fail, _), fails).
+iso_test('8.10.2', bagof(_, _, _), error(instantiation_error)).
+iso_test('8.10.3', (setof(X, member(X, [c, a, b, a]), L), L == [a, b, c]), succeeds).
+
+% 8.14 term input/output.
+
+iso_test('8.14.3', op(_, xfx, ++), error(instantiation_error)).
+iso_test('8.14.3', op(200, _, ++), error(instantiation_error)).
+iso_test('8.14.3', op(a, xfx, ++), error(type_error(integer, a))).
+iso_test('8.14.3', op(1201, xfx, ++), error(domain_error(operator_priority, 1201))).
+iso_test('8.14.3', op(200, yyy, ++), error(domain_error(operator_specifier, yyy))).
+iso_test('8.14.3', op(200, xfx, 1), error(type_error(list, 1))).
+iso_test('8.14.3', op(1000, xfy, ','), error(permission_error(modify, operator, ','))).
+iso_test('8.14.4', current_op(1200, xfx, (:-)), succeeds).
+
+% 8.15 logic and control.
+
+iso_test('8.15.1', \+ true, fails).
+iso_test('8.15.1', \+ _, error(instantiation_error)).
+iso_test('8.15.2', once(_), error(instantiation_error)).
+iso_test('8.15.2', (once((X = 1 ; X = 2)), X == 1), succeeds).
+
+% 8.16 atomic term processing.
+
+iso_test('8.16.1', atom_length(abc, 3), succeeds).
+iso_test('8.16.1', atom_length(_, _), error(instantiation_error)).
+iso_test('8.16.1', atom_length(123, _), error(type_error(atom, 123))).
+iso_test('8.16.1', atom_length(abc, a), error(type_error(integer, a))).
+iso_test('8.16.1', atom_length(abc, -1), error(domain_error(not_less_than_zero, -1))).
+iso_test('8.16.2', atom_concat(ab, cd, abcd), succeeds).
+iso_test('8.16.2', findall(X-Y, atom_concat(X, Y, ab), [''-ab, a-b, ab-'']), succeeds).
+iso_test('8.16.2', atom_concat(_, b, _), error(instantiation_error)).
+iso_test('8.16.2', atom_concat(f(a), b, _), error(type_error(atom, f(a)))).
+iso_test('8.16.3', findall(B, sub_atom(abracadabra, B, 2, _, ab), [0, 7]), succeeds).
+iso_test('8.16.3', sub_atom(_, _, _, _, _), error(instantiation_error)).
+iso_test('8.16.3', sub_atom(f(a), _, _, _, _), error(type_error(atom, f(a)))).
+iso_test('8.16.3', sub_atom(abc, -1, _, _, _), error(domain_error(not_less_than_zero, -1))).
+iso_test('8.16.4', (atom_chars(A, [a, b]), A == ab), succeeds).
+iso_test('8.16.4', atom_chars(_, _), error(instantiation_error)).
+iso_test('8.16.4', atom_chars(_, [a|_]), error(instantiation_error)).
+iso_test('8.16.4', atom_chars(f(a), _), error(type_error(atom, f(a)))).
+iso_test('8.16.5', (atom_codes(abc, L), L == [0'a, 0'b, 0'c]), succeeds).
+iso_test('8.16.5', atom_codes(_, _), error(instantiation_error)).
+iso_test('8.16.5', atom_codes(_, [0'a, -1]), error(representation_error(character_code))).
+iso_test('8.16.6', (char_code(a, C), C == 0'a), succeeds).
+iso_test('8.16.6', char_code(_, _), error(instantiation_error)).
+iso_test('8.16.6', char_code(ab, _), error(type_error(character, ab))).
+iso_test('8.16.6', char_code(_, a), error(type_error(integer, a))).
+iso_test('8.16.7', (number_chars(N, ['1', '2']), N == 12), succeeds).
+iso_test('8.16.7', number_chars(_, _), error(instantiation_error)).
+iso_test('8.16.7', number_chars(a, _), error(type_error(number, a))).
+iso_test('8.16.7', number_chars(_, [a]), error(syntax_error(_))).
+iso_test('8.16.8', (number_codes(N, [0'1, 0'2]), N == 12), succeeds).
+iso_test('8.16.8', number_codes(_, _), error(instantiation_error)).
+iso_test('8.16.8', number_codes(_, [0'a]), error(syntax_error(_))).
+
+% 8.17 implementation defined hooks.
+
+iso_test('8.17.1', set_prolog_flag(_, true), error(instantiation_error)).
+iso_test('8.17.1', set_prolog_flag(5, true), error(type_error(atom, 5))).
+iso_test('8.17.1', set_prolog_flag(date, true), error(domain_error(prolog_flag, date))).
+iso_test('8.17.1', set_prolog_flag(double_quotes, foo),
+         error(domain_error(flag_value, double_quotes + foo))).
+iso_test('8.17.2', current_prolog_flag(bounded, false), succeeds).
+iso_test('8.17.2', current_prolog_flag(5, _), error(type_error(atom, 5))).
+iso_test('8.17.2', current_prolog_flag(date, _), error(domain_error(prolog_flag, date))).
@@ -4,10 +4,19 @@
 %% ?- use_module(library(iso_ext)).
 
 :- module(iso_ext, [bb_b_put/2, bb_get/2, bb_put/2, call_cleanup/2,
-		    call_with_inference_limit/3, forall/2, maybe/0,
+		    call_with_inference_limit/3, call_with_resource_limits/2,
+		    call_with_time_limit/2,
+		    forall/2, maybe/0, maybe/1, maybe/2, null_stream/1,
+		    open_memory_stream/3,
 		    partial_string/1, partial_string/3,
-		    partial_string_tail/2, set_random/1,
-		    setup_call_cleanup/3, variant/2]).
+		    partial_string_tail/2, prolog_current_choice/1,
+		    prolog_cut_to/1, random_member/2, random_select/3,
+		    sandboxed_call/1,
+		    sandboxed_call/2, set_random/1,
+		    setup_call_cleanup/3, variant/2, variant_hash/2,
+		    with_flags/2,
+		    with_open_file/3, with_output_to/2,
+		    with_output_to_stream/2]).
 
 forall(Generate, Test) :-
     \+ (Generate, \+ Test).
@@ -46,6 +55,183 @@ bb_get(Key, _) :- throw(error(type_error(atom, Key), bb_get/2)).
 call_cleanup(G, C) :- setup_call_cleanup(true, G, C).
 
 
+%% capturing and comparing cut barriers.
+
+%% Choice is the most recent choice point, as an opaque cut barrier.
+%% two barriers compare equal under ==/2 iff no choice point was
+%% created or removed between capturing them. prolog_cut_to(Choice)
+%% removes the choice points created since Choice was captured, as
+%% !/0 does for those created since entering a clause, and so lets
+%% libraries implement their own control constructs.
+
+prolog_current_choice(Choice) :- '$get_b_value'(Choice).
+
+prolog_cut_to(Choice) :-
+    (  var(Choice) ->
+       throw(error(instantiation_error, prolog_cut_to/1))
+    ;  '$set_cp'(Choice)
+    ).
+
+% opens File in Mode, calls Goal with the stream bound to Stream, and
+% closes the stream once Goal exits deterministically, fails, raises
+% an exception or has its choicepoints cut.
+
+with_open_file(File, Mode, Lambda) :-
+    (  var(Lambda) ->
+       throw(error(instantiation_error, with_open_file/3))
+    ;  Lambda = Stream^Goal ->
+       setup_call_cleanup(open(File, Mode, Stream), Goal, close(Stream))
+    ;  throw(error(type_error(lambda, Lambda), with_open_file/3))
+    ).
+
+% Stream is a new output stream discarding everything written to it.
+
+null_stream(Stream) :-
+    (  var(Stream) ->
+       '$null_stream'(Stream)
+    ;  throw(error(uninstantiation_error(Stream), null_stream/1))
+    ).
+
+% Stream is a new input stream reading Text, an atom or a list of
+% characters or codes, from memory. the only option is type(T), where
+% T is text, the default, or binary. Stream is closed by close/1 as
+% any other.
+
+open_memory_stream(Text, Stream, Options) :-
+    (  var(Text) ->
+       throw(error(instantiation_error, open_memory_stream/3))
+    ;  nonvar(Stream) ->
+       throw(error(uninstantiation_error(Stream), open_memory_stream/3))
+    ;  memory_stream_options(Options, text, Type),
+       memory_stream_chars(Text, Chars),
+       '$open_memory_stream'(Chars, Type, Stream)
+    ).
+
+memory_stream_options(Options, _, _) :-
+    var(Options),
+    throw(error(instantiation_error, open_memory_stream/3)).
+memory_stream_options([], Type, Type) :- !.
+memory_stream_options([Option | Options], Type0, Type) :-
+    !,
+    (  var(Option) ->
+       throw(error(instantiation_error, open_memory_stream/3))
+    ;  Option = type(Type1), ( Type1 == text ; Type1 == binary ) ->
+       memory_stream_options(Options, Type1, Type)
+    ;  throw(error(domain_error(stream_option, Option), open_memory_stream/3))
+    ).
+memory_stream_options(Options, _, _) :-
+    throw(error(type_error(list, Options), open_memory_stream/3)).
+
+memory_stream_chars(Text, Chars) :-
+    (  Text == [] ->
+       Chars = []
+    ;  atom(Text) ->
+       atom_chars(Text, Chars)
+    ;  Text = [C | _], integer(C) ->
+       codes_to_chars(Text, Chars)
+    ;  Chars = Text
+    ).
+
+codes_to_chars(Codes, _) :-
+    var(Codes),
+    throw(error(instantiation_error, open_memory_stream/3)).
+codes_to_chars([], []) :- !.
+codes_to_chars([Code | Codes], [C | Cs]) :-
+    !,
+    (  var(Code) ->
+       throw(error(instantiation_error, open_memory_stream/3))
+    ;  catch(char_code(C, Code), error(E, _), throw(error(E, open_memory_stream/3)))
+    ),
+    codes_to_chars(Codes, Cs).
+codes_to_chars(Codes, _) :-
+    throw(error(type_error(list, Codes), open_memory_stream/3)).
+
+% calls Goal with the current output set to Stream. the previous
+% current output is restored when Goal exits, fails, raises an
+% exception or has its choicepoints cut, and Stream set again on
+% backtracking into Goal.
+
+with_output_to_stream(Stream, Goal) :-
+    current_output(OldStream),
+    setup_call_cleanup(set_output(Stream),
+                       call_with_exit_redo(Goal, set_output(OldStream), set_output(Stream)),
+                       set_output(OldStream)).
+
+% calls Goal, calling Exit when Goal succeeds leaving choicepoints
+% and Redo on backtracking into them, so that a state set up around
+% Goal is undone while its caller runs and set again while Goal runs.
+% a deterministic exit leaves the undoing to setup_call_cleanup/3.
+call_with_exit_redo(Goal, Exit, Redo) :-
+    call(Goal),
+    deterministic(Det),
+    (  Det == true ->
+       true
+    ;  call(Exit)
+    ;  call(Redo),
+       '$fail'
+    ).
+
+% calls Goal once, collecting what it writes to the current output
+% into Sink, which is one of atom(A), string(S), codes(Cs),
+% codes(Cs, Tail), chars(Cs) or chars(Cs, Tail). a string is a list
+% as the double_quotes flag has it, or an atom under double_quotes
+% atom.
+
+with_output_to(Sink, Goal) :-
+    must_be_output_sink(Sink),
+    '$capture_output'(Stream),
+    setup_call_cleanup(true,
+                       ( with_output_to_stream(Stream, once(Goal)),
+                         '$captured_output'(Stream, Chars)
+                       ),
+                       close(Stream)),
+    output_sink(Sink, Chars).
+
+must_be_output_sink(Sink) :-
+    (  var(Sink) ->
+       throw(error(instantiation_error, with_output_to/2))
+    ;  output_sink_form(Sink) ->
+       true
+    ;  throw(error(domain_error(output_sink, Sink), with_output_to/2))
+    ).
+
+output_sink_form(atom(_)).
+output_sink_form(string(_)).
+output_sink_form(codes(_)).
+output_sink_form(codes(_, _)).
+output_sink_form(chars(_)).
+output_sink_form(chars(_, _)).
+
+output_sink(atom(A), Chars) :-
+    atom_chars(A, Chars).
+output_sink(string(S), Chars) :-
+    current_prolog_flag(double_quotes, DQ),
+    output_sink_string(DQ, S, Chars).
+output_sink(codes(Cs), Chars) :-
+    chars_to_codes(Chars, Cs, []).
+output_sink(codes(Cs, Tail), Chars) :-
+    chars_to_codes(Chars, Cs, Tail).
+output_sink(chars(Cs), Chars) :-
+    Cs = Chars.
+output_sink(chars(Cs, Tail), Chars) :-
+    chars_to_chars(Chars, Cs, Tail).
+
+output_sink_string(chars, S, Chars) :-
+    S = Chars.
+output_sink_string(codes, S, Chars) :-
+    chars_to_codes(Chars, S, []).
+output_sink_string(atom, S, Chars) :-
+    atom_chars(S, Chars).
+
+chars_to_chars([], Tail, Tail).
+chars_to_chars([C|Cs], [C|Cs1], Tail) :-
+    chars_to_chars(Cs, Cs1, Tail).
+
+chars_to_codes([], Tail, Tail).
+chars_to_codes([C|Cs], [Code|Codes], Tail) :-
+    char_code(C, Code),
+    chars_to_codes(Cs, Codes, Tail).
+
 % setup_call_cleanup.
 
 setup_call_cleanup(S, G, C) :-
@@ -109,7 +295,7 @@ end_block(B, Bb, NBb, L) :-
     '$fail'.
 
 :- non_counted_backtracking handle_ile/3.
-handle_ile(B, inference_limit_exceeded(B), inference_limit_exceeded) :- !.
+handle_ile(B, '$inference_limit_exceeded'(B), inference_limit_exceeded) :- !.
 handle_ile(B, E, _) :-
     '$remove_call_policy_check'(B),
     '$call_with_default_policy'(throw(E)).
@@ -141,20 +327,251 @@ call_with_inference_limit(_, _, R, Bb, B) :-
     '$erase_ball',
     '$call_with_default_policy'(handle_ile(B, Ball, R)).
 
+% call_with_time_limit
+
+call_with_time_limit(Time, G) :-
+    (  var(Time) ->
+       throw(error(instantiation_error, call_with_time_limit/2))
+    ;  integer(Time) ->
+       Time > 0
+    ;  float(Time) ->
+       Time > 0.0
+    ;  throw(error(type_error(number, Time), call_with_time_limit/2))
+    ),
+    !,
+    '$get_current_block'(Bb),
+    '$get_b_value'(B),
+    '$call_with_default_policy'(call_with_time_limit(G, Time, Bb, B)),
+    !,
+    '$remove_call_policy_check'(B).
+call_with_time_limit(_, _) :-
+    throw(time_limit_exceeded).
+
+:- non_counted_backtracking call_with_time_limit/4.
+call_with_time_limit(G, Time, Bb, B) :-
+    '$install_new_block'(NBb),
+    '$install_time_limit'(B, Time),
+    call(G),
+    '$remove_time_limit'(B),
+    '$clean_up_block'(NBb),
+    '$reset_block'(Bb).
+call_with_time_limit(_, _, Bb, B) :-
+    '$reset_block'(Bb),
+    '$remove_time_limit'(B),
+    (  '$get_ball'(Ball),
+       '$get_level'(Cp),
+       '$set_cp_by_default'(Cp)
+    ;  '$remove_call_policy_check'(B),
+       '$fail'
+    ),
+    '$erase_ball',
+    '$call_with_default_policy'(handle_tle(B, Ball)).
+
+:- non_counted_backtracking handle_tle/2.
+handle_tle(B, '$time_limit_exceeded'(B)) :-
+    !,
+    '$remove_call_policy_check'(B),
+    '$call_with_default_policy'(throw(time_limit_exceeded)).
+handle_tle(B, E) :-
+    '$remove_call_policy_check'(B),
+    '$call_with_default_policy'(throw(E)).
+
+% call_with_resource_limits
+
+%% call_with_resource_limits(Goal, Limits) calls Goal once, within the
+%% limits of the list Limits, of which each of inferences(N), time(T)
+%% (in seconds) and memory(Cells) (the number of cells the heap may
+%% grow by) may appear at most once. exceeding any of them throws
+%% resource_limit_exceeded(Which), with Which one of inferences, time
+%% and memory.
+
+call_with_resource_limits(G, Limits) :-
+    resource_limits(Limits, none, Inferences, none, Time, none, Memory),
+    (  Inferences == none, Time == none, Memory == none ->
+       call(G),
+       !
+    ;  '$get_current_block'(Bb),
+       '$get_b_value'(B),
+       '$call_with_default_policy'(call_with_resource_limits(G, Inferences, Time, Memory, Bb, B)),
+       !,
+       '$remove_call_policy_check'(B)
+    ).
+
+:- non_counted_backtracking call_with_resource_limits/6.
+call_with_resource_limits(G, Inferences, Time, Memory, Bb, B) :-
+    '$install_new_block'(NBb),
+    (  Time == none -> true
+    ;  '$install_time_limit'(B, Time)
+    ),
+    (  Memory == none -> true
+    ;  '$install_memory_limit'(B, Memory)
+    ),
+    (  Inferences == none -> true
+    ;  '$install_inference_counter'(B, Inferences, _)
+    ),
+    call(G),
+    '$remove_inference_counter'(B, _),
+    '$remove_time_limit'(B),
+    '$remove_memory_limit'(B),
+    '$clean_up_block'(NBb),
+    '$reset_block'(Bb).
+call_with_resource_limits(_, _, _, _, Bb, B) :-
+    '$reset_block'(Bb),
+    '$remove_inference_counter'(B, _),
+    '$remove_time_limit'(B),
+    '$remove_memory_limit'(B),
+    (  '$get_ball'(Ball),
+       '$get_level'(Cp),
+       '$set_cp_by_default'(Cp)
+    ;  '$remove_call_policy_check'(B),
+       '$fail'
+    ),
+    '$erase_ball',
+    '$call_with_default_policy'(handle_rle(B, Ball)).
+
+:- non_counted_backtracking handle_rle/2.
+handle_rle(B, Ball) :-
+    '$remove_call_policy_check'(B),
+    (  exceeded_resource_limit(Ball, B, Which) ->
+       '$call_with_default_policy'(throw(resource_limit_exceeded(Which)))
+    ;  '$call_with_default_policy'(throw(Ball))
+    ).
+
+exceeded_resource_limit('$inference_limit_exceeded'(B), B, inferences).
+exceeded_resource_limit('$time_limit_exceeded'(B), B, time).
+exceeded_resource_limit('$memory_limit_exceeded'(B), B, memory).
+
+resource_limits(Limits, I0, I, T0, T, M0, M) :-
+    '$skip_max_list'(_, -1, Limits, Tail),
+    (  var(Tail) ->
+       throw(error(instantiation_error, call_with_resource_limits/2))
+    ;  Tail == [] ->
+       resource_limits_(Limits, I0, I, T0, T, M0, M)
+    ;  throw(error(type_error(list, Limits), call_with_resource_limits/2))
+    ).
+
+resource_limits_([], I, I, T, T, M, M).
+resource_limits_([Limit | Limits], I0, I, T0, T, M0, M) :-
+    (  var(Limit) ->
+       throw(error(instantiation_error, call_with_resource_limits/2))
+    ;  Limit = inferences(N), I0 == none ->
+       must_be_limit(integer, N),
+       resource_limits_(Limits, N, I, T0, T, M0, M)
+    ;  Limit = time(Secs), T0 == none ->
+       must_be_limit(number, Secs),
+       resource_limits_(Limits, I0, I, Secs, T, M0, M)
+    ;  Limit = memory(Cells), M0 == none ->
+       must_be_limit(integer, Cells),
+       resource_limits_(Limits, I0, I, T0, T, Cells, M)
+    ;  throw(error(domain_error(resource_limit, Limit), call_with_resource_limits/2))
+    ).
+
+must_be_limit(Type, N) :-
+    (  var(N) ->
+       throw(error(instantiation_error, call_with_resource_limits/2))
+    ;  integer(N) ->
+       (  N < 0 ->
+          throw(error(domain_error(not_less_than_zero, N), call_with_resource_limits/2))
+       ;  true
+       )
+    ;  Type == number, float(N) ->
+       (  N < 0.0 ->
+          throw(error(domain_error(not_less_than_zero, N), call_with_resource_limits/2))
+       ;  true
+       )
+    ;  throw(error(type_error(Type, N), call_with_resource_limits/2))
+    ).
+
 variant(X, Y) :- '$variant'(X, Y).
 
+% Hash is a non-negative integer hash of Term that is the same for
+% every variant of Term, so it can key tables of calls up to variable
+% renaming. cyclic terms have no variant hash.
+variant_hash(Term, Hash) :-
+    (  acyclic_term(Term) ->
+       '$variant_hash'(Term, Hash)
+    ;  throw(error(type_error(acyclic_term, Term), variant_hash/2))
+    ).
+
+%% random choice. every draw is from the one random number generator,
+%% so that once set_random(seed(S)) is called, the same choices are
+%% made on every machine. statistics(random_draws, N) counts the
+%% draws made since.
+
 % succeeds with probability 0.5.
 maybe :- '$maybe'.
 
-set_random(Seed) :-
-    (  nonvar(Seed) ->
-       (  Seed = seed(S) ->
-	  (  var(S) -> throw(error(instantiation_error, set_random/1))
-	  ;  integer(S) -> '$set_seed'(S)
-	  ;  throw(error(type_error(integer(S), set_random/1)))
-	  )
+% succeeds with probability P, a float from 0.0 to 1.0.
+maybe(P) :-
+    (  var(P) -> throw(error(instantiation_error, maybe/1))
+    ;  \+ float(P) -> throw(error(type_error(float, P), maybe/1))
+    ;  ( P < 0.0 ; P > 1.0 ) -> throw(error(domain_error(probability, P), maybe/1))
+    ;  '$random_float'(F),
+       F < P
+    ).
+
+% succeeds with probability K/N, for integers K and N with
+% 0 =< K =< N and N > 0.
+maybe(K, N) :-
+    (  ( var(K) ; var(N) ) -> throw(error(instantiation_error, maybe/2))
+    ;  \+ integer(K) -> throw(error(type_error(integer, K), maybe/2))
+    ;  \+ integer(N) -> throw(error(type_error(integer, N), maybe/2))
+    ;  N =< 0 -> throw(error(domain_error(positive_integer, N), maybe/2))
+    ;  ( K < 0 ; K > N ) -> throw(error(domain_error(probability, K/N), maybe/2))
+    ;  '$random_integer'(N, R),
+       R < K
+    ).
+
+% X is an element of the proper list List, chosen uniformly. fails
+% if List is empty.
+random_member(X, List) :-
+    '$skip_max_list'(N, -1, List, Tail),
+    (  Tail == [] ->
+       N > 0,
+       '$random_integer'(N, I),
+       select_nth0(I, List, X, _)
+    ;  var(Tail) -> throw(error(instantiation_error, random_member/2))
+    ;  throw(error(type_error(list, List), random_member/2))
+    ).
+
+% X is an element of the proper list List chosen uniformly, and Rest
+% the remaining elements. if List is partial, X is inserted into the
+% proper list Rest at a uniformly chosen position instead.
+random_select(X, List, Rest) :-
+    '$skip_max_list'(N, -1, List, Tail),
+    (  Tail == [] ->
+       N > 0,
+       '$random_integer'(N, I),
+       select_nth0(I, List, X, Rest)
+    ;  var(Tail) ->
+       '$skip_max_list'(M, -1, Rest, RestTail),
+       (  RestTail == [] ->
+          M1 is M + 1,
+          '$random_integer'(M1, I),
+          select_nth0(I, List, X, Rest)
+       ;  var(RestTail) -> throw(error(instantiation_error, random_select/3))
+       ;  throw(error(type_error(list, Rest), random_select/3))
+       )
+    ;  throw(error(type_error(list, List), random_select/3))
+    ).
+
+select_nth0(0, [X | Xs], X, Xs) :-
+    !.
+select_nth0(I, [Y | Ys], X, [Y | Xs]) :-
+    I1 is I - 1,
+    select_nth0(I1, Ys, X, Xs).
+
+% set_random(seed(S)) seeds the random number generator with the
+% integer S, or with a seed differing from run to run if S is random.
+set_random(Option) :-
+    (  var(Option) -> throw(error(instantiation_error, set_random/1))
+    ;  Option = seed(S) ->
+       (  var(S) -> throw(error(instantiation_error, set_random/1))
+       ;  S == random -> '$set_seed'(random)
+       ;  integer(S) -> '$set_seed'(S)
+       ;  throw(error(type_error(integer, S), set_random/1))
        )
-    ;  throw(error(instantiation_error, set_random/1))
+    ;  throw(error(domain_error(set_random_option, Option), set_random/1))
     ).
 
 partial_string(String, L, L0) :-
@@ -174,3 +591,126 @@ partial_string_tail(String, Tail) :-
        '$partial_string_tail'(String, Tail)
     ;  throw(error(type_error(partial_string, String), partial_string_tail/2))
     ).
+
+%% with_flags(FlagValues, Goal) calls Goal with the prolog flags set
+%% as in the list FlagValues of Flag-Value pairs. the previous values
+%% are restored when Goal exits, fails, raises an exception or has its
+%% choicepoints cut, and FlagValues set again on backtracking into Goal.
+
+with_flags(FlagValues, Goal) :-
+    must_be_flag_values(FlagValues),
+    current_flag_values(FlagValues, OldFlagValues),
+    setup_call_cleanup(swap_flag_values(FlagValues, OldFlagValues),
+                       call_with_exit_redo(Goal,
+                                           set_flag_values(OldFlagValues),
+                                           set_flag_values(FlagValues)),
+                       set_flag_values(OldFlagValues)).
+
+% sets FlagValues, or restores OldFlagValues if any of them can't be set.
+swap_flag_values(FlagValues, OldFlagValues) :-
+    (  catch(set_flag_values(FlagValues), E,
+             (set_flag_values(OldFlagValues), throw(E))) ->
+       true
+    ;  set_flag_values(OldFlagValues),
+       '$fail'
+    ).
+
+must_be_flag_values(FlagValues) :-
+    '$skip_max_list'(_, -1, FlagValues, Tail),
+    (  var(Tail) ->
+       throw(error(instantiation_error, with_flags/2))
+    ;  Tail == [] ->
+       must_be_flag_value_pairs(FlagValues)
+    ;  throw(error(type_error(list, FlagValues), with_flags/2))
+    ).
+
+must_be_flag_value_pairs([]).
+must_be_flag_value_pairs([FlagValue | FlagValues]) :-
+    (  var(FlagValue) ->
+       throw(error(instantiation_error, with_flags/2))
+    ;  FlagValue = Flag - Value ->
+       (  var(Flag) -> throw(error(instantiation_error, with_flags/2))
+       ;  var(Value) -> throw(error(instantiation_error, with_flags/2))
+       ;  must_be_flag_value_pairs(FlagValues)
+       )
+    ;  throw(error(type_error(pair, FlagValue), with_flags/2))
+    ).
+
+current_flag_values([], []).
+current_flag_values([Flag - _ | FlagValues], OldFlagValues) :-
+    (  current_prolog_flag(Flag, Value) ->
+       OldFlagValues = [Flag - Value | OldFlagValues0]
+    ;  OldFlagValues = OldFlagValues0
+    ),
+    current_flag_values(FlagValues, OldFlagValues0).
+
+set_flag_values([]).
+set_flag_values([Flag - Value | FlagValues]) :-
+    set_prolog_flag(Flag, Value),
+    set_flag_values(FlagValues).
+
+%% sandboxed_call(Goal, Whitelist) calls Goal in a sandbox, for
+%% evaluating untrusted queries. inside it, opening and closing
+%% streams, loading files, halting, setting flags, operators and
+%% global variables, garbage collecting clauses, calling the '$'
+%% system predicates from code outside the libraries and asserting,
+%% retracting or abolishing any predicate not in the list Whitelist of
+%% Name/Arity and Module:Name/Arity indicators raise a permission_error
+%% with type sandboxed_procedure. the sandbox is lifted when Goal exits, fails,
+%% raises an exception or has its choicepoints cut, and put back on
+%% backtracking into Goal. sandboxes nest, and a nested sandbox never
+%% whitelists more than the ones enclosing it.
+
+sandboxed_call(Goal) :-
+    sandboxed_call(Goal, []).
+
+sandboxed_call(Goal, Whitelist) :-
+    must_be_sandbox_whitelist(Whitelist),
+    setup_call_cleanup(install_sandbox(Key, Whitelist),
+                       call_with_exit_redo(Goal,
+                                           remove_sandbox(Key),
+                                           install_sandbox(Key, Whitelist)),
+                       remove_sandbox(Key)).
+
+% Key is a fresh variable that only sandboxed_call/2 holds, so the
+% sandboxed goal can't remove its own sandbox.
+install_sandbox(Key, Whitelist) :- '$install_sandbox'(Key, Whitelist).
+
+remove_sandbox(Key) :- '$remove_sandbox'(Key).
+
+must_be_sandbox_whitelist(Whitelist) :-
+    '$skip_max_list'(_, -1, Whitelist, Tail),
+    (  var(Tail) ->
+       throw(error(instantiation_error, sandboxed_call/2))
+    ;  Tail == [] ->
+       must_be_sandboxed_predicates(Whitelist)
+    ;  throw(error(type_error(list, Whitelist), sandboxed_call/2))
+    ).
+
+must_be_sandboxed_predicates([]).
+must_be_sandboxed_predicates([PI | PIs]) :-
+    (  var(PI) ->
+       throw(error(instantiation_error, sandboxed_call/2))
+    ;  PI = Module:PI0 ->
+       (  var(Module) -> throw(error(instantiation_error, sandboxed_call/2))
+       ;  atom(Module) -> must_be_sandboxed_predicate(PI0, PI)
+       ;  throw(error(type_error(atom, Module), sandboxed_call/2))
+       )
+    ;  must_be_sandboxed_predicate(PI, PI)
+    ),
+    must_be_sandboxed_predicates(PIs).
+
+must_be_sandboxed_predicate(PI0, PI) :-
+    (  var(PI0) ->
+       throw(error(instantiation_error, sandboxed_call/2))
+    ;  PI0 = Name/Arity ->
+       (  var(Name) -> throw(error(instantiation_error, sandboxed_call/2))
+       ;  var(Arity) -> throw(error(instantiation_error, sandboxed_call/2))
+       ;  \+ atom(Name) -> throw(error(type_error(atom, Name), sandboxed_call/2))
+       ;  \+ integer(Arity) -> throw(error(type_error(integer, Arity), sandboxed_call/2))
+       ;  Arity < 0 ->
+          throw(error(domain_error(not_less_than_zero, Arity), sandboxed_call/2))
+       ;  true
+       )
+    ;  throw(error(type_error(predicate_indicator, PI), sandboxed_call/2))
+    ).
@@ -0,0 +1,23 @@
+:- module(listing, [portray_clause/1, portray_clause/2]).
+
+:- use_module(library(iso_ext)).
+
+%% portray_clause(+Clause)
+%%
+%% writes Clause to the current output, followed by a full stop and a
+%% newline. the body of a rule is printed one goal per line, with
+%% disjunctions and if-then-elses laid out in indented columns, and
+%% variables are renamed to A, B, ..., singletons being written as _.
+
+portray_clause(Clause) :-
+    '$portray_clause'(Clause).
+
+%% portray_clause(+Stream, +Clause)
+%%
+%% as portray_clause/1, but writes to Stream.
+
+portray_clause(Stream, Clause) :-
+    current_output(Output),
+    setup_call_cleanup(set_output(Stream),
+                       portray_clause(Clause),
+                       set_output(Output)).
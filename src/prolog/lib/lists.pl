@@ -1,5 +1,5 @@
 :- module(lists, [member/2, select/3, append/2, append/3, foldl/4, foldl/5,
-		  memberchk/2, reverse/2, length/2, maplist/2,
+		  memberchk/2, merge/3, reverse/2, length/2, maplist/2,
 		  maplist/3, maplist/4, maplist/5, maplist/6,
 		  maplist/7, maplist/8, maplist/9, same_length/2,
 		  sum_list/2, transpose/2]).
@@ -50,8 +50,21 @@ append([L0|Ls0], Ls) :-
     append(Ls0, Rest).
 
 
-append([], R, R).
-append([X|L], R, [X|S]) :- append(L, R, S).
+% a proper first list is copied in front of the second natively,
+% which is what the terminals of grammar rules expand to.
+append(Xs, Ys, Zs) :-
+    (  is_list(Xs) -> '$append'(Xs, Ys, Zs)
+    ;  append_(Xs, Ys, Zs)
+    ).
+
+append_([], R, R).
+append_([X|L], R, [X|S]) :- append_(L, R, S).
+
+
+% merge(+Xs, +Ys, -Zs) merges the lists Xs and Ys, each ordered by
+% the standard order of terms, keeping duplicates.
+merge(Xs, Ys, Zs) :-
+    '$merge'(Xs, Ys, false, Zs).
 
 
 memberchk(X, Xs) :- member(X, Xs), !.
@@ -360,7 +360,11 @@ ord_union_all(N, Sets0, Union, Sets) :-
 %   Union is the union of Set1 and Set2
 
 ord_union(Set1, Set2, Union) :-
-    oset_union(Set1, Set2, Union).
+    (   is_list(Set1),
+        is_list(Set2)
+    ->  '$merge'(Set1, Set2, true, Union)
+    ;   oset_union(Set1, Set2, Union)
+    ).
 
 
 %!  ord_union(+Set1, +Set2, -Union, -New) is det.
@@ -0,0 +1,78 @@
+%% term pipes: bounded channels carrying terms between machines
+%% in the fast term encoding. load at the REPL with
+
+%% ?- use_module(library(pipes)).
+
+%% each end of a pipe is named by an atom. pipes shared with other
+%% machines are installed by the embedder; pipe_create/3 creates both
+%% ends within the current machine.
+
+:- module(pipes, [pipe_close/1, pipe_create/3, pipe_receive/2,
+                  pipe_send/2, pipe_try_receive/2, pipe_try_send/2]).
+
+:- use_module(library(error)).
+:- use_module(library(lists)).
+
+%% Options:
+%%
+%%    capacity(N): the number of terms the pipe holds before
+%%                 senders block, at least 1 and at most 65536
+%%                 (default 64).
+%%    attributes(A): one of strip (default), which sends attributed
+%%                   variables as plain variables, or copy, which
+%%                   sends their attributes along with them.
+
+pipe_create(SendAlias, ReceiveAlias, Options) :-
+    must_be(atom, SendAlias),
+    must_be(atom, ReceiveAlias),
+    must_be(list, Options),
+    pipe_option(capacity(Capacity), Options, 64),
+    pipe_option(attributes(Attrs), Options, strip),
+    must_be(integer, Capacity),
+    (  Capacity < 1 ->
+       throw(error(domain_error(pipe_capacity, Capacity), pipe_create/3))
+    ;  Capacity > 65536 ->
+       throw(error(resource_error(term_pipe_capacity), pipe_create/3))
+    ;  true
+    ),
+    must_be(atom, Attrs),
+    (  lists:member(Attrs, [strip, copy]) -> true
+    ;  throw(error(domain_error(pipe_attributes, Attrs), pipe_create/3))
+    ),
+    '$pipe_create'(SendAlias, ReceiveAlias, Capacity, Attrs).
+
+pipe_option(Option, Options, Default) :-
+    (  lists:member(Option, Options) -> true
+    ;  arg(1, Option, Default)
+    ).
+
+pipe_close(Alias) :-
+    must_be(atom, Alias),
+    (  '$pipe_close'(Alias) -> true
+    ;  throw(error(existence_error(term_pipe, Alias), pipe_close/1))
+    ).
+
+%% blocks while the pipe is full. if the receiving end is held by the
+%% current machine, nothing else can empty the pipe, so instead of
+%% blocking forever resource_error(term_pipe) is thrown.
+pipe_send(Alias, Term) :-
+    must_be(atom, Alias),
+    '$pipe_send'(Alias, Term, true).
+
+%% fails if the pipe is full.
+pipe_try_send(Alias, Term) :-
+    must_be(atom, Alias),
+    '$pipe_send'(Alias, Term, false).
+
+%% blocks while the pipe is empty. Term is unified with end_of_file
+%% once every sending end has been closed. if the sending end is held
+%% by the current machine, resource_error(term_pipe) is thrown rather
+%% than blocking forever.
+pipe_receive(Alias, Term) :-
+    must_be(atom, Alias),
+    '$pipe_receive'(Alias, Term, true).
+
+%% fails if the pipe is empty.
+pipe_try_receive(Alias, Term) :-
+    must_be(atom, Alias),
+    '$pipe_receive'(Alias, Term, false).
@@ -0,0 +1,278 @@
+%% unit tests in the style of plunit. load with
+
+%% ?- use_module(library(plunit)).
+
+%% the tests of a unit are written between the directives
+%% begin_tests(Unit) and end_tests(Unit), as clauses
+
+%%     test(Name) :- Body.
+%%     test(Name, Options) :- Body.
+
+%% where Options is an option or a list of options among
+
+%%     true(Cond)     Body succeeds, after which Cond holds. a
+%%                    comparison such as X == 1 stands for true(X == 1)
+%%     fail           Body fails
+%%     throws(Ball)   Body throws a ball subsumed by Ball. error(E)
+%%                    stands for throws(error(E, _))
+%%     all(Cmp)       Cmp is Template Op Expected, as in all(X == [1, 2]),
+%%                    and the Templates of all solutions of Body are
+%%                    related to Expected by Op
+%%     setup(Goal)    Goal is called once before Body
+%%     cleanup(Goal)  Goal is called once after Body, if setup succeeded
+%%     blocked(Why)   the test is skipped with a warning
+
+%% tests are run by run_tests/0, which runs every loaded unit, and by
+%% run_tests(Spec), where Spec is a Unit or a Unit:Name. each test is
+%% reported through print_message/2 with its processor time, failures
+%% with the file and line of the test, followed by a summary of the
+%% tests passed and failed and the assertions checked. run_tests/0,1
+%% fail if any test failed.
+
+%% assertion(Goal) checks Goal within a test. a failing assertion is
+%% reported but lets the test run on, failing it at the end.
+
+:- module(plunit, [begin_tests/1, end_tests/1, run_tests/0, run_tests/1,
+                   assertion/1]).
+
+:- use_module(library(error)).
+:- use_module(library(lists)).
+
+:- dynamic(unit_/3).
+:- dynamic(loading_unit_/1).
+:- dynamic(test_/7).
+:- dynamic(current_test_/5).
+:- dynamic(count_/2).
+
+%% begin_tests(+Unit) starts the tests of Unit in the file being
+%% loaded, replacing any tests of Unit loaded before.
+
+begin_tests(Unit) :-
+    must_be(atom, Unit),
+    load_context(Module, File),
+    retractall(unit_(Unit, _, _)),
+    retractall(test_(Unit, _, _, _, _, _, _)),
+    assertz(unit_(Unit, Module, File)),
+    retractall(loading_unit_(_)),
+    assertz(loading_unit_(Unit)).
+
+end_tests(Unit) :-
+    must_be(atom, Unit),
+    (  retract(loading_unit_(Unit)) -> true
+    ;  throw(error(existence_error(unit, Unit), end_tests/1))
+    ).
+
+load_context(Module, File) :-
+    (  prolog_load_context(module, Module) -> true
+    ;  Module = user
+    ),
+    (  prolog_load_context(file, File) -> true
+    ;  File = user
+    ).
+
+user:term_expansion((:- begin_tests(Unit)), []) :-
+    begin_tests(Unit).
+user:term_expansion((:- end_tests(Unit)), []) :-
+    end_tests(Unit).
+user:term_expansion((test(Name) :- Body), []) :-
+    loading_unit_(Unit),
+    add_test(Unit, Name, [], Body).
+user:term_expansion((test(Name, Options) :- Body), []) :-
+    loading_unit_(Unit),
+    add_test(Unit, Name, Options, Body).
+user:term_expansion(test(Name), []) :-
+    loading_unit_(Unit),
+    add_test(Unit, Name, [], true).
+user:term_expansion(test(Name, Options), []) :-
+    loading_unit_(Unit),
+    add_test(Unit, Name, Options, true).
+
+add_test(Unit, Name, Options0, Body) :-
+    (  ( Options0 == [] ; Options0 = [_|_] ) ->
+       maplist(test_option, Options0, Options)
+    ;  test_option(Options0, Option),
+       Options = [Option]
+    ),
+    load_context(Module, File),
+    (  prolog_load_context(term_position, Line) -> true
+    ;  Line = 0
+    ),
+    assertz(test_(Unit, Name, Module, File, Line, Options, Body)).
+
+test_option(Option0, Option) :-
+    (  var(Option0) ->
+       throw(error(instantiation_error, test/2))
+    ;  Option0 = error(E) ->
+       Option = throws(error(E, _))
+    ;  Option0 == false ->
+       Option = fail
+    ;  comparison(Option0) ->
+       Option = true(Option0)
+    ;  Option0 = all(Cmp), comparison(Cmp) ->
+       Option = Option0
+    ;  test_option(Option0) ->
+       Option = Option0
+    ;  throw(error(domain_error(test_option, Option0), test/2))
+    ).
+
+test_option(true(_)).
+test_option(fail).
+test_option(throws(_)).
+test_option(setup(_)).
+test_option(cleanup(_)).
+test_option(blocked(_)).
+
+comparison(Cmp) :-
+    functor(Cmp, Op, 2),
+    memberchk(Op, [==, \==, =, =@=, =:=]).
+
+run_tests :-
+    findall(Unit, unit_(Unit, _, _), Units),
+    run_units(Units, _).
+
+run_tests(Spec) :-
+    (  var(Spec) ->
+       throw(error(instantiation_error, run_tests/1))
+    ;  Spec = Unit:Test -> true
+    ;  Unit = Spec
+    ),
+    must_be(atom, Unit),
+    (  unit_(Unit, _, _) -> true
+    ;  throw(error(existence_error(unit, Unit), run_tests/1))
+    ),
+    run_units([Unit], Test).
+
+run_units(Units, Test) :-
+    retractall(count_(_, _)),
+    statistics(cputime, T0),
+    (  member(Unit, Units),
+       test_(Unit, Test, Module, File, Line, Options, Body),
+       run_test(Unit, Test, Module, File, Line, Options, Body),
+       false
+    ;  true
+    ),
+    statistics(cputime, T),
+    milliseconds(T0, T, Ms),
+    count(passed, Passed),
+    count(failed, Failed),
+    count(assertions, Assertions),
+    print_message(informational, plunit(summary(Passed, Failed, Assertions, Ms))),
+    Failed =:= 0.
+
+run_test(Unit, Test, _, _, _, Options, _) :-
+    memberchk(blocked(Why), Options),
+    !,
+    print_message(warning, plunit(blocked(Unit, Test, Why))).
+run_test(Unit, Test, Module, File, Line, Options, Body) :-
+    count(failed_assertions, F0),
+    retractall(current_test_(_, _, _, _, _)),
+    assertz(current_test_(Unit, Test, Module, File, Line)),
+    statistics(cputime, T0),
+    (  setup(Options, Module) ->
+       test_result(Options, Module, Body, Result0),
+       cleanup(Options, Module)
+    ;  memberchk(setup(Setup), Options),
+       Result0 = failure(setup(Setup))
+    ),
+    statistics(cputime, T),
+    retractall(current_test_(_, _, _, _, _)),
+    milliseconds(T0, T, Ms),
+    count(failed_assertions, F),
+    (  Result0 == passed, F > F0 ->
+       N is F - F0,
+       Result = failure(assertions(N))
+    ;  Result = Result0
+    ),
+    report(Result, Unit, Test, File, Line, Ms).
+
+setup(Options, Module) :-
+    (  memberchk(setup(Setup), Options) ->
+       catch(once(Module:Setup), _, false)
+    ;  true
+    ).
+
+cleanup(Options, Module) :-
+    (  memberchk(cleanup(Cleanup), Options),
+       catch(once(Module:Cleanup), _, true) ->
+       true
+    ;  true
+    ).
+
+test_result(Options, Module, Body, Result) :-
+    catch(test_result_(Options, Module, Body, Result),
+          Ball,
+          thrown_result(Options, Ball, Result)).
+
+test_result_(Options, Module, Body, Result) :-
+    (  memberchk(fail, Options) ->
+       (  call(Module:Body) -> Result = failure(succeeded)
+       ;  Result = passed
+       )
+    ;  memberchk(throws(Ball), Options) ->
+       (  call(Module:Body) -> Result = failure(no_exception(Ball))
+       ;  Result = failure(failed)
+       )
+    ;  memberchk(all(Cmp), Options) ->
+       Cmp =.. [Op, Template, Expected],
+       findall(Template, Module:Body, Found),
+       (  call(Op, Found, Expected) -> Result = passed
+       ;  Result = failure(all(Found, Expected))
+       )
+    ;  (  memberchk(true(Cond), Options) -> true
+       ;  Cond = true
+       ),
+       (  call(Module:Body) ->
+          (  call(Module:Cond) -> Result = passed
+          ;  Result = failure(condition(Cond))
+          )
+       ;  Result = failure(failed)
+       )
+    ).
+
+thrown_result(Options, Ball, Result) :-
+    (  memberchk(throws(Expected), Options) ->
+       (  subsumes_term(Expected, Ball) -> Result = passed
+       ;  Result = failure(wrong_exception(Expected, Ball))
+       )
+    ;  Result = failure(raised(Ball))
+    ).
+
+report(passed, Unit, Test, _, _, Ms) :-
+    bump(passed),
+    print_message(informational, plunit(passed(Unit, Test, Ms))).
+report(failure(Reason), Unit, Test, File, Line, _) :-
+    bump(failed),
+    print_message(error, plunit(failed(File, Line, Unit, Test, Reason))).
+
+%% assertion(+Goal) checks that Goal succeeds, calling it in the module
+%% of the test being run. a failure or error is reported, and fails
+%% the test once it is done.
+
+assertion(Goal) :-
+    bump(assertions),
+    (  current_test_(Unit, Test, Module, File, Line) -> true
+    ;  Module = user
+    ),
+    (  catch(once(Module:Goal), _, false) ->
+       true
+    ;  bump(failed_assertions),
+       (  nonvar(Unit) ->
+          print_message(error, plunit(assertion_failed(File, Line, Unit, Test, Goal)))
+       ;  throw(error(assertion_failed(Goal), assertion/1))
+       )
+    ).
+
+count(Key, N) :-
+    (  count_(Key, N) -> true
+    ;  N = 0
+    ).
+
+bump(Key) :-
+    (  retract(count_(Key, N0)) -> true
+    ;  N0 = 0
+    ),
+    N is N0 + 1,
+    assertz(count_(Key, N)).
+
+milliseconds(T0, T, Ms) :-
+    Ms is round((T - T0) * 1000).
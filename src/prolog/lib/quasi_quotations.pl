@@ -0,0 +1,125 @@
+%% quasi quotations: literal text of another language embedded in a
+%% clause, written {|Type||Text|} and parsed when the clause is
+%% loaded. load at the REPL with
+
+%% ?- use_module(library(quasi_quotations)).
+
+%% Type is a callable term whose name is that of a syntax declared by
+%% quasi_quotation_syntax/1. a quotation of Type Name(A1, ..., An) is
+%% replaced by the Result of
+
+%%     call(M:Name, Content, [A1, ..., An], VariableNames, Result)
+
+%% where M is the module that declared the syntax, VariableNames are
+%% the Name = Var equations of the clause containing the quotation,
+%% and Content holds its text and position, which are available
+%% through quasi_quotation_text/2, quasi_quotation_position/2 and
+%% phrase_from_quasi_quotation/2. the variables of the clause may so
+%% be interpolated into Result by name.
+
+:- module(quasi_quotations, [quasi_quotation_syntax/1,
+                             quasi_quotation_text/2,
+                             quasi_quotation_position/2,
+                             phrase_from_quasi_quotation/2,
+                             quasi_quotation_syntax_error/1]).
+
+:- use_module(library(dcgs)).
+:- use_module(library(error)).
+:- use_module(library(lists)).
+
+%% loading the library lets the reader read {|Type||Text|} as a
+%% quasi quotation.
+
+:- set_prolog_flag(quasi_quotations, true).
+
+:- dynamic(quasi_quotation_syntax_/2).
+
+%% quasi_quotation_syntax(+Name) declares Name a syntax of quasi
+%% quotations, parsed by the predicate Name/4 of the module being
+%% loaded. M:Name names a predicate of module M instead. it may be
+%% used as a directive, which takes effect as it is read.
+
+quasi_quotation_syntax(M:Name) :-
+    !,
+    must_be(atom, M),
+    must_be(atom, Name),
+    retractall(quasi_quotation_syntax_(Name, _)),
+    assertz(quasi_quotation_syntax_(Name, M)).
+quasi_quotation_syntax(Name) :-
+    (  prolog_load_context(module, M) -> true
+    ;  M = user
+    ),
+    quasi_quotation_syntax(M:Name).
+
+quasi_quotation_text('$quasi_quotation_content'(Text, _, _), Text).
+
+quasi_quotation_position('$quasi_quotation_content'(_, File, Line), File:Line).
+
+%% phrase_from_quasi_quotation(+Grammar, +Content) parses the
+%% characters of the quotation's text with the grammar body Grammar.
+
+phrase_from_quasi_quotation(Grammar, Content) :-
+    quasi_quotation_text(Content, Text),
+    atom_chars(Text, Chars),
+    phrase(Grammar, Chars).
+
+%% quasi_quotation_syntax_error(+Error) throws a syntax error from
+%% a quasi quotation parser. the error is reported at the position
+%% of the quotation.
+
+quasi_quotation_syntax_error(Error) :-
+    throw(error(syntax_error(Error), _)).
+
+user:term_expansion((:- quasi_quotation_syntax(Name)), []) :-
+    quasi_quotation_syntax(Name).
+user:term_expansion(Term0, Term) :-
+    nonvar(Term0),
+    contains_quasi_quotation(Term0),
+    (  prolog_load_context(variable_names, VarNames) -> true
+    ;  VarNames = []
+    ),
+    expand_quasi_quotations(Term0, VarNames, Term).
+
+contains_quasi_quotation(Term) :-
+    compound(Term),
+    (  Term = '$quasi_quotation'(_, _, _) -> true
+    ;  Term =.. [_ | Args],
+       member(Arg, Args),
+       contains_quasi_quotation(Arg) ->
+       true
+    ).
+
+expand_quasi_quotations(Term0, _, Term) :-
+    var(Term0),
+    !,
+    Term = Term0.
+expand_quasi_quotations('$quasi_quotation'(Type, Text, Line), VarNames, Result) :-
+    !,
+    parse_quasi_quotation(Type, Text, Line, VarNames, Result).
+expand_quasi_quotations(Term0, VarNames, Term) :-
+    compound(Term0),
+    !,
+    Term0 =.. [Name | Args0],
+    maplist(expand_quasi_quotations_(VarNames), Args0, Args),
+    Term =.. [Name | Args].
+expand_quasi_quotations(Term, _, Term).
+
+expand_quasi_quotations_(VarNames, Term0, Term) :-
+    expand_quasi_quotations(Term0, VarNames, Term).
+
+parse_quasi_quotation(Type, Text, Line, VarNames, Result) :-
+    must_be(callable, Type),
+    Type =.. [Name | Args],
+    (  prolog_load_context(file, File) -> true
+    ;  File = user
+    ),
+    (  quasi_quotation_syntax_(Name, M) -> true
+    ;  throw(error(existence_error(quasi_quotation_syntax, Name), File:Line))
+    ),
+    Content = '$quasi_quotation_content'(Text, File, Line),
+    catch(call(M:Name, Content, Args, VarNames, Result),
+          error(syntax_error(Error), Context),
+          (  var(Context) ->
+             throw(error(syntax_error(Error), File:Line))
+          ;  throw(error(syntax_error(Error), Context))
+          )).
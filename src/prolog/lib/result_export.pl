@@ -0,0 +1,195 @@
+%% writing the solutions of a goal as JSON lines or CSV rows. load with
+
+%% ?- use_module(library(result_export)).
+
+%% findall_export(+Format, +Bindings, :Goal, +Sink) calls Goal and
+%% writes the values of the variables of Bindings, a list of Name=Var
+%% pairs as given by the variable_names/1 read option, for each of its
+%% solutions to Sink, an output stream or file(File). each solution is
+%% written as soon as it is found. Format is one of
+
+%%     json   a JSON object per line, keyed by the Names
+%%     csv    a header row of the Names, then a row per solution
+
+%% in JSON, numbers are written as numbers, unbound variables as null,
+%% the atoms true, false and null as themselves, other atoms and lists
+%% of characters as strings, and other lists as arrays. any other term
+%% is written as a string of its quoted text, as it is in CSV, where
+%% unbound variables are written as empty fields.
+
+:- module(result_export, [findall_export/4]).
+
+:- use_module(library(dcgs)).
+:- use_module(library(error)).
+:- use_module(library(format)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+findall_export(Format, Bindings, Goal, Sink) :-
+    must_be(atom, Format),
+    (  memberchk(Format, [json, csv]) -> true
+    ;  throw(error(domain_error(export_format, Format), findall_export/4))
+    ),
+    must_be(list, Bindings),
+    maplist(binding_name, Bindings, Names),
+    (  var(Sink) ->
+       throw(error(instantiation_error, findall_export/4))
+    ;  Sink = file(File) ->
+       must_be(atom, File),
+       setup_call_cleanup(open(File, write, S),
+                          export_solutions(Format, Names, Bindings, Goal, S),
+                          close(S))
+    ;  export_solutions(Format, Names, Bindings, Goal, Sink)
+    ).
+
+binding_name(Binding, Name) :-
+    (  var(Binding) ->
+       throw(error(instantiation_error, findall_export/4))
+    ;  Binding = (Name = _), atom(Name) ->
+       true
+    ;  throw(error(domain_error(variable_binding, Binding), findall_export/4))
+    ).
+
+export_solutions(Format, Names, Bindings, Goal, S) :-
+    (  Format == csv ->
+       phrase(csv_row(Names), Header),
+       write_line(S, Header)
+    ;  true
+    ),
+    (  call(Goal),
+       phrase(solution(Format, Bindings), Cs),
+       write_line(S, Cs),
+       false
+    ;  true
+    ).
+
+write_line(S, Cs) :-
+    with_output_to_stream(S, format("~s~n", [Cs])).
+
+solution(json, Bindings) -->
+    "{",
+    json_members(Bindings),
+    "}".
+solution(csv, Bindings) -->
+    { maplist(binding_value, Bindings, Values) },
+    csv_row(Values).
+
+binding_value(_ = Value, Value).
+
+json_members([]) --> [].
+json_members([Name = Value | Bindings]) -->
+    { atom_chars(Name, Cs) },
+    json_string(Cs),
+    ":",
+    json_value(Value),
+    (  { Bindings == [] } -> []
+    ;  ",",
+       json_members(Bindings)
+    ).
+
+json_value(Value) -->
+    (  { var(Value) } -> "null"
+    ;  { number(Value) } ->
+       { number_chars(Value, Cs) },
+       seq(Cs)
+    ;  { memberchk(Value, [true, false, null]) } ->
+       { atom_chars(Value, Cs) },
+       seq(Cs)
+    ;  { Value == [] } -> "[]"
+    ;  { atom(Value) } ->
+       { atom_chars(Value, Cs) },
+       json_string(Cs)
+    ;  { chars(Value) } ->
+       json_string(Value)
+    ;  { is_list(Value) } ->
+       "[",
+       json_elements(Value),
+       "]"
+    ;  { quoted_chars(Value, Cs) },
+       json_string(Cs)
+    ).
+
+json_elements([Value | Values]) -->
+    json_value(Value),
+    (  { Values == [] } -> []
+    ;  ",",
+       json_elements(Values)
+    ).
+
+json_string(Cs) -->
+    "\"",
+    json_chars(Cs),
+    "\"".
+
+json_chars([]) --> [].
+json_chars([C | Cs]) -->
+    json_char(C),
+    json_chars(Cs).
+
+json_char('"') --> !, "\\\"".
+json_char('\\') --> !, "\\\\".
+json_char('\n') --> !, "\\n".
+json_char('\r') --> !, "\\r".
+json_char('\t') --> !, "\\t".
+json_char(C) -->
+    { char_code(C, Code) },
+    (  { Code < 32 } ->
+       { Hi is Code >> 4,
+         Lo is Code /\ 15,
+         hex_digit(Hi, H),
+         hex_digit(Lo, L) },
+       "\\u00", [H, L]
+    ;  [C]
+    ).
+
+hex_digit(D, H) :-
+    nth0(D, "0123456789abcdef", H).
+
+csv_row([]) --> [].
+csv_row([Value | Values]) -->
+    csv_field(Value),
+    (  { Values == [] } -> []
+    ;  ",",
+       csv_row(Values)
+    ).
+
+csv_field(Value) -->
+    (  { var(Value) } -> []
+    ;  { field_chars(Value, Cs) },
+       (  { member(C, Cs), memberchk(C, [',', '"', '\n', '\r']) } ->
+          "\"",
+          csv_quoted_chars(Cs),
+          "\""
+       ;  seq(Cs)
+       )
+    ).
+
+field_chars(Value, Cs) :-
+    (  number(Value) -> number_chars(Value, Cs)
+    ;  atom(Value), Value \== [] -> atom_chars(Value, Cs)
+    ;  chars(Value) -> Cs = Value
+    ;  quoted_chars(Value, Cs)
+    ).
+
+csv_quoted_chars([]) --> [].
+csv_quoted_chars([C | Cs]) -->
+    (  { C == '"' } -> "\"\""
+    ;  [C]
+    ),
+    csv_quoted_chars(Cs).
+
+seq([]) --> [].
+seq([C | Cs]) --> [C], seq(Cs).
+
+% a nonempty list of characters, written as a string.
+chars(Value) :-
+    Value = [_ | _],
+    is_list(Value),
+    maplist(is_char, Value).
+
+is_char(C) :-
+    atom(C),
+    atom_length(C, 1).
+
+quoted_chars(Value, Cs) :-
+    '$write_term_to_chars'(Value, false, true, true, _, 0, Cs).
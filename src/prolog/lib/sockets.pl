@@ -0,0 +1,79 @@
+%% TCP client and server streams. load with
+
+%% ?- use_module(library(sockets)).
+
+%% socket_client_open(+Address, -Stream, +Options) connects to
+%% Address, Host:Port, and unifies Stream with a stream both reading
+%% from and writing to the connection, so that read_term/2, get_char/1,
+%% write/1 and the rest work over it once it is made the current input
+%% or output. Options is a list of type(text) or type(binary), text by
+%% default.
+
+%% socket_server_open(?Address, -Server) listens at Address, Host:Port
+%% or Port, the host then being 0.0.0.0. an unbound Port is unified
+%% with the port chosen by the operating system.
+
+%% socket_server_accept(+Server, -Client, -Stream, +Options) waits for
+%% a connection to Server, unifying Client with the Host:Port text of
+%% the peer and Stream with the connection, as socket_client_open/3.
+%% Server is closed by close/1.
+
+:- module(sockets, [socket_client_open/3, socket_server_accept/4,
+                    socket_server_open/2]).
+
+:- use_module(library(error)).
+:- use_module(library(lists)).
+
+socket_client_open(Address, Stream, Options) :-
+    (  var(Address) ->
+       throw(error(instantiation_error, socket_client_open/3))
+    ;  Address = Host:Port ->
+       must_be(atom, Host),
+       port(Port, socket_client_open/3)
+    ;  throw(error(domain_error(socket_address, Address), socket_client_open/3))
+    ),
+    stream_type(Options, socket_client_open/3, Type),
+    '$socket_client_open'(Host, Port, Address, Stream, Type).
+
+socket_server_open(Address, Server) :-
+    (  var(Address) ->
+       throw(error(instantiation_error, socket_server_open/2))
+    ;  Address = Host:Port ->
+       must_be(atom, Host)
+    ;  Host = '0.0.0.0',
+       Port = Address
+    ),
+    (  var(Port) -> true
+    ;  port(Port, socket_server_open/2)
+    ),
+    '$socket_server_open'(Host, Port, Server).
+
+socket_server_accept(Server, Client, Stream, Options) :-
+    (  var(Server) ->
+       throw(error(instantiation_error, socket_server_accept/4))
+    ;  stream_type(Options, socket_server_accept/4, Type),
+       '$socket_server_accept'(Server, Client, Stream, Type)
+    ).
+
+port(Port, Context) :-
+    (  var(Port) ->
+       throw(error(instantiation_error, Context))
+    ;  integer(Port) ->
+       (  Port >= 0, Port =< 65535 -> true
+       ;  throw(error(domain_error(port, Port), Context))
+       )
+    ;  throw(error(type_error(integer, Port), Context))
+    ).
+
+stream_type(Options, Context, Type) :-
+    must_be(list, Options),
+    (  member(Option, Options), var(Option) ->
+       throw(error(instantiation_error, Context))
+    ;  member(Option, Options), Option \= type(_) ->
+       throw(error(domain_error(socket_option, Option), Context))
+    ;  member(type(Type), Options) ->
+       (  memberchk(Type, [text, binary]) -> true
+       ;  throw(error(domain_error(socket_option, type(Type)), Context))
+       )
+    ;  Type = text
+    ).
@@ -1,6 +1,7 @@
-:- module(terms, [numbervars/3]).
+:- module(terms, [foldsubterms/5, mapsubterms/3, numbervars/3]).
 
 :- use_module(library(error)).
+:- use_module(library(lists)).
 
 numbervars(Term, N0, N) :-
    catch(internal_numbervars(Term, N0, N),
@@ -17,3 +18,28 @@ numberlist([], N, N).
 numberlist(['$VAR'(N0)|Vars], N0, N) :-
    N1 is N0+1,
    numberlist(Vars, N1, N).
+
+% mapsubterms(G, +T1, -T2): T2 is T1 with each outermost subterm S1
+% for which call(G, S1, S2) succeeds replaced by S2. Subterms that are
+% left unchanged are shared with T1, not copied. Proper lists are
+% mapped element-wise, so that long lists do not exhaust the stack.
+
+mapsubterms(G, T1, T2) :-
+   (  call(G, T1, T2) -> true
+   ;  '$subterms'(T1, Ts1) ->
+      maplist(mapsubterms(G), Ts1, Ts2),
+      '$rebuild_subterms'(T1, Ts2, T2)
+   ;  T2 = T1
+   ).
+
+% foldsubterms(G, +T1, -T2, +S0, -S) is mapsubterms/3 threading an
+% accumulator through the calls of G, from left to right.
+
+foldsubterms(G, T1, T2, S0, S) :-
+   (  call(G, T1, T2, S0, S1) -> S = S1
+   ;  '$subterms'(T1, Ts1) ->
+      foldl(foldsubterms(G), Ts1, Ts2, S0, S),
+      '$rebuild_subterms'(T1, Ts2, T2)
+   ;  T2 = T1,
+      S = S0
+   ).
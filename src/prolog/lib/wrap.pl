@@ -0,0 +1,191 @@
+%% wrapping predicates in goals of their own. load with
+
+%% ?- use_module(library(wrap)).
+
+%% wrap_predicate(+Head, +Name, -Wrapped, +Body) puts Body in place of
+%% the predicate of Head, or of Module:Head, for every call to it,
+%% however and whenever compiled, so that a predicate can be traced,
+%% cached or mocked without changing its source. Wrapped is unified
+%% with a goal calling the former implementation with the arguments
+%% of Head, for Body to call if it needs to:
+
+%%     ?- wrap_predicate(p(X), logged, W, (write(p(X)), nl, W)).
+
+%% Body is called as if it were a clause of the user module. a
+%% predicate may have several wrappers, known by their Names, the
+%% last installed being called first. installing a wrapper under a
+%% Name the predicate already has replaces it.
+
+%% unwrap_predicate(+PI, +Name) removes the wrapper Name of PI, which
+%% is Name/Arity or Module:Name/Arity, failing if it has none.
+
+%% advice(+PI, +Ports, +Switch) writes the goals calling PI at the
+%% Ports, a list of call, exit and fail, while Switch is on, by a
+%% wrapper named advice. it is removed when Switch is off.
+
+%% with_redefined(+PI, +Clauses, :Goal) calls Goal with the predicate
+%% PI defined by Clauses in place of its own, restoring it once Goal
+%% is done with, however it exits, so that a test can stub out the
+%% predicates reading or writing files, say:
+
+%%     ?- with_redefined(read_config/1, [read_config([])], run).
+
+%% a wrapper of a dynamic predicate lasts until its clauses change.
+
+:- module(wrap, [advice/3, unwrap_predicate/2, with_redefined/3,
+                 wrap_predicate/4]).
+
+:- use_module(library(error)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+:- dynamic(wrapper_/3).
+
+wrap_predicate(Spec, Name, Wrapped, Body) :-
+    must_be(atom, Name),
+    head_spec(Spec, Module, Head),
+    functor(Head, F, N),
+    hidden_names(Module, F, Name, Inner, Outer),
+    Head =.. [F | Args],
+    Wrapped =.. [Inner | Args],
+    OuterHead =.. [Outer | Args],
+    install_wrapper(Module, F/N, Name, [(OuterHead :- Body)], wrap_predicate/4).
+
+% compiles Clauses, for the wrapper predicate given by hidden_names/5,
+% and puts it in place of the predicate F/N of Module.
+install_wrapper(Module, F/N, Name, Clauses, PI) :-
+    (  wrapper_(Module, F/N, Name) ->
+       unwrap_predicate(Module:F/N, Name)
+    ;  true
+    ),
+    hidden_names(Module, F, Name, Inner, Outer),
+    (  '$copy_code_index'(Module, F, N, user, Inner) ->
+       true
+    ;  throw(error(existence_error(procedure, F/N), PI))
+    ),
+    abolish(Outer/N),
+    maplist(assertz, Clauses),
+    '$copy_code_index'(user, Outer, N, Module, F),
+    asserta(wrapper_(Module, F/N, Name)).
+
+unwrap_predicate(Spec, Name) :-
+    must_be(atom, Name),
+    pi_spec(Spec, unwrap_predicate/2, Module, F/N),
+    findall(Name0, wrapper_(Module, F/N, Name0), Names),
+    append(Above, [Name | _], Names),
+    !,
+    hidden_names(Module, F, Name, Inner, _),
+    % the wrapper above Name, or the predicate itself if there is
+    % none, now calls what Name called.
+    (  Above == [] ->
+       '$copy_code_index'(user, Inner, N, Module, F)
+    ;  append(_, [AboveName], Above),
+       hidden_names(Module, F, AboveName, AboveInner, _),
+       '$copy_code_index'(user, Inner, N, user, AboveInner)
+    ),
+    retract(wrapper_(Module, F/N, Name)).
+
+with_redefined(Spec, Clauses0, Goal) :-
+    pi_spec(Spec, with_redefined/3, Module, F/N),
+    must_be(list, Clauses0),
+    % nested redefinitions of a predicate are told apart by depth.
+    findall(Name0, wrapper_(Module, F/N, Name0), Names),
+    length(Names, Depth),
+    number_chars(Depth, DepthChars),
+    atom_chars(DepthAtom, DepthChars),
+    atom_concat('with_redefined ', DepthAtom, Name),
+    hidden_names(Module, F, Name, _, Outer),
+    maplist(redefined_clause(F/N, Outer), Clauses0, Clauses1),
+    (  Clauses1 == [] ->
+       functor(OuterHead, Outer, N),
+       Clauses = [(OuterHead :- false)]
+    ;  Clauses = Clauses1
+    ),
+    setup_call_cleanup(install_wrapper(Module, F/N, Name, Clauses, with_redefined/3),
+                       Goal,
+                       unwrap_predicate(Module:F/N, Name)).
+
+redefined_clause(F/N, Outer, Clause, (OuterHead :- Body)) :-
+    (  var(Clause) ->
+       throw(error(instantiation_error, with_redefined/3))
+    ;  Clause = (Head :- Body) ->
+       true
+    ;  Head = Clause,
+       Body = true
+    ),
+    (  callable(Head), functor(Head, F, N) ->
+       Head =.. [F | Args],
+       OuterHead =.. [Outer | Args]
+    ;  throw(error(domain_error(clause_of(F/N), Clause), with_redefined/3))
+    ).
+
+advice(Spec, Ports, Switch) :-
+    pi_spec(Spec, advice/3, Module, F/N),
+    must_be(list, Ports),
+    maplist(advice_port, Ports),
+    must_be(atom, Switch),
+    (  Switch == on ->
+       functor(Head, F, N),
+       wrap_predicate(Module:Head, advice, Wrapped,
+                      wrap:advised(Ports, Head, user:Wrapped))
+    ;  Switch == off ->
+       (  unwrap_predicate(Module:F/N, advice) -> true
+       ;  true
+       )
+    ;  throw(error(domain_error(advice_switch, Switch), advice/3))
+    ).
+
+advice_port(Port) :-
+    must_be(atom, Port),
+    (  memberchk(Port, [call, exit, fail]) -> true
+    ;  throw(error(domain_error(advice_port, Port), advice/3))
+    ).
+
+advised(Ports, Goal, Wrapped) :-
+    advise(call, Ports, Goal),
+    (  call(Wrapped),
+       advise(exit, Ports, Goal)
+    ;  advise(fail, Ports, Goal),
+       false
+    ).
+
+advise(Port, Ports, Goal) :-
+    (  memberchk(Port, Ports) ->
+       write(Port), write(': '), writeq(Goal), nl
+    ;  true
+    ).
+
+head_spec(Spec, Module, Head) :-
+    (  var(Spec) ->
+       throw(error(instantiation_error, wrap_predicate/4))
+    ;  Spec = Module:Head ->
+       must_be(atom, Module),
+       must_be(callable, Head)
+    ;  must_be(callable, Spec),
+       Module = user,
+       Head = Spec
+    ).
+
+pi_spec(Spec, Context, Module, PI) :-
+    (  nonvar(Spec), Spec = Module:PI ->
+       must_be(atom, Module)
+    ;  Module = user,
+       PI = Spec
+    ),
+    (  var(PI) ->
+       throw(error(instantiation_error, Context))
+    ;  PI = F/N, atom(F), integer(N) ->
+       true
+    ;  throw(error(type_error(predicate_indicator, PI), Context))
+    ).
+
+% the predicates, in the user module, of the implementation wrapped by
+% Name and of the wrapper itself.
+hidden_names(Module, F, Name, Inner, Outer) :-
+    concat_atoms(['$wrapped ', Module, :, F, ' ', Name], Inner),
+    concat_atoms(['$wrapper ', Module, :, F, ' ', Name], Outer).
+
+concat_atoms([], '').
+concat_atoms([A | As], Atom) :-
+    concat_atoms(As, Atom0),
+    atom_concat(A, Atom0, Atom).
@@ -1,7 +1,9 @@
 driver(Vars, Values) :-
     iterate(Vars, Values, ListOfListsOfGoalLists),
     !,
-    call_goals(ListOfListsOfGoalLists),
+    agenda(ListOfListsOfGoalLists, Agenda0),
+    keysort(Agenda0, Agenda),
+    call_goals(Agenda),
     '$return_from_verify_attr'.
 
 iterate([Var|VarBindings], [Value|ValueBindings], [ListOfGoalLists | ListsCubed]) :-
@@ -24,27 +26,57 @@ call_verify_attributes([Attr|Attrs], Var, Value, ListOfGoalLists) :-
     sort(Modules0, Modules),    
     verify_attrs(Modules, Var, Value, ListOfGoalLists).
 
-verify_attrs([Module|Modules], Var, Value, [Goals|ListOfGoalLists]) :-
+verify_attrs([Module|Modules], Var, Value, [Module-Goals|ListOfGoalLists]) :-
     catch(Module:verify_attributes(Var, Value, Goals),
           error(evaluation_error((Module:verify_attributes)/3), verify_attributes/3),
           Goals = []),    
     verify_attrs(Modules, Var, Value, ListOfGoalLists).
 verify_attrs([], _, _, []).
 
-call_goals([ListOfGoalLists | ListsCubed]) :-
-    call_goals_0(ListOfGoalLists),
-    call_goals(ListsCubed).
-call_goals([]).
+% the goals of the hooks, keyed by their priorities, as declared by
+% propagation_priority/2. keysort/2 is stable, so goals of equal
+% priority are called in the order they were posted.
+agenda(ListsCubed, Agenda) :-
+    (  '$fetch_global_var'('$propagation_priorities', Priorities) -> true
+    ;  Priorities = []
+    ),
+    agenda(ListsCubed, Priorities, Agenda, []).
 
-call_goals_0([GoalList | GoalLists]) :-
+agenda([ListOfGoalLists | ListsCubed], Priorities, Agenda0, Agenda) :-
+    goal_lists_agenda(ListOfGoalLists, Priorities, Agenda0, Agenda1),
+    agenda(ListsCubed, Priorities, Agenda1, Agenda).
+agenda([], _, Agenda, Agenda).
+
+goal_lists_agenda([Module-GoalList | GoalLists], Priorities, Agenda0, Agenda) :-
     (  var(GoalList), throw(error(instantiation_error, call_goals_0/1))
     ;  true
     ),
-    call_goals_1(GoalList),
-    call_goals_0(GoalLists).
-call_goals_0([]).
+    goal_list_agenda(GoalList, Module, Priorities, Agenda0, Agenda1),
+    goal_lists_agenda(GoalLists, Priorities, Agenda1, Agenda).
+goal_lists_agenda([], _, Agenda, Agenda).
+
+goal_list_agenda([Goal | Goals], Module, Priorities, [P-Goal | Agenda0], Agenda) :-
+    goal_priority(Goal, Module, Priorities, P),
+    goal_list_agenda(Goals, Module, Priorities, Agenda0, Agenda).
+goal_list_agenda([], _, _, Agenda, Agenda).
+
+% the priority of the predicate of Goal, else that of the Module whose
+% hook posted it, else 0.
+goal_priority(Goal, Module, Priorities, P) :-
+    (  nonvar(Goal), Goal = M:G -> true
+    ;  M = Module, G = Goal
+    ),
+    (  callable(G), functor(G, N, A), priority_of(Priorities, M:N/A, P0) -> P = P0
+    ;  priority_of(Priorities, Module, P0) -> P = P0
+    ;  P = 0
+    ).
 
-call_goals_1([Goal | Goals]) :-
+priority_of([Key0-P0 | Priorities], Key, P) :-
+    (  Key0 == Key -> P = P0
+    ;  priority_of(Priorities, Key, P)
+    ).
+
+call_goals([_-Goal | Agenda]) :-
     call(Goal),
-    call_goals_1(Goals).
-call_goals_1([]).
+    call_goals(Agenda).
+call_goals([]).
@@ -0,0 +1,439 @@
+use crate::prolog::machine::streams::Stream;
+use crate::ref_thread_local::RefThreadLocal;
+
+use prolog_parser::ast::ParserError;
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{ErrorKind, Read};
+
+ref_thread_local! {
+    static managed BLOCK_STRINGS: bool = false;
+    static managed QUASI_QUOTATIONS: bool = false;
+}
+
+// the value of the block_strings flag.
+#[inline]
+pub(crate) fn block_strings() -> bool {
+    *BLOCK_STRINGS.borrow()
+}
+
+#[inline]
+pub(crate) fn set_block_strings(value: bool) {
+    *BLOCK_STRINGS.borrow_mut() = value;
+}
+
+// the value of the quasi_quotations flag, which loading
+// library(quasi_quotations) sets.
+#[inline]
+pub(crate) fn quasi_quotations() -> bool {
+    *QUASI_QUOTATIONS.borrow()
+}
+
+#[inline]
+pub(crate) fn set_quasi_quotations(value: bool) {
+    *QUASI_QUOTATIONS.borrow_mut() = value;
+}
+
+#[inline]
+fn is_symbol_char(b: u8) -> bool {
+    match b {
+        b'+' | b'-' | b'*' | b'/' | b'\\' | b'^' | b'<' | b'>' | b'=' | b'~' | b':' | b'.'
+      | b'?' | b'@' | b'#' | b'&' | b'$' => true,
+        _ => false,
+    }
+}
+
+// the error of a BlockStringReader whose input ends inside a block
+// comment, at the line and column where the comment begins.
+#[derive(Debug)]
+pub struct UnterminatedComment {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for UnterminatedComment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unterminated block comment at {}:{}", self.line, self.col)
+    }
+}
+
+impl Error for UnterminatedComment {}
+
+// the position of the unterminated block comment err reports, if it
+// reports one.
+pub(crate) fn unterminated_comment(err: &ParserError) -> Option<(usize, usize)> {
+    match err {
+        ParserError::IO(e) => e.get_ref()
+            .and_then(|e| e.downcast_ref::<UnterminatedComment>())
+            .map(|e| (e.line, e.col)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Text,
+    Quoted(u8), // inside text quoted by ', " or `.
+    LineComment,
+    BlockComment,
+    BlockString,
+    QuasiType, // between {| and ||.
+    QuasiBody, // between || and |}.
+}
+
+/* Rewrites the block strings of the source text read from inner,
+ * which are delimited by """ and may span several lines, into double
+ * quoted strings of the same characters, escaping them so that the
+ * lexer reads them verbatim. Block strings are only recognized while
+ * the block_strings flag is set, and never inside quoted text,
+ * comments or character codes such as 0'".
+ *
+ * Quasi quotations, {|Type||Text|}, are rewritten likewise while the
+ * quasi_quotations flag is set, into
+ *
+ *     '$quasi_quotation'(Type, 'Text', Line)
+ *
+ * where Line is the line at which the quotation starts.
+ * library(quasi_quotations) expands these terms as clauses are
+ * loaded.
+ *
+ * Since it tells quoted text and comments apart from the rest, the
+ * reader also counts the end tokens it has passed to the lexer, and
+ * can skip the remainder of a clause the lexer has given up on.
+ *
+ * Block comments, /* ... */, are passed to the lexer as a space
+ * followed by the newlines they contain, so that the lexer never
+ * sees one cut short by the end of a buffer. They don't nest. Input
+ * ending inside a block comment is an UnterminatedComment error,
+ * and input ending inside a line comment is ended with a newline.
+ *
+ * inner is read a buffer at a time. Whatever is left of the buffer
+ * when the reader is dropped is put back into inner, so that other
+ * readers of a shared stream, such as the current input, don't lose
+ * the text read ahead of the lexer.
+ */
+pub struct BlockStringReader {
+    inner: Stream,
+    state: ScanState,
+    lookahead: VecDeque<u8>,
+    output: VecDeque<u8>,
+    clause_ends: usize,
+    // whether the last byte of unquoted text was a symbol char, in
+    // which case a following . continues a symbolic atom.
+    after_symbol_char: bool,
+    last_byte_read: Option<u8>,
+    lines: usize, // the number of lines read from inner.
+    col: usize, // the number of characters read from inner since the last newline.
+    comment_start: (usize, usize), // the line and column of the block comment being read.
+    in_quasi_type: bool, // whether quoted text belongs to a quasi quotation type.
+    quasi_line: usize,
+}
+
+impl BlockStringReader {
+    #[inline]
+    pub fn new(inner: Stream) -> Self {
+        BlockStringReader {
+            inner,
+            state: ScanState::Text,
+            lookahead: VecDeque::new(),
+            output: VecDeque::new(),
+            clause_ends: 0,
+            after_symbol_char: false,
+            last_byte_read: None,
+            lines: 0,
+            col: 0,
+            comment_start: (0, 0),
+            in_quasi_type: false,
+            quasi_line: 0,
+        }
+    }
+
+    // the number of end tokens read from the reader so far.
+    #[inline]
+    pub fn clause_ends(&self) -> usize {
+        self.clause_ends
+    }
+
+    /* discards input through the end token of the clause being read,
+     * returning the number of lines discarded. the last byte read,
+     * likely held back by the lexer as lookahead, is counted among
+     * them. */
+    pub fn skip_clause(&mut self) -> std::io::Result<usize> {
+        let mut lines = if self.last_byte_read == Some(b'\n') { 1 } else { 0 };
+        let clause_ends = self.clause_ends;
+
+        loop {
+            lines += self.output.drain(..).filter(|b| *b == b'\n').count();
+
+            if self.clause_ends > clause_ends || !self.scan()? {
+                break;
+            }
+        }
+
+        self.last_byte_read = None;
+        Ok(lines)
+    }
+
+    fn at_clause_end(&mut self) -> std::io::Result<bool> {
+        Ok(match self.peek_byte(0)? {
+            None | Some(b'%') => true,
+            Some(b'/') => self.peek_byte(1)? == Some(b'*'),
+            Some(b) => b.is_ascii_whitespace(),
+        })
+    }
+
+    // appends the next buffer of inner to the lookahead, returning
+    // false at its end.
+    fn fill_lookahead(&mut self) -> std::io::Result<bool> {
+        let mut buf = [0u8; 4096];
+        let len = self.inner.read(&mut buf)?;
+
+        self.lookahead.extend(&buf[.. len]);
+        Ok(len > 0)
+    }
+
+    fn next_byte(&mut self) -> std::io::Result<Option<u8>> {
+        if self.lookahead.is_empty() && !self.fill_lookahead()? {
+            return Ok(None);
+        }
+
+        let b = match self.lookahead.pop_front() {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+
+        // continuation bytes of UTF-8 don't start a character.
+        if b == b'\n' {
+            self.lines += 1;
+            self.col = 0;
+        } else if b & 0xc0 != 0x80 {
+            self.col += 1;
+        }
+
+        Ok(Some(b))
+    }
+
+    // the byte n places past the next, without consuming it.
+    fn peek_byte(&mut self, n: usize) -> std::io::Result<Option<u8>> {
+        while self.lookahead.len() <= n {
+            if !self.fill_lookahead()? {
+                return Ok(None);
+            }
+        }
+
+        Ok(self.lookahead.get(n).cloned())
+    }
+
+    // consumes the next byte if it is b.
+    fn next_byte_if(&mut self, b: u8) -> std::io::Result<bool> {
+        if self.peek_byte(0)? == Some(b) {
+            self.next_byte()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn at_triple_quote(&mut self) -> std::io::Result<bool> {
+        Ok(self.peek_byte(0)? == Some(b'"') && self.peek_byte(1)? == Some(b'"'))
+    }
+
+    // pushes b as a character of text quoted by q.
+    fn push_escaped(&mut self, b: u8, q: u8) {
+        match b {
+            b'\\' => self.output.extend(b"\\\\"),
+            b'"' | b'\'' if b == q => self.output.extend(&[b'\\', q]),
+            b'\n' => self.output.extend(b"\\n"),
+            b'\r' => self.output.extend(b"\\r"),
+            b'\t' => self.output.extend(b"\\t"),
+            0 ..= 0x1f | 0x7f => self.output.extend(format!("\\x{:x}\\", b).bytes()),
+            _ => self.output.push_back(b),
+        }
+    }
+
+    // ends the scan of inner, returning false once nothing is left
+    // for the lexer.
+    fn scan_eof(&mut self) -> std::io::Result<bool> {
+        match self.state {
+            ScanState::LineComment => {
+                self.output.push_back(b'\n');
+                self.state = ScanState::Text;
+                Ok(true)
+            }
+            ScanState::BlockComment => {
+                let (line, col) = self.comment_start;
+                let err = UnterminatedComment { line, col };
+
+                Err(std::io::Error::new(ErrorKind::UnexpectedEof, err))
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // scans the next byte of inner, returning false at its end.
+    fn scan(&mut self) -> std::io::Result<bool> {
+        let b = match self.next_byte()? {
+            Some(b) => b,
+            None => return self.scan_eof(),
+        };
+
+        let after_symbol_char = self.after_symbol_char;
+        self.after_symbol_char = false;
+
+        match self.state {
+            ScanState::Text => match b {
+                b'"' if block_strings() && self.at_triple_quote()? => {
+                    self.lookahead.drain(.. 2);
+                    self.output.push_back(b'"');
+                    self.state = ScanState::BlockString;
+                }
+                b'\'' | b'"' | b'`' => {
+                    self.output.push_back(b);
+                    self.state = ScanState::Quoted(b);
+                }
+                b'{' if quasi_quotations() && self.next_byte_if(b'|')? => {
+                    self.output.extend(b"'$quasi_quotation'(");
+                    self.quasi_line = self.lines + 1;
+                    self.state = ScanState::QuasiType;
+                }
+                b'%' => {
+                    self.output.push_back(b);
+                    self.state = ScanState::LineComment;
+                }
+                b'/' if self.next_byte_if(b'*')? => {
+                    self.output.push_back(b' ');
+                    self.comment_start = (self.lines + 1, self.col - 1);
+                    self.state = ScanState::BlockComment;
+                }
+                b'0' if self.next_byte_if(b'\'')? => {
+                    self.output.extend(b"0'");
+
+                    match self.next_byte()? {
+                        Some(b'\\') => {
+                            self.output.push_back(b'\\');
+
+                            if let Some(c) = self.next_byte()? {
+                                self.output.push_back(c);
+                            }
+                        }
+                        Some(b'\'') => {
+                            self.output.push_back(b'\'');
+
+                            if self.next_byte_if(b'\'')? {
+                                self.output.push_back(b'\'');
+                            }
+                        }
+                        Some(c) => {
+                            self.output.push_back(c);
+                        }
+                        None => {}
+                    }
+                }
+                b'.' if !after_symbol_char && self.at_clause_end()? => {
+                    self.output.push_back(b);
+                    self.clause_ends += 1;
+                }
+                _ => {
+                    self.output.push_back(b);
+                    self.after_symbol_char = is_symbol_char(b);
+                }
+            },
+            ScanState::Quoted(q) => {
+                self.output.push_back(b);
+
+                if b == b'\\' {
+                    if let Some(c) = self.next_byte()? {
+                        self.output.push_back(c);
+                    }
+                } else if b == q {
+                    if self.next_byte_if(q)? {
+                        self.output.push_back(q);
+                    } else if self.in_quasi_type {
+                        self.in_quasi_type = false;
+                        self.state = ScanState::QuasiType;
+                    } else {
+                        self.state = ScanState::Text;
+                    }
+                }
+            }
+            ScanState::LineComment => {
+                self.output.push_back(b);
+
+                if b == b'\n' {
+                    self.state = ScanState::Text;
+                }
+            }
+            ScanState::BlockComment => {
+                if b == b'\n' {
+                    self.output.push_back(b);
+                } else if b == b'*' && self.next_byte_if(b'/')? {
+                    self.state = ScanState::Text;
+                }
+            }
+            ScanState::BlockString => {
+                if b == b'"' && self.at_triple_quote()? {
+                    self.lookahead.drain(.. 2);
+                    self.output.push_back(b'"');
+                    self.state = ScanState::Text;
+                } else {
+                    self.push_escaped(b, b'"');
+                }
+            }
+            ScanState::QuasiType => {
+                match b {
+                    b'|' if self.next_byte_if(b'|')? => {
+                        self.output.extend(b",'");
+                        self.state = ScanState::QuasiBody;
+                    }
+                    b'\'' | b'"' | b'`' => {
+                        self.output.push_back(b);
+                        self.in_quasi_type = true;
+                        self.state = ScanState::Quoted(b);
+                    }
+                    _ => {
+                        self.output.push_back(b);
+                    }
+                }
+            }
+            ScanState::QuasiBody => {
+                if b == b'|' && self.next_byte_if(b'}')? {
+                    self.output.extend(format!("',{})", self.quasi_line).bytes());
+                    self.state = ScanState::Text;
+                } else {
+                    self.push_escaped(b, b'\'');
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl Read for BlockStringReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.output.is_empty() {
+            if !self.scan()? {
+                return Ok(0);
+            }
+        }
+
+        let len = std::cmp::min(buf.len(), self.output.len());
+
+        for (dest, b) in buf.iter_mut().zip(self.output.drain(.. len)) {
+            *dest = b;
+        }
+
+        self.last_byte_read = Some(buf[len - 1]);
+
+        Ok(len)
+    }
+}
+
+impl Drop for BlockStringReader {
+    fn drop(&mut self) {
+        let rest: Vec<u8> = self.lookahead.drain(..).collect();
+        self.inner.unread(&rest);
+    }
+}
@@ -0,0 +1,152 @@
+use prolog_parser::ast::*;
+
+use crate::prolog::machine::machine_errors::*;
+use crate::prolog::machine::machine_indices::*;
+use crate::prolog::machine::machine_state::*;
+
+use std::io::{self, Read};
+
+/* Feeds the parser the characters of a list of chars on the heap,
+ * walking the list lazily instead of first copying it into a String.
+ * Partial string blocks are read in place. The text is followed by a
+ * newline and a full stop, so that the final term of the text need
+ * not be terminated by the caller. Text that is entirely layout is
+ * left unterminated, so that reading it meets the end of the input.
+ *
+ * A list that is partial or contains a non-character ends the input
+ * early. The cause is kept in error, which must be checked before the
+ * parser's result is trusted. */
+pub(super) struct CharsReader<'a> {
+    machine_st: &'a MachineState,
+    list: Addr,
+    tail: Option<Addr>,
+    block: &'a [u8],
+    buf: Vec<u8>,
+    buf_pos: usize,
+    seen_text: bool,
+    pub(super) error: Option<MachineError>,
+}
+
+impl<'a> CharsReader<'a> {
+    pub(super) fn new(machine_st: &'a MachineState, list: Addr) -> Self {
+        CharsReader {
+            machine_st,
+            list: list.clone(),
+            tail: Some(list),
+            block: &[],
+            buf: vec![],
+            buf_pos: 0,
+            seen_text: false,
+            error: None,
+        }
+    }
+
+    fn note_text(&mut self, s: &str) {
+        if !self.seen_text {
+            self.seen_text = s.chars().any(|c| !c.is_whitespace());
+        }
+    }
+
+    fn fill_buf(&mut self, s: &str) {
+        self.note_text(s);
+        self.buf.clear();
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf_pos = 0;
+    }
+
+    // advances to the next element of the list, returning false once
+    // the input is exhausted.
+    fn next_chunk(&mut self) -> bool {
+        let machine_st = self.machine_st;
+
+        let tail = match self.tail.take() {
+            Some(tail) => machine_st.store(machine_st.deref(tail)),
+            None => return false,
+        };
+
+        match tail {
+            Addr::Lis(l) => {
+                let head = machine_st.heap[l].as_addr(l);
+
+                match machine_st.store(machine_st.deref(head)) {
+                    Addr::Con(Constant::Char(c)) => {
+                        let mut bytes = [0; 4];
+                        self.fill_buf(c.encode_utf8(&mut bytes));
+                    }
+                    Addr::Con(Constant::Atom(ref name, _)) if name.as_str().chars().count() == 1 => {
+                        self.fill_buf(name.as_str());
+                    }
+                    Addr::HeapCell(_) | Addr::StackCell(..) | Addr::AttrVar(_) => {
+                        self.error = Some(MachineError::instantiation_error());
+                        return false;
+                    }
+                    head => {
+                        self.error = Some(MachineError::type_error(ValidType::Character, head));
+                        return false;
+                    }
+                }
+
+                self.tail = Some(machine_st.heap[l + 1].as_addr(l + 1));
+            }
+            Addr::PStrLocation(h, n) => {
+                if let HeapCellValue::PartialString(ref pstr) = &machine_st.heap[h] {
+                    let block = &pstr.block_as_str()[n ..];
+
+                    self.note_text(block);
+                    self.block = block.as_bytes();
+                }
+
+                self.tail = Some(machine_st.heap[h + 1].as_addr(h + 1));
+            }
+            Addr::Con(Constant::String(n, ref s)) if machine_st.flags.double_quotes.is_chars() => {
+                if s.len() > n {
+                    self.fill_buf(&s[n ..]);
+                }
+
+                self.tail = Some(Addr::Con(Constant::EmptyList));
+            }
+            Addr::Con(Constant::EmptyList) => {
+                if self.seen_text {
+                    self.fill_buf("\n.");
+                }
+            }
+            Addr::HeapCell(_) | Addr::StackCell(..) | Addr::AttrVar(_) => {
+                self.error = Some(MachineError::instantiation_error());
+                return false;
+            }
+            _ => {
+                self.error = Some(MachineError::type_error(ValidType::List, self.list.clone()));
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl<'a> Read for CharsReader<'a> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        while written < out.len() {
+            if !self.block.is_empty() {
+                let len = self.block.len().min(out.len() - written);
+
+                out[written .. written + len].copy_from_slice(&self.block[.. len]);
+                self.block = &self.block[len ..];
+                written += len;
+            } else if self.buf_pos < self.buf.len() {
+                let len = (self.buf.len() - self.buf_pos).min(out.len() - written);
+
+                out[written .. written + len]
+                    .copy_from_slice(&self.buf[self.buf_pos .. self.buf_pos + len]);
+                self.buf_pos += len;
+                written += len;
+            } else if !self.next_chunk() {
+                break;
+            }
+        }
+
+        Ok(written)
+    }
+}
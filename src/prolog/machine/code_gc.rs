@@ -0,0 +1,179 @@
+use crate::prolog::clause_types::*;
+use crate::prolog::instructions::*;
+use crate::prolog::machine::code_walker::*;
+use crate::prolog::machine::heap::*;
+use crate::prolog::machine::machine_indices::*;
+use crate::prolog::machine::machine_state::*;
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/* Redefining a predicate compiles its new clauses to the end of the
+ * code area and swaps the shared CodeIndex cell to point at them, so
+ * that calls made after the redefinition run the new code while
+ * continuations and choicepoints captured before it keep running the
+ * old. The superseded code is recorded by IndexStore::retire_code and
+ * released here once code walking shows that nothing can still reach
+ * it. The code area only grows, so a released predicate's
+ * instructions are overwritten rather than removed, which frees the
+ * data they own. They are overwritten by calls to '$reclaimed_code',
+ * which raises system_error(reclaimed_code). */
+
+// pushes the offset of p onto ptrs if it points into the code area.
+fn push_code_ptr(ptrs: &mut Vec<usize>, p: &LocalCodePtr) {
+    if let LocalCodePtr::DirEntry(p) = p {
+        ptrs.push(*p);
+    }
+}
+
+// code pointers stored on the heap by reset/3 and shift/1.
+fn heap_code_ptrs(heap: &Heap, ptrs: &mut Vec<usize>) {
+    for h in 0 .. heap.h() {
+        if let HeapCellValue::NamedStr(1, ref name, _) = &heap[h] {
+            if name.as_str() == "dir_entry" {
+                if let Some(p) = heap.to_local_code_ptr(&Addr::Str(h)) {
+                    push_code_ptr(ptrs, &p);
+                }
+            }
+        }
+    }
+}
+
+impl MachineState {
+    // the offsets into the code area that execution may still
+    // continue from: the machine's own code pointers, those of its
+    // environments and choicepoints, and those saved on the heaps.
+    fn live_code_ptrs(&self, indices: &IndexStore) -> Vec<usize> {
+        let mut ptrs = vec![];
+
+        push_code_ptr(&mut ptrs, &self.p.local());
+        push_code_ptr(&mut ptrs, &self.cp);
+        push_code_ptr(&mut ptrs, &self.attr_var_init.cp);
+        push_code_ptr(&mut ptrs, &self.attr_var_init.instigating_p);
+
+        let mut envs = vec![self.e];
+        let mut b = self.b;
+
+        while b > 0 {
+            let prelude = &self.stack.index_or_frame(b).prelude;
+
+            push_code_ptr(&mut ptrs, &prelude.bp);
+            push_code_ptr(&mut ptrs, &prelude.cp);
+
+            envs.push(prelude.e);
+            b = prelude.b;
+        }
+
+        let mut visited_envs = HashSet::new();
+
+        for mut e in envs {
+            while e > 0 && visited_envs.insert(e) {
+                let prelude = &self.stack.index_and_frame(e).prelude;
+
+                push_code_ptr(&mut ptrs, &prelude.cp);
+                push_code_ptr(&mut ptrs, &prelude.interrupt_cp);
+
+                e = prelude.e;
+            }
+        }
+
+        heap_code_ptrs(&self.heap, &mut ptrs);
+        heap_code_ptrs(&self.lifted_heap, &mut ptrs);
+        heap_code_ptrs(&self.ball.stub, &mut ptrs);
+
+        for (ball, _) in indices.global_variables.values() {
+            heap_code_ptrs(&ball.stub, &mut ptrs);
+        }
+
+        // clause/2 and retract/1 find the clauses of dynamic
+        // predicates at these offsets.
+        for info in indices.dynamic_code_dir.values() {
+            ptrs.push(info.clauses_subsection_p);
+        }
+
+        ptrs
+    }
+
+    /* Releases the retired predicate code that can no longer be
+     * reached, returning the number of retired predicates released.
+     * Starting from the predicates of every code directory and from
+     * the live code pointers, the code is walked and the targets of
+     * its calls followed; a retired predicate is released if its
+     * code overlaps none of the code walked.
+     */
+    pub(super) fn reclaim_retired_code(&self, indices: &mut IndexStore, code: &mut Code) -> usize {
+        if indices.retired_code.is_empty() {
+            return 0;
+        }
+
+        let mut worklist = self.live_code_ptrs(indices);
+
+        let code_dirs = std::iter::once(&indices.code_dir)
+            .chain(indices.modules.values().map(|module| &module.code_dir));
+
+        for code_dir in code_dirs {
+            worklist.extend(code_dir.values().filter_map(CodeIndex::local));
+        }
+
+        let mut visited = HashSet::new();
+        let mut live_extents: Vec<Range<usize>> = vec![];
+
+        while let Some(p) = worklist.pop() {
+            if p >= code.len() || !visited.insert(p) {
+                continue;
+            }
+
+            walk_code(code, p, |instr| {
+                if let Line::Control(ControlInstruction::CallClause(ref ct, ..)) = instr {
+                    match ct {
+                        ClauseType::Named(_, _, ref idx) | ClauseType::Op(_, _, ref idx) => {
+                            worklist.extend(idx.local());
+                        }
+                        _ => {}
+                    }
+                }
+            });
+
+            live_extents.push(code_extent(code, p));
+        }
+
+        let retired_code = std::mem::replace(&mut indices.retired_code, vec![]);
+        let mut reclaimed = 0;
+
+        for p in retired_code {
+            let extent = code_extent(code, p);
+
+            let is_live = live_extents.iter().any(|live| {
+                live.start < extent.end && extent.start < live.end
+            });
+
+            if is_live {
+                indices.retired_code.push(p);
+                continue;
+            }
+
+            // a code pointer the walk missed runs into an error,
+            // rather than succeeding as though the code had run.
+            for instr in &mut code[extent] {
+                *instr = Line::Control(ControlInstruction::CallClause(
+                    ClauseType::System(SystemClauseType::ReclaimedCode),
+                    0,
+                    0,
+                    true,
+                    false,
+                ));
+            }
+
+            reclaimed += 1;
+        }
+
+        log_event!(
+            info,
+            "clause gc reclaimed {} retired predicate(s), {} still referenced",
+            reclaimed,
+            indices.retired_code.len()
+        );
+
+        reclaimed
+    }
+}
@@ -1,12 +1,36 @@
 use crate::prolog::instructions::*;
 
+use std::cmp::{max, min};
 use std::collections::VecDeque;
+use std::ops::Range;
+
+// finds the trust instruction closing the choice sequence opened just
+// before after_idx. try_me_else chains can nest, as they do around the
+// indexing code of a run of clauses, so only the trust_me at the
+// chain's own depth closes it; indexed try sequences don't nest.
+fn scan_for_trust_me(
+    code: &Code,
+    jmp_offsets: &mut VecDeque<usize>,
+    after_idx: &mut usize,
+    indexed: bool,
+) {
+    let mut depth = 0;
 
-fn scan_for_trust_me(code: &Code, jmp_offsets: &mut VecDeque<usize>, after_idx: &mut usize) {
     for (idx, instr) in code[*after_idx..].iter().enumerate() {
         match instr {
+            &Line::Choice(ChoiceInstruction::TryMeElse(..)) if !indexed => {
+                depth += 1;
+            }
             &Line::Choice(ChoiceInstruction::TrustMe)
-          | &Line::IndexedChoice(IndexedChoiceInstruction::Trust(..)) => {
+          | &Line::Choice(ChoiceInstruction::DefaultTrustMe) if !indexed => {
+                if depth == 0 {
+                    *after_idx += idx;
+                    return;
+                }
+
+                depth -= 1;
+            }
+            &Line::IndexedChoice(IndexedChoiceInstruction::Trust(..)) if indexed => {
                 *after_idx += idx;
                 return;
             }
@@ -21,11 +45,14 @@ fn scan_for_trust_me(code: &Code, jmp_offsets: &mut VecDeque<usize>, after_idx:
 fn capture_next_range(code: &Code, queue: &mut VecDeque<usize>, last_idx: &mut usize) {
     loop {
         match &code[*last_idx] {
-            &Line::Choice(ChoiceInstruction::TryMeElse(..))
-          | &Line::IndexedChoice(IndexedChoiceInstruction::Try(..)) => {
-                    *last_idx += 1;
-                    scan_for_trust_me(code, queue, last_idx);
-                }
+            &Line::Choice(ChoiceInstruction::TryMeElse(..)) => {
+                *last_idx += 1;
+                scan_for_trust_me(code, queue, last_idx, false);
+            }
+            &Line::IndexedChoice(IndexedChoiceInstruction::Try(..)) => {
+                *last_idx += 1;
+                scan_for_trust_me(code, queue, last_idx, true);
+            }
             &Line::Control(ControlInstruction::JmpBy(_, offset, _, false)) => {
                 queue.push_back(*last_idx + offset);
                 *last_idx += 1;
@@ -48,6 +75,14 @@ fn capture_next_range(code: &Code, queue: &mut VecDeque<usize>, last_idx: &mut u
  * walker function.
  */
 pub fn walk_code(code: &Code, p: usize, mut walker: impl FnMut(&Line))
+{
+    walk_code_offsets(code, p, |_, instr| walker(instr));
+}
+
+/* Identical to walk_code, except that the walker function is also
+ * passed the offset of each instruction in code.
+ */
+pub fn walk_code_offsets(code: &Code, p: usize, mut walker: impl FnMut(usize, &Line))
 {
     let mut queue = VecDeque::from(vec![p]);
 
@@ -56,8 +91,8 @@ pub fn walk_code(code: &Code, p: usize, mut walker: impl FnMut(&Line))
 
         capture_next_range(code, &mut queue, &mut last_idx);
 
-        for instr in &code[first_idx .. last_idx + 1] {
-            walker(instr);
+        for (idx, instr) in code[first_idx .. last_idx + 1].iter().enumerate() {
+            walker(first_idx + idx, instr);
         }
     }
 }
@@ -79,3 +114,23 @@ pub fn walk_code_mut(code: &mut Code, p: usize, mut walker: impl FnMut(&mut Line
         }
     }
 }
+
+/* Returns the range of offsets spanned by the code of the predicate
+ * beginning at p, as traversed by walk_code.
+ */
+pub fn code_extent(code: &Code, p: usize) -> Range<usize>
+{
+    let mut queue = VecDeque::from(vec![p]);
+    let mut extent = p .. p + 1;
+
+    while let Some(first_idx) = queue.pop_front() {
+        let mut last_idx = first_idx;
+
+        capture_next_range(code, &mut queue, &mut last_idx);
+
+        extent.start = min(extent.start, first_idx);
+        extent.end = max(extent.end, last_idx + 1);
+    }
+
+    extent
+}
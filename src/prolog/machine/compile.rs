@@ -7,12 +7,14 @@ use crate::prolog::debray_allocator::*;
 use crate::prolog::forms::*;
 use crate::prolog::instructions::*;
 use crate::prolog::iterators::*;
+use crate::prolog::machine::block_strings::*;
 use crate::prolog::machine::code_walker::*;
 use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::term_expansion::ExpansionAdditionResult;
 use crate::prolog::machine::toplevel::*;
 use crate::prolog::machine::*;
+use crate::prolog::bignum::Integer;
 
 use indexmap::{IndexMap, IndexSet};
 
@@ -22,7 +24,7 @@ use std::cell::Cell;
 use std::collections::VecDeque;
 use std::fs::File;
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[allow(dead_code)]
 pub fn print_code(code: &Code) {
@@ -40,7 +42,7 @@ pub fn print_code(code: &Code) {
     }
 }
 
-fn fix_filename(
+pub(super) fn fix_filename(
     atom_tbl: TabledData<Atom>,
     mut path: PathBuf,
 ) -> Result<PathBuf, SessionError>
@@ -77,11 +79,7 @@ fn load_module(
         listing_src.clone(),
     );
 
-    let results = compiler.gather_items(
-        wam,
-        &mut parsing_stream(stream),
-        &mut indices,
-    );
+    let results = compiler.gather_items(wam, stream, &mut indices);
 
     let module_name = if let Some(ref module) = &compiler.module {
         module.module_decl.name.clone()
@@ -101,7 +99,7 @@ fn load_module(
     results.and_then(|results| compile_work_impl(&mut compiler, wam, indices, results))
            .or_else(|e| {
                wam.indices.take_module(module_name.clone());
-               compiler.print_error(&e);
+               compiler.print_error(wam, &e);
                Err(e)
            })?;
 
@@ -127,6 +125,16 @@ fn load_module_from_file(
     load_module(wam, file_handle, suppress_warnings, &listing_src)
 }
 
+// compiles a module from source generated at runtime, by
+// create_module/3, rather than read from a file.
+pub(super)
+fn load_module_from_source(
+    wam: &mut Machine,
+    src: String,
+) -> Result<ClauseName, SessionError> {
+    load_module(wam, Stream::from(src), true, &ListingSource::User)
+}
+
 pub type PredicateCompileQueue = (Predicate, VecDeque<TopLevel>);
 
 // throw errors if declaration or query found.
@@ -142,11 +150,22 @@ fn compile_relation(
     }
 }
 
+#[inline]
+fn atom_term(name: ClauseName) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(name, None))
+}
+
+#[inline]
+fn message_term(name: &'static str, args: Vec<Term>) -> Term {
+    Term::Clause(Cell::default(), clause_name!(name), args.into_iter().map(Box::new).collect(), None)
+}
+
 fn issue_singleton_warnings(
+    wam: &mut Machine,
     src_name: ClauseName,
-    terms_and_locs: Vec<(Term, usize, usize)>,
+    terms_and_locs: Vec<(Term, Option<ClauseName>, usize, usize)>,
 ) {
-    for (term, line_num, _col_num) in terms_and_locs {
+    for (term, included_file_name, line_num, _col_num) in terms_and_locs {
         let mut singletons = vec![];
         let mut var_count = IndexMap::new();
 
@@ -163,16 +182,27 @@ fn issue_singleton_warnings(
             }
         }
 
-        if let Some(last_var) = singletons.pop() {
-            print!("Warning: {}:{}: Singleton variables: [",
-                   src_name, line_num);
+        if singletons.is_empty() {
+            continue;
+        }
 
-            for var in singletons {
-                print!("{}, ", var);
-            }
+        let atom_tbl = wam.indices.atom_tbl.clone();
 
-            println!("{}]", last_var);
-        }
+        let names = singletons.into_iter().rev().fold(
+            Term::Constant(Cell::default(), Constant::EmptyList),
+            |tail, var| {
+                let name = atom_term(clause_name!(var.to_string(), atom_tbl));
+                Term::Cons(Cell::default(), Box::new(name), Box::new(tail))
+            },
+        );
+
+        let file_name = included_file_name.unwrap_or_else(|| src_name.clone());
+        let line_num = Term::Constant(Cell::default(), Constant::Integer(Integer::from(line_num)));
+
+        wam.print_message(
+            "warning",
+            message_term("singletons", vec![atom_term(file_name), line_num, names]),
+        );
     }
 }
 
@@ -371,11 +401,7 @@ fn compile_into_module_impl(
     wam.code_repo.compile_hook(CompileTimeHook::TermExpansion)?;
     wam.code_repo.compile_hook(CompileTimeHook::GoalExpansion)?;
 
-    let mut results = compiler.gather_items(
-        wam,
-        &mut parsing_stream(src),
-        &mut indices,
-    )?;
+    let mut results = compiler.gather_items(wam, src, &mut indices)?;
 
     compiler.adapt_in_situ_code(
         results.worker_results,
@@ -405,6 +431,7 @@ fn compile_into_module_impl(
 
     wam.code_repo.code.extend(results.in_situ_code.into_iter());
     clause_code_generator.add_clause_code(wam, results.dynamic_clause_map);
+    compiler.retire_code(wam);
 
     Ok(compiler.drop_expansions(&mut wam.code_repo))
 }
@@ -415,7 +442,7 @@ pub struct GatherResult {
     toplevel_results: Vec<PredicateCompileQueue>,
     toplevel_indices: IndexStore,
     addition_results: ExpansionAdditionResult,
-    top_level_terms: Vec<(Term, usize, usize)>,
+    top_level_terms: Vec<(Term, Option<ClauseName>, usize, usize)>,
     top_level_term_dirs: TermDirQuantum,
     module_term_dirs: TermDirQuantum,
     in_situ_code_dir: InSituCodeDir,
@@ -423,6 +450,43 @@ pub struct GatherResult {
     in_situ_module_dir: ModuleStubDir,
 }
 
+// attribute an error raised while reading from an included file to
+// that file.
+fn in_included_file(worker: &TopLevelBatchWorker, err: SessionError) -> SessionError {
+    match worker.term_stream.included_file_name() {
+        Some(filename) => SessionError::InIncludedFile(filename, Box::new(err)),
+        None => err,
+    }
+}
+
+// the load context of a listing before any of its terms are read.
+fn load_context(
+    atom_tbl: TabledData<Atom>,
+    listing_src: &ListingSource,
+    module: ClauseName,
+) -> LoadContext {
+    let (source, directory) = match listing_src {
+        ListingSource::File(name, path) => match Path::new(name.as_str()).canonicalize() {
+            Ok(path_buf) => {
+                let directory = path_buf.parent().map(Path::to_path_buf)
+                    .unwrap_or_else(|| path.clone());
+
+                (clause_name!(path_buf.to_string_lossy().to_string(), atom_tbl), directory)
+            }
+            Err(_) => (name.clone(), path.clone()),
+        },
+        ListingSource::User => (clause_name!("user"), listing_src.path()),
+    };
+
+    LoadContext {
+        source,
+        directory,
+        included_file: None,
+        module,
+        term_line_num: None,
+    }
+}
+
 pub struct ClauseCodeGenerator {
     len_offset: usize,
     code: Code,
@@ -441,7 +505,10 @@ impl ClauseCodeGenerator {
         }
     }
 
-    // compiles the latest version of clause/2.
+    // compiles the latest version of clause/2, as facts of
+    // clause(Key, Head, Body) where Key is the first argument of Head,
+    // or Head itself if it has none, so that the subsection is indexed
+    // on the first argument as the predicate is.
     fn generate_clause_code(
         &mut self,
         dynamic_clause_map: &DynamicClauseMap,
@@ -456,10 +523,17 @@ impl ClauseCodeGenerator {
                 heads_and_tails
                     .iter()
                     .map(|(head, tail)| {
+                        let key = match head {
+                            Term::Clause(_, _, ref terms, _) if !terms.is_empty() => {
+                                terms[0].clone()
+                            }
+                            _ => Box::new(head.clone()),
+                        };
+
                         let clause = Term::Clause(
                             Cell::default(),
                             clause_name!("clause"),
-                            vec![Box::new(head.clone()), Box::new(tail.clone())],
+                            vec![key, Box::new(head.clone()), Box::new(tail.clone())],
                             None,
                         );
                         PredicateClause::Fact(clause, 0, 0)
@@ -537,6 +611,8 @@ pub struct ListingCompiler {
     initialization_goals: (Vec<QueryTerm>, VecDeque<TopLevel>),
     suppress_warnings: bool,
     listing_src: ListingSource, // a file? a module?
+    retired_code: Vec<usize>, // the code of redefined predicates.
+    syntax_errors: Vec<SessionError>, // recovered from, and reported once the listing is read.
 }
 
 fn add_toplevel(
@@ -627,7 +703,9 @@ impl ListingCompiler {
                 .term_dir_entry_len((clause_name!("goal_expansion"), 2)),
 	    initialization_goals: (vec![], VecDeque::from(vec![])),
             suppress_warnings,
-            listing_src
+            listing_src,
+            retired_code: vec![],
+            syntax_errors: vec![],
         }
     }
 
@@ -765,6 +843,10 @@ impl ListingCompiler {
             .entry(key.clone())
             .or_insert(CodeIndex::default());
 
+        if let Some(old_p) = idx.local() {
+            self.retired_code.push(old_p);
+        }
+
         Ok(match in_situ_code_dir.get(&key) {
             Some(in_situ_p) => {
                 set_code_index!(idx, IndexPtr::Index(p + *in_situ_p), self.get_module_name());
@@ -913,7 +995,7 @@ impl ListingCompiler {
             ),
         );
 
-        op_decl.submit(self.get_module_name(), spec, &mut indices.op_dir)
+        op_decl.submit(self.get_module_name(), spec, indices.op_dir_mut())
     }
 
     fn process_decl(
@@ -979,10 +1061,18 @@ impl ListingCompiler {
             Declaration::Op(op_decl) => {
                 self.submit_op(wam, indices, &op_decl)
             }
-            Declaration::SetPrologFlag(dbl_quotes) => {
+            Declaration::SetPrologFlag(PrologFlag::BlockStrings(value)) => {
+                set_block_strings(value);
+                Ok(())
+            }
+            Declaration::SetPrologFlag(PrologFlag::DoubleQuotes(dbl_quotes)) => {
                 wam.machine_st.flags.double_quotes = dbl_quotes;
                 Ok(())
             }
+            Declaration::SetPrologFlag(PrologFlag::QuasiQuotations(value)) => {
+                set_quasi_quotations(value);
+                Ok(())
+            }
             Declaration::UseModule(ModuleSource::Library(name)) => {
                 let name = if !wam.indices.modules.contains_key(&name) {
                     load_library(wam, name, true)?
@@ -1119,40 +1209,72 @@ impl ListingCompiler {
     pub(crate) fn gather_items(
         &mut self,
         wam: &mut Machine,
-        src: &mut ParsingStream<Stream>,
+        src: Stream,
+        indices: &mut IndexStore,
+    ) -> Result<GatherResult, SessionError> {
+        let atom_tbl = wam.indices.atom_tbl.clone();
+        let load_context = load_context(atom_tbl, &self.listing_src, clause_name!("user"));
+
+        wam.indices.load_contexts.push(load_context);
+        let result = self.gather_items_impl(wam, src, indices);
+        wam.indices.load_contexts.pop();
+
+        for e in mem::replace(&mut self.syntax_errors, vec![]) {
+            self.print_error(wam, &e);
+        }
+
+        result
+    }
+
+    fn gather_items_impl(
+        &mut self,
+        wam: &mut Machine,
+        src: Stream,
         indices: &mut IndexStore,
     ) -> Result<GatherResult, SessionError> {
         let flags = wam.machine_flags();
         let atom_tbl = indices.atom_tbl.clone();
-        let mut worker = TopLevelBatchWorker::new(src, atom_tbl.clone(), flags, wam);
+
+        let source = src.with_block_strings();
+        let mut stream = parsing_stream(source.clone());
+
+        let mut worker = TopLevelBatchWorker::new(
+            &mut stream,
+            source,
+            atom_tbl.clone(),
+            flags,
+            wam,
+            self.listing_src.clone(),
+        );
 
         let mut toplevel_results = vec![];
         let mut toplevel_indices = default_index_store!(atom_tbl.clone());
 
         let mut top_level_term_dirs = TermDirQuantum::new();
 
-        while let Some(decl) = worker.consume(indices)? {
-            if decl.is_module_decl() {
-                toplevel_indices.copy_and_swap(indices);
-                mem::swap(&mut worker.results, &mut toplevel_results);
-                worker.in_module = true;
-
-                self.process_and_commit_decl(decl, &mut worker, indices, flags)?;
-
-                if let Some(ref module) = &self.module {
-                    worker.term_stream.set_atom_tbl(module.atom_tbl.clone());
+        let result = self.consume_decls(
+            &mut worker,
+            indices,
+            flags,
+            &mut toplevel_results,
+            &mut toplevel_indices,
+            &mut top_level_term_dirs,
+        );
 
-                    top_level_term_dirs = mem::replace(
-                        &mut worker.term_dirs,
-                        TermDirQuantum::new(),
-                    );
+        // syntax errors are collected even if the listing fails to load.
+        self.syntax_errors.extend(
+            worker.term_stream.take_syntax_errors().into_iter().map(|(filename, e)| {
+                match filename {
+                    Some(filename) => SessionError::InIncludedFile(
+                        filename,
+                        Box::new(SessionError::ParserError(e)),
+                    ),
+                    None => SessionError::ParserError(e),
                 }
-            } else if decl.is_end_of_file() {
-                break;
-            } else {
-                self.process_and_commit_decl(decl, &mut worker, indices, flags)?;
-            }
-        }
+            })
+        );
+
+        result?;
 
         let addition_results = worker.term_stream.rollback_expansion_code()?;
 
@@ -1178,6 +1300,58 @@ impl ListingCompiler {
         })
     }
 
+    // reads and processes the declarations of the listing up to its
+    // end. the items preceding a module declaration are moved to
+    // toplevel_results and toplevel_indices.
+    fn consume_decls(
+        &mut self,
+        worker: &mut TopLevelBatchWorker,
+        indices: &mut IndexStore,
+        flags: MachineFlags,
+        toplevel_results: &mut Vec<PredicateCompileQueue>,
+        toplevel_indices: &mut IndexStore,
+        top_level_term_dirs: &mut TermDirQuantum,
+    ) -> Result<(), SessionError> {
+        while let Some(decl) = worker.consume(indices).map_err(|e| in_included_file(worker, e))? {
+            if decl.is_module_decl() {
+                toplevel_indices.copy_and_swap(indices);
+                mem::swap(&mut worker.results, toplevel_results);
+                worker.in_module = true;
+
+                self.process_and_commit_decl(decl, worker, indices, flags)
+                    .map_err(|e| in_included_file(worker, e))?;
+
+                if let Some(ref module) = &self.module {
+                    worker.term_stream.set_atom_tbl(module.atom_tbl.clone());
+
+                    if let Some(load_context) = worker.term_stream.wam.indices.load_contexts.last_mut() {
+                        load_context.module = module.module_decl.name.clone();
+                    }
+
+                    *top_level_term_dirs = mem::replace(
+                        &mut worker.term_dirs,
+                        TermDirQuantum::new(),
+                    );
+                }
+            } else if decl.is_end_of_file() {
+                break;
+            } else {
+                self.process_and_commit_decl(decl, worker, indices, flags)
+                    .map_err(|e| in_included_file(worker, e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // hands the code of the predicates redefined by the listing to
+    // the machine, once their new code has been added.
+    fn retire_code(&mut self, wam: &mut Machine) {
+        for p in self.retired_code.drain(..) {
+            wam.indices.retire_code(p);
+        }
+    }
+
     fn drop_expansions(&self, code_repo: &mut CodeRepo) {
         let (te_len, te_queue_len) = self.orig_term_expansion_lens;
         let (ge_len, ge_queue_len) = self.orig_goal_expansion_lens;
@@ -1189,10 +1363,24 @@ impl ListingCompiler {
         discard_result!(code_repo.compile_hook(CompileTimeHook::UserTermExpansion));
     }
 
-    fn print_error(&self, e: &SessionError) {
+    fn print_error(&self, wam: &mut Machine, e: &SessionError) {
+        let (src_name, e) = match e {
+            SessionError::InIncludedFile(filename, e) => (filename.clone(), e.as_ref()),
+            e => (self.listing_src.name(), e),
+        };
+
         if let &SessionError::ParserError(ref e) = e {
             if let Some((line_num, _col_num)) = e.line_and_col_num() {
-                println!("{}:{}: {}", self.listing_src.name(), line_num, e.as_str());
+                let line_num = Constant::Integer(Integer::from(line_num));
+                let description = clause_name!(e.as_str().to_string(), wam.indices.atom_tbl);
+
+                let message = message_term("syntax_error", vec![
+                    atom_term(src_name),
+                    Term::Constant(Cell::default(), line_num),
+                    atom_term(description),
+                ]);
+
+                wam.print_message("error", message);
             }
         }
     }
@@ -1204,6 +1392,11 @@ fn compile_work_impl(
     mut indices: IndexStore,
     mut results: GatherResult,
 ) -> Result<(), SessionError> {
+    let module_name = match &compiler.module {
+        Some(module) if !module.is_impromptu_module => module.module_decl.name.clone(),
+        _ => clause_name!("user"),
+    };
+
     if let Some(ref mut module) = &mut compiler.module {
         // compile the module-level goal and term expansions and store
         // their locations to the module's code_dir.
@@ -1269,8 +1462,9 @@ fn compile_work_impl(
         wam.check_toplevel_code(&results.toplevel_indices)?;
         clause_code_generator.generate_clause_code(&results.dynamic_clause_map, wam)?;
 
-        if let Some(ref module) = wam.indices.modules.swap_remove(&module.module_decl.name) {
+        if let Some(ref module) = wam.indices.modules.shift_remove(&module.module_decl.name) {
             wam.indices.remove_module(clause_name!("user"), module);
+            wam.indices.retire_module_code(module);
         }
 
         if module.is_impromptu_module {
@@ -1298,17 +1492,27 @@ fn compile_work_impl(
         )?;
     }
 
+    compiler.retire_code(wam);
+
     let init_goal_code = compiler.generate_init_goal_code()?;
 
     if init_goal_code.len() > 0 {
-	if !wam.run_init_code(init_goal_code) {
-            println!("Warning: initialization goal for {} failed",
-                     compiler.listing_src.name());
+        let atom_tbl = wam.indices.atom_tbl.clone();
+        let load_context = load_context(atom_tbl, &compiler.listing_src, module_name);
+
+        wam.indices.load_contexts.push(load_context);
+        let succeeded = wam.run_init_code(init_goal_code);
+        wam.indices.load_contexts.pop();
+
+	if !succeeded {
+            let file_name = atom_term(compiler.listing_src.name());
+            wam.print_message("warning", message_term("initialization_failed", vec![file_name]));
         }
     }
 
     if !compiler.suppress_warnings {
         issue_singleton_warnings(
+            wam,
             compiler.listing_src.name(),
             results.top_level_terms,
         );
@@ -1323,7 +1527,6 @@ fn compile_work(
     src: Stream,
     mut indices: IndexStore,
 ) -> EvalSession {
-    let src = &mut parsing_stream(src);
     let results = try_eval_session!(compiler.gather_items(wam, src, &mut indices));
 
     try_eval_session!(compile_work_impl(compiler, wam, indices, results));
@@ -1342,7 +1545,6 @@ pub fn compile_special_form(
     let mut indices = default_index_store!(wam.indices.atom_tbl.clone());
     setup_indices(wam, clause_name!("builtins"), &mut indices)?;
 
-    let src = &mut parsing_stream(src);
     let mut compiler = ListingCompiler::new(&wam.code_repo, true, listing_src);
     let mut results = compiler.gather_items(wam, src, &mut indices)?;
 
@@ -1362,6 +1564,7 @@ pub fn compile_special_form(
     add_toplevel(wam, indices, top_level_term_dir);
 
     wam.code_repo.code.extend(results.in_situ_code.into_iter());
+    compiler.retire_code(wam);
 
     Ok(p)
 }
@@ -1374,12 +1577,15 @@ pub fn compile_listing(
     suppress_warnings: bool,
     listing_src: ListingSource,
 ) -> EvalSession {
+    log_event!(info, "consulting {}", listing_src.name().as_str());
+
     let mut compiler = ListingCompiler::new(&wam.code_repo, suppress_warnings, listing_src);
 
     match compile_work(&mut compiler, wam, src, indices) {
         EvalSession::Error(e) => {
+            log_event!(warn, "consult failed: {}", e);
             compiler.drop_expansions(&mut wam.code_repo);
-            compiler.print_error(&e);
+            compiler.print_error(wam, &e);
 
             EvalSession::Error(e)
         }
@@ -3,7 +3,7 @@ use crate::prolog::machine::stack::*;
 
 use std::ops::IndexMut;
 
-type Trail = Vec<(Ref, HeapCellValue)>;
+pub(crate) type CopyTrail = Vec<(Ref, HeapCellValue)>;
 
 #[derive(Clone, Copy)]
 pub enum AttrVarPolicy {
@@ -21,12 +21,25 @@ pub(crate) trait CopierTarget: IndexMut<usize, Output = HeapCellValue> {
 
 pub(crate)
 fn copy_term<T: CopierTarget>(target: T, addr: Addr, attr_var_policy: AttrVarPolicy) {
-    let mut copy_term_state = CopyTermState::new(target, attr_var_policy);
+    copy_term_with_trail(target, addr, attr_var_policy, vec![]);
+}
+
+// copies as copy_term does, using trail, which is returned empty, for
+// the bindings made while copying.
+pub(crate)
+fn copy_term_with_trail<T: CopierTarget>(
+    target: T,
+    addr: Addr,
+    attr_var_policy: AttrVarPolicy,
+    trail: CopyTrail,
+) -> CopyTrail {
+    let mut copy_term_state = CopyTermState::new(target, attr_var_policy, trail);
     copy_term_state.copy_term_impl(addr);
+    copy_term_state.trail
 }
 
 struct CopyTermState<T: CopierTarget> {
-    trail: Trail,
+    trail: CopyTrail,
     scan: usize,
     old_h: usize,
     target: T,
@@ -34,9 +47,9 @@ struct CopyTermState<T: CopierTarget> {
 }
 
 impl<T: CopierTarget> CopyTermState<T> {
-    fn new(target: T, attr_var_policy: AttrVarPolicy) -> Self {
+    fn new(target: T, attr_var_policy: AttrVarPolicy, trail: CopyTrail) -> Self {
         CopyTermState {
-            trail: vec![],
+            trail,
             scan: 0,
             old_h: target.threshold(),
             target,
@@ -260,6 +273,9 @@ impl<T: CopierTarget> CopyTermState<T> {
         self.scan += 1;
     }
 
+    // the copy is made breadth first: scan walks the cells already
+    // copied to the target, so no subterms are held on the native
+    // stack, however deep the term.
     fn copy_term_impl(&mut self, addr: Addr) {
         self.scan = self.target.threshold();
         self.target.push(HeapCellValue::Addr(addr));
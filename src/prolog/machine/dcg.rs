@@ -0,0 +1,191 @@
+use prolog_parser::ast::*;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/* grammar rules, Head --> Body, are translated to clauses as they are
+ * loaded, once any term_expansion/2 hooks have declined them. a pair
+ * of list variables, named $S0, $S1, .. since no variable read can
+ * begin with $, is threaded through the nonterminals of the body. */
+pub(crate) fn is_grammar_rule(term: &Term) -> bool {
+    match term {
+        &Term::Clause(_, ref name, ref terms, _) => name.as_str() == "-->" && terms.len() == 2,
+        _ => false,
+    }
+}
+
+pub(crate) fn translate_grammar_rule(term: Term) -> Result<Term, ParserError> {
+    match term {
+        Term::Clause(_, _, mut terms, _) if terms.len() == 2 => {
+            let body = *terms.pop().unwrap();
+            let head = *terms.pop().unwrap();
+
+            GrammarRuleTranslator { var_count: 0 }.rule(head, body)
+        }
+        _ => Err(ParserError::InvalidRuleHead),
+    }
+}
+
+#[inline]
+fn clause(name: &'static str, terms: Vec<Term>) -> Term {
+    Term::Clause(Cell::default(), clause_name!(name), terms.into_iter().map(Box::new).collect(), None)
+}
+
+#[inline]
+fn unify(t1: Term, t2: Term) -> Term {
+    clause("=", vec![t1, t2])
+}
+
+// the terminals of a list or a string, ending in tail, or None if
+// the list is partial.
+fn terminal_list(term: Term, tail: Term) -> Option<Term> {
+    let mut terminals = vec![];
+    let mut term = term;
+
+    loop {
+        match term {
+            Term::Cons(_, head, next) => {
+                terminals.push(*head);
+                term = *next;
+            }
+            Term::Constant(_, Constant::String(n, s)) => {
+                terminals.extend(s[n ..].chars().map(|c| Term::Constant(Cell::default(), Constant::Char(c))));
+                break;
+            }
+            Term::Constant(_, Constant::EmptyList) => {
+                break;
+            }
+            _ => {
+                return None;
+            }
+        }
+    }
+
+    Some(terminals.into_iter().rev().fold(tail, |tail, terminal| {
+        Term::Cons(Cell::default(), Box::new(terminal), Box::new(tail))
+    }))
+}
+
+// the goal of a nonterminal, which takes s0 and s as its last two
+// arguments. call//N and phrase//1 are nonterminals in this sense.
+fn non_terminal(term: Term, s0: Term, s: Term) -> Result<Term, Term> {
+    match term {
+        Term::Clause(cell, name, mut terms, _) => {
+            if name.as_str() == ":" && terms.len() == 2 {
+                let non_terminal = non_terminal(*terms.pop().unwrap(), s0, s)?;
+                terms.push(Box::new(non_terminal));
+
+                return Ok(Term::Clause(cell, name, terms, None));
+            }
+
+            terms.push(Box::new(s0));
+            terms.push(Box::new(s));
+
+            Ok(Term::Clause(cell, name, terms, None))
+        }
+        Term::Constant(cell, Constant::Atom(name, _)) => {
+            Ok(Term::Clause(cell, name, vec![Box::new(s0), Box::new(s)], None))
+        }
+        term => Err(term),
+    }
+}
+
+struct GrammarRuleTranslator {
+    var_count: usize,
+}
+
+impl GrammarRuleTranslator {
+    fn fresh_var(&mut self) -> Term {
+        let var = Term::Var(Cell::default(), Rc::new(format!("$S{}", self.var_count)));
+        self.var_count += 1;
+        var
+    }
+
+    fn rule(&mut self, head: Term, body: Term) -> Result<Term, ParserError> {
+        let s0 = self.fresh_var();
+        let s = self.fresh_var();
+
+        // a pushback list, Head, PushBack --> Body, is put back on
+        // the list once the body is done with it.
+        let (head, pushback) = match head {
+            Term::Clause(_, ref name, ref terms, _) if name.as_str() == "," && terms.len() == 2 => {
+                (*terms[0].clone(), Some(*terms[1].clone()))
+            }
+            head => (head, None),
+        };
+
+        let head = non_terminal(head, s0.clone(), s.clone())
+            .map_err(|_| ParserError::InvalidRuleHead)?;
+
+        let body = match pushback {
+            Some(pushback) => {
+                let s1 = self.fresh_var();
+                let goal = self.body(body, s0, s1.clone());
+                let list = terminal_list(pushback, s1).ok_or(ParserError::InvalidRuleHead)?;
+
+                clause(",", vec![goal, unify(s, list)])
+            }
+            None => self.body(body, s0, s),
+        };
+
+        Ok(clause(":-", vec![head, body]))
+    }
+
+    fn body(&mut self, term: Term, s0: Term, s: Term) -> Term {
+        match term {
+            Term::Var(..) => clause("phrase", vec![term, s0, s]),
+            Term::Clause(cell, name, mut terms, fixity) => match (name.as_str(), terms.len()) {
+                (",", 2) => {
+                    let s1 = self.fresh_var();
+                    let second = self.body(*terms.pop().unwrap(), s1.clone(), s);
+                    let first = self.body(*terms.pop().unwrap(), s0, s1);
+
+                    clause(",", vec![first, second])
+                }
+                (";", 2) | ("|", 2) => {
+                    let second = self.body(*terms.pop().unwrap(), s0.clone(), s.clone());
+                    let first = self.body(*terms.pop().unwrap(), s0, s);
+
+                    clause(";", vec![first, second])
+                }
+                ("->", 2) => {
+                    let s1 = self.fresh_var();
+                    let then = self.body(*terms.pop().unwrap(), s1.clone(), s);
+                    let cond = self.body(*terms.pop().unwrap(), s0, s1);
+
+                    clause("->", vec![cond, then])
+                }
+                ("\\+", 1) => {
+                    let s1 = self.fresh_var();
+                    let goal = self.body(*terms.pop().unwrap(), s0.clone(), s1);
+
+                    clause(",", vec![clause("\\+", vec![goal]), unify(s0, s)])
+                }
+                ("{}", 1) => {
+                    let goal = *terms.pop().unwrap();
+                    clause(",", vec![goal, unify(s0, s)])
+                }
+                _ => match non_terminal(Term::Clause(cell, name, terms, fixity), s0.clone(), s.clone()) {
+                    Ok(goal) => goal,
+                    Err(term) => clause("phrase", vec![term, s0, s]),
+                },
+            },
+            Term::Constant(_, Constant::Atom(ref name, _)) if name.as_str() == "!" => {
+                let cut = Term::Constant(Cell::default(), Constant::Atom(clause_name!("!"), None));
+                clause(",", vec![cut, unify(s0, s)])
+            }
+            Term::Constant(_, Constant::EmptyList) => unify(s0, s),
+            Term::Cons(..) | Term::Constant(_, Constant::String(..)) => {
+                match terminal_list(term.clone(), s.clone()) {
+                    Some(list) => unify(s0, list),
+                    None => clause("phrase", vec![term, s0, s]),
+                }
+            }
+            // anything else is left to phrase/3 to raise a type error.
+            term => match non_terminal(term, s0.clone(), s.clone()) {
+                Ok(goal) => goal,
+                Err(term) => clause("phrase", vec![term, s0, s]),
+            },
+        }
+    }
+}
@@ -125,7 +125,7 @@ impl Machine {
         pred_str: String,
         name: ClauseName,
         arity: usize,
-        src: ClauseName,
+        stub: MachineStub,
     ) {
         let machine_st = mem::replace(&mut self.machine_st, MachineState::new());
 
@@ -139,7 +139,6 @@ impl Machine {
 
         if let EvalSession::Error(err) = result {
             let h = self.machine_st.heap.h();
-            let stub = MachineError::functor_stub(src, 1);
             let err = MachineError::session_error(h, err);
             let err = self.machine_st.error_form(err, stub);
 
@@ -165,7 +164,8 @@ impl Machine {
             Err(err) => return self.machine_st.throw_exception(err),
         };
 
-        self.handle_eval_result_from_dynamic_compile(pred_str, name, arity, place.predicate_name());
+        let stub = MachineError::functor_stub(place.predicate_name(), 1);
+        self.handle_eval_result_from_dynamic_compile(pred_str, name, arity, stub);
     }
 
     fn set_module_atom_tbl(&mut self, module_addr: Addr, name: &mut ClauseName) -> bool {
@@ -231,7 +231,7 @@ impl Machine {
                 pred_str,
                 name,
                 arity,
-                clause_name!("retract"),
+                MachineError::functor_stub(clause_name!("retract"), 1),
             );
         }
     }
@@ -264,15 +264,45 @@ impl Machine {
             pred_str,
             name,
             arity,
-            clause_name!("retract"),
+            MachineError::functor_stub(clause_name!("retract"), 1),
         );
     }
 
+    // appends a list of clauses to a dynamic predicate, recompiling it
+    // once rather than once per clause.
+    fn bulk_assert_dynamic_predicate(&mut self) {
+        let (name, arity) = self.get_predicate_key(temp_v!(3), temp_v!(4));
+        let stub = MachineError::functor_stub(clause_name!("load_facts"), 2);
+
+        let pred_str = match self.machine_st.try_from_list(temp_v!(2), stub.clone()) {
+            Ok(addrs) => {
+                let mut addrs = VecDeque::from(addrs);
+
+                match self.machine_st.try_from_list(temp_v!(1), stub.clone()) {
+                    Ok(added_clauses) => addrs.extend(added_clauses),
+                    Err(err) => return self.machine_st.throw_exception(err),
+                }
+
+                self.print_new_dynamic_clause(addrs, name.clone(), arity)
+            }
+            Err(err) => return self.machine_st.throw_exception(err),
+        };
+
+        self.handle_eval_result_from_dynamic_compile(pred_str, name, arity, stub);
+    }
+
     pub(super) fn dynamic_transaction(
         &mut self,
         trans_type: DynamicTransactionType,
         p: LocalCodePtr,
     ) {
+        log_event!(debug, "dynamic transaction: {}", match trans_type {
+            DynamicTransactionType::Abolish | DynamicTransactionType::ModuleAbolish => "abolish",
+            DynamicTransactionType::Assert(_) | DynamicTransactionType::ModuleAssert(_) => "assert",
+            DynamicTransactionType::BulkAssert => "bulk assert",
+            DynamicTransactionType::Retract | DynamicTransactionType::ModuleRetract => "retract",
+        });
+
         match trans_type {
             DynamicTransactionType::Abolish => {
                 self.abolish_dynamic_clause(temp_v!(1), temp_v!(2))
@@ -280,6 +310,9 @@ impl Machine {
             DynamicTransactionType::Assert(place) => {
                 self.recompile_dynamic_predicate(place)
             }
+            DynamicTransactionType::BulkAssert => {
+                self.bulk_assert_dynamic_predicate()
+            }
             DynamicTransactionType::ModuleAbolish => {
                 self.abolish_dynamic_clause_in_module(temp_v!(1), temp_v!(2), temp_v!(3))
             }
@@ -294,6 +327,7 @@ impl Machine {
             }
         }
 
+        self.indices.invalidate_module_calls();
         self.machine_st.p = CodePtr::Local(p);
     }
 }
@@ -0,0 +1,499 @@
+use prolog_parser::ast::*;
+use prolog_parser::parser::*;
+
+use crate::prolog::forms::*;
+use crate::prolog::heap_print::*;
+use crate::prolog::machine::compile::*;
+use crate::prolog::machine::machine_errors::*;
+use crate::prolog::machine::streams::*;
+use crate::prolog::machine::*;
+use crate::prolog::bignum::Integer;
+use crate::prolog::ordered_float::OrderedFloat;
+use crate::prolog::read::write_term_to_heap;
+
+use std::cell::Cell;
+use std::mem;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::time::Duration;
+
+/* the embedding API. a Rust program builds a machine with
+ * Machine::new_embedded, loads its code with Machine::consult, and
+ * iterates the solutions of queries given as text with
+ * Machine::run_query:
+ *
+ *     let mut wam = Machine::new_embedded();
+ *     wam.consult("p(1). p(2).")?;
+ *
+ *     for solution in wam.run_query("p(X)") {
+ *         ...
+ *     }
+ *
+ * each query is run under catch/3, so that an uncaught exception
+ * ends its solutions rather than the host program. Machine::solve
+ * passes the solutions to a callback instead, which can stop them
+ * early:
+ *
+ *     wam.solve("p(X)", |solution| {
+ *         ...
+ *         ControlFlow::Break(())
+ *     });
+ *
+ * a query run many times over is better read once by
+ * Machine::prepare_query, naming the variables that are its
+ * parameters, and then run by Machine::run_prepared with a term for
+ * each of them:
+ *
+ *     let query = wam.prepare_query("p(X), Y is X * 2", &["X"])?;
+ *
+ *     for solution in wam.run_prepared(&query, &[x]) {
+ *         ...
+ *     }
+ *
+ * a run writes the query and its arguments to the heap of the reset
+ * machine, which is reused from run to run, and calls it. nothing is
+ * read or compiled again.
+ *
+ * a server running the queries of many users in one process bounds
+ * each of them by Machine::run_query_with_limits, which runs it once
+ * under call_with_resource_limits/2:
+ *
+ *     let limits = ResourceLimits { memory: Some(1 << 20), ..ResourceLimits::default() };
+ *
+ *     for solution in wam.run_query_with_limits("p(X)", &limits) {
+ *         ...
+ *     }
+ *
+ * a query exceeding a limit ends with the exception
+ * resource_limit_exceeded(Which), naming the limit.
+ */
+
+// an answer to a query run by Machine::run_query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Solution {
+    // the named variables of the query, other than those beginning
+    // with an underscore, paired with the quoted text of their values.
+    Bindings(Vec<(String, String)>),
+    // the quoted text of an uncaught ball, or of the syntax error of
+    // a query that couldn't be read. it is the last solution of its
+    // query.
+    Exception(String),
+}
+
+// a query read by Machine::prepare_query, to be run any number of
+// times with its parameters bound to the arguments of each run.
+#[derive(Clone)]
+pub struct PreparedQuery {
+    goal: Term,
+    params: Vec<String>,
+}
+
+impl PreparedQuery {
+    #[inline]
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+}
+
+// the limits of a query run by Machine::run_query_with_limits, as
+// the inferences(N), time(T) and memory(Cells) limits taken by
+// call_with_resource_limits/2. a limit of None isn't imposed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub inferences: Option<u64>,
+    pub time: Option<Duration>,
+    // the number of cells the heap may grow by.
+    pub memory: Option<usize>,
+}
+
+impl ResourceLimits {
+    // the list of limits passed to call_with_resource_limits/2.
+    fn to_term(&self) -> Term {
+        let mut limits = vec![];
+
+        if let Some(n) = self.inferences {
+            limits.push(goal_term("inferences", vec![integer_term(Integer::from(n))]));
+        }
+
+        if let Some(time) = self.time {
+            let secs = Constant::Float(OrderedFloat(time.as_secs_f64()));
+            limits.push(goal_term("time", vec![Term::Constant(Cell::default(), secs)]));
+        }
+
+        if let Some(cells) = self.memory {
+            limits.push(goal_term("memory", vec![integer_term(Integer::from(cells))]));
+        }
+
+        let empty_list = Term::Constant(Cell::default(), Constant::EmptyList);
+
+        limits.into_iter().rev().fold(empty_list, |tail, head| {
+            Term::Cons(Cell::default(), Box::new(head), Box::new(tail))
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrepareError {
+    // the quoted text of the syntax error of the query.
+    Syntax(String),
+    // a parameter that isn't a named variable of the query.
+    UnknownParameter(String),
+}
+
+enum SolutionsState {
+    Unreadable(String),
+    Unstarted,
+    Running,
+    Exhausted,
+}
+
+// the solutions of a query, found as they are iterated. dropping it
+// abandons the query.
+pub struct Solutions<'a> {
+    wam: &'a mut Machine,
+    end_ptr: CodePtr,
+    bindings: Vec<(String, Addr)>,
+    ball: Addr,
+    // the variable of a query run by Machine::solve that, once bound
+    // to stop, has the query cut its choicepoints on backtracking.
+    stop: Option<Addr>,
+    state: SolutionsState,
+}
+
+impl<'a> Solutions<'a> {
+    fn solution(&self) -> Solution {
+        let ball = self.wam.machine_st.store(self.wam.machine_st.deref(self.ball.clone()));
+
+        if !ball.is_ref() {
+            return Solution::Exception(self.wam.quoted_text(ball));
+        }
+
+        let bindings = self.bindings.iter().map(|(name, addr)| {
+            (name.clone(), self.wam.quoted_text(addr.clone()))
+        });
+
+        Solution::Bindings(bindings.collect())
+    }
+
+    // ends the solutions of a query run by Machine::solve, cutting
+    // the choicepoints it left so that the cleanup goals of any
+    // setup_call_cleanup/3 calls among them are run.
+    fn stop(&mut self) {
+        if let SolutionsState::Running = self.state {
+            if let Some(Addr::HeapCell(h)) = self.stop {
+                // the variable is older than every choicepoint of the
+                // query, so its binding is left untrailed to outlast
+                // the backtracking that sees it.
+                self.wam.machine_st.heap[h] = HeapCellValue::Addr(Addr::Con(atom!("stop")));
+                self.next();
+            }
+        }
+
+        self.state = SolutionsState::Exhausted;
+    }
+}
+
+impl<'a> Iterator for Solutions<'a> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        match self.state {
+            SolutionsState::Unreadable(ref mut ball) => {
+                let ball = mem::replace(ball, String::new());
+                self.state = SolutionsState::Exhausted;
+
+                return Some(Solution::Exception(ball));
+            }
+            SolutionsState::Unstarted => {
+                self.state = SolutionsState::Running;
+            }
+            SolutionsState::Running => {
+                // retry the most recent choicepoint of the query, as
+                // the toplevel does when asked for another answer.
+                if self.wam.machine_st.b == 0 {
+                    self.state = SolutionsState::Exhausted;
+                    return None;
+                }
+
+                self.wam.machine_st.backtrack();
+            }
+            SolutionsState::Exhausted => {
+                return None;
+            }
+        }
+
+        if !self.wam.machine_st.fail {
+            self.wam.resume_query(self.end_ptr.clone(), None);
+        }
+
+        if self.wam.machine_st.fail {
+            self.state = SolutionsState::Exhausted;
+            return None;
+        }
+
+        let solution = self.solution();
+
+        if let Solution::Exception(_) = solution {
+            self.state = SolutionsState::Exhausted;
+        }
+
+        Some(solution)
+    }
+}
+
+impl Machine {
+    // a machine with the libraries loaded by Machine::new, reading
+    // from an empty input stream and writing to standard output. unlike
+    // Machine::new, it doesn't consult ~/.scryerrc, so that a host
+    // program behaves the same for every user.
+    pub fn new_embedded() -> Self {
+        Machine::with_streams(Stream::from(String::new()), Stream::stdout())
+    }
+
+    // compiles src into the user module, as consult/1 would a file.
+    pub fn consult(&mut self, src: &str) -> Result<(), SessionError> {
+        match compile_user_module(self, Stream::from(src.to_string()), false, ListingSource::User) {
+            EvalSession::EntrySuccess => Ok(()),
+            EvalSession::Error(err) => Err(err),
+        }
+    }
+
+    // reads query, a goal optionally ended by a full stop, and returns
+    // its solutions, discarding any query run before it.
+    pub fn run_query(&mut self, query: &str) -> Solutions {
+        match self.read_query(query) {
+            Ok(goal) => self.solutions(&wrap_query(goal, false), &[], &[]),
+            Err(ball) => self.unreadable(ball),
+        }
+    }
+
+    // reads query as run_query does, and returns its first solution
+    // found within limits. exceeding one ends the solutions with the
+    // exception resource_limit_exceeded(Which).
+    pub fn run_query_with_limits(&mut self, query: &str, limits: &ResourceLimits) -> Solutions {
+        match self.read_query(query) {
+            Ok(goal) => {
+                let goal = goal_term(":", vec![
+                    atom_term("iso_ext"),
+                    goal_term("call_with_resource_limits", vec![goal, limits.to_term()]),
+                ]);
+
+                self.solutions(&wrap_query(goal, false), &[], &[])
+            }
+            Err(ball) => self.unreadable(ball),
+        }
+    }
+
+    // calls f with the solutions of query, as run_query finds them,
+    // until f breaks or they run out, returning the value f broke
+    // with. the choicepoints left by the query when f breaks are cut,
+    // running the cleanup goals of any setup_call_cleanup/3 calls
+    // they belong to.
+    pub fn solve<B, F>(&mut self, query: &str, f: F) -> Option<B>
+        where F: FnMut(Solution) -> ControlFlow<B>
+    {
+        match self.read_query(query) {
+            Ok(goal) => solve_with(self.solutions(&wrap_query(goal, true), &[], &[]), f),
+            Err(ball) => solve_with(self.unreadable(ball), f),
+        }
+    }
+
+    // reads query as run_query does, to be run by run_prepared and
+    // solve_prepared with its variables named by params bound to
+    // their arguments. the parameters aren't among the bindings of
+    // its solutions.
+    pub fn prepare_query(&mut self, query: &str, params: &[&str]) -> Result<PreparedQuery, PrepareError> {
+        let goal = self.read_query(query).map_err(PrepareError::Syntax)?;
+
+        let mut query_vars = vec![];
+        collect_var_names(&goal, &mut query_vars);
+
+        for param in params {
+            if *param == "_" || !query_vars.iter().any(|var| var.as_str() == *param) {
+                return Err(PrepareError::UnknownParameter(param.to_string()));
+            }
+        }
+
+        Ok(PreparedQuery {
+            goal: wrap_query(goal, true),
+            params: params.iter().map(|param| param.to_string()).collect(),
+        })
+    }
+
+    // the solutions of query with its parameters bound to args, in
+    // order, discarding any query run before it. panics unless there
+    // is an argument for each parameter.
+    pub fn run_prepared(&mut self, query: &PreparedQuery, args: &[Term]) -> Solutions {
+        assert_eq!(query.params.len(), args.len(), "an argument is needed for each parameter");
+        self.solutions(&query.goal, &query.params, args)
+    }
+
+    // calls f with the solutions of query as solve does, with its
+    // parameters bound to args as by run_prepared.
+    pub fn solve_prepared<B, F>(&mut self, query: &PreparedQuery, args: &[Term], f: F) -> Option<B>
+        where F: FnMut(Solution) -> ControlFlow<B>
+    {
+        solve_with(self.run_prepared(query, args), f)
+    }
+
+    fn read_query(&mut self, query: &str) -> Result<Term, String> {
+        let mut query = query.trim_end().to_string();
+
+        if !query.ends_with('.') {
+            query.push_str(" .");
+        }
+
+        let mut stream = parsing_stream(std::io::Cursor::new(query));
+        let mut parser = Parser::new(&mut stream, self.indices.atom_tbl.clone(), self.machine_flags());
+
+        match parser.read_term(composite_op!(&self.indices.op_dir)) {
+            Ok(mut goal) => {
+                self.indices.string_tbl.intern_term(&mut goal);
+                Ok(goal)
+            }
+            Err(err) => Err(format!("error(syntax_error({}),_)", err.as_str())),
+        }
+    }
+
+    fn unreadable(&mut self, ball: String) -> Solutions {
+        Solutions {
+            wam: self,
+            end_ptr: CodePtr::default(),
+            bindings: vec![],
+            ball: Addr::Con(Constant::EmptyList),
+            stop: None,
+            state: SolutionsState::Unreadable(ball),
+        }
+    }
+
+    // the solutions of goal, a query wrapped by wrap_query, with its
+    // variables named by params bound to args. the arguments are
+    // written to the heap after the goal, and their variables are
+    // bound to them in place.
+    fn solutions(&mut self, goal: &Term, params: &[String], args: &[Term]) -> Solutions {
+        let (end_ptr, result) = self.cache_goal(goal);
+
+        for (param, arg) in params.iter().zip(args) {
+            if let Some(addr) = result.var_dict.get(param).cloned() {
+                let arg = write_term_to_heap(arg, &mut self.machine_st);
+                self.machine_st.unify(addr, Addr::HeapCell(arg.heap_loc));
+            }
+        }
+
+        let mut ball = Addr::HeapCell(result.heap_loc);
+        let mut stop = None;
+        let mut bindings = vec![];
+
+        for (var, addr) in result.var_dict {
+            if var.as_str() == "$ball" {
+                ball = addr;
+            } else if var.as_str() == "$stop" {
+                stop = Some(addr);
+            } else if !var.starts_with('_') && !var.starts_with('$')
+                   && !params.iter().any(|param| param.as_str() == var.as_str())
+            {
+                bindings.push((var.to_string(), addr));
+            }
+        }
+
+        Solutions {
+            wam: self,
+            end_ptr,
+            bindings,
+            ball,
+            stop,
+            state: SolutionsState::Unstarted,
+        }
+    }
+
+    fn quoted_text(&self, addr: Addr) -> String {
+        let mut printer = HCPrinter::new(&self.machine_st, &self.indices.op_dir, PrinterOutputter::new());
+
+        printer.quoted = true;
+        printer.numbervars = true;
+
+        printer.print(addr).result()
+    }
+}
+
+fn solve_with<B, F>(mut solutions: Solutions, mut f: F) -> Option<B>
+    where F: FnMut(Solution) -> ControlFlow<B>
+{
+    while let Some(solution) = solutions.next() {
+        if let ControlFlow::Break(value) = f(solution) {
+            solutions.stop();
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+// goal run under catch/3. if stoppable, it is followed by a
+// choicepoint that, once its stop variable is bound, cuts those of
+// the goal:
+//
+//     '$get_level'(B0), catch(Goal, Ball, true),
+//     ( true ; Stop == stop, '$set_cp'(B0), false )
+//
+// the variables added to the goal can't clash with its own, since
+// variable names never begin with '$'.
+fn wrap_query(goal: Term, stoppable: bool) -> Term {
+    let goal = goal_term("catch", vec![goal, var_term("$ball"), atom_term("true")]);
+
+    if stoppable {
+        let guard = goal_term(";", vec![
+            atom_term("true"),
+            goal_term(",", vec![
+                goal_term("==", vec![var_term("$stop"), atom_term("stop")]),
+                goal_term(",", vec![
+                    goal_term("$set_cp", vec![var_term("$b0")]),
+                    atom_term("false"),
+                ]),
+            ]),
+        ]);
+
+        goal_term(",", vec![
+            goal_term("$get_level", vec![var_term("$b0")]),
+            goal_term(",", vec![goal, guard]),
+        ])
+    } else {
+        goal
+    }
+}
+
+fn collect_var_names(term: &Term, names: &mut Vec<String>) {
+    match term {
+        &Term::Var(_, ref var) => names.push(var.to_string()),
+        &Term::Clause(_, _, ref args, _) => {
+            for arg in args {
+                collect_var_names(arg, names);
+            }
+        }
+        &Term::Cons(_, ref head, ref tail) => {
+            collect_var_names(head, names);
+            collect_var_names(tail, names);
+        }
+        _ => {}
+    }
+}
+
+#[inline]
+fn atom_term(name: &'static str) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(clause_name!(name), None))
+}
+
+#[inline]
+fn integer_term(n: Integer) -> Term {
+    Term::Constant(Cell::default(), Constant::Integer(n))
+}
+
+#[inline]
+fn var_term(name: &str) -> Term {
+    Term::Var(Cell::default(), Rc::new(String::from(name)))
+}
+
+#[inline]
+fn goal_term(name: &'static str, args: Vec<Term>) -> Term {
+    Term::Clause(Cell::default(), clause_name!(name), args.into_iter().map(Box::new).collect(), None)
+}
@@ -0,0 +1,584 @@
+use prolog_parser::ast::*;
+use prolog_parser::tabled_rc::*;
+
+use crate::prolog::forms::*;
+use crate::prolog::machine::machine_errors::*;
+use crate::prolog::machine::machine_indices::*;
+use crate::prolog::machine::machine_state::*;
+use crate::prolog::machine::copier::AttrVarPolicy;
+use crate::prolog::ordered_float::OrderedFloat;
+use crate::prolog::bignum::{Integer, Rational};
+
+use indexmap::IndexMap;
+
+use std::io::{self, ErrorKind, Read};
+use std::rc::Rc;
+
+/* the fast term format is a pre-order traversal of the term, each
+ * node prefixed by a one byte tag. lengths, arities, indices and
+ * small integers are written as LEB128 varints (integers zigzag
+ * encoded first). atoms are written in full, together with the name
+ * of their owning module, on first occurrence and referenced by their
+ * index in the per-term atom table thereafter, unless they are
+ * written by their ids in the machine's atom table (see AtomIds);
+ * variables are likewise numbered by first occurrence. under the
+ * DeepCopy policy, the first occurrence of an attributed variable is
+ * tagged separately and followed by its attribute list. cut points
+ * are written only for checkpoints, which carry the cut barriers of
+ * a continuation; they are read back as the reader's current
+ * choicepoint, as call_continuation does with the first cut point
+ * of a chunk. */
+
+const FAST_TERM_MAGIC: [u8; 2] = [0xfa, 0x01];
+
+/* a saved atom table is the number of its atoms followed by each
+ * atom, in order of id, as it would follow TAG_ATOM in a term. */
+const ATOM_TABLE_MAGIC: [u8; 2] = [0xfa, 0x02];
+
+const TAG_VAR: u8 = 0;
+const TAG_ATOM: u8 = 1;
+const TAG_ATOM_REF: u8 = 2;
+const TAG_CHAR: u8 = 3;
+const TAG_INTEGER: u8 = 4;
+const TAG_BIG_INTEGER: u8 = 5;
+const TAG_RATIONAL: u8 = 6;
+const TAG_FLOAT: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_EMPTY_LIST: u8 = 9;
+const TAG_LIST: u8 = 10;
+const TAG_STRUCT: u8 = 11;
+const TAG_ATTR_VAR: u8 = 12;
+const TAG_CUT_POINT: u8 = 13;
+const TAG_ATOM_ID: u8 = 14;
+
+struct FastTermWriter<'a> {
+    bytes: Vec<u8>,
+    atoms: IndexMap<(ClauseName, ClauseName), usize>,
+    atom_ids: Option<&'a mut AtomIds>,
+    vars: IndexMap<Addr, usize>,
+    attr_var_policy: AttrVarPolicy,
+    cut_points: bool,
+}
+
+impl<'a> FastTermWriter<'a> {
+    fn new(
+        attr_var_policy: AttrVarPolicy,
+        cut_points: bool,
+        atom_ids: Option<&'a mut AtomIds>,
+    ) -> Self {
+        FastTermWriter {
+            bytes: FAST_TERM_MAGIC.to_vec(),
+            atoms: IndexMap::new(),
+            atom_ids,
+            vars: IndexMap::new(),
+            attr_var_policy,
+            cut_points,
+        }
+    }
+
+    fn write_varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.bytes.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+
+        self.bytes.push(n as u8);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_varint(s.len() as u64);
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+
+    fn write_atom(&mut self, name: &ClauseName) {
+        if let Some(id) = self.atom_ids.as_mut().map(|atom_ids| atom_ids.insert(name)) {
+            self.bytes.push(TAG_ATOM_ID);
+            self.write_varint(id as u64);
+            return;
+        }
+
+        let key = (name.clone(), name.owning_module());
+
+        if let Some(&idx) = self.atoms.get(&key) {
+            self.bytes.push(TAG_ATOM_REF);
+            self.write_varint(idx as u64);
+        } else {
+            let idx = self.atoms.len();
+
+            self.bytes.push(TAG_ATOM);
+            self.write_str(key.0.as_str());
+            self.write_str(key.1.as_str());
+
+            self.atoms.insert(key, idx);
+        }
+    }
+
+    // returns true iff the variable is seen for the first time.
+    fn write_var(&mut self, addr: Addr, tag: u8) -> bool {
+        let next_idx = self.vars.len();
+        let idx = *self.vars.entry(addr).or_insert(next_idx);
+
+        if idx == next_idx {
+            self.bytes.push(tag);
+        } else {
+            self.bytes.push(TAG_VAR);
+        }
+
+        self.write_varint(idx as u64);
+        idx == next_idx
+    }
+
+    fn write_integer(&mut self, n: &Integer) {
+        if let Some(n) = n.to_isize() {
+            let n = n as i64;
+
+            self.bytes.push(TAG_INTEGER);
+            self.write_varint(((n << 1) ^ (n >> 63)) as u64);
+        } else {
+            self.bytes.push(TAG_BIG_INTEGER);
+            self.write_str(&n.to_string());
+        }
+    }
+}
+
+// a stream that runs dry mid-term is a truncated term. other stream
+// failures are kept, to be reported as I/O errors.
+fn read_error(err: io::Error) -> FastTermError {
+    match err.kind() {
+        ErrorKind::UnexpectedEof => FastTermError::UnexpectedEOF,
+        kind => FastTermError::IO(kind),
+    }
+}
+
+struct FastTermReader<'a, R: Read> {
+    stream: &'a mut R,
+    atoms: Vec<ClauseName>,
+    vars: Vec<Addr>,
+}
+
+impl<'a, R: Read> FastTermReader<'a, R> {
+    fn new(stream: &'a mut R) -> Self {
+        FastTermReader {
+            stream,
+            atoms: vec![],
+            vars: vec![],
+        }
+    }
+
+    // returns None if the stream is exhausted before the first byte.
+    fn read_byte_or_eof(&mut self) -> Result<Option<u8>, FastTermError> {
+        let mut buf = [0u8; 1];
+
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(read_error(e)),
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, FastTermError> {
+        self.read_byte_or_eof()?.ok_or(FastTermError::UnexpectedEOF)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, FastTermError> {
+        let mut n = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let b = self.read_byte()?;
+
+            if shift >= 64 {
+                return Err(FastTermError::InvalidNumber);
+            }
+
+            n |= ((b & 0x7f) as u64) << shift;
+            shift += 7;
+
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, FastTermError> {
+        let len = self.read_varint()?;
+        let mut buf = vec![];
+
+        // the buffer grows with the bytes read, not the length
+        // claimed, which may be corrupt.
+        let read = self.stream
+            .by_ref()
+            .take(len)
+            .read_to_end(&mut buf)
+            .map_err(read_error)?;
+
+        if read as u64 != len {
+            return Err(FastTermError::UnexpectedEOF);
+        }
+
+        String::from_utf8(buf).map_err(|_| FastTermError::InvalidUTF8)
+    }
+
+    fn read_big_integer(&mut self) -> Result<Integer, FastTermError> {
+        let s = self.read_string()?;
+        s.parse::<Integer>().map_err(|_| FastTermError::InvalidNumber)
+    }
+
+    fn read_atom(&mut self, tag: u8, indices: &IndexStore) -> Result<ClauseName, FastTermError> {
+        match tag {
+            TAG_ATOM => {
+                let name = self.read_string()?;
+                let module_name = clause_name!(self.read_string()?, indices.atom_tbl);
+
+                // atoms keep their owning module so that, e.g., the
+                // attribute goals of a copied attributed variable are
+                // resolved in the module that defines them.
+                let atom_tbl = match indices.modules.get(&module_name) {
+                    Some(module) => module.atom_tbl.clone(),
+                    None => indices.atom_tbl.clone(),
+                };
+
+                let name = clause_name!(name, atom_tbl);
+                self.atoms.push(name.clone());
+                Ok(name)
+            }
+            TAG_ATOM_REF => {
+                let idx = self.read_varint()? as usize;
+
+                self.atoms
+                    .get(idx)
+                    .cloned()
+                    .ok_or(FastTermError::InvalidAtomIndex)
+            }
+            TAG_ATOM_ID => {
+                let id = self.read_varint()? as usize;
+
+                indices.atom_ids
+                    .get(id)
+                    .cloned()
+                    .ok_or(FastTermError::UnknownAtomId)
+            }
+            _ => Err(FastTermError::InvalidTag),
+        }
+    }
+}
+
+impl<'a> FastTermWriter<'a> {
+    fn write_term(&mut self, machine_st: &MachineState, addr: Addr) -> Result<(), Addr> {
+        let atom_double_quotes = machine_st.machine_flags().double_quotes.is_atom();
+
+        for value in machine_st.pre_order_iter(addr) {
+            match value {
+                HeapCellValue::NamedStr(arity, name, _) => {
+                    self.bytes.push(TAG_STRUCT);
+                    self.write_varint(arity as u64);
+                    self.write_atom(&name);
+                }
+                HeapCellValue::Addr(Addr::Lis(_))
+              | HeapCellValue::Addr(Addr::PStrLocation(..)) => {
+                    self.bytes.push(TAG_LIST);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::String(n, s))) => {
+                    if atom_double_quotes {
+                        self.bytes.push(TAG_STRING);
+                        self.write_str(&s[n ..]);
+                    } else {
+                        // the iterator unfolds the string into a list.
+                        self.bytes.push(TAG_LIST);
+                    }
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::EmptyList)) => {
+                    self.bytes.push(TAG_EMPTY_LIST);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::Atom(name, _))) => {
+                    self.write_atom(&name);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::Char(c))) => {
+                    self.bytes.push(TAG_CHAR);
+                    self.write_varint(c as u64);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::CharCode(c))) => {
+                    self.write_integer(&Integer::from(c));
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::Integer(n))) => {
+                    self.write_integer(&n);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::Rational(r))) => {
+                    self.bytes.push(TAG_RATIONAL);
+                    self.write_str(&r.numer().to_string());
+                    self.write_str(&r.denom().to_string());
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::Float(OrderedFloat(f)))) => {
+                    self.bytes.push(TAG_FLOAT);
+                    self.bytes.extend_from_slice(&f.to_bits().to_le_bytes());
+                }
+                HeapCellValue::Addr(Addr::AttrVar(h)) => {
+                    if let AttrVarPolicy::DeepCopy = self.attr_var_policy {
+                        if self.write_var(Addr::AttrVar(h), TAG_ATTR_VAR) {
+                            self.write_term(machine_st, Addr::HeapCell(h + 1))?;
+                        }
+                    } else {
+                        self.write_var(Addr::AttrVar(h), TAG_VAR);
+                    }
+                }
+                HeapCellValue::Addr(addr @ Addr::HeapCell(_))
+              | HeapCellValue::Addr(addr @ Addr::StackCell(..)) => {
+                    self.write_var(addr, TAG_VAR);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::CutPoint(_))) if self.cut_points => {
+                    self.bytes.push(TAG_CUT_POINT);
+                }
+                HeapCellValue::Addr(addr) => {
+                    return Err(addr);
+                }
+                HeapCellValue::PartialString(_) => {
+                    unreachable!()
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl MachineState {
+    /* serialize the term at addr. on failure, the unserializable
+     * subterm is returned. if atom_ids is given, atoms are written by
+     * their ids in it, registering those that have none. */
+    pub(super) fn fast_write_term(
+        &self,
+        addr: Addr,
+        attr_var_policy: AttrVarPolicy,
+        cut_points: bool,
+        atom_ids: Option<&mut AtomIds>,
+    ) -> Result<Vec<u8>, Addr> {
+        let mut writer = FastTermWriter::new(attr_var_policy, cut_points, atom_ids);
+
+        writer.write_term(self, addr)?;
+        Ok(writer.bytes)
+    }
+
+    /* read a term written by fast_write_term from stream onto the
+     * heap, returning its heap location, or None if the stream was
+     * already at its end. */
+    pub(super) fn fast_read_term<R: Read>(
+        &mut self,
+        stream: &mut R,
+        indices: &IndexStore,
+    ) -> Result<Option<usize>, FastTermError> {
+        let mut reader = FastTermReader::new(stream);
+
+        match reader.read_byte_or_eof()? {
+            None => return Ok(None),
+            Some(b) if b == FAST_TERM_MAGIC[0] => {
+                if reader.read_byte()? != FAST_TERM_MAGIC[1] {
+                    return Err(FastTermError::BadHeader);
+                }
+            }
+            Some(_) => return Err(FastTermError::BadHeader),
+        }
+
+        let h = self.heap.h();
+
+        match self.fast_read_cells(&mut reader, indices) {
+            Ok(root) => Ok(Some(root)),
+            Err(err) => {
+                // the cells of a malformed term are discarded.
+                self.heap.truncate(h);
+                Err(err)
+            }
+        }
+    }
+
+    // read the body of a term, following its header, onto the heap.
+    fn fast_read_cells<R: Read>(
+        &mut self,
+        reader: &mut FastTermReader<R>,
+        indices: &IndexStore,
+    ) -> Result<usize, FastTermError> {
+        let root = self.heap.h();
+        self.heap.push(HeapCellValue::Addr(Addr::HeapCell(root)));
+
+        // heap cells awaiting their values, in stream order.
+        let mut slots = vec![root];
+
+        while let Some(slot) = slots.pop() {
+            let value = match reader.read_byte()? {
+                TAG_VAR => {
+                    let idx = reader.read_varint()? as usize;
+
+                    if idx < reader.vars.len() {
+                        reader.vars[idx].clone()
+                    } else if idx == reader.vars.len() {
+                        reader.vars.push(Addr::HeapCell(slot));
+                        Addr::HeapCell(slot)
+                    } else {
+                        return Err(FastTermError::InvalidVarIndex);
+                    }
+                }
+                TAG_ATTR_VAR => {
+                    let idx = reader.read_varint()? as usize;
+
+                    if idx != reader.vars.len() {
+                        return Err(FastTermError::InvalidVarIndex);
+                    }
+
+                    let h = self.heap.h();
+
+                    self.heap.push(HeapCellValue::Addr(Addr::AttrVar(h)));
+                    self.heap.push(HeapCellValue::Addr(Addr::HeapCell(h + 1)));
+
+                    reader.vars.push(Addr::AttrVar(h));
+                    slots.push(h + 1);
+
+                    Addr::AttrVar(h)
+                }
+                tag @ TAG_ATOM | tag @ TAG_ATOM_REF | tag @ TAG_ATOM_ID => {
+                    let name = reader.read_atom(tag, indices)?;
+                    let spec = fetch_atom_op_spec(name.clone(), None, &indices.op_dir);
+
+                    Addr::Con(Constant::Atom(name, spec))
+                }
+                TAG_CHAR => {
+                    let code = reader.read_varint()?;
+
+                    match std::char::from_u32(code as u32) {
+                        Some(c) if code <= u32::MAX as u64 => Addr::Con(Constant::Char(c)),
+                        _ => return Err(FastTermError::InvalidNumber),
+                    }
+                }
+                TAG_INTEGER => {
+                    let n = reader.read_varint()?;
+                    let n = ((n >> 1) as i64) ^ -((n & 1) as i64);
+
+                    Addr::Con(Constant::Integer(Integer::from(n as isize)))
+                }
+                TAG_BIG_INTEGER => {
+                    Addr::Con(Constant::Integer(reader.read_big_integer()?))
+                }
+                TAG_RATIONAL => {
+                    let numer = reader.read_big_integer()?;
+                    let denom = reader.read_big_integer()?;
+
+                    if denom == 0 {
+                        return Err(FastTermError::InvalidNumber);
+                    }
+
+                    let r = Rational::from(numer) / Rational::from(denom);
+                    Addr::Con(Constant::Rational(r))
+                }
+                TAG_FLOAT => {
+                    let mut buf = [0u8; 8];
+
+                    for b in buf.iter_mut() {
+                        *b = reader.read_byte()?;
+                    }
+
+                    let f = f64::from_bits(u64::from_le_bytes(buf));
+                    Addr::Con(Constant::Float(OrderedFloat(f)))
+                }
+                TAG_STRING => {
+                    let s = reader.read_string()?;
+                    Addr::Con(Constant::String(0, Rc::new(s)))
+                }
+                TAG_EMPTY_LIST => {
+                    Addr::Con(Constant::EmptyList)
+                }
+                TAG_CUT_POINT => {
+                    Addr::Con(Constant::CutPoint(self.b))
+                }
+                TAG_LIST => {
+                    let l = self.heap.h();
+
+                    self.heap.push(HeapCellValue::Addr(Addr::HeapCell(l)));
+                    self.heap.push(HeapCellValue::Addr(Addr::HeapCell(l + 1)));
+
+                    slots.push(l + 1);
+                    slots.push(l);
+
+                    Addr::Lis(l)
+                }
+                TAG_STRUCT => {
+                    let arity = reader.read_varint()? as usize;
+
+                    if arity == 0 || arity > MAX_ARITY {
+                        return Err(FastTermError::InvalidArity);
+                    }
+
+                    let tag = reader.read_byte()?;
+                    let name = reader.read_atom(tag, indices)?;
+
+                    let s = self.heap.h();
+                    let spec = fetch_op_spec(name.clone(), arity, None, &indices.op_dir);
+
+                    self.heap.push(HeapCellValue::NamedStr(arity, name, spec));
+
+                    for idx in 1 .. arity + 1 {
+                        self.heap.push(HeapCellValue::Addr(Addr::HeapCell(s + idx)));
+                    }
+
+                    slots.extend((1 .. arity + 1).rev().map(|idx| s + idx));
+
+                    Addr::Str(s)
+                }
+                _ => {
+                    return Err(FastTermError::InvalidTag);
+                }
+            };
+
+            self.heap[slot] = HeapCellValue::Addr(value);
+        }
+
+        Ok(root)
+    }
+}
+
+/* serialize the atoms of atom_ids, so that terms written by their ids
+ * can be read by a machine that loads them with read_atom_table. */
+pub(super) fn write_atom_table(atom_ids: &AtomIds) -> Vec<u8> {
+    let mut writer = FastTermWriter::new(AttrVarPolicy::StripAttributes, false, None);
+
+    writer.bytes = ATOM_TABLE_MAGIC.to_vec();
+    writer.write_varint(atom_ids.len() as u64);
+
+    for (name, module_name) in atom_ids.iter() {
+        writer.write_str(name.as_str());
+        writer.write_str(module_name.as_str());
+    }
+
+    writer.bytes
+}
+
+/* register the atoms of a table written by write_atom_table under
+ * their saved ids. atoms already registered must have the same ids
+ * they were saved with, as a machine may only extend its numbering.
+ * returns the number of atoms read. */
+pub(super) fn read_atom_table<R: Read>(
+    stream: &mut R,
+    indices: &mut IndexStore,
+) -> Result<usize, FastTermError> {
+    let mut reader = FastTermReader::new(stream);
+
+    if reader.read_byte()? != ATOM_TABLE_MAGIC[0] || reader.read_byte()? != ATOM_TABLE_MAGIC[1] {
+        return Err(FastTermError::BadHeader);
+    }
+
+    let len = reader.read_varint()? as usize;
+
+    for id in 0 .. len {
+        let name = reader.read_atom(TAG_ATOM, indices)?;
+
+        match indices.atom_ids.id(&name) {
+            Some(name_id) if name_id == id => {}
+            None if indices.atom_ids.len() == id => {
+                indices.atom_ids.insert(&name);
+            }
+            _ => return Err(FastTermError::AtomIdConflict),
+        }
+    }
+
+    Ok(len)
+}
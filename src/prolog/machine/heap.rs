@@ -246,6 +246,13 @@ impl<T: RawBlockTraits> HeapTemplate<T> {
 
     pub(crate)
     fn to_list<Iter: Iterator<Item = Addr>>(&mut self, values: Iter) -> usize {
+        self.to_list_with_tail(values, Addr::Con(Constant::EmptyList))
+    }
+
+    // the list of values ending in tail, which is shared rather than
+    // copied.
+    pub(crate)
+    fn to_list_with_tail<Iter: Iterator<Item = Addr>>(&mut self, values: Iter, tail: Addr) -> usize {
         let head_addr = self.h();
 
         for value in values {
@@ -255,7 +262,7 @@ impl<T: RawBlockTraits> HeapTemplate<T> {
             self.push(HeapCellValue::Addr(value));
         }
 
-        self.push(HeapCellValue::Addr(Addr::Con(Constant::EmptyList)));
+        self.push(HeapCellValue::Addr(tail));
 
         head_addr
     }
@@ -0,0 +1,21 @@
+use prolog_parser::ast::*;
+
+use crate::prolog::machine::term_view::TermView;
+
+use indexmap::IndexMap;
+
+/* host callbacks are functions of the embedding program, registered
+ * under a name with Machine::register_host_callback, that Prolog code
+ * invokes with host_call(Request, Reply). the callback registered
+ * under the name of Request receives a view of it, and answers in one
+ * of three ways:
+ *
+ * Ok(Some(term)): term is written to the heap and unified with Reply.
+ * Ok(None): host_call/2 fails.
+ * Err(term): term is written to the heap and thrown as a ball. */
+
+pub type HostCallResult = Result<Option<Term>, Term>;
+
+pub type HostCallback = Box<dyn FnMut(TermView) -> HostCallResult>;
+
+pub(crate) type HostCallbackDir = IndexMap<ClauseName, HostCallback>;
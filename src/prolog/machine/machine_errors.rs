@@ -1,10 +1,12 @@
 use prolog_parser::ast::*;
 
 use crate::prolog::forms::PredicateKey;
+use crate::prolog::machine::block_strings::unterminated_comment;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::machine_state::*;
-use crate::prolog::rug::Integer;
+use crate::prolog::bignum::Integer;
 
+use std::io::ErrorKind;
 use std::rc::Rc;
 
 pub(crate) type MachineStub = Vec<HeapCellValue>;
@@ -127,10 +129,40 @@ impl MachineError {
                     from: ErrorProvenance::Constructed,
                 }
             }
+            ExistenceError::SourceSink(addr) => {
+                let culprit = HeapCellValue::Addr(addr);
+                let stub = functor!("existence_error", 2, [heap_atom!("source_sink"), culprit]);
+
+                MachineError {
+                    stub,
+                    location: None,
+                    from: ErrorProvenance::Constructed,
+                }
+            }
             ExistenceError::Stream(addr) => {
                 let culprit = HeapCellValue::Addr(addr);
                 let stub = functor!("existence_error", 2, [heap_atom!("stream"), culprit]);
 
+                MachineError {
+                    stub,
+                    location: None,
+                    from: ErrorProvenance::Constructed,
+                }
+            }
+            ExistenceError::HostCallback(addr) => {
+                let culprit = HeapCellValue::Addr(addr);
+                let stub = functor!("existence_error", 2, [heap_atom!("host_callback"), culprit]);
+
+                MachineError {
+                    stub,
+                    location: None,
+                    from: ErrorProvenance::Constructed,
+                }
+            }
+            ExistenceError::TermPipe(addr) => {
+                let culprit = HeapCellValue::Addr(addr);
+                let stub = functor!("existence_error", 2, [heap_atom!("term_pipe"), culprit]);
+
                 MachineError {
                     stub,
                     location: None,
@@ -152,9 +184,26 @@ impl MachineError {
                     Addr::Con(Constant::Atom(pred_str, None)),
                 )
             }
+            SessionError::IncludeCycle(filename) => {
+                Self::permission_error(
+                    PermissionError::Include,
+                    "source_sink",
+                    Addr::Con(Constant::Atom(filename, None)),
+                )
+            }
+            SessionError::InIncludedFile(_, err) => Self::session_error(h, *err),
             SessionError::InvalidFileName(filename) => {
                 Self::existence_error(h, ExistenceError::Module(filename))
             }
+            SessionError::InvalidIncludeDecl => {
+                let stub = functor!("syntax_error", 1, [heap_atom!("invalid_include_declaration")]);
+
+                MachineError {
+                    stub,
+                    location: None,
+                    from: ErrorProvenance::Received,
+                }
+            }
             SessionError::ModuleDoesNotContainExport(..) => Self::permission_error(
                 PermissionError::Access,
                 "private_procedure",
@@ -176,6 +225,23 @@ impl MachineError {
         }
     }
 
+    // failures of the underlying stream are reported as I/O errors
+    // on the stream being read, the rest as syntax errors.
+    pub(super)
+    fn fast_term_error(err: FastTermError, stream: Addr) -> Self {
+        if let FastTermError::IO(kind) = err {
+            return Self::io_error(kind, "read", stream);
+        }
+
+        let stub = functor!("syntax_error", 1, [heap_atom!(err.as_str())]);
+
+        MachineError {
+            stub,
+            location: None,
+            from: ErrorProvenance::Received,
+        }
+    }
+
     pub(super)
     fn permission_error(
         err: PermissionError,
@@ -196,7 +262,35 @@ impl MachineError {
         }
     }
 
-    fn arithmetic_error(h: usize, err: ArithmeticError) -> Self {
+    // permission_error(Perm, sandboxed_procedure, Name/Arity), raised
+    // from within sandboxed_call/1,2.
+    pub(super)
+    fn sandbox_error(
+        h: usize,
+        err: PermissionError,
+        name: ClauseName,
+        arity: usize,
+    ) -> Self {
+        let mut stub = functor!(
+            "permission_error",
+            3,
+            [
+                heap_atom!(err.as_str()),
+                heap_atom!("sandboxed_procedure"),
+                heap_str!(4 + h)
+            ]
+        );
+
+        stub.append(&mut Self::functor_stub(name, arity));
+
+        MachineError {
+            stub,
+            location: None,
+            from: ErrorProvenance::Constructed,
+        }
+    }
+
+    pub(super) fn arithmetic_error(h: usize, err: ArithmeticError) -> Self {
         match err {
             ArithmeticError::UninstantiatedVar => Self::instantiation_error(),
             ArithmeticError::NonEvaluableFunctor(name, arity) => {
@@ -226,6 +320,14 @@ impl MachineError {
             return Self::arithmetic_error(h, err);
         }
 
+        if let Some(location) = unterminated_comment(&err) {
+            return MachineError {
+                stub: functor!("syntax_error", 1, [heap_atom!("unterminated_block_comment")]),
+                location: Some(location),
+                from: ErrorProvenance::Constructed,
+            };
+        }
+
         let location = err.line_and_col_num();
         let err = vec![heap_atom!(err.as_str())];
 
@@ -268,6 +370,26 @@ impl MachineError {
         }
     }
 
+    pub(super)
+    fn uninstantiation_error(culprit: Addr) -> Self {
+        let stub = functor!("uninstantiation_error", 1, [HeapCellValue::Addr(culprit)]);
+        MachineError {
+            stub,
+            location: None,
+            from: ErrorProvenance::Received,
+        }
+    }
+
+    pub(super)
+    fn system_error(culprit: &'static str) -> Self {
+        let stub = functor!("system_error", 1, [heap_atom!(culprit)]);
+        MachineError {
+            stub,
+            location: None,
+            from: ErrorProvenance::Constructed,
+        }
+    }
+
     pub(super)
     fn representation_error(flag: RepFlag) -> Self {
         let stub = functor!("representation_error", 1, [heap_atom!(flag.as_str())]);
@@ -278,6 +400,44 @@ impl MachineError {
         }
     }
 
+    // raised when a blocking read or write on stream outlives the
+    // deadline set by set_stream/2.
+    pub(super)
+    fn timeout_error(operation: &'static str, stream: Addr) -> Self {
+        let stub = functor!(
+            "timeout_error",
+            2,
+            [heap_atom!(operation), HeapCellValue::Addr(stream)]
+        );
+
+        MachineError {
+            stub,
+            location: None,
+            from: ErrorProvenance::Constructed,
+        }
+    }
+
+    // io_error(Operation, Stream), or timeout_error(Operation, Stream)
+    // if the stream timed out.
+    pub(super)
+    fn io_error(kind: ErrorKind, operation: &'static str, stream: Addr) -> Self {
+        if kind == ErrorKind::TimedOut {
+            return Self::timeout_error(operation, stream);
+        }
+
+        let stub = functor!(
+            "io_error",
+            2,
+            [heap_atom!(operation), HeapCellValue::Addr(stream)]
+        );
+
+        MachineError {
+            stub,
+            location: None,
+            from: ErrorProvenance::Constructed,
+        }
+    }
+
     fn into_iter(self, offset: usize) -> Box<dyn Iterator<Item = HeapCellValue>> {
         match self.from {
             ErrorProvenance::Constructed => {
@@ -298,9 +458,13 @@ impl MachineError {
 #[derive(Clone, Copy)]
 pub enum PermissionError {
     Access,
+    Call,
     Create,
+    Delete,
+    Include,
     InputStream,
     Modify,
+    Open,
     OutputStream,
 }
 
@@ -308,9 +472,13 @@ impl PermissionError {
     pub fn as_str(self) -> &'static str {
         match self {
             PermissionError::Access => "access",
+            PermissionError::Call => "call",
             PermissionError::Create => "create",
+            PermissionError::Delete => "delete",
+            PermissionError::Include => "include",
             PermissionError::InputStream => "input",
             PermissionError::Modify => "modify",
+            PermissionError::Open => "open",
             PermissionError::OutputStream => "output",
         }
     }
@@ -322,7 +490,7 @@ pub enum ValidType {
     Atom,
     Atomic,
     //    Boolean,
-    //    Byte,
+    Byte,
     Callable,
     Character,
     Compound,
@@ -344,7 +512,7 @@ impl ValidType {
             ValidType::Atom => "atom",
             ValidType::Atomic => "atomic",
             //            ValidType::Boolean => "boolean",
-            //            ValidType::Byte => "byte",
+            ValidType::Byte => "byte",
             ValidType::Callable => "callable",
             ValidType::Character => "character",
             ValidType::Compound => "compound",
@@ -365,16 +533,30 @@ impl ValidType {
 #[derive(Clone, Copy)]
 pub enum DomainError {
     NotLessThanZero,
+    PipeCapacity,
+    PolicyState,
+    Serializable,
+    ServerSocket,
+    SourceSink,
     Stream,
     StreamOrAlias,
+    StreamProperty,
+    Utf8Bytes,
 }
 
 impl DomainError {
     pub fn as_str(self) -> &'static str {
         match self {
             DomainError::NotLessThanZero => "not_less_than_zero",
+            DomainError::PipeCapacity => "pipe_capacity",
+            DomainError::PolicyState => "policy_state",
+            DomainError::Serializable => "serializable",
+            DomainError::ServerSocket => "server_socket",
+            DomainError::SourceSink => "source_sink",
             DomainError::Stream => "stream",
             DomainError::StreamOrAlias => "stream_or_alias",
+            DomainError::StreamProperty => "stream_property",
+            DomainError::Utf8Bytes => "utf8_bytes",
         }
     }
 }
@@ -386,7 +568,7 @@ pub enum RepFlag {
     CharacterCode,
     //    InCharacterCode,
     MaxArity,
-    //    MaxInteger,
+    MaxInteger,
     //    MinInteger
 }
 
@@ -397,7 +579,7 @@ impl RepFlag {
             RepFlag::CharacterCode => "character_code",
             //            RepFlag::InCharacterCode => "in_character_code",
             RepFlag::MaxArity => "max_arity",
-            //            RepFlag::MaxInteger => "max_integer",
+            RepFlag::MaxInteger => "max_integer",
             //            RepFlag::MinInteger => "min_integer"
         }
     }
@@ -423,6 +605,22 @@ impl EvalError {
     }
 }
 
+// from 7.12.2 h) of 13211-1:1995
+#[derive(Clone, Copy)]
+pub enum ResourceError {
+    Stack,
+    TermPipe,
+}
+
+impl ResourceError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResourceError::Stack => "stack",
+            ResourceError::TermPipe => "term_pipe",
+        }
+    }
+}
+
 // used by '$skip_max_list'.
 pub(super) enum CycleSearchResult {
     EmptyList,
@@ -549,6 +747,20 @@ impl MachineState {
         stub
     }
 
+    // error(resource_error(Resource), _), where the context is left
+    // unbound as there's no predicate to blame.
+    pub(super) fn resource_error(&self, err: ResourceError) -> MachineStub {
+        let h = self.heap.h();
+
+        vec![
+            HeapCellValue::NamedStr(2, clause_name!("error"), None),
+            HeapCellValue::Addr(Addr::HeapCell(h + 3)),
+            HeapCellValue::Addr(Addr::HeapCell(h + 2)),
+            HeapCellValue::NamedStr(1, clause_name!("resource_error"), None),
+            heap_atom!(err.as_str()),
+        ]
+    }
+
     pub(super) fn throw_exception(&mut self, err: MachineStub) {
         let h = self.heap.h();
 
@@ -566,14 +778,53 @@ impl MachineState {
 
 pub enum ExistenceError {
     Module(ClauseName),
+    HostCallback(Addr),
     Procedure(ClauseName, usize),
+    SourceSink(Addr),
     Stream(Addr),
+    TermPipe(Addr),
+}
+
+#[derive(Clone, Copy)]
+pub enum FastTermError {
+    AtomIdConflict,
+    BadHeader,
+    InvalidArity,
+    InvalidAtomIndex,
+    InvalidNumber,
+    InvalidTag,
+    InvalidUTF8,
+    InvalidVarIndex,
+    IO(ErrorKind),
+    UnexpectedEOF,
+    UnknownAtomId,
+}
+
+impl FastTermError {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FastTermError::AtomIdConflict => "fast_term_atom_id_conflict",
+            FastTermError::BadHeader => "fast_term_bad_header",
+            FastTermError::InvalidArity => "fast_term_invalid_arity",
+            FastTermError::InvalidAtomIndex => "fast_term_invalid_atom_index",
+            FastTermError::InvalidNumber => "fast_term_invalid_number",
+            FastTermError::InvalidTag => "fast_term_invalid_tag",
+            FastTermError::InvalidUTF8 => "fast_term_invalid_utf8",
+            FastTermError::InvalidVarIndex => "fast_term_invalid_var_index",
+            FastTermError::IO(_) => "fast_term_io_error",
+            FastTermError::UnexpectedEOF => "fast_term_unexpected_eof",
+            FastTermError::UnknownAtomId => "fast_term_unknown_atom_id",
+        }
+    }
 }
 
 pub enum SessionError {
     CannotOverwriteBuiltIn(ClauseName),
     CannotOverwriteImport(ClauseName),
+    IncludeCycle(ClauseName),
+    InIncludedFile(ClauseName, Box<SessionError>),
     InvalidFileName(ClauseName),
+    InvalidIncludeDecl,
     ModuleDoesNotContainExport(ClauseName, PredicateKey),
     ModuleNotFound,
     NamelessEntry,
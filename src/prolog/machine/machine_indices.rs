@@ -7,19 +7,22 @@ use crate::prolog::forms::*;
 use crate::prolog::machine::code_repo::CodeRepo;
 use crate::prolog::machine::Ball;
 use crate::prolog::machine::heap::*;
+use crate::prolog::machine::host_callbacks::HostCallbackDir;
 use crate::prolog::machine::partial_string::*;
 use crate::prolog::machine::raw_block::RawBlockTraits;
 use crate::prolog::machine::streams::Stream;
+use crate::prolog::machine::term_pipes::TermPipeEnd;
 use crate::prolog::instructions::*;
-use crate::prolog::rug::Integer;
+use crate::prolog::bignum::Integer;
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::mem;
 use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::path::PathBuf;
 use std::rc::Rc;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -325,6 +328,7 @@ impl DynamicAssertPlace {
 pub enum DynamicTransactionType {
     Abolish,
     Assert(DynamicAssertPlace),
+    BulkAssert,
     ModuleAbolish,
     ModuleAssert(DynamicAssertPlace),
     ModuleRetract,
@@ -334,6 +338,8 @@ pub enum DynamicTransactionType {
 #[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
 pub enum REPLCodePtr {
     CompileBatch,
+    CreateModule,
+    GarbageCollectClauses,
     UseModule,
     UseQualifiedModule,
     UseModuleFromFile,
@@ -575,6 +581,21 @@ pub type InSituCodeDir = IndexMap<PredicateKey, usize>;
 // key type: module name, predicate indicator.
 pub type DynamicCodeDir = IndexMap<(ClauseName, ClauseName, usize), DynamicPredicateInfo>;
 
+// the code indices resolved by module-qualified calls, keyed by
+// (module, name, arity). a CodeIndex is shared with the code dir it
+// was found in, so an entry follows its predicate through
+// recompilation, but the cache is cleared whenever modules or dynamic
+// predicates might have been replaced or removed.
+pub type ModuleCallCache = RefCell<IndexMap<(ClauseName, ClauseName, usize), CodeIndex>>;
+
+// the operator table as enumerated by current_op/3, taken when an
+// enumeration begins after the table last changed. enumerations
+// already begun keep the snapshot they began with.
+pub type OpDirSnapshot = RefCell<Option<Rc<OssifiedOpDir>>>;
+
+// the tables of IndexStore iterate in insertion order. entries are
+// removed with shift_remove, never swap_remove, so that the order
+// doesn't depend on the history of removals.
 pub type GlobalVarDir = IndexMap<ClauseName, (Ball, Option<usize>)>;
 
 pub(crate) struct ModuleStub {
@@ -594,17 +615,151 @@ impl ModuleStub {
 pub(crate) type ModuleStubDir = IndexMap<ClauseName, ModuleStub>;
 pub(crate) type StreamAliasDir = IndexMap<ClauseName, Stream>;
 
+// the streams opened by open/3 and not yet closed, as enumerated by
+// stream_property/2 along with the current input and output.
+pub(crate) type OpenStreamDir = Vec<Stream>;
+pub(crate) type TermPipeDir = IndexMap<ClauseName, TermPipeEnd>;
+
+// the state of a file being loaded, as reported by prolog_load_context/2.
+#[derive(Clone)]
+pub struct LoadContext {
+    pub(super) source: ClauseName, // the file being loaded.
+    pub(super) directory: PathBuf, // the directory of source.
+    pub(super) included_file: Option<PathBuf>, // the file being read, if included by source.
+    pub(super) module: ClauseName,
+    pub(super) term_line_num: Option<usize>, // the line of the last term read.
+}
+
+// string literals are interned as they are read, so that repeated
+// strings in large data files share a single allocation. strings no
+// longer referenced outside the table are swept once it doubles in size.
+pub struct StringTable {
+    strings: HashSet<Rc<String>>,
+    sweep_len: usize,
+}
+
+impl StringTable {
+    const MIN_SWEEP_LEN: usize = 1024;
+
+    #[inline]
+    pub fn new() -> Self {
+        StringTable {
+            strings: HashSet::new(),
+            sweep_len: Self::MIN_SWEEP_LEN,
+        }
+    }
+
+    pub fn intern(&mut self, s: &Rc<String>) -> Rc<String> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+
+        if self.strings.len() >= self.sweep_len {
+            self.sweep();
+            self.sweep_len = std::cmp::max(2 * self.strings.len(), Self::MIN_SWEEP_LEN);
+        }
+
+        self.strings.insert(s.clone());
+        s.clone()
+    }
+
+    // replace the string constants of term by their interned copies.
+    pub fn intern_term(&mut self, term: &mut Term) {
+        let mut terms = vec![term];
+
+        while let Some(term) = terms.pop() {
+            match term {
+                Term::Constant(_, Constant::String(_, ref mut s)) => {
+                    *s = self.intern(s);
+                }
+                Term::Clause(_, _, ref mut subterms, _) => {
+                    terms.extend(subterms.iter_mut().map(|subterm| subterm.as_mut()));
+                }
+                Term::Cons(_, ref mut head, ref mut tail) => {
+                    terms.push(head.as_mut());
+                    terms.push(tail.as_mut());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[inline]
+    pub fn sweep(&mut self) {
+        self.strings.retain(|s| Rc::strong_count(s) > 1);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+}
+
+// atoms numbered in the order they were registered. the numbering is
+// never revised, so that terms written by fast_write/3 can refer to
+// atoms by their ids, and saved together with the table by
+// save_atom_table/1, they can be read back by another machine that
+// loads the table first.
+pub struct AtomIds {
+    atoms: IndexSet<(ClauseName, ClauseName)>,
+}
+
+impl AtomIds {
+    #[inline]
+    pub fn new() -> Self {
+        AtomIds { atoms: IndexSet::new() }
+    }
+
+    // the id of name, registering it if it has none.
+    #[inline]
+    pub fn insert(&mut self, name: &ClauseName) -> usize {
+        let (id, _) = self.atoms.insert_full((name.clone(), name.owning_module()));
+        id
+    }
+
+    #[inline]
+    pub fn id(&self, name: &ClauseName) -> Option<usize> {
+        self.atoms
+            .get_full(&(name.clone(), name.owning_module()))
+            .map(|(id, _)| id)
+    }
+
+    #[inline]
+    pub fn get(&self, id: usize) -> Option<&ClauseName> {
+        self.atoms.get_index(id).map(|(name, _)| name)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &(ClauseName, ClauseName)> {
+        self.atoms.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+}
+
 pub struct IndexStore {
+    pub(super) atom_ids: AtomIds,
     pub(super) atom_tbl: TabledData<Atom>,
     pub(super) code_dir: CodeDir,
     pub(super) dynamic_code_dir: DynamicCodeDir,
     pub(super) global_variables: GlobalVarDir,
+    pub(super) host_callbacks: HostCallbackDir,
     pub(super) in_situ_code_dir: InSituCodeDir,
     pub(super) in_situ_module_dir: ModuleStubDir,
+    pub(super) load_contexts: Vec<LoadContext>,
+    pub(super) module_call_cache: ModuleCallCache,
     pub(super) module_dir: ModuleDir,
     pub(super) modules: ModuleDir,
     pub(super) op_dir: OpDir,
+    pub(super) op_dir_snapshot: OpDirSnapshot,
+    pub(super) open_streams: OpenStreamDir,
+    pub(super) retired_code: Vec<usize>,
     pub(super) stream_aliases: StreamAliasDir,
+    pub(super) string_tbl: StringTable,
+    pub(super) term_pipes: TermPipeDir,
 }
 
 impl IndexStore {
@@ -646,7 +801,7 @@ impl IndexStore {
 
     #[inline]
     pub fn remove_clause_subsection(&mut self, module: ClauseName, name: ClauseName, arity: usize) {
-        self.dynamic_code_dir.swap_remove(&(module, name, arity));
+        self.dynamic_code_dir.shift_remove(&(module, name, arity));
     }
 
     #[inline]
@@ -669,9 +824,57 @@ impl IndexStore {
         mem::replace(&mut self.in_situ_code_dir, InSituCodeDir::new())
     }
 
+    #[inline]
+    pub(super) fn invalidate_module_calls(&self) {
+        self.module_call_cache.borrow_mut().clear();
+    }
+
+    #[inline]
+    pub(super) fn invalidate_op_dir_snapshot(&self) {
+        self.op_dir_snapshot.borrow_mut().take();
+    }
+
+    // the operator table, for changing it.
+    #[inline]
+    pub(super) fn op_dir_mut(&mut self) -> &mut OpDir {
+        self.invalidate_op_dir_snapshot();
+        &mut self.op_dir
+    }
+
+    // the operators of the table ordered by name and fixity, sorted
+    // only for the first enumeration after the table changes.
+    pub(super) fn op_dir_snapshot(&self) -> Rc<OssifiedOpDir> {
+        if let Some(ref snapshot) = *self.op_dir_snapshot.borrow() {
+            return snapshot.clone();
+        }
+
+        let mut ossified_op_dir = OssifiedOpDir::new();
+
+        ossified_op_dir.extend(self.op_dir.iter().filter_map(
+            |(key, op_dir_val)| {
+                let (name, fixity) = key.clone();
+
+                let prec = op_dir_val.shared_op_desc().prec();
+
+                if prec == 0 {
+                    return None;
+                }
+
+                let assoc = op_dir_val.shared_op_desc().assoc();
+
+                Some((OrderedOpDirKey(name, fixity), (prec, assoc)))
+            },
+        ));
+
+        let snapshot = Rc::new(ossified_op_dir);
+        *self.op_dir_snapshot.borrow_mut() = Some(snapshot.clone());
+
+        snapshot
+    }
+
     #[inline]
     pub fn take_module(&mut self, name: ClauseName) -> Option<Module> {
-        self.modules.swap_remove(&name)
+        self.modules.shift_remove(&name)
     }
 
     #[inline]
@@ -682,16 +885,46 @@ impl IndexStore {
     #[inline]
     pub(super) fn new() -> Self {
         IndexStore {
+            atom_ids: AtomIds::new(),
             atom_tbl: TabledData::new(Rc::new("user".to_string())),
             code_dir: CodeDir::new(),
             module_dir: ModuleDir::new(),
             dynamic_code_dir: DynamicCodeDir::new(),
             global_variables: GlobalVarDir::new(),
+            host_callbacks: HostCallbackDir::new(),
             in_situ_code_dir: InSituCodeDir::new(),
             in_situ_module_dir: ModuleStubDir::new(),
+            load_contexts: vec![],
+            module_call_cache: ModuleCallCache::default(),
             op_dir: default_op_dir(),
+            op_dir_snapshot: OpDirSnapshot::default(),
+            open_streams: OpenStreamDir::new(),
             modules: ModuleDir::new(),
+            retired_code: vec![],
             stream_aliases: StreamAliasDir::new(),
+            string_tbl: StringTable::new(),
+            term_pipes: TermPipeDir::new(),
+        }
+    }
+
+    // records that the predicate code beginning at p has been
+    // superseded by a redefinition. It is kept until no continuation
+    // refers to it (see MachineState::reclaim_retired_code).
+    pub(super) fn retire_code(&mut self, p: usize) {
+        if !self.retired_code.contains(&p) {
+            self.retired_code.push(p);
+        }
+    }
+
+    // retires the code of the predicates defined by a module that is
+    // being replaced.
+    pub(super) fn retire_module_code(&mut self, module: &Module) {
+        for idx in module.code_dir.values() {
+            if idx.module_name() == module.module_decl.name {
+                if let Some(p) = idx.local() {
+                    self.retire_code(p);
+                }
+            }
         }
     }
 
@@ -703,6 +936,12 @@ impl IndexStore {
         mem::swap(&mut self.code_dir, &mut other.code_dir);
         mem::swap(&mut self.op_dir, &mut other.op_dir);
         mem::swap(&mut self.modules, &mut other.modules);
+
+        self.invalidate_module_calls();
+        other.invalidate_module_calls();
+
+        self.invalidate_op_dir_snapshot();
+        other.invalidate_op_dir_snapshot();
     }
 
     #[inline]
@@ -8,17 +8,22 @@ use crate::prolog::machine::heap::*;
 use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::modules::*;
+use crate::prolog::machine::scratch::*;
 use crate::prolog::machine::stack::*;
 use crate::prolog::machine::streams::*;
-use crate::prolog::rug::Integer;
+use crate::prolog::machine::snapshot::predicate_at;
+use crate::prolog::machine::{AUTOLOAD_INDEX, LIBRARIES};
+use crate::prolog::bignum::Integer;
 
 use downcast::Any;
+use ref_thread_local::RefThreadLocal;
 
 use std::cmp::Ordering;
 use std::io::Write;
 use std::mem;
 use std::ops::{Index, IndexMut};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 pub struct Ball {
     pub(super) boundary: usize,
@@ -239,6 +244,40 @@ impl IndexMut<RegType> for MachineState {
 
 pub type Registers = Vec<Addr>;
 
+// the high-water marks of the heap, in cells, the trail, in entries,
+// and the stack, in bytes, since the toplevel last began a query, and
+// the number of choice points created by it. the heap and trail only
+// shrink as the machine backtracks and the stack as it deallocates
+// or cuts, so the marks are taken then.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct QueryStats {
+    pub(super) heap: usize,
+    pub(super) trail: usize,
+    pub(super) stack: usize,
+    pub(super) choice_points: usize,
+    pub(super) base: (usize, usize, usize),
+}
+
+// the instant the machine was created, and the runtime and walltime
+// in milliseconds as last reported by statistics/2, from which it
+// reports the time passed since.
+#[derive(Clone, Copy)]
+pub(super) struct TimeStats {
+    pub(super) start: Instant,
+    pub(super) last_runtime: u64,
+    pub(super) last_walltime: u64,
+}
+
+impl TimeStats {
+    pub(super) fn new() -> Self {
+        TimeStats {
+            start: Instant::now(),
+            last_runtime: 0,
+            last_walltime: 0,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub(super) enum MachineMode {
     Read,
@@ -314,10 +353,19 @@ pub struct MachineState {
     pub(super) ball: Ball,
     pub(super) lifted_heap: Heap,
     pub(super) interms: Vec<Number>, // intermediate numbers.
+    pub(super) scratch: Scratch, // buffers reused by system calls.
     pub(super) last_call: bool,
     pub(crate) heap_locs: HeapVarDict,
     pub(crate) flags: MachineFlags,
-    pub(crate) at_end_of_expansion: bool
+    pub(crate) at_end_of_expansion: bool,
+    pub(super) autoload: bool,
+    pub(super) reproducible: bool,
+    pub(crate) unicode_identifiers: bool,
+    pub(super) query_stats: QueryStats,
+    pub(super) time_stats: TimeStats,
+    // true while the ball thrown by abort/0 is being unwound to the
+    // toplevel, which alone may then reset the machine.
+    pub(super) aborting: bool,
 }
 
 impl MachineState {
@@ -382,8 +430,23 @@ impl MachineState {
         last_call: bool,
     ) -> CallResult {
         let (name, arity) = key;
+        let cache_key = (module_name.clone(), name.clone(), arity);
+
+        let cached_idx = indices.module_call_cache.borrow().get(&cache_key).cloned();
+
+        let idx = cached_idx.or_else(|| {
+            let idx = indices.get_code_index((name.clone(), arity), module_name.clone())?;
 
-        if let Some(ref idx) = indices.get_code_index((name.clone(), arity), module_name.clone()) {
+            // in situ entries only last as long as the compilation of
+            // their module, so they aren't cached.
+            if let IndexPtr::Index(_) = idx.0.borrow().0 {
+                indices.module_call_cache.borrow_mut().insert(cache_key, idx.clone());
+            }
+
+            Some(idx)
+        });
+
+        if let Some(ref idx) = idx {
             match idx.0.borrow().0 {
                 IndexPtr::Index(compiled_tl_index) => {
                     if last_call {
@@ -471,6 +534,8 @@ fn try_in_situ(
 
         machine_st.p = CodePtr::Local(p);
         Ok(())
+    } else if let Some(result) = try_autoload(machine_st, name.clone(), arity, indices, last_call) {
+        result
     } else {
         let stub = MachineError::functor_stub(name.clone(), arity);
         let h = machine_st.heap.h();
@@ -480,9 +545,87 @@ fn try_in_situ(
     }
 }
 
+// if the autoload flag is set and name/arity is exported by a library
+// in the autoload index, call '$autoload'(Library, Module, Goal) in
+// place of the undefined predicate. it loads the library, imports
+// name/arity into Module, the module of the call site, and calls the
+// goal.
+fn try_autoload(
+    machine_st: &mut MachineState,
+    name: ClauseName,
+    arity: usize,
+    indices: &IndexStore,
+    last_call: bool,
+) -> Option<CallResult> {
+    if !machine_st.autoload {
+        return None;
+    }
+
+    let library = AUTOLOAD_INDEX.iter().find_map(|&(export, export_arity, library)| {
+        if export == name.as_str() && export_arity == arity {
+            Some(library)
+        } else {
+            None
+        }
+    })?;
+
+    let p = match indices.code_dir.get(&(clause_name!("$autoload"), 3)) {
+        Some(idx) => match idx.0.borrow().0 {
+            IndexPtr::Index(p) => LocalCodePtr::DirEntry(p),
+            _ => return None,
+        },
+        None => return None,
+    };
+
+    let module = name.owning_module();
+
+    let goal = if arity == 0 {
+        Addr::Con(Constant::Atom(name, None))
+    } else {
+        let h = machine_st.heap.h();
+        machine_st.heap.push(HeapCellValue::NamedStr(arity, name, None));
+
+        for i in 1 .. arity + 1 {
+            let addr = machine_st.store(machine_st.deref(machine_st.registers[i].clone()));
+
+            if let Addr::StackCell(..) = addr {
+                // unbound permanent variables are globalized, as
+                // in put_unsafe_value.
+                let h = machine_st.heap.h();
+
+                machine_st.heap.push(HeapCellValue::Addr(Addr::HeapCell(h)));
+                machine_st.bind(Ref::HeapCell(h), addr);
+            } else {
+                machine_st.heap.push(HeapCellValue::Addr(addr));
+            }
+        }
+
+        Addr::Str(h)
+    };
+
+    machine_st.registers[1] = Addr::Con(Constant::Atom(clause_name!(library), None));
+    machine_st.registers[2] = Addr::Con(Constant::Atom(module, None));
+    machine_st.registers[3] = goal;
+
+    if last_call {
+        machine_st.execute_at_index(3, p);
+    } else {
+        machine_st.call_at_index(3, p);
+    }
+
+    machine_st.p = CodePtr::Local(p);
+    Some(Ok(()))
+}
+
 pub(crate) type CallResult = Result<(), Vec<HeapCellValue>>;
 
 pub(crate) trait CallPolicy: Any {
+    // the state of the policy, from which CallPolicyState::into_policy
+    // rebuilds it.
+    fn state(&self) -> CallPolicyState;
+
+    fn visit_refs(&mut self, visitor: &mut dyn PolicyVisitor);
+
     fn retry_me_else(&mut self, machine_st: &mut MachineState, offset: usize) -> CallResult {
         let b = machine_st.b;
         let n = machine_st.stack.index_or_frame(b).prelude.univ_prelude.num_cells;
@@ -497,6 +640,8 @@ pub(crate) trait CallPolicy: Any {
 
         machine_st.stack.index_or_frame_mut(b).prelude.bp = machine_st.p.local() + offset;
 
+        machine_st.record_high_water();
+
         let old_tr = machine_st.stack.index_or_frame(b).prelude.tr;
         let curr_tr = machine_st.tr;
 
@@ -536,6 +681,8 @@ pub(crate) trait CallPolicy: Any {
 
         machine_st.stack.index_or_frame_mut(b).prelude.bp = machine_st.p.local() + 1;
 
+        machine_st.record_high_water();
+
         let old_tr = machine_st.stack.index_or_frame(b).prelude.tr;
         let curr_tr = machine_st.tr;
 
@@ -570,6 +717,8 @@ pub(crate) trait CallPolicy: Any {
         machine_st.e = machine_st.stack.index_or_frame(b).prelude.e;
         machine_st.cp = machine_st.stack.index_or_frame(b).prelude.cp;
 
+        machine_st.record_high_water();
+
         let old_tr = machine_st.stack.index_or_frame(b).prelude.tr;
         let curr_tr = machine_st.tr;
 
@@ -610,6 +759,8 @@ pub(crate) trait CallPolicy: Any {
         machine_st.e = machine_st.stack.index_or_frame(b).prelude.e;
         machine_st.cp = machine_st.stack.index_or_frame(b).prelude.cp;
 
+        machine_st.record_high_water();
+
         let old_tr = machine_st.stack.index_or_frame(b).prelude.tr;
         let curr_tr = machine_st.tr;
 
@@ -761,18 +912,31 @@ pub(crate) trait CallPolicy: Any {
                 return_from_clause!(machine_st.last_call, machine_st)
             }
             &BuiltInClauseType::Nl => {
-                write!(current_output_stream, "\n").unwrap();
-                current_output_stream.flush().unwrap();
+                if let Err(e) = write!(current_output_stream, "\n")
+                    .and_then(|_| current_output_stream.flush())
+                {
+                    let stub = MachineError::functor_stub(clause_name!("nl"), 0);
+                    let stream = Addr::Stream(current_output_stream.clone());
+                    let err = MachineError::io_error(e.kind(), "write", stream);
+
+                    return Err(machine_st.error_form(err, stub));
+                }
 
                 return_from_clause!(machine_st.last_call, machine_st)
             }
             &BuiltInClauseType::Read => {
+                let double_quotes = current_input_stream
+                    .double_quotes()
+                    .unwrap_or(machine_st.flags.double_quotes);
+
                 match machine_st.read(
-                    &mut parsing_stream(current_input_stream.clone()),
+                    &mut parsing_stream(current_input_stream.clone().term_source()),
                     indices.atom_tbl.clone(),
                     &indices.op_dir,
+                    &mut indices.string_tbl,
+                    double_quotes,
                 ) {
-                    Ok(offset) => {
+                    Ok((offset, _)) => {
                         let addr = machine_st[temp_v!(1)].clone();
                         machine_st.unify(addr, Addr::HeapCell(offset.heap_loc));
                     }
@@ -949,6 +1113,36 @@ pub(crate) trait CallPolicy: Any {
 }
 
 impl CallPolicy for CWILCallPolicy {
+    fn state(&self) -> CallPolicyState {
+        let now = Instant::now();
+
+        CallPolicyState::Limits {
+            count: self.count.clone(),
+            limits: self.limits.clone(),
+            deadlines: self.deadlines.iter()
+                .map(|&(deadline, b)| (deadline.saturating_duration_since(now), b))
+                .collect(),
+            heap_limits: self.heap_limits.clone(),
+            prev: Box::new(self.prev_policy.state()),
+        }
+    }
+
+    fn visit_refs(&mut self, visitor: &mut dyn PolicyVisitor) {
+        for (_, b) in self.limits.iter_mut() {
+            visitor.visit_choice_point(b);
+        }
+
+        for (_, b) in self.deadlines.iter_mut() {
+            visitor.visit_choice_point(b);
+        }
+
+        for (_, b) in self.heap_limits.iter_mut() {
+            visitor.visit_choice_point(b);
+        }
+
+        self.prev_policy.visit_refs(visitor);
+    }
+
     fn context_call(
         &mut self,
         machine_st: &mut MachineState,
@@ -1023,19 +1217,93 @@ impl CallPolicy for CWILCallPolicy {
 
 downcast!(dyn CallPolicy);
 
+/* the states of the call and cut policies as plain data. they can be
+ * enumerated without downcasting the policies, written to saved
+ * states as terms (see snapshot.rs), and made into policies again.
+ * the heap addresses and choice points held by a policy are visited
+ * in place by a PolicyVisitor, so that a collector moving the heap
+ * or the stack can update them. */
+pub(crate) enum CallPolicyState {
+    Default,
+    Limits {
+        count: Integer,
+        limits: Vec<(Integer, usize)>,
+        deadlines: Vec<(Duration, usize)>, // the time left before each deadline.
+        heap_limits: Vec<(usize, usize)>,
+        prev: Box<CallPolicyState>,
+    },
+}
+
+impl CallPolicyState {
+    pub(crate) fn into_policy(self) -> Box<dyn CallPolicy> {
+        match self {
+            CallPolicyState::Default => Box::new(DefaultCallPolicy {}),
+            CallPolicyState::Limits { count, limits, deadlines, heap_limits, prev } => {
+                let now = Instant::now();
+
+                Box::new(CWILCallPolicy {
+                    prev_policy: prev.into_policy(),
+                    count,
+                    limits,
+                    deadlines: deadlines.into_iter().map(|(left, b)| (now + left, b)).collect(),
+                    heap_limits,
+                })
+            }
+        }
+    }
+}
+
+pub(crate) enum CutPolicyState {
+    Default,
+    Cleaners {
+        cont_pts: Vec<(Addr, usize, usize)>,
+        r_c_w_h: usize,
+        r_c_wo_h: usize,
+    },
+}
+
+impl CutPolicyState {
+    pub(crate) fn into_policy(self) -> Box<dyn CutPolicy> {
+        match self {
+            CutPolicyState::Default => Box::new(DefaultCutPolicy {}),
+            CutPolicyState::Cleaners { cont_pts, r_c_w_h, r_c_wo_h } => {
+                Box::new(SCCCutPolicy { cont_pts, r_c_w_h, r_c_wo_h })
+            }
+        }
+    }
+}
+
+pub(crate) trait PolicyVisitor {
+    fn visit_addr(&mut self, addr: &mut Addr);
+    fn visit_choice_point(&mut self, b: &mut usize);
+}
+
 pub(crate) struct DefaultCallPolicy {}
 
-impl CallPolicy for DefaultCallPolicy {}
+impl CallPolicy for DefaultCallPolicy {
+    fn state(&self) -> CallPolicyState {
+        CallPolicyState::Default
+    }
+
+    fn visit_refs(&mut self, _: &mut dyn PolicyVisitor) {}
+}
 
 pub(crate) struct CWILCallPolicy {
     pub(crate) prev_policy: Box<dyn CallPolicy>,
     count: Integer,
+    // inference limits, deadlines and heap sizes, each paired with the
+    // choice point of the call_with_*_limit(s) that installed it. an
+    // exceeded limit throws a token naming that choice point, so that
+    // only the call that installed the limit handles it.
     limits: Vec<(Integer, usize)>,
-    inference_limit_exceeded: bool,
+    deadlines: Vec<(Instant, usize)>,
+    heap_limits: Vec<(usize, usize)>,
 }
 
 impl CWILCallPolicy {
     pub(crate) fn new_in_place(policy: &mut Box<dyn CallPolicy>) {
+        log_event!(debug, "call policy switched to CWILCallPolicy");
+
         let mut prev_policy: Box<dyn CallPolicy> = Box::new(DefaultCallPolicy {});
         mem::swap(&mut prev_policy, policy);
 
@@ -1043,27 +1311,62 @@ impl CWILCallPolicy {
             prev_policy,
             count: Integer::from(0),
             limits: vec![],
-            inference_limit_exceeded: false,
+            deadlines: vec![],
+            heap_limits: vec![],
         };
         *policy = Box::new(new_policy);
     }
 
     fn increment(&mut self, machine_st: &MachineState) -> CallResult {
-        if self.inference_limit_exceeded || machine_st.ball.stub.h() > 0 {
+        if machine_st.ball.stub.h() > 0 {
             return Ok(());
         }
 
-        if let Some(&(ref limit, bp)) = self.limits.last() {
-            if self.count == *limit {
-                self.inference_limit_exceeded = true;
-                return Err(functor!(
-                    "inference_limit_exceeded",
-                    1,
-                    [HeapCellValue::Addr(Addr::Con(Constant::Usize(bp)))]
-                ));
-            } else {
-                self.count += 1;
-            }
+        let count = &self.count;
+
+        let inference_bp = self.limits.iter()
+            .filter(|&(limit, _)| count == limit)
+            .map(|&(_, bp)| bp)
+            .min();
+
+        let time_bp = if self.deadlines.is_empty() {
+            None
+        } else {
+            let now = Instant::now();
+
+            self.deadlines.iter()
+                .filter(|&&(deadline, _)| now >= deadline)
+                .map(|&(_, bp)| bp)
+                .min()
+        };
+
+        let heap_bp = self.heap_limits.iter()
+            .filter(|&&(limit, _)| machine_st.heap.h() > limit)
+            .map(|&(_, bp)| bp)
+            .min();
+
+        // when several limits are exceeded at once, the outermost of
+        // them (the one with the oldest choice point) claims the
+        // exception, since unwinding to it abandons the inner calls.
+        let token = vec![
+            inference_bp.map(|bp| ("$inference_limit_exceeded", bp)),
+            time_bp.map(|bp| ("$time_limit_exceeded", bp)),
+            heap_bp.map(|bp| ("$memory_limit_exceeded", bp)),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by_key(|&(_, bp)| bp);
+
+        if let Some((name, bp)) = token {
+            return Err(functor!(
+                name,
+                1,
+                [HeapCellValue::Addr(Addr::Con(Constant::Usize(bp)))]
+            ));
+        }
+
+        if !self.limits.is_empty() {
+            self.count += 1;
         }
 
         Ok(())
@@ -1071,27 +1374,39 @@ impl CWILCallPolicy {
 
     pub(crate) fn add_limit(&mut self, mut limit: Integer, b: usize) -> &Integer {
         limit += &self.count;
-
-        match self.limits.last().cloned() {
-            Some((ref inner_limit, _)) if *inner_limit <= limit => {}
-            _ => self.limits.push((limit, b)),
-        };
+        self.limits.push((limit, b));
 
         &self.count
     }
 
     pub(crate) fn remove_limit(&mut self, b: usize) -> &Integer {
-        if let Some((_, bp)) = self.limits.last().cloned() {
-            if bp == b {
-                self.limits.pop();
-            }
+        self.limits.retain(|&(_, bp)| bp != b);
+        &self.count
+    }
+
+    // a deadline past the latest representable instant is never
+    // reached, and so isn't kept.
+    pub(crate) fn add_deadline(&mut self, timeout: Duration, b: usize) {
+        if let Some(deadline) = Instant::now().checked_add(timeout) {
+            self.deadlines.push((deadline, b));
         }
+    }
 
-        &self.count
+    pub(crate) fn remove_deadline(&mut self, b: usize) {
+        self.deadlines.retain(|&(_, bp)| bp != b);
+    }
+
+    // cells is the number of heap cells the goal may grow the heap by.
+    pub(crate) fn add_heap_limit(&mut self, cells: usize, h: usize, b: usize) {
+        self.heap_limits.push((h.saturating_add(cells), b));
+    }
+
+    pub(crate) fn remove_heap_limit(&mut self, b: usize) {
+        self.heap_limits.retain(|&(_, bp)| bp != b);
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.limits.is_empty()
+        self.limits.is_empty() && self.deadlines.is_empty() && self.heap_limits.is_empty()
     }
 
     pub(crate) fn into_inner(&mut self) -> Box<dyn CallPolicy> {
@@ -1101,9 +1416,207 @@ impl CWILCallPolicy {
     }
 }
 
+// a predicate indicator whitelisted by sandboxed_call/2. a missing
+// module matches the predicate in any module.
+pub(crate) type SandboxedPredicate = (Option<ClauseName>, ClauseName, usize);
+
+pub(crate) struct SandboxPolicy {
+    // the whitelists of the active sandboxed_call/2 goals, innermost
+    // last, each paired with the heap cell of the fresh variable that
+    // keys it. the goal can't get hold of that variable, so it can't
+    // lift its own sandbox.
+    sandboxes: Vec<(usize, Vec<SandboxedPredicate>)>,
+}
+
+impl SandboxPolicy {
+    pub(crate) fn new() -> Self {
+        SandboxPolicy { sandboxes: vec![] }
+    }
+
+    pub(crate) fn push(&mut self, key: usize, whitelist: Vec<SandboxedPredicate>) {
+        self.sandboxes.push((key, whitelist));
+    }
+
+    // removes the sandbox keyed by key, along with any left installed
+    // inside it.
+    pub(crate) fn remove(&mut self, key: usize) {
+        if let Some(idx) = self.sandboxes.iter().rposition(|&(h, _)| h == key) {
+            self.sandboxes.truncate(idx);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.sandboxes.clear();
+    }
+
+    // a predicate may be modified only if every enclosing sandbox
+    // whitelists it, so that nesting sandboxes can't widen them.
+    fn may_modify(&self, module: &ClauseName, name: &ClauseName, arity: usize) -> bool {
+        self.sandboxes.iter().all(|(_, whitelist)| {
+            whitelist.iter().any(|(m, n, a)| {
+                n == name && *a == arity && m.as_ref().map(|m| m == module).unwrap_or(true)
+            })
+        })
+    }
+
+    fn modified_predicate(
+        machine_st: &MachineState,
+        name: RegType,
+        arity: RegType,
+        module: Option<RegType>,
+    ) -> Option<(ClauseName, ClauseName, usize)> {
+        let name = match machine_st.store(machine_st.deref(machine_st[name].clone())) {
+            Addr::Con(Constant::Atom(name, _)) => name,
+            _ => return None,
+        };
+
+        let arity = match machine_st.store(machine_st.deref(machine_st[arity].clone())) {
+            Addr::Con(Constant::Integer(n)) => n.to_usize()?,
+            Addr::Con(Constant::Usize(n)) => n,
+            _ => return None,
+        };
+
+        let module = match module {
+            Some(module) => match machine_st.store(machine_st.deref(machine_st[module].clone())) {
+                Addr::Con(Constant::Atom(module, _)) => module,
+                _ => return None,
+            },
+            None => clause_name!("user"),
+        };
+
+        Some((module, name, arity))
+    }
+
+    // true if the code at p was loaded by the user rather than taken
+    // from the builtins, the toplevel or a library.
+    fn is_user_code(p: LocalCodePtr, indices: &IndexStore) -> bool {
+        match p {
+            LocalCodePtr::DirEntry(p) => match predicate_at(p, indices) {
+                Some((module_name, ..)) => {
+                    module_name.as_str() != "$toplevel"
+                        && !LIBRARIES.borrow().contains_key(module_name.as_str())
+                }
+                None => false,
+            },
+            LocalCodePtr::InSituDirEntry(_) => false,
+            _ => true,
+        }
+    }
+
+    // throws a permission error if ct may not run inside the active
+    // sandboxes. ct is one of the system clauses behind file and stream
+    // IO, loading code, halting the process, setting flags, operators
+    // and global variables and modifying the database, or a system
+    // clause called by the user's own code, which the libraries alone
+    // are trusted to call.
+    pub(crate) fn check(
+        &self,
+        machine_st: &MachineState,
+        indices: &IndexStore,
+        ct: &SystemClauseType,
+        arity: usize,
+    ) -> CallResult {
+        if self.sandboxes.is_empty() {
+            return Ok(());
+        }
+
+        if Self::is_user_code(machine_st.p.local(), indices) {
+            let h = machine_st.heap.h();
+            let err = MachineError::sandbox_error(h, PermissionError::Call, ct.name(), arity);
+            let stub = MachineError::functor_stub(ct.name(), arity);
+
+            return Err(machine_st.error_form(err, stub));
+        }
+
+        let (pred, name, arity, module) = match *ct {
+            SystemClauseType::AssertDynamicPredicateToFront =>
+                ("asserta", temp_v!(3), temp_v!(4), None),
+            SystemClauseType::AssertDynamicPredicateToBack =>
+                ("assertz", temp_v!(3), temp_v!(4), None),
+            SystemClauseType::ModuleAssertDynamicPredicateToFront =>
+                ("asserta", temp_v!(3), temp_v!(4), Some(temp_v!(5))),
+            SystemClauseType::ModuleAssertDynamicPredicateToBack =>
+                ("assertz", temp_v!(3), temp_v!(4), Some(temp_v!(5))),
+            SystemClauseType::RetractClause =>
+                ("retract", temp_v!(1), temp_v!(2), None),
+            SystemClauseType::ModuleRetractClause =>
+                ("retract", temp_v!(1), temp_v!(2), Some(temp_v!(5))),
+            SystemClauseType::AbolishClause =>
+                ("abolish", temp_v!(1), temp_v!(2), None),
+            SystemClauseType::AbolishModuleClause =>
+                ("abolish", temp_v!(1), temp_v!(2), Some(temp_v!(3))),
+            SystemClauseType::OpenStream =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "open", 4)),
+            SystemClauseType::CloseStream =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "close", 2)),
+            SystemClauseType::DeleteFile =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "delete_file", 1)),
+            SystemClauseType::SocketClientOpen =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "socket_client_open", 3)),
+            SystemClauseType::SocketServerAccept =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "socket_server_accept", 4)),
+            SystemClauseType::SocketServerOpen =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "socket_server_open", 2)),
+            SystemClauseType::Halt =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "halt", 0)),
+            SystemClauseType::OpDeclaration =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "op", 3)),
+            SystemClauseType::SetAutoload
+          | SystemClauseType::SetBlockStrings
+          | SystemClauseType::SetDoubleQuotes
+          | SystemClauseType::SetQuasiQuotations
+          | SystemClauseType::SetReproducible
+          | SystemClauseType::SetToplevelPrompt
+          | SystemClauseType::SetUnicodeIdentifiers =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "set_prolog_flag", 2)),
+            SystemClauseType::StoreGlobalVar =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "bb_put", 2)),
+            SystemClauseType::REPL(REPLCodePtr::GarbageCollectClauses) =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "garbage_collect_clauses", 0)),
+            SystemClauseType::REPL(REPLCodePtr::CompileBatch) =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "consult", 1)),
+            SystemClauseType::REPL(REPLCodePtr::UseModuleFromFile) =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "use_module", 1)),
+            SystemClauseType::REPL(REPLCodePtr::UseQualifiedModuleFromFile) =>
+                return Err(Self::sandbox_error(machine_st, PermissionError::Call, "use_module", 2)),
+            _ => return Ok(()),
+        };
+
+        match Self::modified_predicate(machine_st, name, arity, module) {
+            Some((module, name, arity)) if !self.may_modify(&module, &name, arity) => {
+                let h = machine_st.heap.h();
+                let err = MachineError::sandbox_error(h, PermissionError::Modify, name, arity);
+                let stub = MachineError::functor_stub(clause_name!(pred), 1);
+
+                Err(machine_st.error_form(err, stub))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn sandbox_error(
+        machine_st: &MachineState,
+        perm: PermissionError,
+        name: &'static str,
+        arity: usize,
+    ) -> MachineStub {
+        let h = machine_st.heap.h();
+        let err = MachineError::sandbox_error(h, perm, clause_name!(name), arity);
+        let stub = MachineError::functor_stub(clause_name!(name), arity);
+
+        machine_st.error_form(err, stub)
+    }
+}
+
 pub(crate) trait CutPolicy: Any {
     // returns true iff we fail or cut redirected the MachineState's p itself
     fn cut(&mut self, machine_st: &mut MachineState, r: RegType) -> bool;
+
+    // the state of the policy, from which CutPolicyState::into_policy
+    // rebuilds it.
+    fn state(&self) -> CutPolicyState;
+
+    fn visit_refs(&mut self, visitor: &mut dyn PolicyVisitor);
 }
 
 downcast!(dyn CutPolicy);
@@ -1116,6 +1629,7 @@ fn cut_body(machine_st: &mut MachineState, addr: Addr) -> bool {
             if b > b0 {
                 machine_st.b = b0;
                 machine_st.tidy_trail();
+                machine_st.trim_stack();
             }
         }
         _ => {
@@ -1139,6 +1653,12 @@ impl CutPolicy for DefaultCutPolicy {
         let addr = machine_st[r].clone();
         cut_body(machine_st, addr)
     }
+
+    fn state(&self) -> CutPolicyState {
+        CutPolicyState::Default
+    }
+
+    fn visit_refs(&mut self, _: &mut dyn PolicyVisitor) {}
 }
 
 pub(crate) struct SCCCutPolicy {
@@ -1212,4 +1732,20 @@ impl CutPolicy for SCCCutPolicy {
 
         self.run_cleaners(machine_st)
     }
+
+    fn state(&self) -> CutPolicyState {
+        CutPolicyState::Cleaners {
+            cont_pts: self.cont_pts.clone(),
+            r_c_w_h: self.r_c_w_h,
+            r_c_wo_h: self.r_c_wo_h,
+        }
+    }
+
+    fn visit_refs(&mut self, visitor: &mut dyn PolicyVisitor) {
+        for (addr, b, prev_block) in self.cont_pts.iter_mut() {
+            visitor.visit_addr(addr);
+            visitor.visit_choice_point(b);
+            visitor.visit_choice_point(prev_block);
+        }
+    }
 }
@@ -4,7 +4,6 @@ use prolog_parser::tabled_rc::*;
 use crate::prolog::arithmetic::*;
 use crate::prolog::clause_types::*;
 use crate::prolog::forms::*;
-use crate::prolog::heap_iter::*;
 use crate::prolog::instructions::*;
 use crate::prolog::machine::INTERRUPT;
 use crate::prolog::machine::attributed_variables::*;
@@ -14,15 +13,18 @@ use crate::prolog::machine::heap::*;
 use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::machine_state::*;
+use crate::prolog::machine::scratch::*;
 use crate::prolog::machine::stack::*;
 use crate::prolog::machine::streams::*;
 use crate::prolog::ordered_float::*;
-use crate::prolog::rug::{Integer, Rational};
+use crate::prolog::bignum::{Integer, Rational};
 
 use indexmap::{IndexMap, IndexSet};
 
 use std::cmp::{max, min, Ordering};
+use std::collections::hash_map::DefaultHasher;
 use std::f64;
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
 use std::mem;
 use std::rc::Rc;
@@ -72,10 +74,17 @@ impl MachineState {
             ball: Ball::new(),
             lifted_heap: Heap::new(),
             interms: vec![Number::default(); 256],
+            scratch: Scratch::new(),
             last_call: false,
             heap_locs: HeapVarDict::new(),
             flags: MachineFlags::default(),
-            at_end_of_expansion: false
+            at_end_of_expansion: false,
+            autoload: false,
+            reproducible: false,
+            unicode_identifiers: false,
+            query_stats: QueryStats::default(),
+            time_stats: TimeStats::new(),
+            aborting: false,
         }
     }
 
@@ -102,10 +111,17 @@ impl MachineState {
             ball: Ball::new(),
             lifted_heap: Heap::new(),
             interms: vec![Number::default(); 0],
+            scratch: Scratch::new(),
             last_call: false,
             heap_locs: HeapVarDict::new(),
             flags: MachineFlags::default(),
-            at_end_of_expansion: false
+            at_end_of_expansion: false,
+            autoload: false,
+            reproducible: false,
+            unicode_identifiers: false,
+            query_stats: QueryStats::default(),
+            time_stats: TimeStats::new(),
+            aborting: false,
         }
     }
 
@@ -402,6 +418,11 @@ impl MachineState {
                             self.fail = true;
                         }
                     }
+                    (Addr::Stream(ref s1), Addr::Stream(ref s2)) => {
+                        if s1 != s2 {
+                            self.fail = true;
+                        }
+                    }
                     (Addr::Str(a1), Addr::Str(a2)) => {
                         let r1 = &self.heap[a1];
                         let r2 = &self.heap[a2];
@@ -427,6 +448,8 @@ impl MachineState {
         }
     }
 
+    // pairs of subterms awaiting unification are kept on pdl, not the
+    // native stack, so the depth of a term is bounded by the heap.
     pub(super)
     fn unify(&mut self, a1: Addr, a2: Addr) {
         let mut pdl = vec![a1, a2];
@@ -572,6 +595,11 @@ impl MachineState {
                             self.fail = true;
                         }
                     }
+                    (Addr::Stream(ref s1), Addr::Stream(ref s2)) => {
+                        if s1 != s2 {
+                            self.fail = true;
+                        }
+                    }
                     (Addr::Str(a1), Addr::Str(a2)) => {
                         let r1 = &self.heap[a1];
                         let r2 = &self.heap[a2];
@@ -815,9 +843,7 @@ impl MachineState {
                         "rem" => interms.push(Number::Integer(self.remainder(a1, a2)?)),
                         "atan2" => interms.push(Number::Float(OrderedFloat(self.atan2(a1, a2)?))),
                         "gcd" => interms.push(Number::Integer(self.gcd(a1, a2)?)),
-                        _ => {
-                            return Err(self.error_form(MachineError::instantiation_error(), caller))
-                        }
+                        _ => return Err(self.non_evaluable_error(name, 2, caller)),
                     }
                 }
                 HeapCellValue::NamedStr(1, name, _) => {
@@ -843,11 +869,12 @@ impl MachineState {
                         "floor" => interms.push(Number::Integer(self.floor(a1))),
                         "\\" => interms.push(Number::Integer(self.bitwise_complement(a1)?)),
                         "sign" => interms.push(Number::Integer(self.sign(a1))),
-                        _ => {
-                            return Err(self.error_form(MachineError::instantiation_error(), caller))
-                        }
+                        _ => return Err(self.non_evaluable_error(name, 1, caller)),
                     }
                 }
+                HeapCellValue::NamedStr(arity, name, _) => {
+                    return Err(self.non_evaluable_error(name, arity, caller));
+                }
                 HeapCellValue::Addr(Addr::Con(Constant::Integer(n))) => {
                     interms.push(Number::Integer(n))
                 }
@@ -862,6 +889,9 @@ impl MachineState {
                 {
                     interms.push(Number::Float(OrderedFloat(f64::consts::PI)))
                 }
+                HeapCellValue::Addr(Addr::Con(Constant::Atom(name, _))) => {
+                    return Err(self.non_evaluable_error(name, 0, caller));
+                }
                 _ => {
                     return Err(self.error_form(MachineError::instantiation_error(), caller));
                 }
@@ -871,6 +901,13 @@ impl MachineState {
         Ok(interms.pop().unwrap())
     }
 
+    fn non_evaluable_error(&self, name: ClauseName, arity: usize, caller: MachineStub) -> MachineStub {
+        let err = ArithmeticError::NonEvaluableFunctor(Constant::Atom(name, None), arity);
+        let err = MachineError::arithmetic_error(self.heap.h(), err);
+
+        self.error_form(err, caller)
+    }
+
     fn rdiv(&self, r1: Rational, r2: Rational) -> Result<Rational, MachineStub> {
         let stub = MachineError::functor_stub(clause_name!("(rdiv)"), 2);
 
@@ -1909,6 +1946,11 @@ impl MachineState {
         let addr = self[temp_v!(1)].clone();
         self.ball.boundary = self.heap.h();
 
+        self.aborting = match self.store(self.deref(addr.clone())) {
+            Addr::Con(Constant::Atom(ref name, _)) => name.as_str() == "$aborted",
+            _ => false,
+        };
+
         copy_term(
             CopyBallTerm::new(&mut self.stack, &mut self.heap, &mut self.ball.stub),
             addr,
@@ -1986,31 +2028,59 @@ impl MachineState {
     }
 
     pub(super) fn unwind_stack(&mut self) {
+        log_event!(debug, "exception thrown, unwinding to choice point {}", self.block);
+
         self.b = self.block;
         self.fail = true;
     }
 
+    // a term is cyclic if one of its compound subterms contains
+    // itself. subterms that are merely shared, as in f(T, T), are
+    // searched once and don't make the term cyclic.
     pub(crate) fn is_cyclic_term(&self, addr: Addr) -> bool {
-        let mut seen = IndexSet::new();
-        let mut fail = false;
-        let mut iter = self.pre_order_iter(addr);
+        enum Visit {
+            Enter(Addr),
+            Exit(usize),
+        }
 
-        loop {
-            if let Some(addr) = iter.stack().last() {
-                if !seen.contains(addr) {
-                    seen.insert(addr.clone());
-                } else {
-                    fail = true;
-                    break;
+        let mut on_path = IndexSet::new();
+        let mut searched = IndexSet::new();
+        let mut visits = vec![Visit::Enter(addr)];
+
+        while let Some(visit) = visits.pop() {
+            let addr = match visit {
+                Visit::Enter(addr) => addr,
+                Visit::Exit(h) => {
+                    on_path.remove(&h);
+                    searched.insert(h);
+                    continue;
                 }
+            };
+
+            let (h, subterms) = match self.store(self.deref(addr)) {
+                Addr::Str(s) => match &self.heap[s] {
+                    &HeapCellValue::NamedStr(arity, ..) => (s, s + 1 .. s + arity + 1),
+                    _ => continue,
+                },
+                Addr::Lis(l) => (l, l .. l + 2),
+                Addr::PStrLocation(h, _) => (h, h + 1 .. h + 2),
+                _ => continue,
+            };
+
+            if on_path.contains(&h) {
+                return true;
             }
 
-            if iter.next().is_none() {
-                break;
+            if searched.contains(&h) {
+                continue;
             }
+
+            on_path.insert(h);
+            visits.push(Visit::Exit(h));
+            visits.extend(subterms.rev().map(|h| Visit::Enter(Addr::HeapCell(h))));
         }
 
-        fail
+        false
     }
 
     // arg(+N, +Term, ?Arg)
@@ -2825,7 +2895,16 @@ impl MachineState {
         caller: MachineStub,
     ) -> Result<Vec<Addr>, MachineStub> {
         let a1 = self.store(self.deref(self[r].clone()));
+        self.try_from_list_addr(a1, caller)
+    }
 
+    // the elements of the list at a1, which is dereferenced.
+    pub(super)
+    fn try_from_list_addr(
+        &self,
+        a1: Addr,
+        caller: MachineStub,
+    ) -> Result<Vec<Addr>, MachineStub> {
         match a1.clone() {
             Addr::Lis(l) => {
                 self.try_from_inner_list(vec![], l, caller, a1)
@@ -2876,7 +2955,13 @@ impl MachineState {
                         Addr::Con(Constant::String(n, ref s))
                             if !self.flags.double_quotes.is_atom() =>
                         {
-                            result.push(Addr::Con(Constant::String(n, s.clone())));
+                            // a string tail holds the remaining elements.
+                            if s.len() > n {
+                                result.extend(s[n ..].chars().map(|c| {
+                                    Addr::Con(Constant::Char(c))
+                                }));
+                            }
+
                             break;
                         }
                         Addr::PStrLocation(h, n) => {
@@ -3084,6 +3169,208 @@ impl MachineState {
         false
     }
 
+    // hashes the acyclic term at addr modulo variable renaming, so
+    // that terms found variant by structural_eq_test hash alike.
+    // variables, attributed or not, are hashed by their order of
+    // first occurrence, and strings as the lists they stand for.
+    pub(super) fn variant_hash(&self, addr: Addr) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let mut vars = IndexMap::new();
+
+        let strings_are_lists = !self.flags.double_quotes.is_atom();
+
+        for value in self.pre_order_iter(addr) {
+            match value {
+                HeapCellValue::NamedStr(arity, name, _) => {
+                    0u8.hash(&mut hasher);
+                    name.as_str().hash(&mut hasher);
+                    arity.hash(&mut hasher);
+                }
+                HeapCellValue::Addr(Addr::Lis(_))
+              | HeapCellValue::Addr(Addr::PStrLocation(..)) => {
+                    1u8.hash(&mut hasher);
+                }
+                HeapCellValue::Addr(Addr::Con(Constant::String(..))) if strings_are_lists => {
+                    1u8.hash(&mut hasher);
+                }
+                HeapCellValue::Addr(var @ Addr::AttrVar(_))
+              | HeapCellValue::Addr(var @ Addr::HeapCell(_))
+              | HeapCellValue::Addr(var @ Addr::StackCell(..)) => {
+                    let var_count = vars.len();
+
+                    2u8.hash(&mut hasher);
+                    vars.entry(var).or_insert(var_count).hash(&mut hasher);
+                }
+                HeapCellValue::Addr(Addr::Con(c)) => {
+                    match c {
+                        Constant::Atom(name, _) => {
+                            3u8.hash(&mut hasher);
+                            name.as_str().hash(&mut hasher);
+                        }
+                        Constant::Char(c) => {
+                            3u8.hash(&mut hasher);
+                            c.to_string().as_str().hash(&mut hasher);
+                        }
+                        Constant::CharCode(c) => {
+                            4u8.hash(&mut hasher);
+                            Integer::from(c).hash(&mut hasher);
+                        }
+                        Constant::Integer(n) => {
+                            4u8.hash(&mut hasher);
+                            n.hash(&mut hasher);
+                        }
+                        Constant::Rational(r) => {
+                            5u8.hash(&mut hasher);
+                            r.hash(&mut hasher);
+                        }
+                        Constant::Float(f) => {
+                            6u8.hash(&mut hasher);
+                            f.hash(&mut hasher);
+                        }
+                        Constant::String(n, s) => {
+                            7u8.hash(&mut hasher);
+                            s[n ..].hash(&mut hasher);
+                        }
+                        Constant::EmptyList => {
+                            8u8.hash(&mut hasher);
+                        }
+                        Constant::CutPoint(b) | Constant::Usize(b) => {
+                            9u8.hash(&mut hasher);
+                            b.hash(&mut hasher);
+                        }
+                    }
+                }
+                HeapCellValue::Addr(addr) => {
+                    10u8.hash(&mut hasher);
+                    addr.hash(&mut hasher);
+                }
+                HeapCellValue::PartialString(_) => {
+                    1u8.hash(&mut hasher);
+                }
+            }
+        }
+
+        hasher.finish()
+    }
+
+    // unifies a1 and a2, passes the outcome to f and undoes every
+    // binding made by the unification before returning f's result.
+    // attribute hooks are not run on the bindings.
+    pub(super)
+    fn unify_with_rollback<R, F>(&mut self, a1: Addr, a2: Addr, f: F) -> R
+        where F: FnOnce(&MachineState) -> R
+    {
+        let (old_b, old_hb, old_tr, old_h) = (self.b, self.hb, self.tr, self.heap.h());
+        let (old_p, old_cp) = (self.p.clone(), self.cp);
+
+        let old_attr_var_cp = self.attr_var_init.cp;
+        let old_instigating_p = self.attr_var_init.instigating_p;
+        let old_queue_len = self.attr_var_init.attr_var_queue.len();
+        let old_bindings_len = self.attr_var_init.bindings.len();
+
+        // trail every binding, including those of variables newer
+        // than the last choice point.
+        self.hb = old_h;
+        self.b = usize::max_value();
+
+        self.unify(a1, a2);
+
+        let result = f(self);
+
+        let tr = self.tr;
+
+        self.unwind_trail(old_tr, tr);
+        self.trail.truncate(old_tr);
+        self.tr = old_tr;
+
+        self.b = old_b;
+        self.hb = old_hb;
+        self.heap.truncate(old_h);
+
+        self.p = old_p;
+        self.cp = old_cp;
+
+        self.attr_var_init.cp = old_attr_var_cp;
+        self.attr_var_init.instigating_p = old_instigating_p;
+        self.attr_var_init.backtrack(old_queue_len, old_bindings_len);
+
+        self.fail = false;
+        result
+    }
+
+    // true iff a1 and a2 are identical or do not unify, i.e. if their
+    // unification can be decided without binding variables.
+    pub(super)
+    fn decidable_test(&mut self, a1: Addr, a2: Addr) -> bool {
+        let old_tr = self.tr;
+
+        self.unify_with_rollback(a1, a2, |machine_st| {
+            machine_st.fail || machine_st.tr == old_tr
+        })
+    }
+
+    // the bindings made by unifying a1 and a2, as Var-Value pairs in
+    // the order they were made, or None if a1 and a2 do not unify.
+    // the bindings are undone before returning.
+    pub(super)
+    fn unifier(&mut self, a1: Addr, a2: Addr) -> Option<Vec<(Addr, Addr)>> {
+        let old_tr = self.tr;
+
+        self.unify_with_rollback(a1, a2, |machine_st| {
+            if machine_st.fail {
+                return None;
+            }
+
+            let bindings = machine_st.trail[old_tr .. machine_st.tr].iter()
+                .filter_map(|r| match r {
+                    TrailRef::Ref(r) => {
+                        let value = match r {
+                            Ref::StackCell(fr, sc) =>
+                                machine_st.stack.index_or_frame(*fr)[*sc].clone(),
+                            Ref::HeapCell(h) | Ref::AttrVar(h) =>
+                                machine_st.heap[*h].as_addr(*h),
+                        };
+
+                        Some((r.as_addr(), value))
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            Some(bindings)
+        })
+    }
+
+    // returns true on failure. general subsumes specific iff they
+    // unify without binding any variable of specific.
+    pub(super)
+    fn subsumes_test(&mut self, general: Addr, specific: Addr) -> bool {
+        let specific_vars: IndexSet<Addr> = self.acyclic_pre_order_iter(specific.clone())
+            .filter_map(|value| match value {
+                HeapCellValue::Addr(addr) if addr.is_ref() => Some(addr),
+                _ => None,
+            })
+            .collect();
+
+        self.unify_with_rollback(general, specific, |machine_st| {
+            if machine_st.fail {
+                return true;
+            }
+
+            let mut seen = IndexSet::new();
+
+            for var in specific_vars {
+                let addr = machine_st.store(machine_st.deref(var));
+
+                if !addr.is_ref() || !seen.insert(addr) {
+                    return true;
+                }
+            }
+
+            false
+        })
+    }
+
     // returns true on failure.
     pub(super)
     fn ground_test(&self) -> bool {
@@ -3109,8 +3396,118 @@ impl MachineState {
         self.p = CodePtr::BuiltInClause(ct, self.p.local());
     }
 
+    // frees the frames above both the current environment and the
+    // latest choice point, left by calls that exited before a cut
+    // removed the choice points keeping them. without this, a
+    // recursion cutting away a choice point on each step grows the
+    // stack until the clause making it deallocates.
+    pub(super)
+    fn trim_stack(&mut self) {
+        if self.e == 0 {
+            return;
+        }
+
+        let e_frame = self.stack.index_and_frame(self.e);
+        let e_top = self.e + AndFrame::size_of(e_frame.prelude.univ_prelude.num_cells);
+
+        let b_top = if self.b > 0 {
+            let b_frame = self.stack.index_or_frame(self.b);
+            self.b + OrFrame::size_of(b_frame.prelude.univ_prelude.num_cells)
+        } else {
+            0
+        };
+
+        let top = max(e_top, b_top);
+
+        if top < self.stack.size() {
+            self.record_high_water();
+            self.stack.truncate(top);
+        }
+    }
+
+    // the number of environments reachable from the current one and
+    // of choice points reachable from the latest.
+    pub(super)
+    fn frame_counts(&self) -> (usize, usize) {
+        let mut environments = 0;
+        let mut e = self.e;
+
+        while e > 0 {
+            environments += 1;
+            e = self.stack.index_and_frame(e).prelude.e;
+        }
+
+        let mut choice_points = 0;
+        let mut b = self.b;
+
+        while b > 0 {
+            choice_points += 1;
+            b = self.stack.index_or_frame(b).prelude.b;
+        }
+
+        (environments, choice_points)
+    }
+
+    pub(super)
+    fn record_high_water(&mut self) {
+        let stats = &mut self.query_stats;
+
+        stats.heap = max(stats.heap, self.heap.h());
+        stats.trail = max(stats.trail, self.trail.len());
+        stats.stack = max(stats.stack, self.stack.size());
+    }
+
+    // begins the statistics of a query at the current sizes of the
+    // heap, trail and stack.
+    pub(super)
+    fn reset_query_stats(&mut self) {
+        let base = (self.heap.h(), self.trail.len(), self.stack.size());
+
+        self.query_stats = QueryStats {
+            heap: base.0,
+            trail: base.1,
+            stack: base.2,
+            choice_points: 0,
+            base,
+        };
+    }
+
+    // the growth of the heap, trail and stack above their sizes when
+    // the query began, at their highest, and the choice points it
+    // created.
+    pub(super)
+    fn query_stats(&mut self) -> [usize; 4] {
+        self.record_high_water();
+
+        let stats = &self.query_stats;
+
+        [
+            stats.heap.saturating_sub(stats.base.0),
+            stats.trail.saturating_sub(stats.base.1),
+            stats.stack.saturating_sub(stats.base.2),
+            stats.choice_points,
+        ]
+    }
+
+    // throws resource_error(stack) if no further frames can be
+    // allocated, returning true if it did.
+    fn stack_exhausted(&mut self) -> bool {
+        if self.stack.size() < STACK_LIMIT {
+            return false;
+        }
+
+        let err = self.resource_error(ResourceError::Stack);
+        self.throw_exception(err);
+
+        true
+    }
+
     pub(super)
     fn allocate(&mut self, num_cells: usize) {
+        if self.stack_exhausted() {
+            return;
+        }
+
         let e = self.stack.allocate_and_frame(num_cells);
         let and_frame = self.stack.index_and_frame_mut(e);
 
@@ -3130,6 +3527,7 @@ impl MachineState {
         self.e  = frame.prelude.e;
 
         if e > self.b {
+            self.record_high_water();
             self.stack.truncate(e);
         }
 
@@ -3142,6 +3540,7 @@ impl MachineState {
         code_repo: &CodeRepo,
         call_policy: &mut Box<dyn CallPolicy>,
         cut_policy: &mut Box<dyn CutPolicy>,
+        sandbox_policy: &mut SandboxPolicy,
         current_input_stream: &mut Stream,
         current_output_stream: &mut Stream,
         ct: &ClauseType,
@@ -3196,18 +3595,22 @@ impl MachineState {
                     call_policy.context_call(self, name.clone(), arity, idx.clone(), indices)
                 )
             }
-            &ClauseType::System(ref ct) => try_or_fail!(
-                self,
-                self.system_call(
-                    ct,
-                    code_repo,
-                    indices,
-                    call_policy,
-                    cut_policy,
-                    current_input_stream,
-                    current_output_stream,
+            &ClauseType::System(ref ct) => {
+                try_or_fail!(self, sandbox_policy.check(self, indices, ct, arity));
+                try_or_fail!(
+                    self,
+                    self.system_call(
+                        ct,
+                        code_repo,
+                        indices,
+                        call_policy,
+                        cut_policy,
+                        sandbox_policy,
+                        current_input_stream,
+                        current_output_stream,
+                    )
                 )
-            ),
+            }
         };
 
         self.last_call = false;
@@ -3219,6 +3622,7 @@ impl MachineState {
         code_repo: &CodeRepo,
         call_policy: &mut Box<dyn CallPolicy>,
         cut_policy: &mut Box<dyn CutPolicy>,
+        sandbox_policy: &mut SandboxPolicy,
         current_input_stream: &mut Stream,
         current_output_stream: &mut Stream,
         instr: &ControlInstruction,
@@ -3233,6 +3637,7 @@ impl MachineState {
                     code_repo,
                     call_policy,
                     cut_policy,
+                    sandbox_policy,
                     current_input_stream,
                     current_output_stream,
                     ct,
@@ -3263,8 +3668,15 @@ impl MachineState {
     ) {
         match instr {
             &IndexedChoiceInstruction::Try(offset) => {
+                if self.stack_exhausted() {
+                    return;
+                }
+
                 let n = self.num_of_args;
                 let b = self.stack.allocate_or_frame(n);
+
+                self.query_stats.choice_points += 1;
+
                 let or_frame = self.stack.index_or_frame_mut(b);
 
                 or_frame.prelude.univ_prelude.num_cells = n;
@@ -3302,8 +3714,15 @@ impl MachineState {
     ) {
         match instr {
             &ChoiceInstruction::TryMeElse(offset) => {
+                if self.stack_exhausted() {
+                    return;
+                }
+
                 let n = self.num_of_args;
                 let b = self.stack.allocate_or_frame(n);
+
+                self.query_stats.choice_points += 1;
+
                 let or_frame = self.stack.index_or_frame_mut(b);
 
                 or_frame.prelude.univ_prelude.num_cells = n;
@@ -3358,6 +3777,7 @@ impl MachineState {
                     self.tidy_trail();
 
                     if b > self.e {
+                        self.record_high_water();
                         self.stack.truncate(b);
                     }
                 }
@@ -9,21 +9,33 @@ use crate::prolog::machine::heap::*;
 use crate::prolog::read::*;
 
 mod attributed_variables;
+mod block_strings;
+mod chars_reader;
+mod code_gc;
 pub(super) mod code_repo;
 pub mod code_walker;
 pub mod compile;
 mod copier;
+mod dcg;
 mod dynamic_database;
+pub mod embedded;
+mod fast_term;
 pub mod heap;
+pub mod host_callbacks;
 pub mod machine_errors;
 pub mod machine_indices;
 pub(super) mod machine_state;
 pub mod modules;
 mod partial_string;
+pub mod preemption;
 mod raw_block;
+mod scratch;
+mod snapshot;
 mod stack;
-pub(crate) mod streams;
+pub mod streams;
 pub(super) mod term_expansion;
+pub mod term_pipes;
+pub mod term_view;
 pub mod toplevel;
 
 #[macro_use]
@@ -33,15 +45,20 @@ mod system_calls;
 use crate::prolog::machine::attributed_variables::*;
 use crate::prolog::machine::code_repo::*;
 use crate::prolog::machine::compile::*;
+use crate::prolog::machine::host_callbacks::*;
 use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::machine_state::*;
 use crate::prolog::machine::modules::*;
+use crate::prolog::machine::preemption::*;
 use crate::prolog::machine::streams::*;
+use crate::prolog::machine::term_pipes::*;
+use crate::prolog::machine::term_view::*;
 use crate::prolog::machine::toplevel::*;
 
 use indexmap::IndexMap;
 
+use std::cell::Cell;
 use std::collections::VecDeque;
 use std::fs::File;
 use std::mem;
@@ -53,6 +70,8 @@ use std::sync::atomic::AtomicBool;
 pub struct MachinePolicies {
     call_policy: Box<dyn CallPolicy>,
     cut_policy: Box<dyn CutPolicy>,
+    sandbox_policy: SandboxPolicy,
+    preemption_hook: Option<PreemptionHook>,
 }
 
 lazy_static! {
@@ -65,10 +84,27 @@ impl MachinePolicies {
         MachinePolicies {
             call_policy: Box::new(DefaultCallPolicy {}),
             cut_policy: Box::new(DefaultCutPolicy {}),
+            sandbox_policy: SandboxPolicy::new(),
+            preemption_hook: None,
         }
     }
 }
 
+// how far a query started by Machine::start_goal has run.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum QueryPoll {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+// a query driven by the embedder, a few instructions at a time.
+struct HostQuery {
+    end_ptr: CodePtr,
+    goal: Addr,
+    poll: QueryPoll,
+}
+
 pub struct Machine {
     pub(super) machine_st: MachineState,
     pub(super) inner_heap: Heap,
@@ -78,6 +114,7 @@ pub struct Machine {
     pub(super) toplevel_idx: usize,
     pub(super) current_input_stream: Stream,
     pub(super) current_output_stream: Stream,
+    host_query: Option<HostQuery>,
 }
 
 impl Index<LocalCodePtr> for CodeRepo {
@@ -108,7 +145,7 @@ impl SubModuleUser for IndexStore {
     }
 
     fn op_dir(&mut self) -> &mut OpDir {
-        &mut self.op_dir
+        self.op_dir_mut()
     }
 
     fn get_code_index(&self, key: PredicateKey, module_name: ClauseName) -> Option<CodeIndex> {
@@ -148,7 +185,7 @@ impl SubModuleUser for IndexStore {
     }
 
     fn insert_dir_entry(&mut self, name: ClauseName, arity: usize, idx: CodeIndex) {
-        if let Some(ref code_idx) = self.code_dir.get(&(name.clone(), arity)) {
+        if let Some(code_idx) = self.code_dir.get(&(name.clone(), arity)).cloned() {
             if !code_idx.is_undefined() {
                 match (name.as_str(), arity) {
                     ("term_expansion", 2) => {
@@ -161,8 +198,17 @@ impl SubModuleUser for IndexStore {
                 }
             }
 
+            let old_p = code_idx.local();
             let (p, module_name) = idx.0.borrow().clone();
+
             set_code_index!(code_idx, p, module_name);
+
+            if let Some(old_p) = old_p {
+                if code_idx.local() != Some(old_p) {
+                    self.retire_code(old_p);
+                }
+            }
+
             return;
         }
 
@@ -316,6 +362,7 @@ impl Machine {
     pub fn reset(&mut self) {
         self.current_input_stream = readline::input_stream();
         self.policies.cut_policy = Box::new(DefaultCutPolicy {});
+        self.policies.sandbox_policy.clear();
         self.machine_st.reset();
     }
 
@@ -324,7 +371,7 @@ impl Machine {
 	self.machine_st.reset();
 
 	self.code_repo.cached_query = code;
-	self.run_query();
+	self.run_cached_query();
 
         let result = self.machine_st.fail;
 	self.absorb_snapshot(old_machine_st);
@@ -332,28 +379,71 @@ impl Machine {
         !result
     }
 
+    // print message through print_message/2, so that it is rendered,
+    // and possibly intercepted, by Prolog code. messages printed
+    // before builtins is loaded are dropped.
+    pub(super) fn print_message(&mut self, kind: &'static str, message: Term) {
+        let code_idx = match self.indices.modules.get(&clause_name!("builtins")) {
+            Some(builtins) => match builtins.code_dir.get(&(clause_name!("print_message"), 2)) {
+                Some(code_idx) => code_idx.clone(),
+                None => return,
+            },
+            None => return,
+        };
+
+        let old_machine_st = self.sink_to_snapshot();
+        self.machine_st.reset();
+
+        let heap_loc = write_term_to_heap(&message, &mut self.machine_st).heap_loc;
+
+        self.machine_st[temp_v!(1)] = Addr::Con(Constant::Atom(clause_name!(kind), None));
+        self.machine_st[temp_v!(2)] = Addr::HeapCell(heap_loc);
+
+        let print_message = ClauseType::Named(clause_name!("print_message"), 2, code_idx);
+
+        self.code_repo.cached_query = vec![call_clause!(print_message, 2, 0, true)];
+        self.run_cached_query();
+
+        self.absorb_snapshot(old_machine_st);
+    }
+
+    // runs the toplevel, '$repl'/1 of toplevel.pl, as a query begun like
+    // those of Machine::run_query, with the command line arguments
+    // as the files to consult.
     pub fn run_top_level(&mut self) {
 	use std::env;
 
-	let mut filename_atoms = vec![];
+	let empty_list = Term::Constant(Cell::default(), Constant::EmptyList);
 
 	// the first of these is the path to the scryer-prolog executable, so skip
 	// it.
-	for filename in env::args().skip(1) {
+	let filenames = env::args().skip(1).collect::<Vec<_>>();
+	let filename_list = filenames.into_iter().rev().fold(empty_list, |tail, filename| {
 	    let atom = atom!(filename, self.indices.atom_tbl);
-	    filename_atoms.push(Addr::Con(atom));
-	}
+	    let head = Term::Constant(Cell::default(), atom);
 
-	let list_addr =
-	    Addr::HeapCell(self.machine_st.heap.to_list(filename_atoms.into_iter()));
+	    Term::Cons(Cell::default(), Box::new(head), Box::new(tail))
+	});
 
-	self.machine_st[temp_v!(1)] = list_addr;
-        self.machine_st.p = CodePtr::Local(LocalCodePtr::DirEntry(self.toplevel_idx));
+	let repl = Term::Clause(
+	    Cell::default(),
+	    clause_name!("$repl"),
+	    vec![Box::new(filename_list)],
+	    None,
+	);
 
-        self.run_query();
+	let (end_ptr, _) = self.cache_goal(&repl);
+	self.resume_query(end_ptr, None);
     }
 
     pub fn new(current_input_stream: Stream, current_output_stream: Stream) -> Self
+    {
+        let mut wam = Machine::with_streams(current_input_stream, current_output_stream);
+        wam.compile_scryerrc();
+        wam
+    }
+
+    fn with_streams(current_input_stream: Stream, current_output_stream: Stream) -> Self
     {
         let mut wam = Machine {
             machine_st: MachineState::new(),
@@ -364,6 +454,7 @@ impl Machine {
             toplevel_idx: 0,
             current_input_stream,
             current_output_stream,
+            host_query: None,
         };
 
         let atom_tbl = wam.indices.atom_tbl.clone();
@@ -427,8 +518,6 @@ impl Machine {
             panic!("Loading '$toplevel' module failed");
         }
 
-        wam.compile_scryerrc();
-
         wam
     }
 
@@ -479,10 +568,16 @@ impl Machine {
     pub(crate) fn add_batched_code_dir(&mut self, code_dir: CodeDir) {
         // error detection has finished, so update the master index of keys.
         for (key, idx) in code_dir {
-            if let Some(ref master_idx) = self.indices.code_dir.get(&key) {
+            if let Some(master_idx) = self.indices.code_dir.get(&key).cloned() {
                 // ensure we don't double borrow if master_idx == idx.
                 // we don't need to modify anything in that case.
                 if !Rc::ptr_eq(&master_idx.0, &idx.0) {
+                    if let Some(old_p) = master_idx.local() {
+                        if idx.local() != Some(old_p) {
+                            self.indices.retire_code(old_p);
+                        }
+                    }
+
                     set_code_index!(master_idx, idx.0.borrow().0, idx.module_name());
                 }
 
@@ -495,15 +590,23 @@ impl Machine {
 
     #[inline]
     pub(crate) fn add_batched_ops(&mut self, op_dir: OpDir) {
-        self.indices.op_dir.extend(op_dir.into_iter());
+        self.indices.op_dir_mut().extend(op_dir.into_iter());
     }
 
     pub(crate) fn add_in_situ_module_dir(&mut self, module_dir: ModuleDir) {
+        let mut retired = vec![];
+
         for (module_name, module_skeleton) in module_dir {
             match self.indices.modules.get_mut(&module_name) {
                 Some(ref mut module) => {
                     for (key, idx) in module_skeleton.code_dir {
                         if let Some(existing_idx) = module.code_dir.get(&key) {
+                            if let Some(old_p) = existing_idx.local() {
+                                if idx.local() != Some(old_p) {
+                                    retired.push(old_p);
+                                }
+                            }
+
                             set_code_index!(existing_idx, idx.0.borrow().0, module_name.clone());
                         } else {
                             module.code_dir.insert(key, idx);
@@ -515,13 +618,127 @@ impl Machine {
                 }
             }
         }
+
+        for p in retired {
+            self.indices.retire_code(p);
+        }
     }
 
     #[inline]
     pub fn add_module(&mut self, module: Module) {
-        self.indices
+        let old_module = self.indices
             .modules
             .insert(module.module_decl.name.clone(), module);
+
+        if let Some(old_module) = old_module {
+            self.indices.retire_module_code(&old_module);
+        }
+
+        self.indices.invalidate_module_calls();
+    }
+
+    // makes one end of a term pipe available to the machine's
+    // pipe_send/2 and pipe_receive/2 under the given alias. machines
+    // running on other threads communicate by sharing a pipe
+    // created by term_pipes::term_pipe.
+    pub fn add_term_pipe_end(&mut self, alias: &str, end: TermPipeEnd) {
+        let alias = clause_name!(alias.to_string(), self.indices.atom_tbl);
+        self.indices.term_pipes.insert(alias, end);
+    }
+
+    // a view of the term in argument register r, valid until the
+    // machine next runs.
+    pub fn register_view(&self, r: usize) -> TermView {
+        TermView::new(&self.machine_st, &self.machine_st.registers[r])
+    }
+
+    // installs callback as the host callback named name, replacing
+    // any callback previously registered under it.
+    pub fn register_host_callback<F>(&mut self, name: &str, callback: F)
+        where F: FnMut(TermView) -> HostCallResult + 'static
+    {
+        let name = clause_name!(name.to_string(), self.indices.atom_tbl);
+        self.indices.host_callbacks.insert(name, Box::new(callback));
+    }
+
+    // begins, without running it, a query calling goal, discarding
+    // any query begun before it. the query is run by step.
+    pub fn start_goal(&mut self, goal: &Term) {
+        let (end_ptr, result) = self.cache_goal(goal);
+
+        self.host_query = Some(HostQuery {
+            end_ptr,
+            goal: Addr::HeapCell(result.heap_loc),
+            poll: QueryPoll::Pending,
+        });
+    }
+
+    // writes goal to the heap of a reset machine and caches a query
+    // calling it, returning where the query ends.
+    fn cache_goal(&mut self, goal: &Term) -> (CodePtr, TermWriteResult) {
+        self.machine_st.reset();
+
+        let result = write_term_to_heap(goal, &mut self.machine_st);
+
+        self.machine_st[temp_v!(1)] = Addr::HeapCell(result.heap_loc);
+        self.code_repo.cached_query = vec![call_clause!(ClauseType::CallN, 1, 0, true)];
+
+        (self.start_query(), result)
+    }
+
+    // runs at most n instructions of the query begun by start_goal,
+    // so that hosts can interleave it with their own event loops.
+    pub fn step(&mut self, n: usize) -> QueryPoll {
+        let end_ptr = match self.host_query {
+            Some(HostQuery { ref end_ptr, poll: QueryPoll::Pending, .. }) => end_ptr.clone(),
+            _ => return self.poll_answer(),
+        };
+
+        if self.resume_query(end_ptr, Some(n)) {
+            let poll = if self.machine_st.fail {
+                QueryPoll::Failed
+            } else {
+                QueryPoll::Succeeded
+            };
+
+            if let Some(ref mut host_query) = self.host_query {
+                host_query.poll = poll;
+            }
+        }
+
+        self.poll_answer()
+    }
+
+    // the state of the query begun by start_goal. a query is Failed
+    // if none was begun.
+    pub fn poll_answer(&self) -> QueryPoll {
+        match self.host_query {
+            Some(ref host_query) => host_query.poll,
+            None => QueryPoll::Failed,
+        }
+    }
+
+    // installs hook, replacing any installed before it, to be called
+    // once every interval instructions.
+    pub fn set_preemption_hook<F>(&mut self, interval: usize, hook: F)
+        where F: FnMut(u64) -> PreemptionAction + 'static
+    {
+        self.policies.preemption_hook = Some(PreemptionHook::new(interval, Box::new(hook)));
+    }
+
+    pub fn clear_preemption_hook(&mut self) {
+        self.policies.preemption_hook = None;
+    }
+
+    // a view of the goal of a Succeeded query, with its variables
+    // bound to their answer.
+    pub fn answer_view(&self) -> Option<TermView> {
+        match self.host_query {
+            Some(HostQuery { ref goal, poll: QueryPoll::Succeeded, .. }) => {
+                Some(TermView::new(&self.machine_st, goal))
+            }
+            _ => None,
+        }
     }
 
     fn throw_session_error(&mut self, err: SessionError, key: PredicateKey) {
@@ -700,18 +917,95 @@ impl Machine {
 	}
     }
 
+    fn print_quoted(&self, output: PrinterOutputter, addr: Addr) -> PrinterOutputter {
+        let mut printer = HCPrinter::new(&self.machine_st, &self.indices.op_dir, output);
+        printer.quoted = true;
+
+        printer.print(addr)
+    }
+
+    // the text of a module declaring the module named in the first
+    // argument register with the export list of the second, each
+    // exported predicate dynamic, and using the libraries listed in
+    // the third.
+    fn created_module_src(&self, stub: MachineStub) -> Result<String, MachineStub> {
+        let exports = self.machine_st.try_from_list(temp_v!(2), stub.clone())?;
+        let imports = self.machine_st.try_from_list(temp_v!(3), stub)?;
+
+        let mut output = PrinterOutputter::new();
+
+        output.append(":- module(");
+        output = self.print_quoted(output, self.machine_st[temp_v!(1)].clone());
+        output.append(", ");
+        output = self.print_quoted(output, self.machine_st[temp_v!(2)].clone());
+        output.append("). ");
+
+        for import in imports {
+            output.append(":- use_module(library(");
+            output = self.print_quoted(output, import);
+            output.append(")). ");
+        }
+
+        for export in exports {
+            if let Addr::Str(s) = self.machine_st.store(self.machine_st.deref(export.clone())) {
+                match &self.machine_st.heap[s] {
+                    HeapCellValue::NamedStr(arity, ref name, _)
+                        if *arity == 2 && name.as_str() == "/" => {
+                            output.append(":- dynamic(");
+                            output = self.print_quoted(output, export);
+                            output.append("). ");
+                        }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(output.result())
+    }
+
+    fn create_module(&mut self) {
+        let stub = MachineError::functor_stub(clause_name!("create_module"), 3);
+
+        let src = match self.created_module_src(stub) {
+            Ok(src) => src,
+            Err(err) => return self.machine_st.throw_exception(err),
+        };
+
+	// the term expander will overwrite the cached query, so save it here.
+	let cached_query = mem::replace(&mut self.code_repo.cached_query, vec![]);
+
+        let result = load_module_from_source(self, src).and_then(|name| {
+            let module = self.indices.take_module(name).unwrap();
+
+            self.indices.use_module(&mut self.code_repo, self.machine_st.flags, &module)?;
+            Ok(self.indices.insert_module(module))
+        });
+
+	self.code_repo.cached_query = cached_query;
+
+	if let Err(e) = result {
+	    self.throw_session_error(e, (clause_name!("create_module"), 3));
+	}
+    }
+
     fn handle_toplevel_command(&mut self, code_ptr: REPLCodePtr, p: LocalCodePtr) {
         match code_ptr {
             REPLCodePtr::CompileBatch => {
                 let user_src = ListingSource::User;
 
                 let src = readline::input_stream();
-                readline::set_prompt(false);
+                readline::set_prompt(readline::Prompt::None);
 
                 if let EvalSession::Error(e) = compile_user_module(self, src, false, user_src) {
                     self.throw_session_error(e, (clause_name!("repl"), 0));
                 }
             }
+            REPLCodePtr::CreateModule => {
+                self.create_module();
+            }
+            REPLCodePtr::GarbageCollectClauses => {
+                self.machine_st.reclaim_retired_code(&mut self.indices, &mut self.code_repo.code);
+            }
 	    REPLCodePtr::UseModule =>
 		self.use_module(ModuleSource::Library),
 	    REPLCodePtr::UseModuleFromFile =>
@@ -722,6 +1016,7 @@ impl Machine {
 		self.use_qualified_module(ModuleSource::File)
         }
 
+        self.indices.invalidate_module_calls();
         self.machine_st.p = CodePtr::Local(p);
     }
 
@@ -775,18 +1070,31 @@ impl Machine {
         self.machine_st.lifted_heap = snapshot.lifted_heap.take();
     }
 
-    pub(super) fn run_query(&mut self) {
+    pub(super) fn run_cached_query(&mut self) {
+        let end_ptr = self.start_query();
+        self.resume_query(end_ptr, None);
+    }
+
+    // returns the code pointer at which the cached query ends.
+    fn start_query(&mut self) -> CodePtr {
 	self.machine_st.cp = LocalCodePtr::TopLevel(0, self.code_repo.size_of_cached_query());
-        let end_ptr = CodePtr::Local(self.machine_st.cp);
+        CodePtr::Local(self.machine_st.cp)
+    }
 
+    // runs the cached query up to end_ptr, or until budget
+    // instructions have run. returns true iff the query finished.
+    fn resume_query(&mut self, end_ptr: CodePtr, mut budget: Option<usize>) -> bool {
         while self.machine_st.p < end_ptr {
-            self.machine_st.query_stepper(
+            if self.machine_st.query_stepper(
                 &mut self.indices,
                 &mut self.policies,
                 &mut self.code_repo,
                 &mut self.current_input_stream,
                 &mut self.current_output_stream,
-            );
+                &mut budget,
+            ) {
+                return false;
+            }
 
             match self.machine_st.p {
                 CodePtr::Local(LocalCodePtr::TopLevel(_, p)) if p > 0 => {
@@ -811,6 +1119,8 @@ impl Machine {
                     break
             };
         }
+
+        true
     }
 }
 
@@ -837,6 +1147,7 @@ impl MachineState {
                 code_repo,
                 &mut policies.call_policy,
                 &mut policies.cut_policy,
+                &mut policies.sandbox_policy,
                 current_input_stream,
                 current_output_stream,
                 control_instr,
@@ -970,6 +1281,7 @@ impl MachineState {
         self.verify_attr_interrupt(p);
     }
 
+    // returns true iff it stopped for having run budget instructions.
     fn query_stepper(
         &mut self,
         indices: &mut IndexStore,
@@ -977,8 +1289,39 @@ impl MachineState {
         code_repo: &mut CodeRepo,
         current_input_stream: &mut Stream,
         current_output_stream: &mut Stream,
-    ) {
+        budget: &mut Option<usize>,
+    ) -> bool {
         loop {
+            if let Some(ref mut n) = budget {
+                if *n == 0 {
+                    return true;
+                }
+
+                *n -= 1;
+            }
+
+            if let Some(ref mut hook) = policies.preemption_hook {
+                match hook.tick() {
+                    PreemptionAction::Continue => {
+                    }
+                    PreemptionAction::Yield => {
+                        if budget.is_some() {
+                            return true;
+                        }
+                    }
+                    PreemptionAction::Cancel => {
+                        self.throw_exception(vec![heap_atom!("$aborted")]);
+                        self.backtrack();
+
+                        if !self.check_machine_index(code_repo) {
+                            break;
+                        }
+
+                        continue;
+                    }
+                }
+            }
+
             self.execute_instr(
                 indices,
                 policies,
@@ -1023,5 +1366,7 @@ impl MachineState {
                 }
             }
         }
+
+        false
     }
 }
@@ -0,0 +1,52 @@
+/* a preemption hook is called by the dispatch loop once every
+ * interval instructions, with the number of instructions run since
+ * it was installed. it answers with what the machine should do next:
+ *
+ * Continue: go on running.
+ * Yield: return from Machine::step as though its budget had run out.
+ *        outside of step, it's the same as Continue.
+ * Cancel: abort the running query, as abort/0 does.
+ *
+ * with no hook installed, the dispatch loop pays one branch per
+ * instruction. */
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PreemptionAction {
+    Continue,
+    Yield,
+    Cancel,
+}
+
+pub struct PreemptionHook {
+    interval: usize,
+    countdown: usize,
+    instructions: u64,
+    hook: Box<dyn FnMut(u64) -> PreemptionAction>,
+}
+
+impl PreemptionHook {
+    pub(super) fn new(interval: usize, hook: Box<dyn FnMut(u64) -> PreemptionAction>) -> Self {
+        let interval = std::cmp::max(interval, 1);
+
+        PreemptionHook {
+            interval,
+            countdown: interval,
+            instructions: 0,
+            hook,
+        }
+    }
+
+    #[inline]
+    pub(super) fn tick(&mut self) -> PreemptionAction {
+        self.countdown -= 1;
+
+        if self.countdown > 0 {
+            return PreemptionAction::Continue;
+        }
+
+        self.countdown = self.interval;
+        self.instructions += self.interval as u64;
+
+        (self.hook)(self.instructions)
+    }
+}
@@ -0,0 +1,73 @@
+use crate::prolog::machine::copier::CopyTrail;
+use crate::prolog::machine::machine_indices::*;
+
+use indexmap::IndexSet;
+
+use std::mem;
+
+// buffers holding more than this many entries are freed rather than
+// kept for reuse.
+const MAX_RETAINED_LEN: usize = 1 << 16;
+
+/* Buffers for the short-lived collections built by system calls,
+ * kept by the machine state between calls so that tight query loops
+ * don't allocate and free them on every call. A call takes a buffer,
+ * which is empty, and gives it back once done with it, at which point
+ * it's cleared, keeping its capacity. A buffer not given back, say on
+ * an error, is simply replaced by a fresh one on the next take. */
+pub(crate) struct Scratch {
+    addrs: Vec<Addr>,
+    vars: IndexSet<Addr>,
+    copy_trail: CopyTrail,
+}
+
+impl Scratch {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Scratch {
+            addrs: vec![],
+            vars: IndexSet::new(),
+            copy_trail: vec![],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn take_addrs(&mut self) -> Vec<Addr> {
+        mem::replace(&mut self.addrs, vec![])
+    }
+
+    #[inline]
+    pub(crate) fn give_addrs(&mut self, mut addrs: Vec<Addr>) {
+        if addrs.len() <= MAX_RETAINED_LEN {
+            addrs.clear();
+            self.addrs = addrs;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn take_vars(&mut self) -> IndexSet<Addr> {
+        mem::replace(&mut self.vars, IndexSet::new())
+    }
+
+    #[inline]
+    pub(crate) fn give_vars(&mut self, mut vars: IndexSet<Addr>) {
+        if vars.len() <= MAX_RETAINED_LEN {
+            vars.clear();
+            self.vars = vars;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn take_copy_trail(&mut self) -> CopyTrail {
+        mem::replace(&mut self.copy_trail, vec![])
+    }
+
+    // the copier unwinds its trail before returning it, so it's
+    // already empty.
+    #[inline]
+    pub(crate) fn give_copy_trail(&mut self, copy_trail: CopyTrail) {
+        if copy_trail.capacity() <= MAX_RETAINED_LEN {
+            self.copy_trail = copy_trail;
+        }
+    }
+}
@@ -0,0 +1,661 @@
+use prolog_parser::ast::*;
+use prolog_parser::tabled_rc::*;
+
+use crate::prolog::instructions::*;
+use crate::prolog::machine::code_walker::*;
+use crate::prolog::machine::machine_indices::*;
+use crate::prolog::machine::machine_state::*;
+use crate::prolog::bignum::Integer;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/* Renders the heap, argument registers, trail and choicepoint chain
+ * of the machine as a Prolog term, for inspecting the engine from
+ * Prolog code (see library(diag)). Heap cells are described rather
+ * than copied, so that the structure of the heap is preserved in the
+ * snapshot: a cell pointing at heap offset H becomes heap_cell(H),
+ * not the term found at H. */
+
+// the predicate whose code contains p, as its module, name, arity and
+// the offset at which its code begins.
+pub(super) fn predicate_at(p: usize, indices: &IndexStore) -> Option<(ClauseName, ClauseName, usize, usize)> {
+    let code_dirs = std::iter::once(&indices.code_dir)
+        .chain(indices.modules.values().map(|module| &module.code_dir));
+
+    let mut found = None;
+
+    for code_dir in code_dirs {
+        for ((name, arity), idx) in code_dir.iter() {
+            if let Some(start) = idx.local() {
+                let closer = match &found {
+                    Some((_, _, _, found_start)) => *found_start < start,
+                    None => true,
+                };
+
+                if start <= p && closer {
+                    found = Some((idx.module_name(), name.clone(), *arity, start));
+                }
+            }
+        }
+    }
+
+    found
+}
+
+// the offset at which the code of the predicate module:name/arity
+// begins, the inverse of predicate_at.
+fn predicate_start(module_name: &str, name: &str, arity: usize, indices: &IndexStore) -> Option<usize> {
+    let code_dirs = std::iter::once(&indices.code_dir)
+        .chain(indices.modules.values().map(|module| &module.code_dir));
+
+    for code_dir in code_dirs {
+        for ((key_name, key_arity), idx) in code_dir.iter() {
+            if key_name.as_str() == name && *key_arity == arity
+                && idx.module_name().as_str() == module_name
+            {
+                if let Some(start) = idx.local() {
+                    return Some(start);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// the 1-based index of the clause containing target, in the predicate
+// whose code begins at start. clauses begin at the choice instructions
+// of the try_me_else chain that follows the indexing instructions.
+fn clause_index_at(code: &Code, start: usize, target: usize) -> usize {
+    let mut p = start;
+
+    while let Some(Line::Indexing(_)) | Some(Line::IndexedChoice(_)) = code.get(p) {
+        p += 1;
+    }
+
+    let mut clause_starts = vec![p];
+
+    while let Some(Line::Choice(instr)) = code.get(p) {
+        match instr {
+            &ChoiceInstruction::TryMeElse(o)
+          | &ChoiceInstruction::RetryMeElse(o)
+          | &ChoiceInstruction::DefaultRetryMeElse(o) if o > 0 => {
+                p += o;
+                clause_starts.push(p);
+            }
+            _ => break,
+        }
+    }
+
+    clause_starts.iter().filter(|&&p| p <= target).count().max(1)
+}
+
+// the offsets that the indexing instruction at p can jump to.
+fn indexing_targets(p: usize, line: &Line) -> Vec<usize> {
+    match line {
+        Line::Indexing(IndexingInstruction::SwitchOnTerm(v, c, l, s)) => {
+            vec![*v, *c, *l, *s].into_iter().filter(|o| *o > 0).map(|o| p + o).collect()
+        }
+        Line::Indexing(IndexingInstruction::SwitchOnConstant(_, ref hm)) => {
+            hm.values().map(|o| p + o).collect()
+        }
+        Line::Indexing(IndexingInstruction::SwitchOnStructure(_, ref hm)) => {
+            hm.values().map(|o| p + o).collect()
+        }
+        Line::IndexedChoice(ref choice_instr) => {
+            vec![p + choice_instr.offset()]
+        }
+        _ => vec![],
+    }
+}
+
+impl MachineState {
+    fn push_snapshot_functor(&mut self, name: &'static str, args: Vec<Addr>) -> Addr {
+        if args.is_empty() {
+            return Addr::Con(atom!(name));
+        }
+
+        let h = self.heap.h();
+
+        self.heap.push(HeapCellValue::NamedStr(args.len(), clause_name!(name), None));
+
+        for arg in args {
+            self.heap.push(HeapCellValue::Addr(arg));
+        }
+
+        Addr::Str(h)
+    }
+
+    fn push_snapshot_list(&mut self, items: Vec<Addr>) -> Addr {
+        Addr::HeapCell(self.heap.to_list(items.into_iter()))
+    }
+
+    fn push_snapshot_pair(&mut self, key: usize, value: Addr) -> Addr {
+        let key = Addr::Con(Constant::Integer(Integer::from(key)));
+        self.push_snapshot_functor("-", vec![key, value])
+    }
+
+    fn push_snapshot_addr(&mut self, addr: Addr) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        match addr {
+            Addr::AttrVar(h) => self.push_snapshot_functor("attr_var", vec![int(h)]),
+            Addr::Con(c) => self.push_snapshot_functor("con", vec![Addr::Con(c)]),
+            Addr::DBRef(db_ref) => self.push_snapshot_functor("db_ref", vec![Addr::DBRef(db_ref)]),
+            Addr::Lis(l) => self.push_snapshot_functor("lis", vec![int(l)]),
+            Addr::HeapCell(h) => self.push_snapshot_functor("heap_cell", vec![int(h)]),
+            Addr::StackCell(fr, sc) => {
+                self.push_snapshot_functor("stack_cell", vec![int(fr), int(sc)])
+            }
+            Addr::Str(s) => self.push_snapshot_functor("str", vec![int(s)]),
+            Addr::PStrLocation(h, n) => {
+                self.push_snapshot_functor("pstr_location", vec![int(h), int(n)])
+            }
+            Addr::Stream(stream) => self.push_snapshot_functor("stream", vec![Addr::Stream(stream)]),
+        }
+    }
+
+    fn push_snapshot_code_ptr(&mut self, p: LocalCodePtr) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        match p {
+            LocalCodePtr::DirEntry(p) => self.push_snapshot_functor("dir_entry", vec![int(p)]),
+            LocalCodePtr::InSituDirEntry(p) => {
+                self.push_snapshot_functor("in_situ_dir_entry", vec![int(p)])
+            }
+            LocalCodePtr::TopLevel(cn, p) => {
+                self.push_snapshot_functor("top_level", vec![int(cn), int(p)])
+            }
+            LocalCodePtr::UserGoalExpansion(p) => {
+                self.push_snapshot_functor("user_goal_expansion", vec![int(p)])
+            }
+            LocalCodePtr::UserTermExpansion(p) => {
+                self.push_snapshot_functor("user_term_expansion", vec![int(p)])
+            }
+        }
+    }
+
+    fn push_snapshot_trail_ref(&mut self, r: TrailRef) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        match r {
+            TrailRef::Ref(r) => self.push_snapshot_addr(r.as_addr()),
+            TrailRef::AttrVarHeapLink(h) => {
+                self.push_snapshot_functor("attr_var_heap_link", vec![int(h)])
+            }
+            TrailRef::AttrVarListLink(h, l) => {
+                self.push_snapshot_functor("attr_var_list_link", vec![int(h), int(l)])
+            }
+        }
+    }
+
+    /* Returns a term of the form
+     *
+     * [heap-Cells, registers-Registers, trail-Entries, choicepoints-ChoicePoints]
+     *
+     * where Cells holds an Offset-Cell pair for every heap cell at or
+     * above from and below the heap top at the time of the call,
+     * Registers holds an N-Cell pair for each argument register,
+     * Entries lists the trail from bottom to top, and ChoicePoints
+     * lists the choicepoint chain from the newest choicepoint down. */
+    pub(super) fn machine_snapshot(&mut self, from: usize, atom_tbl: TabledData<Atom>) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+        let h = self.heap.h();
+
+        let cells: Vec<_> = (from .. h).map(|h| self.heap[h].clone()).collect();
+        let mut heap = Vec::with_capacity(cells.len());
+
+        for (offset, cell) in cells.into_iter().enumerate() {
+            let cell = match cell {
+                HeapCellValue::Addr(addr) => self.push_snapshot_addr(addr),
+                HeapCellValue::NamedStr(arity, name, _) => {
+                    let name = Addr::Con(Constant::Atom(name, None));
+                    self.push_snapshot_functor("named", vec![name, int(arity)])
+                }
+                HeapCellValue::PartialString(pstr) => {
+                    let chars = clause_name!(pstr.block_as_str().to_string(), atom_tbl);
+                    self.push_snapshot_functor("pstr", vec![Addr::Con(Constant::Atom(chars, None))])
+                }
+            };
+
+            heap.push(self.push_snapshot_pair(from + offset, cell));
+        }
+
+        let mut registers = Vec::with_capacity(self.num_of_args);
+
+        for i in 1 .. self.num_of_args + 1 {
+            let cell = self.push_snapshot_addr(self.registers[i].clone());
+            registers.push(self.push_snapshot_pair(i, cell));
+        }
+
+        let trail_refs: Vec<_> = self.trail[0 .. self.tr].to_vec();
+        let mut trail = Vec::with_capacity(trail_refs.len());
+
+        for r in trail_refs {
+            trail.push(self.push_snapshot_trail_ref(r));
+        }
+
+        let mut choicepoints = vec![];
+        let mut b = self.b;
+
+        while b > 0 {
+            let (bp, cp, e, prev_b, h, tr, b0) = {
+                let prelude = &self.stack.index_or_frame(b).prelude;
+                (prelude.bp, prelude.cp, prelude.e, prelude.b, prelude.h, prelude.tr, prelude.b0)
+            };
+
+            let bp = self.push_snapshot_code_ptr(bp);
+            let cp = self.push_snapshot_code_ptr(cp);
+
+            let fields = vec![
+                self.push_snapshot_functor("alternative", vec![bp]),
+                self.push_snapshot_functor("continuation", vec![cp]),
+                self.push_snapshot_functor("environment", vec![int(e)]),
+                self.push_snapshot_functor("heap_top", vec![int(h)]),
+                self.push_snapshot_functor("trail_top", vec![int(tr)]),
+                self.push_snapshot_functor("cut_barrier", vec![int(b0)]),
+            ];
+
+            let fields = self.push_snapshot_list(fields);
+
+            choicepoints.push(self.push_snapshot_functor("choicepoint", vec![int(b), fields]));
+            b = prev_b;
+        }
+
+        let heap = self.push_snapshot_list(heap);
+        let registers = self.push_snapshot_list(registers);
+        let trail = self.push_snapshot_list(trail);
+        let choicepoints = self.push_snapshot_list(choicepoints);
+
+        let sections = vec![
+            self.push_snapshot_functor("-", vec![Addr::Con(atom!("heap")), heap]),
+            self.push_snapshot_functor("-", vec![Addr::Con(atom!("registers")), registers]),
+            self.push_snapshot_functor("-", vec![Addr::Con(atom!("trail")), trail]),
+            self.push_snapshot_functor("-", vec![Addr::Con(atom!("choicepoints")), choicepoints]),
+        ];
+
+        self.push_snapshot_list(sections)
+    }
+
+    // the choicepoint chain, most recent first, as a list of
+    // choice_point(PI, ClauseIndex, TrailMark) terms. PI is the
+    // predicate whose alternative is pending, qualified by the module
+    // defining it if any, and ClauseIndex the clause to which the
+    // alternative belongs. Choicepoints of code outside any predicate
+    // (toplevel queries and expansion hooks) have PI set to the kind
+    // of the code and ClauseIndex 0.
+    pub(super) fn choice_points(&mut self, code: &Code, indices: &IndexStore) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        let mut choice_points = vec![];
+        let mut b = self.b;
+
+        while b > 0 {
+            let (bp, prev_b, tr) = {
+                let prelude = &self.stack.index_or_frame(b).prelude;
+                (prelude.bp, prelude.b, prelude.tr)
+            };
+
+            let (pi, clause_index) = match bp {
+                LocalCodePtr::DirEntry(p) => {
+                    // the alternatives of indexed choice instructions jump
+                    // into the clause they try.
+                    let target = match code.get(p) {
+                        Some(Line::IndexedChoice(IndexedChoiceInstruction::Retry(o)))
+                      | Some(Line::IndexedChoice(IndexedChoiceInstruction::Trust(o))) => p + o,
+                        _ => p,
+                    };
+
+                    match predicate_at(target, indices) {
+                        Some((module_name, name, arity, start)) => {
+                            let name = Addr::Con(Constant::Atom(name, None));
+                            let pi = self.push_snapshot_functor("/", vec![name, int(arity)]);
+
+                            let is_module = match indices.modules.get(&module_name) {
+                                Some(module) => !module.is_impromptu_module,
+                                None => false,
+                            };
+
+                            let pi = if !is_module {
+                                pi
+                            } else {
+                                let module_name = Addr::Con(Constant::Atom(module_name, None));
+                                self.push_snapshot_functor(":", vec![module_name, pi])
+                            };
+
+                            (pi, clause_index_at(code, start, target))
+                        }
+                        None => (Addr::Con(atom!("unknown")), 0),
+                    }
+                }
+                LocalCodePtr::InSituDirEntry(_) => (Addr::Con(atom!("in_situ")), 0),
+                LocalCodePtr::TopLevel(..) => (Addr::Con(atom!("top_level")), 0),
+                LocalCodePtr::UserGoalExpansion(_) => (Addr::Con(atom!("goal_expansion")), 0),
+                LocalCodePtr::UserTermExpansion(_) => (Addr::Con(atom!("term_expansion")), 0),
+            };
+
+            choice_points.push(
+                self.push_snapshot_functor("choice_point", vec![pi, int(clause_index), int(tr)])
+            );
+
+            b = prev_b;
+        }
+
+        self.push_snapshot_list(choice_points)
+    }
+
+    /* Locates the code offset p relative to the predicate containing
+     * it, as code_location(Module, Name, Arity, Offset, Length), where
+     * Length is the length of the predicate's code. Unlike p itself,
+     * the location remains valid in another process that has loaded
+     * the same predicate, possibly elsewhere in its code area.
+     */
+    pub(super) fn code_location(&mut self, p: usize, code: &Code, indices: &IndexStore) -> Option<Addr> {
+        let (module_name, name, arity, start) = predicate_at(p, indices)?;
+        let length = code_extent(code, start).len();
+
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        let module_name = Addr::Con(Constant::Atom(module_name, None));
+        let name = Addr::Con(Constant::Atom(name, None));
+
+        Some(self.push_snapshot_functor(
+            "code_location",
+            vec![module_name, name, int(arity), int(p - start), int(length)],
+        ))
+    }
+
+    // the code offset of a location given by code_location, provided
+    // its predicate is defined and its code is of the same length.
+    pub(super) fn code_location_offset(&self, location: Addr, code: &Code, indices: &IndexStore) -> Option<usize> {
+        let s = match self.store(self.deref(location)) {
+            Addr::Str(s) => s,
+            _ => return None,
+        };
+
+        match &self.heap[s] {
+            HeapCellValue::NamedStr(5, ref name, _) if name.as_str() == "code_location" => {}
+            _ => return None,
+        }
+
+        let args: Vec<Addr> = (1 .. 6)
+            .map(|i| self.store(self.deref(Addr::HeapCell(s + i))))
+            .collect();
+
+        let int = |addr: &Addr| match addr {
+            Addr::Con(Constant::Integer(n)) => n.to_usize(),
+            _ => None,
+        };
+
+        let (module_name, name) = match (&args[0], &args[1]) {
+            (Addr::Con(Constant::Atom(module_name, _)), Addr::Con(Constant::Atom(name, _))) => {
+                (module_name.clone(), name.clone())
+            }
+            _ => return None,
+        };
+
+        let (arity, offset, length) = (int(&args[2])?, int(&args[3])?, int(&args[4])?);
+        let start = predicate_start(module_name.as_str(), name.as_str(), arity, indices)?;
+
+        if code_extent(code, start).len() == length {
+            Some(start + offset)
+        } else {
+            None
+        }
+    }
+
+    /* The state of a call policy as a term, for saved states:
+     *
+     *     default
+     *     limits(Count, Inferences, Deadlines, HeapLimits, Previous)
+     *
+     * where Inferences, Deadlines and HeapLimits list the limits of the
+     * call_with_*_limit goals as Limit-ChoicePoint pairs, deadlines
+     * given by the milliseconds left before them, and Previous is the
+     * state of the policy the limits were installed over. */
+    pub(super) fn call_policy_state_term(&mut self, state: CallPolicyState) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        match state {
+            CallPolicyState::Default => Addr::Con(atom!("default")),
+            CallPolicyState::Limits { count, limits, deadlines, heap_limits, prev } => {
+                let limits = limits.into_iter().map(|(limit, b)| {
+                    let limit = Addr::Con(Constant::Integer(limit));
+                    self.push_snapshot_functor("-", vec![limit, int(b)])
+                }).collect();
+
+                let deadlines = deadlines.into_iter().map(|(left, b)| {
+                    let left = Addr::Con(Constant::Integer(Integer::from(left.as_millis() as u64)));
+                    self.push_snapshot_functor("-", vec![left, int(b)])
+                }).collect();
+
+                let heap_limits = heap_limits.into_iter().map(|(limit, b)| {
+                    self.push_snapshot_functor("-", vec![int(limit), int(b)])
+                }).collect();
+
+                let count = Addr::Con(Constant::Integer(count));
+                let limits = self.push_snapshot_list(limits);
+                let deadlines = self.push_snapshot_list(deadlines);
+                let heap_limits = self.push_snapshot_list(heap_limits);
+                let prev = self.call_policy_state_term(*prev);
+
+                self.push_snapshot_functor("limits", vec![count, limits, deadlines, heap_limits, prev])
+            }
+        }
+    }
+
+    /* The state of a cut policy as a term, either default or
+     *
+     *     cleaners(ContPts, CleanerWithHeap, CleanerWithoutHeap)
+     *
+     * where ContPts lists the pending setup_call_cleanup/3 cleanups as
+     * cont_pt(Cleanup, ChoicePoint, PreviousBlock) terms, oldest first,
+     * and the cleaners are the code offsets of the predicates calling
+     * them. */
+    pub(super) fn cut_policy_state_term(&mut self, state: CutPolicyState) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        match state {
+            CutPolicyState::Default => Addr::Con(atom!("default")),
+            CutPolicyState::Cleaners { cont_pts, r_c_w_h, r_c_wo_h } => {
+                let cont_pts = cont_pts.into_iter().map(|(addr, b, prev_block)| {
+                    self.push_snapshot_functor("cont_pt", vec![addr, int(b), int(prev_block)])
+                }).collect();
+
+                let cont_pts = self.push_snapshot_list(cont_pts);
+                self.push_snapshot_functor("cleaners", vec![cont_pts, int(r_c_w_h), int(r_c_wo_h)])
+            }
+        }
+    }
+
+    // the arguments of addr, if it is a term of the given name and arity.
+    fn snapshot_args(&self, addr: Addr, name: &str, arity: usize) -> Option<Vec<Addr>> {
+        match self.store(self.deref(addr)) {
+            Addr::Con(Constant::Atom(ref atom, _)) if arity == 0 && atom.as_str() == name => {
+                Some(vec![])
+            }
+            Addr::Str(s) => match &self.heap[s] {
+                HeapCellValue::NamedStr(n, ref atom, _) if *n == arity && atom.as_str() == name => {
+                    Some((1 .. arity + 1).map(|i| self.store(self.deref(Addr::HeapCell(s + i)))).collect())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn snapshot_items(&self, addr: Addr) -> Option<Vec<Addr>> {
+        let mut items = vec![];
+        let mut addr = self.store(self.deref(addr));
+
+        loop {
+            match addr {
+                Addr::Lis(l) => {
+                    items.push(self.store(self.deref(Addr::HeapCell(l))));
+                    addr = self.store(self.deref(Addr::HeapCell(l + 1)));
+                }
+                Addr::Con(Constant::EmptyList) => return Some(items),
+                _ => return None,
+            }
+        }
+    }
+
+    fn snapshot_integer(&self, addr: &Addr) -> Option<Integer> {
+        match self.store(self.deref(addr.clone())) {
+            Addr::Con(Constant::Integer(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn snapshot_usize(&self, addr: &Addr) -> Option<usize> {
+        self.snapshot_integer(addr)?.to_usize()
+    }
+
+    // the Limit-ChoicePoint pairs of a list given by call_policy_state_term.
+    fn snapshot_limits(&self, addr: Addr) -> Option<Vec<(Integer, usize)>> {
+        self.snapshot_items(addr)?.into_iter().map(|item| {
+            let args = self.snapshot_args(item, "-", 2)?;
+            Some((self.snapshot_integer(&args[0])?, self.snapshot_usize(&args[1])?))
+        }).collect()
+    }
+
+    // the inverse of call_policy_state_term.
+    pub(super) fn call_policy_state(&self, addr: Addr) -> Option<CallPolicyState> {
+        if self.snapshot_args(addr.clone(), "default", 0).is_some() {
+            return Some(CallPolicyState::Default);
+        }
+
+        let args = self.snapshot_args(addr, "limits", 5)?;
+
+        let deadlines = self.snapshot_limits(args[2].clone())?.into_iter().map(|(left, b)| {
+            Some((Duration::from_millis(left.to_u64()?), b))
+        }).collect::<Option<Vec<_>>>()?;
+
+        let heap_limits = self.snapshot_limits(args[3].clone())?.into_iter().map(|(limit, b)| {
+            Some((limit.to_usize()?, b))
+        }).collect::<Option<Vec<_>>>()?;
+
+        Some(CallPolicyState::Limits {
+            count: self.snapshot_integer(&args[0])?,
+            limits: self.snapshot_limits(args[1].clone())?,
+            deadlines,
+            heap_limits,
+            prev: Box::new(self.call_policy_state(args[4].clone())?),
+        })
+    }
+
+    // the inverse of cut_policy_state_term.
+    pub(super) fn cut_policy_state(&self, addr: Addr) -> Option<CutPolicyState> {
+        if self.snapshot_args(addr.clone(), "default", 0).is_some() {
+            return Some(CutPolicyState::Default);
+        }
+
+        let args = self.snapshot_args(addr, "cleaners", 3)?;
+
+        let cont_pts = self.snapshot_items(args[0].clone())?.into_iter().map(|item| {
+            let args = self.snapshot_args(item, "cont_pt", 3)?;
+            Some((args[0].clone(), self.snapshot_usize(&args[1])?, self.snapshot_usize(&args[2])?))
+        }).collect::<Option<Vec<_>>>()?;
+
+        Some(CutPolicyState::Cleaners {
+            cont_pts,
+            r_c_w_h: self.snapshot_usize(&args[1])?,
+            r_c_wo_h: self.snapshot_usize(&args[2])?,
+        })
+    }
+
+    /* Lists the instructions of the predicate whose code begins at p.
+     * The jumps of indexing instructions are disassembled to symbolic
+     * labels: each instruction they can jump to is preceded in the
+     * listing by label(N), and their offsets are replaced by label(N),
+     * or by fail where the offset is 0. switch_on_constant and
+     * switch_on_structure list their tables as Key-label(N) pairs.
+     */
+    pub(super) fn wam_instructions(&mut self, code: &Code, p: usize) -> Addr {
+        let int = |n: usize| Addr::Con(Constant::Integer(Integer::from(n)));
+
+        let mut offsets = vec![];
+        let mut targets = BTreeSet::new();
+
+        walk_code_offsets(code, p, |offset, instr| {
+            offsets.push(offset);
+            targets.extend(indexing_targets(offset, instr));
+        });
+
+        let labels: BTreeMap<usize, usize> = targets
+            .into_iter()
+            .enumerate()
+            .map(|(n, target)| (target, n + 1))
+            .collect();
+
+        let label = |machine_st: &mut MachineState, target: usize| match labels.get(&target) {
+            Some(&n) => machine_st.push_snapshot_functor("label", vec![int(n)]),
+            None => Addr::Con(atom!("fail")),
+        };
+
+        let mut listing = vec![];
+
+        for offset in offsets {
+            if labels.contains_key(&offset) {
+                listing.push(label(self, offset));
+            }
+
+            let instr = match &code[offset] {
+                Line::Indexing(IndexingInstruction::SwitchOnTerm(v, c, l, s)) => {
+                    let args = vec![*v, *c, *l, *s]
+                        .into_iter()
+                        .map(|o| if o > 0 { label(self, offset + o) } else { Addr::Con(atom!("fail")) })
+                        .collect();
+
+                    self.push_snapshot_functor("switch_on_term", args)
+                }
+                Line::Indexing(IndexingInstruction::SwitchOnConstant(_, ref hm)) => {
+                    let mut table = vec![];
+
+                    for (c, o) in hm.iter() {
+                        let target = label(self, offset + o);
+                        table.push(self.push_snapshot_functor("-", vec![Addr::Con(c.clone()), target]));
+                    }
+
+                    let table = self.push_snapshot_list(table);
+                    self.push_snapshot_functor("switch_on_constant", vec![table])
+                }
+                Line::Indexing(IndexingInstruction::SwitchOnStructure(_, ref hm)) => {
+                    let mut table = vec![];
+
+                    for ((name, arity), o) in hm.iter() {
+                        let name = Addr::Con(Constant::Atom(name.clone(), None));
+                        let key = self.push_snapshot_functor("/", vec![name, int(*arity)]);
+                        let target = label(self, offset + o);
+
+                        table.push(self.push_snapshot_functor("-", vec![key, target]));
+                    }
+
+                    let table = self.push_snapshot_list(table);
+                    self.push_snapshot_functor("switch_on_structure", vec![table])
+                }
+                Line::IndexedChoice(ref choice_instr) => {
+                    let name = match choice_instr {
+                        IndexedChoiceInstruction::Try(_) => "try",
+                        IndexedChoiceInstruction::Retry(_) => "retry",
+                        IndexedChoiceInstruction::Trust(_) => "trust",
+                    };
+
+                    let target = label(self, offset + choice_instr.offset());
+                    self.push_snapshot_functor(name, vec![target])
+                }
+                line => {
+                    let h = self.heap.h();
+
+                    self.heap.extend(line.to_functor(h).into_iter());
+                    Addr::HeapCell(h)
+                }
+            };
+
+            listing.push(instr);
+        }
+
+        self.push_snapshot_list(listing)
+    }
+}
@@ -28,6 +28,10 @@ impl RawBlockTraits for StackTraits {
     }
 }
 
+// the size (in bytes) past which the allocation of new frames raises
+// resource_error(stack) rather than growing the stack further.
+pub const STACK_LIMIT: usize = 1024 * 1024 * 1024;
+
 const fn prelude_size<Prelude>() -> usize {
     let size = mem::size_of::<Prelude>();
     let align = mem::align_of::<Addr>();
@@ -283,6 +287,11 @@ impl Stack {
         }
     }
 
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.buf.top as usize - self.buf.base as usize
+    }
+
     pub fn take(&mut self) -> Self {
         Stack { buf: self.buf.take(), _marker: PhantomData }
     }
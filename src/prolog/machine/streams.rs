@@ -1,15 +1,18 @@
 use crate::prolog_parser::ast::*;
 
+use crate::prolog::machine::block_strings::{block_strings, quasi_quotations, BlockStringReader};
 use crate::prolog::read::readline::*;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
 use std::io::{stdin, stdout, Cursor, ErrorKind, Read, Write};
 use std::hash::{Hash, Hasher};
-use std::net::TcpStream;
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::rc::Rc;
+use std::time::Duration;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StreamType {
@@ -17,6 +20,55 @@ pub enum StreamType {
     Text,
 }
 
+impl StreamType {
+    #[inline]
+    pub(crate)
+    fn as_str(self) -> &'static str {
+        match self {
+            StreamType::Binary => "binary",
+            StreamType::Text => "text",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamMode {
+    Read,
+    Write,
+    Append,
+}
+
+impl StreamMode {
+    #[inline]
+    pub(crate)
+    fn as_str(self) -> &'static str {
+        match self {
+            StreamMode::Read => "read",
+            StreamMode::Write => "write",
+            StreamMode::Append => "append",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EndOfStream {
+    Not,
+    At,
+    Past,
+}
+
+impl EndOfStream {
+    #[inline]
+    pub(crate)
+    fn as_str(self) -> &'static str {
+        match self {
+            EndOfStream::Not => "not",
+            EndOfStream::At => "at",
+            EndOfStream::Past => "past",
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EOFAction {
     EOFCode,
@@ -25,24 +77,51 @@ pub enum EOFAction {
 }
 
 /* all these streams are closed automatically when the instance is
- * dropped. */
+ * dropped. Closed is left in place of the instance by close/1, since
+ * the stream may still be referenced from the heap. */
 pub enum StreamInstance {
+    BlockStrings(BlockStringReader),
     Bytes(Cursor<Vec<u8>>),
+    Closed,
     DynReadSource(Box<dyn Read>),
-    File(File),    
+    InputBuffer(Cursor<Vec<u8>>),
+    InputFile(File),
+    Null,
+    OutputBuffer(Vec<u8>),
+    OutputFile(File),
     ReadlineStream(ReadlineStream),
     Stdin,
     Stdout,
+    TcpListener(TcpListener),
     TcpStream(TcpStream),
 }
 
+// what is known of a stream besides its instance, kept beside it so
+// that it is shared by all copies of the stream: the double_quotes
+// value set by set_stream/2, the file and mode given to open/3, the
+// number of bytes read or written, the number of newlines read, the
+// column the next character written is written at, whether the last
+// read hit the end of the stream, and the bytes read ahead by the
+// peek predicates and at_end_of_stream/1, last to be read first.
+#[derive(Default)]
+struct StreamMetadata {
+    double_quotes: Cell<Option<DoubleQuotes>>,
+    file_name: RefCell<Option<ClauseName>>,
+    mode: Cell<Option<StreamMode>>,
+    position: Cell<u64>,
+    lines: Cell<u64>,
+    column: Cell<u64>,
+    past_end_of_stream: Cell<bool>,
+    pushback: RefCell<Vec<u8>>,
+}
+
 #[derive(Clone)]
-struct WrappedStreamInstance(Rc<RefCell<StreamInstance>>);
+struct WrappedStreamInstance(Rc<RefCell<StreamInstance>>, Rc<StreamMetadata>);
 
 impl WrappedStreamInstance {
     #[inline]
     fn new(stream_inst: StreamInstance) -> Self {
-        WrappedStreamInstance(Rc::new(RefCell::new(stream_inst)))
+        WrappedStreamInstance(Rc::new(RefCell::new(stream_inst)), Rc::new(StreamMetadata::default()))
     }
 }
 
@@ -71,14 +150,19 @@ impl Hash for WrappedStreamInstance {
 
 #[derive(Debug)]
 enum StreamError {
+    AccessClosedStream,
     ReadFromOutputStream,
     WriteToInputStream,
     FlushToInputStream,
+    ReadWriteServerSocket,
 }
 
 impl fmt::Display for StreamError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            StreamError::AccessClosedStream => {
+                write!(f, "attempted to access a closed stream")
+            }
             StreamError::ReadFromOutputStream => {
                 write!(f, "attempted to read from a write-only stream")
             }
@@ -88,6 +172,9 @@ impl fmt::Display for StreamError {
             StreamError::FlushToInputStream => {
                 write!(f, "attempted to flush a read-only stream")
             }
+            StreamError::ReadWriteServerSocket => {
+                write!(f, "attempted to read or write a server socket")
+            }
         }
     }
 }
@@ -158,7 +245,29 @@ impl From<File> for Stream {
         Stream {
             options: StreamOptions::default(),
             stream_inst: WrappedStreamInstance::new(
-                StreamInstance::File(file)
+                StreamInstance::InputFile(file)
+            ),
+        }
+    }
+}
+
+impl From<TcpStream> for Stream {
+    fn from(tcp_stream: TcpStream) -> Stream {
+        Stream {
+            options: StreamOptions::default(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::TcpStream(tcp_stream)
+            ),
+        }
+    }
+}
+
+impl From<TcpListener> for Stream {
+    fn from(tcp_listener: TcpListener) -> Stream {
+        Stream {
+            options: StreamOptions::default(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::TcpListener(tcp_listener)
             ),
         }
     }
@@ -201,6 +310,327 @@ impl Stream {
         }
     }
 
+    /* an input stream of the source text read from stream, with its
+     * block strings rewritten for the lexer (see block_strings.rs).
+     * consulted text is always read through one, as the
+     * block_strings and quasi_quotations flags may be set by its own
+     * directives. */
+    #[inline]
+    pub(crate)
+    fn with_block_strings(self) -> Self {
+        Stream {
+            options: self.options.clone(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::BlockStrings(BlockStringReader::new(self))
+            ),
+        }
+    }
+
+    /* the stream read_term reads terms from: the stream itself, or
+     * while the block_strings or quasi_quotations flag is set, the
+     * stream made by with_block_strings. */
+    #[inline]
+    pub(crate)
+    fn term_source(self) -> Self {
+        if block_strings() || quasi_quotations() {
+            self.with_block_strings()
+        } else {
+            self
+        }
+    }
+
+    /* the number of clause end tokens read through a stream made by
+     * with_block_strings. other streams don't count them. */
+    #[inline]
+    pub(crate)
+    fn clause_ends(&self) -> usize {
+        match *self.stream_inst.0.borrow() {
+            StreamInstance::BlockStrings(ref src) => {
+                src.clause_ends()
+            }
+            _ => {
+                0
+            }
+        }
+    }
+
+    /* skips the rest of the clause being read through a stream made
+     * by with_block_strings, returning the number of lines skipped. */
+    #[inline]
+    pub(crate)
+    fn skip_clause(&self) -> std::io::Result<usize> {
+        match *self.stream_inst.0.borrow_mut() {
+            StreamInstance::BlockStrings(ref mut src) => {
+                src.skip_clause()
+            }
+            _ => {
+                Ok(0)
+            }
+        }
+    }
+
+    /* an output stream discarding everything written to it. */
+    #[inline]
+    pub(crate)
+    fn null() -> Self {
+        Stream {
+            options: StreamOptions::default(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::Null
+            ),
+        }
+    }
+
+    /* an output stream collecting everything written to it in
+     * memory, as with_output_to/2 does. */
+    #[inline]
+    pub(crate)
+    fn output_buffer() -> Self {
+        Stream {
+            options: StreamOptions::default(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::OutputBuffer(vec![])
+            ),
+        }
+    }
+
+    /* an input stream reading the given text from memory, as
+     * open_memory_stream/3 does. unlike a stream of bytes, it can't
+     * be written. */
+    #[inline]
+    pub(crate)
+    fn input_buffer(text: String) -> Self {
+        Stream {
+            options: StreamOptions::default(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::InputBuffer(Cursor::new(text.into_bytes()))
+            ),
+        }
+    }
+
+    /* takes the text written to an output buffer so far, leaving it
+     * empty. None if the stream isn't an output buffer. */
+    pub(crate)
+    fn take_output(&self) -> Option<String> {
+        match *self.stream_inst.0.borrow_mut() {
+            StreamInstance::OutputBuffer(ref mut buf) => {
+                Some(String::from_utf8_lossy(&mem::take(buf)).into_owned())
+            }
+            _ => {
+                None
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate)
+    fn from_output_file(file: File) -> Self {
+        Stream {
+            options: StreamOptions::default(),
+            stream_inst: WrappedStreamInstance::new(
+                StreamInstance::OutputFile(file)
+            ),
+        }
+    }
+
+    /* drops the underlying instance, closing any file or socket it
+     * holds, even while other copies of the stream are still live. */
+    #[inline]
+    pub(crate)
+    fn close(&self) {
+        *self.stream_inst.0.borrow_mut() = StreamInstance::Closed;
+    }
+
+    /* sets the deadline for blocking reads and writes. only sockets
+     * can block indefinitely, so the deadline is handed to the socket
+     * itself, which reports an expired deadline as WouldBlock or
+     * TimedOut depending on the platform. */
+    pub(crate)
+    fn set_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match *self.stream_inst.0.borrow() {
+            StreamInstance::TcpStream(ref tcp_stream) => {
+                tcp_stream.set_read_timeout(timeout)?;
+                tcp_stream.set_write_timeout(timeout)
+            }
+            _ => {
+                Ok(())
+            }
+        }
+    }
+
+    /* waits for a connection to a server socket, returning a stream
+     * both reading from and writing to it, and the address of its
+     * client. None if the stream isn't a server socket. */
+    pub(crate)
+    fn accept(&self) -> Option<std::io::Result<(Stream, SocketAddr)>> {
+        match *self.stream_inst.0.borrow() {
+            StreamInstance::TcpListener(ref tcp_listener) => {
+                Some(tcp_listener.accept().map(|(tcp_stream, addr)| (Stream::from(tcp_stream), addr)))
+            }
+            _ => {
+                None
+            }
+        }
+    }
+
+    // the value of the double_quotes flag for terms read from the
+    // stream, if set_stream/2 overrode it.
+    #[inline]
+    pub(crate)
+    fn double_quotes(&self) -> Option<DoubleQuotes> {
+        self.stream_inst.1.double_quotes.get()
+    }
+
+    #[inline]
+    pub(crate)
+    fn set_double_quotes(&self, double_quotes: DoubleQuotes) {
+        self.stream_inst.1.double_quotes.set(Some(double_quotes));
+    }
+
+    // records the file a stream was opened on by open/3, and how.
+    #[inline]
+    pub(crate)
+    fn set_file_source(&self, file_name: ClauseName, mode: StreamMode) {
+        *self.stream_inst.1.file_name.borrow_mut() = Some(file_name);
+        self.stream_inst.1.mode.set(Some(mode));
+    }
+
+    #[inline]
+    pub(crate)
+    fn file_name(&self) -> Option<ClauseName> {
+        self.stream_inst.1.file_name.borrow().clone()
+    }
+
+    /* the mode given to open/3, otherwise read for input streams and
+     * append for output streams, as for the standard streams. */
+    #[inline]
+    pub(crate)
+    fn mode(&self) -> StreamMode {
+        match self.stream_inst.1.mode.get() {
+            Some(mode) => mode,
+            None if self.is_input_stream() => StreamMode::Read,
+            None => StreamMode::Append,
+        }
+    }
+
+    // the number of bytes read from or written to the stream.
+    #[inline]
+    pub(crate)
+    fn position(&self) -> u64 {
+        self.stream_inst.1.position.get()
+    }
+
+    // the column the next character written to the stream is written
+    // at, counting from 0.
+    #[inline]
+    pub(crate)
+    fn column(&self) -> u64 {
+        self.stream_inst.1.column.get()
+    }
+
+    // the number of newlines read from the stream.
+    #[inline]
+    pub(crate)
+    fn lines(&self) -> u64 {
+        self.stream_inst.1.lines.get()
+    }
+
+    /* the end_of_stream property of an input stream. only streams
+     * whose reads can't block are read ahead to tell whether they are
+     * at their end, so the property of a socket or terminal is not
+     * until a read has reached its end. */
+    pub(crate)
+    fn end_of_stream(&mut self) -> std::io::Result<EndOfStream> {
+        if self.stream_inst.1.past_end_of_stream.get() {
+            return Ok(EndOfStream::Past);
+        }
+
+        let nonblocking = match *self.stream_inst.0.borrow() {
+            StreamInstance::Bytes(_)
+          | StreamInstance::InputBuffer(_)
+          | StreamInstance::DynReadSource(_)
+          | StreamInstance::BlockStrings(_)
+          | StreamInstance::InputFile(_) => {
+                true
+            }
+            _ => {
+                false
+            }
+        };
+
+        if nonblocking && self.at_end_of_stream()? {
+            Ok(EndOfStream::At)
+        } else {
+            Ok(EndOfStream::Not)
+        }
+    }
+
+    /* true iff the next read of the stream will find its end. the
+     * byte read ahead to tell is kept for that read. */
+    pub(crate)
+    fn at_end_of_stream(&mut self) -> std::io::Result<bool> {
+        if !self.stream_inst.1.pushback.borrow().is_empty() {
+            return Ok(false);
+        }
+
+        if self.stream_inst.1.past_end_of_stream.get() {
+            return Ok(true);
+        }
+
+        let mut byte = [0u8];
+
+        if self.read_instance(&mut byte)? == 0 {
+            Ok(true)
+        } else {
+            self.stream_inst.1.pushback.borrow_mut().push(byte[0]);
+            Ok(false)
+        }
+    }
+
+    /* the next byte of the stream, left to be read by its next read,
+     * or None if it is at its end. */
+    pub(crate)
+    fn peek_byte(&mut self) -> std::io::Result<Option<u8>> {
+        if self.at_end_of_stream()? {
+            Ok(None)
+        } else {
+            Ok(self.stream_inst.1.pushback.borrow().last().cloned())
+        }
+    }
+
+    /* puts bytes just read back in front of the rest of the stream,
+     * to be read again by its next reads. */
+    pub(crate)
+    fn unread(&self, bytes: &[u8]) {
+        let metadata = &self.stream_inst.1;
+
+        metadata.pushback.borrow_mut().extend(bytes.iter().rev());
+        metadata.past_end_of_stream.set(false);
+        metadata.position.set(metadata.position.get().saturating_sub(bytes.len() as u64));
+        metadata.lines.set(metadata.lines.get().saturating_sub(newlines(bytes)));
+    }
+
+    // streams opened with the type(binary) option of open/4, which are
+    // read and written by bytes rather than by characters.
+    #[inline]
+    pub(crate)
+    fn is_binary_stream(&self) -> bool {
+        self.options.stream_type == StreamType::Binary
+    }
+
+    #[inline]
+    pub(crate)
+    fn is_closed(&self) -> bool {
+        match *self.stream_inst.0.borrow() {
+            StreamInstance::Closed => {
+                true
+            }
+            _ => {
+                false
+            }
+        }
+    }
+
     #[inline]
     pub(crate)
     fn is_stdout(&self) -> bool {
@@ -234,9 +664,11 @@ impl Stream {
             StreamInstance::Stdin
           | StreamInstance::TcpStream(_)
           | StreamInstance::Bytes(_) 
+          | StreamInstance::InputBuffer(_)
           | StreamInstance::ReadlineStream(_)
           | StreamInstance::DynReadSource(_)
-          | StreamInstance::File(_) => {
+          | StreamInstance::BlockStrings(_)
+          | StreamInstance::InputFile(_) => {
                 true
            }
             _ => {
@@ -252,7 +684,9 @@ impl Stream {
             StreamInstance::Stdout
           | StreamInstance::TcpStream(_)
           | StreamInstance::Bytes(_) 
-          | StreamInstance::File(_) => {
+          | StreamInstance::Null
+          | StreamInstance::OutputBuffer(_)
+          | StreamInstance::OutputFile(_) => {
                 true
            }
             _ => {
@@ -260,16 +694,79 @@ impl Stream {
             }
         }
     }
+
+    /* reads a toplevel command line starting with prefix, if one is
+     * next in the input. only interactive input has commands. */
+    pub(crate)
+    fn read_command(&self, prefix: &str) -> std::io::Result<Option<String>> {
+        match *self.stream_inst.0.borrow_mut() {
+            StreamInstance::ReadlineStream(ref mut rl_stream) => {
+                rl_stream.read_command(prefix)
+            }
+            _ => {
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[inline]
+fn timed_out(err: std::io::Error) -> std::io::Error {
+    if err.kind() == ErrorKind::WouldBlock {
+        std::io::Error::from(ErrorKind::TimedOut)
+    } else {
+        err
+    }
+}
+
+#[inline]
+fn newlines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&b| b == b'\n').count() as u64
+}
+
+// the number of characters of UTF-8 encoded bytes, which are the
+// bytes that aren't continuation bytes.
+#[inline]
+fn chars(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&b| b & 0xc0 != 0x80).count() as u64
 }
 
 impl Read for Stream {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let metadata = self.stream_inst.1.clone();
+
+        let pushed_back = metadata.pushback.borrow_mut().pop();
+
+        let n = match pushed_back {
+            Some(byte) => {
+                buf[0] = byte;
+                1
+            }
+            None => {
+                self.read_instance(buf)?
+            }
+        };
+
+        metadata.past_end_of_stream.set(n == 0);
+        metadata.position.set(metadata.position.get() + n as u64);
+        metadata.lines.set(metadata.lines.get() + newlines(&buf[.. n]));
+
+        Ok(n)
+    }
+}
+
+impl Stream {
+    fn read_instance(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match *self.stream_inst.0.borrow_mut() {
-            StreamInstance::File(ref mut file) => {
+            StreamInstance::InputFile(ref mut file) => {
                 file.read(buf)
             }
             StreamInstance::TcpStream(ref mut tcp_stream) => {
-                tcp_stream.read(buf)
+                tcp_stream.read(buf).map_err(timed_out)
             }
             StreamInstance::ReadlineStream(ref mut rl_stream) => {
                 rl_stream.read(buf)
@@ -277,30 +774,69 @@ impl Read for Stream {
             StreamInstance::DynReadSource(ref mut src) => {
                 src.read(buf)
             }
-            StreamInstance::Bytes(ref mut cursor) => {
+            StreamInstance::BlockStrings(ref mut src) => {
+                src.read(buf)
+            }
+            StreamInstance::Bytes(ref mut cursor)
+          | StreamInstance::InputBuffer(ref mut cursor) => {
                 cursor.read(buf)
             }
             StreamInstance::Stdin => {
                 stdin().read(buf)
             }
-            StreamInstance::Stdout => {
+            StreamInstance::OutputFile(_)
+          | StreamInstance::OutputBuffer(_)
+          | StreamInstance::Null
+          | StreamInstance::Stdout => {
                 Err(std::io::Error::new(
                     ErrorKind::PermissionDenied,
                     StreamError::ReadFromOutputStream,
                 ))
             }
+            StreamInstance::TcpListener(_) => {
+                Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    StreamError::ReadWriteServerSocket,
+                ))
+            }
+            StreamInstance::Closed => {
+                Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    StreamError::AccessClosedStream,
+                ))
+            }
         }
     }
 }
 
 impl Write for Stream {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.write_instance(buf)?;
+
+        let metadata = &self.stream_inst.1;
+        metadata.position.set(metadata.position.get() + n as u64);
+
+        match buf[.. n].iter().rposition(|&b| b == b'\n') {
+            Some(idx) => metadata.column.set(chars(&buf[idx + 1 .. n])),
+            None => metadata.column.set(metadata.column.get() + chars(&buf[.. n])),
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flush_instance()
+    }
+}
+
+impl Stream {
+    fn write_instance(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         match *self.stream_inst.0.borrow_mut() {
-            StreamInstance::File(ref mut file) => {
+            StreamInstance::OutputFile(ref mut file) => {
                 file.write(buf)
             }
             StreamInstance::TcpStream(ref mut tcp_stream) => {
-                tcp_stream.write(buf)
+                tcp_stream.write(buf).map_err(timed_out)
             }
             StreamInstance::Bytes(ref mut cursor) => {
                 cursor.write(buf)
@@ -308,6 +844,25 @@ impl Write for Stream {
             StreamInstance::Stdout => {
                 stdout().write(buf)
             }
+            StreamInstance::Null => {
+                Ok(buf.len())
+            }
+            StreamInstance::OutputBuffer(ref mut output) => {
+                output.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            StreamInstance::Closed => {
+                Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    StreamError::AccessClosedStream,
+                ))
+            }
+            StreamInstance::TcpListener(_) => {
+                Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    StreamError::ReadWriteServerSocket,
+                ))
+            }
             _ => {
                 Err(std::io::Error::new(
                     ErrorKind::PermissionDenied,
@@ -317,13 +872,13 @@ impl Write for Stream {
         }
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
+    fn flush_instance(&mut self) -> std::io::Result<()> {
         match *self.stream_inst.0.borrow_mut() {
-            StreamInstance::File(ref mut file) => {
+            StreamInstance::OutputFile(ref mut file) => {
                 file.flush()
             }
             StreamInstance::TcpStream(ref mut tcp_stream) => {
-                tcp_stream.flush()
+                tcp_stream.flush().map_err(timed_out)
             }
             StreamInstance::Bytes(ref mut cursor) => {
                 cursor.flush()
@@ -331,6 +886,21 @@ impl Write for Stream {
             StreamInstance::Stdout => {
                 stdout().flush()
             }
+            StreamInstance::Null | StreamInstance::OutputBuffer(_) => {
+                Ok(())
+            }
+            StreamInstance::Closed => {
+                Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    StreamError::AccessClosedStream,
+                ))
+            }
+            StreamInstance::TcpListener(_) => {
+                Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    StreamError::ReadWriteServerSocket,
+                ))
+            }
             _ => {
                 Err(std::io::Error::new(
                     ErrorKind::PermissionDenied,
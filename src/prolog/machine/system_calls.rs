@@ -6,30 +6,51 @@ use crate::prolog::clause_types::*;
 use crate::prolog::forms::*;
 use crate::prolog::heap_print::*;
 use crate::prolog::instructions::*;
+use crate::prolog::machine::block_strings::*;
+use crate::prolog::machine::chars_reader::CharsReader;
 use crate::prolog::machine::code_repo::CodeRepo;
 use crate::prolog::machine::copier::*;
-use crate::prolog::machine::code_walker::*;
+use crate::prolog::machine::fast_term::{read_atom_table, write_atom_table};
 use crate::prolog::machine::machine_errors::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::machine_state::*;
+use crate::prolog::machine::modules::SubModuleUser;
 use crate::prolog::machine::streams::*;
+use crate::prolog::machine::term_pipes::*;
+use crate::prolog::machine::term_view::TermView;
+use crate::prolog::machine::AUTOLOAD_INDEX;
 use crate::prolog::machine::toplevel::to_op_decl;
 use crate::prolog::ordered_float::OrderedFloat;
-use crate::prolog::read::readline;
-use crate::prolog::rug::Integer;
+use crate::prolog::read::{readline, write_term_to_heap};
+use crate::prolog::bignum::Integer;
+use crate::prolog::bignum::RandState;
 
 use crate::ref_thread_local::RefThreadLocal;
 
-use indexmap::{IndexMap, IndexSet};
+use indexmap::IndexMap;
 
-use std::io::{stdout, Write};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{Cursor, ErrorKind, Read, Write};
 use std::iter::once;
 use std::mem;
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process;
 use std::rc::Rc;
+use std::sync::atomic::{self, AtomicUsize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "repl")]
 use crate::crossterm::event::{read, Event, KeyCode, KeyEvent};
+#[cfg(feature = "repl")]
 use crate::crossterm::terminal::{enable_raw_mode, disable_raw_mode};
 
+// numbers the names handed out by tmp_file/2.
+static TMP_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
 pub enum ContinueResult {
     ContinueQuery,
     Conclude,
@@ -38,32 +59,52 @@ pub enum ContinueResult {
     PrintWithMaxDepth
 }
 
+fn continue_result(c: char) -> Option<ContinueResult> {
+    match c {
+        'w' => Some(ContinueResult::PrintWithoutMaxDepth),
+        'p' => Some(ContinueResult::PrintWithMaxDepth),
+        ' ' | ';' | 'n' => Some(ContinueResult::ContinueQuery),
+        '.' => Some(ContinueResult::Conclude),
+        'h' => Some(ContinueResult::Help),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "repl")]
 pub fn next_keypress() -> ContinueResult {
-    loop {
-        match read() {
-            Ok(Event::Key(KeyEvent { code, .. })) => {
-                match code {
-                    KeyCode::Char('w') => {
-                        return ContinueResult::PrintWithoutMaxDepth;
-                    }
-                    KeyCode::Char('p') => {
-                        return ContinueResult::PrintWithMaxDepth;
-                    }
-                    KeyCode::Char(' ') | KeyCode::Char(';') | KeyCode::Char('n') => {
-                        return ContinueResult::ContinueQuery;
-                    }
-                    KeyCode::Char('.') => {
-                        return ContinueResult::Conclude;
-                    }
-                    KeyCode::Char('h') => {
-                        return ContinueResult::Help;
-                    }
-                    _ => {}
+    enable_raw_mode().expect("failed to transition into raw mode");
+
+    let result = loop {
+        if let Ok(Event::Key(KeyEvent { code: KeyCode::Char(c), .. })) = read() {
+            if let Some(result) = continue_result(c) {
+                break result;
+            }
+        }
+    };
+
+    disable_raw_mode().expect("failed to transition out of raw mode");
+    result
+}
+
+// without a terminal to put into raw mode, the keys are read from
+// standard input as they are buffered, and its end concludes the
+// query.
+#[cfg(not(feature = "repl"))]
+pub fn next_keypress() -> ContinueResult {
+    use std::io::Read;
+
+    for byte in std::io::stdin().lock().bytes() {
+        match byte {
+            Ok(byte) => {
+                if let Some(result) = continue_result(byte as char) {
+                    return result;
                 }
             }
-            _ => {}
+            Err(_) => break,
         }
     }
+
+    ContinueResult::Conclude
 }
 
 struct BrentAlgState {
@@ -128,6 +169,173 @@ fn is_builtin_predicate(name: &ClauseName) -> bool {
     in_builtins || hidden_name
 }
 
+fn integer_to_radix_string(n: &Integer, radix: u32) -> String {
+    if radix == 10 {
+        return n.to_string();
+    }
+
+    let mut m = n.clone().abs();
+    let mut digits = vec![];
+
+    while m > 0 {
+        digits.push(std::char::from_digit(m.mod_u(radix), radix).unwrap());
+        m = m.div_rem_floor(Integer::from(radix)).0;
+    }
+
+    if digits.is_empty() {
+        digits.push('0');
+    }
+
+    if n < &0 {
+        digits.push('-');
+    }
+
+    digits.into_iter().rev().collect()
+}
+
+// the references held by the call and cut policies, collected for
+// '$policy_refs'/2.
+#[derive(Default)]
+struct PolicyRefCollector {
+    addrs: Vec<Addr>,
+    choice_points: Vec<usize>,
+}
+
+impl PolicyVisitor for PolicyRefCollector {
+    fn visit_addr(&mut self, addr: &mut Addr) {
+        self.addrs.push(addr.clone());
+    }
+
+    fn visit_choice_point(&mut self, b: &mut usize) {
+        self.choice_points.push(*b);
+    }
+}
+
+// tightens the bounds of the variables of the linear constraint
+// sum(cs[i] * x[i]) + c =< 0, x[i] lying between los[i] and his[i],
+// where None is an infinite bound. false if no values of the
+// variables satisfy it.
+fn fd_linear_le(
+    cs: &[Integer],
+    c: &Integer,
+    los: &mut [Option<Integer>],
+    his: &mut [Option<Integer>],
+) -> bool {
+    // the least value of each term, None if it's unbounded below.
+    let mins: Vec<Option<Integer>> = cs.iter().enumerate().map(|(i, ci)| {
+        let bound = if ci > &0 { &los[i] } else { &his[i] };
+        bound.clone().map(|b| b * ci.clone())
+    }).collect();
+
+    let unbounded = mins.iter().filter(|min| min.is_none()).count();
+    let mut finite_sum = Integer::from(0);
+
+    for min in mins.iter().flatten() {
+        finite_sum += min.clone();
+    }
+
+    if unbounded == 0 && finite_sum.clone() + c.clone() > 0 {
+        return false;
+    }
+
+    for (i, ci) in cs.iter().enumerate() {
+        // the least value of the other terms.
+        let rest = match (&mins[i], unbounded) {
+            (Some(min), 0) => finite_sum.clone() - min.clone(),
+            (None, 1) => finite_sum.clone(),
+            _ => continue,
+        };
+
+        // ci * x[i] =< bound.
+        let bound = -(rest + c.clone());
+
+        if ci > &0 {
+            let hi = bound.div_rem_floor(ci.clone()).0;
+
+            if his[i].as_ref().map_or(true, |h| &hi < h) {
+                his[i] = Some(hi);
+            }
+        } else {
+            let lo = -((-bound).div_rem_floor(ci.clone()).0);
+
+            if los[i].as_ref().map_or(true, |l| &lo > l) {
+                los[i] = Some(lo);
+            }
+        }
+    }
+
+    los.iter().zip(his.iter()).all(|bounds| match bounds {
+        (Some(lo), Some(hi)) => lo <= hi,
+        _ => true,
+    })
+}
+
+// draws an unsigned number of the given number of bits, at most 64,
+// from RANDOM_STATE, 32 bits at a time.
+fn random_bits(bits: u32) -> u64 {
+    let mut rand = RANDOM_STATE.borrow_mut();
+    let mut n = 0;
+    let mut remaining = bits;
+
+    while remaining > 0 {
+        let chunk = std::cmp::min(remaining, 32);
+
+        n = (n << chunk) | rand.bits(chunk) as u64;
+        remaining -= chunk;
+    }
+
+    *RANDOM_DRAWS.borrow_mut() += 1;
+    n
+}
+
+// draws uniformly from 0 .. bound, where bound > 0, rejecting the
+// draws of as many bits as bound - 1 that fall beyond it.
+fn random_below(bound: u64) -> u64 {
+    let bits = 64 - (bound - 1).leading_zeros();
+
+    loop {
+        let n = random_bits(bits);
+
+        if n < bound {
+            return n;
+        }
+    }
+}
+
+// parses integers written as Radix'Digits, with an optional leading
+// minus sign, for radices from 2 to 36. 0'c character codes and
+// everything else are left to the parser.
+fn parse_radix_integer(string: &str) -> Option<Integer> {
+    let (negative, string) = match string.strip_prefix('-') {
+        Some(string) => (true, string),
+        None => (false, string),
+    };
+
+    let quote = string.find('\'')?;
+    let (radix, digits) = (&string[.. quote], &string[quote + 1 ..]);
+
+    if radix.is_empty() || !radix.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let radix = radix.parse::<u32>().ok().filter(|radix| (2 ..= 36).contains(radix))?;
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    let mut n = Integer::from(0);
+
+    for c in digits.chars() {
+        let digit = Integer::from(c.to_digit(radix)?);
+
+        n = n * radix;
+        n += &digit;
+    }
+
+    Some(if negative { -n } else { n })
+}
+
 impl MachineState {
     // a step in Brent's algorithm.
     fn brents_alg_step(&self, brent_st: &mut BrentAlgState) -> Option<CycleSearchResult> {
@@ -268,6 +476,192 @@ impl MachineState {
         }
     }
 
+    // the length of the list at addr, if it's a proper list.
+    pub(crate)
+    fn proper_list_len(&self, addr: Addr) -> Option<usize> {
+        match self.detect_cycles(addr) {
+            CycleSearchResult::EmptyList => Some(0),
+            CycleSearchResult::ProperList(n) => Some(n),
+            CycleSearchResult::CompleteString(_, s) => Some(s.chars().count()),
+            _ => None,
+        }
+    }
+
+    // merges xs and ys, both ordered by the standard order of terms.
+    // an element of xs precedes the equal elements of ys, which are
+    // dropped if dedup is set.
+    fn merge_lists(&self, xs: Vec<Addr>, ys: Vec<Addr>, dedup: bool) -> Vec<Addr> {
+        let mut merged = Vec::with_capacity(xs.len() + ys.len());
+
+        let mut xs = xs.into_iter().peekable();
+        let mut ys = ys.into_iter().peekable();
+
+        loop {
+            let order = match (xs.peek(), ys.peek()) {
+                (Some(x), Some(y)) => self.compare_term_test(x, y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match order {
+                Ordering::Less => merged.extend(xs.next()),
+                Ordering::Greater => merged.extend(ys.next()),
+                Ordering::Equal => {
+                    merged.extend(xs.next());
+
+                    if dedup {
+                        ys.next();
+                    }
+                }
+            }
+        }
+
+        merged
+    }
+
+    // a callable term is an atom or a compound term. a deep check
+    // also requires the arguments of the control constructs (,)/2,
+    // (;)/2 and (->)/2 to be callable or variables, as they must be
+    // for the term to be converted to a goal.
+    fn is_callable(&self, addr: Addr, deep: bool) -> bool {
+        let mut terms = vec![addr];
+        let mut top = true;
+
+        while let Some(addr) = terms.pop() {
+            let addr = self.store(self.deref(addr));
+
+            match addr {
+                Addr::Con(Constant::Atom(..))
+                | Addr::Con(Constant::Char(_))
+                | Addr::Con(Constant::EmptyList)
+                | Addr::Con(Constant::String(..))
+                | Addr::Lis(_)
+                | Addr::PStrLocation(..) => {}
+                Addr::Str(s) => {
+                    if let HeapCellValue::NamedStr(2, ref name, _) = &self.heap[s] {
+                        if deep {
+                            match name.as_str() {
+                                "," | ";" | "->" => {
+                                    terms.push(self.heap[s + 1].as_addr(s + 1));
+                                    terms.push(self.heap[s + 2].as_addr(s + 2));
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                addr if addr.is_ref() && !top => {}
+                _ => return false,
+            }
+
+            top = false;
+        }
+
+        true
+    }
+
+    // the immediate subterms of term as traversed by mapsubterms/3
+    // and foldsubterms/5: the elements of a proper list, the
+    // arguments of any other compound term. None for atomic terms and
+    // variables.
+    fn subterms(&self, term: Addr) -> Option<Vec<Addr>> {
+        match term {
+            Addr::Lis(_) | Addr::PStrLocation(..) | Addr::Con(Constant::String(..))
+                if self.proper_list_len(term.clone()).is_some() =>
+            {
+                let stub = MachineError::functor_stub(clause_name!("$subterms"), 2);
+                self.try_from_list_addr(term, stub).ok()
+            }
+            Addr::Lis(l) => {
+                Some(vec![self.heap[l].as_addr(l), self.heap[l + 1].as_addr(l + 1)])
+            }
+            Addr::Str(s) => match &self.heap[s] {
+                &HeapCellValue::NamedStr(arity, ..) => {
+                    Some((1 .. arity + 1).map(|i| self.heap[s + i].as_addr(s + i)).collect())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // builds the term with the functor of term and the given
+    // subterms, reusing term, or the longest suffix of it if term is
+    // a proper list, wherever the subterms are unchanged.
+    fn rebuild_subterms(&mut self, term: Addr, old: Vec<Addr>, new: Vec<Addr>) -> Addr {
+        let changed = old.iter().zip(new.iter()).rposition(|(a1, a2)| {
+            self.store(self.deref(a1.clone())) != self.store(self.deref(a2.clone()))
+        });
+
+        let k = match changed {
+            Some(k) => k,
+            None => return term,
+        };
+
+        match term {
+            Addr::Str(s) => {
+                let h = self.heap.h();
+
+                self.heap.push(self.heap[s].clone());
+
+                for addr in new {
+                    self.heap.push(HeapCellValue::Addr(addr));
+                }
+
+                Addr::Str(h)
+            }
+            Addr::Lis(_) if self.proper_list_len(term.clone()).is_none() => {
+                let h = self.heap.h();
+
+                for addr in new {
+                    self.heap.push(HeapCellValue::Addr(addr));
+                }
+
+                Addr::Lis(h)
+            }
+            _ => {
+                let mut cursor = term;
+                let mut tail = None;
+
+                // find the tail of term following the last changed
+                // element. partial strings are copied, not shared.
+                for i in 0 .. k + 1 {
+                    match cursor {
+                        Addr::Lis(l) => {
+                            let addr = self.heap[l + 1].as_addr(l + 1);
+
+                            if i == k {
+                                tail = Some(addr);
+                                break;
+                            }
+
+                            cursor = self.store(self.deref(addr));
+                        }
+                        _ => break,
+                    }
+                }
+
+                let (n, tail) = match tail {
+                    Some(tail) => (k + 1, tail),
+                    None => (new.len(), Addr::Con(Constant::EmptyList)),
+                };
+
+                let h = self.heap.h();
+
+                for addr in new.into_iter().take(n) {
+                    let c = self.heap.h();
+
+                    self.heap.push(HeapCellValue::Addr(Addr::Lis(c + 1)));
+                    self.heap.push(HeapCellValue::Addr(addr));
+                }
+
+                self.heap.push(HeapCellValue::Addr(tail));
+                Addr::HeapCell(h)
+            }
+        }
+    }
+
     fn finalize_skip_max_list(&mut self, n: usize, addr: Addr) {
         let target_n = self[temp_v!(1)].clone();
         self.unify(Addr::Con(Constant::Integer(Integer::from(n))), target_n);
@@ -361,6 +755,7 @@ impl MachineState {
         addr: Addr,
         indices: &IndexStore,
         caller: &'static str,
+        arity: usize,
     ) -> Result<Stream, MachineStub>
     {
         Ok(match addr {
@@ -370,7 +765,7 @@ impl MachineState {
                         stream.clone()
                     }
                     None => {
-                        let stub = MachineError::functor_stub(clause_name!(caller), 1);
+                        let stub = MachineError::functor_stub(clause_name!(caller), arity);
                         let addr = Addr::Con(Constant::Atom(atom, op_spec));
 
                         let h = self.heap.h();
@@ -383,10 +778,24 @@ impl MachineState {
                 }
             }
             Addr::Stream(stream) => {
+                if stream.is_closed() {
+                    let stub = MachineError::functor_stub(clause_name!(caller), arity);
+                    let h = self.heap.h();
+
+                    return Err(self.error_form(
+                        MachineError::existence_error(h, ExistenceError::Stream(Addr::Stream(stream))),
+                        stub,
+                    ));
+                }
+
                 stream
             }
+            addr if addr.is_ref() => {
+                let stub = MachineError::functor_stub(clause_name!(caller), arity);
+                return Err(self.error_form(MachineError::instantiation_error(), stub));
+            }
             _ => {
-                let stub = MachineError::functor_stub(clause_name!(caller), 1);
+                let stub = MachineError::functor_stub(clause_name!(caller), arity);
                 
                 return Err(self.error_form(
                     MachineError::domain_error(DomainError::StreamOrAlias, addr),
@@ -396,134 +805,534 @@ impl MachineState {
         })     
     }
 
-    fn read_term(&mut self,
-                 current_input_stream: &mut Stream,
-                 indices: &mut IndexStore)
-                 -> CallResult
-    {
-        match self.read(
-            &mut parsing_stream(current_input_stream.clone()),
-            indices.atom_tbl.clone(),
-            &indices.op_dir,
-        ) {
-            Ok(term_write_result) => {
-                let a1 = self[temp_v!(1)].clone();
-                self.unify(Addr::HeapCell(term_write_result.heap_loc), a1);
-
-                if self.fail {
-                    return Ok(());
-                }
+    // unifies the registers from the first on with values.
+    fn unify_integers(&mut self, values: &[usize]) {
+        for (i, value) in values.iter().enumerate() {
+            let value = Addr::Con(Constant::Integer(Integer::from(*value)));
+            let target = self[RegType::Temp(i + 1)].clone();
 
-                let mut list_of_var_eqs = vec![];
+            self.unify(value, target);
 
-                for (var, binding) in term_write_result.var_dict.into_iter().rev() {
-                    let var_atom = clause_name!(var.to_string(), indices.atom_tbl);
-                    let var_atom = Constant::Atom(var_atom, None);
+            if self.fail {
+                return;
+            }
+        }
+    }
 
-                    let h = self.heap.h();
-                    let spec = fetch_atom_op_spec(clause_name!("="), None, &indices.op_dir);
+    // calls the clauses subsection of a dynamic predicate at p with
+    // the head and body in the first two registers. the subsection is
+    // indexed on the first argument of its heads, which is passed
+    // ahead of them, so that clause/2 and retract/1 given a bound
+    // first argument don't try every clause.
+    fn call_clauses_subsection(&mut self, p: usize) {
+        let head = self.store(self.deref(self[temp_v!(1)].clone()));
+        let body = self[temp_v!(2)].clone();
+
+        let key = match head {
+            Addr::Str(s) => self.heap[s + 1].as_addr(s + 1),
+            _ => head.clone(),
+        };
 
-                    self.heap.push(HeapCellValue::NamedStr(2, clause_name!("="), spec));
-                    self.heap.push(HeapCellValue::Addr(Addr::Con(var_atom)));
-                    self.heap.push(HeapCellValue::Addr(binding));
+        self[temp_v!(1)] = key;
+        self[temp_v!(2)] = head;
+        self[temp_v!(3)] = body;
 
-                    list_of_var_eqs.push(Addr::Str(h));
-                }
+        self.execute_at_index(3, dir_entry!(p));
+    }
 
-                let a2 = self[temp_v!(2)].clone();
-                let list_offset =
-                    Addr::HeapCell(self.heap.to_list(list_of_var_eqs.into_iter()));
+    // true iff the register holds the stream type binary, rather
+    // than text.
+    fn is_binary_type(&self, r: RegType) -> bool {
+        match self.store(self.deref(self[r].clone())) {
+            Addr::Con(Constant::Atom(ref name, _)) => name.as_str() == "binary",
+            _ => false,
+        }
+    }
 
-                Ok(self.unify(list_offset, a2))
-            }
-            Err(err) => {
-                if let ParserError::UnexpectedEOF = err {
-                    std::process::exit(0);
-                }
+    /* text predicates refuse binary streams, and byte predicates
+     * text streams, with the permission errors of 8.13 and 8.14 of the
+     * standard, in which perm is input or output. */
+    fn check_stream_type(
+        &self,
+        stream: &Stream,
+        binary: bool,
+        perm: PermissionError,
+        caller: &'static str,
+        arity: usize,
+    ) -> Result<(), MachineStub>
+    {
+        if stream.is_binary_stream() == binary {
+            return Ok(());
+        }
 
-                // reset the input stream after an input failure.
-                *current_input_stream = readline::input_stream();
+        let stream_type = if binary {
+            "text_stream"
+        } else {
+            "binary_stream"
+        };
 
-                let h = self.heap.h();
-                let syntax_error = MachineError::syntax_error(h, err);
-                let stub = MachineError::functor_stub(clause_name!("read_term"), 2);
+        let stub = MachineError::functor_stub(clause_name!(caller), arity);
+        let err = MachineError::permission_error(perm, stream_type, Addr::Stream(stream.clone()));
 
-                Err(self.error_form(syntax_error, stub))
-            }
-        }
+        Err(self.error_form(err, stub))
     }
 
-    #[inline]
-    fn install_new_block(&mut self, r: RegType) -> usize {
-        self.block = self.b;
+    // the stream or alias in the first register, binary or text, to
+    // be read from if perm is input and written to if it is output.
+    fn checked_stream(
+        &self,
+        indices: &IndexStore,
+        binary: bool,
+        perm: PermissionError,
+        caller: &'static str,
+    ) -> Result<Stream, MachineStub>
+    {
+        let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+        let stream = self.get_stream_or_alias(addr, indices, caller, 2)?;
 
-        let c = Constant::Usize(self.block);
-        let addr = self[r].clone();
+        let permitted = match perm {
+            PermissionError::InputStream => stream.is_input_stream(),
+            _ => stream.is_output_stream(),
+        };
 
-        self.write_constant_to_var(addr, c);
-        self.block
-    }
+        if !permitted {
+            let stub = MachineError::functor_stub(clause_name!(caller), 2);
+            let err = MachineError::permission_error(perm, "stream", Addr::Stream(stream));
 
-    fn copy_findall_solution(&mut self, lh_offset: usize, copy_target: Addr) -> usize {
-        let threshold = self.lifted_heap.h() - lh_offset;
+            return Err(self.error_form(err, stub));
+        }
 
-        let mut copy_ball_term = CopyBallTerm::new(
-            &mut self.stack,
-            &mut self.heap,
-            &mut self.lifted_heap,
-        );
+        self.check_stream_type(&stream, binary, perm, caller, 2)?;
+        Ok(stream)
+    }
 
-        copy_ball_term.push(HeapCellValue::Addr(Addr::Lis(threshold + 1)));
-        copy_ball_term.push(HeapCellValue::Addr(Addr::HeapCell(threshold + 3)));
-        copy_ball_term.push(HeapCellValue::Addr(Addr::HeapCell(threshold + 2)));
+    // prints the term in the first register to output per the
+    // write_term options in the next five, returning None if the
+    // options are unusable.
+    fn print_with_write_options<Outputter: HCValueOutputter>(
+        &mut self,
+        indices: &IndexStore,
+        stub: MachineStub,
+        output: Outputter,
+    ) -> Result<Option<Outputter::Output>, MachineStub> {
+        let addr = self[temp_v!(1)].clone();
 
-        copy_term(copy_ball_term, copy_target, AttrVarPolicy::DeepCopy);
+        let ignore_ops = self.store(self.deref(self[temp_v!(2)].clone()));
+        let numbervars = self.store(self.deref(self[temp_v!(3)].clone()));
+        let quoted = self.store(self.deref(self[temp_v!(4)].clone()));
+        let max_depth = self.store(self.deref(self[temp_v!(6)].clone()));
 
-        threshold + lh_offset + 2
-    }
+        let mut printer = HCPrinter::new(&self, &indices.op_dir, output);
 
-    fn repl_redirect(&mut self, repl_code_ptr: REPLCodePtr) -> CallResult {
-        let p = if self.last_call {
-            self.cp
-        } else {
-            self.p.local() + 1
-        };
+        if let &Addr::Con(Constant::Atom(ref name, ..)) = &ignore_ops {
+            printer.ignore_ops = name.as_str() == "true";
+        }
 
-        Ok(self.p = CodePtr::REPL(repl_code_ptr, p))
-    }
+        if let &Addr::Con(Constant::Atom(ref name, ..)) = &numbervars {
+            printer.numbervars = name.as_str() == "true";
+        }
 
-    fn truncate_if_no_lifted_heap_diff<AddrConstr>(&mut self, addr_constr: AddrConstr)
-    where
-        AddrConstr: Fn(usize) -> Addr,
-    {
-        match self.store(self.deref(self[temp_v!(1)].clone())) {
-            Addr::Con(Constant::Usize(lh_offset)) => {
-                if lh_offset >= self.lifted_heap.h() {
-                    self.lifted_heap.truncate(lh_offset);
-                } else {
-                    let threshold = self.lifted_heap.h() - lh_offset;
-                    self.lifted_heap.push(HeapCellValue::Addr(addr_constr(threshold)));
-                }
+        if let &Addr::Con(Constant::Atom(ref name, ..)) = &quoted {
+            printer.quoted = name.as_str() == "true";
+        }
+
+        if let &Addr::Con(Constant::Integer(ref n)) = &max_depth {
+            if let Some(n) = n.to_usize() {
+                printer.max_depth = n;
+            } else {
+                return Ok(None);
             }
-            _ => self.fail = true,
         }
-    }
 
-    fn get_next_db_ref(&mut self, indices: &IndexStore, db_ref: &DBRef) {
-        match db_ref {
-            &DBRef::NamedPred(ref name, arity, _) => {
-                let key = (name.clone(), arity);
-                let mut iter = indices.code_dir.range(key..).skip(1);
+        // variable_names/1 is unbound if it wasn't among the options,
+        // in which case variables are written by their locations.
+        if self.store(self.deref(self[temp_v!(5)].clone())).is_ref() {
+            return Ok(Some(printer.print(addr).result()));
+        }
 
-                while let Some(((name, arity), idx)) = iter.next() {
-                    if idx.is_undefined() {
-                        self.fail = true;
-                        return;
-                    }
+        printer.number_unnamed_vars = true;
 
-                    if is_builtin_predicate(&name) {
-                        continue;
-                    }
+        match self.try_from_list(temp_v!(5), stub) {
+            Ok(addrs) => {
+                let mut var_names: IndexMap<Addr, String> = IndexMap::new();
+
+                for addr in addrs {
+                    match addr {
+                        Addr::Str(s) => match &self.heap[s] {
+                            &HeapCellValue::NamedStr(2, ref name, _)
+                                if name.as_str() == "=" =>
+                            {
+                                let atom = self.heap[s + 1].as_addr(s + 1);
+                                let var = self.heap[s + 2].as_addr(s + 2);
+
+                                let atom = match self.store(self.deref(atom)) {
+                                    Addr::Con(Constant::Atom(atom, _)) => atom.to_string(),
+                                    Addr::Con(Constant::Char(c)) => c.to_string(),
+                                    _ => unreachable!(),
+                                };
+
+                                let var = self.store(self.deref(var));
+
+                                if var_names.contains_key(&var) {
+                                    continue;
+                                }
+
+                                var_names.insert(var, atom);
+                            }
+                            _ => unreachable!(),
+                        },
+                        _ => unreachable!(),
+                    }
+                }
+
+                printer.var_names = var_names;
+            }
+            Err(err) => return Err(err),
+        }
+
+        Ok(Some(printer.print(addr).result()))
+    }
+
+    // builds the list of the properties of stream reported by
+    // stream_property/2.
+    fn stream_properties(&mut self, mut stream: Stream) -> Addr {
+        let mut properties = vec![];
+
+        if let Some(file_name) = stream.file_name() {
+            let file_name = Addr::Con(Constant::Atom(file_name, None));
+            properties.push(self.unary_property("file_name", file_name));
+        }
+
+        let mode = Addr::Con(Constant::Atom(clause_name!(stream.mode().as_str()), None));
+        properties.push(self.unary_property("mode", mode));
+
+        if stream.is_input_stream() {
+            properties.push(Addr::Con(Constant::Atom(clause_name!("input"), None)));
+        }
+
+        if stream.is_output_stream() {
+            properties.push(Addr::Con(Constant::Atom(clause_name!("output"), None)));
+        }
+
+        if let Some(alias) = stream.options.alias.clone() {
+            properties.push(self.unary_property("alias", Addr::Con(Constant::Atom(alias, None))));
+        }
+
+        let position = Addr::Con(Constant::Integer(Integer::from(stream.position())));
+        properties.push(self.unary_property("position", position));
+
+        if stream.is_input_stream() {
+            let end_of_stream = stream.end_of_stream().unwrap_or(EndOfStream::Not);
+            let end_of_stream = Addr::Con(Constant::Atom(clause_name!(end_of_stream.as_str()), None));
+
+            properties.push(self.unary_property("end_of_stream", end_of_stream));
+        }
+
+        let eof_action = match stream.options.eof_action {
+            EOFAction::EOFCode => "eof_code",
+            EOFAction::Error => "error",
+            EOFAction::Reset => "reset",
+        };
+
+        let eof_action = Addr::Con(Constant::Atom(clause_name!(eof_action), None));
+        properties.push(self.unary_property("eof_action", eof_action));
+
+        let reposition = if stream.options.reposition { "true" } else { "false" };
+        let reposition = Addr::Con(Constant::Atom(clause_name!(reposition), None));
+        properties.push(self.unary_property("reposition", reposition));
+
+        let stream_type = Addr::Con(Constant::Atom(clause_name!(stream.options.stream_type.as_str()), None));
+        properties.push(self.unary_property("type", stream_type));
+
+        Addr::HeapCell(self.heap.to_list(properties.into_iter()))
+    }
+
+    fn unary_property(&mut self, name: &'static str, arg: Addr) -> Addr {
+        let h = self.heap.h();
+
+        self.heap.push(HeapCellValue::NamedStr(1, clause_name!(name), None));
+        self.heap.push(HeapCellValue::Addr(arg));
+
+        Addr::Str(h)
+    }
+
+    // builds the list of Name = Var equations of a read term.
+    fn var_eqs_list(&mut self, var_dict: HeapVarDict, indices: &IndexStore) -> Addr {
+        let mut list_of_var_eqs = self.scratch.take_addrs();
+
+        for (var, binding) in var_dict.into_iter().rev() {
+            let var_atom = clause_name!(var.to_string(), indices.atom_tbl);
+            let var_atom = Constant::Atom(var_atom, None);
+
+            let h = self.heap.h();
+            let spec = fetch_atom_op_spec(clause_name!("="), None, &indices.op_dir);
+
+            self.heap.push(HeapCellValue::NamedStr(2, clause_name!("="), spec));
+            self.heap.push(HeapCellValue::Addr(Addr::Con(var_atom)));
+            self.heap.push(HeapCellValue::Addr(binding));
+
+            list_of_var_eqs.push(Addr::Str(h));
+        }
+
+        let list = Addr::HeapCell(self.heap.to_list(list_of_var_eqs.drain(..)));
+        self.scratch.give_addrs(list_of_var_eqs);
+
+        list
+    }
+
+    fn toplevel_prompt_kind(&self, addr: Addr) -> readline::Prompt {
+        match self.store(self.deref(addr)) {
+            Addr::Con(Constant::Atom(ref kind, _)) if kind.as_str() == "query" => {
+                readline::Prompt::Query
+            }
+            Addr::Con(Constant::Atom(ref kind, _)) if kind.as_str() == "continuation" => {
+                readline::Prompt::Continuation
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // reads a term from current_input_stream, or another input stream,
+    // to the register r, and its variable equations to the next,
+    // returning the lines of the stream, counted from 1, on which it
+    // begins and ends. errors are left to the caller, which raises
+    // them as its own with read_term_error.
+    fn read_term(&mut self,
+                 current_input_stream: &mut Stream,
+                 indices: &mut IndexStore,
+                 double_quotes: Option<DoubleQuotes>,
+                 r: usize)
+                 -> Result<(usize, usize), ParserError>
+    {
+        // the double_quotes read option takes precedence over that of
+        // the stream, which takes precedence over the flag.
+        let double_quotes = double_quotes
+            .or(current_input_stream.double_quotes())
+            .unwrap_or(self.flags.double_quotes);
+
+        let lines_read = current_input_stream.lines() as usize;
+
+        let (term_write_result, (start_line, end_line)) = self.read(
+            &mut parsing_stream(current_input_stream.clone().term_source()),
+            indices.atom_tbl.clone(),
+            &indices.op_dir,
+            &mut indices.string_tbl,
+            double_quotes,
+        )?;
+
+        let term = self[temp_v!(r)].clone();
+        self.unify(Addr::HeapCell(term_write_result.heap_loc), term);
+
+        let lines = (lines_read + start_line + 1, lines_read + end_line + 1);
+
+        if self.fail {
+            return Ok(lines);
+        }
+
+        let list_offset = self.var_eqs_list(term_write_result.var_dict, indices);
+        let var_eqs = self[temp_v!(r + 1)].clone();
+
+        self.unify(list_offset, var_eqs);
+        Ok(lines)
+    }
+
+    // the error of read_term/arity for a read of current_input_stream
+    // that failed with err.
+    fn read_term_error(&mut self,
+                       current_input_stream: &mut Stream,
+                       err: ParserError,
+                       arity: usize)
+                       -> MachineStub
+    {
+        let stub = MachineError::functor_stub(clause_name!("read_term"), arity);
+
+        if let ParserError::IO(ref e) = err {
+            if e.kind() == ErrorKind::TimedOut {
+                let stream = Addr::Stream(current_input_stream.clone());
+                let err = MachineError::timeout_error("read", stream);
+
+                return self.error_form(err, stub);
+            }
+        }
+
+        // reset the input stream after an input failure.
+        *current_input_stream = readline::input_stream();
+
+        let h = self.heap.h();
+        let syntax_error = MachineError::syntax_error(h, err);
+
+        self.error_form(syntax_error, stub)
+    }
+
+    // the value of a double_quotes option, if addr is one.
+    fn double_quotes_value(&self, addr: Addr) -> Option<DoubleQuotes> {
+        match self.store(self.deref(addr)) {
+            Addr::Con(Constant::Atom(ref name, _)) => match name.as_str() {
+                "atom" => Some(DoubleQuotes::Atom),
+                "chars" => Some(DoubleQuotes::Chars),
+                "codes" => Some(DoubleQuotes::Codes),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn install_new_block(&mut self, r: RegType) -> usize {
+        self.block = self.b;
+
+        let c = Constant::Usize(self.block);
+        let addr = self[r].clone();
+
+        self.write_constant_to_var(addr, c);
+        self.block
+    }
+
+    /* copies a findall solution to the lifted heap, behind a header
+     * of four cells: a list cell, its head and tail, and the
+     * relocation of the solution. the header's addresses are relative
+     * to lh_offset, but the solution is copied as though the lifted
+     * heap followed the heap, and is only relocated as it is moved
+     * back to the heap by lifted_heap_to_heap, sparing a pass over
+     * it here. returns the index of the tail cell. */
+    fn copy_findall_solution(&mut self, lh_offset: usize, copy_target: Addr) -> usize {
+        let threshold = self.lifted_heap.h() - lh_offset;
+        let relocation = self.heap.h() + lh_offset;
+
+        let mut copy_ball_term = CopyBallTerm::new(
+            &mut self.stack,
+            &mut self.heap,
+            &mut self.lifted_heap,
+        );
+
+        copy_ball_term.push(HeapCellValue::Addr(Addr::Lis(threshold + 1)));
+        copy_ball_term.push(HeapCellValue::Addr(Addr::HeapCell(threshold + 4)));
+        copy_ball_term.push(HeapCellValue::Addr(Addr::HeapCell(threshold + 2)));
+        copy_ball_term.push(HeapCellValue::Addr(Addr::Con(Constant::Usize(relocation))));
+
+        let copy_trail = self.scratch.take_copy_trail();
+        let copy_trail = copy_term_with_trail(
+            copy_ball_term,
+            copy_target,
+            AttrVarPolicy::DeepCopy,
+            copy_trail,
+        );
+
+        self.scratch.give_copy_trail(copy_trail);
+
+        threshold + lh_offset + 2
+    }
+
+    /* moves the solutions of the lifted heap from lh_offset to the
+     * top of the heap, relocating each by the relocation stored in
+     * its header, and truncates the lifted heap to lh_offset. returns
+     * the heap index of the last cell moved. */
+    fn lifted_heap_to_heap(&mut self, lh_offset: usize) -> usize {
+        let h = self.heap.h();
+        let lh_h = self.lifted_heap.h();
+
+        let mut last_index = h;
+        let mut next_header = lh_offset;
+        let mut relocation = 0;
+
+        let mut i = lh_offset;
+
+        while i < lh_h {
+            last_index = self.heap.h();
+
+            // all but the last cell, which ends the list of solutions,
+            // begin a header.
+            if i == next_header && i + 1 < lh_h {
+                next_header = match &self.lifted_heap[i + 2] {
+                    HeapCellValue::Addr(Addr::HeapCell(tail)) => tail + lh_offset,
+                    _ => unreachable!(),
+                };
+
+                relocation = match &self.lifted_heap[i + 3] {
+                    HeapCellValue::Addr(Addr::Con(Constant::Usize(relocation))) => *relocation,
+                    _ => unreachable!(),
+                };
+
+                for j in i .. i + 4 {
+                    let value = match &self.lifted_heap[j] {
+                        HeapCellValue::Addr(ref addr) => HeapCellValue::Addr(addr.clone() + h),
+                        value => value.clone(),
+                    };
+
+                    self.heap.push(value);
+                }
+
+                last_index = self.heap.h() - 1;
+                i += 4;
+
+                continue;
+            }
+
+            let value = match &self.lifted_heap[i] {
+                HeapCellValue::Addr(ref addr) if i < next_header => {
+                    HeapCellValue::Addr(addr.clone() - relocation + h)
+                }
+                HeapCellValue::Addr(ref addr) => {
+                    HeapCellValue::Addr(addr.clone() + h)
+                }
+                HeapCellValue::PartialString(ref pstr) => {
+                    HeapCellValue::PartialString(pstr.clone())
+                }
+                value => {
+                    value.clone()
+                }
+            };
+
+            self.heap.push(value);
+            i += 1;
+        }
+
+        self.lifted_heap.truncate(lh_offset);
+        last_index
+    }
+
+    fn repl_redirect(&mut self, repl_code_ptr: REPLCodePtr) -> CallResult {
+        let p = if self.last_call {
+            self.cp
+        } else {
+            self.p.local() + 1
+        };
+
+        Ok(self.p = CodePtr::REPL(repl_code_ptr, p))
+    }
+
+    fn truncate_if_no_lifted_heap_diff<AddrConstr>(&mut self, addr_constr: AddrConstr)
+    where
+        AddrConstr: Fn(usize) -> Addr,
+    {
+        match self.store(self.deref(self[temp_v!(1)].clone())) {
+            Addr::Con(Constant::Usize(lh_offset)) => {
+                if lh_offset >= self.lifted_heap.h() {
+                    self.lifted_heap.truncate(lh_offset);
+                } else {
+                    let threshold = self.lifted_heap.h() - lh_offset;
+                    self.lifted_heap.push(HeapCellValue::Addr(addr_constr(threshold)));
+                }
+            }
+            _ => self.fail = true,
+        }
+    }
+
+    fn get_next_db_ref(&mut self, indices: &IndexStore, db_ref: &DBRef) {
+        match db_ref {
+            &DBRef::NamedPred(ref name, arity, _) => {
+                let key = (name.clone(), arity);
+                let mut iter = indices.code_dir.range(key..).skip(1);
+
+                while let Some(((name, arity), idx)) = iter.next() {
+                    if idx.is_undefined() {
+                        self.fail = true;
+                        return;
+                    }
+
+                    if is_builtin_predicate(&name) {
+                        continue;
+                    }
 
                     let a2 = self[temp_v!(2)].clone();
 
@@ -571,6 +1380,46 @@ impl MachineState {
         }
     }
 
+    // the text of a string or of a partial string ending in [], or a
+    // list of characters, without copying it to the atom table.
+    fn string_text(&self, addr: Addr) -> Option<String> {
+        let mut text = String::new();
+        let mut addr = addr;
+
+        loop {
+            match self.store(self.deref(addr)) {
+                Addr::Con(Constant::String(n, ref s)) if !self.flags.double_quotes.is_atom() => {
+                    text += &s[n ..];
+                    return Some(text);
+                }
+                Addr::PStrLocation(h, n) => {
+                    if let HeapCellValue::PartialString(ref pstr) = &self.heap[h] {
+                        text += &pstr.block_as_str()[n ..];
+                    }
+
+                    addr = self.heap[h + 1].as_addr(h + 1);
+                }
+                Addr::Lis(l) => {
+                    match self.store(self.deref(self.heap[l].as_addr(l))) {
+                        Addr::Con(Constant::Char(c)) => text.push(c),
+                        Addr::Con(Constant::Atom(ref name, _)) if name.as_str().chars().count() == 1 => {
+                            text += name.as_str();
+                        }
+                        _ => return None,
+                    }
+
+                    addr = Addr::HeapCell(l + 1);
+                }
+                Addr::Con(Constant::EmptyList) => {
+                    return Some(text);
+                }
+                _ => {
+                    return None;
+                }
+            }
+        }
+    }
+
     fn int_to_char_code(
         &mut self,
         n: &Integer,
@@ -588,6 +1437,141 @@ impl MachineState {
         }
     }
 
+    // reads Name/Arity or Module:Name/Arity, as validated by
+    // sandboxed_call/2.
+    fn sandboxed_predicate(&self, addr: Addr) -> Option<SandboxedPredicate> {
+        let s = match self.store(self.deref(addr)) {
+            Addr::Str(s) => s,
+            _ => return None,
+        };
+
+        match self.heap[s].clone() {
+            HeapCellValue::NamedStr(2, ref name, _) if name.as_str() == ":" => {
+                let module = match self.store(self.deref(Addr::HeapCell(s + 1))) {
+                    Addr::Con(Constant::Atom(module, _)) => module,
+                    _ => return None,
+                };
+
+                let (_, name, arity) = self.sandboxed_predicate(Addr::HeapCell(s + 2))?;
+                Some((Some(module), name, arity))
+            }
+            HeapCellValue::NamedStr(2, ref name, _) if name.as_str() == "/" => {
+                let name = match self.store(self.deref(Addr::HeapCell(s + 1))) {
+                    Addr::Con(Constant::Atom(name, _)) => name,
+                    _ => return None,
+                };
+
+                let arity = match self.store(self.deref(Addr::HeapCell(s + 2))) {
+                    Addr::Con(Constant::Integer(n)) => n.to_usize()?,
+                    _ => return None,
+                };
+
+                Some((None, name, arity))
+            }
+            _ => None,
+        }
+    }
+
+    fn try_code_list(
+        &mut self,
+        addrs: Vec<Addr>,
+        stub: MachineStub,
+    ) -> Result<String, MachineStub> {
+        let mut chars = String::new();
+
+        for addr in addrs {
+            let c = match self.store(self.deref(addr)) {
+                Addr::Con(Constant::CharCode(c)) => c,
+                Addr::Con(Constant::Integer(n)) => match n.to_u32() {
+                    Some(c) => c,
+                    None => {
+                        let err = MachineError::representation_error(RepFlag::CharacterCode);
+                        return Err(self.error_form(err, stub));
+                    }
+                },
+                addr if addr.is_ref() => {
+                    let err = MachineError::instantiation_error();
+                    return Err(self.error_form(err, stub));
+                }
+                addr => {
+                    let err = MachineError::type_error(ValidType::Integer, addr);
+                    return Err(self.error_form(err, stub));
+                }
+            };
+
+            match std::char::from_u32(c) {
+                Some(c) => chars.push(c),
+                None => {
+                    let err = MachineError::representation_error(RepFlag::CharacterCode);
+                    return Err(self.error_form(err, stub));
+                }
+            }
+        }
+
+        Ok(chars)
+    }
+
+    // reads the byte list in r and decodes it as UTF-8.
+    fn try_utf8_byte_list(&mut self, r: RegType, stub: MachineStub) -> Result<String, MachineStub> {
+        let addrs = self.try_from_list(r, stub.clone())?;
+        let mut bytes = Vec::with_capacity(addrs.len());
+
+        for addr in addrs {
+            match self.store(self.deref(addr)) {
+                Addr::Con(Constant::Integer(n)) if n.to_u8().is_some() => {
+                    bytes.push(n.to_u8().unwrap());
+                }
+                Addr::Con(Constant::CharCode(c)) if c < 256 => {
+                    bytes.push(c as u8);
+                }
+                addr if addr.is_ref() => {
+                    let err = MachineError::instantiation_error();
+                    return Err(self.error_form(err, stub));
+                }
+                addr => {
+                    let err = MachineError::type_error(ValidType::Byte, addr);
+                    return Err(self.error_form(err, stub));
+                }
+            }
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(string) => Ok(string),
+            Err(_) => {
+                let bytes = self[r].clone();
+                let err = MachineError::domain_error(DomainError::Utf8Bytes, bytes);
+                Err(self.error_form(err, stub))
+            }
+        }
+    }
+
+    fn unify_utf8_bytes(&mut self, string: String) {
+        let iter = string
+            .into_bytes()
+            .into_iter()
+            .map(|b| Addr::Con(Constant::Integer(Integer::from(b))));
+
+        let bytes = Addr::HeapCell(self.heap.to_list(iter));
+        let a2 = self[temp_v!(2)].clone();
+
+        self.unify(a2, bytes);
+    }
+
+    // the number in register 1 written in the radix in register 2,
+    // which is 10 unless the number is an integer.
+    fn number_to_string(&self) -> String {
+        let radix = match self.store(self.deref(self[temp_v!(2)].clone())) {
+            Addr::Con(Constant::Integer(radix)) => radix.to_u32().unwrap(),
+            _ => unreachable!(),
+        };
+
+        match self.store(self.deref(self[temp_v!(1)].clone())) {
+            Addr::Con(Constant::Float(OrderedFloat(n))) => format!("{0:<20?}", n),
+            Addr::Con(Constant::Integer(n)) => integer_to_radix_string(&n, radix),
+            _ => unreachable!(),
+        }
+    }
+
     fn parse_number_from_string(
         &mut self,
         mut string: String,
@@ -596,6 +1580,11 @@ impl MachineState {
     ) -> CallResult {
         let nx = self[temp_v!(2)].clone();
 
+        if let Some(n) = parse_radix_integer(&string) {
+            self.unify(nx, Addr::Con(Constant::Integer(n)));
+            return Ok(());
+        }
+
         if let Some(c) = string.chars().last() {
             if layout_char!(c) {
                 let (line_num, col_num) = string.chars().fold((0, 0), |(line_num, col_num), c| {
@@ -714,10 +1703,23 @@ impl MachineState {
         indices: &mut IndexStore,
         call_policy: &mut Box<dyn CallPolicy>,
         cut_policy: &mut Box<dyn CutPolicy>,
+        sandbox_policy: &mut SandboxPolicy,
         current_input_stream: &mut Stream,
         current_output_stream: &mut Stream,
     ) -> CallResult {
         match ct {
+            &SystemClauseType::Append => {
+                // the elements of the proper list in the first
+                // argument are copied in front of the second, which
+                // is shared, in one pass over the heap.
+                let stub = MachineError::functor_stub(clause_name!("append"), 3);
+                let prefix = self.try_from_list(temp_v!(1), stub)?;
+
+                let tail = self[temp_v!(2)].clone();
+                let list = Addr::HeapCell(self.heap.to_list_with_tail(prefix.into_iter(), tail));
+
+                self.unify(list, self[temp_v!(3)].clone());
+            }
             &SystemClauseType::AbolishClause => {
                 let p = self.cp;
                 let trans_type = DynamicTransactionType::Abolish;
@@ -765,6 +1767,38 @@ impl MachineState {
                 self.p = CodePtr::DynamicTransaction(trans_type, p);
                 return Ok(());
             }
+            &SystemClauseType::BulkAssertDynamicPredicate => {
+                let p = self.cp;
+                let trans_type = DynamicTransactionType::BulkAssert;
+
+                self.p = CodePtr::DynamicTransaction(trans_type, p);
+                return Ok(());
+            }
+            &SystemClauseType::CloseStream => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let stream = self.get_stream_or_alias(addr, indices, "close", 1)?;
+
+                // the standard streams are never closed.
+                if stream.is_stdin() || stream.is_stdout() {
+                    return return_from_clause!(self.last_call, self);
+                }
+
+                if let Some(ref alias) = stream.options.alias {
+                    indices.stream_aliases.shift_remove(alias);
+                }
+
+                indices.open_streams.retain(|open_stream| open_stream.as_ptr() != stream.as_ptr());
+
+                if *current_input_stream == stream {
+                    *current_input_stream = readline::input_stream();
+                }
+
+                if *current_output_stream == stream {
+                    *current_output_stream = Stream::stdout();
+                }
+
+                stream.close();
+            }
             &SystemClauseType::CurrentInput => {
                 let addr = self.store(self.deref(self[temp_v!(1)].clone()));
                 let stream = current_input_stream.clone();
@@ -970,18 +2004,76 @@ impl MachineState {
             &SystemClauseType::AtomLength => {
                 let a1 = self[temp_v!(1)].clone();
 
-                let atom = match self.store(self.deref(a1)) {
-                    Addr::Con(Constant::Atom(name, _)) => name,
-                    Addr::Con(Constant::EmptyList) => clause_name!("[]"),
-                    Addr::Con(Constant::Char(c)) => clause_name!(c.to_string(), indices.atom_tbl),
-                    _ => unreachable!(),
+                let len = match self.store(self.deref(a1)) {
+                    Addr::Con(Constant::Atom(name, _)) => name.as_str().chars().count(),
+                    Addr::Con(Constant::EmptyList) => 2,
+                    Addr::Con(Constant::Char(_)) => 1,
+                    addr => match self.string_text(addr) {
+                        Some(text) => text.chars().count(),
+                        None => {
+                            self.fail = true;
+                            return Ok(());
+                        }
+                    },
                 };
 
-                let len = Integer::from(atom.as_str().chars().count());
+                let len = Integer::from(len);
                 let a2 = self[temp_v!(2)].clone();
 
                 self.unify(a2, Addr::Con(Constant::Integer(len)));
             }
+            &SystemClauseType::AutoloadImport => {
+                let mut atoms = (1 .. 4).map(|i| {
+                    match self.store(self.deref(self[temp_v!(i)].clone())) {
+                        Addr::Con(Constant::Atom(name, _)) => name,
+                        _ => unreachable!(),
+                    }
+                });
+
+                let module = atoms.next().unwrap();
+                let library = atoms.next().unwrap();
+                let name = atoms.next().unwrap();
+
+                let arity = match self.store(self.deref(self[temp_v!(4)].clone())) {
+                    Addr::Con(Constant::Integer(n)) => n.to_usize().unwrap(),
+                    _ => unreachable!(),
+                };
+
+                let key = (name.clone(), arity);
+
+                let idx = match indices.modules.get(&library) {
+                    Some(library) => match library.code_dir.get(&key) {
+                        Some(idx) => idx.clone(),
+                        None => {
+                            self.fail = true;
+                            return Ok(());
+                        }
+                    },
+                    None => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                };
+
+                // import name/arity into the module of the call site,
+                // updating its undefined code index in place so the
+                // compiled call sites see the import.
+                if module.as_str() == "user" {
+                    indices.insert_dir_entry(name, arity, idx);
+                } else if let Some(module) = indices.modules.get_mut(&module) {
+                    match module.code_dir.get(&key).cloned() {
+                        Some(code_idx) => {
+                            if code_idx.is_undefined() {
+                                let (p, module_name) = idx.0.borrow().clone();
+                                set_code_index!(code_idx, p, module_name);
+                            }
+                        }
+                        None => {
+                            module.code_dir.insert(key, idx);
+                        }
+                    }
+                }
+            }
             &SystemClauseType::CallAttributeGoals => {
                 let p = self.attr_var_init.project_attrs_loc;
 
@@ -1026,6 +2118,26 @@ impl MachineState {
                     },
                 }
             }
+            &SystemClauseType::CharsUtf8Bytes => {
+                let stub = MachineError::functor_stub(clause_name!("chars_utf8bytes"), 2);
+                let bytes = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                if bytes.is_ref() {
+                    let addrs = self.try_from_list(temp_v!(1), stub.clone())?;
+
+                    match self.try_char_list(addrs) {
+                        Ok(string) => self.unify_utf8_bytes(string),
+                        Err(err) => return Err(self.error_form(err, stub)),
+                    }
+                } else {
+                    let string = self.try_utf8_byte_list(temp_v!(2), stub)?;
+                    let iter = string.chars().map(|c| Addr::Con(Constant::Char(c)));
+                    let chars = Addr::HeapCell(self.heap.to_list(iter));
+                    let a1 = self[temp_v!(1)].clone();
+
+                    self.unify(a1, chars);
+                }
+            }
             &SystemClauseType::CreatePartialString => {
                 let atom = match self.store(self.deref(self[temp_v!(1)].clone())) {
                     Addr::Con(Constant::Atom(atom, _)) => {
@@ -1060,41 +2172,306 @@ impl MachineState {
                     self.unify(self[temp_v!(3)].clone(), pstr_tail);
                 }
             }
+            &SystemClauseType::IsCallable => {
+                let deep = match self.store(self.deref(self[temp_v!(2)].clone())) {
+                    Addr::Con(Constant::Atom(ref atom, _)) => atom.as_str() == "deep",
+                    _ => unreachable!(),
+                };
+
+                let term = self[temp_v!(1)].clone();
+                self.fail = !self.is_callable(term, deep);
+            }
+            &SystemClauseType::IsList => {
+                // Brent's algorithm stops at cycles, which are not lists.
+                self.fail = match self.detect_cycles(self[temp_v!(1)].clone()) {
+                    CycleSearchResult::EmptyList
+                    | CycleSearchResult::ProperList(_)
+                    | CycleSearchResult::CompleteString(..) => false,
+                    _ => true,
+                };
+            }
             &SystemClauseType::IsPartialString => {
                 let pstr = self.store(self.deref(self[temp_v!(1)].clone()));
 
-                match pstr {
-                    Addr::PStrLocation(..) => {
+                match pstr {
+                    Addr::PStrLocation(..) => {
+                    }
+                    _ => {
+                        self.fail = true;
+                    }
+                }
+            }
+            &SystemClauseType::IsString => {
+                match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::String(..)) if !self.flags.double_quotes.is_atom() => {
+                    }
+                    Addr::PStrLocation(..) => {
+                    }
+                    _ => {
+                        self.fail = true;
+                    }
+                }
+            }
+            &SystemClauseType::PartialStringTail => {
+                let pstr = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                match pstr {
+                    Addr::PStrLocation(h, _) => {
+                        let tail = self.heap[h + 1].as_addr(h + 1);
+                        let target = self[temp_v!(2)].clone();
+                        
+                        self.unify(tail, target);
+                    }
+                    _ => {
+                        unreachable!()
+                    }
+                }
+            }
+            &SystemClauseType::HostCall => {
+                let request = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                let name = match &request {
+                    Addr::Con(Constant::Atom(ref name, _)) => name.clone(),
+                    Addr::Con(Constant::Char(c)) => clause_name!(c.to_string(), indices.atom_tbl),
+                    Addr::Str(s) => match &self.heap[*s] {
+                        HeapCellValue::NamedStr(_, ref name, _) => name.clone(),
+                        _ => unreachable!(),
+                    },
+                    _ => unreachable!(),
+                };
+
+                let result = match indices.host_callbacks.get_mut(&name) {
+                    Some(callback) => callback(TermView::new(self, &request)),
+                    None => {
+                        let stub = MachineError::functor_stub(clause_name!("host_call"), 2);
+                        let h = self.heap.h();
+                        let err = MachineError::existence_error(
+                            h,
+                            ExistenceError::HostCallback(Addr::Con(Constant::Atom(name, None))),
+                        );
+
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                match result {
+                    Ok(Some(reply)) => {
+                        let h = write_term_to_heap(&reply, self).heap_loc;
+                        let a2 = self[temp_v!(2)].clone();
+
+                        self.unify(Addr::HeapCell(h), a2);
+                    }
+                    Ok(None) => {
+                        self.fail = true;
+                    }
+                    Err(ball) => {
+                        let h = write_term_to_heap(&ball, self).heap_loc;
+                        return Err(vec![HeapCellValue::Addr(Addr::HeapCell(h))]);
+                    }
+                }
+            }
+            &SystemClauseType::PipeClose => {
+                let alias = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(alias, _)) => alias,
+                    _ => unreachable!(),
+                };
+
+                self.fail = indices.term_pipes.shift_remove(&alias).is_none();
+            }
+            &SystemClauseType::PipeCreate => {
+                let send_alias = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(alias, _)) => alias,
+                    _ => unreachable!(),
+                };
+
+                let receive_alias = match self.store(self.deref(self[temp_v!(2)].clone())) {
+                    Addr::Con(Constant::Atom(alias, _)) => alias,
+                    _ => unreachable!(),
+                };
+
+                let capacity = self.store(self.deref(self[temp_v!(3)].clone()));
+
+                let capacity = match &capacity {
+                    Addr::Con(Constant::Integer(n)) => match n.to_usize() {
+                        Some(n) if 0 < n && n <= MAX_TERM_PIPE_CAPACITY => n,
+                        _ => {
+                            let stub = MachineError::functor_stub(clause_name!("pipe_create"), 3);
+                            let err = MachineError::domain_error(DomainError::PipeCapacity, capacity.clone());
+
+                            return Err(self.error_form(err, stub));
+                        }
+                    },
+                    _ => unreachable!(),
+                };
+
+                let attr_var_policy = match self.store(self.deref(self[temp_v!(4)].clone())) {
+                    Addr::Con(Constant::Atom(ref name, _)) if name.as_str() == "copy" => {
+                        AttrVarPolicy::DeepCopy
+                    }
+                    _ => AttrVarPolicy::StripAttributes,
+                };
+
+                for alias in &[&send_alias, &receive_alias] {
+                    if indices.term_pipes.contains_key(*alias) {
+                        let stub = MachineError::functor_stub(clause_name!("pipe_create"), 3);
+                        let err = MachineError::permission_error(
+                            PermissionError::Create,
+                            "term_pipe",
+                            Addr::Con(Constant::Atom((*alias).clone(), None)),
+                        );
+
+                        return Err(self.error_form(err, stub));
+                    }
+                }
+
+                let (sender, receiver) = term_pipe(capacity, attr_var_policy);
+
+                indices.term_pipes.insert(send_alias, TermPipeEnd::Sender(sender));
+                indices.term_pipes.insert(receive_alias, TermPipeEnd::Receiver(receiver));
+            }
+            &SystemClauseType::PipeReceive => {
+                let alias = self.store(self.deref(self[temp_v!(1)].clone()));
+                let block = self.store(self.deref(self[temp_v!(3)].clone()));
+                let block = block == Addr::Con(atom!("true"));
+
+                let stub = MachineError::functor_stub(clause_name!("pipe_receive"), 2);
+
+                let result = match &alias {
+                    Addr::Con(Constant::Atom(ref name, _)) => {
+                        match indices.term_pipes.get(name) {
+                            Some(TermPipeEnd::Receiver(receiver)) => {
+                                receiver.receive(block && !receiver.is_local(&indices.term_pipes))
+                            }
+                            Some(TermPipeEnd::Sender(_)) => {
+                                let err = MachineError::permission_error(
+                                    PermissionError::InputStream,
+                                    "term_pipe",
+                                    alias,
+                                );
+
+                                return Err(self.error_form(err, stub));
+                            }
+                            None => {
+                                let h = self.heap.h();
+                                let err = MachineError::existence_error(
+                                    h,
+                                    ExistenceError::TermPipe(alias),
+                                );
+
+                                return Err(self.error_form(err, stub));
+                            }
+                        }
+                    }
+                    _ => unreachable!(),
+                };
+
+                match result {
+                    Ok(bytes) => {
+                        // every term sent down a pipe is written whole,
+                        // so an empty message is a truncated one.
+                        let term = self
+                            .fast_read_term(&mut Cursor::new(bytes), indices)
+                            .and_then(|h| h.ok_or(FastTermError::UnexpectedEOF));
+
+                        match term {
+                            Ok(h) => {
+                                let a2 = self[temp_v!(2)].clone();
+                                self.unify(Addr::HeapCell(h), a2);
+                            }
+                            Err(err) => {
+                                let err = MachineError::fast_term_error(err, alias);
+                                return Err(self.error_form(err, stub));
+                            }
+                        }
                     }
-                    _ => {
+                    Err(TermPipeError::Disconnected) => {
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(Addr::Con(atom!("end_of_file")), a2);
+                    }
+                    Err(TermPipeError::Empty) if block => {
+                        // the sending end is held by this machine, so
+                        // the pipe will stay empty.
+                        return Err(self.resource_error(ResourceError::TermPipe));
+                    }
+                    Err(_) => {
                         self.fail = true;
                     }
                 }
             }
-            &SystemClauseType::PartialStringTail => {
-                let pstr = self.store(self.deref(self[temp_v!(1)].clone()));
+            &SystemClauseType::PipeSend => {
+                let alias = self.store(self.deref(self[temp_v!(1)].clone()));
+                let block = self.store(self.deref(self[temp_v!(3)].clone()));
+                let block = block == Addr::Con(atom!("true"));
 
-                match pstr {
-                    Addr::PStrLocation(h, _) => {
-                        let tail = self.heap[h + 1].as_addr(h + 1);
-                        let target = self[temp_v!(2)].clone();
-                        
-                        self.unify(tail, target);
+                let stub = MachineError::functor_stub(clause_name!("pipe_send"), 2);
+
+                let sender = match &alias {
+                    Addr::Con(Constant::Atom(ref name, _)) => {
+                        match indices.term_pipes.get(name) {
+                            Some(TermPipeEnd::Sender(sender)) => {
+                                sender
+                            }
+                            Some(TermPipeEnd::Receiver(_)) => {
+                                let err = MachineError::permission_error(
+                                    PermissionError::OutputStream,
+                                    "term_pipe",
+                                    alias,
+                                );
+
+                                return Err(self.error_form(err, stub));
+                            }
+                            None => {
+                                let h = self.heap.h();
+                                let err = MachineError::existence_error(
+                                    h,
+                                    ExistenceError::TermPipe(alias),
+                                );
+
+                                return Err(self.error_form(err, stub));
+                            }
+                        }
                     }
-                    _ => {
-                        unreachable!()
+                    _ => unreachable!(),
+                };
+
+                let bytes = match self.fast_write_term(
+                    self[temp_v!(2)].clone(),
+                    sender.attr_var_policy,
+                    false,
+                    None,
+                ) {
+                    Ok(bytes) => bytes,
+                    Err(culprit) => {
+                        let err = MachineError::domain_error(DomainError::Serializable, culprit);
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                match sender.send(bytes, block && !sender.is_local(&indices.term_pipes)) {
+                    Ok(()) => {
+                    }
+                    Err(TermPipeError::Disconnected) => {
+                        let h = self.heap.h();
+                        let err = MachineError::existence_error(
+                            h,
+                            ExistenceError::TermPipe(alias),
+                        );
+
+                        return Err(self.error_form(err, stub));
+                    }
+                    Err(TermPipeError::Full) if block => {
+                        // the receiving end is held by this machine,
+                        // so the pipe will stay full.
+                        return Err(self.resource_error(ResourceError::TermPipe));
+                    }
+                    Err(_) => {
+                        self.fail = true;
                     }
                 }
             }
             &SystemClauseType::NumberToChars => {
-                let n = self[temp_v!(1)].clone();
-                let chs = self[temp_v!(2)].clone();
-
-                let string = match self.store(self.deref(n)) {
-                    Addr::Con(Constant::Float(OrderedFloat(n))) => format!("{0:<20?}", n),
-                    Addr::Con(Constant::Integer(n)) => n.to_string(),
-                    _ => unreachable!(),
-                };
+                let chs = self[temp_v!(3)].clone();
+                let string = self.number_to_string();
 
                 let chars = string.trim().chars().map(|c| Addr::Con(Constant::Char(c)));
                 let char_list = Addr::HeapCell(self.heap.to_list(chars));
@@ -1102,14 +2479,8 @@ impl MachineState {
                 self.unify(char_list, chs);
             }
             &SystemClauseType::NumberToCodes => {
-                let n = self[temp_v!(1)].clone();
-                let chs = self[temp_v!(2)].clone();
-
-                let string = match self.store(self.deref(n)) {
-                    Addr::Con(Constant::Float(OrderedFloat(n))) => format!("{0:<20?}", n),
-                    Addr::Con(Constant::Integer(n)) => n.to_string(),
-                    _ => unreachable!(),
-                };
+                let chs = self[temp_v!(3)].clone();
+                let string = self.number_to_string();
 
                 let codes = string
                     .trim()
@@ -1119,18 +2490,31 @@ impl MachineState {
 
                 self.unify(codes_list, chs);
             }
+            &SystemClauseType::CodesBytes => {
+                let stub = MachineError::functor_stub(clause_name!("codes_bytes"), 2);
+                let bytes = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                if bytes.is_ref() {
+                    let addrs = self.try_from_list(temp_v!(1), stub.clone())?;
+                    let string = self.try_code_list(addrs, stub)?;
+
+                    self.unify_utf8_bytes(string);
+                } else {
+                    let string = self.try_utf8_byte_list(temp_v!(2), stub)?;
+                    let iter = string.chars().map(|c| Addr::Con(Constant::CharCode(c as u32)));
+                    let codes = Addr::HeapCell(self.heap.to_list(iter));
+                    let a1 = self[temp_v!(1)].clone();
+
+                    self.unify(a1, codes);
+                }
+            }
             &SystemClauseType::CodesToNumber => {
                 let stub = MachineError::functor_stub(clause_name!("number_codes"), 2);
 
-                match self.try_from_list(temp_v!(1), stub.clone()) {
-                    Err(e) => return Err(e),
-                    Ok(addrs) => match self.try_char_list(addrs) {
-                        Ok(chars) => {
-                            self.parse_number_from_string(chars, indices, stub)?
-                        }
-                        Err(err) => return Err(self.error_form(err, stub)),
-                    },
-                }
+                let addrs = self.try_from_list(temp_v!(1), stub.clone())?;
+                let chars = self.try_code_list(addrs, stub.clone())?;
+
+                self.parse_number_from_string(chars, indices, stub)?
             }
             &SystemClauseType::ModuleAssertDynamicPredicateToFront => {
                 let p = self.cp;
@@ -1152,6 +2536,232 @@ impl MachineState {
 
                 self.unify(a1, lh_len);
             }
+            &SystemClauseType::LoadContext => {
+                let load_context = match indices.load_contexts.last() {
+                    Some(load_context) => load_context.clone(),
+                    None => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                };
+
+                let key = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(key, _)) => key,
+                    _ => unreachable!(),
+                };
+
+                let path_atom = |path: &Path| {
+                    Addr::Con(Constant::Atom(
+                        clause_name!(path.to_string_lossy().to_string(), indices.atom_tbl),
+                        None,
+                    ))
+                };
+
+                let value = match key.as_str() {
+                    "module" => Addr::Con(Constant::Atom(load_context.module, None)),
+                    "source" => Addr::Con(Constant::Atom(load_context.source, None)),
+                    "file" => match &load_context.included_file {
+                        Some(path) => path_atom(path),
+                        None => Addr::Con(Constant::Atom(load_context.source, None)),
+                    },
+                    "directory" => match &load_context.included_file {
+                        Some(path) => path_atom(path.parent().unwrap_or(path)),
+                        None => path_atom(&load_context.directory),
+                    },
+                    "term_position" => match load_context.term_line_num {
+                        Some(line_num) => Addr::Con(Constant::Integer(Integer::from(line_num))),
+                        None => {
+                            self.fail = true;
+                            return Ok(());
+                        }
+                    },
+                    "variable_names" => {
+                        let var_dict = self.heap_locs.clone();
+                        self.var_eqs_list(var_dict, indices)
+                    }
+                    _ => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                };
+
+                let a2 = self[temp_v!(2)].clone();
+                self.unify(value, a2);
+            }
+            &SystemClauseType::Statistics => {
+                let key = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(key, _)) => key,
+                    _ => unreachable!(),
+                };
+
+                let value = match key.as_str() {
+                    "atoms" => {
+                        // modules may share an atom table, so count each table once.
+                        let mut tables = vec![];
+                        let mut atoms = 0;
+
+                        let atom_tbls = std::iter::once(&indices.atom_tbl)
+                            .chain(indices.modules.values().map(|module| &module.atom_tbl));
+
+                        for atom_tbl in atom_tbls {
+                            let table = atom_tbl.borrow_mut();
+                            let table_ptr = &*table as *const _;
+
+                            if !tables.contains(&table_ptr) {
+                                tables.push(table_ptr);
+                                atoms += table.len();
+                            }
+                        }
+
+                        atoms
+                    }
+                    "strings" => {
+                        indices.string_tbl.sweep();
+                        indices.string_tbl.len()
+                    }
+                    "heap" => self.heap.h(),
+                    "retired_code" => indices.retired_code.len(),
+                    "random_draws" => *RANDOM_DRAWS.borrow(),
+                    "cputime" => {
+                        let clock = unsafe { libc::clock() };
+                        let seconds = clock as f64 / libc::CLOCKS_PER_SEC as f64;
+
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(Addr::Con(Constant::Float(OrderedFloat(seconds))), a2);
+
+                        return Ok(());
+                    }
+                    "runtime" | "walltime" => {
+                        let total = if key.as_str() == "runtime" {
+                            let clock = unsafe { libc::clock() };
+                            (clock as u64).saturating_mul(1000) / libc::CLOCKS_PER_SEC as u64
+                        } else {
+                            self.time_stats.start.elapsed().as_millis() as u64
+                        };
+
+                        let last = if key.as_str() == "runtime" {
+                            &mut self.time_stats.last_runtime
+                        } else {
+                            &mut self.time_stats.last_walltime
+                        };
+
+                        let since_last = total.saturating_sub(*last);
+                        *last = total;
+
+                        let times = vec![
+                            Addr::Con(Constant::Integer(Integer::from(total))),
+                            Addr::Con(Constant::Integer(Integer::from(since_last))),
+                        ];
+
+                        let times = Addr::HeapCell(self.heap.to_list(times.into_iter()));
+                        let a2 = self[temp_v!(2)].clone();
+
+                        self.unify(times, a2);
+                        return Ok(());
+                    }
+                    _ => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                };
+
+                let a2 = self[temp_v!(2)].clone();
+                self.unify(Addr::Con(Constant::Integer(Integer::from(value))), a2);
+            }
+            &SystemClauseType::ResetQueryStats => {
+                self.reset_query_stats();
+            }
+            &SystemClauseType::QueryStats => {
+                let stats = self.query_stats();
+
+                self.unify_integers(&stats);
+            }
+            &SystemClauseType::FdLinearPropagate => {
+                // '$fd_linear_propagate'(Rel, Cs, C, Los, His, NewLos, NewHis)
+                // bounds the variables of sum(Cs[i] * X[i]) + C Rel 0, Rel
+                // being = or =<, between Los and His, whose elements are
+                // integers, inf or sup.
+                let stub = MachineError::functor_stub(clause_name!("fd_linear_propagate"), 7);
+
+                let rel = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(rel, _)) => rel,
+                    _ => unreachable!(),
+                };
+
+                let c = match self.store(self.deref(self[temp_v!(3)].clone())) {
+                    Addr::Con(Constant::Integer(c)) => c,
+                    _ => unreachable!(),
+                };
+
+                let bound_at = |machine_st: &MachineState, addr: Addr| {
+                    match machine_st.store(machine_st.deref(addr)) {
+                        Addr::Con(Constant::Integer(n)) => Some(n),
+                        _ => None,
+                    }
+                };
+
+                let cs: Vec<Integer> = self.try_from_list(temp_v!(2), stub.clone())?
+                    .into_iter()
+                    .map(|addr| bound_at(self, addr).unwrap())
+                    .collect();
+
+                let mut los: Vec<Option<Integer>> = self.try_from_list(temp_v!(4), stub.clone())?
+                    .into_iter()
+                    .map(|addr| bound_at(self, addr))
+                    .collect();
+
+                let mut his: Vec<Option<Integer>> = self.try_from_list(temp_v!(5), stub)?
+                    .into_iter()
+                    .map(|addr| bound_at(self, addr))
+                    .collect();
+
+                let consistent = match rel.as_str() {
+                    "=<" => fd_linear_le(&cs, &c, &mut los, &mut his),
+                    _ => {
+                        let neg_cs: Vec<Integer> = cs.iter().map(|ci| -ci.clone()).collect();
+
+                        fd_linear_le(&cs, &c, &mut los, &mut his)
+                            && fd_linear_le(&neg_cs, &(-c.clone()), &mut los, &mut his)
+                    }
+                };
+
+                if !consistent {
+                    self.fail = true;
+                    return Ok(());
+                }
+
+                let to_addr = |bound: Option<Integer>, infinity: &'static str| match bound {
+                    Some(n) => Addr::Con(Constant::Integer(n)),
+                    None => Addr::Con(Constant::Atom(clause_name!(infinity), None)),
+                };
+
+                let los = los.into_iter().map(|lo| to_addr(lo, "inf"));
+                let los = Addr::HeapCell(self.heap.to_list(los));
+
+                let his = his.into_iter().map(|hi| to_addr(hi, "sup"));
+                let his = Addr::HeapCell(self.heap.to_list(his));
+
+                let a6 = self[temp_v!(6)].clone();
+                let a7 = self[temp_v!(7)].clone();
+
+                self.unify(los, a6);
+
+                if !self.fail {
+                    self.unify(his, a7);
+                }
+            }
+            &SystemClauseType::StackStatistics => {
+                // the size of the stack in bytes, its high-water mark
+                // since '$reset_query_stats' above its size then, and
+                // the numbers of live environments and choice points.
+                let size = self.stack.size();
+                let high_water = self.query_stats()[2];
+                let (environments, choice_points) = self.frame_counts();
+
+                let stats = [size, high_water, environments, choice_points];
+
+                self.unify_integers(&stats);
+            }
             &SystemClauseType::CharCode => {
                 let a1 = self[temp_v!(1)].clone();
 
@@ -1169,6 +2779,17 @@ impl MachineState {
 
                         self.unify(Addr::Con(Constant::Integer(c)), a2);
                     }
+                    addr @ Addr::Con(Constant::String(..)) | addr @ Addr::PStrLocation(..) => {
+                        match self.string_text(addr).and_then(|text| text.chars().next()) {
+                            Some(c) => {
+                                let a2 = self[temp_v!(2)].clone();
+                                let c = Integer::from(c as u32);
+
+                                self.unify(Addr::Con(Constant::Integer(c)), a2);
+                            }
+                            None => self.fail = true,
+                        }
+                    }
                     addr if addr.is_ref() => {
                         let a2 = self[temp_v!(2)].clone();
 
@@ -1210,6 +2831,45 @@ impl MachineState {
                     _ => self.fail = true,
                 };
             }
+            &SystemClauseType::ChoicePoints => {
+                let choice_points = self.choice_points(&code_repo.code, indices);
+                let a1 = self[temp_v!(1)].clone();
+
+                self.unify(choice_points, a1);
+            }
+            &SystemClauseType::CodeLocation => {
+                let p_functor = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                let p = match self.heap.to_local_code_ptr(&p_functor) {
+                    Some(LocalCodePtr::DirEntry(p)) => Some(p),
+                    _ => None,
+                };
+
+                match p.and_then(|p| self.code_location(p, &code_repo.code, indices)) {
+                    Some(location) => {
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(location, a2);
+                    }
+                    None => {
+                        self.fail = true;
+                    }
+                }
+            }
+            &SystemClauseType::CodeLocationPointer => {
+                let location = self[temp_v!(1)].clone();
+
+                match self.code_location_offset(location, &code_repo.code, indices) {
+                    Some(p) => {
+                        let p_functor = LocalCodePtr::DirEntry(p).as_functor(&mut self.heap);
+                        let a2 = self[temp_v!(2)].clone();
+
+                        self.unify(p_functor, a2);
+                    }
+                    None => {
+                        self.fail = true;
+                    }
+                }
+            }
             &SystemClauseType::CopyTermWithoutAttrVars => {
                 self.copy_term(AttrVarPolicy::StripAttributes);
             }
@@ -1255,23 +2915,133 @@ impl MachineState {
                         self.heap.extend(stub.into_iter());
                         self.unify(addr, Addr::HeapCell(h));
 
-                        *offset = Some(h);
+                        *offset = Some(h);
+                    }
+                    Some((_, Some(h))) => {
+                        let offset = self[temp_v!(3)].clone();
+
+                        self.unify(offset, Addr::Con(Constant::Usize(*h)));
+
+                        if !self.fail {
+                            self.unify(addr, Addr::HeapCell(*h));
+                        }
+                    }
+                    None => {
+                        self.fail = true
+                    }
+                };
+            }
+            &SystemClauseType::GetByte | &SystemClauseType::PeekByte => {
+                let caller = if ct == &SystemClauseType::GetByte {
+                    "get_byte"
+                } else {
+                    "peek_byte"
+                };
+
+                let mut stream = self.checked_stream(indices, true, PermissionError::InputStream, caller)?;
+
+                let result = if ct == &SystemClauseType::GetByte {
+                    let mut byte = [0u8];
+
+                    stream.read(&mut byte).map(|n| if n == 0 { None } else { Some(byte[0]) })
+                } else {
+                    stream.peek_byte()
+                };
+
+                let byte = match result {
+                    Ok(Some(byte)) => Integer::from(byte),
+                    Err(ref e) if e.kind() == ErrorKind::TimedOut => {
+                        let stub = MachineError::functor_stub(clause_name!(caller), 2);
+                        let err = MachineError::timeout_error("read", Addr::Stream(stream));
+
+                        return Err(self.error_form(err, stub));
+                    }
+                    _ => Integer::from(-1),
+                };
+
+                let a2 = self[temp_v!(2)].clone();
+                self.unify(Addr::Con(Constant::Integer(byte)), a2);
+            }
+            &SystemClauseType::PeekChar | &SystemClauseType::PeekCode => {
+                let caller = if ct == &SystemClauseType::PeekChar {
+                    "peek_char"
+                } else {
+                    "peek_code"
+                };
+
+                let mut stream = self.checked_stream(indices, false, PermissionError::InputStream, caller)?;
+                let result = parsing_stream(stream.clone()).next();
+
+                let a2 = self[temp_v!(2)].clone();
+
+                match result {
+                    Some(Ok(b)) => {
+                        let c = b as char;
+                        let mut buf = [0u8; 4];
+
+                        // the character is read again by the next read
+                        // of the stream.
+                        stream.unread(c.encode_utf8(&mut buf).as_bytes());
+
+                        if ct == &SystemClauseType::PeekChar {
+                            self.unify(Addr::Con(Constant::Char(c)), a2);
+                        } else {
+                            self.unify(Addr::Con(Constant::Integer(Integer::from(c as u32))), a2);
+                        }
+                    }
+                    Some(Err(ref e)) if e.kind() == ErrorKind::TimedOut => {
+                        let stub = MachineError::functor_stub(clause_name!(caller), 2);
+                        let err = MachineError::timeout_error("read", Addr::Stream(stream));
+
+                        return Err(self.error_form(err, stub));
+                    }
+                    Some(Err(_)) => {
+                        if ct == &SystemClauseType::PeekChar {
+                            let end_of_file = clause_name!("end_of_file");
+                            self.unify(a2, Addr::Con(Constant::Atom(end_of_file, None)));
+                        } else {
+                            self.unify(a2, Addr::Con(Constant::Integer(Integer::from(-1))));
+                        }
+                    }
+                    None => {
+                        let stub = MachineError::functor_stub(clause_name!(caller), 2);
+                        let err = MachineError::representation_error(RepFlag::Character);
+
+                        return Err(self.error_form(err, stub));
                     }
-                    Some((_, Some(h))) => {
-                        let offset = self[temp_v!(3)].clone();
-
-                        self.unify(offset, Addr::Con(Constant::Usize(*h)));
+                }
+            }
+            &SystemClauseType::PutByte => {
+                let mut stream = self.checked_stream(indices, true, PermissionError::OutputStream, "put_byte")?;
 
-                        if !self.fail {
-                            self.unify(addr, Addr::HeapCell(*h));
-                        }
+                let byte = match self.store(self.deref(self[temp_v!(2)].clone())) {
+                    Addr::Con(Constant::Integer(n)) if n.to_u8().is_some() => {
+                        n.to_u8().unwrap()
                     }
-                    None => {
-                        self.fail = true
+                    addr => {
+                        let stub = MachineError::functor_stub(clause_name!("put_byte"), 2);
+                        let err = MachineError::type_error(ValidType::Byte, addr);
+
+                        return Err(self.error_form(err, stub));
                     }
                 };
+
+                if let Err(e) = stream.write_all(&[byte]).and_then(|_| stream.flush()) {
+                    let stub = MachineError::functor_stub(clause_name!("put_byte"), 2);
+                    let err = MachineError::io_error(e.kind(), "write", Addr::Stream(stream));
+
+                    return Err(self.error_form(err, stub));
+                }
             }
             &SystemClauseType::GetChar => {
+                self.check_stream_type(
+                    current_input_stream,
+                    false,
+                    PermissionError::InputStream,
+                    "get_char",
+                    1,
+                )?;
+
                 let mut iter = parsing_stream(current_input_stream.clone());
                 let result = iter.next();
 
@@ -1279,6 +3049,13 @@ impl MachineState {
 
                 match result {
                     Some(Ok(b)) => self.unify(Addr::Con(Constant::Char(b as char)), a1),
+                    Some(Err(ref e)) if e.kind() == ErrorKind::TimedOut => {
+                        let stub = MachineError::functor_stub(clause_name!("get_char"), 1);
+                        let stream = Addr::Stream(current_input_stream.clone());
+                        let err = MachineError::timeout_error("read", stream);
+
+                        return Err(self.error_form(err, stub));
+                    }
                     Some(Err(_)) => {
                         let end_of_file = clause_name!("end_of_file");
                         self.unify(a1, Addr::Con(Constant::Atom(end_of_file, None)));
@@ -1319,10 +3096,7 @@ impl MachineState {
 
                 match subsection {
                     Some(dynamic_predicate_info) => {
-                        self.execute_at_index(
-                            2,
-                            dir_entry!(dynamic_predicate_info.clauses_subsection_p)
-                        );
+                        self.call_clauses_subsection(dynamic_predicate_info.clauses_subsection_p);
                         return Ok(());
                     }
                     None => self.fail = true,
@@ -1369,25 +3143,68 @@ impl MachineState {
                     _ => unreachable!(),
                 };
             }
+            &SystemClauseType::CopyCodeIndex => {
+                // points the code index of Target/Arity in TargetModule at
+                // the code of Name/Arity in Module. every call to
+                // Target/Arity, compiled before or after, shares the
+                // index, so this redirects them all.
+                let atom_at = |addr: Addr| match addr {
+                    Addr::Con(Constant::Atom(atom, _)) => atom,
+                    _ => unreachable!(),
+                };
+
+                let module_name = atom_at(self.store(self.deref(self[temp_v!(1)].clone())));
+                let name = atom_at(self.store(self.deref(self[temp_v!(2)].clone())));
+                let target_module_name = atom_at(self.store(self.deref(self[temp_v!(4)].clone())));
+                let target = atom_at(self.store(self.deref(self[temp_v!(5)].clone())));
+
+                let arity = match self.store(self.deref(self[temp_v!(3)].clone())) {
+                    Addr::Con(Constant::Integer(n)) => n.to_usize().unwrap(),
+                    _ => unreachable!(),
+                };
+
+                let idx = if module_name.as_str() == "user" {
+                    indices.code_dir.get(&(name, arity))
+                } else {
+                    indices.modules.get(&module_name)
+                        .and_then(|module| module.code_dir.get(&(name, arity)))
+                };
+
+                let ptr = match idx.map(|idx| idx.0.borrow().0) {
+                    Some(IndexPtr::Undefined) | None => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                    Some(ptr) => ptr,
+                };
+
+                let code_dir = if target_module_name.as_str() == "user" {
+                    &mut indices.code_dir
+                } else {
+                    match indices.modules.get_mut(&target_module_name) {
+                        Some(module) => &mut module.code_dir,
+                        None => {
+                            self.fail = true;
+                            return Ok(());
+                        }
+                    }
+                };
+
+                let idx = code_dir.entry((target, arity))
+                    .or_insert_with(|| CodeIndex::new(IndexPtr::Undefined, target_module_name));
+
+                idx.0.borrow_mut().0 = ptr;
+            }
             &SystemClauseType::CopyToLiftedHeap => {
                 match self.store(self.deref(self[temp_v!(1)].clone())) {
                     Addr::Con(Constant::Usize(lh_offset)) => {
                         let copy_target = self[temp_v!(2)].clone();
 
-                        let old_threshold = self.copy_findall_solution(lh_offset, copy_target);
+                        let tail = self.copy_findall_solution(lh_offset, copy_target);
                         let new_threshold = self.lifted_heap.h() - lh_offset;
 
-                        self.lifted_heap[old_threshold] =
+                        self.lifted_heap[tail] =
                             HeapCellValue::Addr(Addr::HeapCell(new_threshold));
-
-                        for addr in self.lifted_heap.iter_mut_from(old_threshold + 1) {
-                            match addr {
-                                HeapCellValue::Addr(ref mut addr) => {
-                                    *addr -= self.heap.h() + lh_offset
-                                }
-                                _ => {}
-                            }
-                        }
                     }
                     _ => self.fail = true,
                 }
@@ -1417,6 +3234,27 @@ impl MachineState {
                     }
                 }
             }
+            &SystemClauseType::DeleteFile => {
+                let file = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                let path = match file {
+                    Addr::Con(Constant::Atom(ref name, _)) => name.as_str().to_string(),
+                    _ => unreachable!(),
+                };
+
+                if let Err(e) = fs::remove_file(&path) {
+                    let stub = MachineError::functor_stub(clause_name!("delete_file"), 1);
+                    let h = self.heap.h();
+
+                    let err = if e.kind() == ErrorKind::NotFound {
+                        MachineError::existence_error(h, ExistenceError::SourceSink(file))
+                    } else {
+                        MachineError::permission_error(PermissionError::Delete, "source_sink", file)
+                    };
+
+                    return Err(self.error_form(err, stub));
+                }
+            }
             &SystemClauseType::DeleteHeadAttribute => {
                 let addr = self.store(self.deref(self[temp_v!(1)].clone()));
 
@@ -1500,6 +3338,135 @@ impl MachineState {
                 self.p = CodePtr::Local(LocalCodePtr::UserTermExpansion(0));
                 return Ok(());
             }
+            &SystemClauseType::FastRead => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "fast_read", 2)?;
+
+                if !stream.is_input_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("fast_read"), 2);
+                    let err = MachineError::permission_error(
+                        PermissionError::InputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                match self.fast_read_term(&mut stream, indices) {
+                    Ok(Some(h)) => {
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(Addr::HeapCell(h), a2);
+                    }
+                    Ok(None) => {
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(Addr::Con(atom!("end_of_file")), a2);
+                    }
+                    Err(err) => {
+                        let stub = MachineError::functor_stub(clause_name!("fast_read"), 2);
+                        let err = MachineError::fast_term_error(err, Addr::Stream(stream));
+
+                        return Err(self.error_form(err, stub));
+                    }
+                }
+            }
+            &SystemClauseType::FastWrite | &SystemClauseType::WriteCheckpoint => {
+                // checkpoints carry the cut points of a continuation.
+                let cut_points = ct == &SystemClauseType::WriteCheckpoint;
+
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "fast_write", 2)?;
+
+                if !stream.is_output_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("fast_write"), 2);
+                    let err = MachineError::permission_error(
+                        PermissionError::OutputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                let term = self[temp_v!(2)].clone();
+
+                // fast_write/3 may write atoms by their ids in the
+                // machine's atom table.
+                let atom_ids = if cut_points {
+                    None
+                } else {
+                    let atom_ids = self.store(self.deref(self[temp_v!(3)].clone()));
+
+                    if atom_ids == Addr::Con(atom!("true")) {
+                        Some(&mut indices.atom_ids)
+                    } else {
+                        None
+                    }
+                };
+
+                match self.fast_write_term(term, AttrVarPolicy::StripAttributes, cut_points, atom_ids) {
+                    Ok(bytes) => {
+                        if let Err(e) = stream.write_all(&bytes).and_then(|_| stream.flush()) {
+                            let stub = MachineError::functor_stub(clause_name!("fast_write"), 2);
+                            let err = MachineError::io_error(e.kind(), "write", Addr::Stream(stream));
+
+                            return Err(self.error_form(err, stub));
+                        }
+                    }
+                    Err(culprit) => {
+                        let stub = MachineError::functor_stub(clause_name!("fast_write"), 2);
+                        let err = MachineError::domain_error(DomainError::Serializable, culprit);
+
+                        return Err(self.error_form(err, stub));
+                    }
+                }
+            }
+            &SystemClauseType::SaveAtomTable => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "save_atom_table", 1)?;
+
+                if !stream.is_output_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("save_atom_table"), 1);
+                    let err = MachineError::permission_error(
+                        PermissionError::OutputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                if let Err(e) = stream.write_all(&write_atom_table(&indices.atom_ids))
+                    .and_then(|_| stream.flush())
+                {
+                    let stub = MachineError::functor_stub(clause_name!("save_atom_table"), 1);
+                    let err = MachineError::io_error(e.kind(), "write", Addr::Stream(stream));
+
+                    return Err(self.error_form(err, stub));
+                }
+            }
+            &SystemClauseType::LoadAtomTable => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "load_atom_table", 1)?;
+
+                if !stream.is_input_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("load_atom_table"), 1);
+                    let err = MachineError::permission_error(
+                        PermissionError::InputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                if let Err(err) = read_atom_table(&mut stream, indices) {
+                    let stub = MachineError::functor_stub(clause_name!("load_atom_table"), 1);
+                    let err = MachineError::fast_term_error(err, Addr::Stream(stream));
+
+                    return Err(self.error_form(err, stub));
+                }
+            }
             &SystemClauseType::GetNextDBRef => {
                 let a1 = self[temp_v!(1)].clone();
 
@@ -1542,25 +3509,7 @@ impl MachineState {
                     addr @ Addr::HeapCell(_)
                     | addr @ Addr::StackCell(..)
                     | addr @ Addr::AttrVar(_) => {
-                        let mut unossified_op_dir = OssifiedOpDir::new();
-
-                        unossified_op_dir.extend(indices.op_dir.iter().filter_map(
-                            |(key, op_dir_val)| {
-                                let (name, fixity) = key.clone();
-
-                                let prec = op_dir_val.shared_op_desc().prec();
-
-                                if prec == 0 {
-                                    return None;
-                                }
-
-                                let assoc = op_dir_val.shared_op_desc().assoc();
-
-                                Some((OrderedOpDirKey(name, fixity), (prec, assoc)))
-                            },
-                        ));
-
-                        let ossified_op_dir = Rc::new(unossified_op_dir);
+                        let ossified_op_dir = indices.op_dir_snapshot();
 
                         match ossified_op_dir.iter().next() {
                             Some((OrderedOpDirKey(name, _), (priority, spec))) => {
@@ -1654,13 +3603,51 @@ impl MachineState {
                 }
             }
             &SystemClauseType::Maybe => {
-                let result = {
-                    let mut rand = RANDOM_STATE.borrow_mut();
+                self.fail = random_bits(1) == 0;
+            }
+            &SystemClauseType::Merge => {
+                let stub = MachineError::functor_stub(clause_name!("merge"), 3);
+
+                let xs = self.try_from_list(temp_v!(1), stub.clone())?;
+                let ys = self.try_from_list(temp_v!(2), stub)?;
 
-                    rand.bits(1) == 0
+                let dedup = match self.store(self.deref(self[temp_v!(3)].clone())) {
+                    Addr::Con(Constant::Atom(ref name, _)) => name.as_str() == "true",
+                    _ => unreachable!(),
+                };
+
+                let merged = self.merge_lists(xs, ys, dedup);
+                let merged = Addr::HeapCell(self.heap.to_list(merged.into_iter()));
+
+                self.unify(merged, self[temp_v!(4)].clone());
+            }
+            &SystemClauseType::RandomFloat => {
+                // 53 bits fill the mantissa of an f64 in [0, 1).
+                let f = random_bits(53) as f64 / (1u64 << 53) as f64;
+                let a1 = self[temp_v!(1)].clone();
+
+                self.unify(Addr::Con(Constant::Float(OrderedFloat(f))), a1);
+            }
+            &SystemClauseType::RandomInteger => {
+                let bound = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Integer(n)) => n.to_u64(),
+                    _ => unreachable!(),
                 };
 
-                self.fail = result;
+                match bound {
+                    Some(bound) if bound > 0 => {
+                        let n = Integer::from(random_below(bound));
+                        let a2 = self[temp_v!(2)].clone();
+
+                        self.unify(Addr::Con(Constant::Integer(n)), a2);
+                    }
+                    _ => {
+                        let stub = MachineError::functor_stub(clause_name!("$random_integer"), 2);
+                        let err = MachineError::representation_error(RepFlag::MaxInteger);
+
+                        return Err(self.error_form(err, stub));
+                    }
+                }
             }
             &SystemClauseType::OpDeclaration => {
                 let priority = self[temp_v!(1)].clone();
@@ -1689,10 +3676,10 @@ impl MachineState {
                     .map_err(SessionError::from)
                     .and_then(|op_decl| {
                         if op_decl.0 == 0 {
-                            Ok(op_decl.remove(&mut indices.op_dir))
+                            Ok(op_decl.remove(indices.op_dir_mut()))
                         } else {
                             let spec = get_desc(op_decl.name(), composite_op!(&indices.op_dir));
-                            op_decl.submit(module, spec, &mut indices.op_dir)
+                            op_decl.submit(module, spec, indices.op_dir_mut())
                         }
                     });
 
@@ -1806,7 +3793,7 @@ impl MachineState {
                     _ => unreachable!()
                 };
 
-                let mut addrs = vec![];
+                let mut addrs = self.scratch.take_addrs();
 
                 for index in 1 .. num_cells + 1 {
                     addrs.push(self.stack.index_and_frame(e)[index].clone());
@@ -1821,7 +3808,8 @@ impl MachineState {
                 ));
 
                 self.heap.push(HeapCellValue::Addr(p_functor));
-                self.heap.extend(addrs.into_iter().map(HeapCellValue::Addr));
+                self.heap.extend(addrs.drain(..).map(HeapCellValue::Addr));
+                self.scratch.give_addrs(addrs);
 
                 self.unify(self[temp_v!(3)].clone(), chunk);
             }
@@ -1838,24 +3826,7 @@ impl MachineState {
                             self.unify(diff, Addr::Con(Constant::EmptyList));
                         } else {
                             let h = self.heap.h();
-                            let mut last_index = h;
-
-                            for value in self.lifted_heap.iter_from(lh_offset) {
-                                last_index = self.heap.h();
-
-                                match value {
-                                    HeapCellValue::Addr(ref addr) => {
-                                        self.heap.push(HeapCellValue::Addr(addr.clone() + h));
-                                    }
-                                    HeapCellValue::PartialString(ref pstr) => {
-                                        let new_pstr = pstr.clone();
-                                        self.heap.push(HeapCellValue::PartialString(new_pstr));
-                                    }
-                                    value => {
-                                        self.heap.push(value.clone());
-                                    }
-                                }
-                            }
+                            let last_index = self.lifted_heap_to_heap(lh_offset);
 
                             if last_index < self.heap.h() {
                                 let addr_opt =
@@ -1871,8 +3842,6 @@ impl MachineState {
                                 });
                             }
 
-                            self.lifted_heap.truncate(lh_offset);
-
                             let solutions = self[temp_v!(2)].clone();
                             self.unify(Addr::HeapCell(h), solutions);
                         }
@@ -1890,23 +3859,7 @@ impl MachineState {
                             self.unify(solutions, Addr::Con(Constant::EmptyList));
                         } else {
                             let h = self.heap.h();
-
-                            for addr in self.lifted_heap.iter_from(lh_offset) {
-                                match addr {
-                                    HeapCellValue::Addr(ref addr) => {
-                                        self.heap.push(HeapCellValue::Addr(addr.clone() + h))
-                                    }
-                                    HeapCellValue::PartialString(ref pstr) => {
-                                        let new_pstr = pstr.clone();
-                                        self.heap.push(HeapCellValue::PartialString(new_pstr));
-                                    }
-                                    value => {
-                                        self.heap.push(value.clone());
-                                    }
-                                }
-                            }
-
-                            self.lifted_heap.truncate(lh_offset);
+                            self.lifted_heap_to_heap(lh_offset);
 
                             let solutions = self[temp_v!(2)].clone();
                             self.unify(Addr::HeapCell(h), solutions);
@@ -1915,6 +3868,24 @@ impl MachineState {
                     _ => self.fail = true,
                 }
             }
+            &SystemClauseType::GetAutoload => {
+                let a1 = self[temp_v!(1)].clone();
+
+                if self.autoload {
+                    self.unify(a1, Addr::Con(atom!("true")));
+                } else {
+                    self.unify(a1, Addr::Con(atom!("false")));
+                }
+            }
+            &SystemClauseType::GetBlockStrings => {
+                let a1 = self[temp_v!(1)].clone();
+
+                if block_strings() {
+                    self.unify(a1, Addr::Con(atom!("true")));
+                } else {
+                    self.unify(a1, Addr::Con(atom!("false")));
+                }
+            }
             &SystemClauseType::GetDoubleQuotes => {
                 let a1 = self[temp_v!(1)].clone();
 
@@ -1924,6 +3895,33 @@ impl MachineState {
                     DoubleQuotes::Codes => self.unify(a1, Addr::Con(atom!("codes"))),
                 }
             }
+            &SystemClauseType::GetQuasiQuotations => {
+                let a1 = self[temp_v!(1)].clone();
+
+                if quasi_quotations() {
+                    self.unify(a1, Addr::Con(atom!("true")));
+                } else {
+                    self.unify(a1, Addr::Con(atom!("false")));
+                }
+            }
+            &SystemClauseType::GetReproducible => {
+                let a1 = self[temp_v!(1)].clone();
+
+                if self.reproducible {
+                    self.unify(a1, Addr::Con(atom!("true")));
+                } else {
+                    self.unify(a1, Addr::Con(atom!("false")));
+                }
+            }
+            &SystemClauseType::GetUnicodeIdentifiers => {
+                let a1 = self[temp_v!(1)].clone();
+
+                if self.unicode_identifiers {
+                    self.unify(a1, Addr::Con(atom!("true")));
+                } else {
+                    self.unify(a1, Addr::Con(atom!("false")));
+                }
+            }
             &SystemClauseType::GetSCCCleaner => {
                 let dest = self[temp_v!(1)].clone();
 
@@ -1958,6 +3956,7 @@ impl MachineState {
                 if cut_policy.downcast_ref::<SCCCutPolicy>().is_err() {
                     let (r_c_w_h, r_c_wo_h) = indices.get_cleaner_sites();
                     *cut_policy = Box::new(SCCCutPolicy::new(r_c_w_h, r_c_wo_h));
+                    log_event!(debug, "cut policy switched to SCCCutPolicy");
                 }
 
                 match cut_policy.downcast_mut::<SCCCutPolicy>().ok() {
@@ -2009,6 +4008,94 @@ impl MachineState {
                     }
                 };
             }
+            &SystemClauseType::InstallSandbox => {
+                // A1 = Key, a fresh variable, A2 = the whitelist, a
+                // list of Name/Arity and Module:Name/Arity terms.
+                let stub = MachineError::functor_stub(clause_name!("sandboxed_call"), 2);
+
+                let key = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::HeapCell(h) => h,
+                    key => {
+                        let err = MachineError::uninstantiation_error(key);
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+                let mut whitelist = vec![];
+
+                for addr in self.try_from_list(temp_v!(2), stub)? {
+                    match self.sandboxed_predicate(addr) {
+                        Some(pred) => whitelist.push(pred),
+                        None => unreachable!(),
+                    }
+                }
+
+                sandbox_policy.push(key, whitelist);
+                log_event!(debug, "sandbox policy entered");
+            }
+            &SystemClauseType::InstallMemoryLimit => {
+                // A1 = B, A2 = the number of heap cells
+                let a1 = self.store(self.deref(self[temp_v!(1)].clone()));
+                let a2 = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                if call_policy.downcast_ref::<CWILCallPolicy>().is_err() {
+                    CWILCallPolicy::new_in_place(call_policy);
+                }
+
+                let cells = match a2 {
+                    Addr::Con(Constant::Integer(n)) => n.to_usize().unwrap_or(usize::MAX),
+                    _ => unreachable!(),
+                };
+
+                let h = self.heap.h();
+
+                match (a1, call_policy.downcast_mut::<CWILCallPolicy>().ok()) {
+                    (Addr::Con(Constant::Usize(bp)), Some(call_policy))
+                  | (Addr::Con(Constant::CutPoint(bp)), Some(call_policy)) => {
+                        call_policy.add_heap_limit(cells, h, bp);
+                    }
+                    _ => panic!(
+                        "install_memory_limit: should have installed \\
+                         CWILCallPolicy."
+                    ),
+                }
+            }
+            &SystemClauseType::InstallTimeLimit => {
+                // A1 = B, A2 = Time, in seconds
+                let a1 = self.store(self.deref(self[temp_v!(1)].clone()));
+                let a2 = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                if call_policy.downcast_ref::<CWILCallPolicy>().is_err() {
+                    CWILCallPolicy::new_in_place(call_policy);
+                }
+
+                // an infinite time, or one too long to represent, is
+                // no limit.
+                let timeout = match a2 {
+                    Addr::Con(Constant::Integer(n)) =>
+                        n.to_u64().map(Duration::from_secs),
+                    Addr::Con(Constant::Float(OrderedFloat(f))) => {
+                        if f.is_finite() && f < u64::MAX as f64 {
+                            Some(Duration::from_secs_f64(f))
+                        } else {
+                            None
+                        }
+                    }
+                    _ => unreachable!(),
+                };
+
+                match (a1, call_policy.downcast_mut::<CWILCallPolicy>().ok()) {
+                    (Addr::Con(Constant::Usize(bp)), Some(call_policy))
+                  | (Addr::Con(Constant::CutPoint(bp)), Some(call_policy)) => {
+                        if let Some(timeout) = timeout {
+                            call_policy.add_deadline(timeout, bp);
+                        }
+                    }
+                    _ => panic!(
+                        "install_time_limit: should have installed \\
+                         CWILCallPolicy."
+                    ),
+                }
+            }
             &SystemClauseType::ModuleExists => {
                 let module = self.store(self.deref(self[temp_v!(1)].clone()));
 
@@ -2101,7 +4188,7 @@ impl MachineState {
                     _ => unreachable!(),
                 };
 
-                indices.global_variables.swap_remove(&key);
+                indices.global_variables.shift_remove(&key);
             }
             &SystemClauseType::ResetGlobalVarAtOffset => {
                 let key = self[temp_v!(1)].clone();
@@ -2133,6 +4220,40 @@ impl MachineState {
                     }
                 };
             },
+            &SystemClauseType::ReclaimedCode => {
+                let stub = MachineError::functor_stub(clause_name!("$reclaimed_code"), 0);
+                let err = MachineError::system_error("reclaimed_code");
+
+                return Err(self.error_form(err, stub));
+            }
+            &SystemClauseType::ResetAfterAbort => {
+                if !self.aborting {
+                    let stub = MachineError::functor_stub(clause_name!("$reset_after_abort"), 0);
+                    let err = MachineError::permission_error(
+                        PermissionError::Access,
+                        "private_procedure",
+                        Addr::Con(Constant::Atom(clause_name!("$reset_after_abort"), None)),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                self.aborting = false;
+
+                // the catchers passed by abort/0 have run their
+                // recovery goals, but the aborted goal may have been
+                // interrupted between installing state and the catcher
+                // that restores it, so the toplevel starts afresh.
+                self.lifted_heap.clear();
+                self.attr_var_init.reset();
+
+                *call_policy = Box::new(DefaultCallPolicy {});
+                *cut_policy = Box::new(DefaultCutPolicy {});
+
+                sandbox_policy.clear();
+
+                log_event!(debug, "policies reset to defaults after abort");
+            }
             &SystemClauseType::ResetAttrVarState => {
                 self.attr_var_init.reset();
             }
@@ -2162,6 +4283,7 @@ impl MachineState {
 
                 if let Some(new_policy) = restore_default {
                     *call_policy = new_policy;
+                    log_event!(debug, "call policy restored from CWILCallPolicy");
                 }
             }
             &SystemClauseType::RemoveInferenceCounter => {
@@ -2189,6 +4311,51 @@ impl MachineState {
                     ),
                 }
             }
+            &SystemClauseType::RemoveSandbox => {
+                if let Addr::HeapCell(key) = self.store(self.deref(self[temp_v!(1)].clone())) {
+                    sandbox_policy.remove(key);
+                }
+            }
+            &SystemClauseType::RemoveMemoryLimit => {
+                match call_policy.downcast_mut::<CWILCallPolicy>().ok() {
+                    Some(call_policy) => {
+                        let a1 = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                        match a1 {
+                            Addr::Con(Constant::Usize(bp)) | Addr::Con(Constant::CutPoint(bp)) => {
+                                call_policy.remove_heap_limit(bp);
+                            }
+                            _ => {
+                                panic!("remove_memory_limit: expected Usize in A1.");
+                            }
+                        }
+                    }
+                    None => panic!(
+                        "remove_memory_limit: requires \\
+                         CWILCallPolicy."
+                    ),
+                }
+            }
+            &SystemClauseType::RemoveTimeLimit => {
+                match call_policy.downcast_mut::<CWILCallPolicy>().ok() {
+                    Some(call_policy) => {
+                        let a1 = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                        match a1 {
+                            Addr::Con(Constant::Usize(bp)) | Addr::Con(Constant::CutPoint(bp)) => {
+                                call_policy.remove_deadline(bp);
+                            }
+                            _ => {
+                                panic!("remove_time_limit: expected Usize in A1.");
+                            }
+                        }
+                    }
+                    None => panic!(
+                        "remove_time_limit: requires \\
+                         CWILCallPolicy."
+                    ),
+                }
+            }
             &SystemClauseType::REPL(repl_code_ptr) => return self.repl_redirect(repl_code_ptr),
             &SystemClauseType::ModuleRetractClause => {
                 let p = self.cp;
@@ -2235,6 +4402,7 @@ impl MachineState {
 
                 if restore_default {
                     *cut_policy = Box::new(DefaultCutPolicy {});
+                    log_event!(debug, "cut policy restored to DefaultCutPolicy");
                 }
             }
             &SystemClauseType::SetCutPoint(r) => {
@@ -2245,11 +4413,237 @@ impl MachineState {
             &SystemClauseType::SetCutPointByDefault(r) => {
                 deref_cut(self, r)
             }
+            &SystemClauseType::OpenStream => {
+                let source_sink = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mode = self.store(self.deref(self[temp_v!(2)].clone()));
+                let stream_type = self.store(self.deref(self[temp_v!(4)].clone()));
+
+                let path = match source_sink {
+                    Addr::Con(Constant::Atom(ref name, _)) => name.as_str().to_string(),
+                    _ => {
+                        let stub = MachineError::functor_stub(clause_name!("open"), 3);
+                        let err = MachineError::domain_error(DomainError::SourceSink, source_sink);
+
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                let mut options = OpenOptions::new();
+
+                match mode {
+                    Addr::Con(Constant::Atom(ref mode, _)) => match mode.as_str() {
+                        "read" => options.read(true),
+                        "write" => options.write(true).truncate(true).create(true),
+                        "append" => options.append(true).create(true),
+                        _ => unreachable!(),
+                    },
+                    _ => unreachable!(),
+                };
+
+                let file = match options.open(&path) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        let stub = MachineError::functor_stub(clause_name!("open"), 3);
+                        let h = self.heap.h();
+
+                        let err = if e.kind() == ErrorKind::NotFound {
+                            MachineError::existence_error(h, ExistenceError::SourceSink(source_sink))
+                        } else {
+                            MachineError::permission_error(
+                                PermissionError::Open,
+                                "source_sink",
+                                source_sink,
+                            )
+                        };
+
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                let (mut stream, mode) = match mode {
+                    Addr::Con(Constant::Atom(ref mode, _)) => match mode.as_str() {
+                        "read" => (Stream::from(file), StreamMode::Read),
+                        "write" => (Stream::from_output_file(file), StreamMode::Write),
+                        _ => (Stream::from_output_file(file), StreamMode::Append),
+                    },
+                    _ => unreachable!(),
+                };
+
+                if let Addr::Con(Constant::Atom(ref name, _)) = stream_type {
+                    if name.as_str() == "binary" {
+                        stream.options.stream_type = StreamType::Binary;
+                    }
+                }
+
+                if let Addr::Con(Constant::Atom(name, _)) = source_sink {
+                    stream.set_file_source(name, mode);
+                }
+
+                indices.open_streams.push(stream.clone());
+
+                let stream_var = self[temp_v!(3)].clone();
+                self.unify(Addr::Stream(stream), stream_var);
+            }
+            &SystemClauseType::OpenMemoryStream => {
+                let stub = MachineError::functor_stub(clause_name!("open_memory_stream"), 3);
+                let addrs = self.try_from_list(temp_v!(1), stub.clone())?;
+
+                let text = match self.try_char_list(addrs) {
+                    Ok(text) => text,
+                    Err(err) => return Err(self.error_form(err, stub)),
+                };
+
+                let mut stream = Stream::input_buffer(text);
+
+                if self.is_binary_type(temp_v!(2)) {
+                    stream.options.stream_type = StreamType::Binary;
+                }
+
+                indices.open_streams.push(stream.clone());
+
+                let stream_var = self[temp_v!(3)].clone();
+                self.unify(Addr::Stream(stream), stream_var);
+            }
+            &SystemClauseType::SocketClientOpen => {
+                let host = self.store(self.deref(self[temp_v!(1)].clone()));
+                let port = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                let address = match (&host, &port) {
+                    (Addr::Con(Constant::Atom(ref host, _)), Addr::Con(Constant::Integer(ref port))) => {
+                        format!("{}:{}", host.as_str(), port)
+                    }
+                    _ => unreachable!(),
+                };
+
+                let mut stream = match TcpStream::connect(address) {
+                    Ok(tcp_stream) => Stream::from(tcp_stream),
+                    Err(_) => {
+                        let stub = MachineError::functor_stub(clause_name!("socket_client_open"), 3);
+                        let address = self.store(self.deref(self[temp_v!(3)].clone()));
+                        let h = self.heap.h();
+
+                        let err = MachineError::existence_error(h, ExistenceError::SourceSink(address));
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                if self.is_binary_type(temp_v!(5)) {
+                    stream.options.stream_type = StreamType::Binary;
+                }
+
+                indices.open_streams.push(stream.clone());
+
+                let a4 = self[temp_v!(4)].clone();
+                self.unify(Addr::Stream(stream), a4);
+            }
+            &SystemClauseType::SocketServerOpen => {
+                let host = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(host, _)) => host,
+                    _ => unreachable!(),
+                };
+
+                let port = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                // an unbound port is chosen by the operating system.
+                let port_number = match port {
+                    Addr::Con(Constant::Integer(ref port)) => port.to_u16().unwrap(),
+                    _ => 0,
+                };
+
+                let listener = match TcpListener::bind((host.as_str(), port_number)) {
+                    Ok(listener) => listener,
+                    Err(_) => {
+                        let stub = MachineError::functor_stub(clause_name!("socket_server_open"), 2);
+                        let err = MachineError::permission_error(
+                            PermissionError::Open,
+                            "source_sink",
+                            port,
+                        );
+
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                if let Ok(addr) = listener.local_addr() {
+                    let bound_port = Addr::Con(Constant::Integer(Integer::from(addr.port())));
+                    self.unify(port, bound_port);
+                }
+
+                let stream = Stream::from(listener);
+                indices.open_streams.push(stream.clone());
+
+                let a3 = self[temp_v!(3)].clone();
+                self.unify(Addr::Stream(stream), a3);
+            }
+            &SystemClauseType::SocketServerAccept => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let server = self.get_stream_or_alias(addr, indices, "socket_server_accept", 4)?;
+
+                let (mut stream, client) = match server.accept() {
+                    Some(Ok(connection)) => connection,
+                    Some(Err(_)) => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                    None => {
+                        let stub = MachineError::functor_stub(clause_name!("socket_server_accept"), 4);
+                        let err = MachineError::domain_error(
+                            DomainError::ServerSocket,
+                            Addr::Stream(server),
+                        );
+
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                if self.is_binary_type(temp_v!(4)) {
+                    stream.options.stream_type = StreamType::Binary;
+                }
+
+                indices.open_streams.push(stream.clone());
+
+                let client = clause_name!(client.to_string(), indices.atom_tbl);
+                let a2 = self[temp_v!(2)].clone();
+
+                self.unify(Addr::Con(Constant::Atom(client, None)), a2);
+
+                if !self.fail {
+                    let a3 = self[temp_v!(3)].clone();
+                    self.unify(Addr::Stream(stream), a3);
+                }
+            }
+            &SystemClauseType::NullStream => {
+                let stream_var = self[temp_v!(1)].clone();
+                self.unify(Addr::Stream(Stream::null()), stream_var);
+            }
+            &SystemClauseType::CaptureOutput => {
+                let stream_var = self[temp_v!(1)].clone();
+                self.unify(Addr::Stream(Stream::output_buffer()), stream_var);
+            }
+            &SystemClauseType::CapturedOutput => {
+                let output = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Stream(stream) => stream.take_output(),
+                    _ => None,
+                };
+
+                match output {
+                    Some(output) => {
+                        let iter = output.chars().map(|c| Addr::Con(Constant::Char(c)));
+                        let list_of_chars = Addr::HeapCell(self.heap.to_list(iter));
+
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(a2, list_of_chars);
+                    }
+                    None => {
+                        self.fail = true;
+                    }
+                }
+            }
             &SystemClauseType::SetInput => {
                 let addr = self.store(self.deref(self[temp_v!(1)].clone()));
-                let stream = self.get_stream_or_alias(addr, indices, "set_input")?;
+                let stream = self.get_stream_or_alias(addr, indices, "set_input", 1)?;
 
-                if stream.is_output_stream() {
+                if !stream.is_input_stream() {
                     let stub = MachineError::functor_stub(clause_name!("set_input"), 1);
                     let err = MachineError::permission_error(
                         PermissionError::InputStream,
@@ -2264,10 +4658,10 @@ impl MachineState {
             }
             &SystemClauseType::SetOutput => {
                 let addr = self.store(self.deref(self[temp_v!(1)].clone()));
-                let stream = self.get_stream_or_alias(addr, indices, "set_output")?;
+                let stream = self.get_stream_or_alias(addr, indices, "set_output", 1)?;
 
-                if stream.is_input_stream() {
-                    let stub = MachineError::functor_stub(clause_name!("set_input"), 1);
+                if !stream.is_output_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("set_output"), 1);
                     let err = MachineError::permission_error(
                         PermissionError::OutputStream,
                         "stream",
@@ -2279,6 +4673,201 @@ impl MachineState {
 
                 *current_output_stream = stream;
             }
+            &SystemClauseType::Subterms => {
+                let term = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                match self.subterms(term) {
+                    Some(subterms) => {
+                        let subterms = Addr::HeapCell(self.heap.to_list(subterms.into_iter()));
+                        let subterms_var = self[temp_v!(2)].clone();
+
+                        self.unify(subterms, subterms_var);
+                    }
+                    None => self.fail = true,
+                }
+            }
+            &SystemClauseType::RebuildSubterms => {
+                let term = self.store(self.deref(self[temp_v!(1)].clone()));
+                let stub = MachineError::functor_stub(clause_name!("$rebuild_subterms"), 3);
+
+                let old = self.subterms(term.clone()).unwrap();
+                let new = self.try_from_list(temp_v!(2), stub)?;
+
+                let rebuilt = self.rebuild_subterms(term, old, new);
+                let rebuilt_var = self[temp_v!(3)].clone();
+
+                self.unify(rebuilt, rebuilt_var);
+            }
+            &SystemClauseType::SetStream => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let stream = self.get_stream_or_alias(addr, indices, "set_stream", 2)?;
+
+                let timeout = match self.store(self.deref(self[temp_v!(2)].clone())) {
+                    Addr::Str(s) => match &self.heap[s] {
+                        &HeapCellValue::NamedStr(1, ref name, _) if name.as_str() == "timeout" => {
+                            let addr = self.heap[s + 1].as_addr(s + 1);
+
+                            match self.store(self.deref(addr)) {
+                                Addr::Con(Constant::Atom(ref name, _))
+                                    if name.as_str() == "infinite" => None,
+                                Addr::Con(Constant::Integer(n)) =>
+                                    Some(Duration::from_secs(n.to_usize().unwrap_or(usize::MAX) as u64)),
+                                Addr::Con(Constant::Float(OrderedFloat(f))) => {
+                                    // from_secs_f64 panics on infinite and
+                                    // NaN durations, and on those too long
+                                    // to represent.
+                                    if !f.is_finite() || f <= 0.0 || f >= u64::MAX as f64 {
+                                        let stub = MachineError::functor_stub(clause_name!("set_stream"), 2);
+                                        let err = MachineError::domain_error(
+                                            DomainError::StreamProperty,
+                                            Addr::Str(s),
+                                        );
+
+                                        return Err(self.error_form(err, stub));
+                                    }
+
+                                    Some(Duration::from_secs_f64(f))
+                                }
+                                _ => unreachable!(),
+                            }
+                        }
+                        &HeapCellValue::NamedStr(1, ref name, _) if name.as_str() == "double_quotes" => {
+                            let addr = self.heap[s + 1].as_addr(s + 1);
+
+                            if let Some(double_quotes) = self.double_quotes_value(addr) {
+                                stream.set_double_quotes(double_quotes);
+                            }
+
+                            return return_from_clause!(self.last_call, self);
+                        }
+                        _ => unreachable!(),
+                    },
+                    _ => unreachable!(),
+                };
+
+                if stream.set_timeout(timeout).is_err() {
+                    let stub = MachineError::functor_stub(clause_name!("set_stream"), 2);
+                    let err = MachineError::permission_error(
+                        PermissionError::Modify,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+            }
+            &SystemClauseType::StreamProperties => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let stream = self.get_stream_or_alias(addr, indices, "stream_property", 2)?;
+
+                let properties = self.stream_properties(stream);
+                let a2 = self[temp_v!(2)].clone();
+
+                self.unify(properties, a2);
+            }
+            &SystemClauseType::Streams => {
+                let mut streams: Vec<Stream> = vec![];
+
+                let candidates = vec![current_input_stream.clone(), current_output_stream.clone()];
+
+                for stream in candidates.into_iter().chain(indices.open_streams.iter().cloned()) {
+                    if !stream.is_closed() && streams.iter().all(|s| s.as_ptr() != stream.as_ptr()) {
+                        streams.push(stream);
+                    }
+                }
+
+                let list = Addr::HeapCell(self.heap.to_list(streams.into_iter().map(Addr::Stream)));
+                let a1 = self[temp_v!(1)].clone();
+
+                self.unify(list, a1);
+            }
+            &SystemClauseType::AtEndOfStream => {
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "at_end_of_stream", 1)?;
+
+                if !stream.is_input_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("at_end_of_stream"), 1);
+                    let err = MachineError::permission_error(
+                        PermissionError::InputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                match stream.at_end_of_stream() {
+                    Ok(true) => {}
+                    _ => self.fail = true,
+                }
+            }
+            &SystemClauseType::SetToplevelPrompt => {
+                let prompt = self.toplevel_prompt_kind(self[temp_v!(1)].clone());
+
+                match self.store(self.deref(self[temp_v!(2)].clone())) {
+                    Addr::Con(Constant::Atom(text, _)) => {
+                        readline::set_prompt_text(prompt, text.as_str().to_string());
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            &SystemClauseType::ToplevelPrompt => {
+                let prompt = self.toplevel_prompt_kind(self[temp_v!(1)].clone());
+                let text = clause_name!(readline::prompt_text(prompt), indices.atom_tbl);
+
+                let a2 = self[temp_v!(2)].clone();
+                self.unify(a2, Addr::Con(Constant::Atom(text, None)));
+            }
+            &SystemClauseType::SetAutoload => match self.store(self.deref(self[temp_v!(1)].clone())) {
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "true" => {
+                    self.autoload = true
+                }
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "false" => {
+                    self.autoload = false
+                }
+                _ => self.fail = true,
+            },
+            &SystemClauseType::SetBlockStrings => match self.store(self.deref(self[temp_v!(1)].clone())) {
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "true" => {
+                    set_block_strings(true)
+                }
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "false" => {
+                    set_block_strings(false)
+                }
+                _ => self.fail = true,
+            },
+            &SystemClauseType::SetQuasiQuotations => match self.store(self.deref(self[temp_v!(1)].clone())) {
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "true" => {
+                    set_quasi_quotations(true)
+                }
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "false" => {
+                    set_quasi_quotations(false)
+                }
+                _ => self.fail = true,
+            },
+            &SystemClauseType::SetReproducible => match self.store(self.deref(self[temp_v!(1)].clone())) {
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "true" => {
+                    // restart the random number generator from its
+                    // initial state, so that every reproducible run
+                    // draws the same sequence of numbers.
+                    *RANDOM_STATE.borrow_mut() = RandState::new();
+                    *RANDOM_DRAWS.borrow_mut() = 0;
+                    self.reproducible = true
+                }
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "false" => {
+                    self.reproducible = false
+                }
+                _ => self.fail = true,
+            },
+            &SystemClauseType::SetUnicodeIdentifiers => match self.store(self.deref(self[temp_v!(1)].clone())) {
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "true" => {
+                    self.unicode_identifiers = true
+                }
+                Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "false" => {
+                    self.unicode_identifiers = false
+                }
+                _ => self.fail = true,
+            },
             &SystemClauseType::SetDoubleQuotes => match self[temp_v!(1)].clone() {
                 Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "chars" => {
                     self.flags.double_quotes = DoubleQuotes::Chars
@@ -2357,6 +4946,50 @@ impl MachineState {
 
                 self.unify(a1, a2);
             }
+            &SystemClauseType::SoftCut => {
+                // B is the choice point of the if/3 disjunction. Rather
+                // than removing it (a later cut to B would then see a
+                // stale chain), its alternative is redirected to the
+                // trust_me of '$soft_cut_alternative'/0, so that
+                // backtracking into B discards it and fails onward
+                // without running the else branch.
+                let b = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Usize(b)) => b,
+                    _ => unreachable!(),
+                };
+
+                let alternative = indices
+                    .modules
+                    .get(&clause_name!("builtins"))
+                    .and_then(|module| {
+                        module.code_dir.get(&(clause_name!("$soft_cut_alternative"), 0))
+                    })
+                    .and_then(|idx| idx.local());
+
+                if let Some(p) = alternative {
+                    if let Line::Choice(ChoiceInstruction::TryMeElse(offset)) = &code_repo.code[p] {
+                        self.stack.index_or_frame_mut(b).prelude.bp =
+                            LocalCodePtr::DirEntry(p + offset);
+                    }
+                }
+            }
+            &SystemClauseType::IsDeterministicAnswer => {
+                // B is the choice point barrier recorded before the
+                // query was called, or the cut barrier of the clause
+                // calling deterministic/1. the answer is the last one
+                // iff no choice point was left above it, in which case
+                // Det is unified with true, and otherwise with false.
+                let b = self.store(self.deref(self[temp_v!(1)].clone()));
+
+                let det = match b {
+                    Addr::Con(Constant::Usize(b)) | Addr::Con(Constant::CutPoint(b))
+                        if self.b <= b => "true",
+                    _ => "false",
+                };
+
+                let target = self[temp_v!(2)].clone();
+                self.unify(Addr::Con(Constant::Atom(clause_name!(det), None)), target);
+            }
             &SystemClauseType::GetClause => {
                 let head = self[temp_v!(1)].clone();
 
@@ -2375,10 +5008,7 @@ impl MachineState {
 
                 match subsection {
                     Some(dynamic_predicate_info) => {
-                        self.execute_at_index(
-                            2,
-                            dir_entry!(dynamic_predicate_info.clauses_subsection_p)
-                        );
+                        self.call_clauses_subsection(dynamic_predicate_info.clauses_subsection_p);
                         return Ok(());
                     }
                     _ => unreachable!(),
@@ -2394,15 +5024,7 @@ impl MachineState {
                 self.install_new_block(temp_v!(1));
             }
             &SystemClauseType::RawInputReadChar => {
-		let keypress = {
-                    enable_raw_mode().expect("failed to transition into raw mode");
-		    let result = next_keypress();
-                    disable_raw_mode().expect("failed to transition out of raw mode");
-
-                    result
-		};
-
-                let c = match keypress {
+                let c = match next_keypress() {
                     ContinueResult::ContinueQuery => ';',
                     ContinueResult::Conclude => '.',
                     ContinueResult::Help => 'h',
@@ -2479,6 +5101,65 @@ impl MachineState {
                 self.fail = true;
                 return Ok(());
             }
+            &SystemClauseType::ProcedureSuggestions => {
+                let name = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(name, _)) => name,
+                    _ => unreachable!(),
+                };
+
+                let arity = match self.store(self.deref(self[temp_v!(2)].clone())) {
+                    Addr::Con(Constant::Integer(n)) => n.to_usize().unwrap(),
+                    _ => unreachable!(),
+                };
+
+                // the arities at which name is defined, by the user,
+                // an imported module or as a builtin.
+                let mut arities: Vec<usize> = indices.code_dir.iter()
+                    .filter(|((key_name, key_arity), idx)| {
+                        key_name.as_str() == name.as_str() && *key_arity != arity
+                            && !idx.is_undefined()
+                    })
+                    .map(|((_, key_arity), _)| *key_arity)
+                    .chain(CLAUSE_TYPE_FORMS.borrow().keys()
+                           .filter(|(key_name, key_arity)| {
+                               *key_name == name.as_str() && *key_arity != arity
+                           })
+                           .map(|(_, key_arity)| *key_arity))
+                    .collect();
+
+                arities.sort();
+                arities.dedup();
+
+                // the libraries exporting name/arity. name/arity is
+                // undefined, so none of them has been imported.
+                let libraries: Vec<&'static str> = AUTOLOAD_INDEX.iter()
+                    .filter_map(|&(export, export_arity, library)| {
+                        if export == name.as_str() && export_arity == arity {
+                            Some(library)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+
+                let arities = arities.into_iter().map(|arity| {
+                    Addr::Con(Constant::Integer(Integer::from(arity)))
+                });
+
+                let arities = Addr::HeapCell(self.heap.to_list(arities));
+
+                let libraries = libraries.into_iter().map(|library| {
+                    Addr::Con(Constant::Atom(clause_name!(library), None))
+                });
+
+                let libraries = Addr::HeapCell(self.heap.to_list(libraries));
+
+                self.unify(self[temp_v!(3)].clone(), arities);
+
+                if !self.fail {
+                    self.unify(self[temp_v!(4)].clone(), libraries);
+                }
+            }
             &SystemClauseType::QuotedToken => {
                 let addr = self.store(self.deref(self[temp_v!(1)].clone()));
 
@@ -2505,15 +5186,146 @@ impl MachineState {
                 }
             }
             &SystemClauseType::ReadQueryTerm => {
-                readline::set_prompt(true);
-                let result = self.read_term(current_input_stream, indices);
-                readline::set_prompt(false);
+                // the query prompt was shown by '$read_toplevel_command'/2,
+                // which reads the first line of the query.
+                readline::set_prompt(readline::Prompt::Continuation);
+                let result = self.read_term(current_input_stream, indices, None, 1);
+                readline::set_prompt(readline::Prompt::None);
+
+                match result {
+                    Ok(_) => {}
+                    // the end of the toplevel's input ends the session.
+                    Err(ParserError::UnexpectedEOF) => std::process::exit(0),
+                    Err(err) => {
+                        return Err(self.read_term_error(current_input_stream, err, 2));
+                    }
+                }
+            }
+            &SystemClauseType::ReadToplevelCommand => {
+                let prefix = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(prefix, _)) => prefix,
+                    _ => unreachable!(),
+                };
+
+                readline::set_prompt(readline::Prompt::Query);
+                let result = current_input_stream.read_command(prefix.as_str());
+                readline::set_prompt(readline::Prompt::None);
+
+                match result {
+                    Ok(Some(command)) => {
+                        let command = clause_name!(command, indices.atom_tbl);
+                        let a2 = self[temp_v!(2)].clone();
 
-                let _ = result?;
+                        self.unify(a2, Addr::Con(Constant::Atom(command, None)));
+                    }
+                    _ => self.fail = true,
+                }
             }
             &SystemClauseType::ReadTerm => {
-                readline::set_prompt(false);
-                self.read_term(current_input_stream, indices)?;
+                let addr = self.store(self.deref(self[temp_v!(1)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "read_term", 3)?;
+
+                if !stream.is_input_stream() {
+                    let stub = MachineError::functor_stub(clause_name!("read_term"), 3);
+                    let err = MachineError::permission_error(
+                        PermissionError::InputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
+
+                    return Err(self.error_form(err, stub));
+                }
+
+                self.check_stream_type(&stream, false, PermissionError::InputStream, "read_term", 3)?;
+
+                let double_quotes = self.double_quotes_value(self[temp_v!(4)].clone());
+
+                readline::set_prompt(readline::Prompt::None);
+
+                // the current input is read in place, so that it is
+                // reset if the read fails.
+                let result = if stream == *current_input_stream {
+                    self.read_term(current_input_stream, indices, double_quotes, 2)
+                        .map_err(|err| self.read_term_error(current_input_stream, err, 3))
+                } else {
+                    self.read_term(&mut stream, indices, double_quotes, 2)
+                        .map_err(|err| self.read_term_error(&mut stream, err, 3))
+                };
+
+                let (start_line, end_line) = result?;
+
+                if self.fail {
+                    return Ok(());
+                }
+
+                let h = self.heap.h();
+                let spec = fetch_atom_op_spec(clause_name!("-"), None, &indices.op_dir);
+
+                self.heap.push(HeapCellValue::NamedStr(2, clause_name!("-"), spec));
+                self.heap.push(heap_integer!(Integer::from(start_line)));
+                self.heap.push(heap_integer!(Integer::from(end_line)));
+
+                let lines = Addr::Str(h);
+
+                let target = self[temp_v!(5)].clone();
+                self.unify(lines, target);
+            }
+            &SystemClauseType::ReadTermFromChars => {
+                let stub = MachineError::functor_stub(clause_name!("read_term_from_chars"), 3);
+                let chars = self[temp_v!(1)].clone();
+
+                let (result, error) = {
+                    let mut reader = CharsReader::new(self, chars);
+
+                    let result = {
+                        let mut stream = parsing_stream(&mut reader);
+                        let mut parser = Parser::new(
+                            &mut stream,
+                            indices.atom_tbl.clone(),
+                            self.machine_flags(),
+                        );
+
+                        parser.read_term(composite_op!(&indices.op_dir))
+                    };
+
+                    (result, reader.error.take())
+                };
+
+                if let Some(err) = error {
+                    return Err(self.error_form(err, stub));
+                }
+
+                let result = match result {
+                    Err(ParserError::UnexpectedEOF) => {
+                        Ok(Term::Constant(Cell::default(), atom!("end_of_file")))
+                    }
+                    result => result,
+                };
+
+                match result {
+                    Ok(mut term) => {
+                        indices.string_tbl.intern_term(&mut term);
+                        let term_write_result = write_term_to_heap(&term, self);
+
+                        let a2 = self[temp_v!(2)].clone();
+                        self.unify(Addr::HeapCell(term_write_result.heap_loc), a2);
+
+                        if self.fail {
+                            return Ok(());
+                        }
+
+                        let list_offset = self.var_eqs_list(term_write_result.var_dict, indices);
+                        let a3 = self[temp_v!(3)].clone();
+
+                        self.unify(list_offset, a3);
+                    }
+                    Err(err) => {
+                        let h = self.heap.h();
+                        let err = MachineError::syntax_error(h, err);
+
+                        return Err(self.error_form(err, stub));
+                    }
+                }
             }
             &SystemClauseType::ResetBlock => {
                 let addr = self.deref(self[temp_v!(1)].clone());
@@ -2535,6 +5347,20 @@ impl MachineState {
                 let seed = match seed {
                     Addr::Con(Constant::Integer(n)) =>
                         n,
+                    Addr::Con(Constant::Atom(ref atom, _)) if atom.as_str() == "random" => {
+                        // a seed differing from run to run, unless runs
+                        // are reproducible.
+                        if self.reproducible {
+                            Integer::from(0)
+                        } else {
+                            let nanos = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map(|d| d.as_nanos() as u64)
+                                .unwrap_or(0);
+
+                            Integer::from(nanos)
+                        }
+                    }
                     Addr::Con(Constant::CharCode(c)) =>
                         Integer::from(c),
                     Addr::Con(Constant::Rational(r)) => {
@@ -2553,6 +5379,8 @@ impl MachineState {
 
                 let mut rand = RANDOM_STATE.borrow_mut();
                 rand.seed(&seed);
+
+                *RANDOM_DRAWS.borrow_mut() = 0;
             }
             &SystemClauseType::SkipMaxList =>
                 if let Err(err) = self.skip_max_list() {
@@ -2606,7 +5434,7 @@ impl MachineState {
             &SystemClauseType::Succeed => {}
             &SystemClauseType::TermVariables => {
                 let a1 = self[temp_v!(1)].clone();
-                let mut seen_vars = IndexSet::new();
+                let mut seen_vars = self.scratch.take_vars();
 
                 for item in self.acyclic_pre_order_iter(a1) {
                     match item {
@@ -2619,17 +5447,69 @@ impl MachineState {
                     }
                 }
 
-                let outcome = Addr::HeapCell(self.heap.to_list(seen_vars.into_iter()));
+                let outcome = Addr::HeapCell(self.heap.to_list(seen_vars.iter().cloned()));
+                self.scratch.give_vars(seen_vars);
 
                 let a2 = self[temp_v!(2)].clone();
                 self.unify(a2, outcome);
             }
+            &SystemClauseType::TmpFile => {
+                let base = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Atom(name, _)) => name,
+                    _ => unreachable!(),
+                };
+
+                let dir = env::temp_dir();
+
+                // the process id keeps the names of concurrently
+                // running machines apart.
+                let path = loop {
+                    let n = TMP_FILE_COUNT.fetch_add(1, atomic::Ordering::Relaxed);
+                    let path = dir.join(format!("scryer_{}_{}_{}", process::id(), n, base.as_str()));
+
+                    if !path.exists() {
+                        break path;
+                    }
+                };
+
+                let path = clause_name!(path.to_string_lossy().to_string(), indices.atom_tbl);
+                let a2 = self[temp_v!(2)].clone();
+
+                self.unify(Addr::Con(Constant::Atom(path, None)), a2);
+            }
             &SystemClauseType::TruncateLiftedHeapTo => {
                 match self.store(self.deref(self[temp_v!(1)].clone())) {
                     Addr::Con(Constant::Usize(lh_offset)) => self.lifted_heap.truncate(lh_offset),
                     _ => self.fail = true,
                 }
             }
+            &SystemClauseType::Unifiable => {
+                let a1 = self[temp_v!(1)].clone();
+                let a2 = self[temp_v!(2)].clone();
+
+                match self.unifier(a1, a2) {
+                    Some(bindings) => {
+                        let mut equations = self.scratch.take_addrs();
+
+                        for (var, value) in bindings {
+                            let h = self.heap.h();
+
+                            self.heap.push(HeapCellValue::NamedStr(2, clause_name!("="), None));
+                            self.heap.push(HeapCellValue::Addr(var));
+                            self.heap.push(HeapCellValue::Addr(value));
+
+                            equations.push(Addr::Str(h));
+                        }
+
+                        let list = Addr::HeapCell(self.heap.to_list(equations.drain(..)));
+                        self.scratch.give_addrs(equations);
+
+                        let unifier = self[temp_v!(3)].clone();
+                        self.unify(list, unifier);
+                    }
+                    None => self.fail = true,
+                }
+            }
             &SystemClauseType::UnifyWithOccursCheck => {
                 let a1 = self[temp_v!(1)].clone();
                 let a2 = self[temp_v!(2)].clone();
@@ -2653,10 +5533,34 @@ impl MachineState {
                 }
             }
             &SystemClauseType::UnwindStack => self.unwind_stack(),
+            &SystemClauseType::Decidable => {
+                let a1 = self[temp_v!(1)].clone();
+                let a2 = self[temp_v!(2)].clone();
+
+                self.fail = !self.decidable_test(a1, a2);
+            }
+            &SystemClauseType::SubsumesTerm => {
+                let general = self[temp_v!(1)].clone();
+                let specific = self[temp_v!(2)].clone();
+
+                self.fail = self.subsumes_test(general, specific);
+            }
             &SystemClauseType::Variant => self.fail = self.structural_eq_test(),
+            &SystemClauseType::VariantHash => {
+                let hash = self.variant_hash(self[temp_v!(1)].clone());
+                let a2 = self[temp_v!(2)].clone();
+
+                self.unify(Addr::Con(Constant::Integer(Integer::from(hash as usize))), a2);
+            }
             &SystemClauseType::WAMInstructions => {
-                let name = self[temp_v!(1)].clone();
-                let arity = self[temp_v!(2)].clone();
+                let module_name = self[temp_v!(1)].clone();
+                let name = self[temp_v!(2)].clone();
+                let arity = self[temp_v!(3)].clone();
+
+                let module_name = match self.store(self.deref(module_name)) {
+                    Addr::Con(Constant::Atom(module_name, _)) => module_name,
+                    _ => unreachable!(),
+                };
 
                 let name = match self.store(self.deref(name)) {
                     Addr::Con(Constant::Atom(name, _)) => name,
@@ -2664,34 +5568,29 @@ impl MachineState {
                 };
 
                 let arity = match self.store(self.deref(arity)) {
-                    Addr::Con(Constant::Integer(n)) => n,
+                    Addr::Con(Constant::Integer(n)) => n.to_usize().unwrap(),
                     _ => unreachable!(),
                 };
 
-                let first_idx = match indices
-                    .code_dir
-                    .get(&(name.clone(), arity.to_usize().unwrap()))
-                {
-                    Some(ref idx) => {
-                        if let Some(idx) = idx.local() {
-                            idx
-                        } else {
-                            let arity = arity.to_usize().unwrap();
-                            let stub = MachineError::functor_stub(name.clone(), arity);
-                            let h = self.heap.h();
+                let key = (name.clone(), arity);
 
-                            let err = MachineError::existence_error(
-                                h,
-                                ExistenceError::Procedure(name, arity),
-                            );
-                            let err = self.error_form(err, stub);
+                let idx_ptr = if module_name.as_str() == "user" {
+                    indices.code_dir.get(&key).map(|idx| idx.0.borrow().0)
+                } else {
+                    indices.modules.get(&module_name)
+                        .and_then(|module| module.code_dir.get(&key))
+                        .map(|idx| idx.0.borrow().0)
+                };
 
-                            self.throw_exception(err);
-                            return Ok(());
-                        }
+                let listing = match idx_ptr {
+                    Some(IndexPtr::Index(p)) => {
+                        self.wam_instructions(&code_repo.code, p)
                     }
-                    None => {
-                        let arity = arity.to_usize().unwrap();
+                    // a dynamic predicate without clauses has no code.
+                    Some(IndexPtr::DynamicUndefined) => {
+                        Addr::Con(Constant::EmptyList)
+                    }
+                    _ => {
                         let stub = MachineError::functor_stub(name.clone(), arity);
                         let h = self.heap.h();
 
@@ -2706,100 +5605,213 @@ impl MachineState {
                     }
                 };
 
-                let mut h = self.heap.h();
-                let mut functors = vec![];
+                let listing_var = self[temp_v!(4)].clone();
+                self.unify(listing, listing_var);
+            }
+            &SystemClauseType::MachineSnapshot => {
+                let from = match self.store(self.deref(self[temp_v!(1)].clone())) {
+                    Addr::Con(Constant::Integer(n)) => n.to_usize().unwrap_or(self.heap.h()),
+                    _ => unreachable!(),
+                };
+
+                let snapshot = self.machine_snapshot(from, indices.atom_tbl.clone());
+                let snapshot_var = self[temp_v!(2)].clone();
 
-                walk_code(
-                    &code_repo.code,
-                    first_idx,
-                    |instr| {
-                        let section = instr.to_functor(h);
-                        functors.push(Addr::HeapCell(h));
+                self.unify(snapshot, snapshot_var);
+            }
+            &SystemClauseType::PolicyRefs => {
+                // '$policy_refs'(Addrs, ChoicePoints) lists the heap
+                // addresses and choice points the policies refer to, as
+                // a collector would visit them.
+                let mut refs = PolicyRefCollector::default();
 
-                        h += section.len();
-                        self.heap.extend(section.into_iter());
-                    },
-                );
+                call_policy.visit_refs(&mut refs);
+                cut_policy.visit_refs(&mut refs);
 
-                let listing = Addr::HeapCell(self.heap.to_list(functors.into_iter()));
-                let listing_var = self[temp_v!(3)].clone();
+                let int = |b: usize| Addr::Con(Constant::Integer(Integer::from(b)));
 
-                self.unify(listing, listing_var);
+                let addrs = Addr::HeapCell(self.heap.to_list(refs.addrs.into_iter()));
+                let choice_points = refs.choice_points.into_iter().map(int);
+                let choice_points = Addr::HeapCell(self.heap.to_list(choice_points));
+
+                let a1 = self[temp_v!(1)].clone();
+                let a2 = self[temp_v!(2)].clone();
+
+                self.unify(addrs, a1);
+
+                if !self.fail {
+                    self.unify(choice_points, a2);
+                }
             }
-            &SystemClauseType::WriteTerm => {
-                let addr = self[temp_v!(1)].clone();
+            &SystemClauseType::PolicyStates => {
+                let call_state = self.call_policy_state_term(call_policy.state());
+                let cut_state = self.cut_policy_state_term(cut_policy.state());
 
-                let ignore_ops = self.store(self.deref(self[temp_v!(2)].clone()));
-                let numbervars = self.store(self.deref(self[temp_v!(3)].clone()));
-                let quoted = self.store(self.deref(self[temp_v!(4)].clone()));
-                let max_depth = self.store(self.deref(self[temp_v!(6)].clone()));
+                let a1 = self[temp_v!(1)].clone();
+                let a2 = self[temp_v!(2)].clone();
 
-                let mut printer = HCPrinter::new(&self, &indices.op_dir, PrinterOutputter::new());
+                self.unify(call_state, a1);
 
-                if let &Addr::Con(Constant::Atom(ref name, ..)) = &ignore_ops {
-                    printer.ignore_ops = name.as_str() == "true";
+                if !self.fail {
+                    self.unify(cut_state, a2);
                 }
+            }
+            &SystemClauseType::SetPolicyStates => {
+                // restores the policies from the states given by
+                // '$policy_states'/2, as when a saved state is loaded.
+                let stub = MachineError::functor_stub(clause_name!("$set_policy_states"), 2);
+
+                let a1 = self.store(self.deref(self[temp_v!(1)].clone()));
+                let a2 = self.store(self.deref(self[temp_v!(2)].clone()));
+
+                let call_state = match self.call_policy_state(a1.clone()) {
+                    Some(call_state) => call_state,
+                    None => {
+                        let err = MachineError::domain_error(DomainError::PolicyState, a1);
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                let cut_state = match self.cut_policy_state(a2.clone()) {
+                    Some(cut_state) => cut_state,
+                    None => {
+                        let err = MachineError::domain_error(DomainError::PolicyState, a2);
+                        return Err(self.error_form(err, stub));
+                    }
+                };
+
+                *call_policy = call_state.into_policy();
+                *cut_policy = cut_state.into_policy();
+            }
+            &SystemClauseType::PortrayClause => {
+                self.check_stream_type(
+                    current_output_stream,
+                    false,
+                    PermissionError::OutputStream,
+                    "portray_clause",
+                    1,
+                )?;
+
+                let addr = self[temp_v!(1)].clone();
+                let output = ClausePortrayer::new(self, &indices.op_dir).portray(addr);
+
+                if let Err(e) = write!(current_output_stream, "{}", output)
+                    .and_then(|_| current_output_stream.flush())
+                {
+                    let stub = MachineError::functor_stub(clause_name!("portray_clause"), 1);
+                    let stream = Addr::Stream(current_output_stream.clone());
+                    let err = MachineError::io_error(e.kind(), "write", stream);
 
-                if let &Addr::Con(Constant::Atom(ref name, ..)) = &numbervars {
-                    printer.numbervars = name.as_str() == "true";
+                    return Err(self.error_form(err, stub));
                 }
+            }
+            &SystemClauseType::PrintTerm => {
+                self.check_stream_type(
+                    current_output_stream,
+                    false,
+                    PermissionError::OutputStream,
+                    "print_term",
+                    2,
+                )?;
+
+                let stub = MachineError::functor_stub(clause_name!("print_term"), 2);
+
+                let right_margin = self.store(self.deref(self[temp_v!(7)].clone()));
+                let indent = self.store(self.deref(self[temp_v!(8)].clone()));
 
-                if let &Addr::Con(Constant::Atom(ref name, ..)) = &quoted {
-                    printer.quoted = name.as_str() == "true";
+                let mut outputter = LayoutOutputter::new();
+
+                outputter.column = current_output_stream.column() as usize;
+
+                match (right_margin, indent) {
+                    (Addr::Con(Constant::Integer(right_margin)), Addr::Con(Constant::Integer(indent))) => {
+                        match (right_margin.to_usize(), indent.to_usize()) {
+                            (Some(right_margin), Some(indent)) => {
+                                outputter.right_margin = right_margin;
+                                outputter.indent = indent;
+                            }
+                            _ => {
+                                self.fail = true;
+                                return Ok(());
+                            }
+                        }
+                    }
+                    _ => {
+                        self.fail = true;
+                        return Ok(());
+                    }
                 }
 
-                if let &Addr::Con(Constant::Integer(ref n)) = &max_depth {
-                    if let Some(n) = n.to_usize() {
-                        printer.max_depth = n;
-                    } else {
+                let output = match self.print_with_write_options(indices, stub.clone(), outputter)? {
+                    Some(output) => output,
+                    None => {
                         self.fail = true;
                         return Ok(());
                     }
+                };
+
+                if let Err(e) = write!(current_output_stream, "{}", output)
+                    .and_then(|_| current_output_stream.flush())
+                {
+                    let stream = Addr::Stream(current_output_stream.clone());
+                    let err = MachineError::io_error(e.kind(), "write", stream);
+
+                    return Err(self.error_form(err, stub));
                 }
+            }
+            &SystemClauseType::WriteTerm => {
+                let addr = self.store(self.deref(self[temp_v!(7)].clone()));
+                let mut stream = self.get_stream_or_alias(addr, indices, "write_term", 3)?;
 
-                let stub = MachineError::functor_stub(clause_name!("write_term"), 2);
+                let stub = MachineError::functor_stub(clause_name!("write_term"), 3);
 
-                match self.try_from_list(temp_v!(5), stub.clone()) {
-                    Ok(addrs) => {
-                        let mut var_names: IndexMap<Addr, String> = IndexMap::new();
-
-                        for addr in addrs {
-                            match addr {
-                                Addr::Str(s) => match &self.heap[s] {
-                                    &HeapCellValue::NamedStr(2, ref name, _)
-                                        if name.as_str() == "=" =>
-                                    {
-                                        let atom = self.heap[s + 1].as_addr(s + 1);
-                                        let var = self.heap[s + 2].as_addr(s + 2);
-
-                                        let atom = match self.store(self.deref(atom)) {
-                                            Addr::Con(Constant::Atom(atom, _)) => atom.to_string(),
-                                            Addr::Con(Constant::Char(c)) => c.to_string(),
-                                            _ => unreachable!(),
-                                        };
-
-                                        let var = self.store(self.deref(var));
-
-                                        if var_names.contains_key(&var) {
-                                            continue;
-                                        }
+                if !stream.is_output_stream() {
+                    let err = MachineError::permission_error(
+                        PermissionError::OutputStream,
+                        "stream",
+                        Addr::Stream(stream),
+                    );
 
-                                        var_names.insert(var, atom);
-                                    }
-                                    _ => unreachable!(),
-                                },
-                                _ => unreachable!(),
-                            }
-                        }
+                    return Err(self.error_form(err, stub));
+                }
 
-                        printer.var_names = var_names;
+                self.check_stream_type(
+                    &stream,
+                    false,
+                    PermissionError::OutputStream,
+                    "write_term",
+                    3,
+                )?;
+
+                let output = match self.print_with_write_options(indices, stub.clone(), PrinterOutputter::new())? {
+                    Some(output) => output,
+                    None => {
+                        self.fail = true;
+                        return Ok(());
                     }
-                    Err(err) => return Err(err),
+                };
+
+                if let Err(e) = write!(stream, "{}", output).and_then(|_| stream.flush()) {
+                    let err = MachineError::io_error(e.kind(), "write", Addr::Stream(stream));
+                    return Err(self.error_form(err, stub));
                 }
+            }
+            &SystemClauseType::WriteTermToChars => {
+                let stub = MachineError::functor_stub(clause_name!("write_term"), 2);
+
+                let output = match self.print_with_write_options(indices, stub, PrinterOutputter::new())? {
+                    Some(output) => output,
+                    None => {
+                        self.fail = true;
+                        return Ok(());
+                    }
+                };
+
+                let iter = output.chars().map(|c| Addr::Con(Constant::Char(c)));
+                let list_of_chars = Addr::HeapCell(self.heap.to_list(iter));
 
-                let output = printer.print(addr);
-                print!("{}", output.result());
-                stdout().flush().unwrap();
+                let a7 = self[temp_v!(7)].clone();
+                self.unify(a7, list_of_chars);
             }
         };
 
@@ -1,14 +1,17 @@
 use prolog_parser::ast::*;
 use prolog_parser::parser::*;
 
+use crate::prolog::machine::dcg::*;
 use crate::prolog::machine::machine_indices::HeapCellValue;
 use crate::prolog::machine::*;
-use crate::prolog::rug::ops::Pow;
-use crate::prolog::rug::Integer;
+use crate::prolog::bignum::ops::Pow;
+use crate::prolog::bignum::Integer;
 
 use std::cell::Cell;
 use std::collections::VecDeque;
 use std::iter::Rev;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::vec::IntoIter;
 
 pub fn fold_by_str<I>(terms: I, mut term: Term, sym: ClauseName) -> Term
@@ -47,14 +50,100 @@ fn extract_from_list(
     }
 }
 
+/* a source of terms over which a fresh parser is laid for each term
+ * read, so its position is kept here rather than in a parser.
+ * included files are read this way, as is the rest of a listing once
+ * a clause has been skipped for a syntax error, since skipping
+ * replaces the parsing stream holding the lexer's lookahead. */
+struct SourceFile {
+    source: Stream,
+    stream: ParsingStream<Stream>,
+    line_num: usize,
+    col_num: usize,
+}
+
+/* a file spliced into the term stream by an include/1 directive. */
+struct IncludedFile {
+    name: ClauseName,
+    path: PathBuf,
+    file: SourceFile,
+}
+
+impl SourceFile {
+    // source must have been made by Stream::with_block_strings.
+    fn new(source: Stream, line_num: usize) -> Self {
+        SourceFile {
+            stream: parsing_stream(source.clone()),
+            source,
+            line_num,
+            col_num: 0,
+        }
+    }
+
+    // skip the rest of the clause being read, past its end token.
+    fn skip_clause(&mut self) -> Result<(), ParserError> {
+        self.line_num += self.source.skip_clause().map_err(ParserError::IO)?;
+        self.col_num = 0;
+        self.stream = parsing_stream(self.source.clone());
+
+        Ok(())
+    }
+
+    // advance the position of the file past the text consumed by a
+    // parser laid over the file at its current position, given the
+    // parser's own position.
+    fn eof(&mut self, atom_tbl: TabledData<Atom>, flags: MachineFlags) -> Result<bool, ParserError> {
+        let mut parser = Parser::new(&mut self.stream, atom_tbl, flags);
+
+        let result = parser.devour_whitespace().and_then(|_| parser.eof());
+        let (line_num, col_num) = (parser.line_num(), parser.col_num());
+
+        let result = result.map_err(|err| self.offset_error(err));
+        self.advance(line_num, col_num);
+
+        result
+    }
+
+    fn advance(&mut self, line_num: usize, col_num: usize) {
+        if line_num > 0 {
+            self.line_num += line_num;
+            self.col_num = col_num;
+        } else {
+            self.col_num += col_num;
+        }
+    }
+
+    fn offset_error(&self, err: ParserError) -> ParserError {
+        let line_num = self.line_num;
+
+        match err {
+            ParserError::BackQuotedString(l, c) => ParserError::BackQuotedString(l + line_num, c),
+            ParserError::UnexpectedChar(ch, l, c) => ParserError::UnexpectedChar(ch, l + line_num, c),
+            ParserError::IncompleteReduction(l, c) => {
+                ParserError::IncompleteReduction(l + line_num, c)
+            }
+            ParserError::MissingQuote(l, c) => ParserError::MissingQuote(l + line_num, c),
+            ParserError::NonPrologChar(l, c) => ParserError::NonPrologChar(l + line_num, c),
+            ParserError::ParseBigInt(l, c) => ParserError::ParseBigInt(l + line_num, c),
+            ParserError::ParseFloat(l, c) => ParserError::ParseFloat(l + line_num, c),
+            ParserError::Utf8Error(l, c) => ParserError::Utf8Error(l + line_num, c),
+            err => err,
+        }
+    }
+}
+
 pub struct TermStream<'a> {
     stack: Vec<Term>,
     pub(crate) wam: &'a mut Machine,
     parser: Parser<'a, Stream>,
+    source: Stream, // the stream beneath parser.
+    resumed: Option<SourceFile>, // the rest of source, after a skipped clause.
+    syntax_errors: Vec<(Option<ClauseName>, ParserError)>, // included file name, error.
     pub(crate) flags: MachineFlags,
     term_expansion_lens: (usize, usize),
     goal_expansion_lens: (usize, usize),
-    top_level_terms: Vec<(Term, usize, usize)>, // term, line_num, col_num.
+    top_level_terms: Vec<(Term, Option<ClauseName>, usize, usize)>, // term, included file, line_num, col_num.
+    includes: Vec<IncludedFile>,
 }
 
 pub struct ExpansionAdditionResult {
@@ -91,6 +180,7 @@ impl<'a> Drop for TermStream<'a> {
 impl<'a> TermStream<'a> {
     pub fn new(
         src: &'a mut ParsingStream<Stream>,
+        source: Stream,
         atom_tbl: TabledData<Atom>,
         flags: MachineFlags,
         wam: &'a mut Machine,
@@ -105,13 +195,24 @@ impl<'a> TermStream<'a> {
                 .term_dir_entry_len((clause_name!("goal_expansion"), 2)),
             wam,
             parser: Parser::new(src, atom_tbl, flags),
+            source,
+            resumed: None,
+            syntax_errors: vec![],
             flags,
             top_level_terms: vec![],
+            includes: vec![],
         }
     }
 
+    // the syntax errors recovered from so far, with the names of the
+    // included files they occurred in.
     #[inline]
-    pub fn top_level_terms(&mut self) -> Vec<(Term, usize, usize)> {
+    pub fn take_syntax_errors(&mut self) -> Vec<(Option<ClauseName>, ParserError)> {
+        mem::replace(&mut self.syntax_errors, vec![])
+    }
+
+    #[inline]
+    pub fn top_level_terms(&mut self) -> Vec<(Term, Option<ClauseName>, usize, usize)> {
         mem::replace(&mut self.top_level_terms, vec![])
     }
 
@@ -132,12 +233,61 @@ impl<'a> TermStream<'a> {
 
     #[inline]
     pub fn line_num(&self) -> usize {
-        self.parser.line_num()
+        match self.includes.last().map(|file| &file.file).or(self.resumed.as_ref()) {
+            Some(file) => file.line_num,
+            None => self.parser.line_num(),
+        }
     }
 
     #[inline]
     pub fn col_num(&self) -> usize {
-        self.parser.col_num()
+        match self.includes.last().map(|file| &file.file).or(self.resumed.as_ref()) {
+            Some(file) => file.col_num,
+            None => self.parser.col_num(),
+        }
+    }
+
+    // the number of end tokens read from the current source.
+    #[inline]
+    fn clause_ends(&self) -> usize {
+        match self.includes.last().map(|file| &file.file).or(self.resumed.as_ref()) {
+            Some(file) => file.source.clause_ends(),
+            None => self.source.clause_ends(),
+        }
+    }
+
+    // splice the terms of the file at path into the stream, ahead of
+    // those remaining in the current file. path must be canonical.
+    pub fn include(&mut self, name: ClauseName, path: PathBuf, src: Stream) {
+        self.includes.push(IncludedFile {
+            name,
+            path,
+            file: SourceFile::new(src.with_block_strings(), 0),
+        });
+    }
+
+    // abandon the remaining terms of the innermost included file, as
+    // when it contains end_of_file. returns false if no included file
+    // is being read.
+    pub fn end_include(&mut self) -> bool {
+        self.includes.pop().is_some()
+    }
+
+    #[inline]
+    pub fn is_included(&self, path: &Path) -> bool {
+        self.includes.iter().any(|file| file.path == path)
+    }
+
+    // the directory of the file terms are currently read from, if it
+    // was included.
+    #[inline]
+    pub fn included_dir(&self) -> Option<PathBuf> {
+        self.includes.last().and_then(|file| file.path.parent().map(Path::to_path_buf))
+    }
+
+    #[inline]
+    pub fn included_file_name(&self) -> Option<ClauseName> {
+        self.includes.last().map(|file| file.name.clone())
     }
 
     #[inline]
@@ -163,8 +313,91 @@ impl<'a> TermStream<'a> {
 
     #[inline]
     pub fn eof(&mut self) -> Result<bool, ParserError> {
+        if !self.stack.is_empty() {
+            return Ok(false);
+        }
+
+        let atom_tbl = self.parser.get_atom_tbl();
+
+        while let Some(file) = self.includes.last_mut() {
+            if !file.file.eof(atom_tbl.clone(), self.flags)? {
+                return Ok(false);
+            }
+
+            self.includes.pop();
+        }
+
+        if let Some(ref mut file) = self.resumed {
+            return file.eof(atom_tbl, self.flags);
+        }
+
 	self.parser.devour_whitespace()?; // eliminate dangling comments before checking for EOF.
-        Ok(self.stack.is_empty() && self.parser.eof()?)
+        self.parser.eof()
+    }
+
+    // records err, a syntax error in the clause being read from the
+    // current source, and skips the rest of the clause, unless its
+    // end token was read before the error was found. clause_ends is
+    // the number of end tokens read before the clause. errors that
+    // don't arise from the text of the source are returned instead.
+    fn recover_from_syntax_error(
+        &mut self,
+        err: ParserError,
+        clause_ends: usize,
+    ) -> Result<(), ParserError> {
+        if err.line_and_col_num().is_none() {
+            return Err(err);
+        }
+
+        let skip = self.clause_ends() == clause_ends;
+        let included_file_name = self.included_file_name();
+
+        self.syntax_errors.push((included_file_name, err));
+
+        if !skip {
+            return Ok(());
+        }
+
+        if let Some(file) = self.includes.last_mut() {
+            return file.file.skip_clause();
+        }
+
+        if let Some(ref mut file) = self.resumed {
+            return file.skip_clause();
+        }
+
+        let mut file = SourceFile::new(self.source.clone(), self.parser.line_num());
+
+        file.skip_clause()?;
+        self.resumed = Some(file);
+
+        Ok(())
+    }
+
+    // reads a term from the innermost included file, or the rest of
+    // the listing after a skipped clause, if there is either.
+    fn read_included_term(&mut self, op_dir: &OpDir) -> Option<Result<Term, ParserError>> {
+        let atom_tbl = self.parser.get_atom_tbl();
+
+        let file = match self.includes.last_mut() {
+            Some(file) => &mut file.file,
+            None => self.resumed.as_mut()?,
+        };
+
+        let mut parser = Parser::new(&mut file.stream, atom_tbl, self.flags);
+
+        let result = parser.read_term(composite_op!(
+            false,
+            &self.wam.indices.op_dir,
+            op_dir
+        ));
+
+        let (line_num, col_num) = (parser.line_num(), parser.col_num());
+
+        let result = result.map_err(|err| file.offset_error(err));
+        file.advance(line_num, col_num);
+
+        Some(result)
     }
 
     pub fn rollback_expansion_code(&mut self) -> Result<ExpansionAdditionResult, ParserError> {
@@ -239,6 +472,9 @@ impl<'a> TermStream<'a> {
                     let term = self.parse_expansion_output(term_string.as_str(), op_dir)?;
                     self.enqueue_term(term)?;
                 }
+                None if is_grammar_rule(&term) => {
+                    return translate_grammar_rule(term);
+                }
                 None => {
                     return Ok(term);
                 }
@@ -254,20 +490,55 @@ impl<'a> TermStream<'a> {
                 return Ok(self.expand_term(term, op_dir)?);
             }
 
-            self.parser.reset();
+            let included_file_name = self.included_file_name();
 
             let line_num = self.line_num();
             let col_num = self.col_num();
 
-            let term = self.parser.read_term(composite_op!(
-                false,
-                &self.wam.indices.op_dir,
-                op_dir
-            ))?;
+            let included_file = self.includes.last().map(|file| file.path.clone());
+
+            if let Some(load_context) = self.wam.indices.load_contexts.last_mut() {
+                load_context.included_file = included_file;
+                load_context.term_line_num = Some(line_num + 1);
+            }
+
+            let clause_ends = self.clause_ends();
+
+            let result = match self.read_included_term(op_dir) {
+                Some(result) => result,
+                None => {
+                    self.parser.reset();
+
+                    self.parser.read_term(composite_op!(
+                        false,
+                        &self.wam.indices.op_dir,
+                        op_dir
+                    ))
+                }
+            };
+
+            let mut term = match result {
+                Ok(term) => term,
+                Err(err) => {
+                    self.recover_from_syntax_error(err, clause_ends)?;
+
+                    // the skipped clause may have been the last.
+                    if self.eof()? {
+                        return Ok(Term::Constant(
+                            Cell::default(),
+                            Constant::Atom(clause_name!("end_of_file"), None),
+                        ));
+                    }
+
+                    continue;
+                }
+            };
+
+            self.wam.indices.string_tbl.intern_term(&mut term);
 
             // preserve a copy of the original unexpanded term for warning scans,
             // if that stage is reached.
-            self.top_level_terms.push((term.clone(), line_num, col_num));
+            self.top_level_terms.push((term.clone(), included_file_name, line_num, col_num));
             self.stack.push(term);
         }
     }
@@ -355,6 +626,9 @@ impl MachineState {
         self.heap.push(HeapCellValue::Addr(Addr::HeapCell(h)));
         self[temp_v!(2)] = Addr::HeapCell(h);
 
+        // the names of the term's variables, for prolog_load_context/2.
+        self.heap_locs = term_write_result.var_dict.clone();
+
         let code = vec![call_clause!(ClauseType::Hook(hook), 2, 0, true)];
         wam.code_repo.cached_query = code;
 
@@ -367,6 +641,7 @@ impl MachineState {
             &mut wam.code_repo,
             &mut readline::input_stream(),
             &mut Stream::stdout(),
+            &mut None,
         );
 
         if self.fail || self.at_end_of_expansion {
@@ -0,0 +1,95 @@
+pub use crate::prolog::machine::copier::AttrVarPolicy;
+use crate::prolog::machine::machine_indices::TermPipeDir;
+
+use std::sync::Arc;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError, TrySendError};
+
+/* term pipes carry terms between machine instances in their fast
+ * term encoding. the two ends are Send, so they can be handed to
+ * machines running on other threads. the channel is bounded, so a
+ * sender blocks (or fails to send, if it declines to block) while
+ * the receiver lags capacity terms behind. */
+
+// pipe_create/3 accepts capacities up to this many terms.
+pub const MAX_TERM_PIPE_CAPACITY: usize = 65536;
+
+pub struct TermPipeSender {
+    sender: SyncSender<Vec<u8>>,
+    pub(super) attr_var_policy: AttrVarPolicy,
+    // shared by the two ends of a pipe, to tell them apart from the
+    // ends of other pipes.
+    pipe: Arc<()>,
+}
+
+pub struct TermPipeReceiver {
+    receiver: Receiver<Vec<u8>>,
+    pipe: Arc<()>,
+}
+
+pub enum TermPipeEnd {
+    Sender(TermPipeSender),
+    Receiver(TermPipeReceiver),
+}
+
+pub(super) enum TermPipeError {
+    Disconnected,
+    Empty,
+    Full,
+}
+
+pub fn term_pipe(
+    capacity: usize,
+    attr_var_policy: AttrVarPolicy,
+) -> (TermPipeSender, TermPipeReceiver) {
+    let (sender, receiver) = sync_channel(capacity);
+    let pipe = Arc::new(());
+
+    (
+        TermPipeSender { sender, attr_var_policy, pipe: pipe.clone() },
+        TermPipeReceiver { receiver, pipe },
+    )
+}
+
+impl TermPipeSender {
+    // true if the receiving end is among ends, in which case a
+    // blocking send to the full pipe would never return.
+    pub(super) fn is_local(&self, ends: &TermPipeDir) -> bool {
+        ends.values().any(|end| match end {
+            TermPipeEnd::Receiver(receiver) => Arc::ptr_eq(&self.pipe, &receiver.pipe),
+            _ => false,
+        })
+    }
+
+    pub(super) fn send(&self, bytes: Vec<u8>, block: bool) -> Result<(), TermPipeError> {
+        if block {
+            self.sender.send(bytes).map_err(|_| TermPipeError::Disconnected)
+        } else {
+            self.sender.try_send(bytes).map_err(|err| match err {
+                TrySendError::Full(_) => TermPipeError::Full,
+                TrySendError::Disconnected(_) => TermPipeError::Disconnected,
+            })
+        }
+    }
+}
+
+impl TermPipeReceiver {
+    // true if the sending end is among ends, in which case a
+    // blocking receive from the empty pipe would never return.
+    pub(super) fn is_local(&self, ends: &TermPipeDir) -> bool {
+        ends.values().any(|end| match end {
+            TermPipeEnd::Sender(sender) => Arc::ptr_eq(&self.pipe, &sender.pipe),
+            _ => false,
+        })
+    }
+
+    pub(super) fn receive(&self, block: bool) -> Result<Vec<u8>, TermPipeError> {
+        if block {
+            self.receiver.recv().map_err(|_| TermPipeError::Disconnected)
+        } else {
+            self.receiver.try_recv().map_err(|err| match err {
+                TryRecvError::Empty => TermPipeError::Empty,
+                TryRecvError::Disconnected => TermPipeError::Disconnected,
+            })
+        }
+    }
+}
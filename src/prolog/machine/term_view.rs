@@ -0,0 +1,184 @@
+use prolog_parser::ast::*;
+
+use crate::prolog::machine::machine_indices::*;
+use crate::prolog::machine::machine_state::*;
+use crate::prolog::bignum::{Integer, Rational};
+
+/* term views are borrowed views of terms in place on the heap (or
+ * stack, or argument registers) of a machine. they let hosts scan
+ * answers without materializing them as owned terms: nothing is
+ * copied or allocated, and since a view borrows the machine, it is
+ * valid only until the machine next runs. */
+
+#[derive(Clone, Copy)]
+enum Loc<'a> {
+    Addr(&'a Addr),
+    Heap(usize),
+}
+
+#[derive(Clone, Copy)]
+pub struct TermView<'a> {
+    machine_st: &'a MachineState,
+    loc: Loc<'a>,
+}
+
+pub enum TermRef<'a> {
+    Var,
+    Atom(&'a str),
+    Char(char),
+    CharCode(u32),
+    Integer(&'a Integer),
+    Rational(&'a Rational),
+    Float(f64),
+    // the text of a string, from its offset onwards.
+    String(&'a str),
+    // a partial string, its characters followed by a tail.
+    PartialString(&'a str, TermView<'a>),
+    EmptyList,
+    List(TermView<'a>, TermView<'a>),
+    Compound(&'a str, TermArgs<'a>),
+    // machine internals such as streams and clause references.
+    Opaque,
+}
+
+// the arguments of a compound term, in order.
+#[derive(Clone, Copy)]
+pub struct TermArgs<'a> {
+    machine_st: &'a MachineState,
+    next: usize,
+    end: usize,
+}
+
+impl<'a> TermArgs<'a> {
+    #[inline]
+    pub fn arity(&self) -> usize {
+        self.end - self.next
+    }
+}
+
+impl<'a> Iterator for TermArgs<'a> {
+    type Item = TermView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next < self.end {
+            let h = self.next;
+            self.next += 1;
+
+            Some(TermView { machine_st: self.machine_st, loc: Loc::Heap(h) })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.arity(), Some(self.arity()))
+    }
+}
+
+impl<'a> TermView<'a> {
+    #[inline]
+    pub(super) fn new(machine_st: &'a MachineState, addr: &'a Addr) -> Self {
+        TermView { machine_st, loc: Loc::Addr(addr) }
+    }
+
+    #[inline]
+    fn at_heap(&self, h: usize) -> Self {
+        TermView { machine_st: self.machine_st, loc: Loc::Heap(h) }
+    }
+
+    // dereferences the view, following bound variables to the term
+    // they're bound to.
+    pub fn get(&self) -> TermRef<'a> {
+        let machine_st = self.machine_st;
+        let mut loc = self.loc;
+
+        loop {
+            let addr = match loc {
+                Loc::Heap(h) => match &machine_st.heap[h] {
+                    HeapCellValue::Addr(ref addr) => {
+                        addr
+                    }
+                    HeapCellValue::NamedStr(arity, ref name, _) => {
+                        let args = TermArgs { machine_st, next: h + 1, end: h + 1 + arity };
+                        return TermRef::Compound(name.as_str(), args);
+                    }
+                    HeapCellValue::PartialString(ref pstr) => {
+                        return TermRef::PartialString(pstr.block_as_str(), self.at_heap(h + 1));
+                    }
+                },
+                Loc::Addr(addr) => {
+                    addr
+                }
+            };
+
+            loc = match addr {
+                Addr::AttrVar(h) | Addr::HeapCell(h) => match &machine_st.heap[*h] {
+                    HeapCellValue::Addr(ref inner) if inner == addr => {
+                        return TermRef::Var;
+                    }
+                    _ => {
+                        Loc::Heap(*h)
+                    }
+                },
+                Addr::StackCell(fr, sc) => {
+                    let inner = &machine_st.stack.index_and_frame(*fr)[*sc];
+
+                    if inner == addr {
+                        return TermRef::Var;
+                    }
+
+                    Loc::Addr(inner)
+                }
+                Addr::Str(s) => {
+                    Loc::Heap(*s)
+                }
+                Addr::Lis(l) => {
+                    return TermRef::List(self.at_heap(*l), self.at_heap(*l + 1));
+                }
+                Addr::PStrLocation(h, n) => match &machine_st.heap[*h] {
+                    HeapCellValue::PartialString(ref pstr) => {
+                        return TermRef::PartialString(&pstr.block_as_str()[*n ..], self.at_heap(*h + 1));
+                    }
+                    _ => {
+                        unreachable!()
+                    }
+                },
+                Addr::Con(ref c) => {
+                    return match c {
+                        Constant::Atom(ref name, _) => TermRef::Atom(name.as_str()),
+                        Constant::Char(c) => TermRef::Char(*c),
+                        Constant::CharCode(c) => TermRef::CharCode(*c),
+                        Constant::EmptyList => TermRef::EmptyList,
+                        Constant::Float(f) => TermRef::Float(f.0),
+                        Constant::Integer(ref n) => TermRef::Integer(n),
+                        Constant::Rational(ref r) => TermRef::Rational(r),
+                        Constant::String(n, ref s) => TermRef::String(&s[*n ..]),
+                        _ => TermRef::Opaque,
+                    };
+                }
+                Addr::DBRef(_) | Addr::Stream(_) => {
+                    return TermRef::Opaque;
+                }
+            };
+        }
+    }
+
+    #[inline]
+    pub fn is_var(&self) -> bool {
+        if let TermRef::Var = self.get() {
+            true
+        } else {
+            false
+        }
+    }
+
+    // the nth argument of a compound term, counting from 1.
+    pub fn arg(&self, n: usize) -> Option<TermView<'a>> {
+        match self.get() {
+            TermRef::Compound(_, mut args) if n > 0 => args.nth(n - 1),
+            TermRef::List(head, _) if n == 1 => Some(head),
+            TermRef::List(_, tail) if n == 2 => Some(tail),
+            _ => None,
+        }
+    }
+}
@@ -13,8 +13,10 @@ use indexmap::{IndexMap, IndexSet};
 use std::borrow::BorrowMut;
 use std::cell::Cell;
 use std::collections::VecDeque;
+use std::fs::File;
 use std::mem;
 use std::ops::DerefMut;
+use std::path::Path;
 use std::rc::Rc;
 
 enum IndexSource<'a, T> {
@@ -109,6 +111,16 @@ impl<'a, 'b, 'c> CompositeIndices<'a, 'b, 'c> {
         }
     }
 
+    // true if name/arity resolves to the predicate of the builtins
+    // module, rather than to one of the same name defined in or
+    // imported by the module being compiled.
+    fn resolves_to_builtin(&mut self, name: ClauseName, arity: usize) -> bool {
+        match self.get_clause_type(name, arity, None) {
+            ClauseType::Named(_, _, idx) => idx.0.borrow().1.as_str() == "builtins",
+            _ => false,
+        }
+    }
+
     fn add_in_situ_module_info(&mut self, module_name: ClauseName, term: &mut Term)
     {
         let atom_tbl =
@@ -342,18 +354,48 @@ fn setup_use_module_decl(mut terms: Vec<Box<Term>>) -> Result<ModuleSource, Pars
     }
 }
 
-fn setup_double_quotes(mut terms: Vec<Box<Term>>) -> Result<DoubleQuotes, ParserError> {
-    let dbl_quotes = *terms.pop().unwrap();
+fn setup_prolog_flag(mut terms: Vec<Box<Term>>) -> Result<PrologFlag, ParserError> {
+    let value = *terms.pop().unwrap();
     
     match terms[0].as_ref() {
         Term::Constant(_, Constant::Atom(ref name, _))
             if name.as_str() == "double_quotes" => {
-                match dbl_quotes {
+                match value {
+                    Term::Constant(_, Constant::Atom(name, _)) => {
+                        match name.as_str() {
+                            "atom"  => Ok(PrologFlag::DoubleQuotes(DoubleQuotes::Atom)),
+                            "chars" => Ok(PrologFlag::DoubleQuotes(DoubleQuotes::Chars)),
+                            "codes" => Ok(PrologFlag::DoubleQuotes(DoubleQuotes::Codes)),
+                            _ => Err(ParserError::InvalidDoubleQuotesDecl),
+                        }
+                    }
+                    _ => {
+                        Err(ParserError::InvalidDoubleQuotesDecl)
+                    }
+                }
+            },
+        Term::Constant(_, Constant::Atom(ref name, _))
+            if name.as_str() == "block_strings" => {
+                match value {
                     Term::Constant(_, Constant::Atom(name, _)) => {
                         match name.as_str() {
-                            "atom"  => Ok(DoubleQuotes::Atom),
-                            "chars" => Ok(DoubleQuotes::Chars),
-                            "codes" => Ok(DoubleQuotes::Codes),
+                            "true"  => Ok(PrologFlag::BlockStrings(true)),
+                            "false" => Ok(PrologFlag::BlockStrings(false)),
+                            _ => Err(ParserError::InvalidDoubleQuotesDecl),
+                        }
+                    }
+                    _ => {
+                        Err(ParserError::InvalidDoubleQuotesDecl)
+                    }
+                }
+            },
+        Term::Constant(_, Constant::Atom(ref name, _))
+            if name.as_str() == "quasi_quotations" => {
+                match value {
+                    Term::Constant(_, Constant::Atom(name, _)) => {
+                        match name.as_str() {
+                            "true"  => Ok(PrologFlag::QuasiQuotations(true)),
+                            "false" => Ok(PrologFlag::QuasiQuotations(false)),
                             _ => Err(ParserError::InvalidDoubleQuotesDecl),
                         }
                     }
@@ -480,6 +522,22 @@ fn mark_cut_variables(terms: &mut Vec<Term>) -> bool {
     found_cut_var
 }
 
+fn is_soft_cut(term: &Term) -> bool {
+    match term {
+        Term::Clause(_, ref name, ref subterms, _) => {
+            name.as_str() == "*->" && subterms.len() == 2
+        }
+        _ => false,
+    }
+}
+
+fn is_unbound_goal(term: &Term) -> bool {
+    match term {
+        Term::Var(_, ref v) => v.as_str() != "!",
+        _ => false,
+    }
+}
+
 // terms is a list of goals composing one clause in a (;) functor. it
 // checks that the first (and only) of these clauses is a ->. if so,
 // it expands its terms using a blocked_!.
@@ -650,7 +708,7 @@ fn setup_declaration<'a, 'b, 'c>(
 		    Ok(Declaration::NonCountedBacktracking(name, arity))
 		}
                 ("set_prolog_flag", 2) => {
-                    Ok(Declaration::SetPrologFlag(setup_double_quotes(terms)?))
+                    Ok(Declaration::SetPrologFlag(setup_prolog_flag(terms)?))
                 }
                 ("multifile", 1) => {
                     let mut term = *terms.pop().unwrap();
@@ -817,6 +875,19 @@ impl RelationWorker {
                 Ok(QueryTerm::UnblockedCut(Cell::default()))
             }
             Term::Clause(r, name, mut terms, fixity) => match (name.as_str(), terms.len()) {
+                (";", 2) if is_soft_cut(&terms[0]) => {
+                    // (If *-> Then ; Else) is compiled as a call to if/3.
+                    let else_term = terms.pop().unwrap();
+
+                    if let Some(Term::Clause(_, _, mut subterms, _)) = terms.pop().map(|t| *t) {
+                        subterms.push(else_term);
+
+                        let term = Term::Clause(r, clause_name!("if"), subterms, None);
+                        self.to_query_term(indices, term)
+                    } else {
+                        unreachable!()
+                    }
+                }
                 (";", 2) => {
                     let term = Term::Clause(r, name.clone(), terms, fixity);
                     let (stub, clauses) = self.fabricate_disjunct(term);
@@ -833,7 +904,22 @@ impl RelationWorker {
                     self.queue.push_back(clauses);
                     Ok(QueryTerm::Jump(stub))
                 }
+                ("\\+", 1) if is_unbound_goal(&terms[0]) => {
+                    // leave the instantiation error to \+/1 itself.
+                    let ct = indices.get_clause_type(name, 1, fixity);
+                    Ok(QueryTerm::Clause(Cell::default(), ct, terms, false))
+                }
                 ("\\+", 1) => {
+                    // call/1 gives the goal its own cut barrier.
+                    let goal = terms.pop().unwrap();
+
+                    terms.push(Box::new(Term::Clause(
+                        Cell::default(),
+                        clause_name!("call"),
+                        vec![goal],
+                        None,
+                    )));
+
                     terms.push(Box::new(Term::Constant(
                         Cell::default(),
                         Constant::Atom(clause_name!("$fail"), None)
@@ -905,6 +991,7 @@ impl RelationWorker {
         let mut query_terms = vec![];
         let mut work_queue = VecDeque::from(terms);
         let mut machine_st = MachineState::new();
+        let mut level_count = 0;
 
         while let Some(term) = work_queue.pop_front() {
             let term = *term;
@@ -932,6 +1019,38 @@ impl RelationWorker {
                             work_queue.push_front(Box::new(subterm));
                         }
 
+                        continue;
+                    } else if name.as_str() == "deterministic" && terms.len() == 1
+                        && indices.resolves_to_builtin(name.clone(), 1)
+                    {
+                        // compare the cut barrier of the clause against
+                        // the current choice point, held in a variable
+                        // fresh to each deterministic/1 goal.
+                        let level = Rc::new(format!("$deterministic_level{}", level_count));
+                        let level = Term::Var(Cell::default(), level);
+
+                        level_count += 1;
+
+                        let get_level = Term::Clause(
+                            Cell::default(),
+                            clause_name!("$get_level"),
+                            vec![Box::new(level.clone())],
+                            None,
+                        );
+
+                        let mut terms = terms;
+                        terms.insert(0, Box::new(level));
+
+                        let is_deterministic_answer = Term::Clause(
+                            Cell::default(),
+                            clause_name!("$is_deterministic_answer"),
+                            terms,
+                            None,
+                        );
+
+                        work_queue.push_front(Box::new(is_deterministic_answer));
+                        work_queue.push_front(Box::new(get_level));
+
                         continue;
                     } else {
                         term = Term::Clause(cell, name, terms, op_spec);
@@ -1131,8 +1250,31 @@ pub type DynamicClause = Vec<(Term, Term)>;
 
 pub type DynamicClauseMap = IndexMap<(ClauseName, usize), DynamicClause>;
 
+// the file named by an include/1 directive, if term is one.
+fn included_file(term: &Term) -> Option<Result<ClauseName, SessionError>> {
+    match term {
+        Term::Clause(_, ref name, ref terms, _) if name.as_str() == ":-" && terms.len() == 1 => {
+            match terms[0].as_ref() {
+                Term::Clause(_, ref name, ref terms, _)
+                    if name.as_str() == "include" && terms.len() == 1 =>
+                {
+                    match terms[0].as_ref() {
+                        Term::Constant(_, Constant::Atom(ref filename, _)) => {
+                            Some(Ok(filename.clone()))
+                        }
+                        _ => Some(Err(SessionError::InvalidIncludeDecl)),
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 pub struct TopLevelBatchWorker<'a> {
     pub(crate) term_stream: TermStream<'a>,
+    listing_src: ListingSource,
     rel_worker: RelationWorker,
     pub(crate) results: Vec<(Predicate, VecDeque<TopLevel>)>,
     pub(crate) dynamic_clause_map: DynamicClauseMap,
@@ -1145,17 +1287,20 @@ pub struct TopLevelBatchWorker<'a> {
 impl<'a> TopLevelBatchWorker<'a> {
     pub fn new(
         stream: &'a mut ParsingStream<Stream>,
+        source: Stream,
         atom_tbl: TabledData<Atom>,
         flags: MachineFlags,
         wam: &'a mut Machine,
+        listing_src: ListingSource,
     ) -> Self {
-        let term_stream = TermStream::new(stream, atom_tbl, flags, wam);
+        let term_stream = TermStream::new(stream, source, atom_tbl, flags, wam);
 
         let line_num = term_stream.line_num();
         let col_num  = term_stream.col_num();
 
         TopLevelBatchWorker {
             term_stream,
+            listing_src,
             rel_worker: RelationWorker::new(flags, line_num, col_num),
             results: vec![],
             dynamic_clause_map: IndexMap::new(),
@@ -1166,6 +1311,41 @@ impl<'a> TopLevelBatchWorker<'a> {
         }
     }
 
+    // splice the terms of an included file into the term stream, so
+    // that they are read as if they had replaced the include/1
+    // directive. the file is resolved relative to the file containing
+    // the directive, which may itself have been included.
+    fn include_file(&mut self, filename: ClauseName) -> Result<(), SessionError> {
+        let mut path_buf = self.term_stream.included_dir()
+            .unwrap_or_else(|| self.listing_src.path());
+
+        path_buf.push(filename.as_str());
+
+        let atom_tbl = self.term_stream.wam.indices.atom_tbl.clone();
+        let path_buf = fix_filename(atom_tbl.clone(), path_buf)?;
+        let filename = clause_name!(path_buf.to_string_lossy().to_string(), atom_tbl);
+
+        let path_buf = path_buf.canonicalize()
+            .map_err(|_| SessionError::InvalidFileName(filename.clone()))?;
+
+        let includes_self = match &self.listing_src {
+            ListingSource::File(name, _) => {
+                Path::new(name.as_str()).canonicalize().ok().as_ref() == Some(&path_buf)
+            }
+            ListingSource::User => false,
+        };
+
+        if includes_self || self.term_stream.is_included(&path_buf) {
+            return Err(SessionError::IncludeCycle(filename));
+        }
+
+        let file = File::open(&path_buf)
+            .map_err(|_| SessionError::InvalidFileName(filename.clone()))?;
+
+        self.term_stream.include(filename, path_buf, Stream::from(file));
+        Ok(())
+    }
+
     fn try_term_to_tl(
         &mut self,
         indices: &mut IndexStore,
@@ -1249,7 +1429,22 @@ impl<'a> TopLevelBatchWorker<'a> {
 
         while !self.term_stream.eof()? {
             let term = self.term_stream.read_term(&indices.op_dir)?;
-            
+
+            // includes are spliced in here rather than processed as
+            // declarations so that clauses of a predicate on either
+            // side of an include/1 directive remain contiguous.
+            if let Some(filename) = included_file(&term) {
+                self.include_file(filename?)?;
+                continue;
+            }
+
+            // end_of_file in an included file ends only that file.
+            if let Term::Constant(_, Constant::Atom(ref name, _)) = &term {
+                if name.as_str() == "end_of_file" && self.term_stream.end_include() {
+                    continue;
+                }
+            }
+
             // if is_consistent is false, preds is non-empty.
             let term = if !term.is_consistent(&preds) {
                 self.process_result(indices, &mut preds)?;
@@ -1,3 +1,20 @@
+// logs a machine event under the scryer_prolog target when the
+// logging feature is enabled. otherwise the arguments are discarded
+// unevaluated.
+#[cfg(feature = "logging")]
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)+) => {
+        log::$level!(target: "scryer_prolog", $($arg)+)
+    };
+}
+
+#[cfg(not(feature = "logging"))]
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)+) => {
+        ()
+    };
+}
+
 macro_rules! interm {
     ($n: expr) => {
         ArithmeticTerm::Interm($n)
@@ -223,16 +240,25 @@ macro_rules! set_code_index {
 macro_rules! index_store {
     ($atom_tbl:expr, $code_dir:expr, $op_dir:expr, $modules:expr) => {
         IndexStore {
+            atom_ids: AtomIds::new(),
             atom_tbl: $atom_tbl,
             code_dir: $code_dir,
             module_dir: ModuleDir::new(),
             dynamic_code_dir: DynamicCodeDir::new(),
             global_variables: GlobalVarDir::new(),
+            host_callbacks: HostCallbackDir::new(),
             in_situ_code_dir: InSituCodeDir::new(),
             in_situ_module_dir: ModuleStubDir::new(),
+            load_contexts: vec![],
+            module_call_cache: ModuleCallCache::default(),
             op_dir: $op_dir,
+            op_dir_snapshot: OpDirSnapshot::default(),
+            open_streams: OpenStreamDir::new(),
             modules: $modules,
+            retired_code: vec![],
             stream_aliases: StreamAliasDir::new(),
+            string_tbl: StringTable::new(),
+            term_pipes: TermPipeDir::new(),
         }
     };
 }
@@ -5,6 +5,7 @@ extern crate prolog_parser;
 extern crate rug;
 #[cfg(feature = "num-rug-adapter")]
 extern crate num_rug_adapter as rug;
+#[cfg(feature = "repl")]
 extern crate rustyline;
 
 #[macro_use]
@@ -14,6 +15,7 @@ pub mod instructions;
 #[macro_use]
 mod allocator;
 mod arithmetic;
+mod bignum;
 mod codegen;
 mod debray_allocator;
 mod fixtures;
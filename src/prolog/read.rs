@@ -7,7 +7,9 @@ use crate::prolog::iterators::*;
 use crate::prolog::machine::machine_indices::*;
 use crate::prolog::machine::machine_state::MachineState;
 use crate::prolog::machine::streams::Stream;
+use crate::prolog::bignum::Integer;
 
+use std::cell::Cell;
 use std::collections::VecDeque;
 
 type SubtermDeque = VecDeque<(usize, usize)>;
@@ -27,60 +29,215 @@ pub type PrologStream = ParsingStream<Stream>;
 
 pub mod readline {
     use crate::prolog::machine::streams::Stream;
+    #[cfg(feature = "repl")]
     use crate::prolog::rustyline::error::ReadlineError;
+    #[cfg(feature = "repl")]
     use crate::prolog::rustyline::{Cmd, Editor, KeyPress};
+    use crate::ref_thread_local::RefThreadLocal;
     use std::io::{Cursor, Read};
+    #[cfg(not(feature = "repl"))]
+    use std::io::{BufRead, Write};
+
+    // the prompt shown before the next line of input is read: the
+    // query prompt at the start of a toplevel query, the continuation
+    // prompt on the following lines of a query, and otherwise none.
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub enum Prompt {
+        None,
+        Query,
+        Continuation,
+    }
+
+    static mut PROMPT: Prompt = Prompt::None;
 
-    static mut PROMPT: bool = false;
+    ref_thread_local! {
+        static managed PROMPT_TEXT: (String, String) = (String::from("?- "), String::new());
+    }
 
-    pub fn set_prompt(value: bool) {
+    pub fn set_prompt(prompt: Prompt) {
         unsafe {
-            PROMPT = value;
+            PROMPT = prompt;
         }
     }
 
     #[inline]
-    fn get_prompt() -> &'static str {
-        unsafe {
-            if PROMPT { "?- " } else { "" }
+    fn get_prompt() -> Prompt {
+        unsafe { PROMPT }
+    }
+
+    // the text shown for prompt, set by the toplevel_prompt and
+    // toplevel_continuation_prompt flags.
+    pub fn prompt_text(prompt: Prompt) -> String {
+        let prompt_text = PROMPT_TEXT.borrow();
+
+        match prompt {
+            Prompt::None => String::new(),
+            Prompt::Query => prompt_text.0.clone(),
+            Prompt::Continuation => prompt_text.1.clone(),
+        }
+    }
+
+    pub fn set_prompt_text(prompt: Prompt, text: String) {
+        let mut prompt_text = PROMPT_TEXT.borrow_mut();
+
+        match prompt {
+            Prompt::None => {}
+            Prompt::Query => prompt_text.0 = text,
+            Prompt::Continuation => prompt_text.1 = text,
         }
     }
 
+    // standard input, read a line at a time. with the repl feature,
+    // lines are read through a line editor keeping the history of
+    // queries; without it, the prompt is written to standard output
+    // and lines are read as they are.
     pub struct ReadlineStream {
+        #[cfg(feature = "repl")]
         rl: Editor<()>,
         pending_input: Cursor<String>,
     }
 
     impl ReadlineStream {
+        #[cfg(feature = "repl")]
         pub fn input_stream(pending_input: String) -> Stream {
             let mut rl = Editor::<()>::new();
             rl.bind_sequence(KeyPress::Tab, Cmd::Insert(1, "\t".to_string()));
             Stream::from(ReadlineStream { rl, pending_input: Cursor::new(pending_input) })
         }
 
-        fn call_readline(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-            match self.rl.readline(get_prompt()) {
+        #[cfg(not(feature = "repl"))]
+        pub fn input_stream(pending_input: String) -> Stream {
+            Stream::from(ReadlineStream { pending_input: Cursor::new(pending_input) })
+        }
+
+        // the next line of input, without its line terminator, or None
+        // at the end of the input.
+        #[cfg(feature = "repl")]
+        fn next_line(&mut self, prompt: Prompt) -> std::io::Result<Option<String>> {
+            match self.rl.readline(&prompt_text(prompt)) {
                 Ok(text) => {
+                    if prompt == Prompt::Query {
+                        self.rl.history_mut().add(&text);
+                    }
+
+                    Ok(Some(text))
+                }
+                Err(ReadlineError::Eof) => {
+                    Ok(None)
+                }
+                Err(e) => {
+                    Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+                }
+            }
+        }
+
+        #[cfg(not(feature = "repl"))]
+        fn next_line(&mut self, prompt: Prompt) -> std::io::Result<Option<String>> {
+            let mut stdout = std::io::stdout();
+
+            stdout.write_all(prompt_text(prompt).as_bytes())?;
+            stdout.flush()?;
+
+            let mut text = String::new();
+
+            if std::io::stdin().lock().read_line(&mut text)? == 0 {
+                return Ok(None);
+            }
+
+            if text.ends_with('\n') {
+                text.pop();
+
+                if text.ends_with('\r') {
+                    text.pop();
+                }
+            }
+
+            Ok(Some(text))
+        }
+
+        // returns false at the end of the input.
+        fn read_line(&mut self) -> std::io::Result<bool> {
+            let prompt = get_prompt();
+
+            match self.next_line(prompt)? {
+                Some(text) => {
                     *self.pending_input.get_mut() = text;
                     self.pending_input.set_position(0);
 
-                    unsafe {
-                        if PROMPT {
-                            self.rl.history_mut().add(self.pending_input.get_ref());
-                            PROMPT = false;
-                        }
+                    if prompt == Prompt::Query {
+                        set_prompt(Prompt::None);
                     }
 
                     *self.pending_input.get_mut() += "\n";
-                    self.pending_input.read(buf)
+                    Ok(true)
                 }
-                Err(ReadlineError::Eof) => {
-                    Ok(0)
+                None => {
+                    Ok(false)
                 }
-                Err(e) => {
-                    Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+            }
+        }
+
+        fn call_readline(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.read_line()? {
+                self.pending_input.read(buf)
+            } else {
+                Ok(0)
+            }
+        }
+
+        // if the next line of input is a command under prefix, as
+        // command_text finds it, consumes the line and returns the
+        // text of the command. a new line is
+        // read first if the pending input holds nothing but layout and
+        // a line comment, such as one following the end of the last
+        // query, otherwise the pending input is left for the reader.
+        pub fn read_command(&mut self, prefix: &str) -> std::io::Result<Option<String>> {
+            let pos = self.pending_input.position() as usize;
+
+            let pending_is_blank = match self.pending_input.get_ref().get(pos ..) {
+                Some(rest) => {
+                    let rest = rest.trim_start();
+                    rest.is_empty() || rest.starts_with('%')
                 }
+                None => true,
+            };
+
+            if pending_is_blank && !self.read_line()? {
+                return Ok(None);
             }
+
+            let pos = self.pending_input.position() as usize;
+
+            let command = match self.pending_input.get_ref().get(pos ..) {
+                Some(rest) => match command_text(rest, prefix) {
+                    Some(command) => command,
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            };
+
+            let len = self.pending_input.get_ref().len();
+            self.pending_input.set_position(len as u64);
+
+            Ok(Some(command))
+        }
+    }
+
+    // the text of the toplevel command on line, if line starts with
+    // prefix followed by the name of a command. a query or directive
+    // also starting with prefix, such as :- G with the default prefix,
+    // isn't a command.
+    pub fn command_text(line: &str, prefix: &str) -> Option<String> {
+        if prefix.is_empty() {
+            return None;
+        }
+
+        let command = line.trim_start().strip_prefix(prefix)?;
+
+        if command.starts_with(char::is_alphabetic) {
+            Some(command.trim().to_string())
+        } else {
+            None
         }
     }
 
@@ -106,16 +263,82 @@ pub mod readline {
 }
 
 impl MachineState {
+    /* strings on the heap are interpreted by the current value of
+     * the double_quotes flag, so those of a term read under another
+     * value are replaced by the lists or atoms they denote. the term
+     * is returned with the lines on which it begins and ends, counted
+     * from 0 at the position of inner. */
     pub fn read(
         &mut self,
         inner: &mut PrologStream,
         atom_tbl: TabledData<Atom>,
         op_dir: &OpDir,
-    ) -> Result<TermWriteResult, ParserError> {
-        let mut parser = Parser::new(inner, atom_tbl, self.flags);
-        let term = parser.read_term(composite_op!(op_dir))?;
+        string_tbl: &mut StringTable,
+        double_quotes: DoubleQuotes,
+    ) -> Result<(TermWriteResult, (usize, usize)), ParserError> {
+        let mut flags = self.flags;
+        flags.double_quotes = double_quotes;
+
+        let mut parser = Parser::new(inner, atom_tbl.clone(), flags);
 
-        Ok(write_term_to_heap(&term, self))
+        parser.devour_whitespace()?;
+        let start_line = parser.line_num();
+
+        let mut term = parser.read_term(composite_op!(op_dir))?;
+        let end_line = parser.line_num();
+
+        match (double_quotes, self.flags.double_quotes) {
+            (DoubleQuotes::Atom, DoubleQuotes::Atom)
+          | (DoubleQuotes::Chars, DoubleQuotes::Chars)
+          | (DoubleQuotes::Codes, DoubleQuotes::Codes) => {}
+            _ => expand_strings(&mut term, double_quotes, &atom_tbl),
+        }
+
+        string_tbl.intern_term(&mut term);
+
+        Ok((write_term_to_heap(&term, self), (start_line, end_line)))
+    }
+}
+
+fn expand_strings(term: &mut Term, double_quotes: DoubleQuotes, atom_tbl: &TabledData<Atom>) {
+    let mut terms = vec![term];
+
+    while let Some(term) = terms.pop() {
+        if let Term::Constant(_, Constant::String(n, ref s)) = *term {
+            let expanded = string_term(&s[n ..], double_quotes, atom_tbl);
+            *term = expanded;
+            continue;
+        }
+
+        match term {
+            Term::Clause(_, _, ref mut subterms, _) => {
+                terms.extend(subterms.iter_mut().map(|subterm| subterm.as_mut()));
+            }
+            Term::Cons(_, ref mut head, ref mut tail) => {
+                terms.push(head.as_mut());
+                terms.push(tail.as_mut());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn string_term(s: &str, double_quotes: DoubleQuotes, atom_tbl: &TabledData<Atom>) -> Term {
+    let empty_list = Term::Constant(Cell::default(), Constant::EmptyList);
+
+    match double_quotes {
+        DoubleQuotes::Atom => {
+            let name = clause_name!(s.to_string(), atom_tbl.clone());
+            Term::Constant(Cell::default(), Constant::Atom(name, None))
+        }
+        DoubleQuotes::Chars => s.chars().rev().fold(empty_list, |tail, c| {
+            let c = Term::Constant(Cell::default(), Constant::Char(c));
+            Term::Cons(Cell::default(), Box::new(c), Box::new(tail))
+        }),
+        DoubleQuotes::Codes => s.chars().rev().fold(empty_list, |tail, c| {
+            let c = Term::Constant(Cell::default(), Constant::Integer(Integer::from(c as u32)));
+            Term::Cons(Cell::default(), Box::new(c), Box::new(tail))
+        }),
     }
 }
 
@@ -1,25 +1,161 @@
 :- use_module(library(lists)).
 :- use_module(library(si)).
 
-:- module('$toplevel', ['$repl'/1, consult/1, use_module/1, use_module/2]).
+:- module('$toplevel', ['$repl'/1, '$autoload'/3, consult/1, create_module/3,
+                        use_module/1, use_module/2]).
 
 '$repl'(ListOfModules) :-
     maplist('$use_list_of_modules', ListOfModules),
     false.
-'$repl'(_) :- '$repl'.
+'$repl'(_) :-
+    '$print_banner',
+    '$repl'.
+
+'$print_banner' :-
+    current_prolog_flag(toplevel_banner, Banner),
+    (  Banner == '' ->
+       true
+    ;  write(Banner),
+       nl
+    ).
 
 '$use_list_of_modules'(Module) :-
     catch(use_module(Module), E, '$print_exception'(E)).
 
 '$repl' :-
-    catch('$read_and_match', E, '$print_exception'(E)),
+    catch(catch('$read_and_match', E, '$print_exception'(E)),
+          '$aborted',
+          '$abort_to_toplevel'),
     false. %% this is for GC, until we get actual GC.
 '$repl' :-
     '$repl'.
 
 '$read_and_match' :-
-    '$read_query_term'(Term, VarList),
-    '$instruction_match'(Term, VarList).
+    current_prolog_flag(toplevel_command_prefix, Prefix),
+    (  '$read_toplevel_command'(Prefix, Command) ->
+       '$run_toplevel_command'(Prefix, Command)
+    ;  '$read_query_term'(Term, VarList),
+       '$instruction_match'(Term, VarList)
+    ).
+
+% toplevel commands, read from lines starting with the
+% toplevel_command_prefix flag, ':' by default.
+
+'$run_toplevel_command'(Prefix, Command) :-
+    atom_chars(Command, Chars),
+    '$command_name_and_argument'(Chars, NameChars, Arg),
+    atom_chars(Name, NameChars),
+    (  '$toplevel_command'(Name, Prefix, Arg) -> true
+    ;  print_message(informational, unknown_toplevel_command(Prefix, Name))
+    ).
+
+'$command_name_and_argument'([], [], []).
+'$command_name_and_argument'([C | Cs], NameChars, Arg) :-
+    (  '$layout_char'(C) ->
+       NameChars = [],
+       '$skip_layout'(Cs, Arg)
+    ;  NameChars = [C | NameChars0],
+       '$command_name_and_argument'(Cs, NameChars0, Arg)
+    ).
+
+'$skip_layout'([], []).
+'$skip_layout'([C | Cs], Arg) :-
+    (  '$layout_char'(C) -> '$skip_layout'(Cs, Arg)
+    ;  Arg = [C | Cs]
+    ).
+
+'$layout_char'(' ').
+'$layout_char'('\t').
+
+'$toplevel_command'(help, Prefix, []) :-
+    '$toplevel_command_help'(Name, Usage, Description),
+    atom_length(Name, N),
+    atom_length(Usage, U),
+    write('   '), write(Prefix), write(Name), write(' '), write(Usage),
+    Pad is 20 - N - U,
+    '$write_spaces'(Pad),
+    write(Description), nl,
+    false.
+'$toplevel_command'(help, _, []).
+'$toplevel_command'(load, _, Arg) :-
+    Arg \== [],
+    atom_chars(File, Arg),
+    consult(File).
+'$toplevel_command'(listing, _, Arg) :-
+    Arg \== [],
+    '$read_command_argument'(Arg, Spec),
+    (  Spec = Name/Arity -> true
+    ;  atom(Spec) -> Name = Spec
+    ;  throw(error(type_error(predicate_indicator, Spec), listing/1))
+    ),
+    (  atom(Name), current_predicate(Name/Arity),
+       '$list_predicate'(Name, Arity),
+       false
+    ;  true
+    ).
+'$toplevel_command'(flags, _, []) :-
+    current_prolog_flag(Flag, Value),
+    write('   '), writeq(Flag), write(' = '), writeq(Value), nl,
+    false.
+'$toplevel_command'(flags, _, []).
+'$toplevel_command'(type, _, Arg) :-
+    Arg \== [],
+    '$read_command_argument'(Arg, Term),
+    '$term_type'(Term, Type),
+    write('   '), writeq(Type), nl.
+'$toplevel_command'(export, _, Arg) :-
+    '$command_name_and_argument'(Arg, FormatChars, Arg0),
+    '$command_name_and_argument'(Arg0, FileChars, QueryChars),
+    FormatChars \== [],
+    FileChars \== [],
+    QueryChars \== [],
+    atom_chars(Format, FormatChars),
+    atom_chars(File, FileChars),
+    '$read_term_from_chars'(QueryChars, Goal, VarList),
+    (  '$module_exists'(result_export) -> true
+    ;  use_module(library(result_export), [])
+    ),
+    result_export:findall_export(Format, VarList, Goal, file(File)).
+
+'$toplevel_command_help'(help, '', 'list the toplevel commands').
+'$toplevel_command_help'(load, 'File', 'consult File').
+'$toplevel_command_help'(listing, 'Name[/Arity]', 'list the clauses of a dynamic predicate').
+'$toplevel_command_help'(flags, '', 'show the prolog flags and their values').
+'$toplevel_command_help'(type, 'Term', 'show the type of Term').
+'$toplevel_command_help'(export, 'json|csv File Query',
+                         'write the solutions of Query to File').
+
+'$write_spaces'(N) :-
+    (  N > 0 -> write(' '), N1 is N - 1, '$write_spaces'(N1)
+    ;  write(' ')
+    ).
+
+'$read_command_argument'(Arg, Term) :-
+    '$read_term_from_chars'(Arg, Term, _).
+
+'$list_predicate'(Name, Arity) :-
+    functor(Head, Name, Arity),
+    (  '$head_is_dynamic'(Head) ->
+       write(':- dynamic('), writeq(Name/Arity), write(').'), nl, nl,
+       (  clause(Head, Body),
+          '$portray_clause'((Head :- Body)),
+          false
+       ;  nl
+       )
+    ;  write('% '), writeq(Name/Arity), write(' is static, its clauses are not kept'), nl
+    ).
+
+'$term_type'(Term, Type) :-
+    (  var(Term) -> Type = var
+    ;  integer(Term) -> Type = integer
+    ;  float(Term) -> Type = float
+    ;  Term == [] -> Type = list
+    ;  atom(Term) -> Type = atom
+    ;  '$skip_max_list'(_, -1, Term, Tail), Tail == [] -> Type = list
+    ;  Term = [_ | _] -> Type = partial_list
+    ;  functor(Term, Name, Arity),
+       Type = compound(Name/Arity)
+    ).
 
 % make '$compile_batch', a system routine, callable.
 '$$compile_batch' :- '$compile_batch'.
@@ -46,13 +182,102 @@
     (  expand_goals(Term0, Term) -> true
     ;  Term0 = Term
     ),
-    (  '$get_b_value'(B), write('   '), call(Term), '$write_eqs_and_read_input'(B, VarList),
-       !
+    '$query_singletons'(VarList, Term0, Singletons),
+    '$warn_about_empty_dynamic_calls'(Term),
+    '$reset_query_stats',
+    (  '$get_b_value'(B), write('   '), call(Term),
+       '$query_stats'(Heap, Trail, Stack, ChoicePoints),
+       '$warn_about_unbound_singletons'(Singletons),
+       '$write_eqs_and_read_input'(B, VarList),
+       !,
+       '$print_query_stats'(Heap, Trail, Stack, ChoicePoints)
     %  clear attribute goal lists, which may be populated by
     %  copy_term/3 prior to failure.
-    ;  '$clear_attribute_goals', write('false.'), nl
+    ;  '$clear_attribute_goals', write('false.'), nl,
+       '$query_stats'(Heap, Trail, Stack, ChoicePoints),
+       '$print_query_stats'(Heap, Trail, Stack, ChoicePoints)
+    ).
+
+% the stats are taken as the last answer is found, before the
+% toplevel writes it.
+'$print_query_stats'(Heap, Trail, Stack, ChoicePoints) :-
+    (  current_prolog_flag(toplevel_print_query_stats, true) ->
+       print_message(informational, query_stats(Heap, Trail, Stack, ChoicePoints))
+    ;  true
+    ).
+
+% query warnings, for typos that would otherwise go unnoticed.
+
+% the variables named in the query, other than those named _Name,
+% that occur in it once.
+'$query_singletons'([], _, []).
+'$query_singletons'([Name = Var | VarList], Term, Singletons) :-
+    (  \+ atom_concat('_', _, Name),
+       '$occurrences'(Term, Var, 0, 1) ->
+       Singletons = [Name = Var | Singletons0]
+    ;  Singletons = Singletons0
+    ),
+    '$query_singletons'(VarList, Term, Singletons0).
+
+'$occurrences'(Term, Var, N0, N) :-
+    (  var(Term) ->
+       (  Term == Var -> N is N0 + 1
+       ;  N = N0
+       )
+    ;  compound(Term) ->
+       Term =.. [_ | Args],
+       '$occurrences_in_list'(Args, Var, N0, N)
+    ;  N = N0
     ).
 
+'$occurrences_in_list'([], _, N, N).
+'$occurrences_in_list'([Arg | Args], Var, N0, N) :-
+    '$occurrences'(Arg, Var, N0, N1),
+    '$occurrences_in_list'(Args, Var, N1, N).
+
+% a singleton left unbound by an answer is most likely misspelt.
+'$warn_about_unbound_singletons'(Singletons) :-
+    (  findall(Name, ( member(Name = Var, Singletons), var(Var) ), Names),
+       Names \== [] ->
+       nl,
+       print_message(warning, query_unbound_singletons(Names)),
+       write('   ')
+    ;  true
+    ).
+
+'$warn_about_empty_dynamic_calls'(Goal) :-
+    (  var(Goal) ->
+       true
+    ;  '$control_construct'(Goal, Goals) ->
+       maplist('$warn_about_empty_dynamic_calls', Goals)
+    ;  '$empty_dynamic_predicate'(Goal, PI) ->
+       print_message(warning, query_calls_empty_dynamic(PI))
+    ;  true
+    ).
+
+'$control_construct'((G1, G2), [G1, G2]).
+'$control_construct'((G1 ; G2), [G1, G2]).
+'$control_construct'((G1 -> G2), [G1, G2]).
+'$control_construct'((G1 *-> G2), [G1, G2]).
+'$control_construct'(\+ G, [G]).
+'$control_construct'(call(G), [G]).
+'$control_construct'(findall(_, G, _), [G]).
+
+'$empty_dynamic_predicate'(Module:Goal, Module:Name/Arity) :-
+    !,
+    atom(Module),
+    callable(Goal),
+    functor(Goal, Name, Arity),
+    functor(Head, Name, Arity),
+    '$module_head_is_dynamic'(Head, Module),
+    \+ clause(Module:Head, _).
+'$empty_dynamic_predicate'(Goal, Name/Arity) :-
+    callable(Goal),
+    functor(Goal, Name, Arity),
+    functor(Head, Name, Arity),
+    '$head_is_dynamic'(Head),
+    \+ clause(Head, _).
+
 '$needs_bracketing'(Value, Op) :-
     catch((functor(Value, F, _),
 	   current_op(EqPrec, EqSpec, Op),
@@ -108,19 +333,27 @@
        '$extend_var_list_'(Vs, N1, VarList, NewVarList0)
     ).
 
+% answers that would run past the right margin are written over
+% several lines.
+'$write_answer_term'(Term, VarList, MaxDepth) :-
+    print_term(Term, [quoted(true), numbervars(false), variable_names(VarList),
+                      max_depth(MaxDepth), right_margin(78)]).
+
 '$write_goal'(G, VarList, MaxDepth) :-
     (  G = (Var = Value) ->
        write(Var),
        write(' = '),
        (  '$needs_bracketing'(Value, (=)) ->
 	  write('('),
-	  write_term(Value, [quoted(true), variable_names(VarList), max_depth(MaxDepth)]),
+	  '$write_answer_term'(Value, VarList, MaxDepth),
 	  write(')')
-       ;  write_term(Value, [quoted(true), variable_names(VarList), max_depth(MaxDepth)])
+       ;  '$write_answer_term'(Value, VarList, MaxDepth)
        )
     ;  G == [] ->
        write('true')
-    ;  write_term(G, [quoted(true), variable_names(VarList), max_depth(MaxDepth)])
+    ;  G == '$elided_bindings' ->
+       write('...')
+    ;  '$write_answer_term'(G, VarList, MaxDepth)
     ).
 
 '$write_last_goal'(G, VarList, MaxDepth) :-
@@ -129,9 +362,9 @@
        write(' = '),
        (  '$needs_bracketing'(Value, (=)) ->
 	  write('('),
-	  write_term(Value, [quoted(true), variable_names(VarList), max_depth(MaxDepth)]),
+	  '$write_answer_term'(Value, VarList, MaxDepth),
 	  write(')')
-       ;  write_term(Value, [quoted(true), variable_names(VarList), max_depth(MaxDepth)]),
+       ;  '$write_answer_term'(Value, VarList, MaxDepth),
 	  (  '$trailing_period_is_ambiguous'(Value) ->
 	     write(' ')
 	  ;  true
@@ -139,7 +372,9 @@
        )
     ;  G == [] ->
        write('true')
-    ;  write_term(G, [quoted(true), variable_names(VarList), max_depth(MaxDepth)])
+    ;  G == '$elided_bindings' ->
+       write('...')
+    ;  '$write_answer_term'(G, VarList, MaxDepth)
     ).
 
 '$write_eq'((G1, G2), VarList, MaxDepth) :-
@@ -167,38 +402,98 @@
 '$write_eqs_and_read_input'(B, VarList) :-
     '$extend_var_list'(VarList, VarList, NewVarList),
     sort(NewVarList, SortedVarList),
-    '$get_b_value'(B0),
-    '$gather_goals'(SortedVarList, SortedVarList, Goals),
-    (  B0 == B ->
+    '$is_deterministic_answer'(B, Det),
+    '$gather_goals'(SortedVarList, SortedVarList, Goals0),
+    '$expand_answer'(Goals0, Goals),
+    (  Det == true ->
        (  Goals == [] ->
 	  write('true.'), nl
-       ;  thread_goals(Goals, ThreadedGoals, (',')),
-	  '$write_eq'(ThreadedGoals, NewVarList, 20),
+       ;  '$write_answer'(Goals, NewVarList, 20),
 	  write('.'),
 	  nl
        )
-    ;  thread_goals(Goals, ThreadedGoals, (',')),
-       '$write_eq'(ThreadedGoals, NewVarList, 20),
-       '$read_input'(ThreadedGoals, NewVarList)
+    ;  '$write_answer'(Goals, NewVarList, 20),
+       '$read_input'(Goals, NewVarList)
+    ).
+
+% a MaxDepth of 0 writes the answer in full, ignoring the
+% toplevel_max_bindings_shown and toplevel_max_atom_length flags.
+'$write_answer'(Goals0, VarList, MaxDepth) :-
+    (  MaxDepth == 0 ->
+       Goals = Goals0
+    ;  current_prolog_flag(toplevel_max_bindings_shown, MaxBindings),
+       current_prolog_flag(toplevel_max_atom_length, MaxLength),
+       '$shorten_atoms'(MaxLength, Goals0, Goals1),
+       '$take_bindings'(MaxBindings, Goals1, Goals)
+    ),
+    thread_goals(Goals, ThreadedGoals, (',')),
+    '$write_eq'(ThreadedGoals, VarList, MaxDepth).
+
+'$take_bindings'(infinite, Goals, Goals) :- !.
+'$take_bindings'(N, Goals0, Goals) :-
+    (  Goals0 == [] ->
+       Goals = []
+    ;  N =:= 0 ->
+       Goals = ['$elided_bindings']
+    ;  Goals0 = [G | Goals1],
+       Goals = [G | Goals2],
+       N1 is N - 1,
+       '$take_bindings'(N1, Goals1, Goals2)
+    ).
+
+'$shorten_atoms'(infinite, Goals, Goals) :- !.
+'$shorten_atoms'(MaxLength, Goals0, Goals) :-
+    (  acyclic_term(Goals0) ->
+       '$shorten_goals'(Goals0, MaxLength, Goals)
+    ;  Goals = Goals0
+    ).
+
+'$shorten_goals'([], _, []).
+'$shorten_goals'([G0 | Goals0], MaxLength, [G | Goals]) :-
+    (  G0 = (Var = Value0) ->
+       '$shorten_atoms_'(Value0, MaxLength, Value),
+       G = (Var = Value)
+    ;  '$shorten_atoms_'(G0, MaxLength, G)
+    ),
+    '$shorten_goals'(Goals0, MaxLength, Goals).
+
+'$shorten_atoms_'(Term0, MaxLength, Term) :-
+    (  atom(Term0), Term0 \== [] ->
+       (  atom_length(Term0, Length),
+	  Length > MaxLength ->
+	  sub_atom(Term0, 0, MaxLength, _, Prefix),
+	  atom_concat(Prefix, '...', Term)
+       ;  Term = Term0
+       )
+    ;  compound(Term0) ->
+       Term0 =.. [F | Args0],
+       '$shorten_args'(Args0, MaxLength, Args),
+       Term =.. [F | Args]
+    ;  Term = Term0
     ).
 
-'$read_input'(ThreadedGoals, NewVarList) :-
+'$shorten_args'([], _, []).
+'$shorten_args'([Arg0 | Args0], MaxLength, [Arg | Args]) :-
+    '$shorten_atoms_'(Arg0, MaxLength, Arg),
+    '$shorten_args'(Args0, MaxLength, Args).
+
+'$read_input'(Goals, NewVarList) :-
     '$raw_input_read_char'(C),
     (  C == w ->
        nl,
        write('   '),
-       '$write_eq'(ThreadedGoals, NewVarList, 0),
-       '$read_input'(ThreadedGoals, NewVarList)
+       '$write_answer'(Goals, NewVarList, 0),
+       '$read_input'(Goals, NewVarList)
     ;  C == p ->
        nl,
        write('   '),
-       '$write_eq'(ThreadedGoals, NewVarList, 20),
-       '$read_input'(ThreadedGoals, NewVarList)
+       '$write_answer'(Goals, NewVarList, 20),
+       '$read_input'(Goals, NewVarList)
     ;  C == (';') ->
        nl, write(';  '), false
     ;  C == h ->
        '$help_message',
-       '$read_input'(ThreadedGoals, NewVarList)
+       '$read_input'(Goals, NewVarList)
     ;  C == '.',
        nl, write(';  ...'), nl
     ).
@@ -232,17 +527,44 @@
 '$gather_goals'([Var = Value | Pairs], VarList, Goals) :-
     (  (  nonvar(Value)
        ;  '$is_a_different_variable'(Pairs, Value)
+       ;  current_prolog_flag(toplevel_elide_self_bindings, false)
        ) ->
        Goals = [Var = Value | Goals0],
        '$gather_goals'(Pairs, VarList, Goals0)
     ;  '$gather_goals'(Pairs, VarList, Goals)
     ).
 
-'$print_exception'(E) :-
-    write_term('caught: ', [quoted(false), max_depth(20)]),
-    writeq(E),
+% called by the machine in place of an undefined predicate exported
+% by Library, if the autoload flag is set. the predicate is imported
+% into Module, the module of the call site, and nowhere else.
+'$autoload'(Library, Module, Goal) :-
+    (  '$module_exists'(Library) -> true
+    ;  use_module(library(Library), [])
+    ),
+    functor(Goal, Name, Arity),
+    '$autoload_import'(Module, Library, Name, Arity),
+    Library:Goal.
+
+'$abort_to_toplevel' :-
+    '$reset_after_abort',
+    '$clear_attribute_goals',
+    write('% Execution aborted'),
     nl.
 
+% user:answer_expansion(Goals0, Goals) may rewrite the list of goals
+% describing an answer before it is printed. if the hook is undefined
+% or fails, the answer is printed as is.
+'$expand_answer'(Goals0, Goals) :-
+    (  once(current_predicate(answer_expansion/2)),
+       user:answer_expansion(Goals0, Goals1) ->
+       error:must_be(list, Goals1),
+       Goals = Goals1
+    ;  Goals = Goals0
+    ).
+
+'$print_exception'(E) :-
+    print_message(error, E).
+
 '$print_exception_with_check'(E) :-
     (  E = error(_, _:_) -> true % if the error source contains a line
                                  % number, a GNU-style error message
@@ -309,6 +631,36 @@ use_module(Module, QualifiedExports) :-
     ).
 
 
+%% create_module(+Module, +Exports, +Imports) creates Module at runtime,
+%% as though loaded from a file declaring it with the export list
+%% Exports and using the libraries of Imports, and imports it into
+%% user. the predicates of Exports are dynamic, so that clauses can
+%% be added to them by Module:assertz/1 and friends.
+
+create_module(Module, Exports, Imports) :-
+    (  var(Module) ->
+       throw(error(instantiation_error, create_module/3))
+    ;  \+ atom(Module) ->
+       throw(error(type_error(atom, Module), create_module/3))
+    ;  '$module_exists'(Module) ->
+       throw(error(permission_error(create, module, Module), create_module/3))
+    ;  \+ list_si(Exports) ->
+       throw(error(type_error(list, Exports), create_module/3))
+    ;  \+ list_si(Imports) ->
+       throw(error(type_error(list, Imports), create_module/3))
+    ;  maplist('$module_export'(create_module/3), Exports),
+       maplist('$library_name'(create_module/3), Imports),
+       '$create_module'(Module, Exports, Imports)
+    ).
+
+'$library_name'(Source, Library) :-
+    (  var(Library) ->
+       throw(error(instantiation_error, Source))
+    ;  atom(Library) ->
+       true
+    ;  throw(error(type_error(atom, Library), Source))
+    ).
+
 % expand goals in initialization directives.
 user:term_expansion(Term0, (:- initialization(ExpandedGoals))) :-
     nonvar(Term0),
@@ -27,6 +27,10 @@ impl fmt::Display for REPLCodePtr {
         match self {
             REPLCodePtr::CompileBatch =>
                 write!(f, "REPLCodePtr::CompileBatch"),
+            REPLCodePtr::CreateModule =>
+                write!(f, "REPLCodePtr::CreateModule"),
+            REPLCodePtr::GarbageCollectClauses =>
+                write!(f, "REPLCodePtr::GarbageCollectClauses"),
 	    REPLCodePtr::UseModule =>
 		write!(f, "REPLCodePtr::UseModule"),
 	    REPLCodePtr::UseQualifiedModule =>
@@ -263,9 +267,18 @@ impl fmt::Display for SessionError {
             &SessionError::CannotOverwriteImport(ref msg) => {
                 write!(f, "cannot overwrite import {}", msg)
             }
+            &SessionError::IncludeCycle(ref filename) => {
+                write!(f, "file {} includes itself", filename)
+            }
+            &SessionError::InIncludedFile(ref filename, ref e) => {
+                write!(f, "{} in included file {}", e, filename)
+            }
             &SessionError::InvalidFileName(ref filename) => {
                 write!(f, "filename {} is invalid", filename)
             }
+            &SessionError::InvalidIncludeDecl => {
+                write!(f, "include/1 expects an atom naming a file")
+            }
             &SessionError::ModuleNotFound => write!(f, "module not found."),
             &SessionError::ModuleDoesNotContainExport(ref module, ref key) => {
                 write!(
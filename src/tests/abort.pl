@@ -0,0 +1,29 @@
+:- module(tests_on_abort, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+test_queries_on_abort :-
+    bb_put(abort_cleanup, pending),
+    catch(setup_call_cleanup(true, abort, bb_put(abort_cleanup, done)),
+          '$aborted',
+          true),
+    bb_get(abort_cleanup, done),
+    bb_put(abort_cleanup, pending),
+    catch((setup_call_cleanup(true,
+                              member(_, [1, 2]),
+                              bb_put(abort_cleanup, done)),
+           abort),
+          '$aborted',
+          true),
+    bb_get(abort_cleanup, done),
+    bb_put(abort_recovery, pending),
+    catch(catch(abort, _, bb_put(abort_recovery, done)), '$aborted', true),
+    bb_get(abort_recovery, done),
+    catch((catch(abort, _, true), false), '$aborted', true),
+    catch((catch(abort, _, false), false), '$aborted', true),
+    catch(catch(abort, '$aborted', true), _, false),
+    catch(findall(X, (member(X, [1, 2]), abort), _), '$aborted', true),
+    findall(Y, member(Y, [a, b]), [a, b]).
+
+:- initialization(test_queries_on_abort).
@@ -0,0 +1,21 @@
+:- module(tests_on_answer_expansion, []).
+
+expand_answer(Goals0, Goals) :-
+    '$toplevel':'$expand_answer'(Goals0, Goals).
+
+test_queries_on_answer_expansion :-
+    expand_answer(['X' = 1], Gs0),
+    Gs0 == ['X' = 1],
+    assertz(user:answer_expansion(['X' = secret], ['X' = hidden])),
+    expand_answer(['X' = secret], Gs1),
+    Gs1 == ['X' = hidden],
+    expand_answer(['X' = 1], Gs2),
+    Gs2 == ['X' = 1],
+    retract(user:answer_expansion(_, _)),
+    assertz(user:answer_expansion(_, not_a_list)),
+    catch((expand_answer([], _), false),
+          error(type_error(list, not_a_list), _),
+          true),
+    retract(user:answer_expansion(_, _)).
+
+:- initialization(test_queries_on_answer_expansion).
@@ -0,0 +1,28 @@
+:- module(tests_on_atom_number, []).
+
+:- set_prolog_flag(double_quotes, chars).
+
+test_queries_on_atom_number :-
+    atom_number('12', 12),
+    atom_number('-3.5', N1), N1 =:= -3.5,
+    atom_number('0x1f', 31),
+    atom_number(A1, 42), A1 == '42',
+    \+ atom_number(abc, _),
+    \+ atom_number('12abc', _),
+    \+ atom_number('', _),
+    \+ atom_number('1', 2),
+    catch(atom_number(_, _), error(instantiation_error, _), true),
+    catch(atom_number(_, a), error(type_error(number, a), _), true),
+    catch(atom_number(f(x), _), error(type_error(atom, f(x)), _), true),
+    catch(atom_number('1', a), error(type_error(number, a), _), true),
+    number_string(N2, " 7 "), N2 == 7,
+    number_string(N3, "2.5e3"), N3 =:= 2500.0,
+    number_string(N4, [0'1, 0'0]), N4 == 10,
+    number_string(N5, '8'), N5 == 8,
+    \+ number_string(_, "seven"),
+    \+ number_string(_, "  "),
+    number_string(12, S1), S1 == "12",
+    catch(number_string(_, _), error(instantiation_error, _), true),
+    catch(number_string(_, f(x)), error(type_error(string, f(x)), _), true).
+
+:- initialization(test_queries_on_atom_number).
@@ -0,0 +1,39 @@
+:- module(tests_on_atom_table, []).
+
+:- use_module(library(files)).
+
+test_queries_on_atom_table :-
+    tmp_file('atom_table_term.bin', TF),
+    tmp_file('atom_table.bin', AF),
+    setup_call_cleanup(true,
+                       atom_table_queries(TF, AF),
+                       ( catch(delete_file(TF), _, true),
+                         catch(delete_file(AF), _, true) )).
+
+atom_table_queries(TF, AF) :-
+    T = f(atom_table_test, [b, 'Quoted atom', b], "str", g(X, X)),
+    setup_call_cleanup(open(TF, write, S0),
+                       fast_write(S0, T, [atom_ids(true)]),
+                       close(S0)),
+    setup_call_cleanup(open(AF, write, S1),
+                       save_atom_table(S1),
+                       close(S1)),
+    setup_call_cleanup(open(AF, read, S2),
+                       load_atom_table(S2),
+                       close(S2)),
+    setup_call_cleanup(open(TF, read, S3),
+                       fast_read(S3, T0),
+                       close(S3)),
+    T0 = f(atom_table_test, [b, 'Quoted atom', b], "str", g(Y, Z)),
+    Y == Z,
+    catch(fast_write(user_output, a, [atom_ids(yes)]),
+          error(domain_error(fast_write_option, atom_ids(yes)), fast_write/3),
+          true),
+    catch(fast_write(user_output, a, _),
+          error(instantiation_error, fast_write/3),
+          true),
+    catch(load_atom_table(_),
+          error(instantiation_error, load_atom_table/1),
+          true).
+
+:- initialization(test_queries_on_atom_table).
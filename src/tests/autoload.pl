@@ -0,0 +1,28 @@
+:- module(tests_on_autoload, []).
+
+keys(Pairs, Keys) :- pairs_keys(Pairs, Keys).
+
+% format_//2 is exported as a non-terminal, and autoloads as format_/4.
+formatted(Cs) :- format_("~w-~w", [a, b], Cs, []).
+
+test_queries_on_autoload :-
+    current_prolog_flag(autoload, Flag),
+    setup_call_cleanup(set_prolog_flag(autoload, true),
+                       autoload_queries,
+                       set_prolog_flag(autoload, Flag)),
+    imported_queries.
+
+autoload_queries :-
+    keys([a-1, b-2], [a, b]),
+    formatted("a-b").
+
+% the autoloaded predicates were imported into this module, and into
+% no other.
+imported_queries :-
+    keys([c-3], [c]),
+    formatted("a-b"),
+    catch((user:pairs_keys([a-1], _), false),
+          error(existence_error(procedure, pairs_keys/2), _),
+          true).
+
+:- initialization(test_queries_on_autoload).
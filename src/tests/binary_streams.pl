@@ -0,0 +1,40 @@
+:- module(tests_on_binary_streams, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+read_char(S, C) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_char(C), set_input(In)).
+
+test_queries_on_binary_streams :-
+    tmp_file('binary_streams_test.bin', F),
+    setup_call_cleanup(true, binary_stream_queries(F), catch(delete_file(F), _, true)).
+
+binary_stream_queries(F) :-
+    open(F, write, W, [type(binary)]),
+    stream_property(W, type(binary)),
+    put_byte(W, 0),
+    put_byte(W, 255),
+    catch(put_byte(W, 256), error(type_error(byte, 256), _), true),
+    catch(put_byte(W, _), error(instantiation_error, _), true),
+    close(W),
+    open(F, read, R, [type(binary)]),
+    peek_byte(R, 0),
+    get_byte(R, 0),
+    \+ get_byte(R, 0),
+    peek_byte(R, -1),
+    catch(get_byte(R, a), error(type_error(in_byte, a), _), true),
+    catch(read_char(R, _), error(permission_error(input, binary_stream, R), _), true),
+    close(R),
+    open(F, read, T),
+    stream_property(T, type(text)),
+    catch(get_byte(T, _), error(permission_error(input, text_stream, T), _), true),
+    catch(put_byte(T, 0), error(permission_error(output, stream, T), _), true),
+    close(T),
+    catch(open(F, read, _, [type(octets)]),
+          error(domain_error(stream_option, type(octets)), _),
+          true),
+    catch(open(F, read, _, foo), error(type_error(list, foo), _), true).
+
+:- initialization(test_queries_on_binary_streams).
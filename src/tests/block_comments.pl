@@ -0,0 +1,32 @@
+:- module(tests_on_block_comments, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_text(F, Text) :-
+    current_output(Out),
+    setup_call_cleanup(open(F, write, S),
+                       ( set_output(S), write(Text) ),
+                       ( set_output(Out), close(S) )).
+
+test_queries_on_block_comments :-
+    tmp_file('block_comments_test.pl', F1),
+    tmp_file('block_comments_test.pl', F2),
+    setup_call_cleanup(true,
+                       block_comment_queries(F1, F2),
+                       ( catch(delete_file(F1), _, true),
+                         catch(delete_file(F2), _, true) )).
+
+% the comments of consulted text are stripped by the reader in front
+% of the lexer, which also reports the line of an unterminated one.
+block_comment_queries(F1, F2) :-
+    write_text(F1, ':- module(block_comments_a, []).\nt(a). /* one\n/* two */ t(b) /**/ ./* three */\nt(f(/* x */1))./**/t(e).\n% last'),
+    use_module(F1),
+    findall(T, block_comments_a:t(T), Ts),
+    Ts == [a, b, f(1), e],
+    write_text(F2, ':- module(block_comments_b, []).\nf(x).\n/* unterminated\n'),
+    catch(use_module(F2), error(syntax_error(E), Context), true),
+    E == unterminated_block_comment,
+    Context = _:3.
+
+:- initialization(test_queries_on_block_comments).
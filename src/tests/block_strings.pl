@@ -0,0 +1,48 @@
+:- module(tests_on_block_strings, []).
+
+:- use_module(library(iso_ext)).
+
+:- set_prolog_flag(double_quotes, chars).
+:- set_prolog_flag(block_strings, true).
+
+template("""SELECT "name"
+FROM t WHERE path = 'C:\dir\'""").
+
+empty("""""").
+
+quoted("a""b", '"""', 0'", "\"").
+
+:- set_prolog_flag(block_strings, false).
+
+test_queries_on_block_strings :-
+    current_prolog_flag(block_strings, false),
+    template(T),
+    atom_chars(A, T),
+    A == 'SELECT "name"\nFROM t WHERE path = \'C:\\dir\\\'',
+    empty(E),
+    E == [],
+    quoted(Q1, Q2, Q3, Q4),
+    Q1 == "a\"b",
+    Q2 == '"""',
+    Q3 == 0'",
+    Q4 == ['"'],
+    catch(set_prolog_flag(block_strings, maybe),
+          error(domain_error(flag_value, block_strings + maybe), _), true),
+    read_queries.
+
+% terms read while the flag is set leave the rest of their stream to
+% be read.
+read_queries :-
+    open_memory_stream('s("""a\nb"""). t. u.', S, []),
+    setup_call_cleanup(set_prolog_flag(block_strings, true),
+                       ( read(S, T1), read_term(S, T2, []) ),
+                       set_prolog_flag(block_strings, false)),
+    read(S, T3),
+    read(S, T4),
+    close(S),
+    T1 = s(Cs), atom_chars(A, Cs), A == 'a\nb',
+    T2 == t,
+    T3 == u,
+    T4 == end_of_file.
+
+:- initialization(test_queries_on_block_strings).
@@ -46,6 +46,9 @@ test_queries_on_builtins :-
     \+ float([1,2,_]),
     \+ (X is 3 rdiv 4, float(X)),
     \+ \+ (X is 3 rdiv 4, rational(X)),
+    catch(_ is foo + 1, error(type_error(evaluable, foo/0), _), true),
+    catch(_ is f(1, 2, 3), error(type_error(evaluable, f/3), _), true),
+    catch(_ is _ + 1, error(instantiation_error, _), true),
     \+ rational(3),
     \+ rational(f(_)),
     \+ rational("sdfa"),
@@ -123,8 +126,30 @@ test_queries_on_builtins :-
     \+ \+ (variant([X,Y,Z], [V,W,Z])),
     \+ \+ (variant([X,Y,X], [V,W,V])),
     \+ \+ (g(B) = B, g(A) = A, variant(A, B)),
+    \+ \+ (variant_hash(f(X,Y,X), H), variant_hash(f(V,W,V), H)),
+    \+ (variant_hash(f(X,Y,X), H), variant_hash(f(V,W,W), H)),
+    \+ \+ (T = g(1, "ab"), variant_hash(h(T, T), H), variant_hash(h(g(1, [a,b]), g(1, "ab")), H)),
+    \+ \+ (G = g(X), acyclic_term(f(G, G))),
+    \+ \+ (A = f(A), catch(variant_hash(A, _), error(type_error(acyclic_term, _), _), true)),
+    subsumes_term(a, a),
+    \+ \+ subsumes_term(f(_,b), f(_,b)),
+    \+ subsumes_term(g(X,X), g(Y,Z)),
+    \+ subsumes_term(X, f(X)),
+    \+ \+ subsumes_term(f(X,Y), f(Z,Z)),
+    \+ subsumes_term(f(Z,Z), f(X,Y)),
+    \+ (subsumes_term(X, Y), X == Y),
+    \+ \+ (A = f(_, A), B = f(b, B), subsumes_term(A, B)),
+    \+ (A = f(_, A), B = f(b, B), subsumes_term(B, A)),
+    a ?= a,
+    a ?= b,
+    \+ \+ (X ?= X),
+    \+ (X ?= Y),
+    \+ \+ (f(X, a) ?= f(Y, b)),
+    \+ (f(X, a) ?= f(Y, a)),
     keysort([1-1,1-1],[1-1,1-1]),
     \+ \+ findall(Sorted, keysort([2-99,1-a,3-f(_),1-z,1-a,2-44],Sorted), [[1-a,1-z,1-a,2-99,2-44,3-f(_)]]),
-    \+ \+ findall(X, keysort([X-1,1-1],[2-1,1-1]), [2]).
+    \+ \+ findall(X, keysort([X-1,1-1],[2-1,1-1]), [2]),
+    number_codes(N, [0'1, 0'2]),
+    N == 12.
 
 :- initialization(test_queries_on_builtins).
@@ -49,6 +49,31 @@ test_queries_on_call_with_inference_limit :-
 	     [true,2],
 	     [true,3],
 	     [true,4],
-	     [!,5]]).
+	     [!,5]]),
+    call_with_inference_limit(catch(loop, _, true), 100, inference_limit_exceeded),
+    call_with_inference_limit(call_with_inference_limit(loop, 10, R3), 1000, R4),
+    R3 == inference_limit_exceeded, R4 == !,
+    call_with_inference_limit(call_with_inference_limit(loop, 1000, _), 50, R5),
+    R5 == inference_limit_exceeded,
+    catch(call_with_time_limit(0.1, catch(loop, _, true)), E1, true),
+    E1 == time_limit_exceeded,
+    catch(call_with_time_limit(0.1, call_with_inference_limit(loop, 100000000, _)), E2, true),
+    E2 == time_limit_exceeded,
+    call_with_time_limit(5, call_with_inference_limit(loop, 100, R6)),
+    R6 == inference_limit_exceeded,
+    % times too long to represent are no limit.
+    call_with_time_limit(1.0e300, true),
+    call_with_time_limit(100000000000000000000000000000, true),
+    (  infinite_float(Inf) ->
+       call_with_time_limit(Inf, true)
+    ;  true
+    ).
+
+loop :- loop.
+
+% an infinite float, if the arithmetic yields one.
+infinite_float(F) :-
+    catch(F is 1.0e308 * 10.0, _, false),
+    float(F).
 
 :- initialization(test_queries_on_call_with_inference_limit).
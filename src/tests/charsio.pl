@@ -0,0 +1,43 @@
+:- module(tests_on_charsio, []).
+
+:- use_module(library(charsio)).
+
+test_queries_on_charsio :-
+    chars_utf8bytes([a, '\xe9\'], Bs1),
+    Bs1 == [97, 195, 169],
+    chars_utf8bytes(Cs1, [97, 195, 169]),
+    Cs1 == [a, '\xe9\'],
+    chars_utf8bytes(Cs2, []),
+    Cs2 == [],
+    codes_bytes(Ds1, [226, 130, 172]),
+    atom_codes(A1, Ds1),
+    A1 == '\x20ac\',
+    codes_bytes(Ds1, Bs2),
+    Bs2 == [226, 130, 172],
+    catch(chars_utf8bytes(_, [255]), error(U1, _), true),
+    U1 = domain_error(utf8_bytes, _),
+    catch(chars_utf8bytes(_, [a]), error(U2, _), true),
+    U2 == type_error(byte, a),
+    catch(chars_utf8bytes(_, [97|_]), error(U3, _), true),
+    U3 == instantiation_error,
+    read_term_from_chars("foo(X, Y, X)", T1, [variable_names(VNs), variables(Vs)]),
+    T1 = foo(A, B, C),
+    A == C, A \== B,
+    VNs = ['Y' = Y, 'X' = X], X == A, Y == B,
+    Vs == [A, B],
+    read_term_from_chars("a + b.", T2, []),
+    T2 == a + b,
+    read_term_from_chars([f, '(', x, ')', ' ', '%', c], T3, []),
+    T3 == f(x),
+    read_term_from_chars("  ", T4, []),
+    T4 == end_of_file,
+    catch(read_term_from_chars([a|_], _, []), error(E1, _), true),
+    E1 == instantiation_error,
+    catch(read_term_from_chars([a, 1], _, []), error(E2, _), true),
+    E2 == type_error(character, 1),
+    catch(read_term_from_chars("f(", _, []), error(E3, _), true),
+    E3 = syntax_error(_),
+    catch(read_term_from_chars("a", _, [bad]), error(E4, _), true),
+    E4 == domain_error(read_option, bad).
+
+:- initialization(test_queries_on_charsio).
@@ -0,0 +1,36 @@
+:- module(tests_on_checkpoint, []).
+
+:- use_module(library(cont)).
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+:- dynamic(step/1).
+
+% sums the integers up to N, checkpointing after every third.
+sum_to(N, N, Acc, Acc) :- !.
+sum_to(I, N, Acc0, Acc) :-
+    I1 is I + 1,
+    Acc1 is Acc0 + I1,
+    assertz(step(I1)),
+    (  I1 mod 3 =:= 0 ->
+       checkpoint
+    ;  true
+    ),
+    sum_to(I1, N, Acc1, Acc).
+
+test_queries_on_checkpoint :-
+    tmp_file('checkpoint_test.bin', F),
+    setup_call_cleanup(true, checkpoint_queries(F), catch(delete_file(F), _, true)).
+
+checkpoint_queries(F) :-
+    call_checkpointed(sum_to(0, 7, 0, S), F),
+    S == 28,
+    findall(I, step(I), [1, 2, 3, 4, 5, 6, 7]),
+    retractall(step(_)),
+    resume_checkpoint(F, G),
+    G = sum_to(0, 7, 0, 28),
+    findall(I, step(I), [7]),
+    catch(call_checkpointed(true, _), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_checkpoint).
@@ -0,0 +1,34 @@
+:- module(tests_on_choice_points, []).
+
+:- use_module(library(diag)).
+:- use_module(library(lists)).
+
+p(1).
+p(2).
+p(3).
+
+q(a, 1).
+q(b, 2).
+q(a, 3).
+
+latest_choice_point(C) :-
+    choice_points([C | _]).
+
+test_queries_on_choice_points :-
+    p(X), X == 1,
+    latest_choice_point(choice_point(tests_on_choice_points:p/1, 2, TR)),
+    integer(TR),
+    p(Y), Y == 2,
+    latest_choice_point(choice_point(tests_on_choice_points:p/1, 3, _)),
+    once(( q(a, Z),
+           latest_choice_point(choice_point(tests_on_choice_points:q/2, 3, _)),
+           Z == 1 )),
+    once(( member(_, [a, b]),
+           latest_choice_point(choice_point(lists:member/2, 2, _)) )),
+    \+ latest_choice_point(choice_point(tests_on_choice_points:q/2, _, _)),
+    choice_points(Cs1),
+    once(p(_)),
+    choice_points(Cs2),
+    Cs1 == Cs2.
+
+:- initialization(test_queries_on_choice_points).
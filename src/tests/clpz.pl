@@ -0,0 +1,29 @@
+:- module(tests_on_clpz, []).
+
+:- use_module(library(clpz)).
+:- use_module(library(lists)).
+
+test_queries_on_clpz :-
+    X1 in 0..5, Y1 #= 2*X1 + 1, Y1 #> 6,
+    fd_dom(X1, D1), D1 == 3..5,
+    fd_dom(Y1, D2), D2 == 7..11,
+    X2 in 1..3 \/ 5..7, X2 #\= 2, fd_dom(X2, D3), D3 == 1\/3\/(5..7),
+    X3 in 1..10, X3 #>= 10, X3 == 10,
+    \+ (X4 in 1..3, X4 #> 3),
+    \+ (X5 in 0..9, 2*X5 #= 7),
+    X6 #= 3 + 4, X6 == 7,
+    X7 in 0..3, \+ X7 = 4, X7 = 2,
+    [A1, B1] ins 0..2, A1 #< B1, A1 = Z2, Z2 #> 0, A1 == 1, B1 == 2,
+    findall(X8-Y8, (X8 in 1..3, Y8 in 1..3, X8 #< Y8, label([X8, Y8])), Ps1),
+    Ps1 == [1-2, 1-3, 2-3],
+    findall(Xs1, (Xs1 = [_, _, _], Xs1 ins 1..3, all_different(Xs1), label(Xs1)), Ls1),
+    length(Ls1, 6),
+    \+ ([A2, B2, C2] ins 1..2, all_different([A2, B2, C2]), label([A2, B2, C2])),
+    findall(Z1, (Z1 in 0..20, X9 in 2..3, Y9 in 3..4, X9*Y9 #= Z1, label([X9, Y9])), Zs1),
+    Zs1 == [6, 8, 9, 12],
+    catch(label([_]), error(instantiation_error, _), true),
+    catch(_ in a, error(type_error(clpz_domain, a), _), true),
+    catch(a #= 1, error(type_error(clpz_expression, a), _), true),
+    catch((X10 in 1..2, X10 = b), error(type_error(integer, b), _), true).
+
+:- initialization(test_queries_on_clpz).
@@ -0,0 +1,56 @@
+:- module(tests_on_code_reloading, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+:- dynamic(counter/1).
+
+write_clauses(F, Clauses) :-
+    open(F, write, S),
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write_clauses_(Clauses), set_output(Out)),
+    close(S).
+
+write_clauses_([]).
+write_clauses_([Clause|Clauses]) :-
+    writeq(Clause),
+    write('.'),
+    nl,
+    write_clauses_(Clauses).
+
+load_clauses(F, Clauses) :-
+    write_clauses(F, Clauses),
+    consult(F).
+
+% reloads version/1 on reaching its first solution.
+reload_at_first(F, X) :-
+    (  X == 1 ->
+       load_clauses(F, [version(10), version(20), version(30)]),
+       garbage_collect_clauses,
+       statistics(retired_code, N),
+       N > 0
+    ;  true
+    ).
+
+test_queries_on_code_reloading :-
+    tmp_file('code_reloading_test.pl', F),
+    setup_call_cleanup(true, code_reloading_queries(F), catch(delete_file(F), _, true)).
+
+code_reloading_queries(F) :-
+    load_clauses(F, [version(1), version(2)]),
+    findall(X, (user:version(X), reload_at_first(F, X)), [1, 2]),
+    garbage_collect_clauses,
+    statistics(retired_code, 0),
+    findall(X, user:version(X), [10, 20, 30]),
+    assertz(counter(1)),
+    assertz(counter(2)),
+    garbage_collect_clauses,
+    findall(X, ( counter(X), ( X == 1 -> assertz(counter(3)), garbage_collect_clauses ; true ) ), [1, 2]),
+    findall(X, counter(X), [1, 2, 3]),
+    retract(counter(2)),
+    garbage_collect_clauses,
+    findall(X, clause(counter(X), true), [1, 3]),
+    statistics(retired_code, 0).
+
+:- initialization(test_queries_on_code_reloading).
@@ -0,0 +1,39 @@
+:- module(tests_on_continuations, []).
+
+:- use_module(library(cont)).
+
+step.
+
+% both clauses have a deep cut, whose barrier is kept in the first
+% permanent variable, and shift/1 after their first call. the chunk
+% of the continuation captured by shift/1 must hold the barrier and
+% every variable used after it.
+deep_cut(X, Y) :-
+    step,
+    !,
+    shift(one),
+    Y = X.
+
+shift_in_if_then_else(X, Y) :-
+    step,
+    (  X == 1 ->
+       shift(one)
+    ;  true
+    ),
+    Y = X.
+
+test_queries_on_continuations :-
+    reset(deep_cut(1, Y1), Ball1, Cont1),
+    Ball1 == one,
+    var(Y1),
+    Cont1 = cont(Goal1),
+    reset(Goal1, _, none),
+    Y1 == 1,
+    reset(shift_in_if_then_else(1, Y2), Ball2, Cont2),
+    Ball2 == one,
+    var(Y2),
+    Cont2 = cont(Goal2),
+    reset(Goal2, _, none),
+    Y2 == 1.
+
+:- initialization(test_queries_on_continuations).
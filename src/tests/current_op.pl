@@ -0,0 +1,20 @@
+:- module(tests_on_current_op, []).
+
+test_queries_on_current_op :-
+    current_op(200, xfy, ^),
+    \+ current_op(_, _, tests_on_current_op_op),
+    op(700, xfx, tests_on_current_op_op),
+    current_op(700, xfx, tests_on_current_op_op),
+    findall(P-T, current_op(P, T, tests_on_current_op_op), [700-xfx]),
+    % an enumeration begun before a change keeps the operators it
+    % began with.
+    findall(P, ( current_op(P, xfx, Op),
+                 Op == tests_on_current_op_op,
+                 op(0, xfx, tests_on_current_op_op) ),
+            [700]),
+    \+ current_op(_, _, tests_on_current_op_op),
+    op(100, fy, tests_on_current_op_op),
+    findall(P-T, current_op(P, T, tests_on_current_op_op), [100-fy]),
+    op(0, fy, tests_on_current_op_op).
+
+:- initialization(test_queries_on_current_op).
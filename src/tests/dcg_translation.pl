@@ -0,0 +1,44 @@
+:- module(tests_on_dcg_translation, []).
+
+greeting --> "hello", ws, name.
+
+ws --> [' '], !, ws.
+ws --> [].
+
+name --> "world".
+name --> "there".
+
+digits([D|Ds]) --> digit(D), ( digits(Ds) -> [] ; { Ds = [] } ).
+
+digit(D) --> [D], { char_code(D, C), C >= 0'0, C =< 0'9 }.
+
+% peeks at the next character, putting it back.
+peek(C), [C] --> [C].
+
+not_a --> \+ [a].
+
+either --> [a] | [b].
+
+with_call(G) --> call(G, x).
+
+x(x, [x|S], S).
+
+test_queries_on_dcg_translation :-
+    phrase(greeting, "hello  world"),
+    \+ phrase(greeting, "helloworlds"),
+    phrase(digits(Ds), "123", Rest),
+    Ds == "123",
+    Rest == [],
+    phrase(peek(C), "ab", S1),
+    C == a,
+    S1 == "ab",
+    phrase(not_a, "b", S2),
+    S2 == "b",
+    \+ phrase(not_a, "a", _),
+    phrase(either, "b"),
+    phrase(with_call(x), "x"),
+    phrase(([a], {true}, ([b] | [c])), "ac"),
+    catch(phrase(_, []), error(E, _), true),
+    E == instantiation_error.
+
+:- initialization(test_queries_on_dcg_translation).
@@ -0,0 +1,40 @@
+:- module(tests_on_deep_terms, []).
+
+:- use_module(library(lists)).
+
+nest(0, T, T) :- !.
+nest(N, T0, T) :- N1 is N - 1, nest(N1, f(T0), T).
+
+nested_list(0, L, L) :- !.
+nested_list(N, L0, L) :- N1 is N - 1, nested_list(N1, [L0], L).
+
+test_queries_on_deep_terms :-
+    nest(200000, a, T1),
+    nest(200000, a, T2),
+    nest(200000, b, T3),
+    nest(200000, _, T4),
+    nested_list(200000, [], L1),
+    copy_term(T1, C1),
+    C1 == T1,
+    copy_term(T4, C4),
+    \+ C4 == T4,
+    copy_term(L1, CL1),
+    CL1 == L1,
+    T1 = T2,
+    \+ T1 = T3,
+    T1 == T2,
+    compare(=, T1, T2),
+    compare(<, T1, T3),
+    T1 @< T3,
+    sort([T3, T1, T2], [T1, T3]),
+    ground(T1),
+    \+ ground(T4),
+    term_variables(T4, [_]),
+    subsumes_term(T4, T1),
+    \+ subsumes_term(T1, T4),
+    \+ \+ unify_with_occurs_check(T4, T3),
+    findall(T1, member(_, [x, y]), [F1, F2]),
+    F1 == T1,
+    F2 == T1.
+
+:- initialization(test_queries_on_deep_terms).
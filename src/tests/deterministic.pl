@@ -0,0 +1,38 @@
+:- module(tests_on_deterministic, []).
+
+:- use_module(library(iso_ext)).
+
+p(1).
+p(2).
+
+q(1).
+
+det_after_unification(D) :- X = 1, deterministic(D), X == 1.
+det_after_choice(D) :- p(_), deterministic(D).
+det_after_last_choice(D) :- p(X), X == 2, deterministic(D).
+det_after_cut(D) :- p(_), !, deterministic(D).
+det_after_fact(D) :- q(_), deterministic(D).
+det_by_call(D) :- p(_), call(deterministic(D)).
+det_twice(D1, D2) :- p(_), deterministic(D1), !, deterministic(D2).
+
+cut_to_captured(X) :-
+    prolog_current_choice(B),
+    p(X),
+    prolog_cut_to(B).
+
+test_queries_on_deterministic :-
+    det_after_unification(true),
+    once(det_after_choice(false)),
+    det_after_last_choice(true),
+    det_after_cut(true),
+    det_after_fact(true),
+    once(det_by_call(true)),
+    det_twice(false, true),
+    prolog_current_choice(B1),
+    prolog_current_choice(B2),
+    B1 == B2,
+    findall(X, cut_to_captured(X), Xs),
+    Xs == [1],
+    catch(prolog_cut_to(_), error(instantiation_error, prolog_cut_to/1), true).
+
+:- initialization(test_queries_on_deterministic).
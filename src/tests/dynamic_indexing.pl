@@ -0,0 +1,32 @@
+:- module(tests_on_dynamic_indexing, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+:- dynamic(f/2).
+:- dynamic(g/0).
+
+numbered_facts(N, Facts) :-
+    findall(f(I, s(I)), between(1, N, I), Facts).
+
+test_queries_on_dynamic_indexing :-
+    numbered_facts(1000, Facts),
+    maplist(assertz, Facts),
+    assertz((f(X, Y) :- Y = X)),
+    assertz(f([a], list)),
+    clause(f(500, S), true),
+    S == s(500),
+    clause(f(1000, _), true), deterministic(D0), D0 == false,
+    clause(f([_], B), true), deterministic(D1), D1 == true, B == list,
+    findall(Body, clause(f(_, _), Body), Bodies),
+    length(Bodies, 1002),
+    retract(f(250, _)),
+    \+ clause(f(250, s(_)), true),
+    f(750, s(750)),
+    f(x, x),
+    assertz(g),
+    clause(g, true),
+    retract(g),
+    \+ clause(g, _).
+
+:- initialization(test_queries_on_dynamic_indexing).
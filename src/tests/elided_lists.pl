@@ -0,0 +1,28 @@
+:- module(tests_on_elided_lists, []).
+
+:- use_module(library(iso_ext)).
+
+written(T, Depth, A) :-
+    with_output_to(atom(A), write_term(T, [max_depth(Depth)])).
+
+ends_with(A, Suffix) :-
+    atom_concat(_, Suffix, A).
+
+test_queries_on_elided_lists :-
+    written([1,2,3,4,5], 3, A1),
+    ends_with(A1, '|...(2 more)...]'),
+    written([1,2,3,4,5,6,7,8,9,10], 4, A2),
+    ends_with(A2, '|...(6 more)...]'),
+    % the tail of a partial list has no element count.
+    written([1,2,3,4|_], 2, A3),
+    ends_with(A3, '|...]'),
+    \+ ends_with(A3, 'more)...]'),
+    written([1,2], 3, A4),
+    A4 == '[1,2]',
+    written([1,2,3], 0, A5),
+    A5 == '[1,2,3]',
+    atom_chars(abcde, Cs),
+    written(Cs, 2, A6),
+    ends_with(A6, '|...(3 more)...]').
+
+:- initialization(test_queries_on_elided_lists).
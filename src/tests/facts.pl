@@ -7,6 +7,16 @@ p(Z, Z).
 clouds(are, nice).
 p(Z, h(Z, W), f(W)).
 
+% first arguments of every kind, indexed in the same predicate.
+q([1], 1).
+q([2], 2).
+q(f(x), 3).
+q(f(y), 4).
+q(a, 5).
+q(a, 6).
+q(b, 7).
+q(g(z), 8).
+
 test_queries_on_facts :-
     findall(Z, p(Z, Z), [Z]),
     findall(Z, p(Z, z), [z]),
@@ -23,6 +33,12 @@ test_queries_on_facts :-
     retract(p(_,_,_)),
     assertz(p(Z, h(Z, W), f(W))),
     p(f(f(a)), h(f(f(a)), f(a)), f(f(a))),
-    retract(p(Z, h(Z, W), f(W))).
+    retract(p(Z, h(Z, W), f(W))),
+    findall(X, q([_], X), [1, 2]),
+    findall(X, q(f(_), X), [3, 4]),
+    findall(X, q(a, X), [5, 6]),
+    findall(X, q(b, X), [7]),
+    findall(X, q(g(z), X), [8]),
+    findall(K-X, q(K, X), [[1]-1, [2]-2, f(x)-3, f(y)-4, a-5, a-6, b-7, g(z)-8]).
 
 :- initialization(test_queries_on_facts).    
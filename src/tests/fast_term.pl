@@ -0,0 +1,74 @@
+:- module(tests_on_fast_term, []).
+
+:- use_module(library(files)).
+:- use_module(library(lists)).
+
+write_terms(F, Ts) :-
+    setup_call_cleanup(open(F, write, S),
+                       write_terms_(Ts, S),
+                       close(S)).
+
+write_terms_([], _).
+write_terms_([T|Ts], S) :-
+    fast_write(S, T),
+    write_terms_(Ts, S).
+
+read_terms(F, Ts) :-
+    setup_call_cleanup(open(F, read, S),
+                       read_terms_(S, Ts),
+                       close(S)).
+
+read_terms_(S, Ts) :-
+    fast_read(S, T),
+    (  T == end_of_file ->
+       Ts = []
+    ;  Ts = [T|Ts0],
+       read_terms_(S, Ts0)
+    ).
+
+write_bytes(F, Bs) :-
+    setup_call_cleanup(open(F, write, S, [type(binary)]),
+                       maplist(put_byte(S), Bs),
+                       close(S)).
+
+test_queries_on_fast_term :-
+    tmp_file('fast_term_test.bin', F),
+    setup_call_cleanup(true,
+                       fast_term_queries(F),
+                       catch(delete_file(F), _, true)).
+
+fast_term_queries(F) :-
+    Ts = [a, 'Quoted atom', [], '[]', c, 0, -1, 123456789012345678901234567890,
+          -98765432109876543210, 1.5, -0.25, "a string", [a, b|_],
+          f(X, g(X, Y), Y, _), (p :- q, r ; \+ s), {x}],
+    write_terms(F, Ts),
+    read_terms(F, Ts0),
+    Ts0 = [A, B, C, D, E, N0, N1, N2, N3, F0, F1, Str, [a, b|T0],
+           f(X0, g(X1, Y0), Y1, Z), Cl, Cu],
+    A == a, B == 'Quoted atom', C == [], D == '[]', E == c,
+    N0 == 0, N1 == -1, N2 == 123456789012345678901234567890,
+    N3 == -98765432109876543210, F0 == 1.5, F1 == -0.25,
+    Str == "a string",
+    var(T0), X0 == X1, Y0 == Y1, X0 \== Y0, var(Z), Z \== X0,
+    Cl == (p :- q, r ; \+ s), Cu == {x},
+    write_bytes(F, [250, 1, 11]),
+    catch((read_terms(F, _), false),
+          error(syntax_error(fast_term_unexpected_eof), fast_read/2),
+          true),
+    % a string whose length prefix claims far more bytes than follow.
+    write_bytes(F, [250, 1, 8, 255, 255, 255, 255, 255, 255, 255, 255, 255, 1, 97]),
+    catch((read_terms(F, _), false),
+          error(syntax_error(fast_term_unexpected_eof), fast_read/2),
+          true),
+    write_bytes(F, [0]),
+    catch((read_terms(F, _), false),
+          error(syntax_error(fast_term_bad_header), fast_read/2),
+          true),
+    % reading a directory fails in the underlying stream.
+    setup_call_cleanup(open('.', read, S),
+                       catch((fast_read(S, _), false),
+                             error(io_error(read, S), fast_read/2),
+                             true),
+                       close(S)).
+
+:- initialization(test_queries_on_fast_term).
@@ -0,0 +1,25 @@
+:- module(tests_on_format, []).
+
+:- use_module(library(format)).
+
+test_queries_on_format :-
+    format(atom(A), "~w-~2f", [x, 3]),
+    A == 'x-3.00',
+    format(chars(Cs), "~a~n", [abc]),
+    Cs == [a, b, c, '\n'],
+    format(codes(Ls), "~d", [42]),
+    Ls == [0'4, 0'2],
+    format(atom(E), "", []),
+    E == '',
+    format(atom(D), "~D", [1234567]),
+    D == '1,234,567',
+    format(atom(x), "x", []),
+    \+ format(atom(y), "x", []),
+    catch((format(_, "x", []), false),
+          error(instantiation_error, format/3),
+          true),
+    catch((format(stream(s), "x", []), false),
+          error(domain_error(format_sink, stream(s)), _),
+          true).
+
+:- initialization(test_queries_on_format).
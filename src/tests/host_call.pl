@@ -0,0 +1,11 @@
+:- module(tests_on_host_call, []).
+
+:- use_module(library(host)).
+
+test_queries_on_host_call :-
+    catch(host_call(no_such_callback(1), _),
+          error(existence_error(host_callback, no_such_callback), host_call/2), true),
+    catch(host_call(_, _), error(instantiation_error, _), true),
+    catch(host_call(1, _), error(type_error(callable, 1), _), true).
+
+:- initialization(test_queries_on_host_call).
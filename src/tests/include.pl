@@ -0,0 +1,29 @@
+:- module(tests_on_include, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+color(red).
+:- include('include/colors').
+color(blue).
+
+write_file(F, Text) :-
+    open(F, write, S),
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write(Text), set_output(Out)),
+    close(S).
+
+% include/1 of a non-atom is an error of its own, not of use_module/1.
+bad_include_queries(F) :-
+    write_file(F, ':- include(3).\n'),
+    catch((consult(F), false),
+          error(syntax_error(invalid_include_declaration), _),
+          true).
+
+test_queries_on_include :-
+    findall(C, color(C), Cs),
+    Cs == [red, green, yellow, blue],
+    tmp_file('include_test.pl', F),
+    setup_call_cleanup(true, bad_include_queries(F), catch(delete_file(F), _, true)).
+
+:- initialization(test_queries_on_include).
@@ -0,0 +1,2 @@
+color(green).
+:- include(shades).
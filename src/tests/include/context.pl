@@ -0,0 +1,3 @@
+% expanded with the context of this file.
+
+context_of(g).
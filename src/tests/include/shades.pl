@@ -0,0 +1,5 @@
+color(yellow).
+
+end_of_file.
+
+color(none).
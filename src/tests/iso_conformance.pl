@@ -0,0 +1,8 @@
+:- module(tests_on_iso_conformance, []).
+
+:- use_module(library(iso_conformance)).
+
+test_queries_on_iso_conformance :-
+    run_iso_tests.
+
+:- initialization(test_queries_on_iso_conformance).
@@ -0,0 +1,23 @@
+:- module(tests_on_last_answer, []).
+
+p(1).
+p(2).
+
+q(1).
+
+% Det is true iff G left no choice point behind its answer, as the
+% toplevel tests before printing it.
+answer(G, Det) :-
+    '$get_b_value'(B),
+    call(G),
+    '$is_deterministic_answer'(B, Det).
+
+test_queries_on_last_answer :-
+    findall(X-D, answer(p(X), D), [1-false, 2-true]),
+    findall(D, answer(q(_), D), [true]),
+    findall(D, answer((p(Y), Y == 1), D), [false]),
+    findall(D, answer(once(p(_)), D), [true]),
+    findall(D, answer((p(_), !), D), [true]),
+    \+ answer(fail, _).
+
+:- initialization(test_queries_on_last_answer).
@@ -0,0 +1,27 @@
+:- module(tests_on_list_primitives, []).
+
+:- use_module(library(lists)).
+:- use_module(library(ordsets)).
+
+test_queries_on_list_primitives :-
+    append([a, b], [c], [a, b, c]),
+    append([a, b], T, L),
+    L == [a, b | T],
+    append([], [c], [c]),
+    append("ab", "cd", "abcd"),
+    append([a | "bc"], [d], [a, b, c, d]),
+    \+ append([a], [b], [a, c]),
+    findall(X-Y, append(X, Y, [1, 2]), [[]-[1, 2], [1]-[2], [1, 2]-[]]),
+    append([a | P], [c], [a, b, c]),
+    P == [b],
+    merge([1, 3, 5], [2, 3, 4], [1, 2, 3, 3, 4, 5]),
+    merge([], [b, a], [b, a]),
+    merge([f(_)], [a, 1], M),
+    M = [1, a, f(_)],
+    catch(merge([a | _], [], _), error(instantiation_error, merge/3), true),
+    ord_union([a, c, e], [b, c, d], [a, b, c, d, e]),
+    ord_union([], [a], [a]),
+    ord_union([a, b], U, [a, b, c]),
+    U == [c].
+
+:- initialization(test_queries_on_list_primitives).
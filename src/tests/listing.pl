@@ -0,0 +1,43 @@
+:- module(tests_on_listing, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+:- use_module(library(listing)).
+
+read_chars(S, N, Cs) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_chars(N, Cs), set_input(In)).
+
+get_chars(0, []) :- !.
+get_chars(N, [C | Cs]) :-
+    get_char(C),
+    N1 is N - 1,
+    get_chars(N1, Cs).
+
+% reads back as many characters as Expected has, since get_char/1
+% cannot yet be used to detect the end of the file.
+portrayed(F, Clause, Expected) :-
+    with_open_file(F, write, S1^portray_clause(S1, Clause)),
+    atom_length(Expected, N),
+    with_open_file(F, read, S2^read_chars(S2, N, Cs)),
+    atom_chars(Expected, Cs).
+
+test_queries_on_listing :-
+    tmp_file('listing_test.txt', F),
+    setup_call_cleanup(true, listing_queries(F), catch(delete_file(F), _, true)).
+
+listing_queries(F) :-
+    portrayed(F, foo(X, 'A b', _, X),
+              'foo(A,\'A b\',_,A).\n'),
+    portrayed(F, (a :- true),
+              'a.\n'),
+    portrayed(F, (p(X, Y) :- q(X), ( X > 1 -> r(Y), s ; X = 0 -> true ; fail ), \+ t(Y)),
+              'p(A,B) :-\n    q(A),\n    (   A>1\n    ->  r(B),\n        s\n    ;   A=0\n    ->  true\n    ;   fail\n    ),\n    \\+t(B).\n'),
+    portrayed(F, (h :- (a :- b), (x, y ; z)),
+              'h :-\n    (a:-b),\n    (   x,\n        y\n    ;   z\n    ).\n'),
+    portrayed(F, (p :- q(aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbbbbbbbbbbbb, cccccccccccccccccccccccccccccc)),
+              'p :-\n    q(aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,\n      bbbbbbbbbbbbbbbbbbbbbbbbbbbbbb,\n      cccccccccccccccccccccccccccccc).\n'),
+    portrayed(F, (:- dynamic(foo/1)),
+              ':- dynamic(foo/1).\n').
+
+:- initialization(test_queries_on_listing).
@@ -0,0 +1,41 @@
+:- module(tests_on_load_context, []).
+
+term_expansion(context_of(Goal), loaded(Goal, Context)) :-
+    findall(Key-Value,
+            ( member(Key, [module, source, file, directory, term_position, variable_names]),
+              prolog_load_context(Key, Value)
+            ),
+            Context).
+
+context_of(f(X, Y, X, Y)).
+:- include('include/context').
+
+ends_with(Atom, Suffix) :-
+    sub_atom(Atom, _, _, 0, Suffix).
+
+init_context(M, S) :-
+    prolog_load_context(module, M),
+    prolog_load_context(source, S).
+
+test_queries_on_load_context :-
+    loaded(f(_, _, _, _), Context1),
+    Context1 = [module-M1, source-S1, file-S1, directory-D1,
+                term_position-10, variable_names-[Y=_, X=_]],
+    X == 'X', Y == 'Y',
+    M1 == tests_on_load_context,
+    ends_with(S1, 'src/tests/load_context.pl'),
+    ends_with(D1, 'src/tests'),
+    loaded(g, Context2),
+    Context2 = [module-M2, source-S2, file-F2, directory-D2,
+                term_position-3, variable_names-[]],
+    M2 == tests_on_load_context,
+    S2 == S1,
+    ends_with(F2, 'src/tests/include/context.pl'),
+    ends_with(D2, 'src/tests/include'),
+    init_context(M3, S3),
+    M3 == tests_on_load_context,
+    S3 == S1,
+    \+ prolog_load_context(foo, _),
+    \+ prolog_load_context(1, _).
+
+:- initialization(test_queries_on_load_context).
@@ -0,0 +1,42 @@
+:- module(tests_on_load_facts, []).
+
+:- use_module(library(facts)).
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_facts(File, Facts) :-
+    current_output(Out),
+    setup_call_cleanup(open(File, write, S),
+                       ( set_output(S), write_facts_(Facts) ),
+                       ( set_output(Out), close(S) )).
+
+write_facts_([]).
+write_facts_([Fact | Facts]) :-
+    writeq(Fact),
+    write('.'),
+    nl,
+    write_facts_(Facts).
+
+test_queries_on_load_facts :-
+    tmp_file('load_facts_test.pl', F),
+    setup_call_cleanup(true, load_facts_queries(F), catch(delete_file(F), _, true)).
+
+load_facts_queries(F) :-
+    write_facts(F, [edge(a, b)]),
+    load_facts(F, 1),
+    write_facts(F, [edge(b, c), weight(a, 1), edge(c, 'D'), weight(b, 2.5)]),
+    load_facts(F, 4),
+    findall(X-Y, user:edge(X, Y), [a-b, b-c, c-'D']),
+    findall(X-W, user:weight(X, W), [a-1, b-2.5]),
+    write_facts(F, [(edge(d, e) :- true)]),
+    catch(load_facts(F, _),
+          error(domain_error(fact, (edge(d, e) :- true)), load_facts/2),
+          true),
+    write_facts(F, [edge(d, e), atom_length(x, 1)]),
+    catch(load_facts(F, _),
+          error(permission_error(modify, static_procedure, atom_length/2), load_facts/2),
+          true),
+    findall(X-Y, user:edge(X, Y), [a-b, b-c, c-'D']),
+    catch(load_facts(_, _), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_load_facts).
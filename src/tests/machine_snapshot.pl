@@ -0,0 +1,40 @@
+:- module(tests_on_machine_snapshot, []).
+
+:- use_module(library(diag)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+snapshot_error(From, E) :-
+    catch(machine_snapshot(From, _), error(E, _), true).
+
+offsets_from(From, Cells) :-
+    forall(member(Offset-_, Cells), Offset >= From).
+
+test_queries_on_machine_snapshot :-
+    machine_snapshot(S),
+    S = [heap-Heap, registers-Registers, trail-Trail, choicepoints-_],
+    is_list(Heap),
+    is_list(Registers),
+    is_list(Trail),
+    offsets_from(0, Heap),
+    forall(member(N-_, Registers), N >= 1),
+    % a pending alternative of member/2 is listed, newest first.
+    member(_, [a, b]),
+    machine_snapshot(S1),
+    member(choicepoints-[choicepoint(B, Fields) | _], S1),
+    integer(B),
+    member(heap_top(H), Fields),
+    integer(H),
+    member(trail_top(_), Fields),
+    member(alternative(_), Fields),
+    !,
+    machine_snapshot(H, S2),
+    member(heap-Heap2, S2),
+    offsets_from(H, Heap2),
+    machine_snapshot(100000000, S3),
+    member(heap-[], S3),
+    snapshot_error(_, instantiation_error),
+    snapshot_error(a, type_error(integer, a)),
+    snapshot_error(-1, domain_error(not_less_than_zero, -1)).
+
+:- initialization(test_queries_on_machine_snapshot).
@@ -0,0 +1,55 @@
+:- module(tests_on_memory_streams, []).
+
+:- use_module(library(iso_ext)).
+
+read_all(S, Cs) :-
+    get_char(S, C),
+    (  C == end_of_file ->
+       Cs = []
+    ;  Cs = [C | Cs0],
+       read_all(S, Cs0)
+    ).
+
+test_queries_on_memory_streams :-
+    open_memory_stream('f(X, y). g.', S1, []),
+    read_term(S1, T1, []),
+    read(S1, T2),
+    read(S1, T3),
+    close(S1),
+    T1 = f(X, Y), var(X), Y == y,
+    T2 == g,
+    T3 == end_of_file,
+    open_memory_stream([a, b], S2, []),
+    read_all(S2, Cs2),
+    Cs2 == [a, b],
+    stream_property(S2, input),
+    \+ stream_property(S2, output),
+    close(S2),
+    open_memory_stream([0'a, 0'b], S3, []),
+    read_all(S3, Cs3),
+    close(S3),
+    Cs3 == [a, b],
+    open_memory_stream("ab", S4, []),
+    peek_char(S4, C4),
+    read_all(S4, Cs4),
+    close(S4),
+    C4 == a,
+    Cs4 == [a, b],
+    open_memory_stream([], S5, []),
+    at_end_of_stream(S5),
+    close(S5),
+    open_memory_stream(a, S6, [type(binary)]),
+    get_byte(S6, B6),
+    get_byte(S6, E6),
+    close(S6),
+    B6 == 0'a,
+    E6 == -1,
+    open_memory_stream(a, S7, []),
+    catch(write(S7, b), error(permission_error(output, stream, S7), _), true),
+    close(S7),
+    catch(open_memory_stream(_, _, []), error(instantiation_error, open_memory_stream/3), true),
+    catch(open_memory_stream(a, _, [foo]), error(domain_error(stream_option, foo), open_memory_stream/3), true),
+    catch(open_memory_stream([a | _], _, []), error(instantiation_error, open_memory_stream/3), true),
+    catch(open_memory_stream(f(a), _, []), error(type_error(list, f(a)), open_memory_stream/3), true).
+
+:- initialization(test_queries_on_memory_streams).
@@ -0,0 +1,63 @@
+:- module(tests_on_messages, []).
+
+:- use_module('messages/hook').
+
+:- dynamic(captured/3).
+
+capture(Message, Kind, Codes) :-
+    assertz(captured(Message, Kind, Codes)).
+
+test_queries_on_messages :-
+    message_to_codes(singletons('f.pl', 3, ['X', 'Y']), warning, Cs1),
+    atom_codes(A1, Cs1),
+    A1 == 'f.pl:3: Singleton variables: [X, Y]',
+    message_to_codes(error(type_error(integer, a), foo/1), error, Cs2),
+    atom_codes(A2, Cs2),
+    A2 == 'caught: foo/1: type error: integer expected, found a',
+    message_to_codes(query_unbound_singletons(['Lne', 'Y']), warning, Cs4),
+    atom_codes(A4, Cs4),
+    A4 == 'singleton variables of the query left unbound: [Lne, Y]',
+    message_to_codes(query_calls_empty_dynamic(fact/1), warning, Cs5),
+    atom_codes(A5, Cs5),
+    A5 == 'the query calls fact/1, which is dynamic and has no clauses',
+    message_to_codes(hello('World'), informational, Cs3),
+    atom_codes(A3, Cs3),
+    A3 == 'Unknown message: hello(\'World\')',
+    print_message(silent, test_message(1)),
+    print_message(warning, test_message(2)),
+    findall(M-K-A, ( captured(M, K, Cs), atom_codes(A, Cs) ), Captured),
+    Captured == [test_message(1)-silent-'Unknown message: test_message(1)',
+                 test_message(2)-warning-'Unknown message: test_message(2)'],
+    catch(print_message(_, m), error(instantiation_error, _), true),
+    catch(print_message(loud, m), error(domain_error(message_kind, loud), _), true),
+    catch(message_to_codes(m, 1, _), error(type_error(atom, 1), _), true),
+    error_text(error(instantiation_error, atom_length/2),
+               'caught: atom_length/2: arguments are not sufficiently instantiated'),
+    error_text(error(type_error(atom, f('A')), atom_length/2),
+               'caught: atom_length/2: type error: atom expected, found f(\'A\')'),
+    error_text(error(existence_error(procedure, foo/1), foo/1),
+               'caught: unknown procedure: foo/1'),
+    error_text(error(existence_error(procedure, atom_length/3), atom_length/3),
+               'caught: unknown procedure: atom_length/3 (did you mean atom_length/2?)'),
+    error_text(error(existence_error(procedure, gensym/2), gensym/2),
+               'caught: unknown procedure: gensym/2 (exported by library(gensym))'),
+    error_text(error(syntax_error(incomplete_reduction), use_module/1:3),
+               'caught: use_module/1, line 3: syntax error: incomplete_reduction'),
+    error_text(error(domain_error(io_mode, bogus), _),
+               'caught: domain error: io_mode expected, found bogus'),
+    numlist(1, 30, Ns),
+    error_text(error(type_error(integer, Ns), f/1),
+               'caught: f/1: type error: integer expected, found [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20|...(10 more)...]'),
+    error_text(error(foo, bar), 'caught: error(foo,bar)'),
+    error_text(foo, 'caught: foo').
+
+error_text(Error, Text) :-
+    message_to_codes(Error, error, Codes),
+    atom_codes(Text, Codes).
+
+numlist(N, N, [N]) :- !.
+numlist(N0, N, [N0 | Ns]) :-
+    N1 is N0 + 1,
+    numlist(N1, N, Ns).
+
+:- initialization(test_queries_on_messages).
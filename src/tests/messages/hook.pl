@@ -0,0 +1,5 @@
+% defines message_hook/3 in user, handing test_message/1 messages to
+% tests_on_messages rather than printing them.
+message_hook(Message, Kind, Codes) :-
+    Message = test_message(_),
+    tests_on_messages:capture(Message, Kind, Codes).
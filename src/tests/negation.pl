@@ -0,0 +1,26 @@
+:- module(tests_on_negation, []).
+
+:- use_module(library(lists)).
+
+test_queries_on_negation :-
+    \+ fail,
+    not(fail),
+    \+ \+ true,
+    \+ member(_, []),
+    catch(\+ _, error(E1, C1), true),
+    E1 == instantiation_error, C1 == (\+)/1,
+    catch(not(_), error(E2, C2), true),
+    E2 == instantiation_error, C2 == not/1,
+    G = _,
+    catch(\+ G, error(E3, _), true),
+    E3 == instantiation_error,
+    findall(X, local_cut(X), L1),
+    L1 == [2, 3],
+    findall(X, inner_cut(X), L2),
+    L2 == [1, 2, 3].
+
+local_cut(X) :- member(X, [1,2,3]), \+ (!, X = 1).
+
+inner_cut(X) :- member(X, [1,2,3]), \+ (member(Y, [a,b]), !, Y == b).
+
+:- initialization(test_queries_on_negation).
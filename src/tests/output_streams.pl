@@ -0,0 +1,42 @@
+:- module(tests_on_output_streams, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+read_chars(S, N, Cs) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_chars(N, Cs), set_input(In)).
+
+get_chars(0, []) :- !.
+get_chars(N, [C | Cs]) :-
+    get_char(C),
+    N1 is N - 1,
+    get_chars(N1, Cs).
+
+written(F, Goal, Expected) :-
+    with_open_file(F, write, Goal),
+    atom_length(Expected, N),
+    with_open_file(F, read, S^read_chars(S, N, Cs)),
+    atom_chars(Expected, Cs).
+
+test_queries_on_output_streams :-
+    tmp_file('output_streams_test.txt', F),
+    setup_call_cleanup(true, output_streams_queries(F), catch(delete_file(F), _, true)).
+
+output_streams_queries(F) :-
+    written(F, S1^( write(S1, a), tab(S1, 1 + 1), print(S1, 'B'), nl(S1),
+                 writeq(S1, '$VAR'(2)), write_canonical(S1, 'A' + b) ),
+            'a  \'B\'\nC+(\'A\',b)'),
+    written(F, S2^with_output_to_stream(S2, ( write(a), tab(2), print(f('$VAR'(0))), nl )),
+            'a  f(A)\n'),
+    current_output(Out),
+    catch(tab(Out, a), error(type_error(evaluable, a/0), tab/2), true),
+    catch(tab(Out, 1.5), error(type_error(integer, 1.5), tab/2), true),
+    catch(tab(_), error(instantiation_error, tab/1), true),
+    catch(nl(_), error(instantiation_error, nl/1), true),
+    catch(nl(foo), error(existence_error(stream, foo), nl/1), true),
+    setup_call_cleanup(open(F, read, In),
+                       catch(nl(In), error(permission_error(output, stream, In), nl/1), true),
+                       close(In)).
+
+:- initialization(test_queries_on_output_streams).
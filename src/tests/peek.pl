@@ -0,0 +1,43 @@
+:- module(tests_on_peek, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_chars(S, Cs) :-
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write(Cs), set_output(Out)).
+
+read_char(S, C) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_char(C), set_input(In)).
+
+test_queries_on_peek :-
+    tmp_file('peek_test.txt', F),
+    setup_call_cleanup(true, peek_queries(F), catch(delete_file(F), _, true)).
+
+peek_queries(F) :-
+    with_open_file(F, write, W^write_chars(W, 'aé')),
+    open(F, read, S),
+    peek_char(S, a),
+    peek_char(S, a),
+    peek_code(S, 0'a),
+    \+ peek_char(S, b),
+    stream_property(S, position(0)),
+    read_char(S, a),
+    peek_code(S, 0'é),
+    peek_char(S, é),
+    \+ at_end_of_stream(S),
+    read_char(S, é),
+    peek_char(S, end_of_file),
+    peek_code(S, -1),
+    at_end_of_stream(S),
+    catch(peek_char(S, ab), error(type_error(in_character, ab), _), true),
+    catch(peek_code(S, a), error(type_error(integer, a), _), true),
+    close(S),
+    open(F, read, B, [type(binary)]),
+    catch(peek_char(B, _), error(permission_error(input, binary_stream, B), _), true),
+    peek_byte(B, 0'a),
+    close(B),
+    catch(peek_char(_, _), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_peek).
@@ -0,0 +1,57 @@
+:- module(tests_on_pipes, []).
+
+:- use_module(library(pipes)).
+
+test_queries_on_pipes :-
+    pipe_create(s, r, [capacity(2)]),
+    pipe_send(s, f(X, X, [a|_])),
+    pipe_try_send(s, 12345678901234567890),
+    \+ pipe_try_send(s, full),
+    catch((pipe_send(s, full), false),
+          error(resource_error(term_pipe), _),
+          true),
+    pipe_receive(r, f(A, B, [a|_])),
+    A == B,
+    pipe_try_receive(r, N),
+    N == 12345678901234567890,
+    \+ pipe_try_receive(r, _),
+    catch((pipe_receive(r, _), false),
+          error(resource_error(term_pipe), _),
+          true),
+    catch((pipe_send(r, a), false),
+          error(permission_error(output, term_pipe, r), _),
+          true),
+    catch((pipe_receive(s, _), false),
+          error(permission_error(input, term_pipe, s), _),
+          true),
+    catch((pipe_create(s, r1, []), false),
+          error(permission_error(create, term_pipe, s), _),
+          true),
+    pipe_close(s),
+    pipe_receive(r, E),
+    E == end_of_file,
+    pipe_close(r),
+    catch((pipe_close(r), false),
+          error(existence_error(term_pipe, r), _),
+          true),
+    pipe_create(s, r, []),
+    pipe_close(r),
+    catch((pipe_send(s, a), false),
+          error(existence_error(term_pipe, s), _),
+          true),
+    pipe_close(s),
+    catch((pipe_create(s, r, [capacity(0)]), false),
+          error(domain_error(pipe_capacity, 0), _),
+          true),
+    catch((pipe_create(s, r, [capacity(65537)]), false),
+          error(resource_error(term_pipe_capacity), _),
+          true),
+    catch((pipe_create(s, r, [capacity(100000000000000000000)]), false),
+          error(resource_error(term_pipe_capacity), _),
+          true),
+    catch((pipe_create(s, r, [attributes(deep)]), false),
+          error(domain_error(pipe_attributes, deep), _),
+          true),
+    \+ pipe_close(s).
+
+:- initialization(test_queries_on_pipes).
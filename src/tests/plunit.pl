@@ -0,0 +1,45 @@
+:- module(tests_on_plunit, []).
+
+:- use_module(library(plunit)).
+:- use_module('plunit/hook').
+:- use_module('plunit/units').
+
+:- dynamic(captured/2).
+
+capture(Message, Kind) :-
+    assertz(captured(Message, Kind)).
+
+ends_with(Atom, Suffix) :-
+    sub_atom(Atom, _, _, 0, Suffix).
+
+test_queries_on_plunit :-
+    run_tests(passing),
+    findall(M, captured(M, _), Ms1),
+    Ms1 = [passed(passing, member, _), passed(passing, binding, _),
+           passed(passing, failing_goal, _), passed(passing, error, _),
+           passed(passing, solutions, _), passed(passing, setup, _),
+           passed(passing, assertions, _), summary(7, 0, 2, Ms)],
+    integer(Ms),
+    retractall(captured(_, _)),
+    \+ run_tests(failing),
+    findall(M-K, captured(M, K), Ms2),
+    Ms2 = [failed(F, 22, failing, goal, failed)-error,
+           failed(F, 23, failing, condition, condition(1 == 2))-error,
+           failed(F, 24, failing, no_exception, no_exception(ball))-error,
+           assertion_failed(F, 25, failing, assertion, member(z, [a]))-error,
+           failed(F, 25, failing, assertion, assertions(1))-error,
+           blocked(failing, skipped, unfinished)-warning,
+           summary(0, 4, 1, _)-informational],
+    ends_with(F, 'src/tests/plunit/units.pl'),
+    retractall(captured(_, _)),
+    run_tests(passing:binding),
+    findall(M, captured(M, _), Ms3),
+    Ms3 = [passed(passing, binding, _), summary(1, 0, 0, _)],
+    retractall(captured(_, _)),
+    message_to_codes(plunit(failed('t.pl', 3, u, t, raised(ball))), error, Cs),
+    atom_codes(A, Cs),
+    A == 't.pl:3: test u:t failed: raised ball',
+    catch(run_tests(nonesuch), error(existence_error(unit, nonesuch), _), true),
+    catch(run_tests(_), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_plunit).
@@ -0,0 +1,4 @@
+% defines message_hook/3 in user, handing the messages of
+% library(plunit) to tests_on_plunit rather than printing them.
+message_hook(plunit(Message), Kind, _) :-
+    tests_on_plunit:capture(Message, Kind).
@@ -0,0 +1,29 @@
+:- module(plunit_units, []).
+
+:- use_module(library(plunit)).
+:- use_module(library(lists)).
+
+:- begin_tests(passing).
+
+test(member) :- member(b, [a, b]).
+test(binding, X == 3) :- X is 1 + 2.
+test(failing_goal, [fail]) :- member(c, [a, b]).
+test(error, error(type_error(_, _))) :- atom_length(f(x), _).
+test(solutions, all(X == [1, 2, 3])) :- member(X, [1, 2, 3]).
+test(setup, [setup(X = 1), cleanup(true)]) :- X == 1.
+test(assertions) :-
+    assertion(1 < 2),
+    assertion(member(a, [a])).
+
+:- end_tests(passing).
+
+:- begin_tests(failing).
+
+test(goal) :- fail.
+test(condition, X == 2) :- X = 1.
+test(no_exception, throws(ball)) :- true.
+test(assertion) :-
+    assertion(member(z, [a])).
+test(skipped, blocked(unfinished)) :- fail.
+
+:- end_tests(failing).
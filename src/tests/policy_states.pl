@@ -0,0 +1,24 @@
+:- module(tests_on_policy_states, []).
+
+:- use_module(library(iso_ext)).
+
+test_queries_on_policy_states :-
+    call_with_inference_limit('$policy_states'(C1, _), 1000, _),
+    C1 = limits(N1, [L1-B1], [], [], _),
+    integer(N1), integer(L1), integer(B1),
+    setup_call_cleanup(true, '$policy_states'(_, K1), true),
+    K1 = cleaners([cont_pt(_, B2, _) | _], _, _),
+    integer(B2),
+    setup_call_cleanup(true, '$policy_refs'(As1, Bs1), true),
+    As1 = [_ | _], Bs1 = [_ | _],
+    call_with_inference_limit(( '$policy_states'(C2, K2),
+                                '$set_policy_states'(C2, K2),
+                                '$policy_states'(C3, K3) ),
+                              1000, _),
+    C3 = limits(_, [_-_], [], [], _),
+    K3 == K2,
+    catch('$set_policy_states'(foo, default), error(domain_error(policy_state, foo), _), true),
+    catch('$set_policy_states'(default, cleaners(x, 1, 2)),
+          error(domain_error(policy_state, cleaners(x, 1, 2)), _), true).
+
+:- initialization(test_queries_on_policy_states).
@@ -0,0 +1,45 @@
+:- module(tests_on_print_term, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+read_chars(S, N, Cs) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_chars(N, Cs), set_input(In)).
+
+get_chars(0, []) :- !.
+get_chars(N, [C | Cs]) :-
+    get_char(C),
+    N1 is N - 1,
+    get_chars(N1, Cs).
+
+printed(F, Term, Options, Expected) :-
+    with_open_file(F, write, S1^with_output_to_stream(S1, print_term(Term, Options))),
+    atom_length(Expected, N),
+    with_open_file(F, read, S2^read_chars(S2, N, Cs)),
+    atom_chars(Expected, Cs).
+
+print_error(Options, E) :-
+    catch(print_term(t, Options), error(E, print_term/2), true).
+
+test_queries_on_print_term :-
+    tmp_file('print_term_test.txt', F),
+    setup_call_cleanup(true, print_term_queries(F), catch(delete_file(F), _, true)).
+
+print_term_queries(F) :-
+    printed(F, f('$VAR'(1), 'A b'), [], 'f(B,\'A b\')'),
+    printed(F, foo(aaaa, bbbb, [c, d]), [right_margin(10)],
+            'foo(aaaa,\n    bbbb,\n    [c,d])'),
+    printed(F, foo(aaaa, [bbbb, cccc]), [right_margin(12)],
+            'foo(aaaa,\n    [bbbb,\n     cccc])'),
+    printed(F, foo(aaaa, bbbb), [right_margin(8), indent_arguments(2)],
+            'foo(\n  aaaa,\n  bbbb)'),
+    printed(F, {aaaa, bbbb}, [right_margin(6)], '{aaaa,bbbb}'),
+    print_error(_, instantiation_error),
+    print_error(foo, type_error(list, foo)),
+    print_error([right_margin(_)], instantiation_error),
+    print_error([right_margin(-1)], domain_error(print_option, right_margin(-1))),
+    print_error([indent_arguments(a)], domain_error(print_option, indent_arguments(a))),
+    print_error([bad], domain_error(write_option, bad)).
+
+:- initialization(test_queries_on_print_term).
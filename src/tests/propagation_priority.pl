@@ -0,0 +1,34 @@
+:- module(tests_on_propagation_priority, []).
+
+:- use_module(library(freeze)).
+
+:- dynamic(ran/1).
+
+slow :- assertz(ran(slow)).
+fast :- assertz(ran(fast)).
+
+clear_ran :-
+    (  retract(ran(_)), false
+    ;  true
+    ).
+
+test_queries_on_propagation_priority :-
+    freeze(X0, tests_on_propagation_priority:slow),
+    freeze(Y0, tests_on_propagation_priority:fast),
+    f(X0, Y0) = f(1, 1),
+    findall(R0, ran(R0), [slow, fast]),
+    clear_ran,
+    propagation_priority(tests_on_propagation_priority:fast/0, -1),
+    freeze(X1, tests_on_propagation_priority:slow),
+    freeze(Y1, tests_on_propagation_priority:fast),
+    f(X1, Y1) = f(1, 1),
+    findall(R1, ran(R1), [fast, slow]),
+    clear_ran,
+    propagation_priority(tests_on_propagation_priority:fast/0, 0),
+    catch(propagation_priority(f(x), 1),
+          error(domain_error(propagation_priority_key, f(x)), _),
+          true),
+    catch(propagation_priority(freeze, a), error(type_error(integer, a), _), true),
+    catch(propagation_priority(_, 1), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_propagation_priority).
@@ -0,0 +1,34 @@
+:- module(tests_on_quasi_quotations, []).
+
+:- use_module(library(iso_ext)).
+
+:- use_module('quasi_quotations/syntaxes').
+
+greeting(Name, {|interp||Name|}).
+
+text({|chars(L)||a 'b'
+"c"||}|}, L).
+
+test_queries_on_quasi_quotations :-
+    greeting(world, G),
+    G == world,
+    text(Cs, L),
+    Cs == [a, ' ', '\'', b, '\'', '\n', '"', c, '"', '|'],
+    L == 7,
+    read_queries.
+
+% read_term reads quotations only while the flag, set by loading
+% library(quasi_quotations), is set.
+read_queries :-
+    current_prolog_flag(quasi_quotations, true),
+    open_memory_stream('t({|a||b|}).', S1, []),
+    read(S1, T1),
+    close(S1),
+    T1 == t('$quasi_quotation'(a, b, 1)),
+    open_memory_stream('t({|a||b|}).', S2, []),
+    setup_call_cleanup(set_prolog_flag(quasi_quotations, false),
+                       catch((read(S2, _), false), error(syntax_error(_), _), true),
+                       set_prolog_flag(quasi_quotations, true)),
+    close(S2).
+
+:- initialization(test_queries_on_quasi_quotations).
@@ -0,0 +1,21 @@
+% quasi quotation syntaxes for tests_on_quasi_quotations.
+:- module(syntaxes, [interp/4, chars/4]).
+
+:- use_module(library(lists)).
+:- use_module(library(quasi_quotations)).
+
+:- quasi_quotation_syntax(interp).
+:- quasi_quotation_syntax(chars).
+
+% the variable of the clause named by the text of the quotation.
+interp(Content, [], VarNames, Var) :-
+    quasi_quotation_text(Content, Name),
+    memberchk(Name = Var, VarNames).
+
+% the characters of the quotation, and its line.
+chars(Content, [Line], _, Chars) :-
+    phrase_from_quasi_quotation(seq(Chars), Content),
+    quasi_quotation_position(Content, _:Line).
+
+seq([]) --> [].
+seq([C | Cs]) --> [C], seq(Cs).
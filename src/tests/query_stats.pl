@@ -0,0 +1,23 @@
+:- module(tests_on_query_stats, []).
+
+:- use_module(library(lists)).
+
+test_queries_on_query_stats :-
+    current_prolog_flag(toplevel_print_query_stats, false),
+    set_prolog_flag(toplevel_print_query_stats, true),
+    current_prolog_flag(toplevel_print_query_stats, true),
+    set_prolog_flag(toplevel_print_query_stats, false),
+    catch(set_prolog_flag(toplevel_print_query_stats, yes),
+          error(domain_error(flag_value, toplevel_print_query_stats + yes), _),
+          true),
+    '$reset_query_stats',
+    '$query_stats'(_, _, _, 0),
+    (  length(L, 1000), false
+    ;  true
+    ),
+    '$query_stats'(Heap, _, _, ChoicePoints),
+    Heap >= 1000,
+    ChoicePoints >= 1,
+    var(L).
+
+:- initialization(test_queries_on_query_stats).
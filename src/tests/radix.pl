@@ -0,0 +1,32 @@
+:- module(tests_on_radix, []).
+
+test_queries_on_radix :-
+    number_chars(N1, ['1', '6', '\'', f, f]),
+    N1 == 255,
+    number_chars(N2, ['-', '2', '\'', '1', '0', '1']),
+    N2 == -5,
+    number_chars(N3, ['0', x, f, f]),
+    N3 == 255,
+    number_codes(N4, [0'1, 0'2]),
+    N4 == 12,
+    number_chars(N5, 16, ['F', f]),
+    N5 == 255,
+    number_codes(N6, 36, [0'-, 0'z, 0'z]),
+    N6 == -1295,
+    number_chars(255, 16, Cs1),
+    Cs1 == [f, f],
+    number_chars(-5, 2, Cs2),
+    Cs2 == ['-', '1', '0', '1'],
+    number_codes(0, 8, Cs3),
+    Cs3 == [0'0],
+    number_chars(100, 10, ['1', '0', '0']),
+    catch(number_chars(_, 2, ['1', '2']),
+          error(syntax_error(_), number_chars/3), true),
+    catch(number_chars(_, 37, _),
+          error(domain_error(radix, 37), number_chars/3), true),
+    catch(number_codes(1.5, 16, _),
+          error(type_error(integer, 1.5), number_codes/3), true),
+    catch(number_codes(_, _, _),
+          error(instantiation_error, number_codes/3), true).
+
+:- initialization(test_queries_on_radix).
@@ -0,0 +1,44 @@
+:- module(tests_on_random, []).
+
+:- use_module(library(between)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+members(Xs) :-
+    findall(X, ( between(1, 20, _), random_member(X, [a, b, c, d]) ), Xs).
+
+coins(Bs) :-
+    findall(B, ( between(1, 20, _), ( maybe -> B = 1 ; B = 0 ) ), Bs).
+
+test_queries_on_random :-
+    set_random(seed(42)),
+    members(Xs1),
+    coins(Bs1),
+    statistics(random_draws, Draws1),
+    set_random(seed(42)),
+    members(Xs2),
+    coins(Bs2),
+    statistics(random_draws, Draws2),
+    Xs1 == Xs2,
+    Bs1 == Bs2,
+    Draws1 == 40,
+    Draws2 == 40,
+    \+ maybe(0.0),
+    maybe(1.0),
+    \+ maybe(0, 5),
+    maybe(5, 5),
+    \+ random_member(_, []),
+    random_select(X, [a, b, c], Rest),
+    sort([X | Rest], [a, b, c]),
+    random_select(x, L, [a, b]),
+    select(x, L, [a, b]),
+    catch(maybe(2.0), error(domain_error(probability, 2.0), _), true),
+    catch(maybe(1), error(type_error(float, 1), _), true),
+    catch(maybe(1, 0), error(domain_error(positive_integer, 0), _), true),
+    catch(random_member(_, [a | _]), error(instantiation_error, _), true),
+    catch(random_select(_, _, [a | b]), error(type_error(list, [a | b]), _), true),
+    catch(set_random(seed(a)), error(type_error(integer, a), _), true),
+    catch(set_random(foo), error(domain_error(set_random_option, foo), _), true),
+    set_random(seed(random)).
+
+:- initialization(test_queries_on_random).
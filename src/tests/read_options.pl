@@ -0,0 +1,63 @@
+:- module(tests_on_read_options, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_text(F, Text) :-
+    current_output(Out),
+    setup_call_cleanup(open(F, write, S),
+                       ( set_output(S), write(Text) ),
+                       ( set_output(Out), close(S) )).
+
+read_with(F, Term, Options) :-
+    current_input(In),
+    setup_call_cleanup(open(F, read, S),
+                       ( set_input(S), read_term(Term, Options) ),
+                       ( set_input(In), close(S) )).
+
+read_error(F, Options, E) :-
+    catch(read_with(F, _, Options), error(E, _), true).
+
+test_queries_on_read_options :-
+    tmp_file('read_options_test.pl', F),
+    setup_call_cleanup(true, read_option_queries(F), catch(delete_file(F), _, true)).
+
+read_option_queries(F) :-
+    write_text(F, 'f(X, Y, _Z, X, _).\n'),
+    read_with(F, T, [variables(Vs), variable_names(VNs), singletons(Ss)]),
+    T = f(X, Y, Z, X, W),
+    Vs == [X, Y, Z, W],
+    VNs == ['X' = X, 'Y' = Y, '_Z' = Z],
+    Ss == ['Y' = Y, '_Z' = Z],
+    read_with(F, f(_, _, _, _, _), [double_quotes(codes)]),
+    read_error(F, [bad], domain_error(read_option, bad)),
+    read_error(F, [quoted(true)], domain_error(read_option, quoted(true))),
+    read_error(F, [double_quotes(bits)], domain_error(read_option, double_quotes(bits))),
+    read_error(F, [variables(_), _], instantiation_error),
+    read_error(F, [variables(_) | _], instantiation_error),
+    read_error(F, foo, type_error(list, foo)),
+    \+ read_with(F, _, [variables(none)]),
+    write_text(F, 'a.\n\nf(X,\n  Y). '),
+    setup_call_cleanup(open(F, read, S),
+                       ( read_term(S, T1, [lines(Start1, _)]),
+                         read_term(S, T2, [lines(Start2, End2), variable_names(VNs2)]),
+                         read(S, T3) ),
+                       close(S)),
+    T1 == a,
+    Start1 == 1,
+    T2 = f(X2, Y2),
+    VNs2 == ['X' = X2, 'Y' = Y2],
+    Start2 == 3,
+    End2 == 4,
+    T3 == end_of_file,
+    write_text(F, 'f(a'),
+    setup_call_cleanup(open(F, read, S4),
+                       catch(read_term(S4, _, []), error(E4, Context4), true),
+                       close(S4)),
+    E4 = syntax_error(_),
+    Context4 == read_term/3,
+    current_output(Out),
+    catch(read_term(Out, _, []), error(E, _), true),
+    E = permission_error(input, stream, _).
+
+:- initialization(test_queries_on_read_options).
@@ -0,0 +1,34 @@
+:- module(tests_on_reproducible, []).
+
+:- use_module(library(between)).
+:- use_module(library(iso_ext)).
+
+draws(Xs) :-
+    findall(X, ( between(1, 10, _), random_member(X, [a, b, c, d]) ), Xs).
+
+test_queries_on_reproducible :-
+    current_prolog_flag(reproducible, Flag),
+    setup_call_cleanup(true,
+                       reproducible_queries,
+                       set_prolog_flag(reproducible, Flag)).
+
+reproducible_queries :-
+    set_prolog_flag(reproducible, true),
+    current_prolog_flag(reproducible, true),
+    draws(Xs1),
+    set_prolog_flag(reproducible, true),
+    draws(Xs2),
+    Xs1 == Xs2,
+    % seed(random) takes no seed from the clock in reproducible runs.
+    set_random(seed(random)),
+    draws(Ys1),
+    set_random(seed(random)),
+    draws(Ys2),
+    Ys1 == Ys2,
+    set_prolog_flag(reproducible, false),
+    current_prolog_flag(reproducible, false),
+    catch(set_prolog_flag(reproducible, maybe),
+          error(domain_error(flag_value, reproducible + maybe), _),
+          true).
+
+:- initialization(test_queries_on_reproducible).
@@ -0,0 +1,41 @@
+:- module(tests_on_resource_limits, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+loop :- loop.
+
+test_queries_on_resource_limits :-
+    catch(call_with_resource_limits(loop, [inferences(1000)]),
+          resource_limit_exceeded(inferences), true),
+    catch(call_with_resource_limits(loop, [time(0.1)]),
+          resource_limit_exceeded(time), true),
+    catch(call_with_resource_limits(length(_, 100000), [memory(1000)]),
+          resource_limit_exceeded(memory), true),
+    call_with_resource_limits(length(L, 10),
+                              [inferences(100000), time(10), memory(100000)]),
+    length(L, 10),
+    findall(X, call_with_resource_limits(member(X, [a, b]), [inferences(100)]), [a]),
+    \+ call_with_resource_limits(fail, [inferences(100)]),
+    catch(call_with_resource_limits(throw(ball), [memory(100)]), ball, true),
+    call_with_resource_limits(true, []),
+    catch(call_with_resource_limits(call_with_resource_limits(loop, [memory(100000000)]),
+                                    [inferences(500)]),
+          resource_limit_exceeded(inferences), true),
+    call_with_inference_limit(call_with_resource_limits(loop, [time(10)]), 500, R),
+    R == inference_limit_exceeded,
+    call_with_inference_limit(true, 10, !),
+    catch(call_with_resource_limits(true, _),
+          error(instantiation_error, call_with_resource_limits/2), true),
+    catch(call_with_resource_limits(true, [foo]),
+          error(domain_error(resource_limit, foo), call_with_resource_limits/2), true),
+    catch(call_with_resource_limits(true, [memory(1), memory(2)]),
+          error(domain_error(resource_limit, memory(2)), call_with_resource_limits/2), true),
+    catch(call_with_resource_limits(true, [time(a)]),
+          error(type_error(number, a), call_with_resource_limits/2), true),
+    catch(call_with_resource_limits(true, [inferences(1.0)]),
+          error(type_error(integer, 1.0), call_with_resource_limits/2), true),
+    catch(call_with_resource_limits(true, [memory(-1)]),
+          error(domain_error(not_less_than_zero, -1), call_with_resource_limits/2), true).
+
+:- initialization(test_queries_on_resource_limits).
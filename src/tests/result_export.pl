@@ -0,0 +1,46 @@
+:- module(tests_on_result_export, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+:- use_module(library(result_export)).
+
+file_chars(File, Cs) :-
+    current_input(In),
+    setup_call_cleanup(open(File, read, S),
+                       ( set_input(S), read_chars(Cs) ),
+                       ( set_input(In), close(S) )).
+
+read_chars(Cs) :-
+    get_char(C),
+    (  C == end_of_file -> Cs = []
+    ;  Cs = [C | Cs0],
+       read_chars(Cs0)
+    ).
+
+export_chars(F, Format, Bindings, Goal, Cs) :-
+    findall_export(Format, Bindings, Goal, file(F)),
+    file_chars(F, Cs).
+
+test_queries_on_result_export :-
+    tmp_file('result_export_test.txt', F),
+    setup_call_cleanup(true, result_export_queries(F), catch(delete_file(F), _, true)).
+
+result_export_queries(F) :-
+    export_chars(F, json, ['X'=X, 'Y'=Y],
+                 member(X-Y, [1-"a \"b\"", 2.5-true, c-[1, x], d-f('Y')]),
+                 Json),
+    Json == "{\"X\":1,\"Y\":\"a \\\"b\\\"\"}\n{\"X\":2.5,\"Y\":true}\n{\"X\":\"c\",\"Y\":[1,\"x\"]}\n{\"X\":\"d\",\"Y\":\"f('Y')\"}\n",
+    export_chars(F, csv, ['N'=N, 'S'=S],
+                 member(N-S, [1-"x,y", 2-'say "hi"', 3-_]),
+                 Csv),
+    Csv == "N,S\n1,\"x,y\"\n2,\"say \"\"hi\"\"\"\n3,\n",
+    export_chars(F, json, [], false, []),
+    catch(findall_export(xml, [], true, user_output),
+          error(domain_error(export_format, xml), findall_export/4),
+          true),
+    catch(findall_export(json, [x], true, user_output),
+          error(domain_error(variable_binding, x), findall_export/4),
+          true).
+
+:- initialization(test_queries_on_result_export).
@@ -0,0 +1,27 @@
+:- module(tests_on_runtime_modules, []).
+
+:- use_module(library(lists)).
+
+test_queries_on_runtime_modules :-
+    create_module(plugin_rules, [rule/2, rules/1], [lists]),
+    '$module_exists'(plugin_rules),
+    assertz(plugin_rules:rule(a, 1)),
+    assertz(plugin_rules:rule(b, 2)),
+    assertz((plugin_rules:rules(Rs) :- findall(K-V, rule(K, V), Rs0), reverse(Rs0, Rs))),
+    plugin_rules:rules([b-2, a-1]),
+    user:rule(a, 1),
+    plugin_rules:rule(a, 1),
+    retract(plugin_rules:rule(a, 1)),
+    \+ plugin_rules:rule(a, _),
+    findall(K, plugin_rules:rule(K, _), [b]),
+    assertz(plugin_rules:rule(c, 3)),
+    findall(K-V, plugin_rules:rule(K, V), [b-2, c-3]),
+    catch(create_module(plugin_rules, [], []),
+          error(permission_error(create, module, plugin_rules), create_module/3),
+          true),
+    catch(create_module(_, [], []), error(instantiation_error, create_module/3), true),
+    catch(create_module(m, [foo], []), error(type_error(module_export, foo), create_module/3), true),
+    catch(create_module(m, [], [1]), error(type_error(atom, 1), create_module/3), true),
+    \+ '$module_exists'(m).
+
+:- initialization(test_queries_on_runtime_modules).
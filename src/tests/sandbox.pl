@@ -0,0 +1,81 @@
+:- module(tests_on_sandbox, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+:- dynamic(scratch/1).
+:- dynamic(other/1).
+
+% system clauses called from user code, rather than from a library.
+reset_after_abort :- '$reset_after_abort'.
+set_double_quotes :- '$set_double_quotes'(atom).
+
+test_queries_on_sandbox :-
+    catch(sandboxed_call(open('sandbox_test.txt', write, _)),
+          error(permission_error(call, sandboxed_procedure, open/4), open/4), true),
+    catch(sandboxed_call(halt),
+          error(permission_error(call, sandboxed_procedure, halt/0), halt/0), true),
+    catch(sandboxed_call(consult('sandbox_test.pl')),
+          error(permission_error(call, sandboxed_procedure, use_module/1), _), true),
+    catch(sandboxed_call(assertz(scratch(a))),
+          error(permission_error(modify, sandboxed_procedure, scratch/1), assertz/1), true),
+    \+ scratch(_),
+    sandboxed_call(assertz(scratch(a)), [scratch/1]),
+    sandboxed_call(asserta(scratch(b)), [tests_on_sandbox:scratch/1]),
+    catch(sandboxed_call(retract(other(_)), [scratch/1]),
+          error(permission_error(modify, sandboxed_procedure, other/1), retract/1), true),
+    catch(sandboxed_call(sandboxed_call(retract(scratch(a)), [scratch/1])),
+          error(permission_error(modify, sandboxed_procedure, scratch/1), retract/1), true),
+    sandboxed_call(retract(scratch(a)), [scratch/1]),
+    findall(X, scratch(X), [b]),
+    sandboxed_call(length([a, b], 2)),
+    \+ sandboxed_call(fail),
+    catch(sandboxed_call(throw(ball)), ball, true),
+    assertz(other(a)),
+    findall(Y-Z,
+            ( sandboxed_call(member(Y, [a, b])),
+              catch(( assertz(other(Y)), Z = lifted ), _, Z = sandboxed)
+            ),
+            Solutions),
+    Solutions == [a-lifted, b-lifted],
+    once(sandboxed_call(member(_, [a, b]))),
+    retract(other(a)),
+    catch(sandboxed_call(iso_ext:remove_sandbox(_)), _, true),
+    catch(sandboxed_call((iso_ext:remove_sandbox(_), assertz(other(c)))),
+          error(permission_error(modify, sandboxed_procedure, other/1), assertz/1), true),
+    catch(sandboxed_call(true, _), error(instantiation_error, sandboxed_call/2), true),
+    catch(sandboxed_call(true, foo), error(type_error(list, foo), sandboxed_call/2), true),
+    catch(sandboxed_call(true, [foo]),
+          error(type_error(predicate_indicator, foo), sandboxed_call/2), true),
+    catch(sandboxed_call(true, [f/a]), error(type_error(integer, a), sandboxed_call/2), true),
+    system_clause_queries.
+
+system_clause_queries :-
+    catch((reset_after_abort, false),
+          error(permission_error(access, private_procedure, '$reset_after_abort'), _),
+          true),
+    catch((sandboxed_call(reset_after_abort), false),
+          error(permission_error(call, sandboxed_procedure, '$reset_after_abort'/0), _),
+          true),
+    catch((sandboxed_call(set_double_quotes), false),
+          error(permission_error(call, sandboxed_procedure, '$set_double_quotes'/1), _),
+          true),
+    catch((sandboxed_call(op(700, xfx, ===>)), false),
+          error(permission_error(call, sandboxed_procedure, op/3), op/3),
+          true),
+    \+ current_op(_, _, ===>),
+    current_prolog_flag(double_quotes, DQ),
+    catch((sandboxed_call(set_prolog_flag(double_quotes, atom)), false),
+          error(permission_error(call, sandboxed_procedure, set_prolog_flag/2), set_prolog_flag/2),
+          true),
+    current_prolog_flag(double_quotes, DQ),
+    catch((sandboxed_call(bb_put(sandbox_key, a)), false),
+          error(permission_error(call, sandboxed_procedure, bb_put/2), bb_put/2),
+          true),
+    \+ bb_get(sandbox_key, _),
+    catch((sandboxed_call(garbage_collect_clauses), false),
+          error(permission_error(call, sandboxed_procedure, garbage_collect_clauses/0),
+                garbage_collect_clauses/0),
+          true).
+
+:- initialization(test_queries_on_sandbox).
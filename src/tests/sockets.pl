@@ -0,0 +1,39 @@
+:- module(tests_on_sockets, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(sockets)).
+
+write_chars(S, Cs) :-
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write(Cs), set_output(Out)).
+
+read_term_from(S, T) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), read(T), set_input(In)).
+
+test_queries_on_sockets :-
+    socket_server_open('127.0.0.1':Port, Server),
+    integer(Port),
+    Port > 0,
+    socket_client_open('127.0.0.1':Port, C, []),
+    socket_server_accept(Server, Client, S, []),
+    atom(Client),
+    write_chars(C, 'hello(world).\n'),
+    read_term_from(S, hello(world)),
+    write_chars(S, 'a. '),
+    peek_char(C, a),
+    close(C),
+    close(S),
+    socket_client_open('127.0.0.1':Port, B, [type(binary)]),
+    socket_server_accept(Server, _, T, [type(binary)]),
+    put_byte(B, 7),
+    get_byte(T, 7),
+    close(B),
+    close(T),
+    close(Server),
+    catch(socket_client_open(_, _, []), error(instantiation_error, _), true),
+    catch(socket_client_open(localhost:port, _, []), error(type_error(integer, port), _), true),
+    catch(socket_server_open(70000, _), error(domain_error(port, 70000), _), true),
+    catch(socket_client_open(localhost:1, _, [tcp]), error(domain_error(socket_option, tcp), _), true).
+
+:- initialization(test_queries_on_sockets).
@@ -0,0 +1,28 @@
+:- module(tests_on_soft_cut, []).
+
+:- use_module(library(lists)).
+
+test_queries_on_soft_cut :-
+    findall(X-Y, (member(X, [1,2,3]) *-> Y = then ; Y = else), L1),
+    L1 == [1-then, 2-then, 3-then],
+    findall(Y, (fail *-> Y = then ; Y = else), L2),
+    L2 == [else],
+    findall(X, if(member(X, [a,b]), true, X = none), L3),
+    L3 == [a, b],
+    findall(X, if(fail, true, X = none), L4),
+    L4 == [none],
+    G = (member(X, [a,b]) *-> true ; X = none),
+    findall(X, call(G), L5),
+    L5 == [a, b],
+    findall(X, positive(X), L6),
+    L6 == [2, 3],
+    findall(X, first(X), L7),
+    L7 == [1],
+    findall(X, (member(X, [1,2]) *-> true), L8),
+    L8 == [1, 2].
+
+positive(X) :- ( member(X, [1,2,3]) *-> X > 1 ; X = 0 ).
+
+first(X) :- ( member(X, [1,2,3]) *-> ! ; X = 0 ).
+
+:- initialization(test_queries_on_soft_cut).
@@ -0,0 +1,28 @@
+:- module(tests_on_stack_limit, []).
+
+% the call to true keeps the frame of each recursive call alive.
+deep(N) :-
+    N1 is N + 1,
+    deep(N1),
+    true.
+
+% each recursive call leaves a choice point behind.
+wide(N) :-
+    N1 is N + 1,
+    (  wide(N1)
+    ;  true
+    ).
+
+shallow(0) :- !.
+shallow(N) :-
+    N1 is N - 1,
+    shallow(N1),
+    true.
+
+test_queries_on_stack_limit :-
+    catch((deep(0), false), error(resource_error(stack), _), true),
+    catch((wide(0), false), error(resource_error(stack), _), true),
+    % the frames are released as the error unwinds them.
+    shallow(100000).
+
+:- initialization(test_queries_on_stack_limit).
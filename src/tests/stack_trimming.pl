@@ -0,0 +1,38 @@
+:- module(tests_on_stack_trimming, []).
+
+:- use_module(library(lists)).
+
+count_down(N) :-
+    (  N > 0 ->
+       N1 is N - 1,
+       count_down(N1)
+    ;  true
+    ).
+
+cut_each_step(N) :-
+    N > 0,
+    member(_, [a, b]),
+    !,
+    N1 is N - 1,
+    cut_each_step(N1).
+cut_each_step(0).
+
+% the stack grown by Goal at its highest, in bytes.
+stack_growth(Goal, Growth) :-
+    '$reset_query_stats',
+    call(Goal),
+    '$stack_statistics'(_, Growth, _, _).
+
+test_queries_on_stack_trimming :-
+    stack_growth(length(_, 100000), G1),
+    G1 < 10000,
+    stack_growth(count_down(100000), G2),
+    G2 < 10000,
+    stack_growth(cut_each_step(100000), G3),
+    G3 < 10000,
+    '$stack_statistics'(Size, _, Environments, ChoicePoints),
+    Size > 0,
+    Environments > 0,
+    integer(ChoicePoints).
+
+:- initialization(test_queries_on_stack_trimming).
@@ -0,0 +1,38 @@
+:- module(tests_on_statistics, []).
+
+:- use_module(library(between)).
+:- use_module(library(charsio)).
+
+test_queries_on_statistics :-
+    statistics(atoms, Atoms),
+    integer(Atoms), Atoms > 0,
+    statistics(heap, Heap0),
+    functor(T, f, 10),
+    statistics(heap, Heap),
+    Heap >= Heap0 + 11,
+    T = f(_, _, _, _, _, _, _, _, _, _),
+    statistics(strings, Strings0),
+    read_term_from_chars("\"a string read twice\".", T1, []),
+    read_term_from_chars("\"a string read twice\".", T2, []),
+    T1 == T2,
+    statistics(strings, Strings),
+    Strings =:= Strings0 + 1,
+    \+ statistics(foo, _),
+    statistics(cputime, T0),
+    float(T0),
+    findall(N, between(1, 1000, N), _),
+    statistics(cputime, T),
+    T >= T0,
+    statistics(runtime, [R0, _]),
+    statistics(walltime, [W0, _]),
+    findall(N, between(1, 1000, N), _),
+    statistics(runtime, [R, RSince]),
+    integer(R), R >= R0,
+    RSince =:= R - R0,
+    statistics(walltime, [W, WSince]),
+    integer(W), W >= W0,
+    WSince =:= W - W0,
+    findall(Key, statistics(Key, _), Keys),
+    Keys == [atoms, strings, heap, retired_code, cputime, runtime, walltime, random_draws].
+
+:- initialization(test_queries_on_statistics).
@@ -0,0 +1,42 @@
+:- module(tests_on_stream_double_quotes, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_data(File) :-
+    current_output(Out),
+    setup_call_cleanup(open(File, write, S),
+                       ( set_output(S),
+                         write('"ab". "cd". "ef". "gh".'), nl ),
+                       ( set_output(Out), close(S) )).
+
+read_data(File, Ts) :-
+    current_input(In),
+    setup_call_cleanup(open(File, read, S),
+                       ( set_input(S), read_data_(S, Ts) ),
+                       ( set_input(In), close(S) )).
+
+read_data_(S, [T1, T2, T3, T4]) :-
+    read_term(T1, [double_quotes(codes)]),
+    set_stream(S, double_quotes(atom)),
+    read(T2),
+    read_term(T3, [double_quotes(chars), variable_names(_)]),
+    read_term(T4, [variable_names(_)]).
+
+test_queries_on_stream_double_quotes :-
+    tmp_file('stream_double_quotes_test.pl', F),
+    setup_call_cleanup(true, stream_double_quotes_queries(F), catch(delete_file(F), _, true)).
+
+stream_double_quotes_queries(F) :-
+    write_data(F),
+    read_data(F, Ts),
+    Ts == [[0'a, 0'b], cd, [e, f], gh],
+    current_prolog_flag(double_quotes, chars),
+    catch(set_stream(user_input, double_quotes(text)),
+          error(domain_error(stream_property, double_quotes(text)), set_stream/2),
+          true),
+    catch(read_term(_, [double_quotes(text)]),
+          error(domain_error(read_option, double_quotes(text)), read_term/2),
+          true).
+
+:- initialization(test_queries_on_stream_double_quotes).
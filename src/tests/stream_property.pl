@@ -0,0 +1,53 @@
+:- module(tests_on_stream_property, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_chars(S, Cs) :-
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write(Cs), set_output(Out)).
+
+read_char(S, C) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_char(C), set_input(In)).
+
+test_queries_on_stream_property :-
+    tmp_file('stream_property_test.txt', F),
+    setup_call_cleanup(true, stream_property_queries(F), catch(delete_file(F), _, true)).
+
+stream_property_queries(F) :-
+    open(F, write, W),
+    stream_property(W, file_name(F)),
+    stream_property(W, mode(write)),
+    stream_property(W, output),
+    \+ stream_property(W, input),
+    stream_property(W, type(text)),
+    stream_property(W, position(0)),
+    write_chars(W, ab),
+    stream_property(W, position(2)),
+    stream_property(S, file_name(F)),
+    S == W,
+    close(W),
+    \+ stream_property(_, file_name(F)),
+    open(F, read, R),
+    stream_property(R, mode(read)),
+    stream_property(R, end_of_stream(not)),
+    \+ at_end_of_stream(R),
+    read_char(R, a),
+    read_char(R, b),
+    at_end_of_stream(R),
+    stream_property(R, end_of_stream(at)),
+    stream_property(R, position(2)),
+    read_char(R, end_of_file),
+    stream_property(R, end_of_stream(past)),
+    close(R),
+    catch(stream_property(_, colour(red)),
+          error(domain_error(stream_property, colour(red)), stream_property/2),
+          true),
+    catch(at_end_of_stream(_), error(instantiation_error, at_end_of_stream/1), true),
+    current_output(Out),
+    catch(at_end_of_stream(Out),
+          error(permission_error(input, stream, _), at_end_of_stream/1),
+          true).
+
+:- initialization(test_queries_on_stream_property).
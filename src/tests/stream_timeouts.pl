@@ -0,0 +1,40 @@
+:- module(tests_on_stream_timeouts, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(sockets)).
+
+write_chars(S, Cs) :-
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write(Cs), set_output(Out)).
+
+timeout_error(S, T, E) :-
+    catch(set_stream(S, timeout(T)), error(E, _), true).
+
+% an infinite float, if the arithmetic yields one.
+infinite_float(F) :-
+    catch(F is 1.0e308 * 10.0, _, false),
+    float(F).
+
+test_queries_on_stream_timeouts :-
+    socket_server_open('127.0.0.1':Port, Server),
+    socket_client_open('127.0.0.1':Port, C, []),
+    socket_server_accept(Server, _, S, []),
+    set_stream(S, timeout(0.1)),
+    catch((peek_char(S, _), false), error(timeout_error(read, S), _), true),
+    set_stream(S, timeout(1)),
+    write_chars(C, 'a. '),
+    peek_char(S, a),
+    set_stream(S, timeout(infinite)),
+    timeout_error(S, _, instantiation_error),
+    timeout_error(S, 0, domain_error(stream_property, timeout(0))),
+    timeout_error(S, -1.0, domain_error(stream_property, timeout(-1.0))),
+    timeout_error(S, never, domain_error(stream_property, timeout(never))),
+    (  infinite_float(Inf) ->
+       timeout_error(S, Inf, domain_error(stream_property, timeout(Inf)))
+    ;  true
+    ),
+    close(C),
+    close(S),
+    close(Server).
+
+:- initialization(test_queries_on_stream_timeouts).
@@ -0,0 +1,27 @@
+:- module(tests_on_string_text, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+:- set_prolog_flag(double_quotes, chars).
+
+test_queries_on_string_text :-
+    atom_length("abc", 3),
+    atom_length("", 0),
+    atom_length(abc, 3),
+    partial_string("ab", PS, "cd"),
+    atom_length(PS, 4),
+    partial_string("ab", PV, _),
+    catch(atom_length(PV, _), error(instantiation_error, atom_length/2), true),
+    catch(atom_length(f(x), _), error(type_error(atom, f(x)), atom_length/2), true),
+    sub_atom("hello", 1, 3, A, Sub),
+    A == 1,
+    Sub == "ell",
+    findall(B-L, sub_atom("abab", B, L, _, "ab"), [0-2, 2-2]),
+    sub_atom("abc", 0, 1, _, a),
+    sub_atom(PS, 2, 2, 0, "cd"),
+    catch(sub_atom(PV, _, _, _, _), error(instantiation_error, sub_atom/5), true),
+    char_code("a", 97),
+    catch(char_code("ab", _), error(type_error(character, "ab"), char_code/2), true).
+
+:- initialization(test_queries_on_string_text).
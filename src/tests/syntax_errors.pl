@@ -0,0 +1,18 @@
+:- module(tests_on_syntax_errors, []).
+
+:- dynamic(captured/1).
+
+capture(Message) :-
+    assertz(captured(Message)).
+
+:- use_module('syntax_errors/hook').
+:- use_module('syntax_errors/fruit').
+
+test_queries_on_syntax_errors :-
+    findall(F, fruit:fruit(F), Fs),
+    Fs == [apple, plum, quince],
+    findall(Line, captured(syntax_error(_, Line, _)), Lines),
+    length(Lines, 3),
+    msort(Lines, Lines).
+
+:- initialization(test_queries_on_syntax_errors).
@@ -0,0 +1,8 @@
+:- module(fruit, [fruit/1]).
+
+fruit(apple).
+fruit(pear) :- .
+fruit(plum).
+fruit(fig)) .
+fruit(quince).
+fruit('kiwi
@@ -0,0 +1,5 @@
+% defines message_hook/3 in user, handing syntax_error/3 messages to
+% tests_on_syntax_errors rather than printing them.
+message_hook(Message, _, _) :-
+    Message = syntax_error(_, _, _),
+    tests_on_syntax_errors:capture(Message).
@@ -0,0 +1,31 @@
+:- module(tests_on_terms, []).
+
+:- use_module(library(lists)).
+:- use_module(library(terms)).
+
+a_to_b(a, b).
+
+count_ints(X, Y, N0, N) :-
+    integer(X),
+    Y is X + 1,
+    N is N0 + 1.
+
+test_queries_on_terms :-
+    mapsubterms(a_to_b, f(a, g(a, c), [a, d]), T1),
+    T1 == f(b, g(b, c), [b, d]),
+    mapsubterms(a_to_b, [a|a], T2),
+    T2 == [b|b],
+    mapsubterms(a_to_b, "xay", T3),
+    T3 == [x, b, y],
+    T4 = f(x, g(y), [1, 2]),
+    mapsubterms(a_to_b, T4, T5),
+    T5 == T4,
+    foldsubterms(count_ints, f(1, [2, x, 3], g(4)), T6, 0, N),
+    T6 == f(2, [3, x, 4], g(5)),
+    N == 4,
+    length(Xs, 100000),
+    maplist(=(x), Xs),
+    mapsubterms(a_to_b, [a|Xs], [b|Ys]),
+    Ys == Xs.
+
+:- initialization(test_queries_on_terms).
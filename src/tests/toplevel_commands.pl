@@ -0,0 +1,41 @@
+:- module(tests_on_toplevel_commands, []).
+
+:- use_module(library(iso_ext)).
+
+test_queries_on_toplevel_commands :-
+    current_prolog_flag(toplevel_command_prefix, (:)),
+    with_flags([toplevel_command_prefix-'#'],
+               current_prolog_flag(toplevel_command_prefix, '#')),
+    with_flags([toplevel_command_prefix-''],
+               current_prolog_flag(toplevel_command_prefix, '')),
+    current_prolog_flag(toplevel_command_prefix, (:)),
+    catch(set_prolog_flag(toplevel_command_prefix, "x"),
+          error(domain_error(flag_value, toplevel_command_prefix + [x]), _), true),
+    current_prolog_flag(toplevel_prompt, '?- '),
+    with_flags([toplevel_prompt-'| ?- ', toplevel_continuation_prompt-'|    '],
+               ( current_prolog_flag(toplevel_prompt, '| ?- '),
+                 current_prolog_flag(toplevel_continuation_prompt, '|    ') )),
+    current_prolog_flag(toplevel_prompt, '?- '),
+    current_prolog_flag(toplevel_continuation_prompt, ''),
+    current_prolog_flag(toplevel_banner, ''),
+    with_flags([toplevel_banner-'hello'], current_prolog_flag(toplevel_banner, hello)),
+    current_prolog_flag(toplevel_banner, ''),
+    catch(set_prolog_flag(toplevel_prompt, 1),
+          error(domain_error(flag_value, toplevel_prompt + 1), _), true),
+    current_prolog_flag(toplevel_max_bindings_shown, infinite),
+    current_prolog_flag(toplevel_elide_self_bindings, true),
+    current_prolog_flag(toplevel_max_atom_length, infinite),
+    with_flags([toplevel_max_bindings_shown-0, toplevel_elide_self_bindings-false,
+                toplevel_max_atom_length-8],
+               ( current_prolog_flag(toplevel_max_bindings_shown, 0),
+                 current_prolog_flag(toplevel_elide_self_bindings, false),
+                 current_prolog_flag(toplevel_max_atom_length, 8) )),
+    current_prolog_flag(toplevel_max_bindings_shown, infinite),
+    catch(set_prolog_flag(toplevel_max_bindings_shown, -1),
+          error(domain_error(flag_value, toplevel_max_bindings_shown + -1), _), true),
+    catch(set_prolog_flag(toplevel_elide_self_bindings, yes),
+          error(domain_error(flag_value, toplevel_elide_self_bindings + yes), _), true),
+    catch(set_prolog_flag(toplevel_max_atom_length, 0),
+          error(domain_error(flag_value, toplevel_max_atom_length + 0), _), true).
+
+:- initialization(test_queries_on_toplevel_commands).
@@ -0,0 +1,46 @@
+:- module(tests_on_type_checks, []).
+
+:- use_module(library(error)).
+
+test_queries_on_type_checks :-
+    is_list([]),
+    is_list([a, _]),
+    is_list("abc"),
+    \+ is_list([a | _]),
+    \+ is_list([a | b]),
+    \+ is_list(_),
+    L = [a, b | L],
+    \+ is_list(L),
+    callable(a),
+    callable(f(_)),
+    callable([a]),
+    callable((a, 1)),
+    \+ callable(1),
+    \+ callable(_),
+    must_be(goal, (a, _ ; b -> c)),
+    catch(must_be(goal, (a, 1)), error(type_error(callable, (a, 1)), _), true),
+    must_be(callable, f(x)),
+    catch(must_be(callable, 3), error(type_error(callable, 3), _), true),
+    must_be(boolean, true),
+    catch(must_be(boolean, yes), error(type_error(boolean, yes), _), true),
+    must_be(nonneg, 0),
+    catch(must_be(nonneg, -1), error(domain_error(not_less_than_zero, -1), _), true),
+    catch(must_be(positive_integer, 0), error(domain_error(positive_integer, 0), _), true),
+    catch(must_be(positive_integer, a), error(type_error(integer, a), _), true),
+    must_be(chars, [a, b]),
+    catch(must_be(chars, [a, 1]), error(type_error(chars, [a, 1]), _), true),
+    catch(must_be(chars, [a | _]), error(instantiation_error, _), true),
+    must_be(codes, [0'a]),
+    catch(must_be(list, [a | b]), error(type_error(list, [a | b]), _), true),
+    catch(must_be(ground, f(_)), error(instantiation_error, _), true),
+    catch(must_be(integer, _), error(instantiation_error, _), true),
+    catch(must_be(var, a), error(uninstantiation_error(a), _), true),
+    catch(must_be(foo, a), error(type_error(type, foo), _), true),
+    A = f(A),
+    catch(must_be(acyclic, A), error(type_error(acyclic_term, _), _), true),
+    can_be(chars, [a | _]),
+    can_be(ground, f(_)),
+    catch(can_be(chars, [1 | _]), error(type_error(chars, _), _), true),
+    catch(can_be(nonneg, -1), error(domain_error(not_less_than_zero, -1), _), true).
+
+:- initialization(test_queries_on_type_checks).
@@ -0,0 +1,28 @@
+":- module(tests_on_unicode_identifiers, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+written(Term, Quoted, Chars) :-
+    '$write_term_to_chars'(Term, false, true, Quoted, [], 0, Chars).
+
+% atoms written by writeq/1 are read back by the reader, which only
+% knows ASCII letters, so they are quoted with the flag on as well.
+test_queries_on_unicode_identifiers :-
+    current_prolog_flag(unicode_identifiers, false),
+    atom_codes(Nihon, [0x65e5, 0x672c]),
+    atom_codes(Ete, [0xc9, 0x74, 0xe9]),
+    written(Nihon, true, Cs1),
+    Cs1 = ['\'', '\\' | _],
+    written(Nihon, false, ['\\' | _]),
+    with_flags([unicode_identifiers-true],
+               ( written(Nihon, true, Cs2),
+                 written(Ete, true, ['\'' | _]),
+                 written(f(Nihon), true, [f, '(', '\'' | _])
+               )),
+    Cs1 == Cs2,
+    current_prolog_flag(unicode_identifiers, false),
+    catch(set_prolog_flag(unicode_identifiers, yes),
+          error(domain_error(flag_value, unicode_identifiers + yes), _), true).
+
+:- initialization(test_queries_on_unicode_identifiers).
@@ -0,0 +1,18 @@
+:- module(tests_on_unifiable, []).
+
+test_queries_on_unifiable :-
+    unifiable(f(X, b), f(a, Y), U1),
+    var(X), var(Y),
+    U1 = [_, _],
+    \+ \+ (maplist_call(U1), X == a, Y == b),
+    \+ unifiable(f(X, b), f(a, X), _),
+    unifiable(g(Z), g(Z), U2),
+    U2 == [],
+    unifiable([A|B], "ab", U3),
+    var(A), var(B),
+    \+ \+ (maplist_call(U3), A == a, B = [b]).
+
+maplist_call([]).
+maplist_call([G|Gs]) :- call(G), maplist_call(Gs).
+
+:- initialization(test_queries_on_unifiable).
@@ -0,0 +1,34 @@
+:- module(tests_on_wam_instructions, []).
+
+:- use_module(library(diag)).
+:- use_module(library(lists)).
+
+:- dynamic(d/1).
+:- dynamic(e/1).
+
+d(1).
+
+s(a).
+s(b).
+s(X) :- X = c.
+
+test_queries_on_wam_instructions :-
+    wam_instructions(tests_on_wam_instructions:s/1, Static),
+    member(switch_on_term(label(V), label(C), fail, fail), Static),
+    member(switch_on_constant(Table), Static),
+    member(a-label(A), Table),
+    member(b-label(B), Table),
+    append(_, [label(A), get_constant(_, a, x(1)) | _], Static),
+    append(_, [label(B), get_constant(_, b, x(1)) | _], Static),
+    append(_, [label(C), switch_on_constant(_) | _], Static),
+    append(_, [label(V), try_me_else(_) | _], Static),
+    member(put_constant(_, c, x(2)), Static),
+    assertz(d(2)),
+    wam_instructions(d/1, Dynamic),
+    member(switch_on_constant([1-label(_), 2-label(_)]), Dynamic),
+    wam_instructions(e/1, []),
+    wam_instructions(lists:append/3, Append),
+    member(execute(append, 3), Append),
+    catch(wam_instructions(nope/0, _), error(existence_error(procedure, nope/0), _), true).
+
+:- initialization(test_queries_on_wam_instructions).
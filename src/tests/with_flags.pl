@@ -0,0 +1,33 @@
+:- module(tests_on_with_flags, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+double_quotes(Value) :-
+    current_prolog_flag(double_quotes, Value).
+
+test_queries_on_with_flags :-
+    double_quotes(Old),
+    with_flags([double_quotes-atom], double_quotes(atom)),
+    double_quotes(Old),
+    catch(with_flags([double_quotes-codes], throw(ball)), ball, true),
+    double_quotes(Old),
+    \+ with_flags([double_quotes-codes], fail),
+    double_quotes(Old),
+    findall(X-V-W,
+            ( with_flags([double_quotes-codes],
+                         ( member(X, [a, b]), double_quotes(V) )),
+              double_quotes(W)
+            ),
+            Solutions),
+    Solutions == [a-codes-Old, b-codes-Old],
+    once(with_flags([double_quotes-codes], member(_, [a, b]))),
+    double_quotes(Old),
+    catch(with_flags([double_quotes-codes, autoload-bogus], true),
+          error(domain_error(flag_value, autoload+bogus), _), true),
+    double_quotes(Old),
+    catch(with_flags(_, true), error(instantiation_error, with_flags/2), true),
+    catch(with_flags(foo, true), error(type_error(list, foo), with_flags/2), true),
+    catch(with_flags([a], true), error(type_error(pair, a), with_flags/2), true).
+
+:- initialization(test_queries_on_with_flags).
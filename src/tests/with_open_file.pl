@@ -0,0 +1,43 @@
+:- module(tests_on_with_open_file, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_chars(S, Cs) :-
+    current_output(Out),
+    setup_call_cleanup(set_output(S), write(Cs), set_output(Out)).
+
+read_char(S, C) :-
+    current_input(In),
+    setup_call_cleanup(set_input(S), get_char(C), set_input(In)).
+
+test_queries_on_with_open_file :-
+    tmp_file('with_open_file_test.txt', F),
+    setup_call_cleanup(true,
+                       with_open_file_queries(F),
+                       catch(delete_file(F), _, true)).
+
+with_open_file_queries(F) :-
+    current_output(Out0),
+    with_open_file(F, write, S1^write_chars(S1, abc)),
+    catch(write_chars(S1, abc), error(existence_error(stream, S1), _), true),
+    with_open_file(F, read, S2^read_char(S2, a)),
+    catch(with_open_file(F, append, S3^(write_chars(S3, d), throw(oops))),
+          oops,
+          true),
+    with_open_file(F, read, S4^(read_char(S4, a), read_char(S4, b),
+                                read_char(S4, c), read_char(S4, d))),
+    \+ with_open_file(F, read, S5^(read_char(S5, C), C == z)),
+    catch(with_open_file(F, read, _), error(instantiation_error, _), true),
+    catch(with_open_file(F, read, foo), error(type_error(lambda, foo), _), true),
+    catch(open(F, modify, _), error(domain_error(io_mode, modify), _), true),
+    catch(open('no/such/dir/file.txt', read, _),
+          error(existence_error(source_sink, _), _),
+          true),
+    current_output(Out),
+    Out == Out0,
+    delete_file(F),
+    catch(delete_file(F), error(existence_error(source_sink, F), _), true),
+    catch(delete_file(_), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_with_open_file).
@@ -0,0 +1,33 @@
+:- module(tests_on_with_output_to, []).
+
+:- use_module(library(iso_ext)).
+
+test_queries_on_with_output_to :-
+    with_output_to(atom(A), ( write(f(x)), nl, print('B') )),
+    A == 'f(x)\n\'B\'',
+    with_output_to(chars(Cs), write(ab)),
+    Cs == [a, b],
+    with_output_to(chars(Cs1, T), write(ab)),
+    Cs1 == [a, b | T],
+    with_output_to(codes(Ds), write(ab)),
+    Ds == [0'a, 0'b],
+    with_output_to(codes(Ds1, []), write(ab)),
+    Ds1 == [0'a, 0'b],
+    with_output_to(string(S), write(ab)),
+    S == "ab",
+    with_output_to(atom(E), true),
+    E == '',
+    with_output_to(atom(O), with_output_to(atom(I), write(inner))),
+    I == inner,
+    O == '',
+    with_output_to(atom(C), member(C0, [a, b])),
+    C == '',
+    C0 == a,
+    current_output(Out),
+    \+ with_output_to(atom(_), ( write(x), fail )),
+    catch(with_output_to(atom(_), throw(ball)), ball, true),
+    current_output(Out),
+    catch(with_output_to(_, true), error(instantiation_error, with_output_to/2), true),
+    catch(with_output_to(foo(_), true), error(domain_error(output_sink, foo(_)), with_output_to/2), true).
+
+:- initialization(test_queries_on_with_output_to).
@@ -0,0 +1,32 @@
+:- module(tests_on_with_output_to_stream, []).
+
+:- use_module(library(iso_ext)).
+:- use_module(library(lists)).
+
+output_is(S) :-
+    current_output(Out),
+    Out == S.
+
+test_queries_on_with_output_to_stream :-
+    current_output(Out0),
+    null_stream(Null),
+    with_output_to_stream(Null, (write(silenced), nl, output_is(Null))),
+    output_is(Out0),
+    catch(with_output_to_stream(Null, throw(ball)), ball, true),
+    output_is(Out0),
+    \+ with_output_to_stream(Null, fail),
+    output_is(Out0),
+    findall(X-W,
+            ( with_output_to_stream(Null, ( member(X, [a, b]), output_is(Null) )),
+              current_output(W)
+            ),
+            Solutions),
+    Solutions == [a-Out0, b-Out0],
+    once(with_output_to_stream(Null, member(_, [a, b]))),
+    output_is(Out0),
+    catch(null_stream(foo), error(uninstantiation_error(foo), null_stream/1), true),
+    catch(with_output_to_stream(_, true), error(instantiation_error, _), true),
+    output_is(Out0),
+    close(Null).
+
+:- initialization(test_queries_on_with_output_to_stream).
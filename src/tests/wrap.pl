@@ -0,0 +1,41 @@
+:- module(tests_on_wrap, []).
+
+:- use_module(library(wrap)).
+
+double(X, Y) :- Y is 2 * X.
+
+quadruple(X, Y) :- double(X, Z), double(Z, Y).
+
+test_queries_on_wrap :-
+    quadruple(1, 4),
+    wrap_predicate(double(_, _), pass, W, W),
+    wrap_predicate(double(A, B), mock, _, (A == 1 -> B = 10 ; B = 0)),
+    quadruple(1, 0),
+    unwrap_predicate(double/2, mock),
+    quadruple(1, 4),
+    \+ unwrap_predicate(double/2, mock),
+    wrap_predicate(double(C, D), twice, Inner, (Inner, D > C)),
+    \+ double(-1, _),
+    double(3, 6),
+    unwrap_predicate(double/2, pass),
+    \+ double(-1, _),
+    unwrap_predicate(double/2, twice),
+    double(-1, -2),
+    catch(wrap_predicate(no_such_predicate(_), w, _, true),
+          error(existence_error(procedure, no_such_predicate/1), _),
+          true),
+    catch(unwrap_predicate(_, w), error(instantiation_error, _), true),
+    catch(advice(double/2, [enter], on), error(domain_error(advice_port, enter), _), true),
+    with_redefined(double/2, [double(_, 7)], quadruple(1, 7)),
+    with_redefined(double/2, [(double(X, Y) :- Y is X + 1)],
+                   ( quadruple(1, 3),
+                     with_redefined(double/2, [], \+ quadruple(1, _)),
+                     quadruple(1, 3) )),
+    quadruple(1, 4),
+    catch(with_redefined(double/2, [], throw(oops)), oops, true),
+    double(1, 2),
+    catch(with_redefined(double/2, [triple(_, _)], true),
+          error(domain_error(clause_of(double/2), triple(_, _)), _),
+          true).
+
+:- initialization(test_queries_on_wrap).
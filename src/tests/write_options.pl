@@ -0,0 +1,50 @@
+:- module(tests_on_write_options, []).
+
+:- use_module(library(files)).
+:- use_module(library(iso_ext)).
+
+write_error(Options, E) :-
+    current_output(Out),
+    catch(write_term(Out, t, Options), error(E, _), true).
+
+test_queries_on_write_options :-
+    tmp_file('write_options_test.pl', F),
+    setup_call_cleanup(true, write_option_queries(F), catch(delete_file(F), _, true)).
+
+write_option_queries(F) :-
+    setup_call_cleanup(open(F, write, W),
+                       ( write_term(W, f(X, 'A b'), [quoted(true), variable_names(['X' = X])]),
+                         write(W, '.\n'),
+                         writeq(W, g('$VAR'(1), [a])),
+                         write(W, '.\n'),
+                         write_canonical(W, 1 + 2),
+                         write(W, '.\n'),
+                         write_term(W, 'h i', [quoted(true), quoted(false)]),
+                         write(W, '.\n') ),
+                       close(W)),
+    setup_call_cleanup(open(F, read, R),
+                       ( read_term(R, T1, [variable_names(VNs1)]),
+                         read(R, T2),
+                         read(R, T3),
+                         read(R, T4) ),
+                       close(R)),
+    T1 = f(Y, 'A b'),
+    VNs1 == ['X' = Y],
+    T2 = g(B, [a]),
+    var(B),
+    T3 == +(1, 2),
+    T4 == 'h i',
+    write_error(_, instantiation_error),
+    write_error([quoted(true) | _], instantiation_error),
+    write_error([_], instantiation_error),
+    write_error([bad], domain_error(write_option, bad)),
+    write_error([quoted(true), max_depth(-1)], domain_error(write_option, max_depth(-1))),
+    write_error(quoted(true), type_error(list, quoted(true))),
+    setup_call_cleanup(open(F, read, In),
+                       catch(write_term(In, t, []),
+                             error(permission_error(output, stream, In), _),
+                             true),
+                       close(In)),
+    catch(write(_, t), error(instantiation_error, _), true).
+
+:- initialization(test_queries_on_write_options).
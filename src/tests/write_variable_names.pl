@@ -0,0 +1,28 @@
+:- module(tests_on_write_variable_names, []).
+
+:- set_prolog_flag(double_quotes, chars).
+
+written(Term, VarNames, Chars) :-
+    '$write_term_to_chars'(Term, false, true, true, VarNames, 0, Chars).
+
+write_error(Options, E) :-
+    catch(write_term(t, Options), error(E, _), true).
+
+test_queries_on_write_variable_names :-
+    written(f(X, Y, X), ['X' = X, 'Y' = Y], "f(X,Y,X)"),
+    written(f(X, Y, Z, Y), ['X' = X], "f(X,_G0,_G1,_G0)"),
+    written(g(Z), ['A' = Z, 'B' = Z], "g(A)"),
+    written(h(_), [], "h(_G0)"),
+    written(X + Y, ['Y' = Y, 'X' = X], "X+Y"),
+    write_error([variable_names(_)], instantiation_error),
+    write_error([variable_names([_])], instantiation_error),
+    write_error([variable_names([_ = _])], instantiation_error),
+    write_error([variable_names(['X' = _ | _])], instantiation_error),
+    write_error([variable_names(x)], domain_error(write_option, variable_names(x))),
+    write_error([variable_names([1 = _])], domain_error(write_option, variable_names([1 = _]))),
+    write_error([variable_names([x])], domain_error(write_option, variable_names([x]))),
+    write_error([variable_names(true)], domain_error(write_option, variable_names(true))),
+    write_error([max_depth(true)], domain_error(write_option, max_depth(true))),
+    write_error([quoted(yes)], domain_error(write_option, quoted(yes))).
+
+:- initialization(test_queries_on_write_variable_names).
@@ -0,0 +1,114 @@
+extern crate scryer_prolog;
+
+use scryer_prolog::{Machine, Solution};
+
+fn bindings(pairs: &[(&str, &str)]) -> Solution {
+    Solution::Bindings(pairs.iter().map(|(var, value)| (var.to_string(), value.to_string())).collect())
+}
+
+#[test]
+fn run_query_finds_every_solution() {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult("p(1, a). p(2, 'B c'). p(3, [x]).").is_ok());
+
+    assert_eq!(
+        wam.run_query("p(N, X)").collect::<Vec<_>>(),
+        vec![
+            bindings(&[("N", "1"), ("X", "a")]),
+            bindings(&[("N", "2"), ("X", "'B c'")]),
+            bindings(&[("N", "3"), ("X", "[x]")]),
+        ]
+    );
+
+    // the full stop is optional, and variables beginning with an
+    // underscore aren't among the bindings.
+    assert_eq!(
+        wam.run_query("p(2, _X).").collect::<Vec<_>>(),
+        vec![bindings(&[])]
+    );
+
+    assert_eq!(wam.run_query("p(4, _)").collect::<Vec<_>>(), vec![]);
+}
+
+// queries are stopped by an uncaught exception or a syntax error,
+// either of which is their last solution.
+#[test]
+fn run_query_ends_at_exceptions() {
+    let mut wam = Machine::new_embedded();
+
+    assert_eq!(
+        wam.run_query("( X = 1 ; throw(oops) ; X = 2 )").collect::<Vec<_>>(),
+        vec![bindings(&[("X", "1")]), Solution::Exception("oops".to_string())]
+    );
+
+    match wam.run_query("p(").collect::<Vec<_>>().as_slice() {
+        [Solution::Exception(ball)] => assert!(ball.starts_with("error(syntax_error("), "{}", ball),
+        solutions => panic!("unexpected solutions {:?}", solutions),
+    }
+
+    // the machine goes on to run the next query.
+    assert_eq!(
+        wam.run_query("atom_length(abc, N)").collect::<Vec<_>>(),
+        vec![bindings(&[("N", "3")])]
+    );
+}
+
+// solutions are found as they're iterated, so that a query with
+// infinitely many of them can be run.
+#[test]
+fn run_query_is_lazy() {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult("nat(0). nat(N) :- nat(N0), N is N0 + 1.").is_ok());
+
+    assert_eq!(
+        wam.run_query("nat(N)").take(3).collect::<Vec<_>>(),
+        vec![bindings(&[("N", "0")]), bindings(&[("N", "1")]), bindings(&[("N", "2")])]
+    );
+}
+
+// directives of consulted text are run as they would be in a file.
+#[test]
+fn consult_runs_directives() {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult(":- use_module(library(lists)).").is_ok());
+
+    assert_eq!(
+        wam.run_query("append(X, [c], [a, b, c])").collect::<Vec<_>>(),
+        vec![bindings(&[("X", "[a,b]")])]
+    );
+}
+
+// solve stops the solutions of a query once its callback breaks,
+// running the cleanup goals of the choicepoints left behind.
+#[test]
+fn solve_stops_at_break() {
+    use std::ops::ControlFlow;
+
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult(":- use_module(library(iso_ext)). \
+                         :- use_module(library(lists)). \
+                         :- dynamic(cleaned/0). \
+                         q(X) :- setup_call_cleanup(true, member(X, [a, b, c]), assertz(cleaned)).").is_ok());
+
+    let mut seen = vec![];
+
+    let found = wam.solve("q(X)", |solution| {
+        seen.push(solution.clone());
+
+        if solution == bindings(&[("X", "b")]) {
+            ControlFlow::Break("b")
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert_eq!(found, Some("b"));
+    assert_eq!(seen, vec![bindings(&[("X", "a")]), bindings(&[("X", "b")])]);
+    assert_eq!(wam.run_query("cleaned").collect::<Vec<_>>(), vec![bindings(&[])]);
+
+    assert_eq!(wam.solve("fail", |_| ControlFlow::Break(())), None);
+}
@@ -0,0 +1,84 @@
+extern crate scryer_prolog;
+#[macro_use]
+extern crate prolog_parser;
+
+use prolog_parser::ast::*;
+use scryer_prolog::prolog::machine::term_view::TermRef;
+use scryer_prolog::{Machine, Solution};
+
+use std::cell::Cell;
+
+fn atom(name: &'static str) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(clause_name!(name), None))
+}
+
+fn compound(name: &'static str, args: Vec<Term>) -> Term {
+    Term::Clause(Cell::default(), clause_name!(name), args.into_iter().map(Box::new).collect(), None)
+}
+
+// a machine with the callback next_letter, which answers the letter
+// after a, declines to answer for b and throws for anything else.
+fn machine_with_callback() -> Machine {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult(":- use_module(library(host)).").is_ok());
+
+    wam.register_host_callback("next_letter", |request| {
+        match request.arg(1).map(|arg| arg.get()) {
+            Some(TermRef::Atom("a")) => Ok(Some(compound("letter", vec![atom("b")]))),
+            Some(TermRef::Atom("b")) => Ok(None),
+            _ => Err(compound("no_letter", vec![atom("after")])),
+        }
+    });
+
+    wam
+}
+
+#[test]
+fn callback_replies_are_unified() {
+    let mut wam = machine_with_callback();
+
+    assert_eq!(
+        wam.run_query("host_call(next_letter(a), R)").collect::<Vec<_>>(),
+        vec![Solution::Bindings(vec![("R".to_string(), "letter(b)".to_string())])]
+    );
+
+    assert_eq!(
+        wam.run_query("host_call(next_letter(a), letter(L))").collect::<Vec<_>>(),
+        vec![Solution::Bindings(vec![("L".to_string(), "b".to_string())])]
+    );
+
+    assert_eq!(
+        wam.run_query("host_call(next_letter(a), letter(c))").collect::<Vec<_>>(),
+        vec![]
+    );
+}
+
+#[test]
+fn callbacks_decline_and_throw() {
+    let mut wam = machine_with_callback();
+
+    assert_eq!(
+        wam.run_query("host_call(next_letter(b), _)").collect::<Vec<_>>(),
+        vec![]
+    );
+
+    assert_eq!(
+        wam.run_query("host_call(next_letter(z), _)").collect::<Vec<_>>(),
+        vec![Solution::Exception("no_letter(after)".to_string())]
+    );
+}
+
+// registering a callback under a name replaces the one registered
+// before it.
+#[test]
+fn callbacks_are_replaced() {
+    let mut wam = machine_with_callback();
+
+    wam.register_host_callback("next_letter", |_| Ok(Some(atom("replaced"))));
+
+    assert_eq!(
+        wam.run_query("host_call(next_letter(a), R)").collect::<Vec<_>>(),
+        vec![Solution::Bindings(vec![("R".to_string(), "replaced".to_string())])]
+    );
+}
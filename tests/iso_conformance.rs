@@ -0,0 +1,38 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+// loads src/tests/iso_conformance.pl, which runs run_iso_tests/0 as
+// it is loaded, and halts from the toplevel once it is done.
+#[test]
+fn iso_conformance() {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    path.push("src");
+    path.push("tests");
+    path.push("iso_conformance.pl");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scryer-prolog"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run scryer-prolog");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"halt.\n")
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stdout.contains("ISO conformance tests passed"),
+        "ISO conformance tests failed:\n{}",
+        stdout
+    );
+}
@@ -0,0 +1,113 @@
+extern crate scryer_prolog;
+#[macro_use]
+extern crate prolog_parser;
+
+use prolog_parser::ast::*;
+use scryer_prolog::prolog::machine::preemption::PreemptionAction;
+use scryer_prolog::prolog::machine::QueryPoll;
+use scryer_prolog::{Machine, Solution};
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+const WALK: &str = "walk([]). walk([_ | T]) :- walk(T). loop :- loop.";
+
+const WALK_QUERY: &str = "length(L, 1000), walk(L)";
+
+fn atom(name: &'static str) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(clause_name!(name), None))
+}
+
+// walk(L) for a list L of n atoms.
+fn walk_goal(n: usize) -> Term {
+    let items = (0 .. n).fold(Term::Constant(Cell::default(), Constant::EmptyList), |tail, _| {
+        Term::Cons(Cell::default(), Box::new(atom("a")), Box::new(tail))
+    });
+
+    Term::Clause(Cell::default(), clause_name!("walk"), vec![Box::new(items)], None)
+}
+
+fn machine() -> Machine {
+    let mut wam = Machine::new_embedded();
+    assert!(wam.consult(WALK).is_ok());
+    wam
+}
+
+fn succeeds(wam: &mut Machine, query: &str) -> bool {
+    match wam.run_query(query).next() {
+        Some(Solution::Bindings(_)) => true,
+        _ => false,
+    }
+}
+
+#[test]
+fn continue_runs_on() {
+    let mut wam = machine();
+    let calls = Rc::new(Cell::new(0));
+
+    let hook_calls = calls.clone();
+
+    wam.set_preemption_hook(10, move |instructions| {
+        assert_eq!(instructions, (hook_calls.get() + 1) * 10);
+        hook_calls.set(hook_calls.get() + 1);
+        PreemptionAction::Continue
+    });
+
+    assert!(succeeds(&mut wam, WALK_QUERY));
+    assert!(calls.get() > 0);
+
+    // a cleared hook is called no more.
+    wam.clear_preemption_hook();
+
+    let calls_before = calls.get();
+
+    assert!(succeeds(&mut wam, WALK_QUERY));
+    assert_eq!(calls.get(), calls_before);
+}
+
+// yield ends a step early, and is otherwise the same as continue.
+#[test]
+fn yield_ends_steps() {
+    let mut wam = machine();
+
+    wam.set_preemption_hook(50, |_| PreemptionAction::Yield);
+
+    wam.start_goal(&walk_goal(1000));
+
+    let mut steps = 1;
+
+    while wam.step(1_000_000) == QueryPoll::Pending {
+        steps += 1;
+    }
+
+    assert!(wam.poll_answer() == QueryPoll::Succeeded);
+    assert!(steps > 1);
+
+    assert!(succeeds(&mut wam, WALK_QUERY));
+}
+
+// cancel aborts the running query, leaving the machine to run the
+// next one.
+#[test]
+fn cancel_aborts_queries() {
+    let mut wam = machine();
+
+    let cancelled = Rc::new(Cell::new(false));
+    let hook_cancelled = cancelled.clone();
+
+    wam.set_preemption_hook(100, move |instructions| {
+        if instructions >= 10_000 && !hook_cancelled.get() {
+            hook_cancelled.set(true);
+            PreemptionAction::Cancel
+        } else {
+            PreemptionAction::Continue
+        }
+    });
+
+    assert!(!succeeds(&mut wam, "loop"));
+    assert!(cancelled.get());
+
+    wam.clear_preemption_hook();
+
+    assert!(succeeds(&mut wam, "true"));
+}
@@ -0,0 +1,82 @@
+extern crate scryer_prolog;
+#[macro_use]
+extern crate prolog_parser;
+
+use prolog_parser::ast::*;
+use scryer_prolog::{Machine, PrepareError, Solution};
+
+use std::cell::Cell;
+use std::ops::ControlFlow;
+
+fn atom(name: &'static str) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(clause_name!(name), None))
+}
+
+fn bindings(pairs: &[(&str, &str)]) -> Solution {
+    Solution::Bindings(pairs.iter().map(|(var, value)| (var.to_string(), value.to_string())).collect())
+}
+
+fn machine() -> Machine {
+    let mut wam = Machine::new_embedded();
+    assert!(wam.consult("edge(a, b). edge(a, c). edge(b, c).").is_ok());
+    wam
+}
+
+#[test]
+fn unreadable_queries_and_unknown_parameters() {
+    let mut wam = machine();
+
+    match wam.prepare_query("edge(X", &["X"]) {
+        Err(PrepareError::Syntax(ball)) => assert!(ball.starts_with("error(syntax_error("), "{}", ball),
+        _ => panic!("the query was read"),
+    }
+
+    match wam.prepare_query("edge(X, Y)", &["X", "Z"]) {
+        Err(err) => assert_eq!(err, PrepareError::UnknownParameter("Z".to_string())),
+        Ok(_) => panic!("Z was taken for a parameter"),
+    }
+
+    match wam.prepare_query("edge(_, Y)", &["_"]) {
+        Err(err) => assert_eq!(err, PrepareError::UnknownParameter("_".to_string())),
+        Ok(_) => panic!("_ was taken for a parameter"),
+    }
+}
+
+// a prepared query is run with its parameters bound to the arguments
+// of each run, and they aren't among the bindings of its solutions.
+#[test]
+fn parameters_are_bound_per_run() {
+    let mut wam = machine();
+
+    let query = match wam.prepare_query("edge(X, Y)", &["X"]) {
+        Ok(query) => query,
+        Err(err) => panic!("the query wasn't prepared: {:?}", err),
+    };
+
+    assert_eq!(query.params(), &["X".to_string()]);
+
+    assert_eq!(
+        wam.run_prepared(&query, &[atom("a")]).collect::<Vec<_>>(),
+        vec![bindings(&[("Y", "b")]), bindings(&[("Y", "c")])]
+    );
+
+    assert_eq!(
+        wam.run_prepared(&query, &[atom("b")]).collect::<Vec<_>>(),
+        vec![bindings(&[("Y", "c")])]
+    );
+
+    assert_eq!(wam.run_prepared(&query, &[atom("c")]).collect::<Vec<_>>(), vec![]);
+
+    let first = wam.solve_prepared(&query, &[atom("a")], |solution| ControlFlow::Break(solution));
+
+    assert_eq!(first, Some(bindings(&[("Y", "b")])));
+}
+
+#[test]
+#[should_panic]
+fn runs_need_an_argument_per_parameter() {
+    let mut wam = machine();
+
+    let query = wam.prepare_query("edge(X, Y)", &["X", "Y"]).ok().unwrap();
+    let _ = wam.run_prepared(&query, &[atom("a")]);
+}
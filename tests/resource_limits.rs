@@ -0,0 +1,62 @@
+extern crate scryer_prolog;
+
+use scryer_prolog::{Machine, ResourceLimits, Solution};
+
+use std::time::Duration;
+
+fn solutions(wam: &mut Machine, query: &str, limits: &ResourceLimits) -> Vec<Solution> {
+    wam.run_query_with_limits(query, limits).collect()
+}
+
+// each limit ends a query exceeding it with an exception naming the
+// limit, and the machine goes on to run the next query.
+#[test]
+fn exceeded_limits_are_named() {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult("loop :- loop.").is_ok());
+
+    let inferences = ResourceLimits { inferences: Some(1000), ..ResourceLimits::default() };
+    let time = ResourceLimits { time: Some(Duration::from_millis(100)), ..ResourceLimits::default() };
+    let memory = ResourceLimits { memory: Some(1000), ..ResourceLimits::default() };
+
+    assert_eq!(
+        solutions(&mut wam, "loop", &inferences),
+        vec![Solution::Exception("resource_limit_exceeded(inferences)".to_string())]
+    );
+
+    assert_eq!(
+        solutions(&mut wam, "loop", &time),
+        vec![Solution::Exception("resource_limit_exceeded(time)".to_string())]
+    );
+
+    assert_eq!(
+        solutions(&mut wam, "length(_, 100000)", &memory),
+        vec![Solution::Exception("resource_limit_exceeded(memory)".to_string())]
+    );
+}
+
+// a query within its limits has its first solution, and no limits
+// at all run it as call_with_resource_limits(Goal, []) would.
+#[test]
+fn queries_within_limits_succeed_once() {
+    let mut wam = Machine::new_embedded();
+
+    let limits = ResourceLimits {
+        inferences: Some(100000),
+        time: Some(Duration::from_secs(10)),
+        memory: Some(100000),
+    };
+
+    assert_eq!(
+        solutions(&mut wam, "( X = a ; X = b )", &limits),
+        vec![Solution::Bindings(vec![("X".to_string(), "a".to_string())])]
+    );
+
+    assert_eq!(
+        solutions(&mut wam, "X is 1 + 1", &ResourceLimits::default()),
+        vec![Solution::Bindings(vec![("X".to_string(), "2".to_string())])]
+    );
+
+    assert_eq!(solutions(&mut wam, "fail", &limits), vec![]);
+}
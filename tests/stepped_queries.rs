@@ -0,0 +1,99 @@
+extern crate scryer_prolog;
+#[macro_use]
+extern crate prolog_parser;
+
+use prolog_parser::ast::*;
+use scryer_prolog::prolog::machine::term_view::TermRef;
+use scryer_prolog::prolog::machine::QueryPoll;
+use scryer_prolog::Machine;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+fn atom(name: &'static str) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(clause_name!(name), None))
+}
+
+fn compound(name: &'static str, args: Vec<Term>) -> Term {
+    Term::Clause(Cell::default(), clause_name!(name), args.into_iter().map(Box::new).collect(), None)
+}
+
+fn var(name: &str) -> Term {
+    Term::Var(Cell::default(), Rc::new(name.to_string()))
+}
+
+fn list(items: Vec<Term>) -> Term {
+    let empty_list = Term::Constant(Cell::default(), Constant::EmptyList);
+
+    items.into_iter().rev().fold(empty_list, |tail, head| {
+        Term::Cons(Cell::default(), Box::new(head), Box::new(tail))
+    })
+}
+
+// steps the query begun by start_goal, n instructions at a time,
+// until it is no longer pending, returning how it ended and the
+// number of steps taken.
+fn run_in_steps(wam: &mut Machine, n: usize) -> (QueryPoll, usize) {
+    let mut steps = 1;
+
+    while wam.step(n) == QueryPoll::Pending {
+        steps += 1;
+    }
+
+    (wam.poll_answer(), steps)
+}
+
+#[test]
+fn queries_are_stepped_to_completion() {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult("walk([], X, X). walk([_ | T], X, Y) :- walk(T, X, Y).").is_ok());
+
+    assert!(wam.poll_answer() == QueryPoll::Failed);
+
+    let items = (0 .. 1000).map(|_| atom("a")).collect();
+    wam.start_goal(&compound("walk", vec![list(items), atom("done"), var("Y")]));
+
+    assert!(wam.poll_answer() == QueryPoll::Pending);
+
+    let (poll, steps) = run_in_steps(&mut wam, 100);
+
+    assert!(poll == QueryPoll::Succeeded);
+    assert!(steps > 1);
+
+    // a finished query isn't run again.
+    assert!(wam.step(100) == QueryPoll::Succeeded);
+
+    let answer = wam.answer_view().unwrap();
+
+    match answer.arg(3).map(|arg| arg.get()) {
+        Some(TermRef::Atom(name)) => assert_eq!(name, "done"),
+        _ => panic!("Y isn't bound to done"),
+    }
+}
+
+#[test]
+fn failed_queries_have_no_answer() {
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult("p(a).").is_ok());
+
+    wam.start_goal(&compound("p", vec![atom("b")]));
+
+    let (poll, _) = run_in_steps(&mut wam, 10);
+
+    assert!(poll == QueryPoll::Failed);
+    assert!(wam.answer_view().is_none());
+
+    // starting a query discards the one begun before it.
+    wam.start_goal(&compound("p", vec![var("X")]));
+
+    let (poll, _) = run_in_steps(&mut wam, 10);
+
+    assert!(poll == QueryPoll::Succeeded);
+
+    match wam.answer_view().and_then(|answer| answer.arg(1)).map(|arg| arg.get()) {
+        Some(TermRef::Atom(name)) => assert_eq!(name, "a"),
+        _ => panic!("X isn't bound to a"),
+    }
+}
@@ -0,0 +1,51 @@
+extern crate scryer_prolog;
+
+use scryer_prolog::prolog::machine::term_pipes::{term_pipe, AttrVarPolicy, TermPipeEnd};
+use scryer_prolog::{Machine, Solution};
+
+use std::thread;
+
+// a machine on another thread sends terms down a pipe, of capacity
+// one so that it blocks until each is received, and closes it.
+#[test]
+fn term_pipe_between_machines() {
+    let (sender, receiver) = term_pipe(1, AttrVarPolicy::StripAttributes);
+
+    let producer = thread::spawn(move || {
+        let mut wam = Machine::new_embedded();
+
+        assert!(wam.consult(":- use_module(library(pipes)).").is_ok());
+        wam.add_term_pipe_end("out", TermPipeEnd::Sender(sender));
+
+        let query = "pipe_send(out, f(X, X, 'a b')), \
+                     pipe_send(out, 12345678901234567890), \
+                     pipe_close(out)";
+
+        wam.run_query(query).collect::<Vec<_>>()
+    });
+
+    let mut wam = Machine::new_embedded();
+
+    assert!(wam.consult(":- use_module(library(pipes)).").is_ok());
+    wam.add_term_pipe_end("in", TermPipeEnd::Receiver(receiver));
+
+    let query = "pipe_receive(in, f(_X, _Y, A)), _X == _Y, \
+                 pipe_receive(in, N), \
+                 pipe_receive(in, E)";
+
+    let solutions: Vec<_> = wam.run_query(query).collect();
+
+    assert_eq!(
+        solutions,
+        vec![Solution::Bindings(vec![
+            ("A".to_string(), "'a b'".to_string()),
+            ("N".to_string(), "12345678901234567890".to_string()),
+            ("E".to_string(), "end_of_file".to_string()),
+        ])]
+    );
+
+    assert_eq!(
+        producer.join().unwrap(),
+        vec![Solution::Bindings(vec![])]
+    );
+}
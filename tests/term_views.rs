@@ -0,0 +1,98 @@
+extern crate scryer_prolog;
+#[macro_use]
+extern crate prolog_parser;
+
+use prolog_parser::ast::*;
+use scryer_prolog::prolog::machine::term_view::{TermRef, TermView};
+use scryer_prolog::Machine;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+fn atom(name: &'static str) -> Term {
+    Term::Constant(Cell::default(), Constant::Atom(clause_name!(name), None))
+}
+
+fn compound(name: &'static str, args: Vec<Term>) -> Term {
+    Term::Clause(Cell::default(), clause_name!(name), args.into_iter().map(Box::new).collect(), None)
+}
+
+fn var(name: &str) -> Term {
+    Term::Var(Cell::default(), Rc::new(name.to_string()))
+}
+
+fn list(items: Vec<Term>) -> Term {
+    let empty_list = Term::Constant(Cell::default(), Constant::EmptyList);
+
+    items.into_iter().rev().fold(empty_list, |tail, head| {
+        Term::Cons(Cell::default(), Box::new(head), Box::new(tail))
+    })
+}
+
+fn atom_name<'a>(view: TermView<'a>) -> Option<&'a str> {
+    match view.get() {
+        TermRef::Atom(name) => Some(name),
+        _ => None,
+    }
+}
+
+// the goal of a query begun by start_goal is in the first argument
+// register until the query runs.
+#[test]
+fn views_of_the_goal_register() {
+    let mut wam = Machine::new_embedded();
+
+    let goal = compound("f", vec![atom("a"), var("X"), list(vec![atom("b"), atom("c")])]);
+    wam.start_goal(&goal);
+
+    let view = wam.register_view(1);
+
+    match view.get() {
+        TermRef::Compound(name, args) => {
+            assert_eq!(name, "f");
+            assert_eq!(args.arity(), 3);
+        }
+        _ => panic!("the goal isn't a compound term"),
+    }
+
+    assert_eq!(atom_name(view.arg(1).unwrap()), Some("a"));
+    assert!(view.arg(2).unwrap().is_var());
+    assert!(view.arg(4).is_none());
+
+    let items = view.arg(3).unwrap();
+
+    match items.get() {
+        TermRef::List(head, tail) => {
+            assert_eq!(atom_name(head), Some("b"));
+            assert_eq!(atom_name(tail.arg(1).unwrap()), Some("c"));
+
+            match tail.arg(2).unwrap().get() {
+                TermRef::EmptyList => {}
+                _ => panic!("the list doesn't end in []"),
+            }
+        }
+        _ => panic!("the third argument isn't a list"),
+    }
+}
+
+// the arguments of a compound term are iterated in order.
+#[test]
+fn views_of_compound_arguments() {
+    let mut wam = Machine::new_embedded();
+
+    let goal = compound("g", vec![compound("h", vec![atom("x")]), atom("y")]);
+    wam.start_goal(&goal);
+
+    let view = wam.register_view(1);
+
+    let names: Vec<_> = match view.get() {
+        TermRef::Compound(_, args) => args.map(|arg| match arg.get() {
+            TermRef::Compound(name, _) | TermRef::Atom(name) => name.to_string(),
+            _ => String::new(),
+        }).collect(),
+        _ => vec![],
+    };
+
+    assert_eq!(names, vec!["h".to_string(), "y".to_string()]);
+    assert_eq!(atom_name(view.arg(1).unwrap().arg(1).unwrap()), Some("x"));
+}
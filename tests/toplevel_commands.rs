@@ -0,0 +1,58 @@
+extern crate scryer_prolog;
+
+use scryer_prolog::prolog::read::readline::command_text;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn commands_follow_the_prefix() {
+    assert_eq!(command_text(":help", ":"), Some("help".to_string()));
+    assert_eq!(command_text("  :type f(X) \n", ":"), Some("type f(X)".to_string()));
+    assert_eq!(command_text("!load f.pl", "!"), Some("load f.pl".to_string()));
+    assert_eq!(command_text("help", ":"), None);
+    assert_eq!(command_text(":help", ""), None);
+}
+
+// a query or directive starting with the prefix isn't a command.
+#[test]
+fn queries_starting_with_the_prefix_are_not_commands() {
+    assert_eq!(command_text(":- dynamic(p/1).", ":"), None);
+    assert_eq!(command_text(":(lists, append(X, Y, [a])).", ":"), None);
+    assert_eq!(command_text(": help", ":"), None);
+}
+
+// runs the toplevel on input, returning what it writes to standard
+// output.
+fn toplevel_output(input: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_scryer-prolog"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run scryer-prolog");
+
+    child.stdin.take().unwrap().write_all(input).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn type_command_shows_term_types() {
+    let stdout = toplevel_output(
+        b":type foo(a, b)\n:type [a, b]\n:type [a | _]\n:type 1.5\nhalt.\n",
+    );
+
+    assert!(stdout.contains("compound(foo/2)"), "{}", stdout);
+    assert!(stdout.contains("   list\n"), "{}", stdout);
+    assert!(stdout.contains("   partial_list\n"), "{}", stdout);
+    assert!(stdout.contains("   float\n"), "{}", stdout);
+}
+
+#[test]
+fn prefixed_queries_are_run() {
+    let stdout = toplevel_output(b":(lists, append([a], [b], X)).\nhalt.\n");
+
+    assert!(stdout.contains("X = [a,b]"), "{}", stdout);
+}